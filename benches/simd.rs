@@ -0,0 +1,103 @@
+//! Compares the vectorized kernels of `strctr::simd` with their scalar versions.
+//!
+//! Run with `cargo bench --features simd`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use strctr::simd::{self, scalar, GROUP_WIDTH};
+
+const ROUNDS: u32 = 2000;
+
+/// Returns the fastest of a few timed runs of `ROUNDS` calls.
+fn time(mut f: impl FnMut()) -> Duration {
+    (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..ROUNDS {
+                f();
+            }
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn report(name: &str, scalar: Duration, simd: Duration) {
+    println!(
+        "{:<24} scalar {:>10.2?}  simd {:>10.2?}  speedup {:.2}x",
+        name,
+        scalar / ROUNDS,
+        simd / ROUNDS,
+        scalar.as_secs_f64() / simd.as_secs_f64()
+    );
+}
+
+fn main() {
+    // A 1 Mbit set, like a BitSet over a million values.
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let b: Vec<u64> = (0..16 * 1024).map(|_| random()).collect();
+    let mut a: Vec<u64> = (0..16 * 1024).map(|_| random()).collect();
+
+    macro_rules! bitwise {
+        ($name:ident) => {
+            report(
+                stringify!($name),
+                time(|| scalar::$name(black_box(&mut a), black_box(&b))),
+                time(|| simd::$name(black_box(&mut a), black_box(&b))),
+            )
+        };
+    }
+    bitwise!(and_assign);
+    bitwise!(or_assign);
+    bitwise!(xor_assign);
+    bitwise!(and_not_assign);
+    report(
+        "count_ones",
+        time(|| {
+            black_box(scalar::count_ones(black_box(&a)));
+        }),
+        time(|| {
+            black_box(simd::count_ones(black_box(&a)));
+        }),
+    );
+
+    // A 64 KiB buffer with the needle at the very end.
+    let mut haystack = vec![b'a'; 64 * 1024];
+    *haystack.last_mut().unwrap() = b'\n';
+    report(
+        "find_byte",
+        time(|| {
+            black_box(scalar::find_byte(black_box(&haystack), b'\n'));
+        }),
+        time(|| {
+            black_box(simd::find_byte(black_box(&haystack), b'\n'));
+        }),
+    );
+
+    // Probing 4096 groups of control bytes, as a hash map with 64K slots would.
+    let groups: Vec<[u8; GROUP_WIDTH]> = (0..4096)
+        .map(|_| std::array::from_fn(|_| random() as u8 & 0x7f))
+        .collect();
+    let groups = &groups;
+    let probe = |f: fn(&[u8; GROUP_WIDTH], u8) -> u16| {
+        move || {
+            let hits: u32 = groups
+                .iter()
+                .map(|g| f(black_box(g), 0x2a).count_ones())
+                .sum();
+            black_box(hits);
+        }
+    };
+    report(
+        "match_group",
+        time(probe(scalar::match_group)),
+        time(probe(simd::match_group)),
+    );
+}
@@ -0,0 +1,7 @@
+//! Classic algorithms over the crate's containers.
+//!
+//! They work on slices, so they run on the elements of any container that lends them out as one, like
+//! [`Array::as_mut_slice()`](`crate::array::Array::as_mut_slice()`) and
+//! [`HybridVec::as_mut_slice()`](`crate::hybrid_vec::HybridVec::as_mut_slice()`), as well as on [`Vec`]s and arrays.
+
+pub mod sort;
@@ -0,0 +1,257 @@
+//! Insertion sort, merge sort, quicksort and heapsort.
+//!
+//! | Algorithm                   | Stable | Time, worst case | Time, average | Extra memory |
+//! |-----------------------------|--------|------------------|---------------|--------------|
+//! | [insertion_sort()]          | yes    | O(n²)            | O(n²)         | O(1)         |
+//! | [merge_sort()]              | yes    | O(n log n)       | O(n log n)    | O(n)         |
+//! | [quicksort()]               | no     | O(n²)            | O(n log n)    | O(log n)     |
+//! | [heapsort()]                | no     | O(n log n)       | O(n log n)    | O(1)         |
+//!
+//! A stable sort keeps elements that compare equal in their original order, which matters when sorting by a key. Each
+//! algorithm also comes in a `_by` variant taking a comparator. [`Algorithm`] names them, for code that runs each in
+//! turn.
+//! ```
+//! # use strctr::algo::sort::{self, Algorithm};
+//! # use strctr::array::Array;
+//! let mut a: Array<i32, 8> = Array::new();
+//! for x in [5, -2, 9, 0, 5, 3] {
+//!     a.push(x);
+//! }
+//! sort::heapsort(a.as_mut_slice());
+//! assert_eq!(a.as_slice(), &[-2, 0, 3, 5, 5, 9]);
+//!
+//! // Sorting pairs by their first field alone shows which algorithms keep equal keys in order.
+//! let pairs = [(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd'), (2, 'e'), (1, 'f')];
+//! let mut expected = pairs;
+//! expected.sort_by_key(|p| p.0);
+//! for algorithm in Algorithm::ALL {
+//!     let mut sorted = pairs;
+//!     algorithm.sort_by(&mut sorted, |a, b| a.0.cmp(&b.0));
+//!     assert!(sorted.windows(2).all(|w| w[0].0 <= w[1].0));
+//!     if algorithm.is_stable() {
+//!         assert_eq!(sorted, expected);
+//!     }
+//! }
+//! ```
+
+use std::cmp::Ordering;
+
+/// Slices up to this length are finished by insertion sort inside quicksort and merge sort, where it is faster.
+const INSERTION_THRESHOLD: usize = 16;
+
+/// The sorting algorithms of this module.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    /// [insertion_sort()]
+    Insertion,
+    /// [merge_sort()]
+    Merge,
+    /// [quicksort()]
+    Quick,
+    /// [heapsort()]
+    Heap,
+}
+
+impl Algorithm {
+    /// All algorithms, stable ones first.
+    pub const ALL: [Algorithm; 4] = [
+        Algorithm::Insertion,
+        Algorithm::Merge,
+        Algorithm::Quick,
+        Algorithm::Heap,
+    ];
+
+    /// Returns whether the algorithm keeps elements that compare equal in their original order.
+    pub fn is_stable(self) -> bool {
+        matches!(self, Algorithm::Insertion | Algorithm::Merge)
+    }
+
+    /// Sorts the slice in ascending order with the algorithm.
+    pub fn sort<T: Ord + Clone>(self, v: &mut [T]) {
+        self.sort_by(v, T::cmp)
+    }
+
+    /// Sorts the slice with the algorithm, as ordered by the comparator.
+    pub fn sort_by<T: Clone, F: FnMut(&T, &T) -> Ordering>(self, v: &mut [T], compare: F) {
+        match self {
+            Algorithm::Insertion => insertion_sort_by(v, compare),
+            Algorithm::Merge => merge_sort_by(v, compare),
+            Algorithm::Quick => quicksort_by(v, compare),
+            Algorithm::Heap => heapsort_by(v, compare),
+        }
+    }
+}
+
+/// Sorts the slice in ascending order by insertion sort, which is stable and fast on short or nearly sorted slices.
+pub fn insertion_sort<T: Ord>(v: &mut [T]) {
+    insertion_sort_by(v, T::cmp)
+}
+
+/// Sorts the slice by insertion sort, as ordered by the comparator.
+pub fn insertion_sort_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut compare: F) {
+    for i in 1..v.len() {
+        let mut j = i;
+        while j > 0 && compare(&v[j - 1], &v[j]) == Ordering::Greater {
+            v.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+}
+
+/// Sorts the slice in ascending order by merge sort, which is stable and copies the elements into a buffer as long
+/// as the slice.
+/// ```
+/// # use strctr::algo::sort;
+/// let mut words = vec!["pear", "fig", "apple", "kiwi", "plum"];
+/// sort::merge_sort_by(&mut words, |a, b| a.len().cmp(&b.len()));
+/// assert_eq!(words, vec!["fig", "pear", "kiwi", "plum", "apple"]);
+/// ```
+pub fn merge_sort<T: Ord + Clone>(v: &mut [T]) {
+    merge_sort_by(v, T::cmp)
+}
+
+/// Sorts the slice by merge sort, as ordered by the comparator.
+pub fn merge_sort_by<T: Clone, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut compare: F) {
+    let mut buffer = Vec::with_capacity(v.len());
+    merge_sort_rec(v, &mut buffer, &mut compare);
+}
+
+fn merge_sort_rec<T: Clone, F: FnMut(&T, &T) -> Ordering>(
+    v: &mut [T],
+    buffer: &mut Vec<T>,
+    compare: &mut F,
+) {
+    if v.len() <= INSERTION_THRESHOLD {
+        insertion_sort_by(v, compare);
+        return;
+    }
+    let mid = v.len() / 2;
+    merge_sort_rec(&mut v[..mid], buffer, compare);
+    merge_sort_rec(&mut v[mid..], buffer, compare);
+    if compare(&v[mid - 1], &v[mid]) != Ordering::Greater {
+        return;
+    }
+    // Merge the runs into the buffer, taking from the left run on ties to stay stable, then copy them back.
+    buffer.clear();
+    let (mut i, mut j) = (0, mid);
+    while i < mid && j < v.len() {
+        if compare(&v[j], &v[i]) == Ordering::Less {
+            buffer.push(v[j].clone());
+            j += 1;
+        } else {
+            buffer.push(v[i].clone());
+            i += 1;
+        }
+    }
+    buffer.extend_from_slice(&v[i..mid]);
+    buffer.extend_from_slice(&v[j..]);
+    v.clone_from_slice(buffer);
+}
+
+/// Sorts the slice in ascending order by quicksort, which is unstable and sorts in place.
+///
+/// Pivots are the median of the first, middle and last elements, which avoids the quadratic worst case on sorted and
+/// reversed input, though inputs crafted against it still take O(n²) time. Elements equal to the pivot are set apart
+/// at once, so duplicates cost no extra time. The recursion only descends into the smaller part, so it is at most
+/// O(log n) deep.
+/// ```
+/// # use strctr::algo::sort;
+/// let mut v: Vec<u32> = (0..1000).rev().collect();
+/// sort::quicksort(&mut v);
+/// assert!(v.iter().copied().eq(0..1000));
+/// ```
+pub fn quicksort<T: Ord>(v: &mut [T]) {
+    quicksort_by(v, T::cmp)
+}
+
+/// Sorts the slice by quicksort, as ordered by the comparator.
+pub fn quicksort_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut compare: F) {
+    quicksort_rec(v, &mut compare);
+}
+
+fn quicksort_rec<T, F: FnMut(&T, &T) -> Ordering>(mut v: &mut [T], compare: &mut F) {
+    while v.len() > INSERTION_THRESHOLD {
+        let (lt, gt) = partition(v, compare);
+        let (rest, right) = v.split_at_mut(gt);
+        let left = &mut rest[..lt];
+        if left.len() < right.len() {
+            quicksort_rec(left, compare);
+            v = right;
+        } else {
+            quicksort_rec(right, compare);
+            v = left;
+        }
+    }
+    insertion_sort_by(v, compare);
+}
+
+/// Partitions the slice in three around the median of three elements, and returns the bounds `(lt, gt)` of the
+/// middle part: the elements before `lt` are less than the pivot, the ones from `lt` to `gt` equal to it, and the ones
+/// from `gt` on greater. Keeping the equal ones apart makes slices with many duplicates sort in O(n log n) time.
+fn partition<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], compare: &mut F) -> (usize, usize) {
+    let last = v.len() - 1;
+    let mid = v.len() / 2;
+    // Order the three candidates, so the median ends up in the middle, then move it to the front.
+    if compare(&v[mid], &v[0]) == Ordering::Less {
+        v.swap(mid, 0);
+    }
+    if compare(&v[last], &v[mid]) == Ordering::Less {
+        v.swap(last, mid);
+        if compare(&v[mid], &v[0]) == Ordering::Less {
+            v.swap(mid, 0);
+        }
+    }
+    v.swap(0, mid);
+    // The elements in lt..i equal the pivot, which is among them, so v[lt] stands in for it.
+    let (mut lt, mut i, mut gt) = (0, 1, v.len());
+    while i < gt {
+        match compare(&v[i], &v[lt]) {
+            Ordering::Less => {
+                v.swap(lt, i);
+                lt += 1;
+                i += 1;
+            }
+            Ordering::Greater => {
+                gt -= 1;
+                v.swap(i, gt);
+            }
+            Ordering::Equal => i += 1,
+        }
+    }
+    (lt, gt)
+}
+
+/// Sorts the slice in ascending order by heapsort, which is unstable, sorts in place and takes O(n log n) time
+/// whatever the input.
+pub fn heapsort<T: Ord>(v: &mut [T]) {
+    heapsort_by(v, T::cmp)
+}
+
+/// Sorts the slice by heapsort, as ordered by the comparator.
+pub fn heapsort_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut compare: F) {
+    // Build a max-heap, then repeatedly move its root behind the shrinking heap.
+    for i in (0..v.len() / 2).rev() {
+        sift_down(v, i, &mut compare);
+    }
+    for end in (1..v.len()).rev() {
+        v.swap(0, end);
+        sift_down(&mut v[..end], 0, &mut compare);
+    }
+}
+
+fn sift_down<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut node: usize, compare: &mut F) {
+    loop {
+        let mut child = 2 * node + 1;
+        if child >= v.len() {
+            return;
+        }
+        if child + 1 < v.len() && compare(&v[child], &v[child + 1]) == Ordering::Less {
+            child += 1;
+        }
+        if compare(&v[node], &v[child]) != Ordering::Less {
+            return;
+        }
+        v.swap(node, child);
+        node = child;
+    }
+}
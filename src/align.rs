@@ -0,0 +1,186 @@
+//! Cache-line padding and alignment arithmetic.
+//!
+//! Two values written by different threads that share a cache line slow each other down even though they are
+//! independent, because the line has to travel between the cores on every write ("false sharing"). [`CachePadded`]
+//! aligns its value to [`CACHE_LINE_SIZE`] so that it has a line to itself. The concurrent structures of this crate
+//! use it for their per-thread slots and indices.
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+/// Assumed size of a cache line on the target, in bytes.
+///
+/// On x86-64, aarch64 and powerpc64 this is 128: their lines are 64 bytes, but neighboring lines are prefetched in
+/// pairs (or the line is 128 bytes outright), so 64-byte padding still shares. Targets known for smaller or larger
+/// lines get those, and everything else 64.
+#[cfg(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "powerpc64"
+))]
+pub const CACHE_LINE_SIZE: usize = 128;
+/// Assumed size of a cache line on the target, in bytes.
+#[cfg(any(
+    target_arch = "arm",
+    target_arch = "mips",
+    target_arch = "mips32r6",
+    target_arch = "mips64",
+    target_arch = "mips64r6",
+    target_arch = "sparc",
+    target_arch = "hexagon"
+))]
+pub const CACHE_LINE_SIZE: usize = 32;
+/// Assumed size of a cache line on the target, in bytes.
+#[cfg(target_arch = "s390x")]
+pub const CACHE_LINE_SIZE: usize = 256;
+/// Assumed size of a cache line on the target, in bytes.
+#[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "powerpc64",
+    target_arch = "arm",
+    target_arch = "mips",
+    target_arch = "mips32r6",
+    target_arch = "mips64",
+    target_arch = "mips64r6",
+    target_arch = "sparc",
+    target_arch = "hexagon",
+    target_arch = "s390x"
+)))]
+pub const CACHE_LINE_SIZE: usize = 64;
+
+/// A value padded and aligned to occupy whole cache lines.
+/// ```
+/// # use strctr::align::{CachePadded, CACHE_LINE_SIZE};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// let counters = [CachePadded::new(AtomicUsize::new(0)), CachePadded::new(AtomicUsize::new(0))];
+/// counters[1].fetch_add(1, Ordering::Relaxed);
+/// assert_eq!(std::mem::size_of_val(&counters), 2 * CACHE_LINE_SIZE);
+/// ```
+// `repr(align)` only takes literals, so the alignment is spelled out for every value of CACHE_LINE_SIZE. The check
+// below keeps both in sync.
+#[cfg_attr(
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "powerpc64"
+    ),
+    repr(align(128))
+)]
+#[cfg_attr(
+    any(
+        target_arch = "arm",
+        target_arch = "mips",
+        target_arch = "mips32r6",
+        target_arch = "mips64",
+        target_arch = "mips64r6",
+        target_arch = "sparc",
+        target_arch = "hexagon"
+    ),
+    repr(align(32))
+)]
+#[cfg_attr(target_arch = "s390x", repr(align(256)))]
+#[cfg_attr(
+    not(any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "powerpc64",
+        target_arch = "arm",
+        target_arch = "mips",
+        target_arch = "mips32r6",
+        target_arch = "mips64",
+        target_arch = "mips64r6",
+        target_arch = "sparc",
+        target_arch = "hexagon",
+        target_arch = "s390x"
+    )),
+    repr(align(64))
+)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CachePadded<T> {
+    value: T,
+}
+
+const _: () = assert!(
+    std::mem::align_of::<CachePadded<u8>>() == CACHE_LINE_SIZE && CACHE_LINE_SIZE.is_power_of_two(),
+    "CachePadded's alignment does not match CACHE_LINE_SIZE"
+);
+
+impl<T> CachePadded<T> {
+    /// Pads the value.
+    pub const fn new(value: T) -> Self {
+        Self { value }
+    }
+
+    /// Consumes the padding, returning the value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for CachePadded<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T> From<T> for CachePadded<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CachePadded<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CachePadded").field(&self.value).finish()
+    }
+}
+
+/// Rounds the number up to the next multiple of the alignment.
+///
+/// Panics if the alignment is not a power of two.
+/// ```
+/// # use strctr::align::{align_down, align_up, is_aligned};
+/// assert_eq!(align_up(100, 64), 128);
+/// assert_eq!(align_up(128, 64), 128);
+/// assert_eq!(align_down(100, 64), 64);
+/// assert!(is_aligned(192, 64));
+/// ```
+pub const fn align_up(n: usize, align: usize) -> usize {
+    assert!(
+        align.is_power_of_two(),
+        "InvalidAlignment: Not a power of two"
+    );
+    (n + align - 1) & !(align - 1)
+}
+
+/// Rounds the number down to the previous multiple of the alignment.
+///
+/// Panics if the alignment is not a power of two.
+pub const fn align_down(n: usize, align: usize) -> usize {
+    assert!(
+        align.is_power_of_two(),
+        "InvalidAlignment: Not a power of two"
+    );
+    n & !(align - 1)
+}
+
+/// Returns whether the number is a multiple of the alignment.
+///
+/// Panics if the alignment is not a power of two.
+pub const fn is_aligned(n: usize, align: usize) -> bool {
+    align_down(n, align) == n
+}
+
+/// Returns whether the pointer is aligned to a cache line.
+pub fn is_cache_aligned<T>(ptr: *const T) -> bool {
+    is_aligned(ptr as usize, CACHE_LINE_SIZE)
+}
@@ -0,0 +1,365 @@
+//! Generational arena: a slab of values addressed by stable [`Key`]s.
+//!
+//! Linked structures like graphs and lists can store keys to each other instead of `Rc<RefCell<..>>` pointers, and
+//! the arena owns all values at once. Removal frees a slot in constant time and insertion reuses it. Every slot counts
+//! how often it was freed, and keys carry the count from when they were handed out, so a key to a removed value stays
+//! invalid even after its slot is reused.
+//!
+//! [`HashConsArena`] builds on it to store every distinct value once, like the shared subterms of expressions.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::ops::{Index, IndexMut};
+
+use crate::snapshot::{Codec, Snapshot, SnapshotError};
+
+mod hash_cons;
+
+pub use hash_cons::{HashConsArena, Subterms, Term};
+
+/// Handle to a value in an [`Arena`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Key {
+    index: u32,
+    generation: u32,
+}
+
+impl Key {
+    /// Returns the slot the key points to. Keys of removed values can share it with live ones.
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+
+    /// Returns how often the slot had been freed when the key was handed out.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+#[derive(Clone)]
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// A collection of values that hands out a [`Key`] for every value inserted.
+/// ```
+/// # use strctr::arena::Arena;
+/// let mut arena = Arena::new();
+/// let a = arena.insert("a");
+/// let b = arena.insert("b");
+/// assert_eq!(arena[a], "a");
+///
+/// assert_eq!(arena.remove(a), Some("a"));
+/// let c = arena.insert("c");
+/// // c reuses a's slot, yet a stays dead.
+/// assert_eq!(a.index(), c.index());
+/// assert_eq!(arena.get(a), None);
+/// assert_eq!(arena.get(c), Some(&"c"));
+/// assert_eq!(arena.len(), 2);
+/// # let _ = b;
+/// ```
+#[derive(Clone)]
+pub struct Arena<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+    len: usize,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Arena<T> {
+    /// Constructs a new, empty arena.
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Constructs a new, empty arena with room for `capacity` values.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slots: Vec::with_capacity(capacity),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the arena holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of slots, live or free.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Inserts the value and returns its key.
+    ///
+    /// Panics if the arena would exceed `u32::MAX` slots.
+    pub fn insert(&mut self, value: T) -> Key {
+        self.insert_with(|_| value)
+    }
+
+    /// Inserts the value built by the function, which receives the value's key. Useful for values that store their own
+    /// key.
+    /// ```
+    /// # use strctr::arena::{Arena, Key};
+    /// struct Node {
+    ///     this: Key,
+    ///     next: Option<Key>,
+    /// }
+    ///
+    /// let mut arena = Arena::new();
+    /// let tail = arena.insert_with(|this| Node { this, next: None });
+    /// let head = arena.insert_with(|this| Node { this, next: Some(tail) });
+    /// assert_eq!(arena[head].this, head);
+    /// assert_eq!(arena[arena[head].next.unwrap()].this, tail);
+    /// ```
+    pub fn insert_with(&mut self, f: impl FnOnce(Key) -> T) -> Key {
+        // The slot is claimed only once the function returns, so a panic in it leaves the arena unchanged.
+        let key = match self.free.last() {
+            Some(&index) => Key {
+                index,
+                generation: self.slots[index as usize].generation,
+            },
+            None => Key {
+                index: u32::try_from(self.slots.len())
+                    .ok()
+                    .filter(|&i| i < u32::MAX)
+                    .expect("InvalidCapacity: Arena cannot hold more than u32::MAX slots"),
+                generation: 0,
+            },
+        };
+        let value = Some(f(key));
+        if self.free.pop().is_none() {
+            self.slots.push(Slot {
+                generation: 0,
+                value: None,
+            });
+        }
+        self.slots[key.index()].value = value;
+        self.len += 1;
+        key
+    }
+
+    /// Removes the value and returns it, or `None` if the key is invalid. The key and all its copies become invalid.
+    pub fn remove(&mut self, key: Key) -> Option<T> {
+        let slot = self.slots.get_mut(key.index())?;
+        if slot.generation != key.generation {
+            return None;
+        }
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(key.index);
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Returns whether the key refers to a value.
+    pub fn contains(&self, key: Key) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns a reference to the value, or `None` if the key is invalid.
+    pub fn get(&self, key: Key) -> Option<&T> {
+        let slot = self.slots.get(key.index())?;
+        if slot.generation != key.generation {
+            return None;
+        }
+        slot.value.as_ref()
+    }
+
+    /// Returns a mutable reference to the value, or `None` if the key is invalid.
+    pub fn get_mut(&mut self, key: Key) -> Option<&mut T> {
+        let slot = self.slots.get_mut(key.index())?;
+        if slot.generation != key.generation {
+            return None;
+        }
+        slot.value.as_mut()
+    }
+
+    /// Returns mutable references to two values at once, or `None` if either key is invalid.
+    ///
+    /// Panics if both keys point to the same slot.
+    /// ```
+    /// # use strctr::arena::Arena;
+    /// let mut arena = Arena::new();
+    /// let (a, b) = (arena.insert(1), arena.insert(2));
+    /// let (x, y) = arena.get2_mut(a, b).unwrap();
+    /// std::mem::swap(x, y);
+    /// assert_eq!((arena[a], arena[b]), (2, 1));
+    /// ```
+    pub fn get2_mut(&mut self, a: Key, b: Key) -> Option<(&mut T, &mut T)> {
+        if a.index == b.index {
+            panic!("AliasedKeys: {:?} and {:?} point to the same slot", a, b);
+        }
+        if !self.contains(a) || !self.contains(b) {
+            return None;
+        }
+        let (low, high) = (a.index().min(b.index()), a.index().max(b.index()));
+        let (head, tail) = self.slots.split_at_mut(high);
+        let (low, high) = (head[low].value.as_mut()?, tail[0].value.as_mut()?);
+        Some(if a.index < b.index {
+            (low, high)
+        } else {
+            (high, low)
+        })
+    }
+
+    /// Removes all values. Their keys stay invalid when the slots are reused.
+    pub fn clear(&mut self) {
+        self.retain(|_, _| false);
+    }
+
+    /// Keeps only the values the predicate returns `true` for.
+    pub fn retain(&mut self, mut pred: impl FnMut(Key, &mut T) -> bool) {
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            let key = Key {
+                index: index as u32,
+                generation: slot.generation,
+            };
+            if let Some(value) = &mut slot.value {
+                if !pred(key, value) {
+                    slot.value = None;
+                    slot.generation = slot.generation.wrapping_add(1);
+                    self.free.push(key.index);
+                    self.len -= 1;
+                }
+            }
+        }
+    }
+
+    /// Returns an iterator over the keys and values, in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (Key, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let key = Key {
+                index: index as u32,
+                generation: slot.generation,
+            };
+            slot.value.as_ref().map(|value| (key, value))
+        })
+    }
+
+    /// Returns an iterator over the keys and mutable values, in slot order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Key, &mut T)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                let key = Key {
+                    index: index as u32,
+                    generation: slot.generation,
+                };
+                slot.value.as_mut().map(|value| (key, value))
+            })
+    }
+
+    /// Returns an iterator over the keys, in slot order.
+    pub fn keys(&self) -> impl Iterator<Item = Key> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Returns an iterator over the values, in slot order.
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.iter().map(|(_, value)| value)
+    }
+}
+
+impl<T> Index<Key> for Arena<T> {
+    type Output = T;
+
+    /// Returns the value of the key.
+    ///
+    /// Panics if the key is invalid.
+    /// ```should_panic
+    /// # use strctr::arena::Arena;
+    /// let mut arena = Arena::new();
+    /// let k = arena.insert(1);
+    /// arena.remove(k);
+    /// let x = arena[k];
+    /// ```
+    fn index(&self, key: Key) -> &Self::Output {
+        match self.get(key) {
+            Some(v) => v,
+            None => panic!("InvalidKey: {:?} does not refer to a value", key),
+        }
+    }
+}
+
+impl<T> IndexMut<Key> for Arena<T> {
+    /// Allows updating the value of the key.
+    ///
+    /// Panics if the key is invalid.
+    fn index_mut(&mut self, key: Key) -> &mut Self::Output {
+        match self.get_mut(key) {
+            Some(v) => v,
+            None => panic!("InvalidKey: {:?} does not refer to a value", key),
+        }
+    }
+}
+
+impl<T> Extend<T> for Arena<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for Arena<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut arena = Self::new();
+        arena.extend(iter);
+        arena
+    }
+}
+
+impl<T: Codec> Snapshot for Arena<T> {
+    const TAG: [u8; 4] = *b"ARNA";
+
+    /// Writes every slot with its generation, so that saved keys stay valid after loading.
+    fn write_payload<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.slots.len().encode(w)?;
+        for slot in &self.slots {
+            slot.generation.encode(w)?;
+            slot.value.encode(w)?;
+        }
+        Ok(())
+    }
+
+    fn read_payload<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let len = usize::decode(r)?;
+        if len > u32::MAX as usize {
+            return Err(SnapshotError::Corrupt);
+        }
+        let mut arena = Self::new();
+        for index in 0..len {
+            let generation = u32::decode(r)?;
+            let value = Option::<T>::decode(r)?;
+            if value.is_some() {
+                arena.len += 1;
+            } else {
+                arena.free.push(index as u32);
+            }
+            arena.slots.push(Slot { generation, value });
+        }
+        // Reuse the lowest slots first, like a fresh arena.
+        arena.free.reverse();
+        Ok(arena)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Arena<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
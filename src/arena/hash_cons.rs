@@ -0,0 +1,255 @@
+//! Arena that stores every distinct value once.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::ops::Index;
+
+use super::{Arena, Key};
+use crate::hash::HashState;
+
+/// A value of a [`HashConsArena`] that refers to other values of the arena by key, like an expression referring to
+/// its operands.
+pub trait Term {
+    /// Returns the keys of the values this one refers to.
+    fn children(&self) -> impl Iterator<Item = Key>;
+}
+
+struct Node<T> {
+    value: T,
+    hash: u64,
+    /// Next node with the same hash.
+    next: Option<Key>,
+}
+
+/// An arena that hash-conses its values: inserting a value equal to one already in the arena returns the existing
+/// key, so equal values, and with them equal trees of values, share one key and compare equal by key alone.
+///
+/// Values refer to their children by key through [`Term`], which makes the arena a DAG of shared subterms. Values are
+/// never removed one by one; instead, [gc()](`Self::gc()`) drops every value not reachable from a set of roots. Keys
+/// of dropped values stay invalid, like those of an [`Arena`].
+/// ```
+/// # use strctr::arena::{HashConsArena, Key, Term};
+/// #[derive(PartialEq, Eq, Hash)]
+/// enum Expr {
+///     Num(i64),
+///     Var(&'static str),
+///     Add(Key, Key),
+///     Mul(Key, Key),
+/// }
+///
+/// impl Term for Expr {
+///     fn children(&self) -> impl Iterator<Item = Key> {
+///         match *self {
+///             Expr::Add(a, b) | Expr::Mul(a, b) => vec![a, b],
+///             _ => vec![],
+///         }
+///         .into_iter()
+///     }
+/// }
+///
+/// let mut exprs = HashConsArena::new();
+/// let x = exprs.insert(Expr::Var("x"));
+/// let one = exprs.insert(Expr::Num(1));
+/// let sum = exprs.insert(Expr::Add(x, one));
+/// let square = exprs.insert(Expr::Mul(sum, sum));
+/// // x + 1 is built again, but stored once.
+/// let x_again = exprs.insert(Expr::Var("x"));
+/// assert_eq!(exprs.insert(Expr::Add(x_again, one)), sum);
+/// assert_eq!(exprs.len(), 4);
+///
+/// exprs.insert(Expr::Num(2));
+/// assert_eq!(exprs.gc([square]), 1);
+/// assert_eq!(exprs.subterms(square).count(), 4);
+/// ```
+pub struct HashConsArena<T> {
+    nodes: Arena<Node<T>>,
+    /// First node of every hash.
+    buckets: HashMap<u64, Key>,
+    hasher: HashState,
+}
+
+impl<T: Hash + Eq> Default for HashConsArena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash + Eq> HashConsArena<T> {
+    /// Constructs a new, empty arena.
+    pub fn new() -> Self {
+        Self::with_hasher(HashState::new())
+    }
+
+    /// Constructs a new, empty arena that hashes values with the state's keys.
+    pub fn with_hasher(state: HashState) -> Self {
+        Self {
+            nodes: Arena::new(),
+            buckets: HashMap::new(),
+            hasher: state,
+        }
+    }
+
+    /// Returns the number of distinct values.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns whether the arena holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Inserts the value unless an equal one is in the arena, and returns the key of the value in the arena.
+    ///
+    /// Panics if the arena would exceed `u32::MAX` slots.
+    pub fn insert(&mut self, value: T) -> Key {
+        let hash = self.hasher.hash_one(&value);
+        if let Some(key) = self.find_hashed(&value, hash) {
+            return key;
+        }
+        let next = self.buckets.get(&hash).copied();
+        let key = self.nodes.insert(Node { value, hash, next });
+        self.buckets.insert(hash, key);
+        key
+    }
+
+    /// Returns the key of the value equal to the given one, or `None` if there is none.
+    pub fn find(&self, value: &T) -> Option<Key> {
+        self.find_hashed(value, self.hasher.hash_one(value))
+    }
+
+    /// Returns whether the key refers to a value.
+    pub fn contains(&self, key: Key) -> bool {
+        self.nodes.contains(key)
+    }
+
+    /// Returns a reference to the value, or `None` if the key is invalid.
+    pub fn get(&self, key: Key) -> Option<&T> {
+        self.nodes.get(key).map(|node| &node.value)
+    }
+
+    /// Returns an iterator over the keys and values, in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (Key, &T)> {
+        self.nodes.iter().map(|(key, node)| (key, &node.value))
+    }
+
+    fn find_hashed(&self, value: &T, hash: u64) -> Option<Key> {
+        let mut next = self.buckets.get(&hash).copied();
+        while let Some(key) = next {
+            let node = &self.nodes[key];
+            if node.value == *value {
+                return Some(key);
+            }
+            next = node.next;
+        }
+        None
+    }
+}
+
+impl<T: Hash + Eq + Term> HashConsArena<T> {
+    /// Returns an iterator over the values reachable from the root, itself included, each once and after all of its
+    /// children. Invalid keys are skipped.
+    /// ```
+    /// # use strctr::arena::{HashConsArena, Key, Term};
+    /// #[derive(PartialEq, Eq, Hash)]
+    /// struct Cons(u8, Option<Key>);
+    ///
+    /// impl Term for Cons {
+    ///     fn children(&self) -> impl Iterator<Item = Key> {
+    ///         self.1.into_iter()
+    ///     }
+    /// }
+    ///
+    /// let mut lists = HashConsArena::new();
+    /// let tail = lists.insert(Cons(3, None));
+    /// let middle = lists.insert(Cons(2, Some(tail)));
+    /// let list = lists.insert(Cons(1, Some(middle)));
+    /// let items: Vec<u8> = lists.subterms(list).map(|(_, cons)| cons.0).collect();
+    /// assert_eq!(items, [3, 2, 1]);
+    /// ```
+    pub fn subterms(&self, root: Key) -> Subterms<'_, T> {
+        Subterms {
+            arena: self,
+            stack: vec![(root, false)],
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Drops every value that is not reachable from one of the roots, returning how many were dropped.
+    pub fn gc(&mut self, roots: impl IntoIterator<Item = Key>) -> usize {
+        let mut reachable = HashSet::new();
+        let mut stack: Vec<Key> = roots.into_iter().collect();
+        while let Some(key) = stack.pop() {
+            if let Some(node) = self.nodes.get(key) {
+                if reachable.insert(key) {
+                    stack.extend(node.value.children());
+                }
+            }
+        }
+        let before = self.nodes.len();
+        self.nodes.retain(|key, _| reachable.contains(&key));
+        // Relink the survivors, since dropped nodes may sit anywhere in the chains.
+        self.buckets.clear();
+        for (key, node) in self.nodes.iter_mut() {
+            node.next = self.buckets.insert(node.hash, key);
+        }
+        before - self.nodes.len()
+    }
+}
+
+impl<T> Index<Key> for HashConsArena<T> {
+    type Output = T;
+
+    /// Returns the value of the key.
+    ///
+    /// Panics if the key is invalid.
+    fn index(&self, key: Key) -> &Self::Output {
+        &self.nodes[key].value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for HashConsArena<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.nodes.iter().map(|(key, node)| (key, &node.value)))
+            .finish()
+    }
+}
+
+/// Iterator over the subterms of a value in a [`HashConsArena`], children first. Created by
+/// [`HashConsArena::subterms()`].
+pub struct Subterms<'a, T> {
+    arena: &'a HashConsArena<T>,
+    /// Keys to visit, and whether their children were pushed already.
+    stack: Vec<(Key, bool)>,
+    seen: HashSet<Key>,
+}
+
+impl<'a, T: Hash + Eq + Term> Iterator for Subterms<'a, T> {
+    type Item = (Key, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((key, expanded)) = self.stack.pop() {
+            let Some(value) = self.arena.get(key) else {
+                continue;
+            };
+            if expanded {
+                return Some((key, value));
+            }
+            if self.seen.insert(key) {
+                self.stack.push((key, true));
+                let start = self.stack.len();
+                self.stack.extend(
+                    value
+                        .children()
+                        .filter(|child| !self.seen.contains(child))
+                        .map(|child| (child, false)),
+                );
+                // Visit the children in their order.
+                self.stack[start..].reverse();
+            }
+        }
+        None
+    }
+}
@@ -1,15 +1,22 @@
+//! Simple implementation of an array. Uses a fixed-size slice for storage.
+
+use std::fmt;
 use std::ops::{Index, IndexMut};
 
-/// Simple implementation of an array. Uses a fixed-size slice for storage.
+use crate::mapped::Plain;
 
 /// List of errors that could occur when dealing with Arrays
+#[derive(Debug, PartialEq, Eq)]
 pub enum ArrayError {
     /// Signals that an overflow has happened; Most probably more elements were pushed
     /// onto the array than its underlying size.
     Overflow,
+    /// The bytes an array was built from do not divide into whole elements.
+    InvalidLength,
 }
 
 /// An array implementation. Uses compile-time constant size [`std::array`] as the underlying data structure.
+#[derive(Clone)]
 pub struct Array<T, const N: usize> {
     elements: [T; N],
     cursor: usize,
@@ -32,6 +39,175 @@ where
     pub fn new() -> Self {
         Self::new_with_default(T::default())
     }
+
+    /// Moves the elements from `at` on into a new Array of size M, leaving the first `at` elements in this one.
+    /// Returns an error and leaves this array unchanged if they do not fit into M.
+    /// ```
+    /// # use strctr::array::{Array, ArrayError};
+    /// let mut packet: Array<u8, 8> = Array::new();
+    /// for byte in [0xCA, 0xFE, 3, b'a', b'b', b'c'] {
+    ///     packet.push(byte);
+    /// }
+    /// assert_eq!(packet.split_off::<2>(2).err(), Some(ArrayError::Overflow));
+    /// let payload: Array<u8, 6> = packet.split_off(2).unwrap();
+    /// assert_eq!(packet.as_slice(), &[0xCA, 0xFE]);
+    /// assert_eq!(payload.as_slice(), &[3, b'a', b'b', b'c']);
+    /// ```
+    ///
+    /// Panics if `at` > [len()](`Self::len()`).
+    /// ```should_panic
+    /// # use strctr::array::Array;
+    /// let mut a: Array<u8, 4> = Array::new();
+    /// a.push(1);
+    /// let _ = a.split_off::<4>(2);
+    /// ```
+    pub fn split_off<const M: usize>(&mut self, at: usize) -> Result<Array<T, M>, ArrayError> {
+        self.check_split(at);
+        if self.cursor - at > M {
+            return Err(ArrayError::Overflow);
+        }
+        let mut tail = Array::new();
+        for &elem in &self.elements[at..self.cursor] {
+            tail.push(elem);
+        }
+        self.cursor = at;
+        Ok(tail)
+    }
+
+    /// Returns a new Array of size R holding the elements of this array followed by the other's. Choosing R as at least
+    /// N + M makes sure that they fit.
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut header: Array<u8, 2> = Array::new();
+    /// header.push(0xCA);
+    /// header.push(0xFE);
+    /// let mut payload: Array<u8, 4> = Array::new();
+    /// payload.push(7);
+    /// let packet: Array<u8, 6> = header.concat(&payload);
+    /// assert_eq!(packet.as_slice(), &[0xCA, 0xFE, 7]);
+    /// ```
+    ///
+    /// Panics if the elements do not fit into R. For a non-panicing version, see [try_concat()](`Self::try_concat()`)
+    /// ```should_panic
+    /// # use strctr::array::Array;
+    /// let mut a: Array<u8, 2> = Array::new();
+    /// a.push(1);
+    /// a.push(2);
+    /// let _: Array<u8, 3> = a.concat(&a);
+    /// ```
+    pub fn concat<const M: usize, const R: usize>(&self, other: &Array<T, M>) -> Array<T, R> {
+        match self.try_concat(other) {
+            Ok(array) => array,
+            Err(_) => panic!(
+                "Overflow: Wanted to concatenate {} elements, but size is {}",
+                self.cursor + other.cursor,
+                R
+            ),
+        }
+    }
+
+    /// Returns a new Array of size R holding the elements of this array followed by the other's, or an error if they
+    /// do not fit into R.
+    /// ```
+    /// # use strctr::array::{Array, ArrayError};
+    /// let mut a: Array<u32, 4> = Array::new();
+    /// let mut b: Array<u32, 4> = Array::new();
+    /// for i in 1..=3 {
+    ///     a.push(i);
+    ///     b.push(i + 3);
+    /// }
+    /// assert_eq!(a.try_concat::<4, 5>(&b).err(), Some(ArrayError::Overflow));
+    /// let joined: Array<u32, 6> = a.try_concat(&b).unwrap();
+    /// assert_eq!(joined.as_slice(), &[1, 2, 3, 4, 5, 6]);
+    /// ```
+    pub fn try_concat<const M: usize, const R: usize>(
+        &self,
+        other: &Array<T, M>,
+    ) -> Result<Array<T, R>, ArrayError> {
+        if self.cursor + other.cursor > R {
+            return Err(ArrayError::Overflow);
+        }
+        let mut array = Array::new();
+        for &elem in self.as_slice().iter().chain(other.as_slice()) {
+            array.push(elem);
+        }
+        Ok(array)
+    }
+}
+
+impl<T, const N: usize> Array<T, N>
+where
+    T: Copy + Default + Ord,
+{
+    /// Merges the elements of this array and the other, both sorted in ascending order, into a new sorted Array of
+    /// size R. Of equal elements, this array's come first. Choosing R as at least N + M makes sure that they fit.
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut morning: Array<u16, 4> = Array::new();
+    /// let mut evening: Array<u16, 4> = Array::new();
+    /// [615, 730, 845].into_iter().for_each(|t| morning.push(t));
+    /// [700, 730, 1800, 1930].into_iter().for_each(|t| evening.push(t));
+    /// let departures: Array<u16, 8> = morning.merge_sorted(&evening);
+    /// assert_eq!(departures.as_slice(), &[615, 700, 730, 730, 845, 1800, 1930]);
+    /// ```
+    ///
+    /// Panics if the elements do not fit into R. The result is unspecified if either array is not sorted. For a
+    /// non-panicing version, see [try_merge_sorted()](`Self::try_merge_sorted()`)
+    /// ```should_panic
+    /// # use strctr::array::Array;
+    /// let mut a: Array<u8, 2> = Array::new();
+    /// a.push(1);
+    /// a.push(2);
+    /// let _: Array<u8, 3> = a.merge_sorted(&a);
+    /// ```
+    pub fn merge_sorted<const M: usize, const R: usize>(&self, other: &Array<T, M>) -> Array<T, R> {
+        match self.try_merge_sorted(other) {
+            Ok(array) => array,
+            Err(_) => panic!(
+                "Overflow: Wanted to merge {} elements, but size is {}",
+                self.cursor + other.cursor,
+                R
+            ),
+        }
+    }
+
+    /// Merges the elements of this array and the other, both sorted in ascending order, into a new sorted Array of
+    /// size R, or returns an error if they do not fit into R.
+    /// ```
+    /// # use strctr::array::{Array, ArrayError};
+    /// let mut a: Array<u32, 4> = Array::new();
+    /// let mut b: Array<u32, 4> = Array::new();
+    /// for i in 1..=3 {
+    ///     a.push(2 * i);
+    ///     b.push(2 * i + 1);
+    /// }
+    /// assert_eq!(a.try_merge_sorted::<4, 5>(&b).err(), Some(ArrayError::Overflow));
+    /// let merged: Array<u32, 6> = a.try_merge_sorted(&b).unwrap();
+    /// assert_eq!(merged.as_slice(), &[2, 3, 4, 5, 6, 7]);
+    /// ```
+    pub fn try_merge_sorted<const M: usize, const R: usize>(
+        &self,
+        other: &Array<T, M>,
+    ) -> Result<Array<T, R>, ArrayError> {
+        if self.cursor + other.cursor > R {
+            return Err(ArrayError::Overflow);
+        }
+        let mut array = Array::new();
+        let (mut a, mut b) = (self.as_slice(), other.as_slice());
+        while let (Some(&x), Some(&y)) = (a.first(), b.first()) {
+            if y < x {
+                array.push(y);
+                b = &b[1..];
+            } else {
+                array.push(x);
+                a = &a[1..];
+            }
+        }
+        for &elem in a.iter().chain(b) {
+            array.push(elem);
+        }
+        Ok(array)
+    }
 }
 
 impl<T: Copy, const N: usize> Array<T, N> {
@@ -138,6 +314,413 @@ impl<T: Copy, const N: usize> Array<T, N> {
             );
         }
     }
+
+    /// Removes the last element from the array and returns it, or `None` if the array is empty.
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut a: Array<usize, 2> = Array::new();
+    /// a.push(1);
+    /// assert_eq!(a.pop(), Some(1));
+    /// assert_eq!(a.pop(), None);
+    /// ```
+    pub fn pop(&mut self) -> Option<T> {
+        if self.cursor == 0 {
+            return None;
+        }
+        self.cursor -= 1;
+        Some(self.elements[self.cursor])
+    }
+
+    /// Removes all elements. The array keeps its size.
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut a: Array<usize, 2> = Array::new();
+    /// a.push(1);
+    /// a.clear();
+    /// assert!(a.is_empty());
+    /// assert_eq!(a.size(), 2);
+    /// ```
+    pub fn clear(&mut self) {
+        self.cursor = 0;
+    }
+
+    /// Sets every element of the array to the value, filling it up to its [size()](`Self::size()`).
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut a: Array<u8, 4> = Array::new();
+    /// a.push(7);
+    /// a.fill(0xFF);
+    /// assert_eq!(a.as_slice(), &[0xFF; 4]);
+    /// ```
+    pub fn fill(&mut self, value: T) {
+        self.elements = [value; N];
+        self.cursor = N;
+    }
+
+    /// Sets every element of the array, from the first, to the values returned by calling the closure, filling it up
+    /// to its [size()](`Self::size()`).
+    /// ```
+    /// # use strctr::array::Array;
+    /// // Squares of 0 to 7, as a lookup table.
+    /// let mut squares: Array<u32, 8> = Array::new();
+    /// let mut i = 0;
+    /// squares.fill_with(|| {
+    ///     i += 1;
+    ///     (i - 1) * (i - 1)
+    /// });
+    /// assert_eq!(squares[5], 25);
+    /// assert_eq!(squares.len(), 8);
+    /// ```
+    pub fn fill_with(&mut self, mut f: impl FnMut() -> T) {
+        for elem in &mut self.elements {
+            *elem = f();
+        }
+        self.cursor = N;
+    }
+
+    /// Returns an array of the same size holding the result of the closure on every pushed element, in order.
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut celsius: Array<i32, 4> = Array::new();
+    /// for c in [-40, 0, 100] {
+    ///     celsius.push(c);
+    /// }
+    /// let fahrenheit: Array<f64, 4> = celsius.map(|c| c as f64 * 1.8 + 32.0);
+    /// assert_eq!(fahrenheit.as_slice(), &[-40.0, 32.0, 212.0]);
+    /// ```
+    pub fn map<U: Copy + Default>(&self, mut f: impl FnMut(T) -> U) -> Array<U, N> {
+        let mut mapped = Array::new();
+        for &elem in self.as_slice() {
+            mapped.push(f(elem));
+        }
+        mapped
+    }
+
+    /// Removes consecutive repeated elements, keeping the first of each run. On a sorted array this removes all
+    /// duplicates.
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut tags: Array<char, 8> = Array::new();
+    /// for t in ['b', 'a', 'b', 'a', 'c', 'b'] {
+    ///     tags.push(t);
+    /// }
+    /// tags.as_mut_slice().sort();
+    /// tags.dedup();
+    /// assert_eq!(tags.as_slice(), &['a', 'b', 'c']);
+    /// assert_eq!(tags.len(), 3);
+    /// ```
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b)
+    }
+
+    /// Removes consecutive elements that map to the same key, keeping the first of each run.
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut readings: Array<i32, 8> = Array::new();
+    /// for r in [10, 14, 21, 25, 29, 12] {
+    ///     readings.push(r);
+    /// }
+    /// // Keep one reading per band of ten.
+    /// readings.dedup_by_key(|r| *r / 10);
+    /// assert_eq!(readings.as_slice(), &[10, 21, 12]);
+    /// ```
+    pub fn dedup_by_key<K: PartialEq>(&mut self, mut key: impl FnMut(&mut T) -> K) {
+        self.dedup_by(|a, b| key(a) == key(b))
+    }
+
+    /// Removes consecutive elements for which `same_bucket` returns `true`, keeping the first of each run. It is
+    /// called with an element and the last element kept before it, in that order, and may change both.
+    /// ```
+    /// # use strctr::array::Array;
+    /// // Merge runs of the same event, counting the repeats into the first.
+    /// let mut events: Array<(char, u32), 8> = Array::new();
+    /// for e in ['x', 'x', 'y', 'x', 'x', 'x'] {
+    ///     events.push((e, 1));
+    /// }
+    /// events.dedup_by(|next, kept| {
+    ///     let same = next.0 == kept.0;
+    ///     if same {
+    ///         kept.1 += 1;
+    ///     }
+    ///     same
+    /// });
+    /// assert_eq!(events.as_slice(), &[('x', 2), ('y', 1), ('x', 3)]);
+    /// ```
+    pub fn dedup_by(&mut self, mut same_bucket: impl FnMut(&mut T, &mut T) -> bool) {
+        if self.cursor == 0 {
+            return;
+        }
+        let mut kept = 1;
+        for i in 1..self.cursor {
+            let (front, back) = self.elements.split_at_mut(i);
+            if !same_bucket(&mut back[0], &mut front[kept - 1]) {
+                self.elements[kept] = self.elements[i];
+                kept += 1;
+            }
+        }
+        self.cursor = kept;
+    }
+
+    /// Returns the pushed elements as a slice.
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut a: Array<usize, 5> = Array::new();
+    /// a.push(1);
+    /// a.push(2);
+    /// assert_eq!(a.as_slice(), &[1, 2]);
+    /// ```
+    pub fn as_slice(&self) -> &[T] {
+        &self.elements[..self.cursor]
+    }
+
+    /// Returns the pushed elements as a mutable slice.
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut a: Array<usize, 5> = Array::new();
+    /// a.push(2);
+    /// a.push(1);
+    /// a.as_mut_slice().sort();
+    /// assert_eq!(a.as_slice(), &[1, 2]);
+    /// ```
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.elements[..self.cursor]
+    }
+
+    /// Divides the pushed elements into two slices, the first holding the elements before `mid` and the second the
+    /// rest.
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut frame: Array<u8, 16> = Array::new();
+    /// for byte in [2, 0, b'h', b'i'] {
+    ///     frame.push(byte);
+    /// }
+    /// let (header, payload) = frame.split_at(2);
+    /// assert_eq!(header, &[2, 0]);
+    /// assert_eq!(payload, b"hi");
+    /// ```
+    ///
+    /// Panics if `mid` > [len()](`Self::len()`).
+    /// ```should_panic
+    /// # use strctr::array::Array;
+    /// let a: Array<u8, 4> = Array::new();
+    /// a.split_at(1);
+    /// ```
+    pub fn split_at(&self, mid: usize) -> (&[T], &[T]) {
+        self.check_split(mid);
+        self.as_slice().split_at(mid)
+    }
+
+    /// Divides the pushed elements into two mutable slices, the first holding the elements before `mid` and the
+    /// second the rest.
+    ///
+    /// Panics if `mid` > [len()](`Self::len()`).
+    /// ```should_panic
+    /// # use strctr::array::Array;
+    /// let mut a: Array<u8, 4> = Array::new();
+    /// a.split_at_mut(1);
+    /// ```
+    pub fn split_at_mut(&mut self, mid: usize) -> (&mut [T], &mut [T]) {
+        self.check_split(mid);
+        self.as_mut_slice().split_at_mut(mid)
+    }
+
+    fn check_split(&self, mid: usize) {
+        if mid > self.cursor {
+            panic!(
+                "OutOfBounds: Wanted to split at {}, but length is {}",
+                mid, self.cursor
+            )
+        }
+    }
+
+    /// Returns an iterator over the pushed elements in slices of `size`, the last one being shorter if they do not
+    /// divide evenly.
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut samples: Array<u16, 8> = Array::new();
+    /// for s in [3, 5, 4, 8, 9] {
+    ///     samples.push(s);
+    /// }
+    /// let frames: Vec<&[u16]> = samples.chunks(2).collect();
+    /// assert_eq!(frames, vec![&[3, 5][..], &[4, 8], &[9]]);
+    /// ```
+    ///
+    /// Panics if `size` is 0.
+    /// ```should_panic
+    /// # use strctr::array::Array;
+    /// let a: Array<u8, 4> = Array::new();
+    /// a.chunks(0);
+    /// ```
+    pub fn chunks(&self, size: usize) -> std::slice::Chunks<'_, T> {
+        check_window(size);
+        self.as_slice().chunks(size)
+    }
+
+    /// Returns an iterator over the pushed elements in mutable slices of `size`, the last one being shorter if they
+    /// do not divide evenly.
+    ///
+    /// Panics if `size` is 0.
+    /// ```should_panic
+    /// # use strctr::array::Array;
+    /// let mut a: Array<u8, 4> = Array::new();
+    /// a.chunks_mut(0);
+    /// ```
+    pub fn chunks_mut(&mut self, size: usize) -> std::slice::ChunksMut<'_, T> {
+        check_window(size);
+        self.as_mut_slice().chunks_mut(size)
+    }
+
+    /// Returns an iterator over the pushed elements in slices of exactly `size`. The elements left over at the end
+    /// are skipped, and returned by [`remainder()`](`std::slice::ChunksExact::remainder()`) on the iterator.
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut samples: Array<u16, 8> = Array::new();
+    /// for s in [3, 5, 4, 8, 9] {
+    ///     samples.push(s);
+    /// }
+    /// let frames = samples.chunks_exact(2);
+    /// assert_eq!(frames.remainder(), &[9]);
+    /// let energy: Vec<u16> = frames.map(|f| f.iter().sum()).collect();
+    /// assert_eq!(energy, vec![8, 12]);
+    /// ```
+    ///
+    /// Panics if `size` is 0.
+    /// ```should_panic
+    /// # use strctr::array::Array;
+    /// let a: Array<u8, 4> = Array::new();
+    /// a.chunks_exact(0);
+    /// ```
+    pub fn chunks_exact(&self, size: usize) -> std::slice::ChunksExact<'_, T> {
+        check_window(size);
+        self.as_slice().chunks_exact(size)
+    }
+
+    /// Returns an iterator over the pushed elements in mutable slices of exactly `size`, skipping the elements left
+    /// over at the end.
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut pixels: Array<u8, 8> = Array::new();
+    /// for channel in [10, 20, 30, 40, 50, 60, 70] {
+    ///     pixels.push(channel);
+    /// }
+    /// for rgb in pixels.chunks_exact_mut(3) {
+    ///     rgb.swap(0, 2);
+    /// }
+    /// assert_eq!(pixels.as_slice(), &[30, 20, 10, 60, 50, 40, 70]);
+    /// ```
+    ///
+    /// Panics if `size` is 0.
+    /// ```should_panic
+    /// # use strctr::array::Array;
+    /// let mut a: Array<u8, 4> = Array::new();
+    /// a.chunks_exact_mut(0);
+    /// ```
+    pub fn chunks_exact_mut(&mut self, size: usize) -> std::slice::ChunksExactMut<'_, T> {
+        check_window(size);
+        self.as_mut_slice().chunks_exact_mut(size)
+    }
+
+    /// Returns an iterator over every run of `size` consecutive pushed elements, overlapping by all but one element.
+    /// There are none if fewer than `size` elements were pushed.
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut readings: Array<i32, 16> = Array::new();
+    /// for r in [1, 4, 7, 4, 1] {
+    ///     readings.push(r);
+    /// }
+    /// let moving_sum: Vec<i32> = readings.windows(3).map(|w| w.iter().sum()).collect();
+    /// assert_eq!(moving_sum, vec![12, 15, 12]);
+    /// assert_eq!(readings.windows(6).count(), 0);
+    /// ```
+    ///
+    /// Panics if `size` is 0.
+    /// ```should_panic
+    /// # use strctr::array::Array;
+    /// let a: Array<u8, 4> = Array::new();
+    /// a.windows(0);
+    /// ```
+    pub fn windows(&self, size: usize) -> std::slice::Windows<'_, T> {
+        check_window(size);
+        self.as_slice().windows(size)
+    }
+}
+
+fn check_window(size: usize) {
+    if size == 0 {
+        panic!("InvalidSize: Chunks and windows must hold at least one element")
+    }
+}
+
+impl<T: Plain, const N: usize> Array<T, N> {
+    /// Returns the bytes of the pushed elements, in memory order, without copying them.
+    /// ```
+    /// # use strctr::array::Array;
+    /// let mut a: Array<u16, 4> = Array::new();
+    /// a.push(0x0102);
+    /// a.push(0x0304);
+    /// assert_eq!(a.as_bytes().len(), 4);
+    /// assert_eq!(a.as_bytes()[..2], 0x0102u16.to_ne_bytes());
+    /// ```
+    pub fn as_bytes(&self) -> &[u8] {
+        let elements = self.as_slice();
+        // Plain types have no padding bytes, so every byte of the elements is initialized.
+        unsafe {
+            std::slice::from_raw_parts(
+                elements.as_ptr().cast::<u8>(),
+                std::mem::size_of_val(elements),
+            )
+        }
+    }
+
+    /// Constructs an Array holding the elements whose bytes, in memory order, are given, as returned by
+    /// [as_bytes()](`Self::as_bytes()`). The bytes need not be aligned. Returns [`ArrayError::InvalidLength`] if they
+    /// do not divide into whole elements, and [`ArrayError::Overflow`] if there are more than N elements.
+    /// ```
+    /// # use strctr::array::{Array, ArrayError};
+    /// # use strctr::mapped::Plain;
+    /// #[derive(Clone, Copy, Default)]
+    /// #[repr(C)]
+    /// struct Reading {
+    ///     sensor: u32,
+    ///     celsius: f32,
+    /// }
+    /// unsafe impl Plain for Reading {}
+    ///
+    /// let mut frame: Array<Reading, 8> = Array::new();
+    /// frame.push(Reading { sensor: 3, celsius: 21.5 });
+    /// frame.push(Reading { sensor: 4, celsius: -2.0 });
+    /// let packet = frame.as_bytes().to_vec();
+    ///
+    /// let received: Array<Reading, 8> = Array::from_bytes(&packet).unwrap();
+    /// assert_eq!(received.len(), 2);
+    /// assert_eq!((received[1].sensor, received[1].celsius), (4, -2.0));
+    /// assert_eq!(Array::<Reading, 8>::from_bytes(&packet[1..]).err(), Some(ArrayError::InvalidLength));
+    /// assert_eq!(Array::<Reading, 1>::from_bytes(&packet).err(), Some(ArrayError::Overflow));
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ArrayError> {
+        let size = std::mem::size_of::<T>();
+        let len = bytes.len().checked_div(size).unwrap_or(0);
+        if len * size != bytes.len() {
+            return Err(ArrayError::InvalidLength);
+        }
+        if len > N {
+            return Err(ArrayError::Overflow);
+        }
+        // Every bit pattern of a plain type is a value, zeros included.
+        let mut array = Self::new_with_default(unsafe { std::mem::zeroed() });
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                array.elements.as_mut_ptr().cast::<u8>(),
+                bytes.len(),
+            );
+        }
+        array.cursor = len;
+        Ok(array)
+    }
 }
 
 impl<T, const N: usize> Index<usize> for Array<T, N>
@@ -204,3 +787,77 @@ where
         &mut self.elements[index]
     }
 }
+
+impl<T: Copy + fmt::Debug, const N: usize> fmt::Debug for Array<T, N> {
+    /// Formats the pushed elements as a list.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Copy + Sync, const N: usize> rayon::iter::IntoParallelIterator for &'a Array<T, N> {
+    type Item = &'a T;
+    type Iter = rayon::slice::Iter<'a, T>;
+
+    /// Returns a parallel iterator over the pushed elements, which makes `par_iter()` available.
+    /// ```
+    /// # use strctr::array::Array;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut samples: Array<u64, 4096> = Array::new();
+    /// for i in 0..4096 {
+    ///     samples.push(i);
+    /// }
+    /// samples.par_iter_mut().for_each(|x| *x *= 2);
+    /// assert_eq!(samples.par_iter().sum::<u64>(), 4095 * 4096);
+    /// ```
+    fn into_par_iter(self) -> Self::Iter {
+        self.as_slice().into_par_iter()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Copy + Send, const N: usize> rayon::iter::IntoParallelIterator for &'a mut Array<T, N> {
+    type Item = &'a mut T;
+    type Iter = rayon::slice::IterMut<'a, T>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.as_mut_slice().into_par_iter()
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl<T, const N: usize> quickcheck::Arbitrary for Array<T, N>
+where
+    T: quickcheck::Arbitrary + Copy + Default,
+{
+    /// Generates an array of up to `N` arbitrary elements, full ones included.
+    /// ```
+    /// # use strctr::array::Array;
+    /// use quickcheck::quickcheck;
+    ///
+    /// fn pop_returns_last(mut a: Array<u8, 8>) -> bool {
+    ///     let last = a.as_slice().last().copied();
+    ///     a.pop() == last && a.len() == a.as_slice().len()
+    /// }
+    /// quickcheck(pop_returns_last as fn(Array<u8, 8>) -> bool);
+    /// ```
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let len = usize::arbitrary(g) % (N + 1);
+        let mut array = Self::new();
+        for _ in 0..len {
+            array.push(T::arbitrary(g));
+        }
+        array
+    }
+
+    /// Shrinks to shorter arrays and to arrays of shrunk elements.
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.as_slice().to_vec().shrink().map(|elements| {
+            let mut array = Self::new();
+            elements.into_iter().for_each(|elem| array.push(elem));
+            array
+        }))
+    }
+}
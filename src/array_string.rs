@@ -0,0 +1,246 @@
+//! String with a fixed capacity, storing its UTF-8 bytes inline. Like [`Array`](`crate::array::Array`), it never
+//! allocates, which makes it usable for formatting in allocation-free or embedded code.
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+
+use crate::array::ArrayError;
+
+/// A string holding at most `N` bytes of UTF-8, stored inline.
+/// ```
+/// # use strctr::array_string::ArrayString;
+/// use std::fmt::Write;
+/// let mut s: ArrayString<16> = ArrayString::new();
+/// write!(s, "{}-{}", 12, "ab").unwrap();
+/// assert_eq!(&*s, "12-ab");
+/// assert!(write!(s, "{}", "too long to fit").is_err());
+/// ```
+#[derive(Clone, Copy)]
+pub struct ArrayString<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for ArrayString<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> ArrayString<N> {
+    /// Constructs a new, empty string.
+    pub fn new() -> Self {
+        Self {
+            bytes: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Returns the length of the string in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes the string can hold.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the string as a [`str`].
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len])
+            .expect("only whole strs are copied in, and truncation happens on char boundaries")
+    }
+
+    /// Appends the string. If it does not fit, an error is returned and nothing is appended.
+    ///
+    /// For a more convenient (but less safe) method, see [push_str()](`Self::push_str()`)
+    /// ```
+    /// # use strctr::array_string::ArrayString;
+    /// let mut s: ArrayString<4> = ArrayString::new();
+    /// assert!(s.try_push_str("abc").is_ok());
+    /// assert!(s.try_push_str("de").is_err());
+    /// assert_eq!(&*s, "abc");
+    /// ```
+    pub fn try_push_str(&mut self, s: &str) -> Result<(), ArrayError> {
+        let end = self.len + s.len();
+        if end > N {
+            return Err(ArrayError::Overflow);
+        }
+        self.bytes[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+
+    /// Appends the string.
+    ///
+    /// Panics if the result would be longer than [capacity()](`Self::capacity()`).
+    /// For a non-panicing version, see [try_push_str()](`Self::try_push_str()`)
+    /// ```should_panic
+    /// # use strctr::array_string::ArrayString;
+    /// let mut s: ArrayString<2> = ArrayString::new();
+    /// s.push_str("abc");
+    /// ```
+    pub fn push_str(&mut self, s: &str) {
+        if self.try_push_str(s).is_err() {
+            panic!(
+                "Overflow: Wanted to add {} bytes to {}, but capacity is {}",
+                s.len(),
+                self.len,
+                N
+            );
+        }
+    }
+
+    /// Appends the character. If it does not fit, an error is returned and nothing is appended.
+    pub fn try_push(&mut self, c: char) -> Result<(), ArrayError> {
+        self.try_push_str(c.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Appends the character.
+    ///
+    /// Panics if the result would be longer than [capacity()](`Self::capacity()`).
+    /// ```
+    /// # use strctr::array_string::ArrayString;
+    /// let mut s: ArrayString<3> = ArrayString::new();
+    /// s.push('é');
+    /// s.push('!');
+    /// assert_eq!(s.len(), 3);
+    /// assert_eq!(s.try_push('x'), Err(strctr::array::ArrayError::Overflow));
+    /// ```
+    pub fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Removes the last character and returns it, or `None` if the string is empty.
+    pub fn pop(&mut self) -> Option<char> {
+        let c = self.as_str().chars().next_back()?;
+        self.len -= c.len_utf8();
+        Some(c)
+    }
+
+    /// Shortens the string to the length in bytes. Does nothing if the string is already shorter.
+    ///
+    /// Panics if the length does not lie on a character boundary.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            if !self.as_str().is_char_boundary(len) {
+                panic!("InvalidBoundary: Byte {} is inside a character", len);
+            }
+            self.len = len;
+        }
+    }
+
+    /// Removes all characters.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> Deref for ArrayString<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> DerefMut for ArrayString<N> {
+    fn deref_mut(&mut self) -> &mut str {
+        std::str::from_utf8_mut(&mut self.bytes[..self.len])
+            .expect("only whole strs are copied in, and truncation happens on char boundaries")
+    }
+}
+
+impl<const N: usize> AsRef<str> for ArrayString<N> {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl<const N: usize> Borrow<str> for ArrayString<N> {
+    fn borrow(&self) -> &str {
+        self
+    }
+}
+
+impl<const N: usize> FromStr for ArrayString<N> {
+    type Err = ArrayError;
+
+    /// Copies the string, failing if it is longer than `N` bytes.
+    /// ```
+    /// # use strctr::array_string::ArrayString;
+    /// let s: ArrayString<8> = "hello".parse().unwrap();
+    /// assert_eq!(s, "hello");
+    /// assert!("far too long".parse::<ArrayString<8>>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut string = Self::new();
+        string.try_push_str(s)?;
+        Ok(string)
+    }
+}
+
+impl<const N: usize> fmt::Write for ArrayString<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.try_push_str(s).map_err(|_| fmt::Error)
+    }
+}
+
+impl<const N: usize> fmt::Display for ArrayString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> fmt::Debug for ArrayString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> PartialEq for ArrayString<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl<const N: usize> Eq for ArrayString<N> {}
+
+impl<const N: usize> PartialEq<str> for ArrayString<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<const N: usize> PartialEq<&str> for ArrayString<N> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl<const N: usize> PartialOrd for ArrayString<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Ord for ArrayString<N> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl<const N: usize> Hash for ArrayString<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
@@ -0,0 +1,496 @@
+//! Sets of small integers stored as one bit per element.
+//!
+//! [`BitSet`] grows on demand and lives on the heap. [`FixedBitSet`] holds `64 * W` bits inline, which makes it
+//! `Copy` and a drop-in replacement for flag tables such as `Array<bool, N>` at an eighth of the memory.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign};
+
+use crate::simd;
+use crate::snapshot::{Codec, Snapshot, SnapshotError};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+fn split(i: usize) -> (usize, u64) {
+    (i / WORD_BITS, 1 << (i % WORD_BITS))
+}
+
+/// A growable set of `usize` values, stored as a bit vector.
+#[derive(Clone, Default)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    /// Constructs a new, empty set.
+    pub fn new() -> Self {
+        Self { words: Vec::new() }
+    }
+
+    /// Constructs a new, empty set with room for the values `0..bits` before it has to grow.
+    pub fn with_capacity(bits: usize) -> Self {
+        Self {
+            words: vec![0; bits.div_ceil(WORD_BITS)],
+        }
+    }
+
+    /// Returns the number of values the set can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.words.len() * WORD_BITS
+    }
+
+    /// Returns the number of values in the set.
+    pub fn count_ones(&self) -> usize {
+        count_ones(&self.words)
+    }
+
+    /// Returns whether the set contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    /// Returns whether the value is in the set.
+    pub fn get(&self, i: usize) -> bool {
+        let (word, mask) = split(i);
+        self.words.get(word).is_some_and(|w| w & mask != 0)
+    }
+
+    /// Adds the value to the set, growing it if needed. Returns whether the value was already present.
+    /// ```
+    /// # use strctr::bitset::BitSet;
+    /// let mut s = BitSet::new();
+    /// assert!(!s.set(100));
+    /// assert!(s.set(100));
+    /// assert!(s.get(100));
+    /// assert!(!s.get(99));
+    /// ```
+    pub fn set(&mut self, i: usize) -> bool {
+        let (word, mask) = split(i);
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        let was_set = self.words[word] & mask != 0;
+        self.words[word] |= mask;
+        was_set
+    }
+
+    /// Removes the value from the set. Returns whether the value was present.
+    pub fn clear(&mut self, i: usize) -> bool {
+        let (word, mask) = split(i);
+        match self.words.get_mut(word) {
+            Some(w) => {
+                let was_set = *w & mask != 0;
+                *w &= !mask;
+                was_set
+            }
+            None => false,
+        }
+    }
+
+    /// Adds the value if it is absent and removes it otherwise. Returns whether the value is now present.
+    pub fn toggle(&mut self, i: usize) -> bool {
+        if self.get(i) {
+            self.clear(i);
+            false
+        } else {
+            self.set(i);
+            true
+        }
+    }
+
+    /// Removes all values from the set, keeping its capacity.
+    pub fn clear_all(&mut self) {
+        self.words.fill(0);
+    }
+
+    /// Returns an iterator over the values in the set, in ascending order.
+    /// ```
+    /// # use strctr::bitset::BitSet;
+    /// let s: BitSet = [3, 64, 1].into_iter().collect();
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), vec![1, 3, 64]);
+    /// ```
+    pub fn iter(&self) -> Ones<'_> {
+        Ones::new(&self.words)
+    }
+
+    /// Adds every value of the other set to this one.
+    pub fn union_with(&mut self, other: &Self) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        simd::or_assign(&mut self.words, &other.words);
+    }
+
+    /// Keeps only the values that are also in the other set.
+    pub fn intersect_with(&mut self, other: &Self) {
+        let shared = self.words.len().min(other.words.len());
+        simd::and_assign(&mut self.words[..shared], &other.words[..shared]);
+        self.words[shared..].fill(0);
+    }
+
+    /// Keeps the values that are in exactly one of the two sets.
+    pub fn symmetric_difference_with(&mut self, other: &Self) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        simd::xor_assign(&mut self.words, &other.words);
+    }
+
+    /// Removes every value of the other set from this one.
+    pub fn difference_with(&mut self, other: &Self) {
+        simd::and_not_assign(&mut self.words, &other.words);
+    }
+
+    /// Returns whether every value of this set is also in the other set.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.words
+            .iter()
+            .enumerate()
+            .all(|(i, a)| a & !other.words.get(i).copied().unwrap_or(0) == 0)
+    }
+
+    /// Returns whether the two sets have no value in common.
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.words.iter().zip(&other.words).all(|(a, b)| a & b == 0)
+    }
+}
+
+impl PartialEq for BitSet {
+    fn eq(&self, other: &Self) -> bool {
+        // Trailing zero words are only spare capacity.
+        let (short, long) = if self.words.len() <= other.words.len() {
+            (&self.words, &other.words)
+        } else {
+            (&other.words, &self.words)
+        };
+        long[..short.len()] == short[..] && long[short.len()..].iter().all(|&w| w == 0)
+    }
+}
+
+impl Eq for BitSet {}
+
+impl fmt::Debug for BitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for BitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for i in iter {
+            self.set(i);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a BitSet {
+    type Item = usize;
+    type IntoIter = Ones<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl BitOrAssign<&BitSet> for BitSet {
+    fn bitor_assign(&mut self, rhs: &BitSet) {
+        self.union_with(rhs);
+    }
+}
+
+impl BitAndAssign<&BitSet> for BitSet {
+    fn bitand_assign(&mut self, rhs: &BitSet) {
+        self.intersect_with(rhs);
+    }
+}
+
+impl BitXorAssign<&BitSet> for BitSet {
+    fn bitxor_assign(&mut self, rhs: &BitSet) {
+        self.symmetric_difference_with(rhs);
+    }
+}
+
+/// Union of two sets.
+/// ```
+/// # use strctr::bitset::BitSet;
+/// let a: BitSet = [1, 2, 3].into_iter().collect();
+/// let b: BitSet = [3, 4, 200].into_iter().collect();
+/// assert_eq!((&a | &b).iter().collect::<Vec<_>>(), vec![1, 2, 3, 4, 200]);
+/// assert_eq!((&a & &b).iter().collect::<Vec<_>>(), vec![3]);
+/// assert_eq!((&a ^ &b).count_ones(), 4);
+/// ```
+impl BitOr for &BitSet {
+    type Output = BitSet;
+
+    fn bitor(self, rhs: &BitSet) -> BitSet {
+        let mut out = self.clone();
+        out |= rhs;
+        out
+    }
+}
+
+impl BitAnd for &BitSet {
+    type Output = BitSet;
+
+    fn bitand(self, rhs: &BitSet) -> BitSet {
+        let mut out = self.clone();
+        out &= rhs;
+        out
+    }
+}
+
+impl BitXor for &BitSet {
+    type Output = BitSet;
+
+    fn bitxor(self, rhs: &BitSet) -> BitSet {
+        let mut out = self.clone();
+        out ^= rhs;
+        out
+    }
+}
+
+impl Snapshot for BitSet {
+    const TAG: [u8; 4] = *b"BSET";
+
+    /// Writes the words up to the last non-zero one.
+    fn write_payload<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let used = self
+            .words
+            .iter()
+            .rposition(|&word| word != 0)
+            .map_or(0, |i| i + 1);
+        used.encode(w)?;
+        self.words[..used]
+            .iter()
+            .try_for_each(|word| word.encode(w))
+    }
+
+    fn read_payload<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        Ok(Self {
+            words: Vec::decode(r)?,
+        })
+    }
+}
+
+/// A set of the values `0..64 * W`, stored inline in `W` words.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FixedBitSet<const W: usize> {
+    words: [u64; W],
+}
+
+impl<const W: usize> Default for FixedBitSet<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const W: usize> FixedBitSet<W> {
+    /// Number of values the set can hold.
+    pub const CAPACITY: usize = W * WORD_BITS;
+
+    /// Constructs a new, empty set.
+    pub const fn new() -> Self {
+        Self { words: [0; W] }
+    }
+
+    /// Returns the number of values the set can hold.
+    pub fn capacity(&self) -> usize {
+        Self::CAPACITY
+    }
+
+    /// Returns the number of values in the set.
+    pub fn count_ones(&self) -> usize {
+        count_ones(&self.words)
+    }
+
+    /// Returns whether the set contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    /// Returns whether the value is in the set. Values beyond the capacity are never present.
+    pub fn get(&self, i: usize) -> bool {
+        let (word, mask) = split(i);
+        self.words.get(word).is_some_and(|w| w & mask != 0)
+    }
+
+    /// Adds the value to the set. Returns whether the value was already present.
+    ///
+    /// Panics if the value is not below [`CAPACITY`](`Self::CAPACITY`).
+    /// ```
+    /// # use strctr::bitset::FixedBitSet;
+    /// let mut flags = FixedBitSet::<2>::new();
+    /// assert_eq!(flags.capacity(), 128);
+    /// flags.set(127);
+    /// flags.toggle(5);
+    /// assert_eq!(flags.iter().collect::<Vec<_>>(), vec![5, 127]);
+    /// ```
+    pub fn set(&mut self, i: usize) -> bool {
+        let (word, mask) = self.checked_split(i);
+        let was_set = self.words[word] & mask != 0;
+        self.words[word] |= mask;
+        was_set
+    }
+
+    /// Removes the value from the set. Returns whether the value was present.
+    pub fn clear(&mut self, i: usize) -> bool {
+        let (word, mask) = split(i);
+        match self.words.get_mut(word) {
+            Some(w) => {
+                let was_set = *w & mask != 0;
+                *w &= !mask;
+                was_set
+            }
+            None => false,
+        }
+    }
+
+    /// Adds the value if it is absent and removes it otherwise. Returns whether the value is now present.
+    ///
+    /// Panics if the value is not below [`CAPACITY`](`Self::CAPACITY`).
+    pub fn toggle(&mut self, i: usize) -> bool {
+        let (word, mask) = self.checked_split(i);
+        self.words[word] ^= mask;
+        self.words[word] & mask != 0
+    }
+
+    /// Removes all values from the set.
+    pub fn clear_all(&mut self) {
+        self.words = [0; W];
+    }
+
+    /// Returns an iterator over the values in the set, in ascending order.
+    pub fn iter(&self) -> Ones<'_> {
+        Ones::new(&self.words)
+    }
+
+    fn checked_split(&self, i: usize) -> (usize, u64) {
+        if i >= Self::CAPACITY {
+            panic!(
+                "OutOfBounds: Wanted to access bit {}, but capacity is {}",
+                i,
+                Self::CAPACITY
+            );
+        }
+        split(i)
+    }
+}
+
+impl<const W: usize> fmt::Debug for FixedBitSet<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, const W: usize> IntoIterator for &'a FixedBitSet<W> {
+    type Item = usize;
+    type IntoIter = Ones<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<const W: usize> BitOrAssign for FixedBitSet<W> {
+    fn bitor_assign(&mut self, rhs: Self) {
+        simd::or_assign(&mut self.words, &rhs.words);
+    }
+}
+
+impl<const W: usize> BitAndAssign for FixedBitSet<W> {
+    fn bitand_assign(&mut self, rhs: Self) {
+        simd::and_assign(&mut self.words, &rhs.words);
+    }
+}
+
+impl<const W: usize> BitXorAssign for FixedBitSet<W> {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        simd::xor_assign(&mut self.words, &rhs.words);
+    }
+}
+
+/// Union of two sets.
+/// ```
+/// # use strctr::bitset::FixedBitSet;
+/// let mut a = FixedBitSet::<1>::new();
+/// let mut b = FixedBitSet::<1>::new();
+/// a.set(1);
+/// a.set(2);
+/// b.set(2);
+/// assert_eq!((a | b).count_ones(), 2);
+/// assert_eq!((a & b).iter().collect::<Vec<_>>(), vec![2]);
+/// assert_eq!((a ^ b).iter().collect::<Vec<_>>(), vec![1]);
+/// ```
+impl<const W: usize> BitOr for FixedBitSet<W> {
+    type Output = Self;
+
+    fn bitor(mut self, rhs: Self) -> Self {
+        self |= rhs;
+        self
+    }
+}
+
+impl<const W: usize> BitAnd for FixedBitSet<W> {
+    type Output = Self;
+
+    fn bitand(mut self, rhs: Self) -> Self {
+        self &= rhs;
+        self
+    }
+}
+
+impl<const W: usize> BitXor for FixedBitSet<W> {
+    type Output = Self;
+
+    fn bitxor(mut self, rhs: Self) -> Self {
+        self ^= rhs;
+        self
+    }
+}
+
+fn count_ones(words: &[u64]) -> usize {
+    simd::count_ones(words)
+}
+
+/// Iterator over the values of a [`BitSet`] or [`FixedBitSet`], in ascending order.
+pub struct Ones<'a> {
+    words: &'a [u64],
+    /// Index of `current` within `words`.
+    index: usize,
+    /// Bits of the current word that were not yielded yet.
+    current: u64,
+}
+
+impl<'a> Ones<'a> {
+    fn new(words: &'a [u64]) -> Self {
+        Self {
+            words,
+            index: 0,
+            current: words.first().copied().unwrap_or(0),
+        }
+    }
+}
+
+impl Iterator for Ones<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            self.index += 1;
+            self.current = *self.words.get(self.index)?;
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        // Clear the lowest set bit.
+        self.current &= self.current - 1;
+        Some(self.index * WORD_BITS + bit)
+    }
+}
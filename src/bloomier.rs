@@ -0,0 +1,250 @@
+//! Bloomier filter: a static map from keys to small integer values that does not store the keys themselves.
+//!
+//! The filter is built once from all of its entries. Each key hashes to three table slots whose XOR encodes the
+//! key's value together with a short fingerprint. Looking up a key that was never inserted returns `None` unless its
+//! fingerprint happens to match, which occurs with probability `2^-fingerprint_bits`. Every slot takes
+//! `value_bits + fingerprint_bits` bits and the table has about `1.25 n` slots.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+/// List of errors that could occur when building a [`BloomierFilter`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum BloomierError {
+    /// `value_bits + fingerprint_bits` is 0 or larger than 64.
+    InvalidWidth,
+    /// A value does not fit into `value_bits` bits.
+    ValueTooLarge,
+    /// The same key appears more than once.
+    DuplicateKey,
+    /// No table could be built within the number of attempts. This happens with negligible probability.
+    ConstructionFailed,
+}
+
+/// Number of seeds tried before giving up on building the table.
+const MAX_ATTEMPTS: u64 = 64;
+
+/// A static approximate map from keys of type `K` to values of up to `value_bits` bits.
+pub struct BloomierFilter<K> {
+    table: Vec<u64>,
+    segment: usize,
+    seed: u64,
+    value_bits: u32,
+    fingerprint_bits: u32,
+    len: usize,
+    _key: PhantomData<fn(&K)>,
+}
+
+impl<K: Hash> BloomierFilter<K> {
+    /// Builds a filter out of the entries. Values must fit into `value_bits` bits, and lookups of unknown keys return
+    /// a wrong value with probability `2^-fingerprint_bits`. With zero fingerprint bits every lookup succeeds, which
+    /// is useful when only keys of the original set are ever looked up.
+    /// ```
+    /// # use strctr::bloomier::{BloomierFilter, BloomierError};
+    /// let colors = [("red", 0), ("green", 1), ("blue", 2)];
+    /// let f = BloomierFilter::build(&colors, 2, 8).unwrap();
+    /// assert_eq!(f.get(&"green"), Some(1));
+    ///
+    /// assert_eq!(BloomierFilter::build(&[("x", 4)], 2, 8).err(), Some(BloomierError::ValueTooLarge));
+    /// assert_eq!(BloomierFilter::build(&[("x", 4)], u32::MAX, 1).err(), Some(BloomierError::InvalidWidth));
+    ///
+    /// // Values may take up the whole 64 bits of a slot.
+    /// let f = BloomierFilter::build(&[("a", 5), ("b", u64::MAX)], 64, 0).unwrap();
+    /// assert_eq!(f.get(&"b"), Some(u64::MAX));
+    /// ```
+    pub fn build(
+        entries: &[(K, u64)],
+        value_bits: u32,
+        fingerprint_bits: u32,
+    ) -> Result<Self, BloomierError> {
+        let width = match value_bits.checked_add(fingerprint_bits) {
+            Some(width @ 1..=64) => width,
+            _ => return Err(BloomierError::InvalidWidth),
+        };
+        if entries.iter().any(|(_, v)| *v > mask(value_bits)) {
+            return Err(BloomierError::ValueTooLarge);
+        }
+
+        let capacity = entries.len() + entries.len() / 4 + 32;
+        let segment = capacity.div_ceil(3);
+        let mut collisions = 0;
+        for seed in 0..MAX_ATTEMPTS {
+            let mut filter = Self {
+                table: vec![0; (3 * segment * width as usize).div_ceil(64)],
+                segment,
+                seed,
+                value_bits,
+                fingerprint_bits,
+                len: entries.len(),
+                _key: PhantomData,
+            };
+            let hashes: Vec<u64> = entries.iter().map(|(k, _)| filter.hash(k)).collect();
+            let mut sorted = hashes.clone();
+            sorted.sort_unstable();
+            if sorted.windows(2).any(|w| w[0] == w[1]) {
+                // Equal hashes under several seeds almost certainly mean equal keys.
+                collisions += 1;
+                if collisions >= 3 {
+                    return Err(BloomierError::DuplicateKey);
+                }
+                continue;
+            }
+            if let Some(order) = filter.peel(&hashes) {
+                filter.assign(entries, &hashes, order);
+                return Ok(filter);
+            }
+        }
+        // Peeling a random 3-hypergraph of this density fails with negligible probability.
+        Err(BloomierError::ConstructionFailed)
+    }
+
+    /// Returns the value stored for the key. For keys that were not part of the filter this is `None`, except for
+    /// false positives, which return an arbitrary value.
+    /// ```
+    /// # use strctr::bloomier::BloomierFilter;
+    /// let entries: Vec<_> = (0..1000u32).map(|i| (i, u64::from(i % 16))).collect();
+    /// let f = BloomierFilter::build(&entries, 4, 12).unwrap();
+    /// assert!((0..1000).all(|i| f.get(&i) == Some(u64::from(i % 16))));
+    /// let false_positives = (1000..11000u32).filter(|i| f.get(i).is_some()).count();
+    /// assert!(false_positives < 20);
+    /// ```
+    pub fn get(&self, key: &K) -> Option<u64> {
+        let h = self.hash(key);
+        let [a, b, c] = self.positions(h);
+        let x = self.slot(a) ^ self.slot(b) ^ self.slot(c);
+        // A value of 64 bits leaves no room for a fingerprint.
+        let fingerprint = x.checked_shr(self.value_bits).unwrap_or(0);
+        (fingerprint == self.fingerprint(h)).then_some(x & mask(self.value_bits))
+    }
+}
+
+impl<K> BloomierFilter<K> {
+    /// Returns the number of entries the filter was built from.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the filter was built from no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes used by the table.
+    pub fn size_in_bytes(&self) -> usize {
+        self.table.len() * std::mem::size_of::<u64>()
+    }
+
+    /// Returns the probability that looking up an unknown key returns a value.
+    pub fn false_positive_rate(&self) -> f64 {
+        0.5f64.powi(self.fingerprint_bits as i32)
+    }
+
+    fn width(&self) -> u32 {
+        self.value_bits + self.fingerprint_bits
+    }
+
+    fn slot(&self, i: usize) -> u64 {
+        let width = self.width() as usize;
+        let bit = i * width;
+        let (word, offset) = (bit / 64, bit % 64);
+        let mut x = self.table[word] >> offset;
+        if offset + width > 64 {
+            x |= self.table[word + 1] << (64 - offset);
+        }
+        x & mask(self.width())
+    }
+
+    fn set_slot(&mut self, i: usize, x: u64) {
+        let width = self.width() as usize;
+        let bit = i * width;
+        let (word, offset) = (bit / 64, bit % 64);
+        let m = mask(self.width());
+        self.table[word] = (self.table[word] & !(m << offset)) | (x << offset);
+        if offset + width > 64 {
+            let shift = 64 - offset;
+            self.table[word + 1] = (self.table[word + 1] & !(m >> shift)) | (x >> shift);
+        }
+    }
+
+    fn positions(&self, h: u64) -> [usize; 3] {
+        let reduce = |x: u64| (((x & 0xffff_ffff) * self.segment as u64) >> 32) as usize;
+        [
+            reduce(h),
+            self.segment + reduce(h.rotate_left(21)),
+            2 * self.segment + reduce(h.rotate_left(42)),
+        ]
+    }
+
+    fn fingerprint(&self, h: u64) -> u64 {
+        let mut x = h ^ (h >> 33);
+        x = x.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        x ^= x >> 33;
+        x & mask(self.fingerprint_bits)
+    }
+
+    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.seed.hash(&mut hasher);
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Repeatedly removes keys that are the only one mapping to some slot. Returns the removed keys along with their
+    /// free slot, in removal order, or `None` if some keys could not be removed.
+    fn peel(&self, hashes: &[u64]) -> Option<Vec<(usize, usize)>> {
+        let slots = 3 * self.segment;
+        let mut count = vec![0u32; slots];
+        let mut xor = vec![0usize; slots];
+        for (i, &h) in hashes.iter().enumerate() {
+            for p in self.positions(h) {
+                count[p] += 1;
+                xor[p] ^= i;
+            }
+        }
+
+        let mut queue: Vec<usize> = (0..slots).filter(|&p| count[p] == 1).collect();
+        let mut order = Vec::with_capacity(hashes.len());
+        while let Some(p) = queue.pop() {
+            if count[p] != 1 {
+                continue;
+            }
+            let i = xor[p];
+            order.push((i, p));
+            for q in self.positions(hashes[i]) {
+                count[q] -= 1;
+                xor[q] ^= i;
+                if count[q] == 1 {
+                    queue.push(q);
+                }
+            }
+        }
+        (order.len() == hashes.len()).then_some(order)
+    }
+
+    /// Fills the table in reverse peeling order, so each key's free slot is written after its other two are final.
+    fn assign(&mut self, entries: &[(K, u64)], hashes: &[u64], order: Vec<(usize, usize)>) {
+        for (i, p) in order.into_iter().rev() {
+            let h = hashes[i];
+            let fingerprint = self
+                .fingerprint(h)
+                .checked_shl(self.value_bits)
+                .unwrap_or(0);
+            let encoded = fingerprint | entries[i].1;
+            let others = self
+                .positions(h)
+                .into_iter()
+                .filter(|&q| q != p)
+                .fold(0, |acc, q| acc ^ self.slot(q));
+            self.set_slot(p, encoded ^ others);
+        }
+    }
+}
+
+fn mask(bits: u32) -> u64 {
+    if bits >= 64 {
+        u64::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
@@ -0,0 +1,685 @@
+//! Binary search tree with a pluggable rebalancing strategy, for comparing strategies against each other.
+//!
+//! [`BalancedTree`] implements searching, inserting, removing and iterating once. After every insertion and removal it
+//! hands the tree's [`Shape`] to a [`BalancePolicy`], which restores its own invariant with rotations or rebuilds. The
+//! policies [`Avl`], [`RedBlack`], [`Scapegoat`] and [`Unbalanced`] thus run on identical node and search code, and the
+//! tree's [`Stats`] count the comparisons, rotations and rebuilt nodes each one costs. Every node also counts the nodes
+//! of its subtree, whatever the policy, so the tree answers order statistics like the k-th smallest key
+//! ([select()](`BalancedTree::select()`)) and the position of a key ([rank()](`BalancedTree::rank()`)) in time
+//! proportional to its height.
+//!
+//! This module is meant for experiments and teaching. The dedicated trees of this crate, like
+//! [`RBTreeMap`](`crate::rbtree::RBTreeMap`), are faster.
+//! ```
+//! # use strctr::bst::{Avl, BalancedTree, RedBlack, Scapegoat, Unbalanced};
+//! let mut avl = BalancedTree::new(Avl);
+//! let mut rb = BalancedTree::new(RedBlack);
+//! let mut scapegoat = BalancedTree::new(Scapegoat::default());
+//! let mut plain = BalancedTree::new(Unbalanced);
+//! for i in 0..1000 {
+//!     avl.insert(i, ());
+//!     rb.insert(i, ());
+//!     scapegoat.insert(i, ());
+//!     plain.insert(i, ());
+//! }
+//! // Sorted input degenerates an unbalanced tree into a list.
+//! assert_eq!(plain.height(), 1000);
+//! assert!(avl.height() <= 11 && rb.height() <= 19 && scapegoat.height() <= 21);
+//! assert!(avl.stats().rotations > 0 && scapegoat.stats().rebuilt_nodes > 0);
+//! ```
+
+mod policies;
+
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::viz::{self, Direction, Event};
+
+pub use policies::{Avl, Color, RedBlack, Scapegoat, Unbalanced};
+
+/// A rebalancing strategy for a [`BalancedTree`].
+///
+/// The tree inserts and removes nodes like an ordinary binary search tree and then calls the policy, which can inspect
+/// and restructure the tree through the [`Shape`]. Nodes are identified by their index.
+pub trait BalancePolicy {
+    /// Data the policy keeps in every node, like a height or a color.
+    type Meta: Copy + fmt::Debug;
+
+    /// Returns the data of a new leaf.
+    fn leaf(&self) -> Self::Meta;
+
+    /// Restores the invariant after `node` was inserted as a leaf.
+    fn after_insert<K, V>(&mut self, shape: &mut Shape<K, V, Self::Meta>, node: usize);
+
+    /// Restores the invariant after a node with the data `removed` was unlinked. `child` took its place, below
+    /// `parent`; either can be `None`.
+    fn after_remove<K, V>(
+        &mut self,
+        shape: &mut Shape<K, V, Self::Meta>,
+        removed: Self::Meta,
+        child: Option<usize>,
+        parent: Option<usize>,
+    );
+
+    /// Returns whether the tree satisfies the policy's invariant.
+    fn is_balanced<K, V>(&self, shape: &Shape<K, V, Self::Meta>) -> bool;
+}
+
+/// Operation counts of a [`BalancedTree`], returned by [stats()](`BalancedTree::stats()`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Key comparisons made by lookups, insertions and removals.
+    pub comparisons: u64,
+    /// Single rotations, each moving one node up and one down.
+    pub rotations: u64,
+    /// Nodes relinked by subtree rebuilds.
+    pub rebuilt_nodes: u64,
+}
+
+/// Invariant violations that [check_invariants()](`BalancedTree::check_invariants()`) can report.
+#[derive(Debug, PartialEq, Eq)]
+pub enum BSTError {
+    /// The keys are not in ascending in-order sequence.
+    Unordered,
+    /// A child does not point back to its parent.
+    BrokenParentLink,
+    /// A node's subtree size is not one more than the sizes of its children's subtrees.
+    WrongSize,
+    /// The policy's balance invariant does not hold.
+    Unbalanced,
+}
+
+struct Node<K, V, M> {
+    key: K,
+    value: V,
+    meta: M,
+    /// Number of nodes in the subtree rooted at this node.
+    size: usize,
+    parent: Option<usize>,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+/// The structure of a [`BalancedTree`], as seen and restructured by its [`BalancePolicy`].
+pub struct Shape<K, V, M> {
+    nodes: Vec<Option<Node<K, V, M>>>,
+    free: Vec<usize>,
+    root: Option<usize>,
+    len: usize,
+    stats: Cell<Stats>,
+}
+
+impl<K, V, M: Copy> Shape<K, V, M> {
+    /// Returns the root node.
+    pub fn root(&self) -> Option<usize> {
+        self.root
+    }
+
+    /// Returns the number of nodes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the tree has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the node's parent.
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        self.node(node).parent
+    }
+
+    /// Returns the node's left child.
+    pub fn left(&self, node: usize) -> Option<usize> {
+        self.node(node).left
+    }
+
+    /// Returns the node's right child.
+    pub fn right(&self, node: usize) -> Option<usize> {
+        self.node(node).right
+    }
+
+    /// Returns the policy's data of the node.
+    pub fn meta(&self, node: usize) -> M {
+        self.node(node).meta
+    }
+
+    /// Replaces the policy's data of the node.
+    pub fn set_meta(&mut self, node: usize, meta: M) {
+        self.node_mut(node).meta = meta;
+    }
+
+    /// Returns the number of edges from the root down to the node.
+    pub fn depth(&self, mut node: usize) -> usize {
+        let mut depth = 0;
+        while let Some(p) = self.parent(node) {
+            node = p;
+            depth += 1;
+        }
+        depth
+    }
+
+    /// Returns the number of nodes in the subtree rooted at the node, or 0 for `None`.
+    pub fn size(&self, node: Option<usize>) -> usize {
+        node.map_or(0, |n| self.node(n).size)
+    }
+
+    /// Returns the number of nodes on the longest path from the node down to a leaf, or 0 for `None`.
+    pub fn height(&self, node: Option<usize>) -> usize {
+        node.map_or(0, |n| {
+            1 + self.height(self.left(n)).max(self.height(self.right(n)))
+        })
+    }
+
+    /// Moves the node's right child up into its place, making the node its left child.
+    ///
+    /// Panics if the node has no right child.
+    pub fn rotate_left(&mut self, x: usize) {
+        let y = self.right(x).expect("rotate_left needs a right child");
+        viz::record(|| Event::Rotate {
+            structure: "BalancedTree",
+            node: x,
+            child: y,
+            direction: Direction::Left,
+        });
+        let y_left = self.left(y);
+        self.node_mut(x).right = y_left;
+        self.set_parent(y_left, Some(x));
+        self.lift(x, y);
+        self.node_mut(y).left = Some(x);
+        self.resize(x);
+    }
+
+    /// Moves the node's left child up into its place, making the node its right child.
+    ///
+    /// Panics if the node has no left child.
+    pub fn rotate_right(&mut self, x: usize) {
+        let y = self.left(x).expect("rotate_right needs a left child");
+        viz::record(|| Event::Rotate {
+            structure: "BalancedTree",
+            node: x,
+            child: y,
+            direction: Direction::Right,
+        });
+        let y_right = self.right(y);
+        self.node_mut(x).left = y_right;
+        self.set_parent(y_right, Some(x));
+        self.lift(x, y);
+        self.node_mut(y).right = Some(x);
+        self.resize(x);
+    }
+
+    /// Relinks the subtree rooted at the node into a perfectly balanced one and returns its new root. The policy's
+    /// data of the nodes is left as it was.
+    pub fn rebuild(&mut self, node: usize) -> usize {
+        let parent = self.parent(node);
+        let mut sorted = Vec::with_capacity(self.size(Some(node)));
+        let mut next = Some(self.minimum(node));
+        let last = self.maximum(node);
+        while let Some(n) = next {
+            sorted.push(n);
+            next = if n == last { None } else { self.successor(n) };
+        }
+        let root = self
+            .build(&sorted, parent)
+            .expect("a subtree has at least one node");
+        self.replace_child(parent, node, Some(root));
+        self.count(|s| s.rebuilt_nodes += sorted.len() as u64);
+        viz::record(|| Event::Rebuild {
+            structure: "BalancedTree",
+            root,
+            nodes: sorted.len(),
+        });
+        root
+    }
+
+    /// Links the sorted nodes into a balanced subtree below the parent and returns its root.
+    fn build(&mut self, sorted: &[usize], parent: Option<usize>) -> Option<usize> {
+        if sorted.is_empty() {
+            return None;
+        }
+        let mid = sorted.len() / 2;
+        let root = sorted[mid];
+        let left = self.build(&sorted[..mid], Some(root));
+        let right = self.build(&sorted[mid + 1..], Some(root));
+        let node = self.node_mut(root);
+        node.parent = parent;
+        node.left = left;
+        node.right = right;
+        node.size = sorted.len();
+        Some(root)
+    }
+
+    /// Puts `y`, a child of `x`, into `x`'s place and makes it `x`'s parent. `y` takes over the size of `x`'s subtree,
+    /// and the caller recomputes `x`'s once its children are relinked.
+    fn lift(&mut self, x: usize, y: usize) {
+        self.node_mut(y).size = self.node(x).size;
+        let x_parent = self.parent(x);
+        self.node_mut(y).parent = x_parent;
+        self.replace_child(x_parent, x, Some(y));
+        self.node_mut(x).parent = Some(y);
+        self.count(|s| s.rotations += 1);
+    }
+
+    /// Replaces the parent's link to `u` with a link to `v`.
+    fn replace_child(&mut self, parent: Option<usize>, u: usize, v: Option<usize>) {
+        match parent {
+            None => self.root = v,
+            Some(p) if self.left(p) == Some(u) => self.node_mut(p).left = v,
+            Some(p) => self.node_mut(p).right = v,
+        }
+    }
+
+    /// Recomputes the node's subtree size from its children's.
+    fn resize(&mut self, node: usize) {
+        self.node_mut(node).size = 1 + self.size(self.left(node)) + self.size(self.right(node));
+    }
+
+    /// Adds the difference to the subtree sizes of the node and all its ancestors.
+    fn resize_path(&mut self, mut node: Option<usize>, grown: bool) {
+        while let Some(n) = node {
+            let size = &mut self.node_mut(n).size;
+            if grown {
+                *size += 1;
+            } else {
+                *size -= 1;
+            }
+            node = self.parent(n);
+        }
+    }
+
+    fn set_parent(&mut self, node: Option<usize>, parent: Option<usize>) {
+        if let Some(n) = node {
+            self.node_mut(n).parent = parent;
+        }
+    }
+
+    fn minimum(&self, mut node: usize) -> usize {
+        while let Some(l) = self.left(node) {
+            node = l;
+        }
+        node
+    }
+
+    fn maximum(&self, mut node: usize) -> usize {
+        while let Some(r) = self.right(node) {
+            node = r;
+        }
+        node
+    }
+
+    fn successor(&self, node: usize) -> Option<usize> {
+        if let Some(r) = self.right(node) {
+            return Some(self.minimum(r));
+        }
+        let mut child = node;
+        let mut parent = self.parent(node);
+        while let Some(p) = parent {
+            if self.right(p) != Some(child) {
+                break;
+            }
+            child = p;
+            parent = self.parent(p);
+        }
+        parent
+    }
+
+    fn node(&self, i: usize) -> &Node<K, V, M> {
+        self.nodes[i].as_ref().expect("dangling node index")
+    }
+
+    fn node_mut(&mut self, i: usize) -> &mut Node<K, V, M> {
+        self.nodes[i].as_mut().expect("dangling node index")
+    }
+
+    fn count(&self, f: impl FnOnce(&mut Stats)) {
+        let mut stats = self.stats.get();
+        f(&mut stats);
+        self.stats.set(stats);
+    }
+}
+
+/// An ordered map whose balance is maintained by the policy `P`.
+pub struct BalancedTree<K, V, P: BalancePolicy> {
+    shape: Shape<K, V, P::Meta>,
+    policy: P,
+}
+
+impl<K, V, P: BalancePolicy + Default> Default for BalancedTree<K, V, P> {
+    fn default() -> Self {
+        Self::new(P::default())
+    }
+}
+
+impl<K, V, P: BalancePolicy> BalancedTree<K, V, P> {
+    /// Constructs a new, empty tree balanced by the policy.
+    pub fn new(policy: P) -> Self {
+        Self {
+            shape: Shape {
+                nodes: Vec::new(),
+                free: Vec::new(),
+                root: None,
+                len: 0,
+                stats: Cell::new(Stats::default()),
+            },
+            policy,
+        }
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.shape.len
+    }
+
+    /// Returns whether the tree contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.shape.len == 0
+    }
+
+    /// Removes all entries. Operation counts are kept.
+    pub fn clear(&mut self) {
+        self.shape.nodes.clear();
+        self.shape.free.clear();
+        self.shape.root = None;
+        self.shape.len = 0;
+    }
+
+    /// Returns the number of nodes on the longest path from the root to a leaf.
+    pub fn height(&self) -> usize {
+        self.shape.height(self.shape.root)
+    }
+
+    /// Returns the policy.
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// Returns the tree's structure.
+    pub fn shape(&self) -> &Shape<K, V, P::Meta> {
+        &self.shape
+    }
+
+    /// Returns the operations counted since the tree was created or [reset_stats()](`Self::reset_stats()`) was called.
+    pub fn stats(&self) -> Stats {
+        self.shape.stats.get()
+    }
+
+    /// Sets all operation counts back to zero.
+    pub fn reset_stats(&mut self) {
+        self.shape.stats.set(Stats::default());
+    }
+
+    /// Returns an iterator over the entries, in ascending key order.
+    pub fn iter(&self) -> Iter<'_, K, V, P::Meta> {
+        Iter {
+            shape: &self.shape,
+            next: self.shape.root.map(|r| self.shape.minimum(r)),
+            remaining: self.shape.len,
+        }
+    }
+}
+
+impl<K: Ord, V, P: BalancePolicy> BalancedTree<K, V, P> {
+    /// Inserts a key-value pair. If the key was already present, its value is replaced and the old value is returned.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut parent = None;
+        let mut ordering = Ordering::Equal;
+        let mut cur = self.shape.root;
+        while let Some(n) = cur {
+            parent = Some(n);
+            ordering = self.compare(&key, n);
+            cur = match ordering {
+                Ordering::Less => self.shape.left(n),
+                Ordering::Greater => self.shape.right(n),
+                Ordering::Equal => {
+                    return Some(std::mem::replace(&mut self.shape.node_mut(n).value, value));
+                }
+            };
+        }
+
+        let node = Node {
+            key,
+            value,
+            meta: self.policy.leaf(),
+            size: 1,
+            parent,
+            left: None,
+            right: None,
+        };
+        let z = match self.shape.free.pop() {
+            Some(i) => {
+                self.shape.nodes[i] = Some(node);
+                i
+            }
+            None => {
+                self.shape.nodes.push(Some(node));
+                self.shape.nodes.len() - 1
+            }
+        };
+        match parent {
+            None => self.shape.root = Some(z),
+            Some(p) if ordering == Ordering::Less => self.shape.node_mut(p).left = Some(z),
+            Some(p) => self.shape.node_mut(p).right = Some(z),
+        }
+        self.shape.len += 1;
+        self.shape.resize_path(parent, true);
+        self.policy.after_insert(&mut self.shape, z);
+        None
+    }
+
+    /// Returns a reference to the value stored under the key.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.find(key).map(|n| &self.shape.node(n).value)
+    }
+
+    /// Returns whether the tree contains the key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Removes the key, returning its value if it was present.
+    /// ```
+    /// # use strctr::bst::{BalancedTree, RedBlack};
+    /// let mut tree: BalancedTree<_, _, RedBlack> = (0..100).map(|i| (i, i * i)).collect();
+    /// assert_eq!(tree.remove(&7), Some(49));
+    /// assert_eq!(tree.remove(&7), None);
+    /// assert_eq!(tree.len(), 99);
+    /// assert!(tree.check_invariants().is_ok());
+    /// ```
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let z = self.find(key)?;
+        // A node with two children trades places with its successor, which has at most one.
+        let y = match (self.shape.left(z), self.shape.right(z)) {
+            (Some(_), Some(r)) => {
+                let y = self.shape.minimum(r);
+                let (a, b) = (z.min(y), z.max(y));
+                let (low, high) = self.shape.nodes.split_at_mut(b);
+                let (a, b) = (low[a].as_mut(), high[0].as_mut());
+                let (a, b) = (
+                    a.expect("dangling node index"),
+                    b.expect("dangling node index"),
+                );
+                std::mem::swap(&mut a.key, &mut b.key);
+                std::mem::swap(&mut a.value, &mut b.value);
+                y
+            }
+            _ => z,
+        };
+        let child = self.shape.left(y).or(self.shape.right(y));
+        let parent = self.shape.parent(y);
+        self.shape.replace_child(parent, y, child);
+        self.shape.set_parent(child, parent);
+
+        let node = self.shape.nodes[y].take().expect("dangling node index");
+        self.shape.free.push(y);
+        self.shape.len -= 1;
+        self.shape.resize_path(parent, false);
+        self.policy
+            .after_remove(&mut self.shape, node.meta, child, parent);
+        Some(node.value)
+    }
+
+    /// Returns the entry with the `k`-th smallest key, counting from 0, or `None` if `k` is out of bounds.
+    /// ```
+    /// # use strctr::bst::{Avl, BalancedTree};
+    /// // Latencies in milliseconds, arriving and expiring over time.
+    /// let mut latencies: BalancedTree<(u32, u64), (), Avl> = BalancedTree::default();
+    /// for (id, ms) in [12, 7, 30, 7, 95, 18, 22, 41, 9, 15].into_iter().enumerate() {
+    ///     latencies.insert((ms, id as u64), ());
+    /// }
+    /// let percentile = |tree: &BalancedTree<(u32, u64), (), Avl>, p: usize| {
+    ///     tree.select((tree.len() - 1) * p / 100).map(|((ms, _), _)| *ms)
+    /// };
+    /// assert_eq!(percentile(&latencies, 50), Some(15));
+    /// assert_eq!(percentile(&latencies, 90), Some(41));
+    /// latencies.remove(&(95, 4));
+    /// latencies.remove(&(41, 7));
+    /// assert_eq!(percentile(&latencies, 100), Some(30));
+    /// ```
+    pub fn select(&self, mut k: usize) -> Option<(&K, &V)> {
+        let mut cur = self.shape.root;
+        while let Some(n) = cur {
+            let left = self.shape.size(self.shape.left(n));
+            cur = match k.cmp(&left) {
+                Ordering::Less => self.shape.left(n),
+                Ordering::Equal => {
+                    let node = self.shape.node(n);
+                    return Some((&node.key, &node.value));
+                }
+                Ordering::Greater => {
+                    k -= left + 1;
+                    self.shape.right(n)
+                }
+            };
+        }
+        None
+    }
+
+    /// Returns the number of keys less than the key, which is its position in ascending order if it is present.
+    /// ```
+    /// # use strctr::bst::{BalancedTree, RedBlack};
+    /// let tree: BalancedTree<_, _, RedBlack> = [10, 20, 30, 40].into_iter().map(|k| (k, ())).collect();
+    /// assert_eq!(tree.rank(&30), 2);
+    /// assert_eq!(tree.rank(&25), 2);
+    /// assert_eq!(tree.rank(&5), 0);
+    /// assert_eq!(tree.rank(&99), 4);
+    /// ```
+    pub fn rank(&self, key: &K) -> usize {
+        let mut rank = 0;
+        let mut cur = self.shape.root;
+        while let Some(n) = cur {
+            cur = match self.compare(key, n) {
+                Ordering::Less => self.shape.left(n),
+                Ordering::Equal => return rank + self.shape.size(self.shape.left(n)),
+                Ordering::Greater => {
+                    rank += self.shape.size(self.shape.left(n)) + 1;
+                    self.shape.right(n)
+                }
+            };
+        }
+        rank
+    }
+
+    /// Verifies that the keys are ordered, the parent links and subtree sizes are consistent and the policy's invariant
+    /// holds.
+    pub fn check_invariants(&self) -> Result<(), BSTError> {
+        if let Some(r) = self.shape.root {
+            if self.shape.parent(r).is_some() {
+                return Err(BSTError::BrokenParentLink);
+            }
+        }
+        for i in (0..self.shape.nodes.len()).filter(|&i| self.shape.nodes[i].is_some()) {
+            for child in [self.shape.left(i), self.shape.right(i)]
+                .into_iter()
+                .flatten()
+            {
+                if self.shape.parent(child) != Some(i) {
+                    return Err(BSTError::BrokenParentLink);
+                }
+            }
+            let children =
+                self.shape.size(self.shape.left(i)) + self.shape.size(self.shape.right(i));
+            if self.shape.size(Some(i)) != 1 + children {
+                return Err(BSTError::WrongSize);
+            }
+        }
+        let mut prev: Option<&K> = None;
+        for (k, _) in self.iter() {
+            if prev.is_some_and(|p| p >= k) {
+                return Err(BSTError::Unordered);
+            }
+            prev = Some(k);
+        }
+        if !self.policy.is_balanced(&self.shape) {
+            return Err(BSTError::Unbalanced);
+        }
+        Ok(())
+    }
+
+    fn find(&self, key: &K) -> Option<usize> {
+        let mut cur = self.shape.root;
+        while let Some(n) = cur {
+            cur = match self.compare(key, n) {
+                Ordering::Less => self.shape.left(n),
+                Ordering::Greater => self.shape.right(n),
+                Ordering::Equal => return Some(n),
+            };
+        }
+        None
+    }
+
+    fn compare(&self, key: &K, node: usize) -> Ordering {
+        self.shape.count(|s| s.comparisons += 1);
+        key.cmp(&self.shape.node(node).key)
+    }
+}
+
+impl<K: Ord, V, P: BalancePolicy + Default> FromIterator<(K, V)> for BalancedTree<K, V, P> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut tree = Self::default();
+        for (k, v) in iter {
+            tree.insert(k, v);
+        }
+        tree
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, P: BalancePolicy> fmt::Debug for BalancedTree<K, V, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// In-order iterator over the entries of a [`BalancedTree`].
+pub struct Iter<'a, K, V, M> {
+    shape: &'a Shape<K, V, M>,
+    next: Option<usize>,
+    remaining: usize,
+}
+
+impl<'a, K, V, M: Copy> Iterator for Iter<'a, K, V, M> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.next?;
+        self.next = self.shape.successor(n);
+        self.remaining -= 1;
+        let node = self.shape.node(n);
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V, P: BalancePolicy> IntoIterator for &'a BalancedTree<K, V, P> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, P::Meta>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -0,0 +1,370 @@
+use super::{BalancePolicy, Shape};
+
+/// No rebalancing at all. Random keys give a height of about `3 log2 n`, sorted keys a list.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Unbalanced;
+
+impl BalancePolicy for Unbalanced {
+    type Meta = ();
+
+    fn leaf(&self) {}
+
+    fn after_insert<K, V>(&mut self, _: &mut Shape<K, V, ()>, _: usize) {}
+
+    fn after_remove<K, V>(
+        &mut self,
+        _: &mut Shape<K, V, ()>,
+        _: (),
+        _: Option<usize>,
+        _: Option<usize>,
+    ) {
+    }
+
+    fn is_balanced<K, V>(&self, _: &Shape<K, V, ()>) -> bool {
+        true
+    }
+}
+
+/// AVL balancing: the heights of every node's subtrees differ by at most one, so the height stays below
+/// `1.44 log2 n`. Every node stores its height.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Avl;
+
+impl Avl {
+    fn height<K, V>(shape: &Shape<K, V, u32>, node: Option<usize>) -> u32 {
+        node.map_or(0, |n| shape.meta(n))
+    }
+
+    fn update<K, V>(shape: &mut Shape<K, V, u32>, node: usize) {
+        let height =
+            1 + Self::height(shape, shape.left(node)).max(Self::height(shape, shape.right(node)));
+        shape.set_meta(node, height);
+    }
+
+    fn balance<K, V>(shape: &Shape<K, V, u32>, node: usize) -> i64 {
+        i64::from(Self::height(shape, shape.left(node)))
+            - i64::from(Self::height(shape, shape.right(node)))
+    }
+
+    /// Updates heights and rotates where needed, from the node up to the root.
+    fn retrace<K, V>(shape: &mut Shape<K, V, u32>, mut node: Option<usize>) {
+        while let Some(mut n) = node {
+            Self::update(shape, n);
+            let balance = Self::balance(shape, n);
+            if balance > 1 {
+                let l = shape.left(n).expect("left-heavy node without left child");
+                if Self::balance(shape, l) < 0 {
+                    shape.rotate_left(l);
+                    Self::update(shape, l);
+                }
+                shape.rotate_right(n);
+                Self::update(shape, n);
+                n = shape.parent(n).expect("rotated node without parent");
+                Self::update(shape, n);
+            } else if balance < -1 {
+                let r = shape
+                    .right(n)
+                    .expect("right-heavy node without right child");
+                if Self::balance(shape, r) > 0 {
+                    shape.rotate_right(r);
+                    Self::update(shape, r);
+                }
+                shape.rotate_left(n);
+                Self::update(shape, n);
+                n = shape.parent(n).expect("rotated node without parent");
+                Self::update(shape, n);
+            }
+            node = shape.parent(n);
+        }
+    }
+
+    fn check<K, V>(shape: &Shape<K, V, u32>, node: Option<usize>) -> Option<u32> {
+        let Some(n) = node else {
+            return Some(0);
+        };
+        let left = Self::check(shape, shape.left(n))?;
+        let right = Self::check(shape, shape.right(n))?;
+        let height = 1 + left.max(right);
+        (left.abs_diff(right) <= 1 && shape.meta(n) == height).then_some(height)
+    }
+}
+
+impl BalancePolicy for Avl {
+    type Meta = u32;
+
+    fn leaf(&self) -> u32 {
+        1
+    }
+
+    fn after_insert<K, V>(&mut self, shape: &mut Shape<K, V, u32>, node: usize) {
+        Self::retrace(shape, shape.parent(node));
+    }
+
+    fn after_remove<K, V>(
+        &mut self,
+        shape: &mut Shape<K, V, u32>,
+        _: u32,
+        _: Option<usize>,
+        parent: Option<usize>,
+    ) {
+        Self::retrace(shape, parent);
+    }
+
+    fn is_balanced<K, V>(&self, shape: &Shape<K, V, u32>) -> bool {
+        Self::check(shape, shape.root()).is_some()
+    }
+}
+
+/// Color of a node under the [`RedBlack`] policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    /// A node whose parent is black.
+    Red,
+    /// A node counted by the black height.
+    Black,
+}
+
+/// Red-black balancing: no red node has a red child, and every path down from a node passes the same number of black
+/// nodes, so the height stays below `2 log2 (n + 1)`. Needs fewer rotations than [`Avl`], at the price of a taller
+/// tree.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RedBlack;
+
+impl RedBlack {
+    fn color<K, V>(shape: &Shape<K, V, Color>, node: Option<usize>) -> Color {
+        node.map_or(Color::Black, |n| shape.meta(n))
+    }
+
+    fn set_color<K, V>(shape: &mut Shape<K, V, Color>, node: Option<usize>, color: Color) {
+        if let Some(n) = node {
+            shape.set_meta(n, color);
+        }
+    }
+
+    fn check<K, V>(shape: &Shape<K, V, Color>, node: Option<usize>) -> Option<usize> {
+        let Some(n) = node else {
+            return Some(1);
+        };
+        let red = shape.meta(n) == Color::Red;
+        if red
+            && (Self::color(shape, shape.left(n)) == Color::Red
+                || Self::color(shape, shape.right(n)) == Color::Red)
+        {
+            return None;
+        }
+        let left = Self::check(shape, shape.left(n))?;
+        let right = Self::check(shape, shape.right(n))?;
+        (left == right).then_some(left + usize::from(!red))
+    }
+}
+
+impl BalancePolicy for RedBlack {
+    type Meta = Color;
+
+    fn leaf(&self) -> Color {
+        Color::Red
+    }
+
+    fn after_insert<K, V>(&mut self, shape: &mut Shape<K, V, Color>, mut z: usize) {
+        while let Some(p) = shape.parent(z) {
+            if shape.meta(p) == Color::Black {
+                break;
+            }
+            // A red node is never the root, so the grandparent exists.
+            let g = shape.parent(p).expect("red node without parent");
+            let p_is_left = shape.left(g) == Some(p);
+            let uncle = if p_is_left {
+                shape.right(g)
+            } else {
+                shape.left(g)
+            };
+            if Self::color(shape, uncle) == Color::Red {
+                Self::set_color(shape, Some(p), Color::Black);
+                Self::set_color(shape, uncle, Color::Black);
+                Self::set_color(shape, Some(g), Color::Red);
+                z = g;
+                continue;
+            }
+            if p_is_left && shape.right(p) == Some(z) {
+                z = p;
+                shape.rotate_left(z);
+            } else if !p_is_left && shape.left(p) == Some(z) {
+                z = p;
+                shape.rotate_right(z);
+            }
+            let p = shape.parent(z).expect("rotated node without parent");
+            let g = shape.parent(p).expect("rotated node without grandparent");
+            Self::set_color(shape, Some(p), Color::Black);
+            Self::set_color(shape, Some(g), Color::Red);
+            if p_is_left {
+                shape.rotate_right(g);
+            } else {
+                shape.rotate_left(g);
+            }
+        }
+        Self::set_color(shape, shape.root(), Color::Black);
+    }
+
+    fn after_remove<K, V>(
+        &mut self,
+        shape: &mut Shape<K, V, Color>,
+        removed: Color,
+        mut x: Option<usize>,
+        mut parent: Option<usize>,
+    ) {
+        if removed == Color::Red {
+            return;
+        }
+        while x != shape.root() && Self::color(shape, x) == Color::Black {
+            // x is not the root, so it has a parent; its sibling exists because x's side is short a black node.
+            let p = parent.expect("non-root node without parent");
+            let x_is_left = shape.left(p) == x;
+            let sibling = |shape: &Shape<K, V, Color>| {
+                if x_is_left {
+                    shape.right(p)
+                } else {
+                    shape.left(p)
+                }
+                .expect("missing sibling")
+            };
+            let mut w = sibling(shape);
+            if shape.meta(w) == Color::Red {
+                Self::set_color(shape, Some(w), Color::Black);
+                Self::set_color(shape, Some(p), Color::Red);
+                if x_is_left {
+                    shape.rotate_left(p);
+                } else {
+                    shape.rotate_right(p);
+                }
+                w = sibling(shape);
+            }
+            let (near, far) = if x_is_left {
+                (shape.left(w), shape.right(w))
+            } else {
+                (shape.right(w), shape.left(w))
+            };
+            if Self::color(shape, near) == Color::Black && Self::color(shape, far) == Color::Black {
+                Self::set_color(shape, Some(w), Color::Red);
+                x = Some(p);
+                parent = shape.parent(p);
+                continue;
+            }
+            if Self::color(shape, far) == Color::Black {
+                Self::set_color(shape, near, Color::Black);
+                Self::set_color(shape, Some(w), Color::Red);
+                if x_is_left {
+                    shape.rotate_right(w);
+                } else {
+                    shape.rotate_left(w);
+                }
+                w = sibling(shape);
+            }
+            Self::set_color(shape, Some(w), shape.meta(p));
+            Self::set_color(shape, Some(p), Color::Black);
+            if x_is_left {
+                Self::set_color(shape, shape.right(w), Color::Black);
+                shape.rotate_left(p);
+            } else {
+                Self::set_color(shape, shape.left(w), Color::Black);
+                shape.rotate_right(p);
+            }
+            x = shape.root();
+            parent = None;
+        }
+        Self::set_color(shape, x, Color::Black);
+    }
+
+    fn is_balanced<K, V>(&self, shape: &Shape<K, V, Color>) -> bool {
+        Self::color(shape, shape.root()) == Color::Black
+            && Self::check(shape, shape.root()).is_some()
+    }
+}
+
+/// Scapegoat balancing: nodes store nothing, and the tree is allowed to grow somewhat unbalanced. When an insertion
+/// lands deeper than `log(n)` to the base `1 / alpha`, the highest ancestor whose subtree is lopsided beyond `alpha` is
+/// rebuilt into a perfectly balanced subtree; when removals shrink the tree below `alpha` times its largest size, the
+/// whole tree is rebuilt. Rebuilds cost `O(log n)` amortized per operation.
+#[derive(Clone, Copy, Debug)]
+pub struct Scapegoat {
+    alpha: f64,
+    /// Largest size since the last full rebuild.
+    max_len: usize,
+}
+
+impl Default for Scapegoat {
+    /// A policy with `alpha = 0.7`.
+    fn default() -> Self {
+        Self::with_alpha(0.7)
+    }
+}
+
+impl Scapegoat {
+    /// Constructs the policy with the given balance factor. Values closer to 1 allow taller trees and rebuild less.
+    ///
+    /// Panics if `alpha` is not between 0.5 and 1, exclusive.
+    pub fn with_alpha(alpha: f64) -> Self {
+        if !(alpha > 0.5 && alpha < 1.0) {
+            panic!("InvalidBounds: Scapegoat alpha must lie between 0.5 and 1, exclusive");
+        }
+        Self { alpha, max_len: 0 }
+    }
+
+    /// Returns the balance factor.
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Returns the greatest depth allowed for a tree of `n` nodes.
+    fn max_depth(&self, n: usize) -> usize {
+        ((n as f64).ln() / (1.0 / self.alpha).ln()).floor() as usize
+    }
+}
+
+impl BalancePolicy for Scapegoat {
+    type Meta = ();
+
+    fn leaf(&self) {}
+
+    fn after_insert<K, V>(&mut self, shape: &mut Shape<K, V, ()>, node: usize) {
+        self.max_len = self.max_len.max(shape.len());
+        if shape.depth(node) <= self.max_depth(shape.len()) {
+            return;
+        }
+        // Some ancestor is lopsided, or the node could not be this deep.
+        let mut child = node;
+        let mut child_size = 1;
+        while let Some(p) = shape.parent(child) {
+            let sibling = if shape.left(p) == Some(child) {
+                shape.right(p)
+            } else {
+                shape.left(p)
+            };
+            let size = 1 + child_size + shape.size(sibling);
+            if child_size as f64 > self.alpha * size as f64 {
+                shape.rebuild(p);
+                return;
+            }
+            child = p;
+            child_size = size;
+        }
+    }
+
+    fn after_remove<K, V>(
+        &mut self,
+        shape: &mut Shape<K, V, ()>,
+        _: (),
+        _: Option<usize>,
+        _: Option<usize>,
+    ) {
+        if (shape.len() as f64) < self.alpha * self.max_len as f64 {
+            if let Some(root) = shape.root() {
+                shape.rebuild(root);
+            }
+            self.max_len = shape.len();
+        }
+    }
+
+    fn is_balanced<K, V>(&self, shape: &Shape<K, V, ()>) -> bool {
+        shape.height(shape.root()) <= self.max_depth(self.max_len.max(1)) + 1
+    }
+}
@@ -0,0 +1,795 @@
+//! B-tree implementation of an ordered map. Each node keeps its keys in a contiguous vector, so lookups touch far fewer
+//! cache lines than a binary search tree of the same size.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::ops::{Bound, RangeBounds};
+
+use crate::simd::prefetch;
+use crate::snapshot::{self, Codec, Snapshot, SnapshotError};
+use crate::viz::{self, Event};
+
+/// List of invariant violations that [check_invariants()](`BTreeMap::check_invariants()`) can report.
+#[derive(Debug, PartialEq, Eq)]
+pub enum BTreeError {
+    /// A non-root node holds fewer than `B - 1` keys, or any node holds more than `2B - 1`.
+    BadKeyCount,
+    /// An internal node does not have exactly one more child than it has keys.
+    BadChildCount,
+    /// Not every leaf sits at the same depth.
+    UnevenLeaves,
+    /// The keys are not in ascending in-order sequence.
+    Unordered,
+}
+
+#[derive(Clone)]
+struct Node<K, V> {
+    keys: Vec<K>,
+    values: Vec<V>,
+    children: Vec<Node<K, V>>,
+}
+
+impl<K, V> Node<K, V> {
+    fn new() -> Self {
+        Self {
+            keys: Vec::new(),
+            values: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Returns the number of levels below the node. All leaves are at the same depth, so any path down will do.
+    fn level(&self) -> usize {
+        let mut level = 0;
+        let mut node = self;
+        while let Some(child) = node.children.first() {
+            level += 1;
+            node = child;
+        }
+        level
+    }
+}
+
+/// An ordered map backed by a B-tree of minimum degree `B`. Every node except the root holds between `B - 1` and
+/// `2B - 1` keys. `B` must be at least 2.
+#[derive(Clone)]
+pub struct BTreeMap<K, V, const B: usize = 6> {
+    root: Option<Box<Node<K, V>>>,
+    len: usize,
+}
+
+impl<K, V, const B: usize> Default for BTreeMap<K, V, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, const B: usize> BTreeMap<K, V, B> {
+    /// Constructs a new, empty map.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let m: BTreeMap<i32, &str, 3> = BTreeMap::new();
+    /// assert!(m.is_empty());
+    /// ```
+    pub fn new() -> Self {
+        const { assert!(B >= 2, "BTreeMap needs a minimum degree of at least 2") };
+        Self { root: None, len: 0 }
+    }
+
+    /// Returns the number of entries in the map.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let mut m: BTreeMap<_, _> = BTreeMap::new();
+    /// assert_eq!(m.len(), 0);
+    /// m.insert(1, "a");
+    /// assert_eq!(m.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the map contains no entries.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let mut m: BTreeMap<_, _> = BTreeMap::new();
+    /// assert!(m.is_empty());
+    /// m.insert(1, "a");
+    /// assert!(!m.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes every entry from the map.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let mut m: BTreeMap<_, _> = BTreeMap::new();
+    /// m.insert(1, "a");
+    /// m.clear();
+    /// assert!(m.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    /// Returns the entry with the smallest key.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let mut m: BTreeMap<_, _> = BTreeMap::new();
+    /// m.insert(2, "b");
+    /// m.insert(1, "a");
+    /// assert_eq!(m.first_key_value(), Some((&1, &"a")));
+    /// ```
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(child) = node.children.first() {
+            node = child;
+        }
+        Some((node.keys.first()?, node.values.first()?))
+    }
+
+    /// Returns the entry with the largest key.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let mut m: BTreeMap<_, _> = BTreeMap::new();
+    /// m.insert(2, "b");
+    /// m.insert(1, "a");
+    /// assert_eq!(m.last_key_value(), Some((&2, &"b")));
+    /// ```
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(child) = node.children.last() {
+            node = child;
+        }
+        Some((node.keys.last()?, node.values.last()?))
+    }
+
+    /// Returns an iterator over the entries of the map, in ascending key order.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let m: BTreeMap<_, _, 2> = [(3, "c"), (1, "a"), (2, "b")].into_iter().collect();
+    /// let keys: Vec<_> = m.iter().map(|(k, _)| *k).collect();
+    /// assert_eq!(keys, vec![1, 2, 3]);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut stack = Vec::new();
+        if let Some(root) = self.root.as_deref() {
+            push_leftmost(&mut stack, root);
+        }
+        Iter {
+            stack,
+            remaining: self.len,
+        }
+    }
+
+    /// Returns an iterator over the keys of the map, in ascending order.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let mut m: BTreeMap<_, _> = BTreeMap::new();
+    /// m.insert(2, "b");
+    /// m.insert(1, "a");
+    /// assert_eq!(m.keys().collect::<Vec<_>>(), vec![&1, &2]);
+    /// ```
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values of the map, in ascending key order.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let mut m: BTreeMap<_, _> = BTreeMap::new();
+    /// m.insert(2, "b");
+    /// m.insert(1, "a");
+    /// assert_eq!(m.values().collect::<Vec<_>>(), vec![&"a", &"b"]);
+    /// ```
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+}
+
+impl<K: Ord, V, const B: usize> BTreeMap<K, V, B> {
+    /// Inserts a key-value pair into the map. If the key was already present, its value is replaced and the old
+    /// value is returned.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let mut m: BTreeMap<_, _> = BTreeMap::new();
+    /// assert_eq!(m.insert(1, "a"), None);
+    /// assert_eq!(m.insert(1, "b"), Some("a"));
+    /// assert_eq!(m.get(&1), Some(&"b"));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let root = self.root.get_or_insert_with(|| Box::new(Node::new()));
+        if root.keys.len() == 2 * B - 1 {
+            let old = std::mem::replace(root, Box::new(Node::new()));
+            root.children.push(*old);
+            Self::split_child(root, 0);
+        }
+        let old = Self::insert_non_full(root, key, value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Returns a reference to the value stored under the key.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let mut m: BTreeMap<_, _> = BTreeMap::new();
+    /// m.insert(1, "a");
+    /// assert_eq!(m.get(&1), Some(&"a"));
+    /// assert_eq!(m.get(&2), None);
+    /// ```
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut node = self.root.as_deref()?;
+        loop {
+            match node.keys.binary_search(key) {
+                Ok(i) => return Some(&node.values[i]),
+                Err(i) => node = node.children.get(i)?,
+            }
+        }
+    }
+
+    /// Returns a mutable reference to the value stored under the key.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let mut m: BTreeMap<_, _> = BTreeMap::new();
+    /// m.insert(1, 10);
+    /// *m.get_mut(&1).unwrap() += 5;
+    /// assert_eq!(m.get(&1), Some(&15));
+    /// ```
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut node = self.root.as_deref_mut()?;
+        loop {
+            match node.keys.binary_search(key) {
+                Ok(i) => return Some(&mut node.values[i]),
+                Err(i) => node = node.children.get_mut(i)?,
+            }
+        }
+    }
+
+    /// Returns whether the map contains the key.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let mut m: BTreeMap<_, _> = BTreeMap::new();
+    /// m.insert(1, "a");
+    /// assert!(m.contains_key(&1));
+    /// assert!(!m.contains_key(&2));
+    /// ```
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes the key from the map, returning its value if it was present.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let mut m: BTreeMap<_, _> = BTreeMap::new();
+    /// m.insert(1, "a");
+    /// assert_eq!(m.remove(&1), Some("a"));
+    /// assert_eq!(m.remove(&1), None);
+    /// ```
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let root = self.root.as_deref_mut()?;
+        let removed = Self::remove_from(root, key);
+        if root.keys.is_empty() {
+            self.root = self.root.take().and_then(|mut r| r.children.pop().map(Box::new));
+        }
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed.map(|(_, v)| v)
+    }
+
+    /// Splits the map in two at the key. Returns a map with every entry whose key is greater than or equal to it,
+    /// leaving the smaller ones in `self`.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let mut m: BTreeMap<_, _, 2> = (0..20).map(|i| (i, i * 10)).collect();
+    /// let high = m.split_off(&15);
+    /// assert_eq!(m.len(), 15);
+    /// assert_eq!(high.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec![15, 16, 17, 18, 19]);
+    /// assert!(m.check_invariants().is_ok() && high.check_invariants().is_ok());
+    /// ```
+    pub fn split_off(&mut self, key: &K) -> Self {
+        let mut other = Self::new();
+        while let Some((last, _)) = self.last_key_value() {
+            if last < key {
+                break;
+            }
+            let root = self.root.as_deref_mut().expect("non-empty map");
+            let (k, v) = Self::remove_last(root);
+            if root.keys.is_empty() {
+                self.root = self.root.take().and_then(|mut r| r.children.pop().map(Box::new));
+            }
+            self.len -= 1;
+            other.insert(k, v);
+        }
+        other
+    }
+
+    /// Moves every entry of `other` into `self`, leaving `other` empty. Values from `other` replace the ones already
+    /// stored under the same key.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let mut a: BTreeMap<_, _> = [(1, "a"), (2, "b")].into_iter().collect();
+    /// let mut b: BTreeMap<_, _> = [(2, "x"), (3, "c")].into_iter().collect();
+    /// a.append(&mut b);
+    /// assert!(b.is_empty());
+    /// assert_eq!(a.iter().collect::<Vec<_>>(), vec![(&1, &"a"), (&2, &"x"), (&3, &"c")]);
+    /// ```
+    pub fn append(&mut self, other: &mut Self) {
+        for (k, v) in std::mem::take(other) {
+            self.insert(k, v);
+        }
+    }
+
+    /// Returns an iterator over the entries whose keys fall within the range, in ascending key order.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let m: BTreeMap<_, _, 2> = (0..20).map(|i| (i, i * 10)).collect();
+    /// let keys: Vec<_> = m.range(5..9).map(|(k, _)| *k).collect();
+    /// assert_eq!(keys, vec![5, 6, 7, 8]);
+    /// let values: Vec<_> = m.range(17..).map(|(_, v)| *v).collect();
+    /// assert_eq!(values, vec![170, 180, 190]);
+    /// assert_eq!(m.range(..=1).count(), 2);
+    /// ```
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V, R> {
+        let mut stack = Vec::new();
+        let mut cur = self.root.as_deref();
+        while let Some(node) = cur {
+            let i = match range.start_bound() {
+                Bound::Included(start) => node.keys.partition_point(|k| k < start),
+                Bound::Excluded(start) => node.keys.partition_point(|k| k <= start),
+                Bound::Unbounded => 0,
+            };
+            stack.push((node, i));
+            cur = node.children.get(i);
+        }
+        Range { stack, range }
+    }
+
+    /// Verifies the B-tree invariants: every node's key count is within bounds, internal nodes have one more child
+    /// than keys, all leaves are at the same depth, and keys are in ascending order. Returns the height of the tree.
+    ///
+    /// This is mostly useful in tests and benchmarks that want to make sure splitting and merging kept the tree valid.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let mut m: BTreeMap<_, _, 2> = BTreeMap::new();
+    /// for i in 0..100 {
+    ///     m.insert(i, i);
+    /// }
+    /// for i in (0..100).step_by(3) {
+    ///     m.remove(&i);
+    /// }
+    /// assert!(m.check_invariants().is_ok());
+    /// ```
+    pub fn check_invariants(&self) -> Result<usize, BTreeError> {
+        let height = match self.root.as_deref() {
+            Some(root) => Self::check_node(root, true)?,
+            None => 0,
+        };
+
+        let mut prev: Option<&K> = None;
+        for (k, _) in self.iter() {
+            if prev.is_some_and(|p| p >= k) {
+                return Err(BTreeError::Unordered);
+            }
+            prev = Some(k);
+        }
+        Ok(height)
+    }
+
+    fn check_node(node: &Node<K, V>, is_root: bool) -> Result<usize, BTreeError> {
+        let n = node.keys.len();
+        if n > 2 * B - 1 || (!is_root && n < B - 1) || node.values.len() != n {
+            return Err(BTreeError::BadKeyCount);
+        }
+        if node.is_leaf() {
+            return Ok(1);
+        }
+        if node.children.len() != n + 1 {
+            return Err(BTreeError::BadChildCount);
+        }
+        let mut height = None;
+        for child in &node.children {
+            let h = Self::check_node(child, false)?;
+            if height.is_some_and(|height| height != h) {
+                return Err(BTreeError::UnevenLeaves);
+            }
+            height = Some(h);
+        }
+        Ok(height.unwrap_or(0) + 1)
+    }
+
+    /// Splits the full child at index `i` around its median key, which moves up into `parent`.
+    fn split_child(parent: &mut Node<K, V>, i: usize) {
+        viz::record(|| Event::Split {
+            structure: "BTreeMap",
+            level: parent.children[i].level(),
+        });
+        let child = &mut parent.children[i];
+        let mut right = Node::new();
+        right.keys = child.keys.split_off(B);
+        right.values = child.values.split_off(B);
+        if !child.is_leaf() {
+            right.children = child.children.split_off(B);
+        }
+        let key = child.keys.pop().expect("split of a non-full node");
+        let value = child.values.pop().expect("split of a non-full node");
+        parent.keys.insert(i, key);
+        parent.values.insert(i, value);
+        parent.children.insert(i + 1, right);
+    }
+
+    fn insert_non_full(mut node: &mut Node<K, V>, key: K, value: V) -> Option<V> {
+        loop {
+            let mut i = match node.keys.binary_search(&key) {
+                Ok(i) => return Some(std::mem::replace(&mut node.values[i], value)),
+                Err(i) => i,
+            };
+            if node.is_leaf() {
+                node.keys.insert(i, key);
+                node.values.insert(i, value);
+                return None;
+            }
+            if node.children[i].keys.len() == 2 * B - 1 {
+                Self::split_child(node, i);
+                match key.cmp(&node.keys[i]) {
+                    std::cmp::Ordering::Equal => {
+                        return Some(std::mem::replace(&mut node.values[i], value))
+                    }
+                    std::cmp::Ordering::Greater => i += 1,
+                    std::cmp::Ordering::Less => {}
+                }
+            }
+            node = &mut node.children[i];
+        }
+    }
+
+    /// Removes the key from the subtree rooted at `node`. Every node the search descends into is first topped up to
+    /// at least `B` keys, so removing from it can never underflow.
+    fn remove_from(mut node: &mut Node<K, V>, key: &K) -> Option<(K, V)> {
+        loop {
+            match node.keys.binary_search(key) {
+                Ok(i) if node.is_leaf() => {
+                    return Some((node.keys.remove(i), node.values.remove(i)));
+                }
+                Ok(i) => {
+                    if node.children[i].keys.len() >= B {
+                        let (k, v) = Self::remove_last(&mut node.children[i]);
+                        let k = std::mem::replace(&mut node.keys[i], k);
+                        let v = std::mem::replace(&mut node.values[i], v);
+                        return Some((k, v));
+                    }
+                    if node.children[i + 1].keys.len() >= B {
+                        let (k, v) = Self::remove_first(&mut node.children[i + 1]);
+                        let k = std::mem::replace(&mut node.keys[i], k);
+                        let v = std::mem::replace(&mut node.values[i], v);
+                        return Some((k, v));
+                    }
+                    Self::merge_children(node, i);
+                    node = &mut node.children[i];
+                }
+                Err(_) if node.is_leaf() => return None,
+                Err(i) => {
+                    let i = Self::fill_child(node, i);
+                    node = &mut node.children[i];
+                }
+            }
+        }
+    }
+
+    fn remove_first(mut node: &mut Node<K, V>) -> (K, V) {
+        while !node.is_leaf() {
+            let i = Self::fill_child(node, 0);
+            node = &mut node.children[i];
+        }
+        (node.keys.remove(0), node.values.remove(0))
+    }
+
+    fn remove_last(mut node: &mut Node<K, V>) -> (K, V) {
+        while !node.is_leaf() {
+            let i = Self::fill_child(node, node.children.len() - 1);
+            node = &mut node.children[i];
+        }
+        let k = node.keys.pop().expect("non-root leaf without keys");
+        let v = node.values.pop().expect("non-root leaf without keys");
+        (k, v)
+    }
+
+    /// Makes sure the child at index `i` has at least `B` keys, borrowing from a sibling or merging with one.
+    /// Returns the index of the child that now covers the same key range.
+    fn fill_child(node: &mut Node<K, V>, i: usize) -> usize {
+        if node.children[i].keys.len() >= B {
+            return i;
+        }
+        if i > 0 && node.children[i - 1].keys.len() >= B {
+            let (left, right) = node.children.split_at_mut(i);
+            let left = &mut left[i - 1];
+            let right = &mut right[0];
+            let k = left.keys.pop().expect("sibling with keys");
+            let v = left.values.pop().expect("sibling with keys");
+            right.keys.insert(0, std::mem::replace(&mut node.keys[i - 1], k));
+            right.values.insert(0, std::mem::replace(&mut node.values[i - 1], v));
+            if let Some(c) = left.children.pop() {
+                right.children.insert(0, c);
+            }
+            return i;
+        }
+        if i + 1 < node.children.len() && node.children[i + 1].keys.len() >= B {
+            let (left, right) = node.children.split_at_mut(i + 1);
+            let left = &mut left[i];
+            let right = &mut right[0];
+            let k = right.keys.remove(0);
+            let v = right.values.remove(0);
+            left.keys.push(std::mem::replace(&mut node.keys[i], k));
+            left.values.push(std::mem::replace(&mut node.values[i], v));
+            if !right.is_leaf() {
+                left.children.push(right.children.remove(0));
+            }
+            return i;
+        }
+        if i + 1 < node.children.len() {
+            Self::merge_children(node, i);
+            i
+        } else {
+            Self::merge_children(node, i - 1);
+            i - 1
+        }
+    }
+
+    /// Merges the child at `i + 1` and the separating key into the child at `i`.
+    fn merge_children(node: &mut Node<K, V>, i: usize) {
+        viz::record(|| Event::Merge {
+            structure: "BTreeMap",
+            level: node.children[i].level(),
+        });
+        let right = node.children.remove(i + 1);
+        let k = node.keys.remove(i);
+        let v = node.values.remove(i);
+        let left = &mut node.children[i];
+        left.keys.push(k);
+        left.values.push(v);
+        left.keys.extend(right.keys);
+        left.values.extend(right.values);
+        left.children.extend(right.children);
+    }
+}
+
+impl<K: Ord, V, const B: usize> FromIterator<(K, V)> for BTreeMap<K, V, B> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (k, v) in iter {
+            map.insert(k, v);
+        }
+        map
+    }
+}
+
+impl<K: Ord + Codec, V: Codec, const B: usize> Snapshot for BTreeMap<K, V, B> {
+    const TAG: [u8; 4] = *b"BTRM";
+
+    fn write_payload<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        snapshot::write_entries(w, self.len(), self.iter())
+    }
+
+    fn read_payload<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let mut map = Self::new();
+        snapshot::read_entries(r, |k, v| map.insert(k, v))?;
+        Ok(map)
+    }
+}
+
+impl<K: PartialEq, V: PartialEq, const B: usize> PartialEq for BTreeMap<K, V, B> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<K: Eq, V: Eq, const B: usize> Eq for BTreeMap<K, V, B> {}
+
+impl<K: fmt::Debug, V: fmt::Debug, const B: usize> fmt::Debug for BTreeMap<K, V, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, const B: usize> IntoIterator for BTreeMap<K, V, B> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    /// Consumes the map and returns its entries in ascending key order.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let m: BTreeMap<_, _, 2> = [(2, "b"), (1, "a"), (3, "c")].into_iter().collect();
+    /// assert_eq!(m.into_iter().collect::<Vec<_>>(), vec![(1, "a"), (2, "b"), (3, "c")]);
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        let mut entries = Vec::with_capacity(self.len);
+        if let Some(root) = self.root {
+            flatten(*root, &mut entries);
+        }
+        IntoIter {
+            entries: entries.into_iter(),
+        }
+    }
+}
+
+fn flatten<K, V>(node: Node<K, V>, out: &mut Vec<(K, V)>) {
+    let mut children = node.children.into_iter();
+    for entry in node.keys.into_iter().zip(node.values) {
+        if let Some(child) = children.next() {
+            flatten(child, out);
+        }
+        out.push(entry);
+    }
+    if let Some(child) = children.next() {
+        flatten(child, out);
+    }
+}
+
+fn push_leftmost<'a, K, V>(stack: &mut Vec<(&'a Node<K, V>, usize)>, mut node: &'a Node<K, V>) {
+    loop {
+        stack.push((node, 0));
+        match node.children.first() {
+            Some(child) => node = child,
+            None => return,
+        }
+    }
+}
+
+/// Pops the next in-order entry off a stack of `(node, next key index)` frames. Descending into child `idx + 1`, it
+/// prefetches the entries of its right sibling, child `idx + 2`, which is scanned after the child's subtree and the key
+/// between them. The child itself is read right away, so prefetching it would come too late to help.
+fn advance<'a, K, V>(stack: &mut Vec<(&'a Node<K, V>, usize)>) -> Option<(&'a K, &'a V)> {
+    loop {
+        let (node, i) = stack.last_mut()?;
+        let node: &'a Node<K, V> = node;
+        if *i < node.keys.len() {
+            let idx = *i;
+            *i += 1;
+            if let Some(child) = node.children.get(idx + 1) {
+                if let Some(next) = node.children.get(idx + 2) {
+                    prefetch(next.keys.as_ptr());
+                    prefetch(next.values.as_ptr());
+                }
+                push_leftmost(stack, child);
+            }
+            return Some((&node.keys[idx], &node.values[idx]));
+        }
+        stack.pop();
+    }
+}
+
+/// In-order iterator over the entries of a [`BTreeMap`].
+pub struct Iter<'a, K, V> {
+    stack: Vec<(&'a Node<K, V>, usize)>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = advance(&mut self.stack)?;
+        self.remaining -= 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V, const B: usize> IntoIterator for &'a BTreeMap<K, V, B> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Entries handed to the closure of [for_each_batch()](`Range::for_each_batch()`) at a time.
+const BATCH_LEN: usize = 64;
+
+/// Iterator over a key range of a [`BTreeMap`], created by [range()](`BTreeMap::range()`).
+///
+/// The iterator prefetches the next node to scan when it moves to a new one, if the `simd` feature is enabled. Long
+/// scans are faster still with [for_each_batch()](`Self::for_each_batch()`).
+pub struct Range<'a, K, V, R> {
+    stack: Vec<(&'a Node<K, V>, usize)>,
+    range: R,
+}
+
+impl<'a, K: Ord, V, R: RangeBounds<K>> Range<'a, K, V, R> {
+    /// Calls the closure on the remaining entries in batches of up to 64, in ascending key order. The entries of a
+    /// leaf are copied into the batch together, after a binary search for the end of the range, rather than stepped
+    /// through and checked one at a time, and the closure can process the batch in a tight loop.
+    /// ```
+    /// # use strctr::btree::BTreeMap;
+    /// let prices: BTreeMap<u32, f64> = (0..1000).map(|t| (t, t as f64 * 0.5)).collect();
+    /// let (mut total, mut batches) = (0.0, 0);
+    /// prices.range(100..900).for_each_batch(|batch| {
+    ///     assert!(batch.len() <= 64);
+    ///     total += batch.iter().map(|(_, p)| **p).sum::<f64>();
+    ///     batches += 1;
+    /// });
+    /// assert_eq!(total, (100..900).map(|t| t as f64 * 0.5).sum::<f64>());
+    /// assert_eq!(batches, 13);
+    /// ```
+    pub fn for_each_batch(self, mut f: impl FnMut(&[(&'a K, &'a V)])) {
+        let Range { mut stack, range } = self;
+        let mut batch = Vec::with_capacity(BATCH_LEN);
+        let mut push = |batch: &mut Vec<_>, entry| {
+            batch.push(entry);
+            if batch.len() == BATCH_LEN {
+                f(batch);
+                batch.clear();
+            }
+        };
+        while let Some(&(node, i)) = stack.last() {
+            if node.is_leaf() {
+                let end = i + node.keys[i..].partition_point(|k| below_end(&range, k));
+                for j in i..end {
+                    push(&mut batch, (&node.keys[j], &node.values[j]));
+                }
+                if end < node.keys.len() {
+                    break;
+                }
+                stack.pop();
+                continue;
+            }
+            match advance(&mut stack) {
+                Some((k, v)) if below_end(&range, k) => push(&mut batch, (k, v)),
+                _ => break,
+            }
+        }
+        if !batch.is_empty() {
+            f(&batch);
+        }
+    }
+}
+
+impl<'a, K: Ord, V, R: RangeBounds<K>> Iterator for Range<'a, K, V, R> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (k, v) = advance(&mut self.stack)?;
+        if !below_end(&self.range, k) {
+            self.stack.clear();
+            return None;
+        }
+        Some((k, v))
+    }
+}
+
+/// Returns whether the key is not past the end of the range.
+fn below_end<K: Ord>(range: &impl RangeBounds<K>, k: &K) -> bool {
+    match range.end_bound() {
+        Bound::Included(end) => k <= end,
+        Bound::Excluded(end) => k < end,
+        Bound::Unbounded => true,
+    }
+}
+
+/// Owning iterator over the entries of a [`BTreeMap`], in ascending key order.
+pub struct IntoIter<K, V> {
+    entries: std::vec::IntoIter<(K, V)>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
@@ -0,0 +1,260 @@
+//! Pool for recycling byte buffers across threads.
+//!
+//! Buffers are sorted into size classes, powers of two between a minimum and a maximum size. Every thread returns
+//! buffers to a stack of its own slot and takes them from there first, so the common case touches no lock any other
+//! thread is likely to hold. When a slot's stack is full, buffers overflow into a list shared by all threads, and an
+//! empty stack refills from that list in batches. Only when both are empty is a new buffer allocated.
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::PoisonError;
+
+use crate::align::CachePadded;
+use crate::sharded_counter::ShardedCounter;
+use crate::sync::{default_shards, thread_slot, Mutex, MutexGuard};
+
+/// Per-thread stacks, one per size class.
+type Stacks = Vec<Vec<Vec<u8>>>;
+
+/// A thread-safe pool of `Vec<u8>` buffers.
+/// ```
+/// # use strctr::buffer_pool::BufferPool;
+/// let pool = BufferPool::new();
+/// std::thread::scope(|s| {
+///     for _ in 0..4 {
+///         s.spawn(|| {
+///             for _ in 0..100 {
+///                 let mut buf = pool.acquire(1500);
+///                 buf.extend_from_slice(b"packet");
+///             }
+///         });
+///     }
+/// });
+/// // Every thread allocated once and recycled from then on.
+/// assert!(pool.stats().allocations <= 4);
+/// ```
+pub struct BufferPool {
+    /// Buffer capacity of every size class, ascending.
+    classes: Box<[usize]>,
+    locals: Box<[CachePadded<Mutex<Stacks>>]>,
+    shared: Box<[Mutex<Vec<Vec<u8>>>]>,
+    local_capacity: usize,
+    shared_capacity: usize,
+    local_hits: ShardedCounter,
+    shared_hits: ShardedCounter,
+    allocations: ShardedCounter,
+}
+
+/// How a [`BufferPool`] satisfied its requests, returned by [stats()](`BufferPool::stats()`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Requests served from the requesting thread's own stack.
+    pub local_hits: u64,
+    /// Requests served from the shared overflow list.
+    pub shared_hits: u64,
+    /// Requests that needed a new buffer.
+    pub allocations: u64,
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BufferPool {
+    /// Constructs a new, empty pool with size classes from 256 bytes to 64 KiB, keeping up to 32 buffers per class in
+    /// every thread's stack and up to 1024 per class in the shared list.
+    pub fn new() -> Self {
+        Self::with_limits(256, 64 * 1024, 32, 1024)
+    }
+
+    /// Constructs a new, empty pool. Size classes are the powers of two from `min_size` to `max_size`, both rounded
+    /// up to a power of two. Every thread's stack keeps up to `local_capacity` buffers per class, and the shared list
+    /// up to `shared_capacity`; buffers beyond that are freed.
+    ///
+    /// Panics if `min_size` is 0 or larger than `max_size`.
+    pub fn with_limits(
+        min_size: usize,
+        max_size: usize,
+        local_capacity: usize,
+        shared_capacity: usize,
+    ) -> Self {
+        if min_size == 0 || min_size > max_size {
+            panic!(
+                "InvalidCapacity: Size classes {}..={} are empty",
+                min_size, max_size
+            );
+        }
+        let classes: Box<[usize]> =
+            std::iter::successors(Some(min_size.next_power_of_two()), |&size| {
+                size.checked_mul(2)
+            })
+            .take_while(|&size| size <= max_size.next_power_of_two())
+            .collect();
+        Self {
+            locals: (0..default_shards())
+                .map(|_| CachePadded::new(Mutex::new(vec![Vec::new(); classes.len()])))
+                .collect(),
+            shared: classes.iter().map(|_| Mutex::new(Vec::new())).collect(),
+            classes,
+            local_capacity,
+            shared_capacity,
+            local_hits: ShardedCounter::new(),
+            shared_hits: ShardedCounter::new(),
+            allocations: ShardedCounter::new(),
+        }
+    }
+
+    /// Returns the buffer capacities of the size classes, ascending.
+    pub fn size_classes(&self) -> &[usize] {
+        &self.classes
+    }
+
+    /// Returns an empty buffer with room for at least `size` bytes. It goes back to the pool when dropped.
+    pub fn acquire(&self, size: usize) -> PooledBuffer<'_> {
+        PooledBuffer {
+            pool: self,
+            buf: self.take(size),
+        }
+    }
+
+    /// Returns an empty buffer with room for at least `size` bytes, owned by the caller. Hand it to
+    /// [recycle()](`Self::recycle()`) to return it, from any thread.
+    ///
+    /// Requests above the largest size class are always allocated fresh.
+    /// ```
+    /// # use strctr::buffer_pool::BufferPool;
+    /// let pool = BufferPool::with_limits(64, 1024, 4, 16);
+    /// let buf = pool.take(100);
+    /// assert_eq!(buf.capacity(), 128);
+    /// pool.recycle(buf);
+    /// let again = pool.take(80);
+    /// assert_eq!(again.capacity(), 128);
+    /// assert_eq!(pool.stats().local_hits, 1);
+    /// ```
+    pub fn take(&self, size: usize) -> Vec<u8> {
+        let Some(class) = self.classes.iter().position(|&c| c >= size) else {
+            self.allocations.increment();
+            return Vec::with_capacity(size);
+        };
+        let mut locals = self.local();
+        if let Some(buf) = locals[class].pop() {
+            self.local_hits.increment();
+            return buf;
+        }
+        let mut shared = lock(&self.shared[class]);
+        if let Some(buf) = shared.pop() {
+            // Refill half the stack, so the next requests stay local.
+            let batch = shared.len().min(self.local_capacity / 2);
+            let from = shared.len() - batch;
+            locals[class].extend(shared.drain(from..));
+            self.shared_hits.increment();
+            return buf;
+        }
+        self.allocations.increment();
+        Vec::with_capacity(self.classes[class])
+    }
+
+    /// Returns a buffer to the pool. It is filed under the largest size class it can serve; buffers smaller than the
+    /// smallest class, larger than twice the largest, or arriving when there is no room left are freed.
+    pub fn recycle(&self, mut buf: Vec<u8>) {
+        let capacity = buf.capacity();
+        let largest = self.classes[self.classes.len() - 1];
+        if capacity > largest.saturating_mul(2) {
+            return;
+        }
+        let Some(class) = self.classes.iter().rposition(|&c| c <= capacity) else {
+            return;
+        };
+        buf.clear();
+        let mut locals = self.local();
+        if locals[class].len() < self.local_capacity {
+            locals[class].push(buf);
+            return;
+        }
+        drop(locals);
+        let mut shared = lock(&self.shared[class]);
+        if shared.len() < self.shared_capacity {
+            shared.push(buf);
+        }
+    }
+
+    /// Returns how requests were satisfied so far.
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            local_hits: self.local_hits.sum(),
+            shared_hits: self.shared_hits.sum(),
+            allocations: self.allocations.sum(),
+        }
+    }
+
+    /// Frees all pooled buffers.
+    pub fn clear(&self) {
+        for local in self.locals.iter() {
+            lock(local).iter_mut().for_each(Vec::clear);
+        }
+        for shared in self.shared.iter() {
+            lock(shared).clear();
+        }
+    }
+
+    fn local(&self) -> MutexGuard<'_, Stacks> {
+        lock(&self.locals[thread_slot(self.locals.len())])
+    }
+}
+
+/// A pool's data stays consistent even if a thread panicked while holding a lock.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl fmt::Debug for BufferPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufferPool")
+            .field("size_classes", &self.classes)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// A buffer borrowed from a [`BufferPool`], created by [acquire()](`BufferPool::acquire()`). It dereferences to the
+/// `Vec<u8>` and goes back to the pool when dropped.
+pub struct PooledBuffer<'a> {
+    pool: &'a BufferPool,
+    buf: Vec<u8>,
+}
+
+impl PooledBuffer<'_> {
+    /// Takes the buffer out of the pool's care. It is not recycled when dropped.
+    pub fn into_inner(mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buf)
+    }
+}
+
+impl Deref for PooledBuffer<'_> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.buf
+    }
+}
+
+impl DerefMut for PooledBuffer<'_> {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buf
+    }
+}
+
+impl Drop for PooledBuffer<'_> {
+    fn drop(&mut self) {
+        // After into_inner(), this is an empty Vec without capacity, which recycle() frees.
+        self.pool.recycle(std::mem::take(&mut self.buf));
+    }
+}
+
+impl fmt::Debug for PooledBuffer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.buf, f)
+    }
+}
@@ -0,0 +1,689 @@
+//! Caches beyond plain least-recently-used ones. [`TtlCache`] gives every entry a time to live and, when full, evicts
+//! by an [`Eviction`] policy: the least recently or the least frequently used entry. [`BudgetCache`] bounds the total
+//! size of its entries, like their bytes, and evicts by size, cost and use.
+//!
+//! Time is read from a [`Clock`]. The cache uses the [`SystemClock`] by default, and a [`ManualClock`] lets tests move
+//! time forward by hand instead of sleeping.
+
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A source of the current time.
+pub trait Clock {
+    /// Returns the current time. It must never go backwards.
+    fn now(&self) -> Instant;
+}
+
+/// The monotonic system clock, [`Instant::now()`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that stands still until advanced. Clones share the same time, so a test can keep one and advance the
+/// clone held by a cache.
+/// ```
+/// # use std::time::Duration;
+/// # use strctr::cache::{Clock, ManualClock};
+/// let clock = ManualClock::new();
+/// let start = clock.now();
+/// clock.clone().advance(Duration::from_secs(5));
+/// assert_eq!(clock.now() - start, Duration::from_secs(5));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    start: Instant,
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Constructs a clock stopped at the current time.
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            nanos: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Moves the clock forward.
+    ///
+    /// Panics if the clock would run more than 584 years past its start.
+    pub fn advance(&self, by: Duration) {
+        let advanced = u64::try_from(by.as_nanos()).ok().and_then(|by| {
+            self.nanos
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |nanos| {
+                    nanos.checked_add(by)
+                })
+                .ok()
+        });
+        if advanced.is_none() {
+            panic!("Overflow: ManualClock can only run for 584 years");
+        }
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+/// Which entry a full [`TtlCache`] evicts when a new key comes in, after any expired ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Eviction {
+    /// The least recently used entry.
+    #[default]
+    Lru,
+    /// The least frequently used entry, and of those the least recently used. Insertions and lookups count as uses.
+    Lfu,
+}
+
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    expires: Option<Instant>,
+    uses: u64,
+    last_use: u64,
+}
+
+/// A cache holding at most `capacity` entries, each of which expires after its time to live.
+///
+/// Expired entries are never returned. They are dropped when looked up, when room is needed for a new key, or all at
+/// once by [evict_expired()](`Self::evict_expired()`); until then they count towards [len()](`Self::len()`). When a
+/// full cache has no expired entries, it evicts one by its [`Eviction`] policy.
+///
+/// Entries are kept ordered by use and by expiry, so every operation takes `O(log n)`.
+/// ```
+/// # use std::time::Duration;
+/// # use strctr::cache::{Eviction, ManualClock, TtlCache};
+/// let clock = ManualClock::new();
+/// let mut tokens = TtlCache::with_clock(100, Eviction::Lru, clock.clone()).with_ttl(Duration::from_secs(60));
+/// tokens.insert("alice", "t0k3n");
+/// tokens.insert_with_ttl("bob", "s3cr3t", Duration::from_secs(300));
+///
+/// clock.advance(Duration::from_secs(90));
+/// assert_eq!(tokens.get(&"alice"), None);
+/// assert_eq!(tokens.get(&"bob"), Some(&"s3cr3t"));
+/// ```
+pub struct TtlCache<K, V, C = SystemClock> {
+    entries: Vec<Option<Entry<K, V>>>,
+    free: Vec<usize>,
+    map: HashMap<K, usize>,
+    /// Live entries by (uses, last use, slot), with uses always 0 under LRU, so the first one is evicted next.
+    order: BTreeSet<(u64, u64, usize)>,
+    /// Entries with a time to live by (expiry, slot).
+    expiries: BTreeSet<(Instant, usize)>,
+    ttl: Option<Duration>,
+    eviction: Eviction,
+    clock: C,
+    /// Counts uses to order them.
+    tick: u64,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> TtlCache<K, V> {
+    /// Constructs a new, empty cache holding at most `capacity` entries, which reads the [`SystemClock`]. Entries
+    /// never expire unless given a time to live.
+    ///
+    /// Panics if the capacity is 0.
+    pub fn new(capacity: usize, eviction: Eviction) -> Self {
+        Self::with_clock(capacity, eviction, SystemClock)
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, C: Clock> TtlCache<K, V, C> {
+    /// Constructs a new, empty cache holding at most `capacity` entries, which reads the clock.
+    ///
+    /// Panics if the capacity is 0.
+    pub fn with_clock(capacity: usize, eviction: Eviction, clock: C) -> Self {
+        if capacity == 0 {
+            panic!("InvalidCapacity: TtlCache needs room for at least one entry");
+        }
+        Self {
+            entries: Vec::new(),
+            free: Vec::new(),
+            map: HashMap::new(),
+            order: BTreeSet::new(),
+            expiries: BTreeSet::new(),
+            ttl: None,
+            eviction,
+            clock,
+            tick: 0,
+            capacity,
+        }
+    }
+
+    /// Sets the time to live of entries added by [insert()](`Self::insert()`). Entries whose expiry would not fit into
+    /// an [`Instant`] never expire.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Returns the maximum number of entries.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of entries, including expired ones that were not dropped yet.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns whether the cache contains no entries, including expired ones.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the eviction policy.
+    pub fn eviction(&self) -> Eviction {
+        self.eviction
+    }
+
+    /// Returns the time to live of entries added by [insert()](`Self::insert()`), if they expire.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Returns the clock.
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Inserts a key-value pair with the cache's time to live, and counts it as a use. Returns the previous value if
+    /// the key was present and not expired; otherwise a full cache drops its expired entries or evicts one.
+    /// ```
+    /// # use strctr::cache::{Eviction, TtlCache};
+    /// let mut c = TtlCache::new(2, Eviction::Lfu);
+    /// c.insert("a", 1);
+    /// c.insert("b", 2);
+    /// c.get(&"b");
+    /// c.get(&"a");
+    /// c.get(&"a");
+    /// c.insert("c", 3);
+    /// assert!(c.contains_key(&"a"));
+    /// assert!(!c.contains_key(&"b"));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let expires = self.ttl.and_then(|ttl| self.clock.now().checked_add(ttl));
+        self.insert_until(key, value, expires)
+    }
+
+    /// Inserts a key-value pair that expires after `ttl`, like [insert()](`Self::insert()`). A time to live too long
+    /// to represent as an [`Instant`] never expires.
+    /// ```
+    /// # use std::time::Duration;
+    /// # use strctr::cache::{Eviction, TtlCache};
+    /// let mut c = TtlCache::new(2, Eviction::Lru);
+    /// c.insert_with_ttl(1, "a", Duration::MAX);
+    /// assert_eq!(c.time_to_live(&1), None);
+    /// assert!(c.contains_key(&1));
+    /// ```
+    pub fn insert_with_ttl(&mut self, key: K, value: V, ttl: Duration) -> Option<V> {
+        let expires = self.clock.now().checked_add(ttl);
+        self.insert_until(key, value, expires)
+    }
+
+    fn insert_until(&mut self, key: K, value: V, expires: Option<Instant>) -> Option<V> {
+        let now = self.clock.now();
+        if let Some(&i) = self.map.get(&key) {
+            let expired = self.is_expired(i, now);
+            self.set_expiry(i, expires);
+            self.touch(i);
+            let old = std::mem::replace(&mut self.entry_mut(i).value, value);
+            return (!expired).then_some(old);
+        }
+        if self.map.len() == self.capacity {
+            self.drop_expired(now);
+        }
+        if self.map.len() == self.capacity {
+            self.pop_evictable();
+        }
+        let entry = Entry {
+            key: key.clone(),
+            value,
+            expires: None,
+            uses: 0,
+            last_use: 0,
+        };
+        let i = match self.free.pop() {
+            Some(i) => {
+                self.entries[i] = Some(entry);
+                i
+            }
+            None => {
+                self.entries.push(Some(entry));
+                self.entries.len() - 1
+            }
+        };
+        self.set_expiry(i, expires);
+        self.touch(i);
+        self.map.insert(key, i);
+        None
+    }
+
+    /// Returns the value of the key and counts it as a use, or drops the entry if it expired.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let i = self.live_slot(key)?;
+        self.touch(i);
+        Some(&self.entry(i).value)
+    }
+
+    /// Returns the value of the key mutably and counts it as a use, or drops the entry if it expired.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let i = self.live_slot(key)?;
+        self.touch(i);
+        Some(&mut self.entry_mut(i).value)
+    }
+
+    /// Returns the value of the key if it did not expire, without counting a use.
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        let i = *self.map.get(key)?;
+        (!self.is_expired(i, self.clock.now())).then(|| &self.entry(i).value)
+    }
+
+    /// Returns whether the cache contains the key and it did not expire, without counting a use.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.peek(key).is_some()
+    }
+
+    /// Returns how long the key has left to live, or `None` if it is not in the cache, expired or never expires.
+    /// ```
+    /// # use std::time::Duration;
+    /// # use strctr::cache::{Eviction, ManualClock, TtlCache};
+    /// let clock = ManualClock::new();
+    /// let mut c = TtlCache::with_clock(4, Eviction::Lru, clock.clone());
+    /// c.insert_with_ttl(1, "a", Duration::from_secs(10));
+    /// clock.advance(Duration::from_secs(4));
+    /// assert_eq!(c.time_to_live(&1), Some(Duration::from_secs(6)));
+    /// ```
+    pub fn time_to_live(&self, key: &K) -> Option<Duration> {
+        let i = *self.map.get(key)?;
+        let expires = self.entry(i).expires?;
+        expires
+            .checked_duration_since(self.clock.now())
+            .filter(|left| !left.is_zero())
+    }
+
+    /// Removes the key from the cache, returning its value if it did not expire.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let i = self.map.remove(key)?;
+        let expired = self.is_expired(i, self.clock.now());
+        let (_, value) = self.take(i);
+        (!expired).then_some(value)
+    }
+
+    /// Drops every expired entry, returning how many there were.
+    /// ```
+    /// # use std::time::Duration;
+    /// # use strctr::cache::{Eviction, ManualClock, TtlCache};
+    /// let clock = ManualClock::new();
+    /// let mut c = TtlCache::with_clock(4, Eviction::Lru, clock.clone()).with_ttl(Duration::from_secs(1));
+    /// c.insert(1, "a");
+    /// c.insert(2, "b");
+    /// c.insert_with_ttl(3, "c", Duration::from_secs(10));
+    /// clock.advance(Duration::from_secs(2));
+    /// assert_eq!(c.len(), 3);
+    /// assert_eq!(c.evict_expired(), 2);
+    /// assert_eq!(c.len(), 1);
+    /// ```
+    pub fn evict_expired(&mut self) -> usize {
+        let now = self.clock.now();
+        self.drop_expired(now)
+    }
+
+    /// Removes and returns the entry the cache would evict next by its policy, whether expired or not.
+    /// ```
+    /// # use strctr::cache::{Eviction, TtlCache};
+    /// let mut c = TtlCache::new(3, Eviction::Lru);
+    /// c.insert(1, "a");
+    /// c.insert(2, "b");
+    /// c.get(&1);
+    /// assert_eq!(c.pop_evictable(), Some((2, "b")));
+    /// ```
+    pub fn pop_evictable(&mut self) -> Option<(K, V)> {
+        let &(_, _, i) = self.order.first()?;
+        let (key, value) = self.take(i);
+        self.map.remove(&key);
+        Some((key, value))
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.free.clear();
+        self.map.clear();
+        self.order.clear();
+        self.expiries.clear();
+    }
+
+    /// Returns an iterator over the entries that did not expire, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let now = self.clock.now();
+        self.entries
+            .iter()
+            .flatten()
+            .filter(move |e| e.expires.is_none_or(|at| at > now))
+            .map(|e| (&e.key, &e.value))
+    }
+
+    fn entry(&self, i: usize) -> &Entry<K, V> {
+        self.entries[i].as_ref().expect("live entry")
+    }
+
+    fn entry_mut(&mut self, i: usize) -> &mut Entry<K, V> {
+        self.entries[i].as_mut().expect("live entry")
+    }
+
+    fn is_expired(&self, i: usize, now: Instant) -> bool {
+        self.entry(i).expires.is_some_and(|at| at <= now)
+    }
+
+    /// Returns the slot of the key, dropping the entry if it expired.
+    fn live_slot(&mut self, key: &K) -> Option<usize> {
+        let i = *self.map.get(key)?;
+        if self.is_expired(i, self.clock.now()) {
+            self.map.remove(key);
+            self.take(i);
+            return None;
+        }
+        Some(i)
+    }
+
+    fn drop_expired(&mut self, now: Instant) -> usize {
+        let mut dropped = 0;
+        while let Some(&(at, i)) = self.expiries.first() {
+            if at > now {
+                break;
+            }
+            let (key, _) = self.take(i);
+            self.map.remove(&key);
+            dropped += 1;
+        }
+        dropped
+    }
+
+    fn set_expiry(&mut self, i: usize, expires: Option<Instant>) {
+        if let Some(at) = std::mem::replace(&mut self.entry_mut(i).expires, expires) {
+            self.expiries.remove(&(at, i));
+        }
+        if let Some(at) = expires {
+            self.expiries.insert((at, i));
+        }
+    }
+
+    /// Counts a use of the entry and moves it to its new place in the eviction order.
+    fn touch(&mut self, i: usize) {
+        self.tick += 1;
+        let (tick, lfu) = (self.tick, self.eviction == Eviction::Lfu);
+        let e = self.entries[i].as_mut().expect("live entry");
+        self.order.remove(&(e.uses, e.last_use, i));
+        if lfu {
+            e.uses = e.uses.saturating_add(1);
+        }
+        e.last_use = tick;
+        self.order.insert((e.uses, e.last_use, i));
+    }
+
+    /// Unorders the entry and frees its slot. The caller removes it from the map.
+    fn take(&mut self, i: usize) -> (K, V) {
+        let entry = self.entries[i].take().expect("live entry");
+        self.order.remove(&(entry.uses, entry.last_use, i));
+        if let Some(at) = entry.expires {
+            self.expiries.remove(&(at, i));
+        }
+        self.free.push(i);
+        (entry.key, entry.value)
+    }
+}
+
+struct Charged<K, V> {
+    key: K,
+    value: V,
+    size: usize,
+    cost: u64,
+    uses: u64,
+    priority: f64,
+    last_use: u64,
+}
+
+/// A cache bounded by the total size of its entries rather than their number, like a number of bytes.
+///
+/// Every entry is inserted with its size, and optionally the cost of fetching it again. When the entries would
+/// exceed the budget, the cache evicts by GreedyDual-Size-Frequency (GDSF): every entry has a priority of
+/// `inflation + uses * cost / size`, set when it is inserted or used, and the entry of the lowest priority goes first,
+/// the least recently used one among equals. Evicting raises the inflation to the evicted priority, so entries that
+/// stop being used age out even if they were once used often. Small, costly and often used entries thus stay longest.
+///
+/// Entries are kept ordered by priority, so every operation takes `O(log n)`, and inserting takes `O(log n)` per
+/// evicted entry.
+/// ```
+/// # use strctr::cache::BudgetCache;
+/// let mut blobs = BudgetCache::new(1000);
+/// blobs.insert("small", "s", 100);
+/// blobs.insert("large", "l", 800);
+/// blobs.get(&"small");
+/// // Room is needed: the large blob has the lower priority.
+/// blobs.insert("medium", "m", 400);
+/// assert!(blobs.contains_key(&"small"));
+/// assert!(!blobs.contains_key(&"large"));
+/// assert_eq!(blobs.used(), 500);
+/// ```
+pub struct BudgetCache<K, V> {
+    entries: Vec<Option<Charged<K, V>>>,
+    free: Vec<usize>,
+    map: HashMap<K, usize>,
+    /// Entries by (priority, last use, slot). Priorities are never negative, so their bits sort like them.
+    order: BTreeSet<(u64, u64, usize)>,
+    inflation: f64,
+    /// Counts uses to order them.
+    tick: u64,
+    used: usize,
+    budget: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> BudgetCache<K, V> {
+    /// Constructs a new, empty cache whose entries may take up to `budget` in total.
+    ///
+    /// Panics if the budget is 0.
+    pub fn new(budget: usize) -> Self {
+        if budget == 0 {
+            panic!("InvalidCapacity: BudgetCache needs a budget of at least 1");
+        }
+        Self {
+            entries: Vec::new(),
+            free: Vec::new(),
+            map: HashMap::new(),
+            order: BTreeSet::new(),
+            inflation: 0.0,
+            tick: 0,
+            used: 0,
+            budget,
+        }
+    }
+
+    /// Returns the total size the entries may take.
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Returns the total size of the entries.
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns whether the cache contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Inserts a key-value pair of the size, which costs 1 to fetch again, like
+    /// [insert_with_cost()](`Self::insert_with_cost()`).
+    pub fn insert(&mut self, key: K, value: V, size: usize) -> Option<V> {
+        self.insert_with_cost(key, value, size, 1)
+    }
+
+    /// Inserts a key-value pair of the size, which costs `cost` to fetch again, and counts it as a use. Returns the
+    /// previous value if the key was present; otherwise the cache evicts entries until the new one fits.
+    ///
+    /// A value larger than the whole budget is not cached, but still replaces the previous value of the key.
+    /// ```
+    /// # use strctr::cache::BudgetCache;
+    /// let mut c = BudgetCache::new(100);
+    /// c.insert_with_cost("cheap", 1, 50, 1);
+    /// c.insert_with_cost("costly", 2, 50, 10);
+    /// c.insert("new", 3, 50);
+    /// assert!(c.contains_key(&"costly"));
+    /// assert!(!c.contains_key(&"cheap"));
+    ///
+    /// assert_eq!(c.insert("new", 4, 500), Some(3));
+    /// assert!(!c.contains_key(&"new"));
+    /// ```
+    pub fn insert_with_cost(&mut self, key: K, value: V, size: usize, cost: u64) -> Option<V> {
+        let (old, uses) = match self.map.remove(&key) {
+            Some(i) => {
+                let old = self.take(i);
+                (Some(old.value), old.uses)
+            }
+            None => (None, 0),
+        };
+        if size > self.budget {
+            return old;
+        }
+        while self.used + size > self.budget {
+            self.pop_evictable();
+        }
+        let entry = Charged {
+            key: key.clone(),
+            value,
+            size,
+            cost,
+            uses,
+            priority: 0.0,
+            last_use: 0,
+        };
+        let i = match self.free.pop() {
+            Some(i) => {
+                self.entries[i] = Some(entry);
+                i
+            }
+            None => {
+                self.entries.push(Some(entry));
+                self.entries.len() - 1
+            }
+        };
+        self.used += size;
+        self.touch(i);
+        self.map.insert(key, i);
+        old
+    }
+
+    /// Returns the value of the key and counts it as a use.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let i = *self.map.get(key)?;
+        self.touch(i);
+        Some(&self.entry(i).value)
+    }
+
+    /// Returns the value of the key mutably and counts it as a use. The entry keeps the size it was inserted with.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let i = *self.map.get(key)?;
+        self.touch(i);
+        Some(&mut self.entries[i].as_mut().expect("live entry").value)
+    }
+
+    /// Returns the value of the key without counting a use.
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        let i = *self.map.get(key)?;
+        Some(&self.entry(i).value)
+    }
+
+    /// Returns whether the cache contains the key, without counting a use.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Returns the size the key was inserted with.
+    pub fn size_of(&self, key: &K) -> Option<usize> {
+        let i = *self.map.get(key)?;
+        Some(self.entry(i).size)
+    }
+
+    /// Removes the key from the cache, returning its value.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let i = self.map.remove(key)?;
+        Some(self.take(i).value)
+    }
+
+    /// Removes and returns the entry of the lowest priority, the one the cache would evict next, and raises the
+    /// inflation to its priority.
+    pub fn pop_evictable(&mut self) -> Option<(K, V)> {
+        let &(_, _, i) = self.order.first()?;
+        let entry = self.take(i);
+        self.map.remove(&entry.key);
+        self.inflation = entry.priority;
+        Some((entry.key, entry.value))
+    }
+
+    /// Removes all entries and resets the inflation.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.free.clear();
+        self.map.clear();
+        self.order.clear();
+        self.inflation = 0.0;
+        self.used = 0;
+    }
+
+    /// Returns an iterator over the entries and their sizes, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V, usize)> {
+        self.entries
+            .iter()
+            .flatten()
+            .map(|e| (&e.key, &e.value, e.size))
+    }
+
+    fn entry(&self, i: usize) -> &Charged<K, V> {
+        self.entries[i].as_ref().expect("live entry")
+    }
+
+    /// Counts a use of the entry and moves it to the place of its new priority.
+    fn touch(&mut self, i: usize) {
+        self.tick += 1;
+        let (tick, inflation) = (self.tick, self.inflation);
+        let e = self.entries[i].as_mut().expect("live entry");
+        self.order.remove(&(e.priority.to_bits(), e.last_use, i));
+        e.uses = e.uses.saturating_add(1);
+        e.priority = inflation + e.uses as f64 * e.cost as f64 / e.size.max(1) as f64;
+        e.last_use = tick;
+        self.order.insert((e.priority.to_bits(), e.last_use, i));
+    }
+
+    /// Unorders the entry, frees its slot and its size. The caller removes it from the map.
+    fn take(&mut self, i: usize) -> Charged<K, V> {
+        let entry = self.entries[i].take().expect("live entry");
+        self.order
+            .remove(&(entry.priority.to_bits(), entry.last_use, i));
+        self.used -= entry.size;
+        self.free.push(i);
+        entry
+    }
+}
@@ -0,0 +1,181 @@
+//! Chtholly tree (also known as an "old driver tree"): a sequence stored as runs of equal values. Assigning a value
+//! to a range collapses it into a single run, so workloads dominated by range assignment stay fast in amortized terms.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// A fixed-length sequence of values stored as maximal runs keyed by their start position.
+pub struct ChthollyTree<T> {
+    runs: BTreeMap<usize, T>,
+    len: usize,
+}
+
+impl<T: Clone> ChthollyTree<T> {
+    /// Constructs a sequence of `len` positions, all holding `value`.
+    /// ```
+    /// # use strctr::chtholly::ChthollyTree;
+    /// let t = ChthollyTree::new(10, 0);
+    /// assert_eq!(t.len(), 10);
+    /// assert_eq!(t.run_count(), 1);
+    /// ```
+    pub fn new(len: usize, value: T) -> Self {
+        let mut runs = BTreeMap::new();
+        if len > 0 {
+            runs.insert(0, value);
+        }
+        Self { runs, len }
+    }
+
+    /// Returns the number of positions in the sequence.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the sequence has no positions.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of runs the sequence is currently split into.
+    pub fn run_count(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Returns the value at the position.
+    /// ```
+    /// # use strctr::chtholly::ChthollyTree;
+    /// let mut t = ChthollyTree::new(10, 'a');
+    /// t.assign(3..5, 'b');
+    /// assert_eq!(t.get(4), Some(&'b'));
+    /// assert_eq!(t.get(5), Some(&'a'));
+    /// assert_eq!(t.get(10), None);
+    /// ```
+    pub fn get(&self, pos: usize) -> Option<&T> {
+        if pos >= self.len {
+            return None;
+        }
+        self.runs.range(..=pos).next_back().map(|(_, v)| v)
+    }
+
+    /// Sets every position in the range to the value, merging them into a single run. The range is clamped to the
+    /// length of the sequence.
+    /// ```
+    /// # use strctr::chtholly::ChthollyTree;
+    /// let mut t = ChthollyTree::new(10, 0);
+    /// t.assign(2..4, 1);
+    /// t.assign(6..8, 2);
+    /// assert_eq!(t.run_count(), 5);
+    /// t.assign(1..9, 3);
+    /// assert_eq!(t.run_count(), 3);
+    /// ```
+    pub fn assign(&mut self, range: Range<usize>, value: T) {
+        let Some(range) = self.clamp(range) else {
+            return;
+        };
+        self.split(range.end);
+        self.split(range.start);
+        let inner: Vec<usize> = self
+            .runs
+            .range(range.start + 1..range.end)
+            .map(|(&k, _)| k)
+            .collect();
+        for k in inner {
+            self.runs.remove(&k);
+        }
+        self.runs.insert(range.start, value);
+    }
+
+    /// Calls `f` on the value of every run overlapping the range, splitting the runs at the range's ends first so
+    /// positions outside the range are unaffected.
+    /// ```
+    /// # use strctr::chtholly::ChthollyTree;
+    /// let mut t = ChthollyTree::new(6, 1);
+    /// t.assign(0..3, 5);
+    /// t.update(2..4, |v| *v *= 10);
+    /// let values: Vec<_> = (0..6).map(|i| *t.get(i).unwrap()).collect();
+    /// assert_eq!(values, vec![5, 5, 50, 10, 1, 1]);
+    /// ```
+    pub fn update<F: FnMut(&mut T)>(&mut self, range: Range<usize>, mut f: F) {
+        let Some(range) = self.clamp(range) else {
+            return;
+        };
+        self.split(range.end);
+        self.split(range.start);
+        for (_, v) in self.runs.range_mut(range) {
+            f(v);
+        }
+    }
+
+    /// Returns an iterator over the runs overlapping the range, as `(positions, value)` pairs. The first and last
+    /// runs are trimmed to the range.
+    /// ```
+    /// # use strctr::chtholly::ChthollyTree;
+    /// let mut t = ChthollyTree::new(10, 'a');
+    /// t.assign(3..6, 'b');
+    /// let runs: Vec<_> = t.runs(2..8).collect();
+    /// assert_eq!(runs, vec![(2..3, &'a'), (3..6, &'b'), (6..8, &'a')]);
+    /// ```
+    pub fn runs(&self, range: Range<usize>) -> impl Iterator<Item = (Range<usize>, &T)> {
+        let range = self.clamp(range).unwrap_or(0..0);
+        let first = self
+            .runs
+            .range(..=range.start)
+            .next_back()
+            .map_or(range.start, |(&k, _)| k);
+        let mut runs = self.runs.range(first..range.end).peekable();
+        let len = self.len;
+        std::iter::from_fn(move || {
+            let (&start, value) = runs.next()?;
+            let end = runs.peek().map_or(len, |(&k, _)| k);
+            Some((start.max(range.start)..end.min(range.end), value))
+        })
+    }
+
+    /// Makes sure a run starts at `pos`, splitting the run that contains it.
+    fn split(&mut self, pos: usize) {
+        if pos >= self.len {
+            return;
+        }
+        let (&start, value) = self
+            .runs
+            .range(..=pos)
+            .next_back()
+            .expect("position 0 always starts a run");
+        if start != pos {
+            let value = value.clone();
+            self.runs.insert(pos, value);
+        }
+    }
+
+    fn clamp(&self, range: Range<usize>) -> Option<Range<usize>> {
+        let end = range.end.min(self.len);
+        (range.start < end).then_some(range.start..end)
+    }
+}
+
+impl<T: Clone + PartialEq> ChthollyTree<T> {
+    /// Merges neighbouring runs holding equal values, bringing [run_count()](`Self::run_count()`) back down after a
+    /// series of [update()](`Self::update()`) calls.
+    /// ```
+    /// # use strctr::chtholly::ChthollyTree;
+    /// let mut t = ChthollyTree::new(10, 0);
+    /// t.update(3..5, |v| *v += 0);
+    /// assert_eq!(t.run_count(), 3);
+    /// t.compact();
+    /// assert_eq!(t.run_count(), 1);
+    /// ```
+    pub fn compact(&mut self) {
+        let mut prev: Option<&T> = None;
+        let mut redundant = Vec::new();
+        for (&k, v) in &self.runs {
+            if prev == Some(v) {
+                redundant.push(k);
+            } else {
+                prev = Some(v);
+            }
+        }
+        for k in redundant {
+            self.runs.remove(&k);
+        }
+    }
+}
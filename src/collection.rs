@@ -0,0 +1,288 @@
+//! Traits shared by the containers of this crate, for code that is generic over them.
+//!
+//! [`Collection`] covers anything with a length that can be emptied. Containers of positioned elements that grow at the
+//! end also implement [`Sequence`], and containers of key-value pairs [`Map`]. The traits only cover what the
+//! implementing structures already do, with the same meaning as their inherent methods, so a benchmark or an algorithm
+//! written against them runs unchanged on any backend. They are implemented by:
+//!
+//! - [`Map`]: [`RBTreeMap`], [`BTreeMap`], [`SkipListMap`], [`BalancedTree`], [`SortedMap`], [`IntervalTree`],
+//!   [`LruCache`] and [`TtlCache`].
+//! - [`Sequence`]: [`Array`], [`HybridVec`] and [`ImplicitTreap`].
+//! - [`Collection`] alone: [`SortedVec`], [`Trie`], [`BinaryHeap`], [`MinMaxHeap`], [`SlidingBuffer`], [`Counter`],
+//!   [`SparseSet`], [`MultiMap`], [`Arena`], [`ArrayString`], [`BudgetCache`] and [`View`].
+//!
+//! Other structures, like the immutable, concurrent and replicated ones, implement none of them. [`Vec`], [`VecDeque`],
+//! [`std::collections::BTreeMap`] and [`HashMap`] implement them too, as baselines.
+//! ```
+//! # use strctr::collection::Map;
+//! # use strctr::rbtree::RBTreeMap;
+//! # use strctr::skiplist::SkipListMap;
+//! fn word_counts<M: Map<Key = String, Value = usize> + Default>(text: &str) -> M {
+//!     let mut counts = M::default();
+//!     for word in text.split_whitespace() {
+//!         let n = counts.get(&word.to_string()).copied().unwrap_or(0);
+//!         counts.insert(word.to_string(), n + 1);
+//!     }
+//!     counts
+//! }
+//!
+//! let text = "the cat saw the other cat";
+//! let tree: RBTreeMap<_, _> = word_counts(text);
+//! let list: SkipListMap<_, _> = word_counts(text);
+//! assert_eq!((tree.len(), tree.get(&"cat".to_string())), (4, Some(&2)));
+//! assert!(tree.iter().eq(list.iter()));
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::{BuildHasher, Hash};
+use std::ops::Range;
+
+use crate::arena::Arena;
+use crate::array::Array;
+use crate::array_string::ArrayString;
+use crate::bst::{BalancePolicy, BalancedTree};
+use crate::btree::BTreeMap;
+use crate::cache::{BudgetCache, Clock, TtlCache};
+use crate::counter::Counter;
+use crate::dataflow::View;
+use crate::growth::GrowthPolicy;
+use crate::heap::{BinaryHeap, Compare, MinMaxHeap};
+use crate::hybrid_vec::HybridVec;
+use crate::interval_tree::IntervalTree;
+use crate::lru::LruCache;
+use crate::multimap::MultiMap;
+use crate::rbtree::RBTreeMap;
+use crate::segment_tree::{ImplicitTreap, Monoid};
+use crate::skiplist::SkipListMap;
+use crate::sliding::SlidingBuffer;
+use crate::sorted_vec::{SortedMap, SortedVec};
+use crate::sparse_set::SparseSet;
+use crate::trie::Trie;
+
+/// A container of elements.
+pub trait Collection {
+    /// Returns the number of elements.
+    fn len(&self) -> usize;
+
+    /// Returns whether there are no elements.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all elements.
+    fn clear(&mut self);
+}
+
+/// A collection of elements at positions `0..len()`, which grows at the end.
+/// ```
+/// # use strctr::array::Array;
+/// # use strctr::collection::Sequence;
+/// # use strctr::hybrid_vec::HybridVec;
+/// # use strctr::segment_tree::{ImplicitTreap, Sum};
+/// fn fill<S: Sequence<Element = u32>>(s: &mut S, n: u32) -> Option<u32> {
+///     for i in 0..n {
+///         s.push(i * i);
+///     }
+///     s.get(3).copied()
+/// }
+///
+/// assert_eq!(fill(&mut Array::<u32, 8>::new(), 8), Some(9));
+/// assert_eq!(fill(&mut HybridVec::<u32, 2>::new(), 8), Some(9));
+/// assert_eq!(fill(&mut ImplicitTreap::new(Vec::new(), Sum), 8), Some(9));
+/// assert_eq!(fill(&mut Vec::new(), 2), None);
+/// ```
+pub trait Sequence: Collection {
+    /// The type of the elements.
+    type Element;
+
+    /// Returns the element at the position, or `None` if it is out of bounds.
+    fn get(&self, index: usize) -> Option<&Self::Element>;
+
+    /// Appends the element.
+    fn push(&mut self, element: Self::Element);
+
+    /// Removes the last element and returns it, or `None` if the sequence is empty.
+    fn pop(&mut self) -> Option<Self::Element>;
+}
+
+/// A collection of key-value pairs with distinct keys.
+pub trait Map: Collection {
+    /// The type of the keys.
+    type Key;
+    /// The type of the values.
+    type Value;
+
+    /// Returns the value of the key, or `None` if it is absent.
+    fn get(&self, key: &Self::Key) -> Option<&Self::Value>;
+
+    /// Inserts a key-value pair. If the key was already present, its value is replaced and the old value is returned.
+    fn insert(&mut self, key: Self::Key, value: Self::Value) -> Option<Self::Value>;
+
+    /// Removes the key and returns its value, or `None` if it was absent.
+    fn remove(&mut self, key: &Self::Key) -> Option<Self::Value>;
+
+    /// Returns whether the key is present.
+    fn contains_key(&self, key: &Self::Key) -> bool {
+        self.get(key).is_some()
+    }
+}
+
+/// Implements [`Collection`] by delegating to the inherent `len` and `clear` methods.
+macro_rules! collection {
+    ([$($generics:tt)*] $t:ty) => {
+        impl<$($generics)*> Collection for $t {
+            fn len(&self) -> usize {
+                <$t>::len(self)
+            }
+
+            fn clear(&mut self) {
+                <$t>::clear(self)
+            }
+        }
+    };
+}
+
+/// Implements [`Collection`] and [`Map`] by delegating to the inherent methods. Lookups delegate to `get` unless another
+/// method taking `&self` is named.
+macro_rules! map {
+    ([$($generics:tt)*] $t:ty, $k:ty, $v:ty) => {
+        map!([$($generics)*] $t, $k, $v, get);
+    };
+    ([$($generics:tt)*] $t:ty, $k:ty, $v:ty, $get:ident) => {
+        collection!([$($generics)*] $t);
+
+        impl<$($generics)*> Map for $t {
+            type Key = $k;
+            type Value = $v;
+
+            fn get(&self, key: &$k) -> Option<&$v> {
+                <$t>::$get(self, key)
+            }
+
+            fn insert(&mut self, key: $k, value: $v) -> Option<$v> {
+                <$t>::insert(self, key, value)
+            }
+
+            fn remove(&mut self, key: &$k) -> Option<$v> {
+                <$t>::remove(self, key)
+            }
+        }
+    };
+}
+
+map!([K: Ord, V] RBTreeMap<K, V>, K, V);
+map!([K: Ord, V, const B: usize] BTreeMap<K, V, B>, K, V);
+map!([K: Ord, V] SkipListMap<K, V>, K, V);
+map!([K: Ord, V, P: BalancePolicy] BalancedTree<K, V, P>, K, V);
+map!([K: Ord, V] SortedMap<K, V>, K, V);
+map!([K: Ord, V] std::collections::BTreeMap<K, V>, K, V);
+map!([K: Hash + Eq, V, S: BuildHasher] HashMap<K, V, S>, K, V);
+map!([K: Ord + Clone, V] IntervalTree<K, V>, Range<K>, V);
+// Lookups through the trait take `&self`, so they peek without counting as a use.
+map!([K: Hash + Eq + Clone, V] LruCache<K, V>, K, V, peek);
+map!([K: Hash + Eq + Clone, V, C: Clock] TtlCache<K, V, C>, K, V, peek);
+
+collection!([T] SortedVec<T>);
+collection!([V] Trie<V>);
+collection!([T, C: Compare<T>] BinaryHeap<T, C>);
+collection!([T, C: Compare<T>] MinMaxHeap<T, C>);
+collection!([T, const N: usize] SlidingBuffer<T, N>);
+collection!([T] Counter<T>);
+collection!([] SparseSet);
+collection!([K, V] MultiMap<K, V>);
+collection!([T] Arena<T>);
+collection!([const N: usize] ArrayString<N>);
+collection!([K: Hash + Eq + Clone, V] BudgetCache<K, V>);
+collection!([K: Ord, V] View<K, V>);
+
+collection!([T: Clone, Op: Monoid<T>] ImplicitTreap<T, Op>);
+
+impl<T: Clone, Op: Monoid<T>> Sequence for ImplicitTreap<T, Op> {
+    type Element = T;
+
+    fn get(&self, index: usize) -> Option<&T> {
+        ImplicitTreap::get(self, index)
+    }
+
+    fn push(&mut self, element: T) {
+        ImplicitTreap::push(self, element)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let len = self.len();
+        (len > 0).then(|| self.remove(len - 1))
+    }
+}
+
+collection!([T: Copy, const N: usize] Array<T, N>);
+
+impl<T: Copy, const N: usize> Sequence for Array<T, N> {
+    type Element = T;
+
+    fn get(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
+    }
+
+    /// Panics if the array is full, like [push()](`Array::push()`).
+    fn push(&mut self, element: T) {
+        Array::push(self, element)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        Array::pop(self)
+    }
+}
+
+collection!([T: Copy, const N: usize, G: GrowthPolicy] HybridVec<T, N, G>);
+
+impl<T: Copy, const N: usize, G: GrowthPolicy> Sequence for HybridVec<T, N, G> {
+    type Element = T;
+
+    fn get(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
+    }
+
+    fn push(&mut self, element: T) {
+        HybridVec::push(self, element)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        HybridVec::pop(self)
+    }
+}
+
+collection!([T] Vec<T>);
+
+impl<T> Sequence for Vec<T> {
+    type Element = T;
+
+    fn get(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
+    }
+
+    fn push(&mut self, element: T) {
+        Vec::push(self, element)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        Vec::pop(self)
+    }
+}
+
+collection!([T] VecDeque<T>);
+
+impl<T> Sequence for VecDeque<T> {
+    type Element = T;
+
+    fn get(&self, index: usize) -> Option<&T> {
+        VecDeque::get(self, index)
+    }
+
+    fn push(&mut self, element: T) {
+        self.push_back(element)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_back()
+    }
+}
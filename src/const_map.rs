@@ -0,0 +1,259 @@
+//! Map that sorts itself at compile time, for small static lookup tables like the keywords of a language.
+//!
+//! [`ConstSortedMap::new()`] is a `const fn`: given a literal array in a `const` or `static` item, it sorts the entries
+//! during compilation, and duplicate keys are a compile error. Lookups are binary searches over the array and can be
+//! evaluated at compile time as well. Unlike a lazily initialized `HashMap`, the table needs no initialization at
+//! runtime, no hashing and no dependencies. Const evaluation cannot call [`Ord`] on stable Rust, so keys are limited to
+//! the types implementing [`ConstKey`].
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::mem::size_of;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// How a [`ConstKey`] is compared during const evaluation.
+#[doc(hidden)]
+#[derive(Clone, Copy)]
+pub enum KeyRepr {
+    Unsigned,
+    Signed,
+    Str,
+}
+
+/// Key types that [`ConstSortedMap`] can compare in const context: the primitive integers, `char`, `bool` and
+/// `&str`. Their order is the same as their [`Ord`] implementation.
+pub trait ConstKey: Ord + Copy + sealed::Sealed {
+    #[doc(hidden)]
+    const REPR: KeyRepr;
+}
+
+macro_rules! const_keys {
+    ($repr:ident: $($t:ty),*) => {
+        $(
+            impl sealed::Sealed for $t {}
+
+            impl ConstKey for $t {
+                const REPR: KeyRepr = KeyRepr::$repr;
+            }
+        )*
+    };
+}
+
+const_keys!(Unsigned: u8, u16, u32, u64, u128, usize, char, bool);
+const_keys!(Signed: i8, i16, i32, i64, i128, isize);
+
+// Any lifetime, not just `'static`: the map is covariant in `K`, so a map of `&'static str` keys can be queried with a
+// string borrowed for less, like a token cut out of a runtime buffer.
+const_keys!(Str: &str);
+
+/// Compares two keys like [`Ord::cmp()`], in const context.
+const fn compare<K: ConstKey>(a: &K, b: &K) -> Ordering {
+    // The trait is sealed, so REPR and the size tell exactly which primitive K is.
+    let (a, b) = (a as *const K, b as *const K);
+    match K::REPR {
+        KeyRepr::Unsigned => compare_u128(unsafe { unsigned(a) }, unsafe { unsigned(b) }),
+        KeyRepr::Signed => {
+            // Flipping the sign bit maps the signed order onto the unsigned one.
+            let (a, b) = unsafe { (signed(a), signed(b)) };
+            compare_u128(a as u128 ^ 1 << 127, b as u128 ^ 1 << 127)
+        }
+        KeyRepr::Str => {
+            let (a, b) = unsafe { (*(a as *const &str), *(b as *const &str)) };
+            compare_bytes(a.as_bytes(), b.as_bytes())
+        }
+    }
+}
+
+/// Reads an unsigned integer, `char` or `bool` key.
+const unsafe fn unsigned<K>(p: *const K) -> u128 {
+    match size_of::<K>() {
+        1 => *(p as *const u8) as u128,
+        2 => *(p as *const u16) as u128,
+        4 => *(p as *const u32) as u128,
+        8 => *(p as *const u64) as u128,
+        _ => *(p as *const u128),
+    }
+}
+
+/// Reads a signed integer key.
+const unsafe fn signed<K>(p: *const K) -> i128 {
+    match size_of::<K>() {
+        1 => *(p as *const i8) as i128,
+        2 => *(p as *const i16) as i128,
+        4 => *(p as *const i32) as i128,
+        8 => *(p as *const i64) as i128,
+        _ => *(p as *const i128),
+    }
+}
+
+const fn compare_u128(a: u128, b: u128) -> Ordering {
+    if a < b {
+        Ordering::Less
+    } else if a > b {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
+
+/// Compares lexicographically, like `<[u8]>::cmp()` and therefore `str::cmp()`.
+const fn compare_bytes(a: &[u8], b: &[u8]) -> Ordering {
+    let mut i = 0;
+    while i < a.len() && i < b.len() {
+        if a[i] != b[i] {
+            return compare_u128(a[i] as u128, b[i] as u128);
+        }
+        i += 1;
+    }
+    compare_u128(a.len() as u128, b.len() as u128)
+}
+
+/// An immutable map of `N` entries, sorted by key when constructed.
+/// ```
+/// # use strctr::const_map::ConstSortedMap;
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// enum Keyword {
+///     Fn,
+///     Let,
+///     Loop,
+///     Return,
+/// }
+///
+/// static KEYWORDS: ConstSortedMap<&str, Keyword, 4> = ConstSortedMap::new([
+///     ("return", Keyword::Return),
+///     ("let", Keyword::Let),
+///     ("fn", Keyword::Fn),
+///     ("loop", Keyword::Loop),
+/// ]);
+///
+/// assert_eq!(KEYWORDS.get("loop"), Some(&Keyword::Loop));
+/// assert_eq!(KEYWORDS.get("lo"), None);
+/// let source = String::from("let x");
+/// let token = source.split(' ').next().unwrap();
+/// assert_eq!(KEYWORDS.get(token), Some(&Keyword::Let));
+/// assert!(!KEYWORDS.contains_key(&source[4..]));
+/// assert_eq!(KEYWORDS.keys().collect::<Vec<_>>(), vec![&"fn", &"let", &"loop", &"return"]);
+/// ```
+/// Duplicate keys fail to compile:
+/// ```compile_fail
+/// # use strctr::const_map::ConstSortedMap;
+/// const PORTS: ConstSortedMap<u16, &str, 2> = ConstSortedMap::new([(80, "http"), (80, "www")]);
+/// # let _ = PORTS.len();
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConstSortedMap<K, V, const N: usize> {
+    entries: [(K, V); N],
+}
+
+impl<K: ConstKey, V, const N: usize> ConstSortedMap<K, V, N> {
+    /// Constructs the map, sorting the entries by key in `O(N^2)` time. In a `const` or `static` item, this happens at
+    /// compile time.
+    ///
+    /// Panics, or fails to compile, if a key appears more than once.
+    pub const fn new(mut entries: [(K, V); N]) -> Self {
+        // Insertion sort, since const evaluation can only move array elements by swapping them.
+        let mut i = 1;
+        while i < N {
+            let mut j = i;
+            while j > 0 {
+                match compare(&entries[j - 1].0, &entries[j].0) {
+                    Ordering::Less => break,
+                    Ordering::Equal => panic!("DuplicateKey: A key appears more than once"),
+                    Ordering::Greater => {
+                        entries.swap(j - 1, j);
+                        j -= 1;
+                    }
+                }
+            }
+            i += 1;
+        }
+        Self { entries }
+    }
+
+    /// Returns the number of entries.
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Returns whether the map contains no entries.
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    /// ```
+    /// # use strctr::const_map::ConstSortedMap;
+    /// const STATUS: ConstSortedMap<u16, &str, 3> =
+    ///     ConstSortedMap::new([(404, "Not Found"), (200, "OK"), (500, "Internal Server Error")]);
+    /// const OK: &str = match STATUS.get(200) {
+    ///     Some(reason) => reason,
+    ///     None => panic!(),
+    /// };
+    /// assert_eq!(OK, "OK");
+    /// assert_eq!(STATUS.get(418), None);
+    /// ```
+    pub const fn get(&self, key: K) -> Option<&V> {
+        match self.index_of(key) {
+            Some(i) => Some(&self.entries[i].1),
+            None => None,
+        }
+    }
+
+    /// Returns the stored key and the value corresponding to the key.
+    pub const fn get_key_value(&self, key: K) -> Option<(&K, &V)> {
+        match self.index_of(key) {
+            Some(i) => Some((&self.entries[i].0, &self.entries[i].1)),
+            None => None,
+        }
+    }
+
+    /// Returns whether the map contains the key.
+    pub const fn contains_key(&self, key: K) -> bool {
+        self.index_of(key).is_some()
+    }
+
+    /// Returns the position of the key in ascending key order.
+    pub const fn index_of(&self, key: K) -> Option<usize> {
+        let (mut lo, mut hi) = (0, N);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match compare(&self.entries[mid].0, &key) {
+                Ordering::Less => lo = mid + 1,
+                Ordering::Greater => hi = mid,
+                Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+
+    /// Returns the entries, in ascending key order.
+    pub const fn as_slice(&self) -> &[(K, V)] {
+        &self.entries
+    }
+
+    /// Returns an iterator over the entries, in ascending key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+
+    /// Returns an iterator over the keys, in ascending order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values, in ascending key order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, const N: usize> fmt::Debug for ConstSortedMap<K, V, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.entries.iter().map(|(k, v)| (k, v)))
+            .finish()
+    }
+}
@@ -0,0 +1,312 @@
+//! Multiset counting the occurrences of items.
+//!
+//! For streams too large to count exactly, see [`SpaceSaving`](`crate::heavy_hitters::SpaceSaving`).
+
+use std::borrow::Borrow;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::io::{self, Read, Write};
+
+use crate::hash::HashState;
+use crate::snapshot::{self, Codec, Snapshot, SnapshotError};
+
+/// List of errors that could occur when adding to a [`Counter`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum CounterError {
+    /// The total number of occurrences would not fit in a `u64`.
+    Overflow,
+}
+
+/// A bag of items with their number of occurrences. Items whose count drops to zero are removed.
+/// ```
+/// # use strctr::counter::Counter;
+/// let words: Counter<_> = "the cat and the hat and the bat".split(' ').collect();
+/// assert_eq!(words.count("the"), 3);
+/// assert_eq!(words.count("dog"), 0);
+/// assert_eq!(words.most_common(2), vec![(&"the", 3), (&"and", 2)]);
+/// assert_eq!(words.total(), 8);
+/// assert_eq!(words.len(), 5);
+/// ```
+#[derive(Clone)]
+pub struct Counter<T> {
+    counts: HashMap<T, u64, HashState>,
+    /// Sum of all counts.
+    total: u64,
+}
+
+impl<T> Default for Counter<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Counter<T> {
+    /// Constructs a new, empty counter.
+    pub fn new() -> Self {
+        Self::with_hasher(HashState::new())
+    }
+
+    /// Constructs a new, empty counter that hashes items with the state's keys.
+    pub fn with_hasher(state: HashState) -> Self {
+        Self {
+            counts: HashMap::with_hasher(state),
+            total: 0,
+        }
+    }
+
+    /// Returns the number of distinct items.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns whether the counter contains no items.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Returns the sum of all counts.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Removes all items.
+    pub fn clear(&mut self) {
+        self.counts.clear();
+        self.total = 0;
+    }
+
+    /// Returns an iterator over the distinct items and their counts, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&T, u64)> {
+        self.counts.iter().map(|(item, &n)| (item, n))
+    }
+
+    /// Returns the `n` items with the highest counts, highest first. Items with equal counts come in no particular
+    /// order.
+    pub fn most_common(&self, n: usize) -> Vec<(&T, u64)> {
+        let mut all: Vec<(&T, u64)> = self.iter().collect();
+        let by_count = |a: &(&T, u64), b: &(&T, u64)| Reverse(a.1).cmp(&Reverse(b.1));
+        if n < all.len() {
+            all.select_nth_unstable_by(n, by_count);
+            all.truncate(n);
+        }
+        all.sort_unstable_by(by_count);
+        all
+    }
+}
+
+impl<T: Hash + Eq> Counter<T> {
+    /// Adds one occurrence of the item.
+    pub fn add(&mut self, item: T) {
+        self.add_n(item, 1);
+    }
+
+    /// Adds `n` occurrences of the item.
+    ///
+    /// Panics if the [total()](`Self::total()`) would overflow a `u64`. For a non-panicing version, see
+    /// [try_add_n()](`Self::try_add_n()`)
+    /// ```should_panic
+    /// # use strctr::counter::Counter;
+    /// let mut c = Counter::new();
+    /// c.add_n("a", u64::MAX);
+    /// c.add("b");
+    /// ```
+    pub fn add_n(&mut self, item: T, n: u64) {
+        if let Err(e) = self.try_add_n(item, n) {
+            panic!(
+                "{:?}: Cannot add {} occurrences to a total of {}",
+                e, n, self.total
+            );
+        }
+    }
+
+    /// Adds `n` occurrences of the item. Returns an error, leaving the counter unchanged, if the
+    /// [total()](`Self::total()`) would overflow a `u64`.
+    ///
+    /// For a more convenient (but less safe) method, see [add_n()](`Self::add_n()`)
+    /// ```
+    /// # use strctr::counter::{Counter, CounterError};
+    /// let mut c = Counter::new();
+    /// assert_eq!(c.try_add_n("a", u64::MAX - 1), Ok(()));
+    /// assert_eq!(c.try_add_n("b", 2), Err(CounterError::Overflow));
+    /// assert_eq!(c.count("b"), 0);
+    /// assert_eq!(c.total(), u64::MAX - 1);
+    /// ```
+    pub fn try_add_n(&mut self, item: T, n: u64) -> Result<(), CounterError> {
+        if n > 0 {
+            self.total = self.total.checked_add(n).ok_or(CounterError::Overflow)?;
+            // Every count is part of the total, so it cannot overflow either.
+            *self.counts.entry(item).or_insert(0) += n;
+        }
+        Ok(())
+    }
+
+    /// Removes one occurrence of the item. Returns `false` if it had none.
+    pub fn remove<Q: Hash + Eq + ?Sized>(&mut self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        self.remove_n(item, 1) == 1
+    }
+
+    /// Removes up to `n` occurrences of the item and returns how many were removed.
+    pub fn remove_n<Q: Hash + Eq + ?Sized>(&mut self, item: &Q, n: u64) -> u64
+    where
+        T: Borrow<Q>,
+    {
+        let Some(count) = self.counts.get_mut(item) else {
+            return 0;
+        };
+        let removed = n.min(*count);
+        *count -= removed;
+        if *count == 0 {
+            self.counts.remove(item);
+        }
+        self.total -= removed;
+        removed
+    }
+
+    /// Removes all occurrences of the item and returns how many there were.
+    pub fn remove_all<Q: Hash + Eq + ?Sized>(&mut self, item: &Q) -> u64
+    where
+        T: Borrow<Q>,
+    {
+        let removed = self.counts.remove(item).unwrap_or(0);
+        self.total -= removed;
+        removed
+    }
+
+    /// Returns the number of occurrences of the item.
+    pub fn count<Q: Hash + Eq + ?Sized>(&self, item: &Q) -> u64
+    where
+        T: Borrow<Q>,
+    {
+        self.counts.get(item).copied().unwrap_or(0)
+    }
+
+    /// Returns whether the item occurs at least once.
+    pub fn contains<Q: Hash + Eq + ?Sized>(&self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        self.counts.contains_key(item)
+    }
+}
+
+impl<T: Hash + Eq + Clone> Counter<T> {
+    /// Returns a counter with the counts of both added up.
+    /// ```
+    /// # use strctr::counter::Counter;
+    /// let a: Counter<_> = "aab".chars().collect();
+    /// let b: Counter<_> = "abbc".chars().collect();
+    /// assert_eq!(a.sum(&b), "aaabbbc".chars().collect());
+    /// assert_eq!(a.difference(&b), "a".chars().collect());
+    /// assert_eq!(a.intersection(&b), "ab".chars().collect());
+    /// assert_eq!(a.union(&b), "aabbc".chars().collect());
+    /// ```
+    pub fn sum(&self, other: &Self) -> Self {
+        let mut sum = self.clone();
+        for (item, n) in other.iter() {
+            sum.add_n(item.clone(), n);
+        }
+        sum
+    }
+
+    /// Returns a counter with the counts of the other counter subtracted from this one's. Items that would drop to zero
+    /// or below are left out.
+    pub fn difference(&self, other: &Self) -> Self {
+        self.combine(|item, n| n.saturating_sub(other.count(item)))
+    }
+
+    /// Returns a counter with the smaller of both counts of every item.
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.combine(|item, n| n.min(other.count(item)))
+    }
+
+    /// Returns a counter with the larger of both counts of every item.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut union = self.clone();
+        for (item, n) in other.iter() {
+            let extra = n.saturating_sub(self.count(item));
+            union.add_n(item.clone(), extra);
+        }
+        union
+    }
+
+    /// Returns whether every item occurs in the other counter at least as often as in this one.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.iter().all(|(item, n)| n <= other.count(item))
+    }
+
+    /// Returns a counter with this counter's items, counted by the function.
+    fn combine(&self, mut f: impl FnMut(&T, u64) -> u64) -> Self {
+        let mut combined = Self::with_hasher(self.counts.hasher().clone());
+        for (item, n) in self.iter() {
+            combined.add_n(item.clone(), f(item, n));
+        }
+        combined
+    }
+}
+
+impl<T: Hash + Eq> FromIterator<T> for Counter<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut counter = Self::new();
+        counter.extend(iter);
+        counter
+    }
+}
+
+impl<T: Hash + Eq> Extend<T> for Counter<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.add(item);
+        }
+    }
+}
+
+impl<T: Hash + Eq + Codec> Snapshot for Counter<T> {
+    const TAG: [u8; 4] = *b"CNTR";
+
+    fn write_payload<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        snapshot::write_entries(w, self.len(), self.counts.iter())
+    }
+
+    /// Rejects counts of zero, and counts whose total does not fit in a `u64`, as corrupt.
+    /// ```
+    /// # use strctr::counter::Counter;
+    /// # use strctr::snapshot::{Codec, Snapshot, SnapshotError, MAGIC, VERSION};
+    /// let mut file = [MAGIC.as_slice(), &VERSION.to_le_bytes(), &Counter::<u8>::TAG].concat();
+    /// vec![(1u8, u64::MAX), (2, 1)].encode(&mut file).unwrap();
+    /// assert!(matches!(Counter::<u8>::load_from(file.as_slice()), Err(SnapshotError::Corrupt)));
+    /// ```
+    fn read_payload<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let mut counter = Self::new();
+        let mut corrupt = false;
+        snapshot::read_entries(r, |item, n: u64| {
+            match counter.total.checked_add(n) {
+                Some(total) if n > 0 => counter.total = total,
+                _ => corrupt = true,
+            }
+            counter.counts.insert(item, n)
+        })?;
+        if corrupt {
+            return Err(SnapshotError::Corrupt);
+        }
+        Ok(counter)
+    }
+}
+
+impl<T: Hash + Eq> PartialEq for Counter<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.counts == other.counts
+    }
+}
+
+impl<T: Hash + Eq> Eq for Counter<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for Counter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.counts.iter()).finish()
+    }
+}
@@ -0,0 +1,387 @@
+//! State-based conflict-free replicated data types (CRDTs).
+//!
+//! Every replica keeps a full copy of the state and applies local updates to it. Replicas exchange their states in
+//! any order, any number of times, and [merge](`Crdt::merge()`) them; once all replicas have seen the same updates
+//! they hold the same value, without any coordination. Replicas are told apart by a [`ReplicaId`] chosen by the
+//! application, which has to be unique per replica.
+//!
+//! Besides counters, sets and registers, the module has the [`Rga`] sequence for collaborative text editing,
+//! [`VectorClock`]s for tracking causality between replicas, and the [`MerkleSearchTree`] set, which replicas
+//! reconcile by exchanging only the parts that differ.
+//!
+//! With the `serde` feature enabled, all types implement `Serialize` and `Deserialize`, so states can be shipped
+//! between processes.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+mod merkle_search_tree;
+mod rga;
+mod vector_clock;
+
+pub use merkle_search_tree::{Digest, MerkleIter, MerkleSearchTree, Page, ReconcileError};
+pub use rga::{ElementId, Rga, RgaError, RgaOp};
+pub use vector_clock::{VectorClock, VersionVector};
+
+/// Identifier of a replica.
+pub type ReplicaId = u64;
+
+/// A replicated data type whose states can be merged.
+pub trait Crdt {
+    /// Merges the other replica's state into this one. Merging is commutative, associative and idempotent, so
+    /// replicas converge no matter how often and in which order they exchange states.
+    fn merge(&mut self, other: &Self);
+}
+
+/// Grow-only counter. Every replica counts its own increments, and the value is the sum over all replicas.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GCounter {
+    counts: BTreeMap<ReplicaId, u64>,
+}
+
+impl GCounter {
+    /// Constructs a new counter with value 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds one on behalf of the replica.
+    pub fn increment(&mut self, replica: ReplicaId) {
+        self.increment_by(replica, 1);
+    }
+
+    /// Adds `n` on behalf of the replica.
+    pub fn increment_by(&mut self, replica: ReplicaId, n: u64) {
+        *self.counts.entry(replica).or_insert(0) += n;
+    }
+
+    /// Returns the value of the counter.
+    /// ```
+    /// # use strctr::crdt::{Crdt, GCounter};
+    /// let mut a = GCounter::new();
+    /// let mut b = GCounter::new();
+    /// a.increment(1);
+    /// b.increment_by(2, 5);
+    /// a.merge(&b);
+    /// a.merge(&b);
+    /// assert_eq!(a.value(), 6);
+    /// ```
+    pub fn value(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// Returns the increments made by the replica.
+    pub fn replica_value(&self, replica: ReplicaId) -> u64 {
+        self.counts.get(&replica).copied().unwrap_or(0)
+    }
+}
+
+impl Crdt for GCounter {
+    fn merge(&mut self, other: &Self) {
+        for (&replica, &n) in &other.counts {
+            let count = self.counts.entry(replica).or_insert(0);
+            *count = (*count).max(n);
+        }
+    }
+}
+
+/// Counter that can be incremented and decremented, made of one [`GCounter`] for each direction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PNCounter {
+    increments: GCounter,
+    decrements: GCounter,
+}
+
+impl PNCounter {
+    /// Constructs a new counter with value 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds one on behalf of the replica.
+    pub fn increment(&mut self, replica: ReplicaId) {
+        self.increments.increment(replica);
+    }
+
+    /// Subtracts one on behalf of the replica.
+    pub fn decrement(&mut self, replica: ReplicaId) {
+        self.decrements.increment(replica);
+    }
+
+    /// Returns the value of the counter.
+    /// ```
+    /// # use strctr::crdt::{Crdt, PNCounter};
+    /// let mut a = PNCounter::new();
+    /// let mut b = PNCounter::new();
+    /// a.increment(1);
+    /// a.increment(1);
+    /// b.decrement(2);
+    /// b.merge(&a);
+    /// assert_eq!(b.value(), 1);
+    /// ```
+    pub fn value(&self) -> i64 {
+        self.increments.value() as i64 - self.decrements.value() as i64
+    }
+}
+
+impl Crdt for PNCounter {
+    fn merge(&mut self, other: &Self) {
+        self.increments.merge(&other.increments);
+        self.decrements.merge(&other.decrements);
+    }
+}
+
+/// Unique tag of one add operation: the replica and its running operation number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Tag(ReplicaId, u64);
+
+/// Observed-remove set. Adding an element tags it uniquely, and removing it only removes the tags the removing
+/// replica has seen, so an add that is concurrent with a remove wins.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ORSet<T: Ord> {
+    /// Live tags of every element in the set.
+    elements: BTreeMap<T, BTreeSet<Tag>>,
+    /// Tags removed anywhere, so merges do not bring them back.
+    removed: BTreeSet<Tag>,
+    /// Number of add operations performed by each replica.
+    clock: BTreeMap<ReplicaId, u64>,
+}
+
+impl<T: Ord> Default for ORSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> ORSet<T> {
+    /// Constructs a new, empty set.
+    pub fn new() -> Self {
+        Self {
+            elements: BTreeMap::new(),
+            removed: BTreeSet::new(),
+            clock: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of elements in the set.
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Returns whether the set contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Returns whether the element is in the set.
+    pub fn contains(&self, elem: &T) -> bool {
+        self.elements.contains_key(elem)
+    }
+
+    /// Returns an iterator over the elements, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.elements.keys()
+    }
+
+    /// Adds the element on behalf of the replica.
+    pub fn insert(&mut self, replica: ReplicaId, elem: T) {
+        let n = self.clock.entry(replica).or_insert(0);
+        *n += 1;
+        self.elements
+            .entry(elem)
+            .or_default()
+            .insert(Tag(replica, *n));
+    }
+
+    /// Removes the element as far as this replica has observed it. Returns whether the element was present.
+    /// ```
+    /// # use strctr::crdt::{Crdt, ORSet};
+    /// let mut a = ORSet::new();
+    /// a.insert(1, "x");
+    /// let mut b = a.clone();
+    /// // Concurrently, replica 1 removes "x" while replica 2 adds it again.
+    /// assert!(a.remove(&"x"));
+    /// b.insert(2, "x");
+    /// a.merge(&b);
+    /// assert!(a.contains(&"x"));
+    /// ```
+    pub fn remove(&mut self, elem: &T) -> bool {
+        match self.elements.remove(elem) {
+            Some(tags) => {
+                self.removed.extend(tags);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<T: Ord + Clone> Crdt for ORSet<T> {
+    fn merge(&mut self, other: &Self) {
+        self.removed.extend(other.removed.iter().copied());
+        for (elem, tags) in &other.elements {
+            self.elements
+                .entry(elem.clone())
+                .or_default()
+                .extend(tags.iter().copied());
+        }
+        let removed = &self.removed;
+        self.elements.retain(|_, tags| {
+            tags.retain(|t| !removed.contains(t));
+            !tags.is_empty()
+        });
+        for (&replica, &n) in &other.clock {
+            let count = self.clock.entry(replica).or_insert(0);
+            *count = (*count).max(n);
+        }
+    }
+}
+
+/// Last-writer-wins register. Every write carries a timestamp, and the write with the highest timestamp wins; equal
+/// timestamps are ordered by replica id.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LWWRegister<T> {
+    value: T,
+    timestamp: u64,
+    replica: ReplicaId,
+}
+
+impl<T> LWWRegister<T> {
+    /// Constructs a new register holding the value written by the replica at the timestamp.
+    pub fn new(value: T, timestamp: u64, replica: ReplicaId) -> Self {
+        Self {
+            value,
+            timestamp,
+            replica,
+        }
+    }
+
+    /// Returns the current value.
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the timestamp of the current value.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Writes the value unless the register already holds a later write. Returns whether the value was written.
+    /// ```
+    /// # use strctr::crdt::LWWRegister;
+    /// let mut r = LWWRegister::new("a", 10, 1);
+    /// assert!(!r.set("b", 5, 1));
+    /// assert!(r.set("c", 10, 2));
+    /// assert_eq!(*r.get(), "c");
+    /// ```
+    pub fn set(&mut self, value: T, timestamp: u64, replica: ReplicaId) -> bool {
+        if (timestamp, replica) <= (self.timestamp, self.replica) {
+            return false;
+        }
+        *self = Self::new(value, timestamp, replica);
+        true
+    }
+}
+
+impl<T: Clone> Crdt for LWWRegister<T> {
+    fn merge(&mut self, other: &Self) {
+        self.set(other.value.clone(), other.timestamp, other.replica);
+    }
+}
+
+/// Map whose entries are [`LWWRegister`]s. Removal writes a tombstone, so a removal wins over earlier writes of the
+/// same key and loses to later ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LWWMap<K: Ord, V> {
+    entries: BTreeMap<K, LWWRegister<Option<V>>>,
+}
+
+impl<K: Ord, V> Default for LWWMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> LWWMap<K, V> {
+    /// Constructs a new, empty map.
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of live entries.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns whether the map has no live entries.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Returns the value of the key.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key)?.get().as_ref()
+    }
+
+    /// Returns an iterator over the live entries, in ascending key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries
+            .iter()
+            .filter_map(|(k, r)| r.get().as_ref().map(|v| (k, v)))
+    }
+
+    /// Writes the value of the key unless the key already holds a later write.
+    /// ```
+    /// # use strctr::crdt::{Crdt, LWWMap};
+    /// let mut a = LWWMap::new();
+    /// let mut b = LWWMap::new();
+    /// a.insert("color", "red", 1, 1);
+    /// b.insert("color", "blue", 2, 2);
+    /// a.remove("color", 3, 1);
+    /// b.merge(&a);
+    /// assert_eq!(b.get(&"color"), None);
+    /// ```
+    pub fn insert(&mut self, key: K, value: V, timestamp: u64, replica: ReplicaId) {
+        self.write(key, Some(value), timestamp, replica);
+    }
+
+    /// Removes the key unless it already holds a later write.
+    pub fn remove(&mut self, key: K, timestamp: u64, replica: ReplicaId) {
+        self.write(key, None, timestamp, replica);
+    }
+
+    fn write(&mut self, key: K, value: Option<V>, timestamp: u64, replica: ReplicaId) {
+        match self.entries.get_mut(&key) {
+            Some(register) => {
+                register.set(value, timestamp, replica);
+            }
+            None => {
+                self.entries
+                    .insert(key, LWWRegister::new(value, timestamp, replica));
+            }
+        }
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Crdt for LWWMap<K, V> {
+    fn merge(&mut self, other: &Self) {
+        for (key, register) in &other.entries {
+            match self.entries.get_mut(key) {
+                Some(own) => own.merge(register),
+                None => {
+                    self.entries.insert(key.clone(), register.clone());
+                }
+            }
+        }
+    }
+}
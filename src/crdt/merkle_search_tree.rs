@@ -0,0 +1,477 @@
+//! Merkle Search Tree: an ordered set whose shape depends only on its keys, for set reconciliation.
+
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::io::{self, Read, Write};
+
+use super::Crdt;
+use crate::snapshot::{Codec, SnapshotError};
+
+/// Hash of a key or a page. Hashes are computed from the [`Codec`] encoding, so every replica and every build of this
+/// crate agrees on them.
+pub type Digest = u128;
+
+/// Hashes bytes with 128-bit FNV-1a, followed by a finalizer. FNV-1a alone barely changes the high bits of the hash
+/// of short inputs, which the levels of keys are taken from.
+fn fnv(bytes: &[u8]) -> Digest {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    let hash = bytes
+        .iter()
+        .fold(OFFSET, |hash, &b| (hash ^ b as u128).wrapping_mul(PRIME));
+    let (mut high, mut low) = ((hash >> 64) as u64, hash as u64);
+    high = fmix64(high ^ low.rotate_left(32));
+    low = fmix64(low ^ high);
+    high = fmix64(high ^ low);
+    (high as u128) << 64 | low as u128
+}
+
+/// The 64-bit finalizer of MurmurHash3, in which every input bit affects every output bit.
+fn fmix64(mut h: u64) -> u64 {
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51afd7ed558ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ceb9fe1a85ec53);
+    h ^ (h >> 33)
+}
+
+fn digest<T: Codec>(value: &T) -> Digest {
+    let mut bytes = Vec::new();
+    value.encode(&mut bytes).expect("writing to a vector");
+    fnv(&bytes)
+}
+
+/// List of errors that could occur when reconciling with another replica's [`MerkleSearchTree`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReconcileError<E> {
+    /// Fetching a page failed.
+    Fetch(E),
+    /// The page fetched for the digest has a different digest, so the other replica is corrupt or lying.
+    WrongPage(Digest),
+}
+
+/// A node of a [`MerkleSearchTree`], addressed by its [`Digest`]: the keys of one level in a range, and between and
+/// around them the roots of the subtrees holding the keys of lower levels.
+///
+/// Pages are what replicas exchange to reconcile, so they implement [`Codec`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Page<K> {
+    level: u32,
+    keys: Vec<K>,
+    children: Vec<Option<Digest>>,
+}
+
+impl<K> Page<K> {
+    /// Returns the level of the page's keys.
+    /// ```
+    /// # use strctr::crdt::MerkleSearchTree;
+    /// let set: MerkleSearchTree<u32> = (0..10_000).collect();
+    /// let (mut pages, mut keys_per_level) = (0, [0; 33]);
+    /// let mut stack = vec![set.root().unwrap()];
+    /// while let Some(digest) = stack.pop() {
+    ///     let page = set.page(&digest).unwrap();
+    ///     pages += 1;
+    ///     keys_per_level[page.level() as usize] += page.keys().len();
+    ///     stack.extend(page.children().iter().flatten());
+    /// }
+    /// // Each level holds about a sixteenth of the keys of the one below, and level 0 is split into many pages.
+    /// assert!((9_000..9_700).contains(&keys_per_level[0]));
+    /// assert!((400..800).contains(&keys_per_level[1]));
+    /// assert!((10..80).contains(&keys_per_level[2]));
+    /// assert!(pages > 400);
+    /// ```
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// Returns the page's keys, in order.
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+
+    /// Returns the digests of the subtrees before, between and after the keys, `None` for empty ones.
+    pub fn children(&self) -> &[Option<Digest>] {
+        &self.children
+    }
+}
+
+impl<K: Codec> Codec for Page<K> {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.level.encode(w)?;
+        self.keys.encode(w)?;
+        self.children.encode(w)
+    }
+
+    fn decode<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let level = u32::decode(r)?;
+        let keys: Vec<K> = Vec::decode(r)?;
+        let children: Vec<Option<Digest>> = Vec::decode(r)?;
+        if keys.is_empty() || children.len() != keys.len() + 1 {
+            return Err(SnapshotError::Corrupt);
+        }
+        Ok(Self {
+            level,
+            keys,
+            children,
+        })
+    }
+}
+
+/// An ordered set stored as a Merkle Search Tree, so that two replicas can find the keys they differ in by exchanging
+/// only the pages on the paths to those keys.
+///
+/// Every key gets a level from its hash, the number of leading zero hex digits, so that a sixteenth of the keys
+/// are on level 1 or above, a 256th on level 2 or above, and so on. Keys of the highest level form the root page,
+/// and the keys between two of them form a subtree built the same way from the next levels. The tree's shape, and with
+/// it every page's digest, thus depends only on the set of keys, not on the order they were inserted in: equal sets
+/// have equal [root digests](`Self::root()`), and equal subtrees equal digests.
+///
+/// A replica reconciles with another by fetching the other's pages from its root down, skipping every page it has
+/// itself, since equal digests mean equal subtrees. With `d` differing keys, that fetches `O(d log n)` pages, found by
+/// [missing_keys()](`Self::missing_keys()`). Used as a grow-only set, the tree is a [`Crdt`] whose merge is the
+/// union.
+/// ```
+/// # use strctr::crdt::{Crdt, MerkleSearchTree};
+/// let mut a: MerkleSearchTree<u32> = (0..1000).collect();
+/// let mut b: MerkleSearchTree<u32> = (0..1000).rev().collect();
+/// assert_eq!(a.root(), b.root());
+///
+/// a.insert(5000);
+/// b.insert(6000);
+/// // Only the pages on the path to the new key are fetched, of the dozens in the tree.
+/// let mut fetched = 0;
+/// let missing = a.missing_keys(b.root(), |digest| {
+///     fetched += 1;
+///     Ok::<_, ()>(b.page(digest).unwrap().clone())
+/// });
+/// assert_eq!(missing, Ok(vec![6000]));
+/// let depth = b.page(&b.root().unwrap()).unwrap().level() + 1;
+/// assert!(fetched <= depth && depth < 6);
+///
+/// a.merge(&b);
+/// b.merge(&a);
+/// assert_eq!(a.root(), b.root());
+/// assert_eq!(a.len(), 1002);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MerkleSearchTree<K> {
+    pages: HashMap<Digest, Page<K>>,
+    root: Option<Digest>,
+    len: usize,
+}
+
+impl<K> Default for MerkleSearchTree<K> {
+    fn default() -> Self {
+        Self {
+            pages: HashMap::new(),
+            root: None,
+            len: 0,
+        }
+    }
+}
+
+impl<K: Codec + Ord + Clone> MerkleSearchTree<K> {
+    /// Constructs a new, empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of keys.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the set holds no keys.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the digest of the root page, or `None` if the set is empty. Sets with the same keys have the same root
+    /// digest.
+    pub fn root(&self) -> Option<Digest> {
+        self.root
+    }
+
+    /// Returns the page with the digest, or `None` if it is not part of the tree.
+    pub fn page(&self, digest: &Digest) -> Option<&Page<K>> {
+        self.pages.get(digest)
+    }
+
+    /// Returns whether the set contains the key.
+    pub fn contains(&self, key: &K) -> bool {
+        let mut node = self.root;
+        while let Some(digest) = node {
+            let page = &self.pages[&digest];
+            match page.keys.binary_search(key) {
+                Ok(_) => return true,
+                Err(i) => node = page.children[i],
+            }
+        }
+        false
+    }
+
+    /// Adds the key to the set, returning whether it was new. Rebuilds the pages on its path in `O(log n)`.
+    pub fn insert(&mut self, key: K) -> bool {
+        if self.contains(&key) {
+            return false;
+        }
+        let level = level(&key);
+        self.root = self.insert_at(self.root, key, level);
+        self.len += 1;
+        true
+    }
+
+    /// Removes the key from the set, returning whether it was present. [merge()](`Crdt::merge()`) is a union, so
+    /// merging with a replica that still has the key adds it back.
+    /// ```
+    /// # use strctr::crdt::MerkleSearchTree;
+    /// let mut a: MerkleSearchTree<u64> = [1, 2].into_iter().collect();
+    /// let b: MerkleSearchTree<u64> = [2].into_iter().collect();
+    /// assert!(a.remove(&1));
+    /// assert_eq!(a.root(), b.root());
+    /// ```
+    pub fn remove(&mut self, key: &K) -> bool {
+        if !self.contains(key) {
+            return false;
+        }
+        self.root = self.remove_at(self.root.expect("the key is in the tree"), key);
+        self.len -= 1;
+        true
+    }
+
+    /// Returns an iterator over the keys, in order.
+    pub fn iter(&self) -> MerkleIter<'_, K> {
+        let mut iter = MerkleIter {
+            pages: &self.pages,
+            stack: Vec::new(),
+        };
+        iter.descend(self.root);
+        iter
+    }
+
+    /// Walks another replica's tree from its root, fetching every page that this tree lacks, and returns the keys of
+    /// those pages that this set lacks, in order. Pages this tree has are skipped with all their subtrees, so the
+    /// number of fetches grows with the number of differing keys, not with the size of the sets.
+    ///
+    /// Stops at the first error returned by `fetch`, or at the first page whose digest is not the one it was fetched
+    /// for. Every digest is fetched at most once, so a faulty replica cannot make the walk loop.
+    /// ```
+    /// # use strctr::crdt::{MerkleSearchTree, ReconcileError};
+    /// let a: MerkleSearchTree<u32> = (0..10).collect();
+    /// let b: MerkleSearchTree<u32> = (5..20).collect();
+    /// // A replica answering with the wrong page is caught.
+    /// let forged = a.page(&a.root().unwrap()).unwrap().clone();
+    /// let result = a.missing_keys(b.root(), |_| Ok::<_, ()>(forged.clone()));
+    /// assert_eq!(result, Err(ReconcileError::WrongPage(b.root().unwrap())));
+    /// ```
+    pub fn missing_keys<E>(
+        &self,
+        root: Option<Digest>,
+        mut fetch: impl FnMut(&Digest) -> Result<Page<K>, E>,
+    ) -> Result<Vec<K>, ReconcileError<E>> {
+        let mut missing = Vec::new();
+        let mut visited = HashSet::new();
+        let mut stack: Vec<Digest> = root.into_iter().collect();
+        while let Some(digest) = stack.pop() {
+            if self.pages.contains_key(&digest) || !visited.insert(digest) {
+                continue;
+            }
+            let page = fetch(&digest).map_err(ReconcileError::Fetch)?;
+            if self::digest(&page) != digest {
+                return Err(ReconcileError::WrongPage(digest));
+            }
+            stack.extend(page.children.iter().flatten());
+            missing.extend(page.keys.into_iter().filter(|key| !self.contains(key)));
+        }
+        missing.sort_unstable();
+        Ok(missing)
+    }
+
+    fn level(&self, digest: Digest) -> u32 {
+        self.pages[&digest].level
+    }
+
+    /// Stores the page and returns its digest, or returns its only child if it has no keys.
+    fn put(&mut self, page: Page<K>) -> Option<Digest> {
+        if page.keys.is_empty() {
+            return page.children[0];
+        }
+        let digest = digest(&page);
+        self.pages.insert(digest, page);
+        Some(digest)
+    }
+
+    /// Removes the page from the store, for it to be replaced.
+    fn take(&mut self, digest: Digest) -> Page<K> {
+        self.pages.remove(&digest).expect("a page of the tree")
+    }
+
+    fn insert_at(&mut self, node: Option<Digest>, key: K, level: u32) -> Option<Digest> {
+        let Some(digest) = node else {
+            return self.put(Page {
+                level,
+                keys: vec![key],
+                children: vec![None, None],
+            });
+        };
+        if self.level(digest) < level {
+            let (left, right) = self.split(node, &key);
+            return self.put(Page {
+                level,
+                keys: vec![key],
+                children: vec![left, right],
+            });
+        }
+        let mut page = self.take(digest);
+        let i = page.keys.binary_search(&key).expect_err("a new key");
+        if page.level == level {
+            let (left, right) = self.split(page.children[i], &key);
+            page.keys.insert(i, key);
+            page.children[i] = left;
+            page.children.insert(i + 1, right);
+        } else {
+            page.children[i] = self.insert_at(page.children[i], key, level);
+        }
+        self.put(page)
+    }
+
+    /// Splits the subtree into the keys before and after the key, which is not in it.
+    fn split(&mut self, node: Option<Digest>, key: &K) -> (Option<Digest>, Option<Digest>) {
+        let Some(digest) = node else {
+            return (None, None);
+        };
+        let mut page = self.take(digest);
+        let i = page.keys.binary_search(key).expect_err("a new key");
+        let (left, right) = self.split(page.children[i], key);
+        let mut right_children = vec![right];
+        right_children.extend(page.children.drain(i + 1..));
+        page.children[i] = left;
+        let right = Page {
+            level: page.level,
+            keys: page.keys.split_off(i),
+            children: right_children,
+        };
+        (self.put(page), self.put(right))
+    }
+
+    fn remove_at(&mut self, digest: Digest, key: &K) -> Option<Digest> {
+        let mut page = self.take(digest);
+        match page.keys.binary_search(key) {
+            Ok(i) => {
+                page.keys.remove(i);
+                let right = page.children.remove(i + 1);
+                page.children[i] = self.merge_trees(page.children[i], right);
+            }
+            Err(i) => {
+                let child = page.children[i].expect("the key is in the subtree");
+                page.children[i] = self.remove_at(child, key);
+            }
+        }
+        self.put(page)
+    }
+
+    /// Joins two subtrees, all keys of the first being smaller than those of the second.
+    fn merge_trees(&mut self, left: Option<Digest>, right: Option<Digest>) -> Option<Digest> {
+        let (Some(l), Some(r)) = (left, right) else {
+            return left.or(right);
+        };
+        let (left_level, right_level) = (self.level(l), self.level(r));
+        if left_level > right_level {
+            let mut page = self.take(l);
+            let last = page.children.pop().expect("a page has children");
+            page.children.push(self.merge_trees(last, right));
+            self.put(page)
+        } else if right_level > left_level {
+            let mut page = self.take(r);
+            page.children[0] = self.merge_trees(left, page.children[0]);
+            self.put(page)
+        } else {
+            let mut page = self.take(l);
+            let mut right = self.take(r);
+            let last = page.children.pop().expect("a page has children");
+            let middle = self.merge_trees(last, right.children[0]);
+            page.keys.append(&mut right.keys);
+            page.children.push(middle);
+            page.children.extend(right.children.drain(1..));
+            self.put(page)
+        }
+    }
+}
+
+/// Returns the level of the key: the number of leading zero hex digits of its hash.
+fn level<K: Codec>(key: &K) -> u32 {
+    digest(key).leading_zeros() / 4
+}
+
+impl<K: Codec + Ord + Clone> Crdt for MerkleSearchTree<K> {
+    /// Adds the keys of the other set, fetching only the pages this tree lacks.
+    fn merge(&mut self, other: &Self) {
+        let missing = self.missing_keys(other.root, |digest| {
+            Ok::<_, Infallible>(other.pages[digest].clone())
+        });
+        for key in missing.expect("the other tree's pages match their digests") {
+            self.insert(key);
+        }
+    }
+}
+
+impl<K: Codec + Ord + Clone> PartialEq for MerkleSearchTree<K> {
+    /// Compares the root digests.
+    fn eq(&self, other: &Self) -> bool {
+        self.root == other.root
+    }
+}
+
+impl<K: Codec + Ord + Clone> Eq for MerkleSearchTree<K> {}
+
+impl<K: Codec + Ord + Clone> Extend<K> for MerkleSearchTree<K> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+impl<K: Codec + Ord + Clone> FromIterator<K> for MerkleSearchTree<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+/// Iterator over the keys of a [`MerkleSearchTree`], created by [iter()](`MerkleSearchTree::iter()`).
+pub struct MerkleIter<'a, K> {
+    pages: &'a HashMap<Digest, Page<K>>,
+    /// Pages on the path to the next key, with the index of their next key.
+    stack: Vec<(&'a Page<K>, usize)>,
+}
+
+impl<'a, K> MerkleIter<'a, K> {
+    fn descend(&mut self, mut node: Option<Digest>) {
+        while let Some(digest) = node {
+            let page = &self.pages[&digest];
+            self.stack.push((page, 0));
+            node = page.children[0];
+        }
+    }
+}
+
+impl<'a, K> Iterator for MerkleIter<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (page, i) = self.stack.last_mut()?;
+            let page: &'a Page<K> = page;
+            if *i < page.keys.len() {
+                let key = &page.keys[*i];
+                *i += 1;
+                let child = page.children[*i];
+                self.descend(child);
+                return Some(key);
+            }
+            self.stack.pop();
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! Replicated Growable Array, a sequence CRDT for collaborative editing.
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{Crdt, ReplicaId};
+
+/// Globally unique identifier of an element of an [`Rga`]: a Lamport timestamp paired with the inserting replica.
+/// Later insertions get larger ids.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ElementId {
+    counter: u64,
+    replica: ReplicaId,
+}
+
+/// An edit made by one replica, to be applied by all others with [apply()](`Rga::apply()`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RgaOp<T> {
+    /// Inserts the value right after the `origin` element, or at the front if there is none.
+    Insert {
+        id: ElementId,
+        origin: Option<ElementId>,
+        value: T,
+    },
+    /// Removes the element.
+    Remove { id: ElementId },
+}
+
+/// List of errors that could occur when applying an [`RgaOp`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum RgaError {
+    /// The operation refers to an element this replica has not seen yet. Operations have to be applied in causal
+    /// order: an element's insertion before anything that refers to it.
+    UnknownElement,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Element<T> {
+    id: ElementId,
+    origin: Option<ElementId>,
+    value: T,
+    removed: bool,
+}
+
+/// A replicated sequence. Local edits return the [`RgaOp`] to broadcast, and applying every replica's operations
+/// makes all replicas converge on the same sequence, however concurrent edits interleave.
+///
+/// Removed elements are kept as tombstones, since concurrent insertions may still refer to them. Lookups by index
+/// or id are linear in the number of elements, tombstones included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rga<T> {
+    replica: ReplicaId,
+    /// Highest counter seen in any id, local or remote.
+    clock: u64,
+    /// All elements in sequence order, tombstones included.
+    elements: Vec<Element<T>>,
+    len: usize,
+}
+
+impl<T: Clone> Rga<T> {
+    /// Constructs a new, empty sequence edited by the replica.
+    pub fn new(replica: ReplicaId) -> Self {
+        Self {
+            replica,
+            clock: 0,
+            elements: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the replica editing this copy of the sequence.
+    pub fn replica(&self) -> ReplicaId {
+        self.replica
+    }
+
+    /// Returns the number of elements, not counting tombstones.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the sequence has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the element at the index.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.iter().nth(index)
+    }
+
+    /// Returns an iterator over the elements, in sequence order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.elements
+            .iter()
+            .filter(|e| !e.removed)
+            .map(|e| &e.value)
+    }
+
+    /// Inserts the value at the index, shifting later elements, and returns the operation to send to other
+    /// replicas.
+    ///
+    /// Panics if the index is greater than [len()](`Self::len()`).
+    /// ```
+    /// # use strctr::crdt::Rga;
+    /// let mut a = Rga::new(1);
+    /// let mut b = Rga::new(2);
+    /// let op = a.insert(0, 'x');
+    /// b.apply(op).unwrap();
+    /// // Both replicas insert at the front at the same time.
+    /// let op_a = a.insert(0, 'a');
+    /// let op_b = b.insert(0, 'b');
+    /// a.apply(op_b).unwrap();
+    /// b.apply(op_a).unwrap();
+    /// assert_eq!(a.to_string(), "bax");
+    /// assert_eq!(b.to_string(), "bax");
+    /// ```
+    pub fn insert(&mut self, index: usize, value: T) -> RgaOp<T> {
+        if index > self.len {
+            panic!(
+                "OutOfBounds: Wanted to insert at {}, but length is {}",
+                index, self.len
+            );
+        }
+        let origin = match index {
+            0 => None,
+            _ => Some(self.elements[self.position(index - 1)].id),
+        };
+        self.clock += 1;
+        let id = ElementId {
+            counter: self.clock,
+            replica: self.replica,
+        };
+        let op = RgaOp::Insert {
+            id,
+            origin,
+            value: value.clone(),
+        };
+        self.integrate(id, origin, value)
+            .expect("origin is a local element");
+        op
+    }
+
+    /// Removes the element at the index and returns the operation to send to other replicas.
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn remove(&mut self, index: usize) -> RgaOp<T> {
+        if index >= self.len {
+            panic!(
+                "OutOfBounds: Wanted to remove {}, but length is {}",
+                index, self.len
+            );
+        }
+        let i = self.position(index);
+        self.elements[i].removed = true;
+        self.len -= 1;
+        RgaOp::Remove {
+            id: self.elements[i].id,
+        }
+    }
+
+    /// Applies an operation made by another replica. Applying an operation more than once has no further effect.
+    pub fn apply(&mut self, op: RgaOp<T>) -> Result<(), RgaError> {
+        match op {
+            RgaOp::Insert { id, origin, value } => self.integrate(id, origin, value),
+            RgaOp::Remove { id } => {
+                let i = self.find(id).ok_or(RgaError::UnknownElement)?;
+                if !self.elements[i].removed {
+                    self.elements[i].removed = true;
+                    self.len -= 1;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the position in `elements` of the visible element at the index.
+    fn position(&self, index: usize) -> usize {
+        self.elements
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !e.removed)
+            .nth(index)
+            .map(|(i, _)| i)
+            .expect("index within bounds")
+    }
+
+    fn find(&self, id: ElementId) -> Option<usize> {
+        self.elements.iter().position(|e| e.id == id)
+    }
+
+    /// Places a new element after its origin. Concurrent insertions after the same origin are ordered by descending
+    /// id, so the scan skips every following element with a larger id: those are either such siblings or inserted
+    /// after them, and in both cases belong in front of the new element.
+    fn integrate(
+        &mut self,
+        id: ElementId,
+        origin: Option<ElementId>,
+        value: T,
+    ) -> Result<(), RgaError> {
+        if self.find(id).is_some() {
+            return Ok(());
+        }
+        let mut i = match origin {
+            Some(origin) => self.find(origin).ok_or(RgaError::UnknownElement)? + 1,
+            None => 0,
+        };
+        while i < self.elements.len() && self.elements[i].id > id {
+            i += 1;
+        }
+        self.elements.insert(
+            i,
+            Element {
+                id,
+                origin,
+                value,
+                removed: false,
+            },
+        );
+        self.clock = self.clock.max(id.counter);
+        self.len += 1;
+        Ok(())
+    }
+}
+
+impl Rga<char> {
+    /// Inserts the string at the index, one element per `char`, and returns the operations to send to other
+    /// replicas.
+    ///
+    /// Panics if the index is greater than [len()](`Self::len()`).
+    /// ```
+    /// # use strctr::crdt::Rga;
+    /// let mut doc = Rga::new(1);
+    /// doc.insert_str(0, "word");
+    /// doc.insert_str(3, "l");
+    /// doc.insert_str(0, "hello ");
+    /// assert_eq!(doc.to_string(), "hello world");
+    /// ```
+    pub fn insert_str(&mut self, index: usize, s: &str) -> Vec<RgaOp<char>> {
+        s.chars()
+            .enumerate()
+            .map(|(i, c)| self.insert(index + i, c))
+            .collect()
+    }
+}
+
+impl fmt::Display for Rga<char> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.iter().try_for_each(|c| fmt::Write::write_char(f, *c))
+    }
+}
+
+impl<T: Clone> Crdt for Rga<T> {
+    /// Merges the other replica's full state. Its elements are in sequence order, so every origin is integrated
+    /// before the elements inserted after it.
+    fn merge(&mut self, other: &Self) {
+        for e in &other.elements {
+            self.integrate(e.id, e.origin, e.value.clone())
+                .expect("origins precede their elements");
+        }
+        for e in other.elements.iter().filter(|e| e.removed) {
+            self.apply(RgaOp::Remove { id: e.id })
+                .expect("element was merged");
+        }
+    }
+}
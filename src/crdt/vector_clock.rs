@@ -0,0 +1,153 @@
+//! Vector clocks for tracking causality between replicas.
+
+use std::cmp::Ordering;
+
+use super::{Crdt, ReplicaId};
+use crate::btree::BTreeMap;
+
+/// A vector clock: one event counter per replica. Comparing two clocks tells whether one state causally precedes
+/// the other or whether they are concurrent. Clocks are only partially ordered, so [`PartialOrd`] returns `None` for
+/// concurrent clocks.
+///
+/// Replicas missing from the clock count as 0, so clocks only store replicas that have recorded events.
+#[derive(Clone, Debug, Default)]
+pub struct VectorClock {
+    counters: BTreeMap<ReplicaId, u64>,
+}
+
+/// A version vector, tracking which updates of every replica a state includes. It is the same structure as a
+/// [`VectorClock`], counting updates instead of events.
+pub type VersionVector = VectorClock;
+
+impl VectorClock {
+    /// Constructs a new clock with all counters at 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the counter of the replica.
+    pub fn get(&self, replica: ReplicaId) -> u64 {
+        self.counters.get(&replica).copied().unwrap_or(0)
+    }
+
+    /// Records an event on the replica and returns its new counter.
+    pub fn increment(&mut self, replica: ReplicaId) -> u64 {
+        match self.counters.get_mut(&replica) {
+            Some(n) => {
+                *n += 1;
+                *n
+            }
+            None => {
+                self.counters.insert(replica, 1);
+                1
+            }
+        }
+    }
+
+    /// Returns an iterator over the replicas with a non-zero counter, in ascending replica order.
+    pub fn iter(&self) -> impl Iterator<Item = (ReplicaId, u64)> + '_ {
+        self.counters.iter().map(|(&r, &n)| (r, n))
+    }
+
+    /// Returns whether this clock causally precedes the other: every counter is at most the other's, and they are
+    /// not equal.
+    /// ```
+    /// # use strctr::crdt::{Crdt, VectorClock};
+    /// let mut a = VectorClock::new();
+    /// a.increment(1);
+    /// let mut b = a.clone();
+    /// b.increment(2);
+    /// assert!(a.happens_before(&b));
+    /// assert!(!b.happens_before(&a));
+    /// a.increment(1);
+    /// assert!(a.concurrent_with(&b));
+    /// b.merge(&a);
+    /// assert!(a.happens_before(&b));
+    /// ```
+    pub fn happens_before(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Less)
+    }
+
+    /// Returns whether neither clock causally precedes the other, meaning their events happened independently.
+    pub fn concurrent_with(&self, other: &Self) -> bool {
+        self.partial_cmp(other).is_none()
+    }
+}
+
+impl Crdt for VectorClock {
+    /// Takes the larger counter of every replica, yielding the earliest clock that follows both.
+    fn merge(&mut self, other: &Self) {
+        for (replica, n) in other.iter() {
+            match self.counters.get_mut(&replica) {
+                Some(own) => *own = (*own).max(n),
+                None => {
+                    self.counters.insert(replica, n);
+                }
+            }
+        }
+    }
+}
+
+impl PartialEq for VectorClock {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl Eq for VectorClock {}
+
+impl PartialOrd for VectorClock {
+    /// Compares the clocks counter by counter.
+    /// ```
+    /// # use strctr::crdt::VectorClock;
+    /// # use std::cmp::Ordering;
+    /// let mut a = VectorClock::new();
+    /// let mut b = VectorClock::new();
+    /// assert_eq!(a.partial_cmp(&b), Some(Ordering::Equal));
+    /// a.increment(1);
+    /// assert!(a > b);
+    /// b.increment(2);
+    /// assert_eq!(a.partial_cmp(&b), None);
+    /// ```
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let mut less = false;
+        let mut greater = false;
+        let replicas = self.counters.keys().chain(other.counters.keys());
+        for &replica in replicas {
+            match self.get(replica).cmp(&other.get(replica)) {
+                Ordering::Less => less = true,
+                Ordering::Greater => greater = true,
+                Ordering::Equal => {}
+            }
+        }
+        match (less, greater) {
+            (false, false) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Less),
+            (false, true) => Some(Ordering::Greater),
+            (true, true) => None,
+        }
+    }
+}
+
+impl FromIterator<(ReplicaId, u64)> for VectorClock {
+    fn from_iter<I: IntoIterator<Item = (ReplicaId, u64)>>(iter: I) -> Self {
+        Self {
+            counters: iter.into_iter().filter(|&(_, n)| n > 0).collect(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for VectorClock {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for VectorClock {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let counters = std::collections::BTreeMap::<ReplicaId, u64>::deserialize(deserializer)?;
+        Ok(counters.into_iter().collect())
+    }
+}
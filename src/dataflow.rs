@@ -0,0 +1,492 @@
+//! Incremental map/reduce over a changing collection.
+//!
+//! A [`Source`] is a map that records its changes, and hands them in batches to its subscribers when
+//! [flushed](`Source::flush()`). [`Operator`]s turn batches of changes to one collection into the changes to a
+//! collection derived from it: [`MapValues`] transforms values, [`Filter`] keeps the entries matching a predicate and
+//! [`GroupReduce`] reduces groups of entries to a value per group. Operators only do work for the entries that
+//! changed, and chain into pipelines with [then()](`Operator::then()`). A [`View`] materializes the output of a
+//! pipeline, and [Source::view()](`Source::view()`) keeps one up to date with the source.
+//! ```
+//! # use strctr::dataflow::{Filter, GroupReduce, Operator, Source};
+//! let mut orders = Source::new();
+//! let big = orders.view(Filter::new(|_, &(_, total): &(&str, u32)| total >= 100));
+//! let spent = orders.view(GroupReduce::new(
+//!     |_, &(customer, _): &(&str, u32)| customer,
+//!     |_, orders| orders.map(|(_, &(_, total))| total).sum::<u32>(),
+//! ));
+//!
+//! orders.insert(1, ("alice", 120));
+//! orders.insert(2, ("bob", 30));
+//! orders.insert(3, ("alice", 50));
+//! orders.flush();
+//! assert_eq!(big.borrow().len(), 1);
+//! assert_eq!(spent.borrow().get(&"alice"), Some(&170));
+//!
+//! orders.insert(3, ("bob", 50));
+//! orders.remove(&1);
+//! orders.flush();
+//! assert!(big.borrow().is_empty());
+//! assert_eq!(spent.borrow().iter().collect::<Vec<_>>(), [(&"bob", &80)]);
+//! ```
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use crate::btree::BTreeMap;
+
+/// A change to an entry of a collection. Replacing the value of a key is a removal of the old entry followed by an
+/// insertion of the new one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change<K, V> {
+    /// The entry was added.
+    Insert(K, V),
+    /// The entry was removed.
+    Remove(K, V),
+}
+
+/// Handle of a subscription to a [`Source`], to [unsubscribe()](`Source::unsubscribe()`) with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Subscription(u64);
+
+/// Subscriber callback, which returns whether it wants further batches.
+type Subscriber<K, V> = Box<dyn FnMut(&[Change<K, V>]) -> bool>;
+
+/// A map whose changes are delivered to subscribers.
+///
+/// Changes are collected until [flush()](`Source::flush()`), which hands them to every subscriber as one batch, so
+/// that derived collections are updated once per batch rather than once per change.
+pub struct Source<K, V> {
+    entries: BTreeMap<K, V>,
+    pending: Vec<Change<K, V>>,
+    subscribers: Vec<(Subscription, Subscriber<K, V>)>,
+    next_id: u64,
+}
+
+impl<K: Ord + Clone, V: Clone> Default for Source<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Source<K, V> {
+    /// Constructs a new, empty source without subscribers.
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            pending: Vec::new(),
+            subscribers: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the value of the key, or `None` if it is absent.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key)
+    }
+
+    /// Returns whether the key is present.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Returns an iterator over the entries, in ascending order of keys.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter()
+    }
+
+    /// Inserts a key-value pair. If the key was already present, its value is replaced and the old value is returned.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let old = self.entries.insert(key.clone(), value.clone());
+        if let Some(old) = &old {
+            self.pending.push(Change::Remove(key.clone(), old.clone()));
+        }
+        self.pending.push(Change::Insert(key, value));
+        old
+    }
+
+    /// Removes the key and returns its value, or `None` if it was absent.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let old = self.entries.remove(key)?;
+        self.pending.push(Change::Remove(key.clone(), old.clone()));
+        Some(old)
+    }
+
+    /// Returns the changes made since the last flush, in order.
+    pub fn pending(&self) -> &[Change<K, V>] {
+        &self.pending
+    }
+
+    /// Hands the changes made since the last flush to every subscriber, in the order they subscribed, and returns
+    /// them.
+    pub fn flush(&mut self) -> Vec<Change<K, V>> {
+        let changes = std::mem::take(&mut self.pending);
+        if !changes.is_empty() {
+            self.subscribers.retain_mut(|(_, notify)| notify(&changes));
+        }
+        changes
+    }
+
+    /// Registers a callback receiving every flushed batch of changes.
+    /// ```
+    /// # use std::cell::Cell;
+    /// # use std::rc::Rc;
+    /// # use strctr::dataflow::{Change, Source};
+    /// let mut source = Source::new();
+    /// let inserts = Rc::new(Cell::new(0));
+    /// let counter = inserts.clone();
+    /// let subscription = source.subscribe(move |changes: &[Change<u32, &str>]| {
+    ///     let n = changes.iter().filter(|c| matches!(c, Change::Insert(..))).count();
+    ///     counter.set(counter.get() + n);
+    /// });
+    ///
+    /// source.insert(1, "a");
+    /// source.insert(1, "b");
+    /// source.flush();
+    /// assert_eq!(inserts.get(), 2);
+    ///
+    /// assert!(source.unsubscribe(subscription));
+    /// source.insert(2, "c");
+    /// source.flush();
+    /// assert_eq!(inserts.get(), 2);
+    /// ```
+    pub fn subscribe(&mut self, mut notify: impl FnMut(&[Change<K, V>]) + 'static) -> Subscription {
+        self.add_subscriber(Box::new(move |changes| {
+            notify(changes);
+            true
+        }))
+    }
+
+    /// Removes the subscription. Returns whether it was still registered.
+    pub fn unsubscribe(&mut self, subscription: Subscription) -> bool {
+        let len = self.subscribers.len();
+        self.subscribers.retain(|(id, _)| *id != subscription);
+        self.subscribers.len() < len
+    }
+
+    /// Returns a view of the pipeline's output over the source, which is updated on every flush.
+    ///
+    /// Pending changes are flushed first, then the view is filled from the current entries. The source only holds
+    /// the view weakly: once it is dropped, the pipeline is unsubscribed.
+    pub fn view<O>(&mut self, mut pipeline: O) -> Rc<RefCell<View<O::Key, O::Value>>>
+    where
+        O: Operator<K, V> + 'static,
+        O::Key: Ord + Clone + 'static,
+        O::Value: Clone + 'static,
+    {
+        self.flush();
+        let initial: Vec<_> = self
+            .entries
+            .iter()
+            .map(|(k, v)| Change::Insert(k.clone(), v.clone()))
+            .collect();
+        let mut view = View::new();
+        view.apply(&pipeline.apply(&initial));
+        let view = Rc::new(RefCell::new(view));
+        let weak = Rc::downgrade(&view);
+        self.add_subscriber(Box::new(move |changes| match weak.upgrade() {
+            Some(view) => {
+                view.borrow_mut().apply(&pipeline.apply(changes));
+                true
+            }
+            None => false,
+        }));
+        view
+    }
+
+    fn add_subscriber(&mut self, notify: Subscriber<K, V>) -> Subscription {
+        let id = Subscription(self.next_id);
+        self.next_id += 1;
+        self.subscribers.push((id, notify));
+        id
+    }
+}
+
+/// A step of a pipeline, which turns the changes to its input collection into the changes to its output.
+///
+/// Operators may keep state across batches, and receive every change to their input exactly once, in order. An
+/// operator must never output two entries of the same key at once, so that its output is a map again.
+pub trait Operator<K, V> {
+    /// The type of the output keys.
+    type Key;
+    /// The type of the output values.
+    type Value;
+
+    /// Returns the changes to the output caused by the changes to the input.
+    fn apply(&mut self, changes: &[Change<K, V>]) -> Vec<Change<Self::Key, Self::Value>>;
+
+    /// Returns a pipeline passing this operator's output into the next one.
+    /// ```
+    /// # use strctr::dataflow::{Change, Filter, MapValues, Operator};
+    /// let mut pipeline = Filter::new(|_, v: &i32| *v > 0).then(MapValues::new(|_, v: &i32| v * 10));
+    /// let out = pipeline.apply(&[Change::Insert("a", 1), Change::Insert("b", -1)]);
+    /// assert_eq!(out, [Change::Insert("a", 10)]);
+    /// ```
+    fn then<O: Operator<Self::Key, Self::Value>>(self, next: O) -> Chain<Self, O>
+    where
+        Self: Sized,
+    {
+        Chain { first: self, next }
+    }
+}
+
+/// Pipeline of two operators, created by [then()](`Operator::then()`).
+pub struct Chain<A, B> {
+    first: A,
+    next: B,
+}
+
+impl<K, V, A: Operator<K, V>, B: Operator<A::Key, A::Value>> Operator<K, V> for Chain<A, B> {
+    type Key = B::Key;
+    type Value = B::Value;
+
+    fn apply(&mut self, changes: &[Change<K, V>]) -> Vec<Change<B::Key, B::Value>> {
+        let changes = self.first.apply(changes);
+        self.next.apply(&changes)
+    }
+}
+
+/// Operator transforming every value, keeping its key.
+///
+/// The function is applied to removed entries again to find the output to remove, so it should depend on nothing but
+/// the key and value.
+pub struct MapValues<F> {
+    f: F,
+}
+
+impl<F> MapValues<F> {
+    /// Constructs an operator mapping values with the function.
+    pub fn new<K, V, W>(f: F) -> Self
+    where
+        F: FnMut(&K, &V) -> W,
+    {
+        Self { f }
+    }
+}
+
+impl<K: Clone, V, W, F: FnMut(&K, &V) -> W> Operator<K, V> for MapValues<F> {
+    type Key = K;
+    type Value = W;
+
+    fn apply(&mut self, changes: &[Change<K, V>]) -> Vec<Change<K, W>> {
+        changes
+            .iter()
+            .map(|change| match change {
+                Change::Insert(k, v) => Change::Insert(k.clone(), (self.f)(k, v)),
+                Change::Remove(k, v) => Change::Remove(k.clone(), (self.f)(k, v)),
+            })
+            .collect()
+    }
+}
+
+/// Operator keeping the entries that match a predicate.
+///
+/// The predicate is applied to removed entries again to find whether they were kept, so it should depend on nothing
+/// but the key and value.
+pub struct Filter<F> {
+    predicate: F,
+}
+
+impl<F> Filter<F> {
+    /// Constructs an operator keeping the entries for which the predicate returns `true`.
+    pub fn new<K, V>(predicate: F) -> Self
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        Self { predicate }
+    }
+}
+
+impl<K: Clone, V: Clone, F: FnMut(&K, &V) -> bool> Operator<K, V> for Filter<F> {
+    type Key = K;
+    type Value = V;
+
+    fn apply(&mut self, changes: &[Change<K, V>]) -> Vec<Change<K, V>> {
+        changes
+            .iter()
+            .filter(|change| match change {
+                Change::Insert(k, v) | Change::Remove(k, v) => (self.predicate)(k, v),
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// Operator grouping entries by a key function, and reducing every group to a value, keyed by the group.
+///
+/// The operator keeps the entries of every group. A batch of changes reduces each group it touched once, from all of
+/// the group's entries, and outputs a change only if the group's value did. A group without entries has no value, and
+/// grouping all entries under `()` reduces the whole collection to a single value.
+/// ```
+/// # use strctr::dataflow::{Change, GroupReduce, Operator};
+/// let mut lengths = GroupReduce::new(|_, word: &&str| word.len(), |_, words| words.count());
+/// let out = lengths.apply(&[Change::Insert(1, "ab"), Change::Insert(2, "cd"), Change::Insert(3, "e")]);
+/// assert_eq!(out, [Change::Insert(1, 1), Change::Insert(2, 2)]);
+///
+/// let out = lengths.apply(&[Change::Remove(3, "e"), Change::Insert(3, "fg")]);
+/// assert_eq!(out, [Change::Remove(1, 1), Change::Remove(2, 2), Change::Insert(2, 3)]);
+/// ```
+pub struct GroupReduce<K, V, G, R, FG, FR> {
+    /// Entries of every group.
+    groups: BTreeMap<G, BTreeMap<K, V>>,
+    /// Value of every group.
+    results: BTreeMap<G, R>,
+    group: FG,
+    reduce: FR,
+}
+
+impl<K, V, G, R, FG, FR> GroupReduce<K, V, G, R, FG, FR>
+where
+    K: Ord,
+    G: Ord,
+    FG: FnMut(&K, &V) -> G,
+    FR: FnMut(&G, &mut dyn Iterator<Item = (&K, &V)>) -> R,
+{
+    /// Constructs an operator grouping entries with `group`, and reducing the entries of every group with `reduce`.
+    /// Like the predicate of a [`Filter`], `group` is applied to removed entries again to find their group.
+    pub fn new(group: FG, reduce: FR) -> Self {
+        Self {
+            groups: BTreeMap::new(),
+            results: BTreeMap::new(),
+            group,
+            reduce,
+        }
+    }
+}
+
+impl<K, V, G, R, FG, FR> Operator<K, V> for GroupReduce<K, V, G, R, FG, FR>
+where
+    K: Ord + Clone,
+    V: Clone,
+    G: Ord + Clone,
+    R: Clone + PartialEq,
+    FG: FnMut(&K, &V) -> G,
+    FR: FnMut(&G, &mut dyn Iterator<Item = (&K, &V)>) -> R,
+{
+    type Key = G;
+    type Value = R;
+
+    fn apply(&mut self, changes: &[Change<K, V>]) -> Vec<Change<G, R>> {
+        let mut touched = BTreeSet::new();
+        for change in changes {
+            match change {
+                Change::Insert(k, v) => {
+                    let g = (self.group)(k, v);
+                    match self.groups.get_mut(&g) {
+                        Some(entries) => {
+                            entries.insert(k.clone(), v.clone());
+                        }
+                        None => {
+                            let mut entries = BTreeMap::new();
+                            entries.insert(k.clone(), v.clone());
+                            self.groups.insert(g.clone(), entries);
+                        }
+                    }
+                    touched.insert(g);
+                }
+                Change::Remove(k, v) => {
+                    let g = (self.group)(k, v);
+                    if let Some(entries) = self.groups.get_mut(&g) {
+                        entries.remove(k);
+                        if entries.is_empty() {
+                            self.groups.remove(&g);
+                        }
+                    }
+                    touched.insert(g);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        for g in touched {
+            let new = self
+                .groups
+                .get(&g)
+                .map(|entries| (self.reduce)(&g, &mut entries.iter()));
+            let old = self.results.get(&g);
+            if old == new.as_ref() {
+                continue;
+            }
+            if let Some(old) = self.results.remove(&g) {
+                out.push(Change::Remove(g.clone(), old));
+            }
+            if let Some(new) = new {
+                self.results.insert(g.clone(), new.clone());
+                out.push(Change::Insert(g, new));
+            }
+        }
+        out
+    }
+}
+
+/// A collection materialized from a stream of changes, like the output of a pipeline.
+pub struct View<K, V> {
+    entries: BTreeMap<K, V>,
+}
+
+impl<K: Ord, V> Default for View<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> View<K, V> {
+    /// Constructs a new, empty view.
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Applies the changes, in order.
+    pub fn apply(&mut self, changes: &[Change<K, V>])
+    where
+        K: Clone,
+        V: Clone,
+    {
+        for change in changes {
+            match change {
+                Change::Insert(k, v) => {
+                    self.entries.insert(k.clone(), v.clone());
+                }
+                Change::Remove(k, _) => {
+                    self.entries.remove(k);
+                }
+            }
+        }
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Returns the value of the key, or `None` if it is absent.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key)
+    }
+
+    /// Returns an iterator over the entries, in ascending order of keys.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter()
+    }
+}
@@ -0,0 +1,204 @@
+//! Disjoint-set forest (union-find). Elements are numbered `0..len()` and grouped into sets that can only be merged.
+//! Path compression and union by rank make every operation effectively constant time.
+
+use std::io::{self, Read, Write};
+
+use crate::snapshot::{Codec, Snapshot, SnapshotError};
+
+/// A collection of disjoint sets over the elements `0..len()`.
+#[derive(Clone)]
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+    size: Vec<usize>,
+    count: usize,
+}
+
+impl Default for DisjointSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DisjointSet {
+    /// Constructs a new, empty collection.
+    pub fn new() -> Self {
+        Self::with_sets(0)
+    }
+
+    /// Constructs a collection of `n` singleton sets, numbered `0..n`.
+    /// ```
+    /// # use strctr::disjoint_set::DisjointSet;
+    /// let s = DisjointSet::with_sets(4);
+    /// assert_eq!(s.len(), 4);
+    /// assert_eq!(s.count(), 4);
+    /// ```
+    pub fn with_sets(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+            rank: vec![0; n],
+            size: vec![1; n],
+            count: n,
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    /// Returns whether there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Returns the number of disjoint sets.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Adds a new element in a set of its own and returns it.
+    /// ```
+    /// # use strctr::disjoint_set::DisjointSet;
+    /// let mut s = DisjointSet::new();
+    /// let a = s.make_set();
+    /// let b = s.make_set();
+    /// assert_eq!((a, b), (0, 1));
+    /// assert!(!s.same_set(a, b));
+    /// ```
+    pub fn make_set(&mut self) -> usize {
+        let x = self.parent.len();
+        self.parent.push(x);
+        self.rank.push(0);
+        self.size.push(1);
+        self.count += 1;
+        x
+    }
+
+    /// Returns the representative of the set containing the element. Two elements are in the same set exactly when
+    /// they have the same representative.
+    ///
+    /// Panics if the element is out of bounds.
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut cur = x;
+        while self.parent[cur] != root {
+            let next = self.parent[cur];
+            self.parent[cur] = root;
+            cur = next;
+        }
+        root
+    }
+
+    /// Merges the sets containing the two elements. Returns `false` if they already were in the same set.
+    ///
+    /// Panics if either element is out of bounds.
+    /// ```
+    /// # use strctr::disjoint_set::DisjointSet;
+    /// let mut s = DisjointSet::with_sets(3);
+    /// assert!(s.union(0, 1));
+    /// assert!(!s.union(1, 0));
+    /// assert_eq!(s.count(), 2);
+    /// ```
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a == b {
+            return false;
+        }
+        let (root, child) = if self.rank[a] >= self.rank[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parent[child] = root;
+        self.size[root] += self.size[child];
+        if self.rank[root] == self.rank[child] {
+            self.rank[root] += 1;
+        }
+        self.count -= 1;
+        true
+    }
+
+    /// Returns whether the two elements are in the same set.
+    ///
+    /// Panics if either element is out of bounds.
+    pub fn same_set(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Returns the number of elements in the set containing the element.
+    ///
+    /// Panics if the element is out of bounds.
+    /// ```
+    /// # use strctr::disjoint_set::DisjointSet;
+    /// let mut s = DisjointSet::with_sets(5);
+    /// s.union(0, 1);
+    /// s.union(1, 2);
+    /// assert_eq!(s.set_size(2), 3);
+    /// assert_eq!(s.set_size(4), 1);
+    /// ```
+    pub fn set_size(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+
+    /// Returns every set as a list of its elements. Sets are ordered by their smallest element, and elements within
+    /// a set are ascending.
+    /// ```
+    /// # use strctr::disjoint_set::DisjointSet;
+    /// let mut s = DisjointSet::with_sets(4);
+    /// s.union(3, 0);
+    /// assert_eq!(s.sets(), vec![vec![0, 3], vec![1], vec![2]]);
+    /// ```
+    pub fn sets(&mut self) -> Vec<Vec<usize>> {
+        let mut index = vec![usize::MAX; self.len()];
+        let mut sets: Vec<Vec<usize>> = Vec::with_capacity(self.count);
+        for x in 0..self.len() {
+            let root = self.find(x);
+            if index[root] == usize::MAX {
+                index[root] = sets.len();
+                sets.push(Vec::new());
+            }
+            sets[index[root]].push(x);
+        }
+        sets
+    }
+}
+
+impl Snapshot for DisjointSet {
+    const TAG: [u8; 4] = *b"DSET";
+
+    /// Writes the forest as it is, so that loading needs no unions.
+    fn write_payload<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.len().encode(w)?;
+        for x in 0..self.len() {
+            self.parent[x].encode(w)?;
+            self.rank[x].encode(w)?;
+            self.size[x].encode(w)?;
+        }
+        Ok(())
+    }
+
+    fn read_payload<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let len = usize::decode(r)?;
+        let mut set = Self::new();
+        for _ in 0..len {
+            set.parent.push(usize::decode(r)?);
+            set.rank.push(u8::decode(r)?);
+            set.size.push(usize::decode(r)?);
+        }
+        // Ranks strictly grow towards the roots, which rules out cycles.
+        for x in 0..len {
+            let p = set.parent[x];
+            if p >= len || (p != x && set.rank[p] <= set.rank[x]) {
+                return Err(SnapshotError::Corrupt);
+            }
+        }
+        set.count = (0..len).filter(|&x| set.parent[x] == x).count();
+        Ok(set)
+    }
+}
@@ -0,0 +1,321 @@
+//! JSON-like document values. Objects are stored in a [`BTreeMap`], so their keys are always kept in sorted order.
+//!
+//! With the `serde` feature enabled, values convert to and from [`serde_json::Value`].
+
+use std::fmt;
+
+use crate::btree::BTreeMap;
+
+/// Map type used for the members of a [`Value::Object`].
+pub type Object = BTreeMap<String, Value>;
+
+/// A JSON-like value.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum Value {
+    /// The absence of a value.
+    #[default]
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// A number. Like in JavaScript, every number is a 64-bit float.
+    Number(f64),
+    /// A string.
+    String(String),
+    /// An ordered list of values.
+    Array(Vec<Value>),
+    /// A collection of named values.
+    Object(Object),
+}
+
+impl Value {
+    /// Returns whether the value is [`Value::Null`].
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// Returns the boolean if the value is one.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the number if the value is one.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Returns the string if the value is one.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements if the value is an array.
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements mutably if the value is an array.
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Value>> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns the members if the value is an object.
+    pub fn as_object(&self) -> Option<&Object> {
+        match self {
+            Value::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// Returns the members mutably if the value is an object.
+    pub fn as_object_mut(&mut self) -> Option<&mut Object> {
+        match self {
+            Value::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// Looks up a value by a JSON pointer (RFC 6901), such as `/a/b/0`. The empty pointer refers to the whole value.
+    /// Inside a token, `~1` stands for `/` and `~0` for `~`.
+    /// ```
+    /// # use strctr::document::{Object, Value};
+    /// let mut inner = Object::new();
+    /// inner.insert("b".to_string(), Value::Array(vec![Value::from(1.0), Value::from(2.0)]));
+    /// let mut outer = Object::new();
+    /// outer.insert("a".to_string(), Value::Object(inner));
+    /// let doc = Value::Object(outer);
+    ///
+    /// assert_eq!(doc.pointer("/a/b/1"), Some(&Value::Number(2.0)));
+    /// assert_eq!(doc.pointer("/a/c"), None);
+    /// assert_eq!(doc.pointer(""), Some(&doc));
+    /// ```
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        let mut cur = self;
+        for token in tokens(pointer)? {
+            cur = match cur {
+                Value::Object(o) => o.get(&token)?,
+                Value::Array(a) => a.get(array_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(cur)
+    }
+
+    /// Looks up a value by a JSON pointer and returns a mutable reference to it. See [pointer()](`Self::pointer()`).
+    /// ```
+    /// # use strctr::document::Value;
+    /// let mut doc = Value::Array(vec![Value::Null, Value::Bool(false)]);
+    /// *doc.pointer_mut("/1").unwrap() = Value::Bool(true);
+    /// assert_eq!(doc.pointer("/1"), Some(&Value::Bool(true)));
+    /// ```
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        let mut cur = self;
+        for token in tokens(pointer)? {
+            cur = match cur {
+                Value::Object(o) => o.get_mut(&token)?,
+                Value::Array(a) => a.get_mut(array_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(cur)
+    }
+}
+
+/// Splits a JSON pointer into its unescaped reference tokens. Returns `None` if the pointer is malformed.
+fn tokens(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+    let rest = pointer.strip_prefix('/')?;
+    rest.split('/').map(unescape).collect()
+}
+
+fn unescape(token: &str) -> Option<String> {
+    let mut out = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        if c != '~' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '0' => out.push('~'),
+            '1' => out.push('/'),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+/// Parses an array index token. Leading zeros are not allowed, as per RFC 6901.
+fn array_index(token: &str) -> Option<usize> {
+    if token.len() > 1 && token.starts_with('0') {
+        return None;
+    }
+    if !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(n: i32) -> Self {
+        Value::Number(n.into())
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(a: Vec<Value>) -> Self {
+        Value::Array(a)
+    }
+}
+
+impl From<Object> for Value {
+    fn from(o: Object) -> Self {
+        Value::Object(o)
+    }
+}
+
+impl FromIterator<Value> for Value {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        Value::Array(iter.into_iter().collect())
+    }
+}
+
+impl FromIterator<(String, Value)> for Value {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(iter: I) -> Self {
+        Value::Object(iter.into_iter().collect())
+    }
+}
+
+/// Formats the value as compact JSON text. Numbers that JSON cannot represent (NaN and infinities) become `null`.
+/// ```
+/// # use strctr::document::Value;
+/// let doc: Value = [
+///     ("name".to_string(), Value::from("strctr")),
+///     ("tags".to_string(), Value::from(vec![Value::from(1), Value::Null])),
+/// ]
+/// .into_iter()
+/// .collect();
+/// assert_eq!(doc.to_string(), r#"{"name":"strctr","tags":[1,null]}"#);
+/// ```
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) if n.is_finite() => write!(f, "{}", n),
+            Value::Number(_) => f.write_str("null"),
+            Value::String(s) => write_escaped(f, s),
+            Value::Array(a) => {
+                f.write_str("[")?;
+                for (i, v) in a.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                f.write_str("]")
+            }
+            Value::Object(o) => {
+                f.write_str("{")?;
+                for (i, (k, v)) in o.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_escaped(f, k)?;
+                    write!(f, ":{}", v)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+fn write_escaped(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Value> for Value {
+    /// Converts a `serde_json` value. Integers that do not fit into an `f64` exactly lose precision.
+    fn from(v: serde_json::Value) -> Self {
+        match v {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Bool(b),
+            serde_json::Value::Number(n) => Value::Number(n.as_f64().unwrap_or(f64::NAN)),
+            serde_json::Value::String(s) => Value::String(s),
+            serde_json::Value::Array(a) => a.into_iter().map(Value::from).collect(),
+            serde_json::Value::Object(o) => o.into_iter().map(|(k, v)| (k, Value::from(v))).collect(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Value> for serde_json::Value {
+    /// Converts into a `serde_json` value. Numbers that JSON cannot represent (NaN and infinities) become `null`.
+    fn from(v: Value) -> Self {
+        match v {
+            Value::Null => serde_json::Value::Null,
+            Value::Bool(b) => serde_json::Value::Bool(b),
+            Value::Number(n) => serde_json::Number::from_f64(n)
+                .map_or(serde_json::Value::Null, serde_json::Value::Number),
+            Value::String(s) => serde_json::Value::String(s),
+            Value::Array(a) => a.into_iter().map(serde_json::Value::from).collect(),
+            Value::Object(o) => o
+                .into_iter()
+                .map(|(k, v)| (k, serde_json::Value::from(v)))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! External merge sort of key-value pairs, for inputs larger than memory.
+//!
+//! An [`ExternalSorter`] collects pushed pairs in a buffer of bounded length. Whenever the buffer fills up, it is sorted
+//! by key and written to a temporary file as a run. [finish()](`ExternalSorter::finish()`) merges the runs and the last
+//! buffer into one sorted iterator, reading every run front to back. Memory use stays at one buffer plus a read buffer
+//! per run, no matter how many pairs are pushed. The streaming builders of frozen structures, like
+//! [`FrozenMapBuilder`](`crate::rcu::FrozenMapBuilder`), sort their input this way.
+//!
+//! Runs are encoded with [`Codec`], so pairs can be of any type that [snapshots](`crate::snapshot`) support. The files
+//! go into the system's temporary directory or a given one, and are removed once the sorted iterator is dropped.
+//! ```
+//! # use strctr::external_sort::ExternalSorter;
+//! let mut sorter = ExternalSorter::new(1000);
+//! for i in 0..10_000u32 {
+//!     sorter.push(i * 7919 % 10_000, i).unwrap();
+//! }
+//! assert_eq!(sorter.spilled_runs(), 10);
+//!
+//! let keys: Vec<u32> = sorter.finish().unwrap().map(|pair| pair.unwrap().0).collect();
+//! assert_eq!(keys, (0..10_000).collect::<Vec<_>>());
+//! ```
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::snapshot::{Codec, SnapshotError};
+
+/// Deliberately the `std` atomic: it only keeps file names apart and is not part of any loom model.
+static NEXT_FILE: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// A temporary file, removed when dropped.
+pub(crate) struct SpillFile {
+    path: PathBuf,
+}
+
+impl SpillFile {
+    /// Creates a new, empty file in the directory and returns it opened for writing.
+    pub(crate) fn create(dir: &Path) -> io::Result<(Self, File)> {
+        let n = NEXT_FILE.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let path = dir.join(format!("strctr-{}-{}.spill", std::process::id(), n));
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok((Self { path }, file))
+    }
+
+    /// Opens the file for reading from the start.
+    pub(crate) fn open(&self) -> io::Result<File> {
+        File::open(&self.path)
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Sorts pairs by key with bounded memory, spilling sorted runs to temporary files. The sort is stable: pairs with
+/// equal keys come out in the order they were pushed.
+pub struct ExternalSorter<K, V> {
+    dir: PathBuf,
+    run_len: usize,
+    buffer: Vec<(K, V)>,
+    /// The spilled runs in push order, with their lengths.
+    runs: Vec<(SpillFile, usize)>,
+    len: usize,
+}
+
+impl<K: Ord + Codec, V: Codec> ExternalSorter<K, V> {
+    /// Constructs a new sorter that keeps up to `run_len` pairs in memory and spills to the system's temporary
+    /// directory.
+    ///
+    /// Panics if `run_len` is 0.
+    pub fn new(run_len: usize) -> Self {
+        Self::in_dir(std::env::temp_dir(), run_len)
+    }
+
+    /// Constructs a new sorter that keeps up to `run_len` pairs in memory and spills to the directory.
+    ///
+    /// Panics if `run_len` is 0.
+    pub fn in_dir(dir: impl Into<PathBuf>, run_len: usize) -> Self {
+        if run_len == 0 {
+            panic!("InvalidCapacity: ExternalSorter needs room for at least one pair per run");
+        }
+        Self {
+            dir: dir.into(),
+            run_len,
+            buffer: Vec::new(),
+            runs: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of pairs pushed.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether no pairs were pushed.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of runs written to files so far.
+    pub fn spilled_runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Returns the directory the runs are spilled to.
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Adds the pair. Fails if the buffer was full and writing it to a file failed.
+    pub fn push(&mut self, key: K, value: V) -> io::Result<()> {
+        self.buffer.push((key, value));
+        self.len += 1;
+        if self.buffer.len() == self.run_len {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.buffer.sort_by(|a, b| a.0.cmp(&b.0));
+        let (file, f) = SpillFile::create(&self.dir)?;
+        let mut w = BufWriter::new(f);
+        for (k, v) in &self.buffer {
+            k.encode(&mut w)?;
+            v.encode(&mut w)?;
+        }
+        w.flush()?;
+        self.runs.push((file, self.buffer.len()));
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Returns an iterator over all pairs in ascending key order. The last buffer is merged from memory, so input that
+    /// fits into one run never touches a file.
+    pub fn finish(mut self) -> io::Result<Sorted<K, V>> {
+        self.buffer.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut sources = Vec::with_capacity(self.runs.len() + 1);
+        for (file, len) in &self.runs {
+            sources.push(Source::File {
+                reader: BufReader::new(file.open()?),
+                remaining: *len,
+            });
+        }
+        sources.push(Source::Memory(std::mem::take(&mut self.buffer).into_iter()));
+        let mut heads = BinaryHeap::with_capacity(sources.len());
+        for (run, source) in sources.iter_mut().enumerate() {
+            if let Some((key, value)) = source.next()? {
+                heads.push(Reverse(Head { key, run, value }));
+            }
+        }
+        Ok(Sorted {
+            sources,
+            heads,
+            remaining: self.len,
+            _files: self.runs.into_iter().map(|(file, _)| file).collect(),
+        })
+    }
+}
+
+impl<K, V> std::fmt::Debug for ExternalSorter<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExternalSorter")
+            .field("dir", &self.dir)
+            .field("run_len", &self.run_len)
+            .field("len", &self.len)
+            .field("spilled_runs", &self.runs.len())
+            .finish()
+    }
+}
+
+enum Source<K, V> {
+    File {
+        reader: BufReader<File>,
+        remaining: usize,
+    },
+    Memory(std::vec::IntoIter<(K, V)>),
+}
+
+impl<K: Codec, V: Codec> Source<K, V> {
+    fn next(&mut self) -> io::Result<Option<(K, V)>> {
+        match self {
+            Source::File { remaining: 0, .. } => Ok(None),
+            Source::File { reader, remaining } => {
+                *remaining -= 1;
+                match <(K, V)>::decode(reader) {
+                    Ok(pair) => Ok(Some(pair)),
+                    Err(SnapshotError::Io(e)) => Err(e),
+                    Err(_) => Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "spill file was modified",
+                    )),
+                }
+            }
+            Source::Memory(pairs) => Ok(pairs.next()),
+        }
+    }
+}
+
+/// The next pair of a run, ordered by key and then by run, which keeps the merge stable.
+struct Head<K, V> {
+    key: K,
+    run: usize,
+    value: V,
+}
+
+impl<K: Ord, V> Ord for Head<K, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key).then(self.run.cmp(&other.run))
+    }
+}
+
+impl<K: Ord, V> PartialOrd for Head<K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, V> PartialEq for Head<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<K: Ord, V> Eq for Head<K, V> {}
+
+/// Iterator over the sorted pairs of an [`ExternalSorter`], created by [finish()](`ExternalSorter::finish()`). Yields
+/// an error if reading a run fails, and ends after it.
+pub struct Sorted<K, V> {
+    /// Declared before the files, so readers are closed before the files are removed.
+    sources: Vec<Source<K, V>>,
+    heads: BinaryHeap<Reverse<Head<K, V>>>,
+    remaining: usize,
+    _files: Vec<SpillFile>,
+}
+
+impl<K: Ord + Codec, V: Codec> Iterator for Sorted<K, V> {
+    type Item = io::Result<(K, V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse(head) = self.heads.pop()?;
+        self.remaining -= 1;
+        match self.sources[head.run].next() {
+            Ok(Some((key, value))) => self.heads.push(Reverse(Head {
+                key,
+                run: head.run,
+                value,
+            })),
+            Ok(None) => {}
+            Err(e) => {
+                self.heads.clear();
+                self.remaining = 0;
+                return Some(Err(e));
+            }
+        }
+        Some(Ok((head.key, head.value)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
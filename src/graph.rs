@@ -0,0 +1,781 @@
+//! Directed and undirected graphs stored as adjacency lists. Nodes and edges live in slabs and are addressed by
+//! generational [`NodeId`] and [`EdgeId`] handles, so removing them never invalidates the handles of others.
+//!
+//! Besides traversals, graphs come with shortest paths ([dijkstra()](`Graph::dijkstra()`)), topological sorting
+//! ([toposort()](`Graph::toposort()`)), [connected_components()](`Graph::connected_components()`), and centrality
+//! scores: [pagerank()](`Graph::pagerank()`), [degree](`Graph::degree_centrality()`),
+//! [closeness](`Graph::closeness_centrality()`) and [betweenness](`Graph::betweenness_centrality()`). Communities of
+//! densely connected nodes are detected by [label_propagation()](`Graph::label_propagation()`), and improved by
+//! [refine_communities()](`Graph::refine_communities()`).
+//!
+//! Patterns are looked up by [subgraph_monomorphisms()](`Graph::subgraph_monomorphisms()`) and
+//! [subgraph_isomorphisms()](`Graph::subgraph_isomorphisms()`), which iterate over the places a small pattern graph
+//! occurs in a graph, with predicates on the weights of the matched nodes and edges.
+//!
+//! Graphs are multigraphs by default: they accept parallel edges and self-loops. Datasets that must not contain them
+//! can have the graph refuse them instead, with [with_parallel_edges()](`Graph::with_parallel_edges()`) and
+//! [with_self_loops()](`Graph::with_self_loops()`).
+//!
+//! A [`GraphBuilder`] builds a graph from edges between keys of any hashable type, adding a node per distinct key.
+
+use std::collections::VecDeque;
+use std::ops::{Index, IndexMut};
+
+mod algo;
+mod builder;
+mod isomorphism;
+
+pub use algo::{Communities, ShortestPaths};
+pub use builder::GraphBuilder;
+pub use isomorphism::SubgraphMatches;
+
+/// List of errors that could occur when modifying a [`Graph`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum GraphError {
+    /// The node id does not belong to a live node; it was removed or never existed.
+    InvalidNode,
+    /// The graph contains a cycle, so the nodes have no topological order.
+    Cycle,
+    /// The nodes are already connected, and the graph refuses parallel edges.
+    ParallelEdge,
+    /// The edge would connect a node to itself, and the graph refuses self-loops.
+    SelfLoop,
+}
+
+/// Handle to a node of a [`Graph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId {
+    index: usize,
+    generation: u32,
+}
+
+impl NodeId {
+    /// Returns the slot index of the node. Indices of live nodes are unique and smaller than
+    /// [node_bound()](`Graph::node_bound()`), which makes them suitable for indexing side tables.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+/// Handle to an edge of a [`Graph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EdgeId {
+    index: usize,
+    generation: u32,
+}
+
+impl EdgeId {
+    /// Returns the slot index of the edge. Indices of live edges are unique and smaller than
+    /// [edge_bound()](`Graph::edge_bound()`).
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+struct NodeData<N> {
+    weight: N,
+    /// Edges leaving the node. In an undirected graph this holds every incident edge.
+    outgoing: Vec<EdgeId>,
+    /// Edges entering the node. Always empty in an undirected graph.
+    incoming: Vec<EdgeId>,
+}
+
+struct EdgeData<E> {
+    weight: E,
+    source: NodeId,
+    target: NodeId,
+}
+
+struct Slot<T> {
+    generation: u32,
+    data: Option<T>,
+}
+
+/// Slab of generational slots shared by the node and edge storage.
+struct Slab<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<usize>,
+    len: usize,
+}
+
+impl<T> Slab<T> {
+    fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+
+    fn insert(&mut self, data: T) -> (usize, u32) {
+        self.len += 1;
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.data = Some(data);
+                (index, slot.generation)
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    data: Some(data),
+                });
+                (self.slots.len() - 1, 0)
+            }
+        }
+    }
+
+    fn get(&self, index: usize, generation: u32) -> Option<&T> {
+        let slot = self.slots.get(index)?;
+        if slot.generation != generation {
+            return None;
+        }
+        slot.data.as_ref()
+    }
+
+    fn get_mut(&mut self, index: usize, generation: u32) -> Option<&mut T> {
+        let slot = self.slots.get_mut(index)?;
+        if slot.generation != generation {
+            return None;
+        }
+        slot.data.as_mut()
+    }
+
+    fn remove(&mut self, index: usize, generation: u32) -> Option<T> {
+        let slot = self.slots.get_mut(index)?;
+        if slot.generation != generation {
+            return None;
+        }
+        let data = slot.data.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(index);
+        self.len -= 1;
+        Some(data)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (usize, u32, &T)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| s.data.as_ref().map(|d| (i, s.generation, d)))
+    }
+}
+
+/// A graph with node weights of type `N` and edge weights of type `E`. Whether edges are directed is decided when the
+/// graph is constructed. Parallel edges and self-loops are allowed unless the graph is told to refuse them.
+pub struct Graph<N, E> {
+    nodes: Slab<NodeData<N>>,
+    edges: Slab<EdgeData<E>>,
+    directed: bool,
+    parallel_edges: bool,
+    self_loops: bool,
+}
+
+impl<N, E> Graph<N, E> {
+    /// Constructs a new, empty directed graph.
+    pub fn new_directed() -> Self {
+        Self {
+            nodes: Slab::new(),
+            edges: Slab::new(),
+            directed: true,
+            parallel_edges: true,
+            self_loops: true,
+        }
+    }
+
+    /// Constructs a new, empty undirected graph.
+    pub fn new_undirected() -> Self {
+        Self {
+            directed: false,
+            ..Self::new_directed()
+        }
+    }
+
+    /// Sets whether the graph accepts a new edge between nodes that are already connected in the same direction, or
+    /// in either direction if the graph is undirected. Edges already in the graph are kept.
+    /// ```
+    /// # use strctr::graph::{Graph, GraphError};
+    /// let mut roads = Graph::new_undirected().with_parallel_edges(false);
+    /// let a = roads.add_node("Aston");
+    /// let b = roads.add_node("Bury");
+    /// roads.add_edge(a, b, 12.5);
+    /// assert_eq!(roads.try_add_edge(b, a, 14.0), Err(GraphError::ParallelEdge));
+    /// ```
+    pub fn with_parallel_edges(mut self, allow: bool) -> Self {
+        self.parallel_edges = allow;
+        self
+    }
+
+    /// Sets whether the graph accepts edges from a node to itself. Edges already in the graph are kept.
+    /// ```
+    /// # use strctr::graph::{Graph, GraphError};
+    /// let mut g = Graph::new_directed().with_self_loops(false);
+    /// let a = g.add_node(());
+    /// assert_eq!(g.try_add_edge(a, a, ()), Err(GraphError::SelfLoop));
+    /// ```
+    pub fn with_self_loops(mut self, allow: bool) -> Self {
+        self.self_loops = allow;
+        self
+    }
+
+    /// Returns whether the edges of the graph are directed.
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    /// Returns whether the graph accepts parallel edges.
+    pub fn allows_parallel_edges(&self) -> bool {
+        self.parallel_edges
+    }
+
+    /// Returns whether the graph accepts self-loops.
+    pub fn allows_self_loops(&self) -> bool {
+        self.self_loops
+    }
+
+    /// Returns the number of nodes.
+    /// ```
+    /// # use strctr::graph::Graph;
+    /// let mut g: Graph<_, ()> = Graph::new_directed();
+    /// assert_eq!(g.node_count(), 0);
+    /// g.add_node("a");
+    /// assert_eq!(g.node_count(), 1);
+    /// ```
+    pub fn node_count(&self) -> usize {
+        self.nodes.len
+    }
+
+    /// Returns the number of edges.
+    pub fn edge_count(&self) -> usize {
+        self.edges.len
+    }
+
+    /// Returns an upper bound on the [index()](`NodeId::index()`) of every live node.
+    pub fn node_bound(&self) -> usize {
+        self.nodes.slots.len()
+    }
+
+    /// Returns an upper bound on the [index()](`EdgeId::index()`) of every live edge.
+    pub fn edge_bound(&self) -> usize {
+        self.edges.slots.len()
+    }
+
+    /// Adds a node with the given weight and returns its id.
+    pub fn add_node(&mut self, weight: N) -> NodeId {
+        let (index, generation) = self.nodes.insert(NodeData {
+            weight,
+            outgoing: Vec::new(),
+            incoming: Vec::new(),
+        });
+        NodeId { index, generation }
+    }
+
+    /// Returns whether the id refers to a live node.
+    pub fn contains_node(&self, id: NodeId) -> bool {
+        self.node(id).is_some()
+    }
+
+    /// Returns whether the id refers to a live edge.
+    pub fn contains_edge(&self, id: EdgeId) -> bool {
+        self.edge(id).is_some()
+    }
+
+    /// Returns a reference to the weight of the node.
+    pub fn node_weight(&self, id: NodeId) -> Option<&N> {
+        self.node(id).map(|n| &n.weight)
+    }
+
+    /// Returns a mutable reference to the weight of the node.
+    pub fn node_weight_mut(&mut self, id: NodeId) -> Option<&mut N> {
+        self.nodes
+            .get_mut(id.index, id.generation)
+            .map(|n| &mut n.weight)
+    }
+
+    /// Returns a reference to the weight of the edge.
+    pub fn edge_weight(&self, id: EdgeId) -> Option<&E> {
+        self.edge(id).map(|e| &e.weight)
+    }
+
+    /// Returns a mutable reference to the weight of the edge.
+    pub fn edge_weight_mut(&mut self, id: EdgeId) -> Option<&mut E> {
+        self.edges
+            .get_mut(id.index, id.generation)
+            .map(|e| &mut e.weight)
+    }
+
+    /// Returns the source and target of the edge. For undirected graphs these are the endpoints in the order they
+    /// were passed to [add_edge()](`Self::add_edge()`).
+    pub fn edge_endpoints(&self, id: EdgeId) -> Option<(NodeId, NodeId)> {
+        self.edge(id).map(|e| (e.source, e.target))
+    }
+
+    /// Adds an edge from `source` to `target` and returns its id.
+    ///
+    /// Returns an error if either node id is invalid, or if the graph refuses the edge as a parallel edge or self-loop.
+    /// ```
+    /// # use strctr::graph::{Graph, GraphError};
+    /// let mut g = Graph::new_directed();
+    /// let a = g.add_node(());
+    /// let b = g.add_node(());
+    /// assert!(g.try_add_edge(a, b, 1.5).is_ok());
+    /// g.remove_node(b);
+    /// assert_eq!(g.try_add_edge(a, b, 2.0), Err(GraphError::InvalidNode));
+    /// ```
+    pub fn try_add_edge(
+        &mut self,
+        source: NodeId,
+        target: NodeId,
+        weight: E,
+    ) -> Result<EdgeId, GraphError> {
+        if !self.contains_node(source) || !self.contains_node(target) {
+            return Err(GraphError::InvalidNode);
+        }
+        if !self.self_loops && source == target {
+            return Err(GraphError::SelfLoop);
+        }
+        if !self.parallel_edges && self.find_edge(source, target).is_some() {
+            return Err(GraphError::ParallelEdge);
+        }
+        let (index, generation) = self.edges.insert(EdgeData {
+            weight,
+            source,
+            target,
+        });
+        let id = EdgeId { index, generation };
+        self.node_mut(source).outgoing.push(id);
+        if self.directed {
+            self.node_mut(target).incoming.push(id);
+        } else if source != target {
+            self.node_mut(target).outgoing.push(id);
+        }
+        Ok(id)
+    }
+
+    /// Adds an edge from `source` to `target` and returns its id.
+    ///
+    /// Panics if either node id is invalid, or if the graph refuses the edge. For a non-panicing version, see [try_add_edge()](`Self::try_add_edge()`)
+    /// ```should_panic
+    /// # use strctr::graph::Graph;
+    /// let mut g = Graph::new_undirected();
+    /// let a = g.add_node(());
+    /// let b = g.add_node(());
+    /// g.remove_node(b);
+    /// g.add_edge(a, b, ());
+    /// ```
+    pub fn add_edge(&mut self, source: NodeId, target: NodeId, weight: E) -> EdgeId {
+        match self.try_add_edge(source, target, weight) {
+            Ok(id) => id,
+            Err(e) => panic!("{:?}: Cannot connect {:?} to {:?}", e, source, target),
+        }
+    }
+
+    /// Removes the edge and returns its weight.
+    pub fn remove_edge(&mut self, id: EdgeId) -> Option<E> {
+        let edge = self.edges.remove(id.index, id.generation)?;
+        self.node_mut(edge.source).outgoing.retain(|&e| e != id);
+        if self.directed {
+            self.node_mut(edge.target).incoming.retain(|&e| e != id);
+        } else if edge.source != edge.target {
+            self.node_mut(edge.target).outgoing.retain(|&e| e != id);
+        }
+        Some(edge.weight)
+    }
+
+    /// Removes the node along with every edge touching it, and returns its weight.
+    /// ```
+    /// # use strctr::graph::Graph;
+    /// let mut g = Graph::new_directed();
+    /// let a = g.add_node('a');
+    /// let b = g.add_node('b');
+    /// g.add_edge(a, b, ());
+    /// g.add_edge(b, a, ());
+    /// assert_eq!(g.remove_node(a), Some('a'));
+    /// assert_eq!(g.edge_count(), 0);
+    /// assert_eq!(g.remove_node(a), None);
+    /// ```
+    pub fn remove_node(&mut self, id: NodeId) -> Option<N> {
+        let node = self.node(id)?;
+        let incident: Vec<EdgeId> = node
+            .outgoing
+            .iter()
+            .chain(&node.incoming)
+            .copied()
+            .collect();
+        for e in incident {
+            self.remove_edge(e);
+        }
+        self.nodes.remove(id.index, id.generation).map(|n| n.weight)
+    }
+
+    /// Returns an edge from `source` to `target`, if there is one. In an undirected graph the direction is ignored.
+    /// ```
+    /// # use strctr::graph::Graph;
+    /// let mut g = Graph::new_undirected();
+    /// let a = g.add_node(());
+    /// let b = g.add_node(());
+    /// let e = g.add_edge(a, b, 7);
+    /// assert_eq!(g.find_edge(b, a), Some(e));
+    /// ```
+    pub fn find_edge(&self, source: NodeId, target: NodeId) -> Option<EdgeId> {
+        self.find_edges(source, target).next()
+    }
+
+    /// Returns an iterator over every edge from `source` to `target`, in the order they were added. In an undirected
+    /// graph the direction is ignored.
+    /// ```
+    /// # use strctr::graph::Graph;
+    /// let mut flights = Graph::new_directed();
+    /// let ams = flights.add_node("AMS");
+    /// let lis = flights.add_node("LIS");
+    /// flights.add_edge(ams, lis, 180);
+    /// flights.add_edge(lis, ams, 175);
+    /// flights.add_edge(ams, lis, 195);
+    /// let minutes: Vec<_> = flights.find_edges(ams, lis).map(|e| flights[e]).collect();
+    /// assert_eq!(minutes, vec![180, 195]);
+    /// ```
+    pub fn find_edges(&self, source: NodeId, target: NodeId) -> impl Iterator<Item = EdgeId> + '_ {
+        self.edges(source)
+            .filter(move |&(_, other, _)| other == target)
+            .map(|(e, _, _)| e)
+    }
+
+    /// Keeps only the edges for which the predicate returns `true`, removing the others. The predicate sees each edge
+    /// as `(id, source, target, weight)` and can update the weight.
+    /// ```
+    /// # use strctr::graph::Graph;
+    /// let mut g = Graph::new_undirected();
+    /// let n: Vec<_> = (0..4).map(|i| g.add_node(i)).collect();
+    /// g.add_edge(n[0], n[1], 0.9);
+    /// g.add_edge(n[1], n[2], 0.2);
+    /// g.add_edge(n[2], n[3], 0.7);
+    /// g.retain_edges(|_, _, _, similarity| *similarity >= 0.5);
+    /// assert_eq!(g.edge_count(), 2);
+    /// assert_eq!(g.find_edge(n[1], n[2]), None);
+    /// ```
+    pub fn retain_edges(&mut self, mut keep: impl FnMut(EdgeId, NodeId, NodeId, &mut E) -> bool) {
+        let mut removed = Vec::new();
+        for (index, slot) in self.edges.slots.iter_mut().enumerate() {
+            if let Some(edge) = slot.data.as_mut() {
+                let id = EdgeId {
+                    index,
+                    generation: slot.generation,
+                };
+                if !keep(id, edge.source, edge.target, &mut edge.weight) {
+                    removed.push(id);
+                }
+            }
+        }
+        for id in removed {
+            self.remove_edge(id);
+        }
+    }
+
+    /// Keeps only the nodes for which the predicate returns `true`, removing the others along with every edge
+    /// touching them. The predicate can update the weights of the nodes it sees.
+    /// ```
+    /// # use strctr::graph::Graph;
+    /// let mut g = Graph::new_directed();
+    /// let n: Vec<_> = ["home", "tmp", "etc"].into_iter().map(|w| g.add_node(w)).collect();
+    /// g.add_edge(n[0], n[1], ());
+    /// g.add_edge(n[1], n[2], ());
+    /// g.retain_nodes(|_, name| *name != "tmp");
+    /// assert_eq!(g.node_count(), 2);
+    /// assert_eq!(g.edge_count(), 0);
+    /// ```
+    pub fn retain_nodes(&mut self, mut keep: impl FnMut(NodeId, &mut N) -> bool) {
+        let mut removed = Vec::new();
+        for (index, slot) in self.nodes.slots.iter_mut().enumerate() {
+            if let Some(node) = slot.data.as_mut() {
+                let id = NodeId {
+                    index,
+                    generation: slot.generation,
+                };
+                if !keep(id, &mut node.weight) {
+                    removed.push(id);
+                }
+            }
+        }
+        for id in removed {
+            self.remove_node(id);
+        }
+    }
+
+    /// Returns an iterator over the ids of every node.
+    pub fn node_ids(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes
+            .iter()
+            .map(|(index, generation, _)| NodeId { index, generation })
+    }
+
+    /// Returns an iterator over every node along with its weight.
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &N)> {
+        self.nodes
+            .iter()
+            .map(|(index, generation, n)| (NodeId { index, generation }, &n.weight))
+    }
+
+    /// Returns an iterator over the ids of every edge.
+    pub fn edge_ids(&self) -> impl Iterator<Item = EdgeId> + '_ {
+        self.edges
+            .iter()
+            .map(|(index, generation, _)| EdgeId { index, generation })
+    }
+
+    /// Returns an iterator over every edge as `(id, source, target, weight)`.
+    pub fn all_edges(&self) -> impl Iterator<Item = (EdgeId, NodeId, NodeId, &E)> {
+        self.edges.iter().map(|(index, generation, e)| {
+            (EdgeId { index, generation }, e.source, e.target, &e.weight)
+        })
+    }
+
+    /// Returns an iterator over the edges leaving the node as `(id, other endpoint, weight)`. In an undirected graph
+    /// these are all edges touching the node.
+    /// ```
+    /// # use strctr::graph::Graph;
+    /// let mut g = Graph::new_directed();
+    /// let a = g.add_node(());
+    /// let b = g.add_node(());
+    /// g.add_edge(a, b, 3);
+    /// let out: Vec<_> = g.edges(a).map(|(_, to, w)| (to, *w)).collect();
+    /// assert_eq!(out, vec![(b, 3)]);
+    /// assert_eq!(g.edges(b).count(), 0);
+    /// ```
+    pub fn edges(&self, id: NodeId) -> impl Iterator<Item = (EdgeId, NodeId, &E)> {
+        let outgoing = self.node(id).map_or(&[][..], |n| &n.outgoing[..]);
+        outgoing.iter().map(move |&e| {
+            let edge = self.edge(e).expect("dangling edge id");
+            let other = if edge.source == id {
+                edge.target
+            } else {
+                edge.source
+            };
+            (e, other, &edge.weight)
+        })
+    }
+
+    /// Returns an iterator over the edges entering the node as `(id, other endpoint, weight)`. In an undirected graph
+    /// this is the same as [edges()](`Self::edges()`).
+    pub fn edges_incoming(&self, id: NodeId) -> impl Iterator<Item = (EdgeId, NodeId, &E)> {
+        let edges = match self.node(id) {
+            Some(n) if self.directed => &n.incoming[..],
+            Some(n) => &n.outgoing[..],
+            None => &[][..],
+        };
+        edges.iter().map(move |&e| {
+            let edge = self.edge(e).expect("dangling edge id");
+            let other = if edge.target == id {
+                edge.source
+            } else {
+                edge.target
+            };
+            (e, other, &edge.weight)
+        })
+    }
+
+    /// Returns an iterator over the nodes reachable from the node through a single edge. Nodes connected through
+    /// parallel edges are yielded once per edge.
+    /// ```
+    /// # use strctr::graph::Graph;
+    /// let mut g = Graph::new_undirected();
+    /// let a = g.add_node(());
+    /// let b = g.add_node(());
+    /// let c = g.add_node(());
+    /// g.add_edge(a, b, ());
+    /// g.add_edge(c, a, ());
+    /// assert_eq!(g.neighbors(a).collect::<Vec<_>>(), vec![b, c]);
+    /// ```
+    pub fn neighbors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.edges(id).map(|(_, other, _)| other)
+    }
+
+    /// Returns an iterator over the nodes with an edge into the node. In an undirected graph this is the same as
+    /// [neighbors()](`Self::neighbors()`).
+    pub fn predecessors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.edges_incoming(id).map(|(_, other, _)| other)
+    }
+
+    /// Returns the number of edges leaving the node. In an undirected graph this is the number of edge ends
+    /// touching it, with self-loops counted once.
+    pub fn out_degree(&self, id: NodeId) -> usize {
+        self.node(id).map_or(0, |n| n.outgoing.len())
+    }
+
+    /// Returns the number of edges entering the node. In an undirected graph this is the same as
+    /// [out_degree()](`Self::out_degree()`).
+    pub fn in_degree(&self, id: NodeId) -> usize {
+        match self.node(id) {
+            Some(n) if self.directed => n.incoming.len(),
+            Some(n) => n.outgoing.len(),
+            None => 0,
+        }
+    }
+
+    /// Returns a breadth-first iterator over the nodes reachable from `start`, beginning with `start` itself.
+    /// ```
+    /// # use strctr::graph::Graph;
+    /// let mut g = Graph::new_directed();
+    /// let n: Vec<_> = (0..5).map(|i| g.add_node(i)).collect();
+    /// g.add_edge(n[0], n[1], ());
+    /// g.add_edge(n[0], n[2], ());
+    /// g.add_edge(n[1], n[3], ());
+    /// g.add_edge(n[2], n[3], ());
+    /// let order: Vec<_> = g.bfs(n[0]).map(|id| g[id]).collect();
+    /// assert_eq!(order, vec![0, 1, 2, 3]);
+    /// ```
+    pub fn bfs(&self, start: NodeId) -> Bfs<'_, N, E> {
+        let mut visited = vec![false; self.node_bound()];
+        let mut queue = VecDeque::new();
+        if self.contains_node(start) {
+            visited[start.index] = true;
+            queue.push_back(start);
+        }
+        Bfs {
+            graph: self,
+            visited,
+            queue,
+        }
+    }
+
+    /// Returns a depth-first (pre-order) iterator over the nodes reachable from `start`, beginning with `start`
+    /// itself.
+    /// ```
+    /// # use strctr::graph::Graph;
+    /// let mut g = Graph::new_directed();
+    /// let n: Vec<_> = (0..5).map(|i| g.add_node(i)).collect();
+    /// g.add_edge(n[0], n[1], ());
+    /// g.add_edge(n[0], n[2], ());
+    /// g.add_edge(n[1], n[3], ());
+    /// g.add_edge(n[2], n[3], ());
+    /// let order: Vec<_> = g.dfs(n[0]).map(|id| g[id]).collect();
+    /// assert_eq!(order, vec![0, 1, 3, 2]);
+    /// ```
+    pub fn dfs(&self, start: NodeId) -> Dfs<'_, N, E> {
+        let stack = if self.contains_node(start) {
+            vec![start]
+        } else {
+            Vec::new()
+        };
+        Dfs {
+            graph: self,
+            visited: vec![false; self.node_bound()],
+            stack,
+        }
+    }
+
+    fn node(&self, id: NodeId) -> Option<&NodeData<N>> {
+        self.nodes.get(id.index, id.generation)
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> &mut NodeData<N> {
+        self.nodes
+            .get_mut(id.index, id.generation)
+            .expect("dangling node id")
+    }
+
+    fn edge(&self, id: EdgeId) -> Option<&EdgeData<E>> {
+        self.edges.get(id.index, id.generation)
+    }
+}
+
+impl<N, E> Index<NodeId> for Graph<N, E> {
+    type Output = N;
+
+    /// Returns the weight of the node.
+    ///
+    /// Panics if the id is invalid.
+    fn index(&self, id: NodeId) -> &Self::Output {
+        match self.node_weight(id) {
+            Some(w) => w,
+            None => panic!("InvalidNode: {:?} is not a live node", id),
+        }
+    }
+}
+
+impl<N, E> IndexMut<NodeId> for Graph<N, E> {
+    /// Allows updating the weight of the node.
+    ///
+    /// Panics if the id is invalid.
+    fn index_mut(&mut self, id: NodeId) -> &mut Self::Output {
+        match self.node_weight_mut(id) {
+            Some(w) => w,
+            None => panic!("InvalidNode: {:?} is not a live node", id),
+        }
+    }
+}
+
+impl<N, E> Index<EdgeId> for Graph<N, E> {
+    type Output = E;
+
+    /// Returns the weight of the edge.
+    ///
+    /// Panics if the id is invalid.
+    fn index(&self, id: EdgeId) -> &Self::Output {
+        match self.edge_weight(id) {
+            Some(w) => w,
+            None => panic!("InvalidEdge: {:?} is not a live edge", id),
+        }
+    }
+}
+
+impl<N, E> IndexMut<EdgeId> for Graph<N, E> {
+    /// Allows updating the weight of the edge.
+    ///
+    /// Panics if the id is invalid.
+    fn index_mut(&mut self, id: EdgeId) -> &mut Self::Output {
+        match self.edge_weight_mut(id) {
+            Some(w) => w,
+            None => panic!("InvalidEdge: {:?} is not a live edge", id),
+        }
+    }
+}
+
+/// Breadth-first traversal of a [`Graph`], created by [bfs()](`Graph::bfs()`).
+pub struct Bfs<'a, N, E> {
+    graph: &'a Graph<N, E>,
+    visited: Vec<bool>,
+    queue: VecDeque<NodeId>,
+}
+
+impl<N, E> Iterator for Bfs<'_, N, E> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.queue.pop_front()?;
+        for next in self.graph.neighbors(id) {
+            if !self.visited[next.index] {
+                self.visited[next.index] = true;
+                self.queue.push_back(next);
+            }
+        }
+        Some(id)
+    }
+}
+
+/// Depth-first traversal of a [`Graph`], created by [dfs()](`Graph::dfs()`).
+pub struct Dfs<'a, N, E> {
+    graph: &'a Graph<N, E>,
+    visited: Vec<bool>,
+    stack: Vec<NodeId>,
+}
+
+impl<N, E> Iterator for Dfs<'_, N, E> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(id) = self.stack.pop() {
+            if self.visited[id.index] {
+                continue;
+            }
+            self.visited[id.index] = true;
+            let unvisited: Vec<NodeId> = self
+                .graph
+                .neighbors(id)
+                .filter(|n| !self.visited[n.index])
+                .collect();
+            self.stack.extend(unvisited.into_iter().rev());
+            return Some(id);
+        }
+        None
+    }
+}
@@ -0,0 +1,109 @@
+//! Building a [`Graph`] from edges between arbitrary node keys.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::{EdgeId, Graph, NodeId};
+use crate::hash::HashState;
+
+/// Builds a [`Graph`] from `(source, target, weight)` edges whose endpoints are keys of any hashable type, like names
+/// or external ids. Each distinct key becomes one node, weighted by the key, the first time it appears.
+/// ```
+/// # use strctr::graph::GraphBuilder;
+/// let follows = [("ana", "bo", ()), ("bo", "cy", ()), ("cy", "ana", ()), ("ana", "cy", ())];
+/// let builder = GraphBuilder::directed().edges(follows);
+/// let ana = builder.node_id(&"ana").unwrap();
+/// let g = builder.build();
+/// assert_eq!((g.node_count(), g.edge_count()), (3, 4));
+/// let followed: Vec<_> = g.neighbors(ana).map(|id| g[id]).collect();
+/// assert_eq!(followed, vec!["bo", "cy"]);
+/// ```
+pub struct GraphBuilder<K, W> {
+    graph: Graph<K, W>,
+    ids: HashMap<K, NodeId, HashState>,
+}
+
+impl<K: Hash + Eq + Clone, W> GraphBuilder<K, W> {
+    /// Constructs a new builder of a directed graph.
+    pub fn directed() -> Self {
+        Self::from_graph(Graph::new_directed())
+    }
+
+    /// Constructs a new builder of an undirected graph.
+    pub fn undirected() -> Self {
+        Self::from_graph(Graph::new_undirected())
+    }
+
+    fn from_graph(graph: Graph<K, W>) -> Self {
+        Self {
+            graph,
+            ids: HashMap::with_hasher(HashState::new()),
+        }
+    }
+
+    /// Sets whether the graph accepts parallel edges, like [Graph::with_parallel_edges()]. Refused edges are skipped.
+    /// ```
+    /// # use strctr::graph::GraphBuilder;
+    /// let pairs = [(1, 2, "a"), (2, 1, "b"), (1, 2, "c")];
+    /// let g = GraphBuilder::undirected().with_parallel_edges(false).edges(pairs).build();
+    /// assert_eq!(g.edge_count(), 1);
+    /// ```
+    pub fn with_parallel_edges(mut self, allow: bool) -> Self {
+        self.graph = self.graph.with_parallel_edges(allow);
+        self
+    }
+
+    /// Sets whether the graph accepts self-loops, like [Graph::with_self_loops()]. Refused edges are skipped, but
+    /// their node is still added.
+    pub fn with_self_loops(mut self, allow: bool) -> Self {
+        self.graph = self.graph.with_self_loops(allow);
+        self
+    }
+
+    /// Returns the node of the key, adding it if the key is new. Nodes without edges are added this way.
+    pub fn node(&mut self, key: K) -> NodeId {
+        if let Some(&id) = self.ids.get(&key) {
+            return id;
+        }
+        let id = self.graph.add_node(key.clone());
+        self.ids.insert(key, id);
+        id
+    }
+
+    /// Returns the node of the key, if it was added.
+    pub fn node_id(&self, key: &K) -> Option<NodeId> {
+        self.ids.get(key).copied()
+    }
+
+    /// Adds an edge between the nodes of the keys, adding them if they are new. Returns `None` if the graph refuses the
+    /// edge as a parallel edge or self-loop.
+    pub fn edge(&mut self, source: K, target: K, weight: W) -> Option<EdgeId> {
+        let source = self.node(source);
+        let target = self.node(target);
+        self.graph.try_add_edge(source, target, weight).ok()
+    }
+
+    /// Adds all the edges, like [edge()](`Self::edge()`).
+    pub fn edges(mut self, edges: impl IntoIterator<Item = (K, K, W)>) -> Self {
+        self.extend(edges);
+        self
+    }
+
+    /// Returns the graph.
+    pub fn build(self) -> Graph<K, W> {
+        self.graph
+    }
+
+    /// Returns the graph and the node of every key.
+    pub fn into_parts(self) -> (Graph<K, W>, HashMap<K, NodeId, HashState>) {
+        (self.graph, self.ids)
+    }
+}
+
+impl<K: Hash + Eq + Clone, W> Extend<(K, K, W)> for GraphBuilder<K, W> {
+    fn extend<I: IntoIterator<Item = (K, K, W)>>(&mut self, iter: I) {
+        for (source, target, weight) in iter {
+            self.edge(source, target, weight);
+        }
+    }
+}
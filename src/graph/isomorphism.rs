@@ -0,0 +1,435 @@
+//! Graph and subgraph isomorphism with the VF2 algorithm.
+
+use std::collections::HashMap;
+
+use super::{Graph, NodeId};
+use crate::hash::HashState;
+
+/// Iterator over the ways a pattern graph occurs in a graph, created by
+/// [subgraph_isomorphisms()](`Graph::subgraph_isomorphisms()`) and
+/// [subgraph_monomorphisms()](`Graph::subgraph_monomorphisms()`). Each item maps every node of the pattern to a
+/// distinct node of the graph.
+///
+/// Matches are found by the VF2 algorithm of Cordella et al., a depth-first search that extends a partial mapping one
+/// pair of nodes at a time. It prefers nodes next to the ones already mapped, and prunes pairs whose neighborhoods
+/// cannot fit, which keeps the search fast on small-to-medium graphs, though the worst case is exponential.
+pub struct SubgraphMatches<'a, PN, PE, N, E, NF, EF> {
+    pattern: &'a Graph<PN, PE>,
+    graph: &'a Graph<N, E>,
+    node_match: NF,
+    edge_match: EF,
+    induced: bool,
+    // The node of the other graph each node is mapped to, by node index.
+    core_pattern: Vec<Option<NodeId>>,
+    core_graph: Vec<Option<NodeId>>,
+    // The depth at which each node first had an edge from or to a mapped node, or 0. Unmapped nodes with a depth form
+    // the terminal sets, from which the next pair is drawn.
+    in_pattern: Vec<usize>,
+    out_pattern: Vec<usize>,
+    in_graph: Vec<usize>,
+    out_graph: Vec<usize>,
+    stack: Vec<Frame>,
+    started: bool,
+}
+
+/// A level of the search: the pattern node being mapped, the graph nodes it may be mapped to, and the one it is
+/// currently mapped to.
+struct Frame {
+    node: NodeId,
+    candidates: Vec<NodeId>,
+    next: usize,
+    mapped: Option<NodeId>,
+}
+
+impl<N, E> Graph<N, E> {
+    /// Returns an iterator over the occurrences of the pattern as an induced subgraph: every pattern node is mapped to
+    /// a distinct node, the edges between the mapped nodes are exactly the edges of the pattern, and the weights of
+    /// the nodes and edges satisfy `node_match` and `edge_match`, which are called with the pattern's weight first.
+    /// Edges follow their direction in directed graphs. Between two nodes, the graph must have as many parallel edges
+    /// as the pattern, and every pattern edge must match one of them.
+    /// ```
+    /// # use strctr::graph::Graph;
+    /// // A bond graph of ethanol, searched for an O-H group.
+    /// let mut molecule = Graph::new_undirected();
+    /// let c1 = molecule.add_node('C');
+    /// let c2 = molecule.add_node('C');
+    /// let o = molecule.add_node('O');
+    /// let h = molecule.add_node('H');
+    /// molecule.add_edge(c1, c2, 1);
+    /// molecule.add_edge(c2, o, 1);
+    /// molecule.add_edge(o, h, 1);
+    ///
+    /// let mut hydroxyl = Graph::new_undirected();
+    /// let po = hydroxyl.add_node('O');
+    /// let ph = hydroxyl.add_node('H');
+    /// hydroxyl.add_edge(po, ph, 1);
+    ///
+    /// let matches: Vec<_> = molecule.subgraph_isomorphisms(&hydroxyl, |p, n| p == n, |p, e| p == e).collect();
+    /// assert_eq!(matches.len(), 1);
+    /// assert_eq!((matches[0][&po], matches[0][&ph]), (o, h));
+    /// ```
+    pub fn subgraph_isomorphisms<'a, PN, PE, NF, EF>(
+        &'a self,
+        pattern: &'a Graph<PN, PE>,
+        node_match: NF,
+        edge_match: EF,
+    ) -> SubgraphMatches<'a, PN, PE, N, E, NF, EF>
+    where
+        NF: FnMut(&PN, &N) -> bool,
+        EF: FnMut(&PE, &E) -> bool,
+    {
+        SubgraphMatches::new(pattern, self, node_match, edge_match, true)
+    }
+
+    /// Returns an iterator over the occurrences of the pattern as a subgraph, like
+    /// [subgraph_isomorphisms()](`Self::subgraph_isomorphisms()`) but allowing edges between the mapped nodes that are
+    /// not in the pattern. This is what pattern-matching queries usually want.
+    /// ```
+    /// # use strctr::graph::Graph;
+    /// // Who follows someone who follows them back?
+    /// let mut follows = Graph::new_directed();
+    /// let n: Vec<_> = ["ana", "bo", "cy"].into_iter().map(|name| follows.add_node(name)).collect();
+    /// for (a, b) in [(0, 1), (1, 0), (1, 2), (2, 0)] {
+    ///     follows.add_edge(n[a], n[b], ());
+    /// }
+    /// let mut mutual = Graph::new_directed();
+    /// let (x, y) = (mutual.add_node(()), mutual.add_node(()));
+    /// mutual.add_edge(x, y, ());
+    /// mutual.add_edge(y, x, ());
+    ///
+    /// let mut pairs: Vec<_> = follows
+    ///     .subgraph_monomorphisms(&mutual, |_, _| true, |_, _| true)
+    ///     .map(|m| (follows[m[&x]], follows[m[&y]]))
+    ///     .collect();
+    /// pairs.sort();
+    /// assert_eq!(pairs, vec![("ana", "bo"), ("bo", "ana")]);
+    /// ```
+    pub fn subgraph_monomorphisms<'a, PN, PE, NF, EF>(
+        &'a self,
+        pattern: &'a Graph<PN, PE>,
+        node_match: NF,
+        edge_match: EF,
+    ) -> SubgraphMatches<'a, PN, PE, N, E, NF, EF>
+    where
+        NF: FnMut(&PN, &N) -> bool,
+        EF: FnMut(&PE, &E) -> bool,
+    {
+        SubgraphMatches::new(pattern, self, node_match, edge_match, false)
+    }
+
+    /// Returns whether the graphs are isomorphic, with the weights of mapped nodes and edges satisfying `node_match`
+    /// and `edge_match`, which are called with this graph's weight first.
+    /// ```
+    /// # use strctr::graph::Graph;
+    /// let mut square = Graph::new_undirected();
+    /// let s: Vec<_> = (0..4).map(|_| square.add_node(())).collect();
+    /// for i in 0..4 {
+    ///     square.add_edge(s[i], s[(i + 1) % 4], ());
+    /// }
+    /// let mut crossed = Graph::new_undirected();
+    /// let c: Vec<_> = (0..4).map(|_| crossed.add_node(())).collect();
+    /// for (a, b) in [(0, 2), (2, 1), (1, 3), (3, 0)] {
+    ///     crossed.add_edge(c[a], c[b], ());
+    /// }
+    /// assert!(square.is_isomorphic_matching(&crossed, |_, _| true, |_, _| true));
+    ///
+    /// crossed.add_edge(c[0], c[1], ());
+    /// assert!(!square.is_isomorphic_matching(&crossed, |_, _| true, |_, _| true));
+    /// ```
+    pub fn is_isomorphic_matching<N2, E2, NF, EF>(
+        &self,
+        other: &Graph<N2, E2>,
+        node_match: NF,
+        edge_match: EF,
+    ) -> bool
+    where
+        NF: FnMut(&N, &N2) -> bool,
+        EF: FnMut(&E, &E2) -> bool,
+    {
+        self.node_count() == other.node_count()
+            && self.edge_count() == other.edge_count()
+            && other
+                .subgraph_isomorphisms(self, node_match, edge_match)
+                .next()
+                .is_some()
+    }
+}
+
+impl<'a, PN, PE, N, E, NF, EF> SubgraphMatches<'a, PN, PE, N, E, NF, EF>
+where
+    NF: FnMut(&PN, &N) -> bool,
+    EF: FnMut(&PE, &E) -> bool,
+{
+    fn new(
+        pattern: &'a Graph<PN, PE>,
+        graph: &'a Graph<N, E>,
+        node_match: NF,
+        edge_match: EF,
+        induced: bool,
+    ) -> Self {
+        Self {
+            pattern,
+            graph,
+            node_match,
+            edge_match,
+            induced,
+            core_pattern: vec![None; pattern.node_bound()],
+            core_graph: vec![None; graph.node_bound()],
+            in_pattern: vec![0; pattern.node_bound()],
+            out_pattern: vec![0; pattern.node_bound()],
+            in_graph: vec![0; graph.node_bound()],
+            out_graph: vec![0; graph.node_bound()],
+            stack: Vec::new(),
+            started: false,
+        }
+    }
+
+    /// Picks the next pattern node to map and the graph nodes to try for it: the first unmapped pattern node with an
+    /// edge from a mapped node and the graph nodes likewise, else the same for edges into mapped nodes, else the first
+    /// unmapped pattern node and every unmapped graph node.
+    fn frame(&self) -> Frame {
+        let terminal_pattern = |depth: &[usize]| {
+            self.pattern
+                .node_ids()
+                .find(|p| depth[p.index()] > 0 && self.core_pattern[p.index()].is_none())
+        };
+        let terminal_graph = |depth: &[usize]| -> Vec<NodeId> {
+            self.graph
+                .node_ids()
+                .filter(|g| depth[g.index()] > 0 && self.core_graph[g.index()].is_none())
+                .collect()
+        };
+        for (pattern_depth, graph_depth) in [
+            (&self.out_pattern, &self.out_graph),
+            (&self.in_pattern, &self.in_graph),
+        ] {
+            if let Some(node) = terminal_pattern(pattern_depth) {
+                let candidates = terminal_graph(graph_depth);
+                if !candidates.is_empty() {
+                    return Frame::new(node, candidates);
+                }
+            }
+        }
+        let node = self
+            .pattern
+            .node_ids()
+            .find(|p| self.core_pattern[p.index()].is_none())
+            .expect("a pattern node is left to map");
+        let candidates = self
+            .graph
+            .node_ids()
+            .filter(|g| self.core_graph[g.index()].is_none())
+            .collect();
+        Frame::new(node, candidates)
+    }
+
+    /// Returns whether mapping the pattern node to the graph node keeps the mapping extendable, as far as the edges to
+    /// mapped nodes and the number of neighbors in the terminal sets tell.
+    fn feasible(&mut self, p: NodeId, g: NodeId) -> bool {
+        if !(self.node_match)(&self.pattern[p], &self.graph[g]) || !self.edges_fit(p, p, g, g) {
+            return false;
+        }
+        let (pattern, graph) = (self.pattern, self.graph);
+        for q in pattern.predecessors(p) {
+            if let Some(h) = self.core_pattern[q.index()] {
+                if !self.edges_fit(q, p, h, g) {
+                    return false;
+                }
+            }
+        }
+        for q in pattern.neighbors(p) {
+            if let Some(h) = self.core_pattern[q.index()] {
+                if !self.edges_fit(p, q, g, h) {
+                    return false;
+                }
+            }
+        }
+        if !self.induced {
+            return true;
+        }
+        // The graph must not have edges to mapped nodes that the pattern lacks.
+        for h in graph.predecessors(g) {
+            if let Some(q) = self.core_graph[h.index()] {
+                if !self.edges_fit(q, p, h, g) {
+                    return false;
+                }
+            }
+        }
+        for h in graph.neighbors(g) {
+            if let Some(q) = self.core_graph[h.index()] {
+                if !self.edges_fit(p, q, g, h) {
+                    return false;
+                }
+            }
+        }
+        // Unmapped neighbors of the pattern node end up mapped to distinct unmapped neighbors of the graph node in
+        // the same terminal sets, so the graph node needs at least as many in each.
+        let classes: [fn(usize, usize) -> bool; 3] = [
+            |d_in, _| d_in > 0,
+            |_, d_out| d_out > 0,
+            |d_in, d_out| d_in == 0 && d_out == 0,
+        ];
+        for class in classes {
+            let count_pattern = |nodes: &mut dyn Iterator<Item = NodeId>| {
+                nodes
+                    .filter(|&q| q != p && self.core_pattern[q.index()].is_none())
+                    .filter(|q| class(self.in_pattern[q.index()], self.out_pattern[q.index()]))
+                    .count()
+            };
+            let count_graph = |nodes: &mut dyn Iterator<Item = NodeId>| {
+                nodes
+                    .filter(|&h| h != g && self.core_graph[h.index()].is_none())
+                    .filter(|h| class(self.in_graph[h.index()], self.out_graph[h.index()]))
+                    .count()
+            };
+            if count_pattern(&mut pattern.predecessors(p)) > count_graph(&mut graph.predecessors(g))
+                || count_pattern(&mut pattern.neighbors(p)) > count_graph(&mut graph.neighbors(g))
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns whether the edges from graph node `ga` to `gb` fit the edges from pattern node `pa` to `pb`: there are
+    /// as many, or at least as many when not induced, and every pattern edge matches one of them.
+    fn edges_fit(&mut self, pa: NodeId, pb: NodeId, ga: NodeId, gb: NodeId) -> bool {
+        let (pattern, graph) = (self.pattern, self.graph);
+        let pattern_edges = pattern.find_edges(pa, pb).count();
+        let graph_edges = graph.find_edges(ga, gb).count();
+        if graph_edges < pattern_edges || (self.induced && graph_edges > pattern_edges) {
+            return false;
+        }
+        for pe in pattern.find_edges(pa, pb) {
+            if !graph
+                .find_edges(ga, gb)
+                .any(|ge| (self.edge_match)(&pattern[pe], &graph[ge]))
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Maps the pattern node to the graph node at the depth, adding their neighbors to the terminal sets.
+    fn map(&mut self, depth: usize, p: NodeId, g: NodeId) {
+        self.core_pattern[p.index()] = Some(g);
+        self.core_graph[g.index()] = Some(p);
+        let (pattern, graph) = (self.pattern, self.graph);
+        for q in pattern.predecessors(p).chain([p]) {
+            mark(&mut self.in_pattern[q.index()], depth);
+        }
+        for q in pattern.neighbors(p).chain([p]) {
+            mark(&mut self.out_pattern[q.index()], depth);
+        }
+        for h in graph.predecessors(g).chain([g]) {
+            mark(&mut self.in_graph[h.index()], depth);
+        }
+        for h in graph.neighbors(g).chain([g]) {
+            mark(&mut self.out_graph[h.index()], depth);
+        }
+    }
+
+    /// Undoes [map()](`Self::map()`) at the depth.
+    fn unmap(&mut self, depth: usize, p: NodeId, g: NodeId) {
+        self.core_pattern[p.index()] = None;
+        self.core_graph[g.index()] = None;
+        let (pattern, graph) = (self.pattern, self.graph);
+        for q in pattern.predecessors(p).chain([p]) {
+            unmark(&mut self.in_pattern[q.index()], depth);
+        }
+        for q in pattern.neighbors(p).chain([p]) {
+            unmark(&mut self.out_pattern[q.index()], depth);
+        }
+        for h in graph.predecessors(g).chain([g]) {
+            unmark(&mut self.in_graph[h.index()], depth);
+        }
+        for h in graph.neighbors(g).chain([g]) {
+            unmark(&mut self.out_graph[h.index()], depth);
+        }
+    }
+
+    fn mapping(&self) -> HashMap<NodeId, NodeId, HashState> {
+        self.pattern
+            .node_ids()
+            .map(|p| {
+                (
+                    p,
+                    self.core_pattern[p.index()].expect("every pattern node is mapped"),
+                )
+            })
+            .collect()
+    }
+}
+
+impl<PN, PE, N, E, NF, EF> Iterator for SubgraphMatches<'_, PN, PE, N, E, NF, EF>
+where
+    NF: FnMut(&PN, &N) -> bool,
+    EF: FnMut(&PE, &E) -> bool,
+{
+    type Item = HashMap<NodeId, NodeId, HashState>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            if self.pattern.node_count() == 0 {
+                return Some(HashMap::default());
+            }
+            if self.pattern.node_count() > self.graph.node_count() {
+                return None;
+            }
+            self.stack.push(self.frame());
+        }
+        while let Some(frame) = self.stack.last_mut() {
+            let (p, mapped) = (frame.node, frame.mapped.take());
+            let depth = self.stack.len();
+            if let Some(g) = mapped {
+                self.unmap(depth, p, g);
+            }
+            let frame = self.stack.last_mut().expect("the frame is still there");
+            let Some(&g) = frame.candidates.get(frame.next) else {
+                self.stack.pop();
+                continue;
+            };
+            frame.next += 1;
+            if !self.feasible(p, g) {
+                continue;
+            }
+            self.map(depth, p, g);
+            self.stack
+                .last_mut()
+                .expect("the frame is still there")
+                .mapped = Some(g);
+            if depth == self.pattern.node_count() {
+                return Some(self.mapping());
+            }
+            self.stack.push(self.frame());
+        }
+        None
+    }
+}
+
+impl Frame {
+    fn new(node: NodeId, candidates: Vec<NodeId>) -> Self {
+        Self {
+            node,
+            candidates,
+            next: 0,
+            mapped: None,
+        }
+    }
+}
+
+/// Notes that a node entered a terminal set at the depth, unless it already had.
+fn mark(entered: &mut usize, depth: usize) {
+    if *entered == 0 {
+        *entered = depth;
+    }
+}
+
+/// Removes a node from a terminal set if it entered at the depth.
+fn unmark(entered: &mut usize, depth: usize) {
+    if *entered == depth {
+        *entered = 0;
+    }
+}
@@ -0,0 +1,148 @@
+//! Growth policies, deciding how much room a growable structure allocates once it runs out, and whether it gives room
+//! back once elements are removed.
+//!
+//! Growing geometrically keeps pushes amortized O(1) at the cost of up to half the allocation going unused, growing
+//! exactly wastes nothing but copies the elements on every push, and a cap bounds the memory a structure may take at
+//! all. None of them shrink on their own; [`Shrinking`] adds that for long-lived structures whose size balloons
+//! transiently. [`HybridVec`](`crate::hybrid_vec::HybridVec`) takes its policy as a type parameter, [`Doubling`] by
+//! default, so memory-sensitive users pick the trade-off without forking the structure.
+//! ```
+//! # use strctr::growth::{Capped, Exact};
+//! # use strctr::hybrid_vec::HybridVec;
+//! let mut v: HybridVec<u32, 2, _> = HybridVec::with_policy(Capped::new(Exact, 3));
+//! v.extend([1, 2, 3]);
+//! assert_eq!(v.capacity(), 3);
+//! assert!(v.try_push(4).is_err());
+//! ```
+
+/// Decides the capacity a structure grows to, and when it shrinks back.
+pub trait GrowthPolicy {
+    /// Returns the capacity to grow to from `capacity`, which is at least `required`, or `None` to refuse growing to
+    /// `required` at all. `required` is always above `capacity`.
+    fn grow(&self, capacity: usize, required: usize) -> Option<usize>;
+
+    /// Returns the capacity to shrink to after a removal left `len` elements in room for `capacity`, which is at least
+    /// `len`, or `None` to keep the capacity. The default never shrinks.
+    fn shrink(&self, len: usize, capacity: usize) -> Option<usize> {
+        let _ = (len, capacity);
+        None
+    }
+}
+
+/// Doubles the capacity, like [`Vec`] does.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Doubling;
+
+impl GrowthPolicy for Doubling {
+    fn grow(&self, capacity: usize, required: usize) -> Option<usize> {
+        Some(capacity.saturating_mul(2).max(required))
+    }
+}
+
+/// Grows the capacity by half, trading more frequent copies for less unused room.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OneAndHalf;
+
+impl GrowthPolicy for OneAndHalf {
+    fn grow(&self, capacity: usize, required: usize) -> Option<usize> {
+        Some(capacity.saturating_add(capacity / 2).max(required))
+    }
+}
+
+/// Grows the capacity to exactly what is required, so no room goes unused but every growth copies the elements.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Exact;
+
+impl GrowthPolicy for Exact {
+    fn grow(&self, _capacity: usize, required: usize) -> Option<usize> {
+        Some(required)
+    }
+}
+
+/// Grows like the inner policy, but never beyond `max`.
+/// ```
+/// # use strctr::growth::{Capped, Doubling, GrowthPolicy};
+/// let policy = Capped::new(Doubling, 100);
+/// assert_eq!(policy.grow(40, 41), Some(80));
+/// assert_eq!(policy.grow(80, 81), Some(100));
+/// assert_eq!(policy.grow(100, 101), None);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capped<P = Doubling> {
+    inner: P,
+    max: usize,
+}
+
+impl<P: GrowthPolicy> Capped<P> {
+    /// Constructs a new policy growing like `inner` up to a capacity of `max`.
+    pub fn new(inner: P, max: usize) -> Self {
+        Self { inner, max }
+    }
+
+    /// Returns the largest capacity the policy grows to.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+}
+
+impl<P: GrowthPolicy> GrowthPolicy for Capped<P> {
+    fn grow(&self, capacity: usize, required: usize) -> Option<usize> {
+        if required > self.max {
+            return None;
+        }
+        Some(self.inner.grow(capacity, required)?.min(self.max))
+    }
+
+    fn shrink(&self, len: usize, capacity: usize) -> Option<usize> {
+        self.inner.shrink(len, capacity)
+    }
+}
+
+/// Grows like the inner policy, and shrinks to twice the elements once fewer than a `1 / divisor` of the room is in
+/// use.
+///
+/// Shrinking to half occupancy rather than to the elements leaves a gap on both sides: the structure takes about as
+/// many pushes to grow again as it took removals to shrink, so alternating pushes and removals around either threshold
+/// does not copy the elements every time.
+/// ```
+/// # use strctr::growth::{Doubling, GrowthPolicy, Shrinking};
+/// let policy = Shrinking::new(Doubling, 4);
+/// assert_eq!(policy.shrink(30, 100), None);
+/// assert_eq!(policy.shrink(24, 100), Some(48));
+/// assert_eq!(policy.grow(48, 49), Some(96));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shrinking<P = Doubling> {
+    inner: P,
+    divisor: usize,
+}
+
+impl<P: GrowthPolicy> Shrinking<P> {
+    /// Constructs a new policy growing like `inner` and shrinking once fewer than a `1 / divisor` of the room is in use.
+    ///
+    /// Panics if `divisor` is below 4, as shrinking above a quarter occupancy lets a doubled structure shrink again
+    /// after a few removals.
+    pub fn new(inner: P, divisor: usize) -> Self {
+        if divisor < 4 {
+            panic!(
+                "InvalidThreshold: Shrinking below 1/{} occupancy thrashes, the divisor must be at least 4",
+                divisor
+            );
+        }
+        Self { inner, divisor }
+    }
+}
+
+impl<P: GrowthPolicy> GrowthPolicy for Shrinking<P> {
+    fn grow(&self, capacity: usize, required: usize) -> Option<usize> {
+        self.inner.grow(capacity, required)
+    }
+
+    fn shrink(&self, len: usize, capacity: usize) -> Option<usize> {
+        if len.saturating_mul(self.divisor) < capacity {
+            Some(2 * len)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Hasher keys for the hashed structures of this crate.
+//!
+//! Hashed structures iterate in an order that depends on their hasher's keys. By default, every structure draws random
+//! keys, like the standard library does, which protects against inputs crafted to collide but makes the order differ
+//! from run to run. A structure constructed with a [`HashState::with_seed()`] iterates in the same order in every run
+//! that performs the same operations, and so do all structures when the crate's `deterministic` feature is enabled,
+//! which makes [`HashState::new()`] use fixed keys. Keys with equal hashes, where structures keep them apart at all,
+//! stay in insertion order.
+//!
+//! Orders are only reproducible with the same build: a different version of this crate or of the standard library may
+//! hash differently.
+//! ```
+//! # use strctr::counter::Counter;
+//! # use strctr::hash::HashState;
+//! let words = ["to", "be", "or", "not", "to", "be"];
+//! let mut a = Counter::with_hasher(HashState::with_seed(7));
+//! let mut b = Counter::with_hasher(HashState::with_seed(7));
+//! a.extend(words);
+//! b.extend(words);
+//! assert!(a.iter().eq(b.iter()));
+//! ```
+
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::hash::{BuildHasher, Hasher};
+
+/// Builds the hashers of a hashed structure, with random keys or keys derived from a seed.
+#[derive(Clone, Debug)]
+pub struct HashState {
+    keys: Keys,
+}
+
+#[derive(Clone, Debug)]
+enum Keys {
+    Random(RandomState),
+    Seeded(u64),
+}
+
+impl Default for HashState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HashState {
+    /// Constructs a new state with random keys, or with the keys of seed 0 if the `deterministic` feature is enabled.
+    pub fn new() -> Self {
+        if cfg!(feature = "deterministic") {
+            Self::with_seed(0)
+        } else {
+            Self {
+                keys: Keys::Random(RandomState::new()),
+            }
+        }
+    }
+
+    /// Constructs a new state whose keys are derived from the seed, so equal seeds hash equally.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            keys: Keys::Seeded(seed),
+        }
+    }
+
+    /// Returns whether the keys are derived from a seed.
+    pub fn is_deterministic(&self) -> bool {
+        matches!(self.keys, Keys::Seeded(_))
+    }
+}
+
+impl BuildHasher for HashState {
+    type Hasher = DefaultHasher;
+
+    fn build_hasher(&self) -> DefaultHasher {
+        match &self.keys {
+            Keys::Random(state) => state.build_hasher(),
+            Keys::Seeded(seed) => {
+                // DefaultHasher::new() always uses the same keys, and the seed makes the hashes of every seed differ.
+                let mut hasher = DefaultHasher::new();
+                hasher.write_u64(*seed);
+                hasher
+            }
+        }
+    }
+}
@@ -0,0 +1,1002 @@
+//! Binary heaps. [`BinaryHeap`] grows on demand, while [`ArrayHeap`] is built on a fixed-size [`Array`].
+//! [`IndexedHeap`] additionally lets the priority of any entry be changed after it was pushed, and
+//! [`PersistentHeap`] is an immutable heap whose versions share structure. [`MinMaxHeap`] serves both ends at once.
+//!
+//! All others are max-heaps with respect to their [`Compare`] implementation. Wrap the elements in
+//! [`Reverse`](`std::cmp::Reverse`) or supply a comparator to get a min-heap.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use crate::array::{Array, ArrayError};
+
+/// Decides the order of elements inside a heap. The heap yields the greatest element first.
+pub trait Compare<T> {
+    /// Compares two elements.
+    fn compare(&self, a: &T, b: &T) -> Ordering;
+}
+
+/// Orders elements by their [`Ord`] implementation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Natural;
+
+impl<T: Ord> Compare<T> for Natural {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+impl<T, F> Compare<T> for F
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self(a, b)
+    }
+}
+
+fn sift_up<T, C: Compare<T>>(data: &mut [T], cmp: &C, mut i: usize) {
+    while i > 0 {
+        let parent = (i - 1) / 2;
+        if cmp.compare(&data[i], &data[parent]) != Ordering::Greater {
+            break;
+        }
+        data.swap(i, parent);
+        i = parent;
+    }
+}
+
+fn sift_down<T, C: Compare<T>>(data: &mut [T], cmp: &C, mut i: usize) {
+    loop {
+        let left = 2 * i + 1;
+        let right = left + 1;
+        let mut largest = i;
+        if left < data.len() && cmp.compare(&data[left], &data[largest]) == Ordering::Greater {
+            largest = left;
+        }
+        if right < data.len() && cmp.compare(&data[right], &data[largest]) == Ordering::Greater {
+            largest = right;
+        }
+        if largest == i {
+            return;
+        }
+        data.swap(i, largest);
+        i = largest;
+    }
+}
+
+fn heapify<T, C: Compare<T>>(data: &mut [T], cmp: &C) {
+    for i in (0..data.len() / 2).rev() {
+        sift_down(data, cmp, i);
+    }
+}
+
+/// A growable binary max-heap.
+#[derive(Clone)]
+pub struct BinaryHeap<T, C = Natural> {
+    data: Vec<T>,
+    cmp: C,
+}
+
+impl<T: Ord> Default for BinaryHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> BinaryHeap<T> {
+    /// Constructs a new, empty heap ordered by [`Ord`].
+    pub fn new() -> Self {
+        Self::with_comparator(Natural)
+    }
+}
+
+impl<T, C: Compare<T>> BinaryHeap<T, C> {
+    /// Constructs a new, empty heap ordered by the comparator. The element the comparator considers greatest is
+    /// popped first.
+    /// ```
+    /// # use strctr::heap::BinaryHeap;
+    /// let mut h = BinaryHeap::with_comparator(|a: &i32, b: &i32| b.cmp(a));
+    /// h.push(3);
+    /// h.push(1);
+    /// h.push(2);
+    /// assert_eq!(h.pop(), Some(1));
+    /// ```
+    pub fn with_comparator(cmp: C) -> Self {
+        Self {
+            data: Vec::new(),
+            cmp,
+        }
+    }
+
+    /// Builds a heap out of the vector's elements in linear time.
+    /// ```
+    /// # use strctr::heap::{BinaryHeap, Natural};
+    /// let h = BinaryHeap::from_vec(vec![2, 7, 1, 5], Natural);
+    /// assert_eq!(h.peek(), Some(&7));
+    /// ```
+    pub fn from_vec(mut data: Vec<T>, cmp: C) -> Self {
+        heapify(&mut data, &cmp);
+        Self { data, cmp }
+    }
+
+    /// Returns the number of elements in the heap.
+    /// ```
+    /// # use strctr::heap::BinaryHeap;
+    /// let mut h = BinaryHeap::new();
+    /// assert_eq!(h.len(), 0);
+    /// h.push(1);
+    /// assert_eq!(h.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns whether the heap contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Removes every element from the heap.
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    /// Adds an element to the heap.
+    /// ```
+    /// # use strctr::heap::BinaryHeap;
+    /// let mut h = BinaryHeap::new();
+    /// h.push(1);
+    /// h.push(5);
+    /// assert_eq!(h.peek(), Some(&5));
+    /// ```
+    pub fn push(&mut self, elem: T) {
+        self.data.push(elem);
+        let last = self.data.len() - 1;
+        sift_up(&mut self.data, &self.cmp, last);
+    }
+
+    /// Removes the greatest element from the heap and returns it, or `None` if the heap is empty.
+    /// ```
+    /// # use strctr::heap::BinaryHeap;
+    /// use std::cmp::Reverse;
+    ///
+    /// let mut h = BinaryHeap::new();
+    /// h.push(Reverse(3));
+    /// h.push(Reverse(1));
+    /// h.push(Reverse(2));
+    /// assert_eq!(h.pop(), Some(Reverse(1)));
+    /// assert_eq!(h.pop(), Some(Reverse(2)));
+    /// ```
+    pub fn pop(&mut self) -> Option<T> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let elem = self.data.swap_remove(0);
+        sift_down(&mut self.data, &self.cmp, 0);
+        Some(elem)
+    }
+
+    /// Returns the greatest element of the heap without removing it.
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// Returns an iterator over the elements in no particular order.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    /// Consumes the heap and returns its elements in no particular order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    /// Consumes the heap and returns its elements in ascending order.
+    /// ```
+    /// # use strctr::heap::BinaryHeap;
+    /// let h: BinaryHeap<_> = [4, 1, 3, 2].into_iter().collect();
+    /// assert_eq!(h.into_sorted_vec(), vec![1, 2, 3, 4]);
+    /// ```
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut end = self.data.len();
+        while end > 1 {
+            end -= 1;
+            self.data.swap(0, end);
+            sift_down(&mut self.data[..end], &self.cmp, 0);
+        }
+        self.data
+    }
+}
+
+impl<T: Ord> FromIterator<T> for BinaryHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from_vec(iter.into_iter().collect(), Natural)
+    }
+}
+
+impl<T, C: Compare<T>> Extend<T> for BinaryHeap<T, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+/// A double-ended priority queue: a min-max heap, whose even levels are ordered like a min-heap and odd levels like
+/// a max-heap. Both the least and the greatest element can be peeked in constant time and popped in `O(log n)`.
+///
+/// Keeping the `k` smallest elements of a stream is a push followed by a [pop_max()](`Self::pop_max()`) whenever the
+/// heap grows past `k`, and the heap hands out both ends of the kept range.
+/// ```
+/// # use strctr::heap::MinMaxHeap;
+/// let mut fastest = MinMaxHeap::new();
+/// for latency in [87, 12, 45, 3, 99, 41, 7, 60] {
+///     fastest.push(latency);
+///     if fastest.len() > 3 {
+///         fastest.pop_max();
+///     }
+/// }
+/// assert_eq!(fastest.peek_min(), Some(&3));
+/// assert_eq!(fastest.peek_max(), Some(&12));
+/// assert_eq!(fastest.pop_min(), Some(3));
+/// assert_eq!(fastest.pop_min(), Some(7));
+/// ```
+#[derive(Clone)]
+pub struct MinMaxHeap<T, C = Natural> {
+    data: Vec<T>,
+    cmp: C,
+}
+
+impl<T: Ord> Default for MinMaxHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> MinMaxHeap<T> {
+    /// Constructs a new, empty heap ordered by [`Ord`].
+    pub fn new() -> Self {
+        Self::with_comparator(Natural)
+    }
+}
+
+impl<T, C: Compare<T>> MinMaxHeap<T, C> {
+    /// Constructs a new, empty heap ordered by the comparator.
+    pub fn with_comparator(cmp: C) -> Self {
+        Self {
+            data: Vec::new(),
+            cmp,
+        }
+    }
+
+    /// Builds a heap out of the vector's elements in linear time.
+    /// ```
+    /// # use strctr::heap::{MinMaxHeap, Natural};
+    /// let h = MinMaxHeap::from_vec(vec![5, 2, 8, 1, 9, 3], Natural);
+    /// assert_eq!((h.peek_min(), h.peek_max()), (Some(&1), Some(&9)));
+    /// ```
+    pub fn from_vec(data: Vec<T>, cmp: C) -> Self {
+        let mut heap = Self { data, cmp };
+        for i in (0..heap.data.len() / 2).rev() {
+            heap.trickle_down(i);
+        }
+        heap
+    }
+
+    /// Returns the number of elements in the heap.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns whether the heap contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Removes every element from the heap.
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    /// Adds an element to the heap.
+    pub fn push(&mut self, elem: T) {
+        self.data.push(elem);
+        let i = self.data.len() - 1;
+        if i == 0 {
+            return;
+        }
+        let parent = (i - 1) / 2;
+        let order = level_order(i);
+        if self.cmp.compare(&self.data[i], &self.data[parent]) == order.reverse() {
+            // The element is beyond its parent, so it belongs on the parent's kind of levels.
+            self.data.swap(i, parent);
+            self.bubble_up(parent, order.reverse());
+        } else {
+            self.bubble_up(i, order);
+        }
+    }
+
+    /// Returns the least element without removing it.
+    pub fn peek_min(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// Returns the greatest element without removing it.
+    pub fn peek_max(&self) -> Option<&T> {
+        self.data.get(self.max_index()?)
+    }
+
+    /// Removes the least element and returns it, or `None` if the heap is empty.
+    pub fn pop_min(&mut self) -> Option<T> {
+        self.remove_at(0)
+    }
+
+    /// Removes the greatest element and returns it, or `None` if the heap is empty.
+    /// ```
+    /// # use strctr::heap::MinMaxHeap;
+    /// let mut h: MinMaxHeap<_> = [4, 1, 7, 3].into_iter().collect();
+    /// assert_eq!(h.pop_max(), Some(7));
+    /// assert_eq!(h.pop_max(), Some(4));
+    /// assert_eq!(h.pop_min(), Some(1));
+    /// assert_eq!(h.pop_max(), Some(3));
+    /// assert_eq!(h.pop_max(), None);
+    /// ```
+    pub fn pop_max(&mut self) -> Option<T> {
+        self.remove_at(self.max_index()?)
+    }
+
+    /// Returns an iterator over the elements in no particular order.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    /// Consumes the heap and returns its elements in no particular order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    /// The greatest element is the root if it is alone, and otherwise one of its children.
+    fn max_index(&self) -> Option<usize> {
+        match self.data.len() {
+            0 => None,
+            1 => Some(0),
+            2 => Some(1),
+            _ => Some(
+                if self.cmp.compare(&self.data[2], &self.data[1]) == Ordering::Greater {
+                    2
+                } else {
+                    1
+                },
+            ),
+        }
+    }
+
+    fn remove_at(&mut self, i: usize) -> Option<T> {
+        if i >= self.data.len() {
+            return None;
+        }
+        let elem = self.data.swap_remove(i);
+        if i < self.data.len() {
+            self.trickle_down(i);
+        }
+        Some(elem)
+    }
+
+    /// Moves the element at `i` up its own kind of levels, while it compares to its grandparent by `order`.
+    fn bubble_up(&mut self, mut i: usize, order: Ordering) {
+        while i >= 3 {
+            let grandparent = ((i - 1) / 2 - 1) / 2;
+            if self.cmp.compare(&self.data[i], &self.data[grandparent]) != order {
+                return;
+            }
+            self.data.swap(i, grandparent);
+            i = grandparent;
+        }
+    }
+
+    /// Moves the element at `i` down until it is ordered with its children and grandchildren.
+    fn trickle_down(&mut self, mut i: usize) {
+        let order = level_order(i);
+        loop {
+            let first_child = 2 * i + 1;
+            let descendants = [
+                first_child,
+                first_child + 1,
+                2 * first_child + 1,
+                2 * first_child + 2,
+                2 * first_child + 3,
+                2 * first_child + 4,
+            ];
+            let Some(m) = descendants
+                .into_iter()
+                .take_while(|&d| d < self.data.len())
+                .reduce(|best, d| {
+                    if self.cmp.compare(&self.data[d], &self.data[best]) == order {
+                        d
+                    } else {
+                        best
+                    }
+                })
+            else {
+                return;
+            };
+            if self.cmp.compare(&self.data[m], &self.data[i]) != order {
+                return;
+            }
+            self.data.swap(i, m);
+            if m <= first_child + 1 {
+                return;
+            }
+            // A grandchild moved down to m may belong on its parent's levels instead.
+            let parent = (m - 1) / 2;
+            if self.cmp.compare(&self.data[m], &self.data[parent]) == order.reverse() {
+                self.data.swap(m, parent);
+            }
+            i = m;
+        }
+    }
+}
+
+/// Returns how the element at index `i` of a [`MinMaxHeap`] compares to its descendants: `Less` on the min levels,
+/// the even ones, and `Greater` on the max levels.
+fn level_order(i: usize) -> Ordering {
+    if (i + 1).ilog2().is_multiple_of(2) {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+impl<T: Ord> FromIterator<T> for MinMaxHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from_vec(iter.into_iter().collect(), Natural)
+    }
+}
+
+impl<T, C: Compare<T>> Extend<T> for MinMaxHeap<T, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+/// A binary max-heap that holds at most `N` elements, stored in an [`Array`].
+pub struct ArrayHeap<T, const N: usize, C = Natural> {
+    data: Array<T, N>,
+    cmp: C,
+}
+
+impl<T, const N: usize> Default for ArrayHeap<T, N>
+where
+    T: Copy + Default + Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> ArrayHeap<T, N>
+where
+    T: Copy + Default + Ord,
+{
+    /// Constructs a new, empty heap ordered by [`Ord`].
+    pub fn new() -> Self {
+        Self::with_comparator(Natural)
+    }
+}
+
+impl<T, const N: usize, C> ArrayHeap<T, N, C>
+where
+    T: Copy + Default,
+    C: Compare<T>,
+{
+    /// Constructs a new, empty heap ordered by the comparator.
+    pub fn with_comparator(cmp: C) -> Self {
+        Self {
+            data: Array::new(),
+            cmp,
+        }
+    }
+}
+
+impl<T: Copy, const N: usize, C: Compare<T>> ArrayHeap<T, N, C> {
+    /// Returns the number of elements in the heap.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns whether the heap contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the maximum number of elements the heap can hold.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Adds an element to the heap. Adding more elements than fit will result in an error being returned.
+    ///
+    /// For a more convenient (but less safe) method, see [push()](`Self::push()`)
+    /// ```
+    /// # use strctr::heap::ArrayHeap;
+    /// let mut h: ArrayHeap<usize, 1> = ArrayHeap::new();
+    /// assert!(h.try_push(1).is_ok());
+    /// assert!(h.try_push(2).is_err());
+    /// ```
+    pub fn try_push(&mut self, elem: T) -> Result<(), ArrayError> {
+        self.data.try_push(elem)?;
+        let last = self.data.len() - 1;
+        sift_up(self.data.as_mut_slice(), &self.cmp, last);
+        Ok(())
+    }
+
+    /// Adds an element to the heap.
+    ///
+    /// Panics if the heap is already full. For a non-panicing version, see [try_push()](`Self::try_push()`)
+    /// ```should_panic
+    /// # use strctr::heap::ArrayHeap;
+    /// let mut h: ArrayHeap<usize, 1> = ArrayHeap::new();
+    /// h.push(1);
+    /// h.push(2);
+    /// ```
+    pub fn push(&mut self, elem: T) {
+        if self.try_push(elem).is_err() {
+            panic!("Overflow: Heap is full, capacity is {}", N);
+        }
+    }
+
+    /// Removes the greatest element from the heap and returns it, or `None` if the heap is empty.
+    /// ```
+    /// # use strctr::heap::ArrayHeap;
+    /// let mut h: ArrayHeap<usize, 4> = ArrayHeap::new();
+    /// h.push(2);
+    /// h.push(9);
+    /// h.push(4);
+    /// assert_eq!(h.pop(), Some(9));
+    /// assert_eq!(h.pop(), Some(4));
+    /// ```
+    pub fn pop(&mut self) -> Option<T> {
+        let len = self.data.len();
+        if len == 0 {
+            return None;
+        }
+        self.data.as_mut_slice().swap(0, len - 1);
+        let elem = self.data.pop();
+        sift_down(self.data.as_mut_slice(), &self.cmp, 0);
+        elem
+    }
+
+    /// Returns the greatest element of the heap without removing it.
+    pub fn peek(&self) -> Option<&T> {
+        self.data.as_slice().first()
+    }
+
+    /// Returns an iterator over the elements in no particular order.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.data.as_slice().iter()
+    }
+}
+
+/// An addressable binary max-heap. Every entry is a unique key with a priority, and the priority of a key already in
+/// the heap can be changed or the key removed in `O(log n)`.
+///
+/// This is the heap graph algorithms like Dijkstra's or Prim's want; wrap the priorities in
+/// [`Reverse`](`std::cmp::Reverse`) to pop the smallest one first.
+/// ```
+/// # use strctr::heap::IndexedHeap;
+/// use std::cmp::Reverse;
+///
+/// let mut h = IndexedHeap::new();
+/// h.push("a", Reverse(10));
+/// h.push("b", Reverse(5));
+/// h.push("c", Reverse(7));
+/// // A shorter path to "a" was found.
+/// h.change_priority(&"a", Reverse(1));
+/// assert_eq!(h.pop(), Some(("a", Reverse(1))));
+/// assert_eq!(h.pop(), Some(("b", Reverse(5))));
+/// ```
+pub struct IndexedHeap<K, P, C = Natural> {
+    entries: Vec<(K, P)>,
+    positions: HashMap<K, usize>,
+    cmp: C,
+}
+
+impl<K: Hash + Eq + Clone, P: Ord> Default for IndexedHeap<K, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq + Clone, P: Ord> IndexedHeap<K, P> {
+    /// Constructs a new, empty heap ordered by the priorities' [`Ord`].
+    pub fn new() -> Self {
+        Self::with_comparator(Natural)
+    }
+}
+
+impl<K, P, C> IndexedHeap<K, P, C>
+where
+    K: Hash + Eq + Clone,
+    C: Compare<P>,
+{
+    /// Constructs a new, empty heap whose priorities are ordered by the comparator.
+    pub fn with_comparator(cmp: C) -> Self {
+        Self {
+            entries: Vec::new(),
+            positions: HashMap::new(),
+            cmp,
+        }
+    }
+
+    /// Returns the number of keys in the heap.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the heap contains no keys.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes every key from the heap.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.positions.clear();
+    }
+
+    /// Returns whether the key is in the heap.
+    pub fn contains(&self, key: &K) -> bool {
+        self.positions.contains_key(key)
+    }
+
+    /// Returns the priority of the key.
+    pub fn priority(&self, key: &K) -> Option<&P> {
+        self.positions.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    /// Adds the key with the given priority. If the key was already present, its priority is replaced and the old
+    /// one is returned.
+    /// ```
+    /// # use strctr::heap::IndexedHeap;
+    /// let mut h = IndexedHeap::new();
+    /// assert_eq!(h.push('x', 1), None);
+    /// assert_eq!(h.push('x', 3), Some(1));
+    /// assert_eq!(h.len(), 1);
+    /// ```
+    pub fn push(&mut self, key: K, priority: P) -> Option<P> {
+        if let Some(&i) = self.positions.get(&key) {
+            return Some(self.replace_at(i, priority));
+        }
+        let i = self.entries.len();
+        self.positions.insert(key.clone(), i);
+        self.entries.push((key, priority));
+        self.sift_up(i);
+        None
+    }
+
+    /// Changes the priority of a key already in the heap and returns the old priority, or `None` if the key is not
+    /// in the heap. The priority may move in either direction.
+    /// ```
+    /// # use strctr::heap::IndexedHeap;
+    /// let mut h = IndexedHeap::new();
+    /// h.push('a', 5);
+    /// h.push('b', 3);
+    /// assert_eq!(h.change_priority(&'b', 9), Some(3));
+    /// assert_eq!(h.peek(), Some((&'b', &9)));
+    /// assert_eq!(h.change_priority(&'z', 1), None);
+    /// ```
+    pub fn change_priority(&mut self, key: &K, priority: P) -> Option<P> {
+        let i = *self.positions.get(key)?;
+        Some(self.replace_at(i, priority))
+    }
+
+    /// Returns the key with the greatest priority, without removing it.
+    pub fn peek(&self) -> Option<(&K, &P)> {
+        self.entries.first().map(|(k, p)| (k, p))
+    }
+
+    /// Removes the key with the greatest priority and returns it along with its priority.
+    pub fn pop(&mut self) -> Option<(K, P)> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        Some(self.remove_at(0))
+    }
+
+    /// Removes the key from the heap and returns its priority.
+    /// ```
+    /// # use strctr::heap::IndexedHeap;
+    /// let mut h = IndexedHeap::new();
+    /// h.push('a', 5);
+    /// h.push('b', 3);
+    /// assert_eq!(h.remove(&'a'), Some(5));
+    /// assert_eq!(h.remove(&'a'), None);
+    /// assert_eq!(h.pop(), Some(('b', 3)));
+    /// ```
+    pub fn remove(&mut self, key: &K) -> Option<P> {
+        let i = *self.positions.get(key)?;
+        Some(self.remove_at(i).1)
+    }
+
+    /// Returns an iterator over the keys and priorities in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &P)> {
+        self.entries.iter().map(|(k, p)| (k, p))
+    }
+
+    fn replace_at(&mut self, i: usize, priority: P) -> P {
+        let old = std::mem::replace(&mut self.entries[i].1, priority);
+        match self.cmp.compare(&self.entries[i].1, &old) {
+            Ordering::Greater => self.sift_up(i),
+            Ordering::Less => self.sift_down(i),
+            Ordering::Equal => {}
+        }
+        old
+    }
+
+    fn remove_at(&mut self, i: usize) -> (K, P) {
+        let last = self.entries.len() - 1;
+        self.swap(i, last);
+        let (key, priority) = self.entries.pop().expect("non-empty heap");
+        self.positions.remove(&key);
+        if i < self.entries.len() {
+            self.sift_down(i);
+            self.sift_up(i);
+        }
+        (key, priority)
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.entries.swap(a, b);
+        for i in [a, b] {
+            if let Some(pos) = self.positions.get_mut(&self.entries[i].0) {
+                *pos = i;
+            }
+        }
+    }
+
+    fn greater(&self, a: usize, b: usize) -> bool {
+        self.cmp.compare(&self.entries[a].1, &self.entries[b].1) == Ordering::Greater
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if !self.greater(i, parent) {
+                break;
+            }
+            self.swap(i, parent);
+            i = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        loop {
+            let left = 2 * i + 1;
+            let right = left + 1;
+            let mut largest = i;
+            if left < self.entries.len() && self.greater(left, largest) {
+                largest = left;
+            }
+            if right < self.entries.len() && self.greater(right, largest) {
+                largest = right;
+            }
+            if largest == i {
+                return;
+            }
+            self.swap(i, largest);
+            i = largest;
+        }
+    }
+}
+
+struct LeftistNode<T> {
+    elem: T,
+    /// Length of the shortest path down to a missing child. The left child always has the larger rank.
+    rank: usize,
+    left: Option<Rc<LeftistNode<T>>>,
+    right: Option<Rc<LeftistNode<T>>>,
+}
+
+/// A persistent (immutable) leftist max-heap. Pushing, popping and merging return a new heap and leave the original
+/// untouched; both share all unchanged nodes, so forking a heap is `O(1)` and every operation is `O(log n)`.
+///
+/// This suits branch-and-bound searches, where every branch continues from the same queue of pending work.
+/// ```
+/// # use strctr::heap::PersistentHeap;
+/// let base = PersistentHeap::new().push(3).push(1);
+/// let left = base.push(5);
+/// let right = base.push(0);
+/// assert_eq!(left.peek(), Some(&5));
+/// assert_eq!(right.peek(), Some(&3));
+/// assert_eq!(base.len(), 2);
+/// ```
+pub struct PersistentHeap<T, C = Natural> {
+    root: Option<Rc<LeftistNode<T>>>,
+    len: usize,
+    cmp: C,
+}
+
+impl<T, C: Clone> Clone for PersistentHeap<T, C> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+            cmp: self.cmp.clone(),
+        }
+    }
+}
+
+impl<T: Ord> Default for PersistentHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> PersistentHeap<T> {
+    /// Constructs a new, empty heap ordered by [`Ord`].
+    pub fn new() -> Self {
+        Self::with_comparator(Natural)
+    }
+}
+
+impl<T, C> PersistentHeap<T, C> {
+    /// Constructs a new, empty heap ordered by the comparator. The element the comparator considers greatest is
+    /// popped first, and every heap derived from this one keeps its comparator.
+    /// ```
+    /// # use strctr::heap::PersistentHeap;
+    /// let h = PersistentHeap::with_comparator(|a: &i32, b: &i32| b.cmp(a)).push(3).push(1).push(2);
+    /// assert_eq!(h.peek(), Some(&1));
+    /// assert_eq!(h.into_sorted_vec(), vec![3, 2, 1]);
+    /// ```
+    pub fn with_comparator(cmp: C) -> Self {
+        Self {
+            root: None,
+            len: 0,
+            cmp,
+        }
+    }
+
+    /// Returns the number of elements in the heap.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the heap contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the greatest element of the heap.
+    pub fn peek(&self) -> Option<&T> {
+        self.root.as_ref().map(|n| &n.elem)
+    }
+}
+
+impl<T: Clone, C: Compare<T> + Clone> PersistentHeap<T, C> {
+    /// Returns a new heap that also contains the element.
+    /// ```
+    /// # use strctr::heap::PersistentHeap;
+    /// let h = PersistentHeap::new();
+    /// let h2 = h.push(1);
+    /// assert!(h.is_empty());
+    /// assert_eq!(h2.len(), 1);
+    /// ```
+    pub fn push(&self, elem: T) -> Self {
+        let single = Rc::new(LeftistNode {
+            elem,
+            rank: 1,
+            left: None,
+            right: None,
+        });
+        Self {
+            root: self.merge_nodes(self.root.clone(), Some(single)),
+            len: self.len + 1,
+            cmp: self.cmp.clone(),
+        }
+    }
+
+    /// Returns the greatest element along with a new heap that contains the rest, or `None` if the heap is empty.
+    /// ```
+    /// # use strctr::heap::PersistentHeap;
+    /// let h = PersistentHeap::new().push(2).push(7).push(4);
+    /// let (top, rest) = h.pop().unwrap();
+    /// assert_eq!(top, 7);
+    /// assert_eq!(rest.peek(), Some(&4));
+    /// assert_eq!(h.len(), 3);
+    /// ```
+    pub fn pop(&self) -> Option<(T, Self)> {
+        let root = self.root.as_ref()?;
+        let rest = Self {
+            root: self.merge_nodes(root.left.clone(), root.right.clone()),
+            len: self.len - 1,
+            cmp: self.cmp.clone(),
+        };
+        Some((root.elem.clone(), rest))
+    }
+
+    /// Returns a new heap containing the elements of both heaps, in `O(log n)`. The new heap is ordered by this heap's
+    /// comparator, which the other heap is assumed to share.
+    /// ```
+    /// # use strctr::heap::PersistentHeap;
+    /// let a = PersistentHeap::new().push(1).push(8);
+    /// let b = PersistentHeap::new().push(5);
+    /// let merged = a.merge(&b);
+    /// assert_eq!(merged.len(), 3);
+    /// assert_eq!(merged.into_sorted_vec(), vec![1, 5, 8]);
+    /// ```
+    pub fn merge(&self, other: &Self) -> Self {
+        Self {
+            root: self.merge_nodes(self.root.clone(), other.root.clone()),
+            len: self.len + other.len,
+            cmp: self.cmp.clone(),
+        }
+    }
+
+    /// Returns the elements in ascending order of the comparator.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        let mut out = Vec::with_capacity(self.len);
+        let mut heap = self;
+        while let Some((elem, rest)) = heap.pop() {
+            out.push(elem);
+            heap = rest;
+        }
+        out.reverse();
+        out
+    }
+
+    fn merge_nodes(
+        &self,
+        a: Option<Rc<LeftistNode<T>>>,
+        b: Option<Rc<LeftistNode<T>>>,
+    ) -> Option<Rc<LeftistNode<T>>> {
+        let (a, b) = match (a, b) {
+            (None, b) => return b,
+            (a, None) => return a,
+            (Some(a), Some(b)) => (a, b),
+        };
+        let (top, other) = if self.cmp.compare(&a.elem, &b.elem) != Ordering::Less {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let merged = self.merge_nodes(top.right.clone(), Some(other));
+        let left = top.left.clone();
+        let rank = |n: &Option<Rc<LeftistNode<T>>>| n.as_ref().map_or(0, |n| n.rank);
+        let (left, right) = if rank(&left) >= rank(&merged) {
+            (left, merged)
+        } else {
+            (merged, left)
+        };
+        Some(Rc::new(LeftistNode {
+            elem: top.elem.clone(),
+            rank: rank(&right) + 1,
+            left,
+            right,
+        }))
+    }
+}
+
+impl<T: Ord + Clone> FromIterator<T> for PersistentHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(Self::new(), |heap, elem| heap.push(elem))
+    }
+}
+
+impl<T, C> Drop for PersistentHeap<T, C> {
+    /// Frees nodes no other heap shares iteratively, so dropping a large heap cannot overflow the stack.
+    fn drop(&mut self) {
+        let mut stack: Vec<Rc<LeftistNode<T>>> = self.root.take().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            if let Ok(mut node) = Rc::try_unwrap(node) {
+                stack.extend(node.left.take());
+                stack.extend(node.right.take());
+            }
+        }
+    }
+}
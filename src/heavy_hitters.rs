@@ -0,0 +1,253 @@
+//! Space-Saving summary for finding the most frequent items of a stream in bounded memory.
+//!
+//! A summary with capacity `k` tracks at most `k` items. Every estimated count is an overestimate by at most the
+//! item's recorded error, and no error exceeds `n / k` after `n` insertions, so every item occurring more than
+//! `n / k` times is guaranteed to be tracked.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::heap::IndexedHeap;
+
+/// Frequency estimate of a tracked item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Estimate {
+    /// Estimated number of occurrences. The true count is at most this.
+    pub count: u64,
+    /// Maximum overestimation. The true count is at least `count - error`.
+    pub error: u64,
+}
+
+impl Estimate {
+    /// Returns the number of occurrences the item is guaranteed to have.
+    pub fn lower_bound(&self) -> u64 {
+        self.count - self.error
+    }
+}
+
+/// An item reported by [report()](`SpaceSaving::report()`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeavyHitter<T> {
+    /// The item.
+    pub item: T,
+    /// Its frequency estimate.
+    pub estimate: Estimate,
+    /// Whether the item is certain to exceed the threshold, not just possibly.
+    pub guaranteed: bool,
+}
+
+/// A Space-Saving summary tracking at most `capacity` items.
+pub struct SpaceSaving<T> {
+    /// Tracked items by count, smallest first, so the item to evict is always on top.
+    counts: IndexedHeap<T, Reverse<u64>>,
+    errors: HashMap<T, u64>,
+    capacity: usize,
+    total: u64,
+}
+
+impl<T: Hash + Eq + Clone> SpaceSaving<T> {
+    /// Constructs a new, empty summary tracking at most `capacity` items.
+    ///
+    /// Panics if the capacity is 0.
+    pub fn new(capacity: usize) -> Self {
+        if capacity == 0 {
+            panic!("InvalidCapacity: SpaceSaving needs to track at least one item");
+        }
+        Self {
+            counts: IndexedHeap::new(),
+            errors: HashMap::new(),
+            capacity,
+            total: 0,
+        }
+    }
+
+    /// Returns the maximum number of tracked items.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of currently tracked items.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns whether no item was inserted yet.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Returns the total number of occurrences inserted into the summary.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the largest possible overestimation of any count: the smallest tracked count once the summary is
+    /// full, and 0 before that.
+    pub fn max_error(&self) -> u64 {
+        if self.counts.len() < self.capacity {
+            return 0;
+        }
+        self.counts.peek().map_or(0, |(_, Reverse(c))| *c)
+    }
+
+    /// Records one occurrence of the item.
+    /// ```
+    /// # use strctr::heavy_hitters::SpaceSaving;
+    /// let mut s = SpaceSaving::new(2);
+    /// for word in ["a", "b", "a", "c", "a"] {
+    ///     s.insert(word);
+    /// }
+    /// assert_eq!(s.estimate(&"a").unwrap().count, 3);
+    /// // "c" evicted "b" and inherited its count as error.
+    /// assert_eq!(s.estimate(&"c").unwrap().error, 1);
+    /// assert!(s.estimate(&"b").is_none());
+    /// ```
+    pub fn insert(&mut self, item: T) {
+        self.insert_n(item, 1);
+    }
+
+    /// Records `n` occurrences of the item.
+    pub fn insert_n(&mut self, item: T, n: u64) {
+        if n == 0 {
+            return;
+        }
+        self.total += n;
+        if let Some(Reverse(count)) = self.counts.priority(&item) {
+            let count = *count + n;
+            self.counts.change_priority(&item, Reverse(count));
+            return;
+        }
+        if self.counts.len() < self.capacity {
+            self.counts.push(item.clone(), Reverse(n));
+            self.errors.insert(item, 0);
+            return;
+        }
+        let (evicted, Reverse(min)) = self.counts.pop().expect("full summary");
+        self.errors.remove(&evicted);
+        self.counts.push(item.clone(), Reverse(min + n));
+        self.errors.insert(item, min);
+    }
+
+    /// Returns the estimate of a tracked item, or `None` if the item is not tracked. An untracked item occurred at
+    /// most [max_error()](`Self::max_error()`) times.
+    pub fn estimate(&self, item: &T) -> Option<Estimate> {
+        let Reverse(count) = *self.counts.priority(item)?;
+        let error = self.errors.get(item).copied().unwrap_or(0);
+        Some(Estimate { count, error })
+    }
+
+    /// Returns up to `k` tracked items with the highest estimated counts, highest first.
+    /// ```
+    /// # use strctr::heavy_hitters::SpaceSaving;
+    /// let mut s = SpaceSaving::new(3);
+    /// for x in [1, 2, 2, 3, 3, 3] {
+    ///     s.insert(x);
+    /// }
+    /// let top: Vec<_> = s.top(2).into_iter().map(|(x, e)| (x, e.count)).collect();
+    /// assert_eq!(top, vec![(3, 3), (2, 2)]);
+    /// ```
+    pub fn top(&self, k: usize) -> Vec<(T, Estimate)> {
+        let mut items: Vec<(T, Estimate)> = self
+            .counts
+            .iter()
+            .map(|(item, _)| (item.clone(), self.estimate(item).expect("tracked item")))
+            .collect();
+        items.sort_by_key(|(_, e)| Reverse(e.count));
+        items.truncate(k);
+        items
+    }
+
+    /// Returns every item that may occur more than `threshold` times, highest estimate first. Items whose
+    /// [lower_bound()](`Estimate::lower_bound()`) exceeds the threshold are marked as guaranteed; the others might be
+    /// false positives. No item occurring more than `threshold` times is missing as long as the threshold is at least
+    /// [max_error()](`Self::max_error()`).
+    /// ```
+    /// # use strctr::heavy_hitters::SpaceSaving;
+    /// let mut s = SpaceSaving::new(2);
+    /// for x in [1, 1, 1, 1, 2, 3, 1] {
+    ///     s.insert(x);
+    /// }
+    /// let report = s.report(2);
+    /// assert_eq!(report.len(), 1);
+    /// assert_eq!(report[0].item, 1);
+    /// assert!(report[0].guaranteed);
+    /// ```
+    pub fn report(&self, threshold: u64) -> Vec<HeavyHitter<T>> {
+        self.top(self.capacity)
+            .into_iter()
+            .filter(|(_, e)| e.count > threshold)
+            .map(|(item, estimate)| HeavyHitter {
+                item,
+                guaranteed: estimate.lower_bound() > threshold,
+                estimate,
+            })
+            .collect()
+    }
+
+    /// Merges another summary into this one, as if this summary had also seen the other's stream. Items tracked by
+    /// only one summary are charged the other's [max_error()](`Self::max_error()`), and only the `capacity` items
+    /// with the highest combined counts are kept.
+    /// ```
+    /// # use strctr::heavy_hitters::SpaceSaving;
+    /// let mut a = SpaceSaving::new(2);
+    /// let mut b = SpaceSaving::new(2);
+    /// a.insert_n("x", 5);
+    /// b.insert_n("x", 4);
+    /// b.insert_n("y", 2);
+    /// a.merge(&b);
+    /// assert_eq!(a.estimate(&"x").unwrap().count, 9);
+    /// assert_eq!(a.total(), 11);
+    /// ```
+    pub fn merge(&mut self, other: &Self) {
+        let own_floor = self.max_error();
+        let other_floor = other.max_error();
+
+        // Entries stay in the order they are first seen, so the stable sort breaks ties the same way in every run.
+        let mut entries: Vec<(T, Estimate)> = Vec::new();
+        let mut index: HashMap<T, usize> = HashMap::new();
+        for (item, _) in self.counts.iter() {
+            let mut e = self.estimate(item).expect("tracked item");
+            if other.estimate(item).is_none() {
+                e.count += other_floor;
+                e.error += other_floor;
+            }
+            index.insert(item.clone(), entries.len());
+            entries.push((item.clone(), e));
+        }
+        for (item, _) in other.counts.iter() {
+            let theirs = other.estimate(item).expect("tracked item");
+            let i = *index.entry(item.clone()).or_insert_with(|| {
+                entries.push((
+                    item.clone(),
+                    Estimate {
+                        count: own_floor,
+                        error: own_floor,
+                    },
+                ));
+                entries.len() - 1
+            });
+            entries[i].1.count += theirs.count;
+            entries[i].1.error += theirs.error;
+        }
+
+        entries.sort_by_key(|(_, e)| Reverse(e.count));
+        entries.truncate(self.capacity);
+
+        self.counts.clear();
+        self.errors.clear();
+        for (item, e) in entries {
+            self.counts.push(item.clone(), Reverse(e.count));
+            self.errors.insert(item, e.error);
+        }
+        self.total += other.total;
+    }
+}
+
+impl<T: Hash + Eq + Clone> Extend<T> for SpaceSaving<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item);
+        }
+    }
+}
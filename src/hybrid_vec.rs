@@ -0,0 +1,417 @@
+//! Vector storing its first elements inline. Small vectors live in an [`Array`] without allocating, and once more
+//! than `N` elements are pushed, they move to a heap-allocated [`Vec`] instead of overflowing. How much room the heap
+//! storage grows by, and whether it shrinks back as elements are removed, is up to a [`GrowthPolicy`], [`Doubling`] by
+//! default, which never shrinks.
+
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+use crate::array::{Array, ArrayError};
+use crate::growth::{Doubling, GrowthPolicy};
+
+#[derive(Clone)]
+enum Storage<T, const N: usize> {
+    Inline(Array<T, N>),
+    Heap(Vec<T>),
+}
+
+/// A vector holding up to `N` elements inline, and spilling to the heap beyond that, where it grows as the policy `G`
+/// decides.
+#[derive(Clone)]
+pub struct HybridVec<T, const N: usize, G = Doubling> {
+    storage: Storage<T, N>,
+    /// Filler for unused inline slots, kept to refill the [`Array`] when shrinking back.
+    default: T,
+    policy: G,
+}
+
+impl<T, const N: usize, G> Default for HybridVec<T, N, G>
+where
+    T: Default + Copy,
+    G: GrowthPolicy + Default,
+{
+    fn default() -> Self {
+        Self::with_policy(G::default())
+    }
+}
+
+impl<T, const N: usize> HybridVec<T, N>
+where
+    T: Copy + Default,
+{
+    /// Constructs a new, empty vector of types T with inline room for N elements, using the default trait call
+    pub fn new() -> Self {
+        Self::new_with_default(T::default())
+    }
+}
+
+impl<T: Copy, const N: usize> HybridVec<T, N> {
+    /// Constructs a new, empty vector of types T with inline room for N elements, using the provided default value to
+    /// fill the inline storage.
+    pub fn new_with_default(def: T) -> Self {
+        Self::with_default_and_policy(def, Doubling)
+    }
+}
+
+impl<T, const N: usize, G> HybridVec<T, N, G>
+where
+    T: Copy + Default,
+    G: GrowthPolicy,
+{
+    /// Constructs a new, empty vector with inline room for N elements, whose heap storage grows as the policy decides.
+    pub fn with_policy(policy: G) -> Self {
+        Self::with_default_and_policy(T::default(), policy)
+    }
+}
+
+impl<T: Copy, const N: usize, G: GrowthPolicy> HybridVec<T, N, G> {
+    /// Constructs a new, empty vector like [new_with_default()](`HybridVec::new_with_default()`), whose heap storage
+    /// grows as the policy decides.
+    pub fn with_default_and_policy(def: T, policy: G) -> Self {
+        Self {
+            storage: Storage::Inline(Array::new_with_default(def)),
+            default: def,
+            policy,
+        }
+    }
+
+    /// Returns the growth policy.
+    pub fn policy(&self) -> &G {
+        &self.policy
+    }
+
+    /// Returns whether the vector is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of elements inside the vector.
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Inline(array) => array.len(),
+            Storage::Heap(vec) => vec.len(),
+        }
+    }
+
+    /// Returns the number of elements the vector can hold without allocating.
+    pub fn capacity(&self) -> usize {
+        match &self.storage {
+            Storage::Inline(_) => N,
+            Storage::Heap(vec) => vec.capacity(),
+        }
+    }
+
+    /// Returns whether the elements are stored inline, meaning the vector never outgrew `N` elements or was shrunk
+    /// back since.
+    /// ```
+    /// # use strctr::hybrid_vec::HybridVec;
+    /// let mut v: HybridVec<usize, 2> = HybridVec::new();
+    /// v.push(1);
+    /// v.push(2);
+    /// assert!(v.is_inline());
+    /// v.push(3);
+    /// assert!(!v.is_inline());
+    /// assert_eq!(v.as_slice(), &[1, 2, 3]);
+    /// ```
+    pub fn is_inline(&self) -> bool {
+        matches!(self.storage, Storage::Inline(_))
+    }
+
+    /// Adds an element to the end of the vector, moving the elements to the heap if they no longer fit inline.
+    ///
+    /// Panics if the vector is full and the growth policy refuses to grow it.
+    pub fn push(&mut self, elem: T) {
+        if self.try_push(elem).is_err() {
+            panic!(
+                "Overflow: The growth policy refused to grow past {} elements",
+                self.capacity()
+            )
+        }
+    }
+
+    /// Adds an element to the end of the vector like [push()](`Self::push()`), but returns an error instead of
+    /// panicking if the vector is full and the growth policy refuses to grow it.
+    /// ```
+    /// # use strctr::growth::{Capped, OneAndHalf};
+    /// # use strctr::hybrid_vec::HybridVec;
+    /// let mut v: HybridVec<u8, 4, _> = HybridVec::with_policy(Capped::new(OneAndHalf, 8));
+    /// v.extend(0..5);
+    /// assert_eq!(v.capacity(), 6);
+    /// v.extend(5..8);
+    /// assert_eq!(v.capacity(), 8);
+    /// assert!(v.try_push(8).is_err());
+    /// assert_eq!(v.len(), 8);
+    /// ```
+    pub fn try_push(&mut self, elem: T) -> Result<(), ArrayError> {
+        let len = self.len();
+        if len == self.capacity() {
+            let capacity = self.policy.grow(len, len + 1).ok_or(ArrayError::Overflow)?;
+            match &mut self.storage {
+                Storage::Inline(array) => {
+                    let mut vec = Vec::with_capacity(capacity);
+                    vec.extend_from_slice(array.as_slice());
+                    self.storage = Storage::Heap(vec);
+                }
+                Storage::Heap(vec) => vec.reserve_exact(capacity - len),
+            }
+        }
+        match &mut self.storage {
+            Storage::Inline(array) => array.push(elem),
+            Storage::Heap(vec) => vec.push(elem),
+        }
+        Ok(())
+    }
+
+    /// Removes the last element from the vector and returns it, or `None` if the vector is empty.
+    ///
+    /// If the growth policy shrinks the vector, its elements move to a smaller allocation, or back inline if they fit.
+    /// ```
+    /// # use strctr::growth::{Doubling, Shrinking};
+    /// # use strctr::hybrid_vec::HybridVec;
+    /// let mut v: HybridVec<u32, 4, _> = HybridVec::with_policy(Shrinking::new(Doubling, 4));
+    /// v.extend(0..64);
+    /// assert_eq!(v.capacity(), 64);
+    /// while v.len() > 15 {
+    ///     v.pop();
+    /// }
+    /// assert_eq!(v.capacity(), 30);
+    /// while v.len() > 1 {
+    ///     v.pop();
+    /// }
+    /// assert!(v.is_inline());
+    /// ```
+    pub fn pop(&mut self) -> Option<T> {
+        let elem = match &mut self.storage {
+            Storage::Inline(array) => array.pop(),
+            Storage::Heap(vec) => vec.pop(),
+        };
+        self.shrink_by_policy();
+        elem
+    }
+
+    /// Removes all elements. Heap storage keeps its allocation, unless the growth policy shrinks the vector.
+    pub fn clear(&mut self) {
+        match &mut self.storage {
+            Storage::Inline(_) => {
+                self.storage = Storage::Inline(Array::new_with_default(self.default))
+            }
+            Storage::Heap(vec) => vec.clear(),
+        }
+        self.shrink_by_policy();
+    }
+
+    /// Moves the elements back inline if they fit, and otherwise shrinks the heap allocation to fit them.
+    /// ```
+    /// # use strctr::hybrid_vec::HybridVec;
+    /// let mut v: HybridVec<usize, 2> = (1..=3).collect();
+    /// v.pop();
+    /// v.shrink_to_fit();
+    /// assert!(v.is_inline());
+    /// assert_eq!(v.as_slice(), &[1, 2]);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        if let Storage::Heap(vec) = &mut self.storage {
+            if vec.len() <= N {
+                let mut array = Array::new_with_default(self.default);
+                vec.iter().for_each(|&elem| array.push(elem));
+                self.storage = Storage::Inline(array);
+            } else {
+                vec.shrink_to_fit();
+            }
+        }
+    }
+
+    /// Shrinks the capacity to at least `min_capacity` and the length, moving the elements back inline if the result
+    /// fits. Does nothing if the capacity is already lower.
+    /// ```
+    /// # use strctr::hybrid_vec::HybridVec;
+    /// let mut v: HybridVec<usize, 2> = (0..100).collect();
+    /// while v.len() > 10 {
+    ///     v.pop();
+    /// }
+    /// v.shrink_to(40);
+    /// assert!(v.capacity() >= 40 && v.capacity() < 128);
+    /// v.shrink_to(0);
+    /// assert_eq!(v.capacity(), 10);
+    /// ```
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        if let Storage::Heap(vec) = &mut self.storage {
+            if min_capacity.max(vec.len()) <= N {
+                self.shrink_to_fit();
+            } else {
+                vec.shrink_to(min_capacity);
+            }
+        }
+    }
+
+    /// Shrinks the vector if the growth policy asks for it.
+    fn shrink_by_policy(&mut self) {
+        if let Storage::Heap(vec) = &self.storage {
+            if let Some(capacity) = self.policy.shrink(vec.len(), vec.capacity()) {
+                self.shrink_to(capacity);
+            }
+        }
+    }
+
+    /// Returns the elements as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        match &self.storage {
+            Storage::Inline(array) => array.as_slice(),
+            Storage::Heap(vec) => vec,
+        }
+    }
+
+    /// Returns the elements as a mutable slice.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        match &mut self.storage {
+            Storage::Inline(array) => array.as_mut_slice(),
+            Storage::Heap(vec) => vec,
+        }
+    }
+
+    /// Returns an iterator over the elements.
+    /// ```
+    /// # use strctr::hybrid_vec::HybridVec;
+    /// let v: HybridVec<usize, 4> = (1..=6).collect();
+    /// assert_eq!(v.iter().sum::<usize>(), 21);
+    /// ```
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    /// Returns an iterator over mutable references to the elements.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.as_mut_slice().iter_mut()
+    }
+}
+
+impl<T: Copy, const N: usize, G: GrowthPolicy> Index<usize> for HybridVec<T, N, G> {
+    type Output = T;
+
+    /// Returns the element at the specified index.
+    /// ```
+    /// # use strctr::hybrid_vec::HybridVec;
+    /// let v: HybridVec<usize, 1> = [4, 5].into_iter().collect();
+    /// assert_eq!(v[1], 5);
+    /// ```
+    ///
+    /// Panics if index >= [len()](`Self::len()`).
+    /// ```should_panic
+    /// # use strctr::hybrid_vec::HybridVec;
+    /// let v: HybridVec<usize, 5> = HybridVec::new();
+    /// let x = v[0];
+    /// ```
+    fn index(&self, index: usize) -> &Self::Output {
+        if index >= self.len() {
+            panic!(
+                "OutOfBounds: Wanted index {}, but length is {}",
+                index,
+                self.len()
+            )
+        }
+        &self.as_slice()[index]
+    }
+}
+
+impl<T: Copy, const N: usize, G: GrowthPolicy> IndexMut<usize> for HybridVec<T, N, G> {
+    /// Allows updating the values within the vector.
+    ///
+    /// Panics if index >= [len()](`Self::len()`).
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        if index >= self.len() {
+            panic!(
+                "OutOfBounds: Wanted index {}, but length is {}",
+                index,
+                self.len()
+            )
+        }
+        &mut self.as_mut_slice()[index]
+    }
+}
+
+impl<T, const N: usize, G> FromIterator<T> for HybridVec<T, N, G>
+where
+    T: Copy + Default,
+    G: GrowthPolicy + Default,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = Self::default();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<T: Copy, const N: usize, G: GrowthPolicy> Extend<T> for HybridVec<T, N, G> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|elem| self.push(elem));
+    }
+}
+
+impl<'a, T: Copy, const N: usize, G: GrowthPolicy> IntoIterator for &'a HybridVec<T, N, G> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Copy + PartialEq, const N: usize, G: GrowthPolicy> PartialEq for HybridVec<T, N, G> {
+    /// Compares the elements, regardless of where they are stored.
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Copy + Eq, const N: usize, G: GrowthPolicy> Eq for HybridVec<T, N, G> {}
+
+impl<T: Copy + fmt::Debug, const N: usize, G: GrowthPolicy> fmt::Debug for HybridVec<T, N, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Copy + Sync, const N: usize, G: GrowthPolicy> rayon::iter::IntoParallelIterator
+    for &'a HybridVec<T, N, G>
+{
+    type Item = &'a T;
+    type Iter = rayon::slice::Iter<'a, T>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.as_slice().into_par_iter()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Copy + Send, const N: usize, G: GrowthPolicy> rayon::iter::IntoParallelIterator
+    for &'a mut HybridVec<T, N, G>
+{
+    type Item = &'a mut T;
+    type Iter = rayon::slice::IterMut<'a, T>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.as_mut_slice().into_par_iter()
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl<T, const N: usize, G> quickcheck::Arbitrary for HybridVec<T, N, G>
+where
+    T: quickcheck::Arbitrary + Copy + Default,
+    G: GrowthPolicy + Default + Clone + 'static,
+{
+    /// Generates a vector of arbitrary elements, which may or may not have spilled to the heap.
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        Vec::<T>::arbitrary(g).into_iter().collect()
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(
+            self.as_slice()
+                .to_vec()
+                .shrink()
+                .map(|elements| elements.into_iter().collect()),
+        )
+    }
+}
@@ -0,0 +1,9 @@
+//! Immutable collections for functional-style code.
+//!
+//! Every "modifying" operation returns a new collection and leaves the original untouched. Both share all the nodes the
+//! change did not touch, so keeping old versions around is cheap and cloning is `O(1)`. See also
+//! [`PersistentHeap`](`crate::heap::PersistentHeap`).
+
+mod hash_map;
+
+pub use hash_map::{HashMap, Iter};
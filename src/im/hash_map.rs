@@ -0,0 +1,428 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+use std::rc::Rc;
+use std::slice;
+
+use crate::hash::HashState;
+
+/// Every level of the trie consumes 5 bits of the hash, so nodes have up to 32 entries.
+const BITS: u32 = 5;
+const MASK: u64 = (1 << BITS) - 1;
+
+#[derive(Clone)]
+struct Node<K, V> {
+    /// Bit `i` is set if the node has an entry for the 5-bit hash chunk `i`. Entries are stored in the order of their
+    /// bits, without gaps.
+    bitmap: u32,
+    entries: Vec<Entry<K, V>>,
+}
+
+#[derive(Clone)]
+enum Entry<K, V> {
+    Leaf {
+        hash: u64,
+        key: K,
+        value: V,
+    },
+    /// Keys whose whole hashes are equal.
+    Collision {
+        hash: u64,
+        pairs: Vec<(K, V)>,
+    },
+    Branch(Rc<Node<K, V>>),
+}
+
+impl<K, V> Node<K, V> {
+    fn empty() -> Self {
+        Self {
+            bitmap: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Returns the bit of the hash chunk at this level, and the index its entry has or would have.
+    fn slot(&self, hash: u64, shift: u32) -> (u32, usize) {
+        let bit = 1 << ((hash >> shift) & MASK);
+        (bit, (self.bitmap & (bit - 1)).count_ones() as usize)
+    }
+}
+
+impl<K, V> Entry<K, V> {
+    fn hash(&self) -> u64 {
+        match self {
+            Entry::Leaf { hash, .. } | Entry::Collision { hash, .. } => *hash,
+            Entry::Branch(_) => unreachable!("branches have no single hash"),
+        }
+    }
+}
+
+/// A persistent (immutable) hash map, stored as a hash array mapped trie.
+///
+/// The trie branches on 5 bits of the key's hash per level, so lookups, insertions and removals take `O(log32 n)`
+/// steps. Inserting and removing return a new map that copies only the nodes on the path to the key.
+/// ```
+/// # use strctr::im::HashMap;
+/// let v1 = HashMap::new().insert("apples", 3).insert("pears", 5);
+/// let v2 = v1.insert("apples", 4).remove("pears");
+/// assert_eq!(v1.get("apples"), Some(&3));
+/// assert_eq!(v1.get("pears"), Some(&5));
+/// assert_eq!(v2.get("apples"), Some(&4));
+/// assert_eq!(v2.get("pears"), None);
+/// ```
+pub struct HashMap<K, V> {
+    root: Rc<Node<K, V>>,
+    len: usize,
+    hasher: HashState,
+}
+
+impl<K, V> Clone for HashMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+impl<K, V> Default for HashMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> HashMap<K, V> {
+    /// Constructs a new, empty map.
+    pub fn new() -> Self {
+        Self::with_hasher(HashState::new())
+    }
+
+    /// Constructs a new, empty map that hashes keys with the state's keys. Maps derived from it by insertions and
+    /// removals keep the state.
+    pub fn with_hasher(state: HashState) -> Self {
+        Self {
+            root: Rc::new(Node::empty()),
+            len: 0,
+            hasher: state,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator over the entries, in no particular order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            stack: vec![self.root.entries.iter()],
+            collision: [].iter(),
+            remaining: self.len,
+        }
+    }
+
+    /// Returns an iterator over the keys, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values, in no particular order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+}
+
+impl<K: Hash + Eq, V> HashMap<K, V> {
+    /// Returns a reference to the value corresponding to the key.
+    pub fn get<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        let hash = self.hasher.hash_one(key);
+        let mut node = &*self.root;
+        let mut shift = 0;
+        loop {
+            let (bit, i) = node.slot(hash, shift);
+            if node.bitmap & bit == 0 {
+                return None;
+            }
+            match &node.entries[i] {
+                Entry::Leaf { key: k, value, .. } => {
+                    return (k.borrow() == key).then_some(value);
+                }
+                Entry::Collision { pairs, .. } => {
+                    return pairs
+                        .iter()
+                        .find(|(k, _)| k.borrow() == key)
+                        .map(|(_, v)| v);
+                }
+                Entry::Branch(child) => {
+                    node = child;
+                    shift += BITS;
+                }
+            }
+        }
+    }
+
+    /// Returns whether the map contains the key.
+    pub fn contains_key<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.get(key).is_some()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> HashMap<K, V> {
+    /// Returns a new map that also maps the key to the value, replacing any previous value.
+    /// ```
+    /// # use strctr::im::HashMap;
+    /// let empty = HashMap::new();
+    /// let one = empty.insert(1, "a");
+    /// assert!(empty.is_empty());
+    /// assert_eq!(one.len(), 1);
+    /// assert_eq!(one.insert(1, "b").len(), 1);
+    /// ```
+    pub fn insert(&self, key: K, value: V) -> Self {
+        let mut map = self.clone();
+        map.insert_in_place(key, value);
+        map
+    }
+
+    /// Returns a new map without the key. If the key is missing, the new map shares the whole trie with this one.
+    pub fn remove<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+    {
+        let mut map = self.clone();
+        // Removing clones the nodes on the way down, which is wasted if the key turns out to be missing.
+        if self.contains_key(key) {
+            let hash = self.hasher.hash_one(key);
+            remove(Rc::make_mut(&mut map.root), hash, 0, key);
+            map.len -= 1;
+        }
+        map
+    }
+
+    /// Inserts into this version, copying only nodes that are shared with other versions.
+    fn insert_in_place(&mut self, key: K, value: V) {
+        let hash = self.hasher.hash_one(&key);
+        if insert(Rc::make_mut(&mut self.root), hash, 0, key, value) {
+            self.len += 1;
+        }
+    }
+}
+
+/// Inserts the pair below the node at the given level. Returns whether the key is new.
+fn insert<K: Eq + Clone, V: Clone>(
+    node: &mut Node<K, V>,
+    hash: u64,
+    shift: u32,
+    key: K,
+    value: V,
+) -> bool {
+    let (bit, i) = node.slot(hash, shift);
+    if node.bitmap & bit == 0 {
+        node.bitmap |= bit;
+        node.entries.insert(i, Entry::Leaf { hash, key, value });
+        return true;
+    }
+    match &mut node.entries[i] {
+        Entry::Leaf {
+            hash: h,
+            key: k,
+            value: v,
+        } if *h == hash => {
+            if *k == key {
+                *v = value;
+                return false;
+            }
+            let old = mem::replace(&mut node.entries[i], placeholder(hash));
+            let Entry::Leaf {
+                key: k, value: v, ..
+            } = old
+            else {
+                unreachable!()
+            };
+            node.entries[i] = Entry::Collision {
+                hash,
+                pairs: vec![(k, v), (key, value)],
+            };
+            true
+        }
+        Entry::Collision { hash: h, pairs } if *h == hash => {
+            match pairs.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, v)) => {
+                    *v = value;
+                    false
+                }
+                None => {
+                    pairs.push((key, value));
+                    true
+                }
+            }
+        }
+        Entry::Branch(child) => insert(Rc::make_mut(child), hash, shift + BITS, key, value),
+        // A leaf or collision with a different hash moves one level down, next to the new leaf.
+        _ => {
+            let old = mem::replace(&mut node.entries[i], placeholder(hash));
+            node.entries[i] = Entry::Branch(Rc::new(pair(
+                old,
+                Entry::Leaf { hash, key, value },
+                shift + BITS,
+            )));
+            true
+        }
+    }
+}
+
+/// Returns an entry to hold a slot while its old entry is moved out. It does not allocate.
+fn placeholder<K, V>(hash: u64) -> Entry<K, V> {
+    Entry::Collision {
+        hash,
+        pairs: Vec::new(),
+    }
+}
+
+/// Builds the node at the given level holding two leaves or collisions with different hashes.
+fn pair<K, V>(a: Entry<K, V>, b: Entry<K, V>, shift: u32) -> Node<K, V> {
+    let (a_chunk, b_chunk) = ((a.hash() >> shift) & MASK, (b.hash() >> shift) & MASK);
+    if a_chunk == b_chunk {
+        // The hashes differ somewhere, so this recursion ends by the last level at the latest.
+        return Node {
+            bitmap: 1 << a_chunk,
+            entries: vec![Entry::Branch(Rc::new(pair(a, b, shift + BITS)))],
+        };
+    }
+    Node {
+        bitmap: 1 << a_chunk | 1 << b_chunk,
+        entries: if a_chunk < b_chunk {
+            vec![a, b]
+        } else {
+            vec![b, a]
+        },
+    }
+}
+
+/// Removes the key, which must be present below the node. Branches left with a single leaf or collision are replaced
+/// by it, so the trie never gets deeper than its keys need.
+fn remove<K: Borrow<Q> + Clone, V: Clone, Q: Eq + ?Sized>(
+    node: &mut Node<K, V>,
+    hash: u64,
+    shift: u32,
+    key: &Q,
+) {
+    let (bit, i) = node.slot(hash, shift);
+    match &mut node.entries[i] {
+        Entry::Leaf { .. } => {
+            node.bitmap &= !bit;
+            node.entries.remove(i);
+        }
+        Entry::Collision { pairs, .. } => {
+            pairs.retain(|(k, _)| k.borrow() != key);
+            if pairs.len() == 1 {
+                let (key, value) = pairs.pop().expect("one pair left");
+                node.entries[i] = Entry::Leaf { hash, key, value };
+            }
+        }
+        Entry::Branch(child) => {
+            let child = Rc::make_mut(child);
+            remove(child, hash, shift + BITS, key);
+            if child.entries.len() == 1 && !matches!(child.entries[0], Entry::Branch(_)) {
+                node.entries[i] = child.entries.pop().expect("one entry left");
+            }
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> FromIterator<(K, V)> for HashMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Extend<(K, V)> for HashMap<K, V> {
+    /// Inserts the pairs into this map. Nodes shared with other versions are copied first, so those stay unchanged.
+    /// ```
+    /// # use strctr::im::HashMap;
+    /// let base: HashMap<_, _> = (0..100).map(|i| (i, i * i)).collect();
+    /// let mut extended = base.clone();
+    /// extended.extend([(7, 0), (100, 10_000)]);
+    /// assert_eq!(extended.get(&7), Some(&0));
+    /// assert_eq!(base.get(&7), Some(&49));
+    /// assert_eq!((base.len(), extended.len()), (100, 101));
+    /// ```
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert_in_place(key, value);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V: PartialEq> PartialEq for HashMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl<K: Hash + Eq, V: Eq> Eq for HashMap<K, V> {}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for HashMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a HashMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the entries of an [`HashMap`], created by [iter()](`HashMap::iter()`).
+pub struct Iter<'a, K, V> {
+    /// The entries left to visit on every level of the path to the current node.
+    stack: Vec<slice::Iter<'a, Entry<K, V>>>,
+    collision: slice::Iter<'a, (K, V)>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((k, v)) = self.collision.next() {
+                self.remaining -= 1;
+                return Some((k, v));
+            }
+            match self.stack.last_mut()?.next() {
+                None => {
+                    self.stack.pop();
+                }
+                Some(Entry::Leaf { key, value, .. }) => {
+                    self.remaining -= 1;
+                    return Some((key, value));
+                }
+                Some(Entry::Collision { pairs, .. }) => self.collision = pairs.iter(),
+                Some(Entry::Branch(child)) => self.stack.push(child.entries.iter()),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
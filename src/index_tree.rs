@@ -0,0 +1,648 @@
+//! Arena-backed n-ary tree. Nodes live in a slab and are addressed by [`NodeId`] handles instead of pointers.
+
+use std::collections::VecDeque;
+use std::ops::{Index, IndexMut};
+
+/// List of errors that could occur when restructuring an [`IndexTree`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum IndexTreeError {
+    /// The node id does not belong to a live node; it was removed or never existed.
+    InvalidNode,
+    /// The operation would make a node its own ancestor.
+    Cycle,
+}
+
+/// Handle to a node of an [`IndexTree`]. Handles of removed nodes are detected rather than silently reused, because
+/// each slot carries a generation counter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: usize,
+    generation: u32,
+}
+
+struct NodeData<T> {
+    value: T,
+    parent: Option<NodeId>,
+    first_child: Option<NodeId>,
+    last_child: Option<NodeId>,
+    prev_sibling: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+}
+
+struct Slot<T> {
+    generation: u32,
+    data: Option<NodeData<T>>,
+}
+
+/// A general n-ary tree (or forest) stored in a slab.
+pub struct IndexTree<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<usize>,
+    len: usize,
+}
+
+impl<T> Default for IndexTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> IndexTree<T> {
+    /// Constructs a new, empty tree.
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of live nodes.
+    /// ```
+    /// # use strctr::index_tree::IndexTree;
+    /// let mut t = IndexTree::new();
+    /// assert_eq!(t.len(), 0);
+    /// t.new_node("root");
+    /// assert_eq!(t.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the tree has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Creates a new detached node and returns its id. Attach it with [append()](`Self::append()`) or one of the
+    /// insert methods.
+    /// ```
+    /// # use strctr::index_tree::IndexTree;
+    /// let mut t = IndexTree::new();
+    /// let root = t.new_node(1);
+    /// assert_eq!(t[root], 1);
+    /// ```
+    pub fn new_node(&mut self, value: T) -> NodeId {
+        let data = NodeData {
+            value,
+            parent: None,
+            first_child: None,
+            last_child: None,
+            prev_sibling: None,
+            next_sibling: None,
+        };
+        self.len += 1;
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.data = Some(data);
+                NodeId {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    data: Some(data),
+                });
+                NodeId {
+                    index: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    /// Returns whether the id refers to a live node.
+    /// ```
+    /// # use strctr::index_tree::IndexTree;
+    /// let mut t = IndexTree::new();
+    /// let n = t.new_node(1);
+    /// assert!(t.contains(n));
+    /// t.remove_subtree(n);
+    /// assert!(!t.contains(n));
+    /// ```
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.data(id).is_some()
+    }
+
+    /// Returns a reference to the node's value.
+    pub fn get(&self, id: NodeId) -> Option<&T> {
+        self.data(id).map(|d| &d.value)
+    }
+
+    /// Returns a mutable reference to the node's value.
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut T> {
+        self.data_mut(id).map(|d| &mut d.value)
+    }
+
+    /// Returns the parent of the node.
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.data(id)?.parent
+    }
+
+    /// Returns the first child of the node.
+    pub fn first_child(&self, id: NodeId) -> Option<NodeId> {
+        self.data(id)?.first_child
+    }
+
+    /// Returns the last child of the node.
+    pub fn last_child(&self, id: NodeId) -> Option<NodeId> {
+        self.data(id)?.last_child
+    }
+
+    /// Returns the sibling following the node.
+    pub fn next_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self.data(id)?.next_sibling
+    }
+
+    /// Returns the sibling preceding the node.
+    pub fn previous_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self.data(id)?.prev_sibling
+    }
+
+    /// Returns an iterator over the direct children of the node.
+    /// ```
+    /// # use strctr::index_tree::IndexTree;
+    /// let mut t = IndexTree::new();
+    /// let root = t.new_node(0);
+    /// let a = t.new_node(1);
+    /// let b = t.new_node(2);
+    /// t.append(root, a);
+    /// t.append(root, b);
+    /// let values: Vec<_> = t.children(root).map(|c| t[c]).collect();
+    /// assert_eq!(values, vec![1, 2]);
+    /// ```
+    pub fn children(&self, id: NodeId) -> Children<'_, T> {
+        Children {
+            tree: self,
+            next: self.first_child(id),
+        }
+    }
+
+    /// Returns an iterator over the ancestors of the node, starting with its parent.
+    /// ```
+    /// # use strctr::index_tree::IndexTree;
+    /// let mut t = IndexTree::new();
+    /// let root = t.new_node(0);
+    /// let mid = t.new_node(1);
+    /// let leaf = t.new_node(2);
+    /// t.append(root, mid);
+    /// t.append(mid, leaf);
+    /// assert_eq!(t.ancestors(leaf).collect::<Vec<_>>(), vec![mid, root]);
+    /// ```
+    pub fn ancestors(&self, id: NodeId) -> Ancestors<'_, T> {
+        Ancestors {
+            tree: self,
+            next: self.parent(id),
+        }
+    }
+
+    /// Returns a pre-order iterator over the subtree rooted at the node: every node is visited before its children.
+    /// ```
+    /// # use strctr::index_tree::IndexTree;
+    /// let mut t = IndexTree::new();
+    /// let root = t.new_node(0);
+    /// let a = t.new_node(1);
+    /// let b = t.new_node(2);
+    /// let c = t.new_node(3);
+    /// t.append(root, a);
+    /// t.append(a, b);
+    /// t.append(root, c);
+    /// let values: Vec<_> = t.pre_order(root).map(|n| t[n]).collect();
+    /// assert_eq!(values, vec![0, 1, 2, 3]);
+    /// ```
+    pub fn pre_order(&self, root: NodeId) -> PreOrder<'_, T> {
+        PreOrder {
+            tree: self,
+            root,
+            next: self.contains(root).then_some(root),
+        }
+    }
+
+    /// Returns a post-order iterator over the subtree rooted at the node: every node is visited after its children.
+    /// ```
+    /// # use strctr::index_tree::IndexTree;
+    /// let mut t = IndexTree::new();
+    /// let root = t.new_node(0);
+    /// let a = t.new_node(1);
+    /// let b = t.new_node(2);
+    /// let c = t.new_node(3);
+    /// t.append(root, a);
+    /// t.append(a, b);
+    /// t.append(root, c);
+    /// let values: Vec<_> = t.post_order(root).map(|n| t[n]).collect();
+    /// assert_eq!(values, vec![2, 1, 3, 0]);
+    /// ```
+    pub fn post_order(&self, root: NodeId) -> PostOrder<'_, T> {
+        PostOrder {
+            tree: self,
+            root,
+            next: self.contains(root).then(|| self.deepest_first(root)),
+        }
+    }
+
+    /// Returns a level-order (breadth-first) iterator over the subtree rooted at the node.
+    /// ```
+    /// # use strctr::index_tree::IndexTree;
+    /// let mut t = IndexTree::new();
+    /// let root = t.new_node(0);
+    /// let a = t.new_node(1);
+    /// let b = t.new_node(2);
+    /// let c = t.new_node(3);
+    /// t.append(root, a);
+    /// t.append(a, b);
+    /// t.append(root, c);
+    /// let values: Vec<_> = t.level_order(root).map(|n| t[n]).collect();
+    /// assert_eq!(values, vec![0, 1, 3, 2]);
+    /// ```
+    pub fn level_order(&self, root: NodeId) -> LevelOrder<'_, T> {
+        let mut queue = VecDeque::new();
+        if self.contains(root) {
+            queue.push_back(root);
+        }
+        LevelOrder { tree: self, queue }
+    }
+
+    /// Appends `child` as the last child of `parent`, moving it (with its subtree) from wherever it was attached.
+    ///
+    /// Returns an error if either id is invalid, or if `child` is `parent` or one of its ancestors.
+    /// ```
+    /// # use strctr::index_tree::{IndexTree, IndexTreeError};
+    /// let mut t = IndexTree::new();
+    /// let root = t.new_node(0);
+    /// let child = t.new_node(1);
+    /// assert!(t.try_append(root, child).is_ok());
+    /// assert_eq!(t.try_append(child, root), Err(IndexTreeError::Cycle));
+    /// ```
+    pub fn try_append(&mut self, parent: NodeId, child: NodeId) -> Result<(), IndexTreeError> {
+        self.check_attach(parent, child)?;
+        self.detach(child);
+        let prev = self.last_child(parent);
+        self.link(child, parent, prev, None);
+        Ok(())
+    }
+
+    /// Appends `child` as the last child of `parent`, moving it (with its subtree) from wherever it was attached.
+    ///
+    /// Panics if either id is invalid or the move would create a cycle. For a non-panicing version, see
+    /// [try_append()](`Self::try_append()`)
+    /// ```should_panic
+    /// # use strctr::index_tree::IndexTree;
+    /// let mut t = IndexTree::new();
+    /// let root = t.new_node(0);
+    /// t.append(root, root);
+    /// ```
+    pub fn append(&mut self, parent: NodeId, child: NodeId) {
+        if let Err(e) = self.try_append(parent, child) {
+            panic!("{:?}: Cannot append {:?} to {:?}", e, child, parent);
+        }
+    }
+
+    /// Inserts `node` as the sibling directly before `sibling`, moving it (with its subtree) from wherever it was
+    /// attached.
+    ///
+    /// Returns an error if either id is invalid, `sibling` is a root, or the move would create a cycle.
+    /// ```
+    /// # use strctr::index_tree::IndexTree;
+    /// let mut t = IndexTree::new();
+    /// let root = t.new_node(0);
+    /// let b = t.new_node(2);
+    /// t.append(root, b);
+    /// let a = t.new_node(1);
+    /// t.try_insert_before(b, a).unwrap();
+    /// let values: Vec<_> = t.children(root).map(|c| t[c]).collect();
+    /// assert_eq!(values, vec![1, 2]);
+    /// ```
+    pub fn try_insert_before(
+        &mut self,
+        sibling: NodeId,
+        node: NodeId,
+    ) -> Result<(), IndexTreeError> {
+        let parent = self.sibling_parent(sibling, node)?;
+        self.check_attach(parent, node)?;
+        self.detach(node);
+        let prev = self.previous_sibling(sibling);
+        self.link(node, parent, prev, Some(sibling));
+        Ok(())
+    }
+
+    /// Inserts `node` as the sibling directly after `sibling`, moving it (with its subtree) from wherever it was
+    /// attached.
+    ///
+    /// Returns an error if either id is invalid, `sibling` is a root, or the move would create a cycle.
+    /// ```
+    /// # use strctr::index_tree::IndexTree;
+    /// let mut t = IndexTree::new();
+    /// let root = t.new_node(0);
+    /// let a = t.new_node(1);
+    /// t.append(root, a);
+    /// let b = t.new_node(2);
+    /// t.try_insert_after(a, b).unwrap();
+    /// let values: Vec<_> = t.children(root).map(|c| t[c]).collect();
+    /// assert_eq!(values, vec![1, 2]);
+    /// ```
+    pub fn try_insert_after(
+        &mut self,
+        sibling: NodeId,
+        node: NodeId,
+    ) -> Result<(), IndexTreeError> {
+        let parent = self.sibling_parent(sibling, node)?;
+        self.check_attach(parent, node)?;
+        self.detach(node);
+        let next = self.next_sibling(sibling);
+        self.link(node, parent, Some(sibling), next);
+        Ok(())
+    }
+
+    /// Inserts `node` as the sibling directly before `sibling`.
+    ///
+    /// Panics on the same conditions that make [try_insert_before()](`Self::try_insert_before()`) fail.
+    pub fn insert_before(&mut self, sibling: NodeId, node: NodeId) {
+        if let Err(e) = self.try_insert_before(sibling, node) {
+            panic!("{:?}: Cannot insert {:?} before {:?}", e, node, sibling);
+        }
+    }
+
+    /// Inserts `node` as the sibling directly after `sibling`.
+    ///
+    /// Panics on the same conditions that make [try_insert_after()](`Self::try_insert_after()`) fail.
+    pub fn insert_after(&mut self, sibling: NodeId, node: NodeId) {
+        if let Err(e) = self.try_insert_after(sibling, node) {
+            panic!("{:?}: Cannot insert {:?} after {:?}", e, node, sibling);
+        }
+    }
+
+    /// Detaches the node from its parent and siblings, making it the root of its own tree. Its subtree stays intact.
+    /// Detaching a root or an invalid id does nothing.
+    /// ```
+    /// # use strctr::index_tree::IndexTree;
+    /// let mut t = IndexTree::new();
+    /// let root = t.new_node(0);
+    /// let child = t.new_node(1);
+    /// t.append(root, child);
+    /// t.detach(child);
+    /// assert_eq!(t.parent(child), None);
+    /// assert_eq!(t.first_child(root), None);
+    /// ```
+    pub fn detach(&mut self, id: NodeId) {
+        let Some(data) = self.data_mut(id) else {
+            return;
+        };
+        let parent = data.parent.take();
+        let prev = data.prev_sibling.take();
+        let next = data.next_sibling.take();
+
+        match prev {
+            Some(p) => self.node_mut(p).next_sibling = next,
+            None => {
+                if let Some(parent) = parent {
+                    self.node_mut(parent).first_child = next;
+                }
+            }
+        }
+        match next {
+            Some(n) => self.node_mut(n).prev_sibling = prev,
+            None => {
+                if let Some(parent) = parent {
+                    self.node_mut(parent).last_child = prev;
+                }
+            }
+        }
+    }
+
+    /// Detaches the node and frees it together with its whole subtree, returning the number of freed nodes. Ids of
+    /// freed nodes become invalid.
+    /// ```
+    /// # use strctr::index_tree::IndexTree;
+    /// let mut t = IndexTree::new();
+    /// let root = t.new_node(0);
+    /// let child = t.new_node(1);
+    /// let grandchild = t.new_node(2);
+    /// t.append(root, child);
+    /// t.append(child, grandchild);
+    /// assert_eq!(t.remove_subtree(child), 2);
+    /// assert_eq!(t.len(), 1);
+    /// assert!(t.get(grandchild).is_none());
+    /// ```
+    pub fn remove_subtree(&mut self, id: NodeId) -> usize {
+        if !self.contains(id) {
+            return 0;
+        }
+        self.detach(id);
+        let doomed: Vec<NodeId> = self.pre_order(id).collect();
+        for node in &doomed {
+            let slot = &mut self.slots[node.index];
+            slot.data = None;
+            slot.generation = slot.generation.wrapping_add(1);
+            self.free.push(node.index);
+        }
+        self.len -= doomed.len();
+        doomed.len()
+    }
+
+    fn data(&self, id: NodeId) -> Option<&NodeData<T>> {
+        let slot = self.slots.get(id.index)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.data.as_ref()
+    }
+
+    fn data_mut(&mut self, id: NodeId) -> Option<&mut NodeData<T>> {
+        let slot = self.slots.get_mut(id.index)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.data.as_mut()
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> &mut NodeData<T> {
+        self.data_mut(id).expect("dangling node id")
+    }
+
+    fn check_attach(&self, parent: NodeId, child: NodeId) -> Result<(), IndexTreeError> {
+        if !self.contains(parent) || !self.contains(child) {
+            return Err(IndexTreeError::InvalidNode);
+        }
+        if parent == child || self.ancestors(parent).any(|a| a == child) {
+            return Err(IndexTreeError::Cycle);
+        }
+        Ok(())
+    }
+
+    fn sibling_parent(&self, sibling: NodeId, node: NodeId) -> Result<NodeId, IndexTreeError> {
+        if sibling == node {
+            return Err(IndexTreeError::Cycle);
+        }
+        self.parent(sibling).ok_or(IndexTreeError::InvalidNode)
+    }
+
+    /// Links a detached node between `prev` and `next` under `parent`.
+    fn link(&mut self, id: NodeId, parent: NodeId, prev: Option<NodeId>, next: Option<NodeId>) {
+        let data = self.node_mut(id);
+        data.parent = Some(parent);
+        data.prev_sibling = prev;
+        data.next_sibling = next;
+
+        match prev {
+            Some(p) => self.node_mut(p).next_sibling = Some(id),
+            None => self.node_mut(parent).first_child = Some(id),
+        }
+        match next {
+            Some(n) => self.node_mut(n).prev_sibling = Some(id),
+            None => self.node_mut(parent).last_child = Some(id),
+        }
+    }
+
+    fn deepest_first(&self, mut id: NodeId) -> NodeId {
+        while let Some(child) = self.first_child(id) {
+            id = child;
+        }
+        id
+    }
+}
+
+impl<T> Index<NodeId> for IndexTree<T> {
+    type Output = T;
+
+    /// Returns the value of the node.
+    ///
+    /// Panics if the id is invalid.
+    /// ```should_panic
+    /// # use strctr::index_tree::IndexTree;
+    /// let mut t = IndexTree::new();
+    /// let n = t.new_node(1);
+    /// t.remove_subtree(n);
+    /// let x = t[n];
+    /// ```
+    fn index(&self, id: NodeId) -> &Self::Output {
+        match self.get(id) {
+            Some(v) => v,
+            None => panic!("InvalidNode: {:?} is not a live node", id),
+        }
+    }
+}
+
+impl<T> IndexMut<NodeId> for IndexTree<T> {
+    /// Allows updating the value of the node.
+    /// ```
+    /// # use strctr::index_tree::IndexTree;
+    /// let mut t = IndexTree::new();
+    /// let n = t.new_node(1);
+    /// t[n] = 5;
+    /// assert_eq!(t[n], 5);
+    /// ```
+    fn index_mut(&mut self, id: NodeId) -> &mut Self::Output {
+        match self.get_mut(id) {
+            Some(v) => v,
+            None => panic!("InvalidNode: {:?} is not a live node", id),
+        }
+    }
+}
+
+/// Iterator over the children of a node.
+pub struct Children<'a, T> {
+    tree: &'a IndexTree<T>,
+    next: Option<NodeId>,
+}
+
+impl<T> Iterator for Children<'_, T> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.next?;
+        self.next = self.tree.next_sibling(id);
+        Some(id)
+    }
+}
+
+/// Iterator over the ancestors of a node.
+pub struct Ancestors<'a, T> {
+    tree: &'a IndexTree<T>,
+    next: Option<NodeId>,
+}
+
+impl<T> Iterator for Ancestors<'_, T> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.next?;
+        self.next = self.tree.parent(id);
+        Some(id)
+    }
+}
+
+/// Pre-order iterator over a subtree.
+pub struct PreOrder<'a, T> {
+    tree: &'a IndexTree<T>,
+    root: NodeId,
+    next: Option<NodeId>,
+}
+
+impl<T> Iterator for PreOrder<'_, T> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.next?;
+        self.next = match self.tree.first_child(id) {
+            Some(child) => Some(child),
+            None => {
+                let mut cur = id;
+                loop {
+                    if cur == self.root {
+                        break None;
+                    }
+                    if let Some(sibling) = self.tree.next_sibling(cur) {
+                        break Some(sibling);
+                    }
+                    match self.tree.parent(cur) {
+                        Some(p) => cur = p,
+                        None => break None,
+                    }
+                }
+            }
+        };
+        Some(id)
+    }
+}
+
+/// Post-order iterator over a subtree.
+pub struct PostOrder<'a, T> {
+    tree: &'a IndexTree<T>,
+    root: NodeId,
+    next: Option<NodeId>,
+}
+
+impl<T> Iterator for PostOrder<'_, T> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.next?;
+        self.next = if id == self.root {
+            None
+        } else if let Some(sibling) = self.tree.next_sibling(id) {
+            Some(self.tree.deepest_first(sibling))
+        } else {
+            self.tree.parent(id)
+        };
+        Some(id)
+    }
+}
+
+/// Level-order iterator over a subtree.
+pub struct LevelOrder<'a, T> {
+    tree: &'a IndexTree<T>,
+    queue: VecDeque<NodeId>,
+}
+
+impl<T> Iterator for LevelOrder<'_, T> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.queue.pop_front()?;
+        self.queue.extend(self.tree.children(id));
+        Some(id)
+    }
+}
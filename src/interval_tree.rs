@@ -0,0 +1,378 @@
+//! Interval tree: a map from half-open ranges to values that finds every stored range overlapping a query range.
+//!
+//! Ranges are kept in an AVL tree ordered by start, and every node also records the largest end within its subtree.
+//! Overlap queries skip any subtree whose largest end lies before the query, and stop once ranges start after it,
+//! so a query takes `O(log n + m)` for `m` results.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Bound, Range};
+
+struct Node<K, V> {
+    range: Range<K>,
+    value: V,
+    /// Largest `range.end` in the subtree rooted here.
+    max_end: K,
+    height: u8,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+/// A map from half-open ranges `start..end` to values, supporting overlap queries.
+pub struct IntervalTree<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+impl<K, V> Default for IntervalTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> IntervalTree<K, V> {
+    /// Constructs a new, empty tree.
+    pub fn new() -> Self {
+        Self { root: None, len: 0 }
+    }
+
+    /// Returns the number of ranges in the tree.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the tree contains no ranges.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all ranges from the tree.
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+}
+
+impl<K: Ord + Clone, V> IntervalTree<K, V> {
+    /// Inserts a range with its value, returning the previous value if the exact same range was already present.
+    ///
+    /// Panics if the range is empty, since an empty range cannot overlap anything.
+    /// ```
+    /// # use strctr::interval_tree::IntervalTree;
+    /// let mut t = IntervalTree::new();
+    /// assert_eq!(t.insert(1..5, "a"), None);
+    /// assert_eq!(t.insert(1..5, "b"), Some("a"));
+    /// assert_eq!(t.len(), 1);
+    /// ```
+    pub fn insert(&mut self, range: Range<K>, value: V) -> Option<V> {
+        if range.start >= range.end {
+            panic!("InvalidInterval: Cannot store an empty range");
+        }
+        let mut old = None;
+        self.root = Some(insert(self.root.take(), range, value, &mut old));
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Returns the value stored for exactly this range.
+    pub fn get(&self, range: &Range<K>) -> Option<&V> {
+        let mut cur = self.root.as_deref();
+        while let Some(node) = cur {
+            cur = match cmp_ranges(range, &node.range) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => return Some(&node.value),
+            };
+        }
+        None
+    }
+
+    /// Removes exactly this range from the tree, returning its value.
+    /// ```
+    /// # use strctr::interval_tree::IntervalTree;
+    /// let mut t: IntervalTree<_, _> = [(0..10, 'a'), (5..15, 'b')].into_iter().collect();
+    /// assert_eq!(t.remove(&(0..10)), Some('a'));
+    /// assert_eq!(t.remove(&(0..9)), None);
+    /// assert_eq!(t.overlapping(0..5).count(), 0);
+    /// ```
+    pub fn remove(&mut self, range: &Range<K>) -> Option<V> {
+        let (root, removed) = remove(self.root.take(), range);
+        self.root = root;
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// Returns an iterator over the stored ranges that overlap the query range, ordered by start. Ranges are
+    /// half-open, so `0..5` and `5..10` do not overlap.
+    /// ```
+    /// # use strctr::interval_tree::IntervalTree;
+    /// let mut meetings = IntervalTree::new();
+    /// meetings.insert(9..10, "standup");
+    /// meetings.insert(10..12, "review");
+    /// meetings.insert(13..14, "lunch");
+    /// let clashes: Vec<_> = meetings.overlapping(9..11).map(|(_, v)| *v).collect();
+    /// assert_eq!(clashes, vec!["standup", "review"]);
+    /// assert_eq!(meetings.overlapping(12..13).count(), 0);
+    /// ```
+    pub fn overlapping(&self, range: Range<K>) -> Overlapping<'_, K, V> {
+        Overlapping::new(
+            self.root.as_deref(),
+            range.start,
+            Bound::Excluded(range.end),
+        )
+    }
+
+    /// Returns an iterator over the stored ranges that contain the point, ordered by start.
+    /// ```
+    /// # use strctr::interval_tree::IntervalTree;
+    /// let t: IntervalTree<_, _> = [(0..10, 'a'), (5..6, 'b'), (6..8, 'c')].into_iter().collect();
+    /// let hits: Vec<_> = t.containing(&6).map(|(_, v)| *v).collect();
+    /// assert_eq!(hits, vec!['a', 'c']);
+    /// ```
+    pub fn containing(&self, point: &K) -> Overlapping<'_, K, V> {
+        Overlapping::new(
+            self.root.as_deref(),
+            point.clone(),
+            Bound::Included(point.clone()),
+        )
+    }
+
+    /// Returns an iterator over all ranges, ordered by start and then by end.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut it = Iter { stack: Vec::new() };
+        it.descend(self.root.as_deref());
+        it
+    }
+}
+
+impl<K: Ord + Clone, V> FromIterator<(Range<K>, V)> for IntervalTree<K, V> {
+    fn from_iter<I: IntoIterator<Item = (Range<K>, V)>>(iter: I) -> Self {
+        let mut tree = Self::new();
+        for (range, value) in iter {
+            tree.insert(range, value);
+        }
+        tree
+    }
+}
+
+impl<K: Ord + Clone + fmt::Debug, V: fmt::Debug> fmt::Debug for IntervalTree<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+fn cmp_ranges<K: Ord>(a: &Range<K>, b: &Range<K>) -> Ordering {
+    a.start.cmp(&b.start).then_with(|| a.end.cmp(&b.end))
+}
+
+fn height<K, V>(link: &Link<K, V>) -> u8 {
+    link.as_ref().map_or(0, |n| n.height)
+}
+
+/// Recomputes the node's height and largest end from its children.
+fn update<K: Ord + Clone, V>(node: &mut Node<K, V>) {
+    node.height = 1 + height(&node.left).max(height(&node.right));
+    let mut max_end = &node.range.end;
+    for child in [&node.left, &node.right].into_iter().flatten() {
+        if child.max_end > *max_end {
+            max_end = &child.max_end;
+        }
+    }
+    node.max_end = max_end.clone();
+}
+
+fn rotate_right<K: Ord + Clone, V>(mut node: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    let mut left = node.left.take().expect("left child");
+    node.left = left.right.take();
+    update(&mut node);
+    left.right = Some(node);
+    update(&mut left);
+    left
+}
+
+fn rotate_left<K: Ord + Clone, V>(mut node: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    let mut right = node.right.take().expect("right child");
+    node.right = right.left.take();
+    update(&mut node);
+    right.left = Some(node);
+    update(&mut right);
+    right
+}
+
+/// Restores the AVL balance of a node whose subtrees differ in height by at most two.
+fn balance<K: Ord + Clone, V>(mut node: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    update(&mut node);
+    let (l, r) = (height(&node.left), height(&node.right));
+    if l > r + 1 {
+        let left = node.left.as_ref().expect("left child");
+        if height(&left.left) < height(&left.right) {
+            node.left = Some(rotate_left(node.left.take().expect("left child")));
+        }
+        return rotate_right(node);
+    }
+    if r > l + 1 {
+        let right = node.right.as_ref().expect("right child");
+        if height(&right.right) < height(&right.left) {
+            node.right = Some(rotate_right(node.right.take().expect("right child")));
+        }
+        return rotate_left(node);
+    }
+    node
+}
+
+fn insert<K: Ord + Clone, V>(
+    link: Link<K, V>,
+    range: Range<K>,
+    value: V,
+    old: &mut Option<V>,
+) -> Box<Node<K, V>> {
+    let Some(mut node) = link else {
+        return Box::new(Node {
+            max_end: range.end.clone(),
+            range,
+            value,
+            height: 1,
+            left: None,
+            right: None,
+        });
+    };
+    match cmp_ranges(&range, &node.range) {
+        Ordering::Less => node.left = Some(insert(node.left.take(), range, value, old)),
+        Ordering::Greater => node.right = Some(insert(node.right.take(), range, value, old)),
+        Ordering::Equal => {
+            *old = Some(std::mem::replace(&mut node.value, value));
+            return node;
+        }
+    }
+    balance(node)
+}
+
+fn remove<K: Ord + Clone, V>(link: Link<K, V>, range: &Range<K>) -> (Link<K, V>, Option<V>) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+    let removed = match cmp_ranges(range, &node.range) {
+        Ordering::Less => {
+            let (left, removed) = remove(node.left.take(), range);
+            node.left = left;
+            removed
+        }
+        Ordering::Greater => {
+            let (right, removed) = remove(node.right.take(), range);
+            node.right = right;
+            removed
+        }
+        Ordering::Equal => {
+            let Node {
+                value, left, right, ..
+            } = *node;
+            let Some(right) = right else {
+                return (left, Some(value));
+            };
+            let (rest, mut successor) = remove_min(right);
+            successor.left = left;
+            successor.right = rest;
+            return (Some(balance(successor)), Some(value));
+        }
+    };
+    (Some(balance(node)), removed)
+}
+
+/// Detaches the leftmost node of the subtree, returning the rest of the subtree and the node.
+fn remove_min<K: Ord + Clone, V>(mut node: Box<Node<K, V>>) -> (Link<K, V>, Box<Node<K, V>>) {
+    match node.left.take() {
+        None => (node.right.take(), node),
+        Some(left) => {
+            let (rest, min) = remove_min(left);
+            node.left = rest;
+            (Some(balance(node)), min)
+        }
+    }
+}
+
+/// In-order iterator over the ranges of an [`IntervalTree`].
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn descend(&mut self, mut node: Option<&'a Node<K, V>>) {
+        while let Some(n) = node {
+            self.stack.push(n);
+            node = n.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a Range<K>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.descend(node.right.as_deref());
+        Some((&node.range, &node.value))
+    }
+}
+
+/// Iterator over the ranges of an [`IntervalTree`] that overlap a query, created by
+/// [overlapping()](`IntervalTree::overlapping()`) and [containing()](`IntervalTree::containing()`).
+pub struct Overlapping<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+    /// Ranges have to end after this.
+    start: K,
+    /// Ranges have to start before this; a point query includes the point itself.
+    end: Bound<K>,
+}
+
+impl<'a, K: Ord, V> Overlapping<'a, K, V> {
+    fn new(root: Option<&'a Node<K, V>>, start: K, end: Bound<K>) -> Self {
+        let mut it = Self {
+            stack: Vec::new(),
+            start,
+            end,
+        };
+        it.descend(root);
+        it
+    }
+
+    /// Walks down the left spine, leaving out subtrees in which every range ends before the query starts.
+    fn descend(&mut self, mut node: Option<&'a Node<K, V>>) {
+        while let Some(n) = node {
+            if n.max_end <= self.start {
+                return;
+            }
+            self.stack.push(n);
+            node = n.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for Overlapping<'a, K, V> {
+    type Item = (&'a Range<K>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            let starts_after = match &self.end {
+                Bound::Included(end) => node.range.start > *end,
+                Bound::Excluded(end) => node.range.start >= *end,
+                Bound::Unbounded => false,
+            };
+            if starts_after {
+                // Every range still to come starts at or after this one.
+                self.stack.clear();
+                return None;
+            }
+            self.descend(node.right.as_deref());
+            if node.range.end > self.start {
+                return Some((&node.range, &node.value));
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,658 @@
+//! Intrusive red-black tree, whose nodes are the caller's own values.
+//!
+//! Instead of allocating a node per entry, the tree links values that embed an [`RBLink`] field. A value with several
+//! links can be in several trees at once, for example a timer ordered by deadline in one tree and by ID in another,
+//! and inserting or removing it never allocates. An [`Adapter`] tells the tree which link of a value to use and which
+//! key to order by; the [`rb_adapter!`](`crate::rb_adapter!`) macro implements one for a link field and a key field.
+//!
+//! The tree borrows the values it links for its whole lifetime, so they can neither move nor be dropped while linked.
+//! Links use interior mutability and are not thread-safe.
+
+use std::cell::Cell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub use crate::rbtree::RBTreeError;
+
+type Ptr = NonNull<RBLink>;
+
+/// Source of tree IDs, so every link knows which tree it belongs to. IDs are never reused.
+static NEXT_TREE_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// The parent, children and color of a value inside an [`IntrusiveRBTree`]. Embed one per tree the value can be in.
+#[derive(Default)]
+pub struct RBLink {
+    /// ID of the tree the value is linked into, or 0 if it is not.
+    tree: Cell<usize>,
+    parent: Cell<Option<Ptr>>,
+    left: Cell<Option<Ptr>>,
+    right: Cell<Option<Ptr>>,
+    red: Cell<bool>,
+}
+
+impl RBLink {
+    /// Constructs a new, unlinked link.
+    pub const fn new() -> Self {
+        Self {
+            tree: Cell::new(0),
+            parent: Cell::new(None),
+            left: Cell::new(None),
+            right: Cell::new(None),
+            red: Cell::new(false),
+        }
+    }
+
+    /// Returns whether the value is in a tree through this link.
+    pub fn is_linked(&self) -> bool {
+        self.tree.get() != 0
+    }
+}
+
+impl fmt::Debug for RBLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RBLink")
+            .field("linked", &self.is_linked())
+            .finish()
+    }
+}
+
+/// Connects an [`IntrusiveRBTree`] to the link and key of its values.
+///
+/// # Safety
+///
+/// [link()](`Self::link()`) must return a pointer to an [`RBLink`] stored inside the value, derived from the given
+/// pointer, and always the same field. Safe code may call it with any pointer, even a dangling one, so it must compute
+/// the address without dereferencing the pointer. [value()](`Self::value()`) must turn such a link pointer back into
+/// the pointer to its value. The [`rb_adapter!`](`crate::rb_adapter!`) macro implements both correctly.
+pub unsafe trait Adapter {
+    /// The type of the linked values.
+    type Value;
+    /// The type the tree orders values by.
+    type Key: Ord + ?Sized;
+
+    /// Returns a pointer to the value's link.
+    fn link(value: NonNull<Self::Value>) -> NonNull<RBLink>;
+
+    /// Returns a pointer to the value containing the link.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must have been returned by [link()](`Self::link()`).
+    unsafe fn value(link: NonNull<RBLink>) -> NonNull<Self::Value>;
+
+    /// Returns the key of the value. It must not change while the value is linked.
+    fn key(value: &Self::Value) -> &Self::Key;
+}
+
+/// Defines a unit struct implementing [`Adapter`](`crate::intrusive_rbtree::Adapter`), which links values of a type
+/// through one of its [`RBLink`](`crate::intrusive_rbtree::RBLink`) fields and orders them by another field.
+/// ```
+/// # use strctr::intrusive_rbtree::{IntrusiveRBTree, RBLink};
+/// # use strctr::rb_adapter;
+/// struct Timer {
+///     id: u32,
+///     deadline: u64,
+///     by_id: RBLink,
+///     by_deadline: RBLink,
+/// }
+/// rb_adapter!(ById = Timer { by_id } key id: u32);
+/// rb_adapter!(ByDeadline = Timer { by_deadline } key deadline: u64);
+///
+/// let timers: Vec<Timer> = [(1, 300), (2, 100), (3, 200)]
+///     .into_iter()
+///     .map(|(id, deadline)| Timer { id, deadline, by_id: RBLink::new(), by_deadline: RBLink::new() })
+///     .collect();
+/// let mut ids = IntrusiveRBTree::<ById>::new();
+/// let mut deadlines = IntrusiveRBTree::<ByDeadline>::new();
+/// for timer in &timers {
+///     ids.insert(timer);
+///     deadlines.insert(timer);
+/// }
+/// let expired = deadlines.pop_first().unwrap();
+/// assert_eq!(expired.id, 2);
+/// ids.remove(expired);
+/// assert_eq!(ids.iter().map(|t| t.id).collect::<Vec<_>>(), vec![1, 3]);
+/// assert_eq!(deadlines.first().unwrap().id, 3);
+/// ```
+#[macro_export]
+macro_rules! rb_adapter {
+    ($vis:vis $name:ident = $value:ty { $link:ident } key $key:ident: $key_ty:ty) => {
+        $vis struct $name;
+
+        unsafe impl $crate::intrusive_rbtree::Adapter for $name {
+            type Value = $value;
+            type Key = $key_ty;
+
+            fn link(
+                value: ::std::ptr::NonNull<$value>,
+            ) -> ::std::ptr::NonNull<$crate::intrusive_rbtree::RBLink> {
+                // Offsetting the raw pointer never dereferences it, and keeps its permission to access the whole value,
+                // which value() relies on.
+                let link = value
+                    .as_ptr()
+                    .wrapping_byte_add(::std::mem::offset_of!($value, $link));
+                ::std::ptr::NonNull::new(link.cast())
+                    .expect("the link's address does not wrap around")
+            }
+
+            unsafe fn value(
+                link: ::std::ptr::NonNull<$crate::intrusive_rbtree::RBLink>,
+            ) -> ::std::ptr::NonNull<$value> {
+                unsafe { link.byte_sub(::std::mem::offset_of!($value, $link)).cast() }
+            }
+
+            fn key(value: &$value) -> &$key_ty {
+                &value.$key
+            }
+        }
+    };
+}
+
+/// A red-black tree linking borrowed values through their embedded [`RBLink`]s, ordered by the key of the
+/// [`Adapter`]. Values with equal keys are kept in insertion order.
+pub struct IntrusiveRBTree<'a, A: Adapter> {
+    id: usize,
+    root: Option<Ptr>,
+    len: usize,
+    _values: PhantomData<(&'a A::Value, A)>,
+}
+
+impl<A: Adapter> Default for IntrusiveRBTree<'_, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, A: Adapter> IntrusiveRBTree<'a, A> {
+    /// Constructs a new, empty tree.
+    pub fn new() -> Self {
+        Self {
+            id: NEXT_TREE_ID.fetch_add(1, Ordering::Relaxed),
+            root: None,
+            len: 0,
+            _values: PhantomData,
+        }
+    }
+
+    /// Returns the number of values in the tree.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the tree contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Links the value into the tree, after any values with an equal key.
+    ///
+    /// Panics if the value is already in a tree through the adapter's link.
+    pub fn insert(&mut self, value: &'a A::Value) {
+        let z = A::link(NonNull::from(value));
+        if self.link(z).is_linked() {
+            panic!("AlreadyLinked: The value is already in a tree through this link");
+        }
+        let key = A::key(value);
+        let mut parent = None;
+        let mut go_left = false;
+        let mut cur = self.root;
+        while let Some(p) = cur {
+            parent = Some(p);
+            go_left = key < A::key(self.value(p));
+            cur = if go_left { self.left(p) } else { self.right(p) };
+        }
+
+        let link = self.link(z);
+        link.tree.set(self.id);
+        link.parent.set(parent);
+        link.left.set(None);
+        link.right.set(None);
+        link.red.set(true);
+        match parent {
+            None => self.root = Some(z),
+            Some(p) if go_left => self.link(p).left.set(Some(z)),
+            Some(p) => self.link(p).right.set(Some(z)),
+        }
+        self.len += 1;
+        self.insert_fixup(z);
+    }
+
+    /// Unlinks the value from the tree. Returns `false` if it was not in this tree.
+    pub fn remove(&mut self, value: &'a A::Value) -> bool {
+        if !self.contains(value) {
+            return false;
+        }
+        self.remove_node(A::link(NonNull::from(value)));
+        true
+    }
+
+    /// Returns whether this very value, rather than one with an equal key, is in the tree.
+    pub fn contains(&self, value: &A::Value) -> bool {
+        self.link(A::link(NonNull::from(value))).tree.get() == self.id
+    }
+
+    /// Returns the first value with the key.
+    pub fn find(&self, key: &A::Key) -> Option<&'a A::Value> {
+        self.lower_bound(key).filter(|value| A::key(value) == key)
+    }
+
+    /// Returns the first value whose key is at least as large as the given one.
+    /// ```
+    /// # use strctr::intrusive_rbtree::{IntrusiveRBTree, RBLink};
+    /// # use strctr::rb_adapter;
+    /// struct Page {
+    ///     offset: u64,
+    ///     link: RBLink,
+    /// }
+    /// rb_adapter!(ByOffset = Page { link } key offset: u64);
+    ///
+    /// let pages: Vec<Page> = (0..4).map(|i| Page { offset: i * 4096, link: RBLink::new() }).collect();
+    /// let mut tree = IntrusiveRBTree::<ByOffset>::new();
+    /// pages.iter().for_each(|page| tree.insert(page));
+    /// assert_eq!(tree.lower_bound(&5000).map(|page| page.offset), Some(8192));
+    /// assert_eq!(tree.find(&4096).map(|page| page.offset), Some(4096));
+    /// assert!(tree.find(&5000).is_none());
+    /// ```
+    pub fn lower_bound(&self, key: &A::Key) -> Option<&'a A::Value> {
+        let mut found = None;
+        let mut cur = self.root;
+        while let Some(p) = cur {
+            if A::key(self.value(p)) < key {
+                cur = self.right(p);
+            } else {
+                found = Some(p);
+                cur = self.left(p);
+            }
+        }
+        found.map(|p| self.value(p))
+    }
+
+    /// Returns the value with the smallest key.
+    pub fn first(&self) -> Option<&'a A::Value> {
+        self.root.map(|r| self.value(self.minimum(r)))
+    }
+
+    /// Returns the value with the largest key.
+    pub fn last(&self) -> Option<&'a A::Value> {
+        self.root.map(|r| self.value(self.maximum(r)))
+    }
+
+    /// Unlinks the value with the smallest key and returns it.
+    pub fn pop_first(&mut self) -> Option<&'a A::Value> {
+        let first = self.minimum(self.root?);
+        self.remove_node(first);
+        Some(self.value(first))
+    }
+
+    /// Returns an iterator over the values, in ascending key order.
+    pub fn iter(&self) -> Iter<'_, 'a, A> {
+        Iter {
+            tree: self,
+            next: self.root.map(|r| self.minimum(r)),
+            remaining: self.len,
+        }
+    }
+
+    /// Unlinks all values, so they can be inserted elsewhere.
+    pub fn clear(&mut self) {
+        // Walks down to a leaf, unlinks it and climbs back up, detaching every child on the way down.
+        let mut cur = self.root.take();
+        while let Some(p) = cur {
+            let link = self.link(p);
+            if let Some(child) = link.left.take().or_else(|| link.right.take()) {
+                cur = Some(child);
+            } else {
+                cur = link.parent.take();
+                link.tree.set(0);
+            }
+        }
+        self.len = 0;
+    }
+
+    /// Verifies the red-black invariants, key ordering and parent links, like
+    /// [RBTreeMap::check_invariants()](`crate::rbtree::RBTreeMap::check_invariants()`). Returns the black height of the
+    /// tree.
+    pub fn check_invariants(&self) -> Result<usize, RBTreeError> {
+        if self.is_red(self.root) {
+            return Err(RBTreeError::RedRoot);
+        }
+        let height = self.check_subtree(self.root, None)?;
+        let mut prev: Option<&A::Key> = None;
+        for value in self.iter() {
+            if prev.is_some_and(|p| p > A::key(value)) {
+                return Err(RBTreeError::Unordered);
+            }
+            prev = Some(A::key(value));
+        }
+        Ok(height)
+    }
+
+    fn check_subtree(&self, p: Option<Ptr>, parent: Option<Ptr>) -> Result<usize, RBTreeError> {
+        let Some(p) = p else {
+            return Ok(1);
+        };
+        if self.parent(p) != parent {
+            return Err(RBTreeError::BrokenParentLink);
+        }
+        if self.is_red(Some(p)) && (self.is_red(self.left(p)) || self.is_red(self.right(p))) {
+            return Err(RBTreeError::RedRedEdge);
+        }
+        let left = self.check_subtree(self.left(p), Some(p))?;
+        let right = self.check_subtree(self.right(p), Some(p))?;
+        if left != right {
+            return Err(RBTreeError::BlackHeightMismatch);
+        }
+        Ok(left + usize::from(!self.is_red(Some(p))))
+    }
+
+    fn link(&self, p: Ptr) -> &'a RBLink {
+        // Every link the tree sees belongs to a value borrowed for 'a.
+        unsafe { p.as_ref() }
+    }
+
+    fn value(&self, p: Ptr) -> &'a A::Value {
+        // Every linked pointer came from A::link() of a value borrowed for 'a.
+        unsafe { A::value(p).as_ref() }
+    }
+
+    fn is_red(&self, p: Option<Ptr>) -> bool {
+        p.is_some_and(|p| self.link(p).red.get())
+    }
+
+    fn set_red(&self, p: Option<Ptr>, red: bool) {
+        if let Some(p) = p {
+            self.link(p).red.set(red);
+        }
+    }
+
+    fn parent(&self, p: Ptr) -> Option<Ptr> {
+        self.link(p).parent.get()
+    }
+
+    fn left(&self, p: Ptr) -> Option<Ptr> {
+        self.link(p).left.get()
+    }
+
+    fn right(&self, p: Ptr) -> Option<Ptr> {
+        self.link(p).right.get()
+    }
+
+    fn set_parent(&self, p: Option<Ptr>, parent: Option<Ptr>) {
+        if let Some(p) = p {
+            self.link(p).parent.set(parent);
+        }
+    }
+
+    fn minimum(&self, mut p: Ptr) -> Ptr {
+        while let Some(l) = self.left(p) {
+            p = l;
+        }
+        p
+    }
+
+    fn maximum(&self, mut p: Ptr) -> Ptr {
+        while let Some(r) = self.right(p) {
+            p = r;
+        }
+        p
+    }
+
+    fn successor(&self, p: Ptr) -> Option<Ptr> {
+        if let Some(r) = self.right(p) {
+            return Some(self.minimum(r));
+        }
+        let mut child = p;
+        let mut parent = self.parent(p);
+        while let Some(q) = parent {
+            if self.right(q) != Some(child) {
+                break;
+            }
+            child = q;
+            parent = self.parent(q);
+        }
+        parent
+    }
+
+    /// Replaces the parent's link to `u` with a link to `v`.
+    fn replace_child(&mut self, parent: Option<Ptr>, u: Ptr, v: Option<Ptr>) {
+        match parent {
+            None => self.root = v,
+            Some(p) if self.left(p) == Some(u) => self.link(p).left.set(v),
+            Some(p) => self.link(p).right.set(v),
+        }
+    }
+
+    fn rotate_left(&mut self, x: Ptr) {
+        let y = self.right(x).expect("rotate_left needs a right child");
+        let y_left = self.left(y);
+        self.link(x).right.set(y_left);
+        self.set_parent(y_left, Some(x));
+        let x_parent = self.parent(x);
+        self.link(y).parent.set(x_parent);
+        self.replace_child(x_parent, x, Some(y));
+        self.link(y).left.set(Some(x));
+        self.link(x).parent.set(Some(y));
+    }
+
+    fn rotate_right(&mut self, x: Ptr) {
+        let y = self.left(x).expect("rotate_right needs a left child");
+        let y_right = self.right(y);
+        self.link(x).left.set(y_right);
+        self.set_parent(y_right, Some(x));
+        let x_parent = self.parent(x);
+        self.link(y).parent.set(x_parent);
+        self.replace_child(x_parent, x, Some(y));
+        self.link(y).right.set(Some(x));
+        self.link(x).parent.set(Some(y));
+    }
+
+    fn insert_fixup(&mut self, mut z: Ptr) {
+        while let Some(p) = self.parent(z) {
+            if !self.is_red(Some(p)) {
+                break;
+            }
+            // A red node is never the root, so the grandparent exists.
+            let g = self.parent(p).expect("red node without parent");
+            if self.left(g) == Some(p) {
+                let uncle = self.right(g);
+                if self.is_red(uncle) {
+                    self.set_red(Some(p), false);
+                    self.set_red(uncle, false);
+                    self.set_red(Some(g), true);
+                    z = g;
+                } else {
+                    if self.right(p) == Some(z) {
+                        z = p;
+                        self.rotate_left(z);
+                    }
+                    let p = self.parent(z).expect("rotated node without parent");
+                    let g = self.parent(p).expect("rotated node without grandparent");
+                    self.set_red(Some(p), false);
+                    self.set_red(Some(g), true);
+                    self.rotate_right(g);
+                }
+            } else {
+                let uncle = self.left(g);
+                if self.is_red(uncle) {
+                    self.set_red(Some(p), false);
+                    self.set_red(uncle, false);
+                    self.set_red(Some(g), true);
+                    z = g;
+                } else {
+                    if self.left(p) == Some(z) {
+                        z = p;
+                        self.rotate_right(z);
+                    }
+                    let p = self.parent(z).expect("rotated node without parent");
+                    let g = self.parent(p).expect("rotated node without grandparent");
+                    self.set_red(Some(p), false);
+                    self.set_red(Some(g), true);
+                    self.rotate_left(g);
+                }
+            }
+        }
+        self.set_red(self.root, false);
+    }
+
+    /// Unlinks node `z` from the tree, rebalancing as needed.
+    fn remove_node(&mut self, z: Ptr) {
+        let mut removed_red = self.is_red(Some(z));
+        let x;
+        let x_parent;
+
+        match (self.left(z), self.right(z)) {
+            (None, right) => {
+                x = right;
+                x_parent = self.parent(z);
+                self.replace_child(x_parent, z, right);
+                self.set_parent(right, x_parent);
+            }
+            (left, None) => {
+                x = left;
+                x_parent = self.parent(z);
+                self.replace_child(x_parent, z, left);
+                self.set_parent(left, x_parent);
+            }
+            (Some(z_left), Some(z_right)) => {
+                let y = self.minimum(z_right);
+                removed_red = self.is_red(Some(y));
+                x = self.right(y);
+                if self.parent(y) == Some(z) {
+                    x_parent = Some(y);
+                } else {
+                    x_parent = self.parent(y);
+                    self.replace_child(x_parent, y, x);
+                    self.set_parent(x, x_parent);
+                    self.link(y).right.set(Some(z_right));
+                    self.link(z_right).parent.set(Some(y));
+                }
+                let z_parent = self.parent(z);
+                self.replace_child(z_parent, z, Some(y));
+                self.link(y).parent.set(z_parent);
+                self.link(y).left.set(Some(z_left));
+                self.link(z_left).parent.set(Some(y));
+                self.set_red(Some(y), self.is_red(Some(z)));
+            }
+        }
+
+        if !removed_red {
+            self.remove_fixup(x, x_parent);
+        }
+
+        let link = self.link(z);
+        link.tree.set(0);
+        link.parent.set(None);
+        link.left.set(None);
+        link.right.set(None);
+        self.len -= 1;
+    }
+
+    fn remove_fixup(&mut self, mut x: Option<Ptr>, mut parent: Option<Ptr>) {
+        while x != self.root && !self.is_red(x) {
+            // x is not the root, so it has a parent; its sibling exists because x's side is short a black node.
+            let p = parent.expect("non-root node without parent");
+            if self.left(p) == x {
+                let mut w = self.right(p).expect("missing sibling");
+                if self.is_red(Some(w)) {
+                    self.set_red(Some(w), false);
+                    self.set_red(Some(p), true);
+                    self.rotate_left(p);
+                    w = self.right(p).expect("missing sibling");
+                }
+                if !self.is_red(self.left(w)) && !self.is_red(self.right(w)) {
+                    self.set_red(Some(w), true);
+                    x = Some(p);
+                    parent = self.parent(p);
+                } else {
+                    if !self.is_red(self.right(w)) {
+                        self.set_red(self.left(w), false);
+                        self.set_red(Some(w), true);
+                        self.rotate_right(w);
+                        w = self.right(p).expect("missing sibling");
+                    }
+                    self.set_red(Some(w), self.is_red(Some(p)));
+                    self.set_red(Some(p), false);
+                    self.set_red(self.right(w), false);
+                    self.rotate_left(p);
+                    x = self.root;
+                    parent = None;
+                }
+            } else {
+                let mut w = self.left(p).expect("missing sibling");
+                if self.is_red(Some(w)) {
+                    self.set_red(Some(w), false);
+                    self.set_red(Some(p), true);
+                    self.rotate_right(p);
+                    w = self.left(p).expect("missing sibling");
+                }
+                if !self.is_red(self.left(w)) && !self.is_red(self.right(w)) {
+                    self.set_red(Some(w), true);
+                    x = Some(p);
+                    parent = self.parent(p);
+                } else {
+                    if !self.is_red(self.left(w)) {
+                        self.set_red(self.right(w), false);
+                        self.set_red(Some(w), true);
+                        self.rotate_left(w);
+                        w = self.left(p).expect("missing sibling");
+                    }
+                    self.set_red(Some(w), self.is_red(Some(p)));
+                    self.set_red(Some(p), false);
+                    self.set_red(self.left(w), false);
+                    self.rotate_right(p);
+                    x = self.root;
+                    parent = None;
+                }
+            }
+        }
+        self.set_red(x, false);
+    }
+}
+
+impl<A: Adapter> Drop for IntrusiveRBTree<'_, A> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<A: Adapter> fmt::Debug for IntrusiveRBTree<'_, A>
+where
+    A::Value: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// In-order iterator over the values of an [`IntrusiveRBTree`].
+pub struct Iter<'t, 'a, A: Adapter> {
+    tree: &'t IntrusiveRBTree<'a, A>,
+    next: Option<Ptr>,
+    remaining: usize,
+}
+
+impl<'a, A: Adapter> Iterator for Iter<'_, 'a, A> {
+    type Item = &'a A::Value;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let p = self.next?;
+        self.next = self.tree.successor(p);
+        self.remaining -= 1;
+        Some(self.tree.value(p))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'t, 'a, A: Adapter> IntoIterator for &'t IntrusiveRBTree<'a, A> {
+    type Item = &'a A::Value;
+    type IntoIter = Iter<'t, 'a, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
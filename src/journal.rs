@@ -0,0 +1,274 @@
+//! Operation log for collections. A [`Journal`] wraps a collection, records every mutating operation applied through
+//! it, and can rebuild the collection's state at any point of the log by replaying the operations onto the snapshot
+//! the log started from.
+//!
+//! Collections take part by implementing [`Journaled`], which names their operation type and applies a single
+//! operation. Because operations are plain values, a log can be inspected for auditing, persisted, or fed into
+//! another collection to reproduce a run deterministically.
+
+use std::ops::Deref;
+
+use crate::btree::BTreeMap;
+use crate::disjoint_set::DisjointSet;
+use crate::heap::{BinaryHeap, Compare};
+use crate::skiplist::SkipListMap;
+use crate::trie::Trie;
+
+/// A collection whose mutations can be described by operation values.
+pub trait Journaled {
+    /// A mutating operation on the collection.
+    type Op: Clone;
+
+    /// Performs the operation on the collection.
+    fn apply(&mut self, op: Self::Op);
+}
+
+/// Applies the operations to the collection, in order.
+/// ```
+/// # use strctr::journal::{replay, MapOp};
+/// # use strctr::skiplist::SkipListMap;
+/// let mut m = SkipListMap::new();
+/// replay(&mut m, [MapOp::Insert(1, "a"), MapOp::Insert(2, "b"), MapOp::Remove(1)]);
+/// assert_eq!(m.keys().collect::<Vec<_>>(), vec![&2]);
+/// ```
+pub fn replay<C: Journaled>(collection: &mut C, ops: impl IntoIterator<Item = C::Op>) {
+    for op in ops {
+        collection.apply(op);
+    }
+}
+
+/// Operation on a map: [`BTreeMap`], [`SkipListMap`] or [`Trie`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MapOp<K, V> {
+    /// Inserts the key-value pair, replacing an existing value.
+    Insert(K, V),
+    /// Removes the key.
+    Remove(K),
+    /// Removes all entries.
+    Clear,
+}
+
+/// Operation on a [`BinaryHeap`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HeapOp<T> {
+    /// Pushes the element.
+    Push(T),
+    /// Pops the greatest element.
+    Pop,
+    /// Removes all elements.
+    Clear,
+}
+
+/// Operation on a [`DisjointSet`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisjointSetOp {
+    /// Adds a new singleton set.
+    MakeSet,
+    /// Merges the sets containing the two elements.
+    Union(usize, usize),
+}
+
+/// A collection together with the log of operations applied to it since its last checkpoint.
+pub struct Journal<C: Journaled> {
+    snapshot: C,
+    state: C,
+    ops: Vec<C::Op>,
+}
+
+impl<C: Journaled + Clone> Journal<C> {
+    /// Starts a journal on the collection. Its current state becomes the snapshot that replays start from.
+    /// ```
+    /// # use strctr::journal::{Journal, MapOp};
+    /// # use strctr::btree::BTreeMap;
+    /// let mut j = Journal::new(BTreeMap::<_, _>::new());
+    /// j.apply(MapOp::Insert("a", 1));
+    /// j.apply(MapOp::Insert("b", 2));
+    /// j.apply(MapOp::Remove("a"));
+    /// assert_eq!(j.get(&"b"), Some(&2));
+    /// assert_eq!(j.ops().len(), 3);
+    /// assert_eq!(j.replay_to(2).len(), 2);
+    /// ```
+    pub fn new(collection: C) -> Self {
+        Self {
+            snapshot: collection.clone(),
+            state: collection,
+            ops: Vec::new(),
+        }
+    }
+
+    /// Rebuilds a journal from a snapshot and the operations applied after it, for example after loading both from
+    /// storage.
+    pub fn from_parts(snapshot: C, ops: Vec<C::Op>) -> Self {
+        let mut state = snapshot.clone();
+        replay(&mut state, ops.iter().cloned());
+        Self {
+            snapshot,
+            state,
+            ops,
+        }
+    }
+
+    /// Applies the operation to the collection and appends it to the log.
+    pub fn apply(&mut self, op: C::Op) {
+        self.ops.push(op.clone());
+        self.state.apply(op);
+    }
+
+    /// Returns the current state of the collection. The journal also dereferences to it.
+    pub fn collection(&self) -> &C {
+        &self.state
+    }
+
+    /// Returns the state the log starts from.
+    pub fn snapshot(&self) -> &C {
+        &self.snapshot
+    }
+
+    /// Returns the operations applied since the snapshot, oldest first.
+    pub fn ops(&self) -> &[C::Op] {
+        &self.ops
+    }
+
+    /// Reconstructs the state after the first `n` logged operations by replaying them onto a copy of the snapshot.
+    ///
+    /// Panics if the log holds fewer than `n` operations.
+    pub fn replay_to(&self, n: usize) -> C {
+        if n > self.ops.len() {
+            panic!(
+                "OutOfBounds: The journal only holds {} operations",
+                self.ops.len()
+            );
+        }
+        let mut state = self.snapshot.clone();
+        replay(&mut state, self.ops[..n].iter().cloned());
+        state
+    }
+
+    /// Replays the whole log onto another collection, for example a replica or a different snapshot.
+    /// ```
+    /// # use strctr::journal::{Journal, HeapOp};
+    /// # use strctr::heap::{BinaryHeap, Natural};
+    /// let mut j = Journal::new(BinaryHeap::new());
+    /// j.apply(HeapOp::Push(3));
+    /// j.apply(HeapOp::Pop);
+    /// j.apply(HeapOp::Push(1));
+    /// let replica = j.replay_onto(BinaryHeap::from_vec(vec![5], Natural));
+    /// // The pop removed the 5 that only the replica had.
+    /// assert_eq!(replica.into_sorted_vec(), vec![1, 3]);
+    /// ```
+    pub fn replay_onto(&self, mut collection: C) -> C {
+        replay(&mut collection, self.ops.iter().cloned());
+        collection
+    }
+
+    /// Makes the current state the new snapshot and empties the log.
+    /// ```
+    /// # use strctr::journal::{Journal, DisjointSetOp};
+    /// # use strctr::disjoint_set::DisjointSet;
+    /// let mut j = Journal::new(DisjointSet::with_sets(3));
+    /// j.apply(DisjointSetOp::Union(0, 1));
+    /// j.checkpoint();
+    /// assert!(j.ops().is_empty());
+    /// assert_eq!(j.snapshot().count(), 2);
+    /// ```
+    pub fn checkpoint(&mut self) {
+        self.snapshot = self.state.clone();
+        self.ops.clear();
+    }
+
+    /// Returns the snapshot and the logged operations, from which [from_parts()](`Self::from_parts()`) can rebuild
+    /// the journal.
+    pub fn into_parts(self) -> (C, Vec<C::Op>) {
+        (self.snapshot, self.ops)
+    }
+
+    /// Returns the current state of the collection, dropping the log.
+    pub fn into_inner(self) -> C {
+        self.state
+    }
+}
+
+impl<C: Journaled> Deref for Journal<C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.state
+    }
+}
+
+impl<K: Ord + Clone, V: Clone, const B: usize> Journaled for BTreeMap<K, V, B> {
+    type Op = MapOp<K, V>;
+
+    fn apply(&mut self, op: Self::Op) {
+        match op {
+            MapOp::Insert(k, v) => {
+                self.insert(k, v);
+            }
+            MapOp::Remove(k) => {
+                self.remove(&k);
+            }
+            MapOp::Clear => self.clear(),
+        }
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Journaled for SkipListMap<K, V> {
+    type Op = MapOp<K, V>;
+
+    fn apply(&mut self, op: Self::Op) {
+        match op {
+            MapOp::Insert(k, v) => {
+                self.insert(k, v);
+            }
+            MapOp::Remove(k) => {
+                self.remove(&k);
+            }
+            MapOp::Clear => self.clear(),
+        }
+    }
+}
+
+impl<V: Clone> Journaled for Trie<V> {
+    type Op = MapOp<String, V>;
+
+    fn apply(&mut self, op: Self::Op) {
+        match op {
+            MapOp::Insert(k, v) => {
+                self.insert(&k, v);
+            }
+            MapOp::Remove(k) => {
+                self.remove(&k);
+            }
+            MapOp::Clear => self.clear(),
+        }
+    }
+}
+
+impl<T: Clone, C: Compare<T>> Journaled for BinaryHeap<T, C> {
+    type Op = HeapOp<T>;
+
+    fn apply(&mut self, op: Self::Op) {
+        match op {
+            HeapOp::Push(elem) => self.push(elem),
+            HeapOp::Pop => {
+                self.pop();
+            }
+            HeapOp::Clear => self.clear(),
+        }
+    }
+}
+
+impl Journaled for DisjointSet {
+    type Op = DisjointSetOp;
+
+    fn apply(&mut self, op: Self::Op) {
+        match op {
+            DisjointSetOp::MakeSet => {
+                self.make_set();
+            }
+            DisjointSetOp::Union(a, b) => {
+                self.union(a, b);
+            }
+        }
+    }
+}
@@ -1 +1,67 @@
+pub mod algo;
+pub mod align;
+pub mod arena;
 pub mod array;
+pub mod array_string;
+pub mod bitset;
+pub mod bloomier;
+pub mod bst;
+pub mod btree;
+pub mod buffer_pool;
+pub mod cache;
+pub mod chtholly;
+pub mod collection;
+pub mod const_map;
+pub mod counter;
+pub mod crdt;
+pub mod dataflow;
+pub mod disjoint_set;
+pub mod document;
+pub mod external_sort;
+pub mod frozen_set;
+pub mod graph;
+pub mod growth;
+pub mod hash;
+pub mod heap;
+pub mod heavy_hitters;
+pub mod hybrid_vec;
+pub mod im;
+pub mod index_tree;
+pub mod interval_tree;
+pub mod intrusive_rbtree;
+pub mod journal;
+pub mod lru;
+pub mod mapped;
+pub mod matrix;
+pub mod memo;
+pub mod multimap;
+pub mod ops;
+pub mod pool;
+pub mod priority_search_tree;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod quantile;
+pub mod rbtree;
+pub mod rctree;
+pub mod rcu;
+pub mod rope;
+pub mod segment_tree;
+pub mod seqlock;
+pub mod sharded_counter;
+pub mod simd;
+pub mod skiplist;
+pub mod sliding;
+pub mod snapshot;
+pub mod sorted_vec;
+pub mod sparse_set;
+pub mod spsc;
+pub mod succinct;
+pub mod sync;
+pub mod tiered;
+#[cfg(feature = "log")]
+pub mod traced;
+pub mod trie;
+pub mod versioned;
+pub mod viz;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,439 @@
+//! Least-recently-used caches. [`LruCache`] is a plain single-threaded cache, and [`ConcurrentLruCache`] splits the
+//! same API over independently locked shards so that many threads can use one cache through a shared reference.
+//!
+//! Both keep their entries in an arena-backed doubly linked list ordered by recency, next to a [`HashMap`] from keys
+//! to list positions, so every operation is `O(1)`.
+
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::sync::PoisonError;
+
+use crate::hash::HashState;
+use crate::sync::{Mutex, MutexGuard};
+
+/// End-of-list marker for links.
+const NIL: usize = usize::MAX;
+
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    prev: usize,
+    next: usize,
+}
+
+/// A cache holding at most `capacity` entries, evicting the least recently used one when full.
+pub struct LruCache<K, V> {
+    entries: Vec<Option<Entry<K, V>>>,
+    free: Vec<usize>,
+    map: HashMap<K, usize>,
+    /// Most recently used entry.
+    head: usize,
+    /// Least recently used entry.
+    tail: usize,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    /// Constructs a new, empty cache holding at most `capacity` entries.
+    ///
+    /// Panics if the capacity is 0.
+    pub fn new(capacity: usize) -> Self {
+        if capacity == 0 {
+            panic!("InvalidCapacity: LruCache needs room for at least one entry");
+        }
+        Self {
+            entries: Vec::new(),
+            free: Vec::new(),
+            map: HashMap::new(),
+            head: NIL,
+            tail: NIL,
+            capacity,
+        }
+    }
+
+    /// Returns the maximum number of entries.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns whether the cache contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Inserts a key-value pair and marks it as most recently used. Returns the previous value if the key was
+    /// present; otherwise a full cache evicts its least recently used entry.
+    /// ```
+    /// # use strctr::lru::LruCache;
+    /// let mut c = LruCache::new(2);
+    /// c.insert("a", 1);
+    /// c.insert("b", 2);
+    /// c.get(&"a");
+    /// c.insert("c", 3);
+    /// assert!(c.contains_key(&"a"));
+    /// assert!(!c.contains_key(&"b"));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(&i) = self.map.get(&key) {
+            self.touch(i);
+            return Some(std::mem::replace(&mut self.entry_mut(i).value, value));
+        }
+        if self.map.len() == self.capacity {
+            self.pop_lru();
+        }
+        let entry = Entry {
+            key: key.clone(),
+            value,
+            prev: NIL,
+            next: NIL,
+        };
+        let i = match self.free.pop() {
+            Some(i) => {
+                self.entries[i] = Some(entry);
+                i
+            }
+            None => {
+                self.entries.push(Some(entry));
+                self.entries.len() - 1
+            }
+        };
+        self.push_front(i);
+        self.map.insert(key, i);
+        None
+    }
+
+    /// Returns the value of the key and marks it as most recently used.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let i = *self.map.get(key)?;
+        self.touch(i);
+        Some(&self.entry(i).value)
+    }
+
+    /// Returns the value of the key mutably and marks it as most recently used.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let i = *self.map.get(key)?;
+        self.touch(i);
+        Some(&mut self.entry_mut(i).value)
+    }
+
+    /// Returns the value of the key without changing its recency.
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        let i = *self.map.get(key)?;
+        Some(&self.entry(i).value)
+    }
+
+    /// Returns whether the cache contains the key, without changing its recency.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Removes the key from the cache, returning its value.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let i = self.map.remove(key)?;
+        Some(self.take(i).1)
+    }
+
+    /// Removes and returns the least recently used entry.
+    /// ```
+    /// # use strctr::lru::LruCache;
+    /// let mut c = LruCache::new(3);
+    /// c.insert(1, "a");
+    /// c.insert(2, "b");
+    /// assert_eq!(c.pop_lru(), Some((1, "a")));
+    /// ```
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        if self.tail == NIL {
+            return None;
+        }
+        let (key, value) = self.take(self.tail);
+        self.map.remove(&key);
+        Some((key, value))
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.free.clear();
+        self.map.clear();
+        self.head = NIL;
+        self.tail = NIL;
+    }
+
+    /// Returns an iterator over the entries, from most to least recently used.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let mut cur = self.head;
+        std::iter::from_fn(move || {
+            if cur == NIL {
+                return None;
+            }
+            let entry = self.entry(cur);
+            cur = entry.next;
+            Some((&entry.key, &entry.value))
+        })
+    }
+
+    fn entry(&self, i: usize) -> &Entry<K, V> {
+        self.entries[i].as_ref().expect("live entry")
+    }
+
+    fn entry_mut(&mut self, i: usize) -> &mut Entry<K, V> {
+        self.entries[i].as_mut().expect("live entry")
+    }
+
+    fn unlink(&mut self, i: usize) {
+        let (prev, next) = {
+            let e = self.entry(i);
+            (e.prev, e.next)
+        };
+        match prev {
+            NIL => self.head = next,
+            p => self.entry_mut(p).next = next,
+        }
+        match next {
+            NIL => self.tail = prev,
+            n => self.entry_mut(n).prev = prev,
+        }
+    }
+
+    fn push_front(&mut self, i: usize) {
+        let head = self.head;
+        {
+            let e = self.entry_mut(i);
+            e.prev = NIL;
+            e.next = head;
+        }
+        match head {
+            NIL => self.tail = i,
+            h => self.entry_mut(h).prev = i,
+        }
+        self.head = i;
+    }
+
+    fn touch(&mut self, i: usize) {
+        if self.head != i {
+            self.unlink(i);
+            self.push_front(i);
+        }
+    }
+
+    /// Unlinks the entry and frees its slot. The caller removes it from the map.
+    fn take(&mut self, i: usize) -> (K, V) {
+        self.unlink(i);
+        let entry = self.entries[i].take().expect("live entry");
+        self.free.push(i);
+        (entry.key, entry.value)
+    }
+}
+
+/// Counters of one shard of a [`ConcurrentLruCache`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShardStats {
+    /// Lookups that found their key.
+    pub hits: u64,
+    /// Lookups that did not find their key.
+    pub misses: u64,
+    /// Entries evicted to make room for new ones.
+    pub evictions: u64,
+    /// Entries currently in the shard.
+    pub len: usize,
+}
+
+struct Shard<K, V> {
+    cache: LruCache<K, V>,
+    stats: ShardStats,
+}
+
+/// A thread-safe LRU cache. Keys are hashed onto shards, each an [`LruCache`] behind its own lock, so threads
+/// working on different shards never wait for each other.
+///
+/// Every shard holds an equal part of the capacity and evicts on its own, so the least recently used entry of the
+/// whole cache is not necessarily the first to go.
+pub struct ConcurrentLruCache<K, V> {
+    shards: Box<[Mutex<Shard<K, V>>]>,
+    hasher: HashState,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> ConcurrentLruCache<K, V> {
+    /// Constructs a new, empty cache holding at most about `capacity` entries, with four shards per available CPU.
+    ///
+    /// Panics if the capacity is 0.
+    pub fn new(capacity: usize) -> Self {
+        let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self::with_shards(capacity, 4 * cpus)
+    }
+
+    /// Constructs a new, empty cache holding at most about `capacity` entries, spread over the number of shards.
+    /// Every shard gets `capacity / shards` entries, rounded up, and there are never more shards than entries.
+    ///
+    /// Panics if the capacity or the number of shards is 0.
+    pub fn with_shards(capacity: usize, shards: usize) -> Self {
+        Self::with_hasher(capacity, shards, HashState::new())
+    }
+
+    /// Constructs a new, empty cache like [with_shards()](`Self::with_shards()`), which hashes keys onto shards with
+    /// the state's keys.
+    ///
+    /// Panics if the capacity or the number of shards is 0.
+    pub fn with_hasher(capacity: usize, shards: usize, state: HashState) -> Self {
+        if capacity == 0 || shards == 0 {
+            panic!("InvalidCapacity: ConcurrentLruCache needs at least one shard with room for one entry");
+        }
+        let shards = shards.min(capacity);
+        let per_shard = capacity.div_ceil(shards);
+        Self {
+            shards: (0..shards)
+                .map(|_| {
+                    Mutex::new(Shard {
+                        cache: LruCache::new(per_shard),
+                        stats: ShardStats::default(),
+                    })
+                })
+                .collect(),
+            hasher: state,
+            capacity: per_shard * shards,
+        }
+    }
+
+    /// Returns the maximum number of entries over all shards.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of shards.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Returns the number of entries. Other threads may change it at any time.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|s| lock(s).cache.len()).sum()
+    }
+
+    /// Returns whether the cache contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts a key-value pair and marks it as most recently used, returning the previous value of the key.
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let mut shard = self.shard(&key);
+        let evicts = !shard.cache.contains_key(&key) && shard.cache.len() == shard.cache.capacity();
+        let old = shard.cache.insert(key, value);
+        if evicts {
+            shard.stats.evictions += 1;
+        }
+        old
+    }
+
+    /// Calls `f` with the value of the key, marking it as most recently used, and returns the result. Use this
+    /// instead of [get()](`Self::get()`) to avoid cloning the value; the shard stays locked while `f` runs.
+    /// ```
+    /// # use strctr::lru::ConcurrentLruCache;
+    /// let c = ConcurrentLruCache::new(16);
+    /// c.insert("k", vec![1, 2, 3]);
+    /// assert_eq!(c.get_with(&"k", |v| v.len()), Some(3));
+    /// ```
+    pub fn get_with<R>(&self, key: &K, f: impl FnOnce(&V) -> R) -> Option<R> {
+        let mut shard = self.shard(key);
+        let result = shard.cache.get(key).map(f);
+        match result {
+            Some(_) => shard.stats.hits += 1,
+            None => shard.stats.misses += 1,
+        }
+        result
+    }
+
+    /// Returns whether the cache contains the key, without changing its recency.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.shard(key).cache.contains_key(key)
+    }
+
+    /// Removes the key from the cache, returning its value.
+    pub fn remove(&self, key: &K) -> Option<V> {
+        self.shard(key).cache.remove(key)
+    }
+
+    /// Removes all entries. Statistics are kept.
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            lock(shard).cache.clear();
+        }
+    }
+
+    /// Returns the statistics of every shard.
+    /// ```
+    /// # use strctr::lru::ConcurrentLruCache;
+    /// let c = ConcurrentLruCache::with_shards(4, 2);
+    /// c.insert(1, "a");
+    /// c.get(&1);
+    /// c.get(&2);
+    /// let stats = c.stats();
+    /// assert_eq!(stats.len(), 2);
+    /// assert_eq!(stats.iter().map(|s| s.hits).sum::<u64>(), 1);
+    /// assert_eq!(stats.iter().map(|s| s.misses).sum::<u64>(), 1);
+    /// ```
+    pub fn stats(&self) -> Vec<ShardStats> {
+        self.shards
+            .iter()
+            .map(|s| {
+                let shard = lock(s);
+                ShardStats {
+                    len: shard.cache.len(),
+                    ..shard.stats
+                }
+            })
+            .collect()
+    }
+
+    /// Resets the hit, miss and eviction counters of every shard.
+    pub fn reset_stats(&self) {
+        for shard in self.shards.iter() {
+            lock(shard).stats = ShardStats::default();
+        }
+    }
+
+    fn shard(&self, key: &K) -> MutexGuard<'_, Shard<K, V>> {
+        let i = self.hasher.hash_one(key) as usize % self.shards.len();
+        lock(&self.shards[i])
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> ConcurrentLruCache<K, V> {
+    /// Returns a copy of the value of the key and marks it as most recently used.
+    /// ```
+    /// # use strctr::lru::ConcurrentLruCache;
+    /// let c = ConcurrentLruCache::new(1000);
+    /// std::thread::scope(|s| {
+    ///     for t in 0..4 {
+    ///         let c = &c;
+    ///         s.spawn(move || {
+    ///             for i in 0..100 {
+    ///                 c.insert(t * 100 + i, i);
+    ///             }
+    ///         });
+    ///     }
+    /// });
+    /// assert_eq!(c.len(), 400);
+    /// assert_eq!(c.get(&250), Some(50));
+    /// ```
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.get_with(key, V::clone)
+    }
+
+    /// Returns a copy of the value of the key without changing its recency or the statistics.
+    pub fn peek(&self, key: &K) -> Option<V> {
+        self.shard(key).cache.peek(key).cloned()
+    }
+}
+
+/// Locks the shard, ignoring poisoning: a panicking [get_with()](`ConcurrentLruCache::get_with()`) callback runs
+/// after the lookup completed and leaves the shard intact.
+fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(PoisonError::into_inner)
+}
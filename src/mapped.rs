@@ -0,0 +1,284 @@
+//! Relocatable byte layouts of frozen structures, for querying them in place from memory-mapped files.
+//!
+//! A [snapshot](`crate::snapshot`) has to be decoded into a new structure before it can be used, which for a large index
+//! takes as long as reading the whole file. The layouts here are the structures' own arrays, written in native byte
+//! order behind a 32-byte header. A view type like [`FrozenIntSetView`](`crate::frozen_set::FrozenIntSetView`) borrows
+//! such bytes, typically a memory-mapped file, checks the header and answers queries directly on them: opening takes
+//! constant time, and the operating system pages in only what queries touch.
+//!
+//! The header holds the magic bytes `STRV`, an endianness marker, the layout version, the element types, a tag naming
+//! the structure and the element count. Opening fails with a [`LayoutError`] if any of them does not match, or if the
+//! arrays are not aligned for their element type. The arrays start at multiples of 8 bytes, so bytes at an 8-aligned
+//! address, like a memory map or an [`AlignedBytes`], are always aligned. The contents of the arrays are not
+//! validated: corrupted bytes give wrong answers, but never undefined behavior.
+//! ```
+//! # use strctr::frozen_set::{FrozenIntSet, FrozenIntSetView};
+//! # use strctr::mapped::AlignedBytes;
+//! let set: FrozenIntSet<u64> = (0..1000).map(|n| n * n).collect();
+//! let mut file = Vec::new();
+//! set.write_mapped(&mut file).unwrap();
+//!
+//! // A memory map of the file would do instead.
+//! let bytes = AlignedBytes::from(file.as_slice());
+//! let view = FrozenIntSetView::<u64>::from_bytes(&bytes).unwrap();
+//! assert!(view.contains(998_001));
+//! assert!(!view.contains(998_000));
+//! assert!(FrozenIntSetView::<u32>::from_bytes(&bytes).is_err());
+//! ```
+
+use std::io::{self, Read, Write};
+use std::mem::{align_of, size_of};
+use std::ops::Deref;
+
+/// Magic bytes starting every layout.
+pub const MAGIC: [u8; 4] = *b"STRV";
+
+/// Version of the layouts written by this release. Other versions are rejected.
+pub const VERSION: u16 = 1;
+
+/// Written in native byte order; reads back differently on a machine of the other endianness.
+const ENDIAN_MARKER: u32 = 0x0102_0304;
+
+/// Size of the header, which keeps the first array 8-aligned.
+const HEADER_LEN: usize = 32;
+
+/// Alignment of every array relative to the start of the layout.
+const ARRAY_ALIGN: usize = 8;
+
+/// List of errors that could occur when opening a layout.
+#[derive(Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    /// The bytes end before the header or an array does.
+    TooShort,
+    /// The bytes do not start with [`MAGIC`].
+    BadMagic,
+    /// The layout was written by a machine of the other endianness.
+    WrongEndianness,
+    /// The layout was written by a different layout version.
+    UnsupportedVersion(u16),
+    /// The layout holds a different structure.
+    WrongStructure { expected: [u8; 4], found: [u8; 4] },
+    /// The layout holds elements of a different type.
+    WrongElementType,
+    /// An array does not start at an address aligned for its element type.
+    Misaligned,
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Element types that can be viewed in place: the fixed-size primitive integers up to 64 bits and the floats. Every
+/// bit pattern is a valid value of these types, so bytes from a file can be read as them.
+pub trait Pod: Copy + sealed::Sealed {
+    #[doc(hidden)]
+    const KIND: u8;
+}
+
+/// Types whose values are plain bytes, which can be reinterpreted as bytes and rebuilt from them, like by
+/// [`Array::as_bytes()`](`crate::array::Array::as_bytes()`). Every [`Pod`] type is plain, and so are `#[repr(C)]`
+/// structs of plain fields laid out without padding.
+/// ```
+/// # use strctr::mapped::Plain;
+/// #[derive(Clone, Copy)]
+/// #[repr(C)]
+/// struct Sample {
+///     timestamp: u64,
+///     value: f32,
+///     channel: u32,
+/// }
+///
+/// // Safety: the fields are plain, and add up to the struct's size, so there is no padding.
+/// unsafe impl Plain for Sample {}
+/// ```
+///
+/// # Safety
+///
+/// The type must have no padding bytes, and every bit pattern of its size must be a valid value. Types holding
+/// references, pointers, `bool`s, `char`s or enums are not plain.
+pub unsafe trait Plain: Copy + 'static {}
+
+macro_rules! pod {
+    ($($t:ty = $kind:literal),*) => {
+        $(
+            impl sealed::Sealed for $t {}
+
+            impl Pod for $t {
+                const KIND: u8 = $kind;
+            }
+
+            unsafe impl Plain for $t {}
+        )*
+    };
+}
+
+// Elements of an array follow each other without gaps, so an array of plain elements has no padding either.
+unsafe impl<T: Plain, const N: usize> Plain for [T; N] {}
+
+pod!(
+    u8 = 1,
+    u16 = 2,
+    u32 = 3,
+    u64 = 4,
+    i8 = 5,
+    i16 = 6,
+    i32 = 7,
+    i64 = 8,
+    f32 = 9,
+    f64 = 10
+);
+
+/// Writes the header of a layout with two element types, `V` being `()` for structures with one.
+pub(crate) fn write_header<W: Write, K: Pod, V: Kind>(
+    w: &mut W,
+    tag: [u8; 4],
+    len: usize,
+) -> io::Result<()> {
+    w.write_all(&MAGIC)?;
+    w.write_all(&ENDIAN_MARKER.to_ne_bytes())?;
+    w.write_all(&VERSION.to_ne_bytes())?;
+    w.write_all(&[<K as Kind>::KIND, <V as Kind>::KIND])?;
+    w.write_all(&tag)?;
+    w.write_all(&(len as u64).to_ne_bytes())?;
+    w.write_all(&[0; 8])
+}
+
+/// Writes an array, padded to the array alignment.
+pub(crate) fn write_array<W: Write, T: Pod>(w: &mut W, values: &[T]) -> io::Result<()> {
+    write_elements(w, values)?;
+    write_padding(w, std::mem::size_of_val(values))
+}
+
+/// Writes part of an array, without padding. An array written in parts ends with [`write_padding()`].
+pub(crate) fn write_elements<W: Write, T: Pod>(w: &mut W, values: &[T]) -> io::Result<()> {
+    // Pod types have no padding bytes, so every byte of the slice is initialized.
+    let bytes = unsafe {
+        std::slice::from_raw_parts(values.as_ptr().cast::<u8>(), std::mem::size_of_val(values))
+    };
+    w.write_all(bytes)
+}
+
+/// Pads an array of `len` bytes to the array alignment.
+pub(crate) fn write_padding<W: Write>(w: &mut W, len: usize) -> io::Result<()> {
+    w.write_all(&[0; ARRAY_ALIGN][..padding(len)])
+}
+
+/// Element type slot of a header: a [`Pod`] type, or `()` for none.
+pub(crate) trait Kind {
+    const KIND: u8;
+}
+
+impl<T: Pod> Kind for T {
+    const KIND: u8 = <T as Pod>::KIND;
+}
+
+impl Kind for () {
+    const KIND: u8 = 0;
+}
+
+fn padding(len: usize) -> usize {
+    (ARRAY_ALIGN - len % ARRAY_ALIGN) % ARRAY_ALIGN
+}
+
+/// Reads the arrays of a layout one after the other.
+pub(crate) struct Arrays<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+/// Checks the header and returns the element count and a reader for the arrays that follow.
+pub(crate) fn read_header<K: Pod, V: Kind>(
+    bytes: &[u8],
+    tag: [u8; 4],
+) -> Result<(usize, Arrays<'_>), LayoutError> {
+    let header = bytes.get(..HEADER_LEN).ok_or(LayoutError::TooShort)?;
+    let field = |at: usize, len: usize| &header[at..at + len];
+    if field(0, 4) != MAGIC {
+        return Err(LayoutError::BadMagic);
+    }
+    if field(4, 4) != ENDIAN_MARKER.to_ne_bytes() {
+        return Err(LayoutError::WrongEndianness);
+    }
+    let version = u16::from_ne_bytes([header[8], header[9]]);
+    if version != VERSION {
+        return Err(LayoutError::UnsupportedVersion(version));
+    }
+    let found = [header[12], header[13], header[14], header[15]];
+    if found != tag {
+        return Err(LayoutError::WrongStructure {
+            expected: tag,
+            found,
+        });
+    }
+    if header[10] != <K as Kind>::KIND || header[11] != <V as Kind>::KIND {
+        return Err(LayoutError::WrongElementType);
+    }
+    let len = u64::from_ne_bytes(field(16, 8).try_into().unwrap());
+    let len = usize::try_from(len).map_err(|_| LayoutError::TooShort)?;
+    Ok((
+        len,
+        Arrays {
+            bytes,
+            offset: HEADER_LEN,
+        },
+    ))
+}
+
+impl<'a> Arrays<'a> {
+    /// Returns the next array, of `len` elements.
+    pub(crate) fn next<T: Pod>(&mut self, len: usize) -> Result<&'a [T], LayoutError> {
+        let size = len
+            .checked_mul(size_of::<T>())
+            .ok_or(LayoutError::TooShort)?;
+        let end = self.offset.checked_add(size).ok_or(LayoutError::TooShort)?;
+        let bytes = self
+            .bytes
+            .get(self.offset..end)
+            .ok_or(LayoutError::TooShort)?;
+        if bytes.as_ptr().align_offset(align_of::<T>()) != 0 {
+            return Err(LayoutError::Misaligned);
+        }
+        self.offset = end + padding(size);
+        // The bytes are in bounds, aligned for T and borrowed for 'a, and every bit pattern is a valid T.
+        Ok(unsafe { std::slice::from_raw_parts(bytes.as_ptr().cast::<T>(), len) })
+    }
+}
+
+/// An owned, 8-aligned byte buffer, for holding a layout in memory where a memory map is not available.
+#[derive(Clone, Default)]
+pub struct AlignedBytes {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl AlignedBytes {
+    /// Reads all bytes from the reader.
+    pub fn read_from<R: Read>(mut r: R) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        r.read_to_end(&mut bytes)?;
+        Ok(bytes.as_slice().into())
+    }
+}
+
+impl From<&[u8]> for AlignedBytes {
+    fn from(bytes: &[u8]) -> Self {
+        let mut words = vec![0u64; bytes.len().div_ceil(8)];
+        // The words span at least bytes.len() bytes, and u64 has no invalid bit patterns.
+        let dst =
+            unsafe { std::slice::from_raw_parts_mut(words.as_mut_ptr().cast::<u8>(), bytes.len()) };
+        dst.copy_from_slice(bytes);
+        Self {
+            words,
+            len: bytes.len(),
+        }
+    }
+}
+
+impl Deref for AlignedBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // The words span at least len initialized bytes.
+        unsafe { std::slice::from_raw_parts(self.words.as_ptr().cast::<u8>(), self.len) }
+    }
+}
@@ -0,0 +1,217 @@
+//! Fixed-size two-dimensional matrix. Like [`Array`](`crate::array::Array`), the dimensions are compile-time
+//! constants and the elements live inline in a nested [`std::array`], so grids and DP tables need no allocation.
+
+use std::ops::{Index, IndexMut};
+
+/// A matrix with `R` rows and `C` columns, stored row by row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Matrix<T, const R: usize, const C: usize> {
+    rows: [[T; C]; R],
+}
+
+impl<T: Copy + Default, const R: usize, const C: usize> Default for Matrix<T, R, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + Default, const R: usize, const C: usize> Matrix<T, R, C> {
+    /// Constructs a new matrix with every element set to `T::default()`.
+    pub fn new() -> Self {
+        Self::fill(T::default())
+    }
+}
+
+impl<T: Copy, const R: usize, const C: usize> Matrix<T, R, C> {
+    /// Constructs a new matrix with every element set to the value.
+    /// ```
+    /// # use strctr::matrix::Matrix;
+    /// let m: Matrix<char, 2, 3> = Matrix::fill('.');
+    /// assert_eq!(m[(1, 2)], '.');
+    /// ```
+    pub fn fill(value: T) -> Self {
+        Self {
+            rows: [[value; C]; R],
+        }
+    }
+
+    /// Returns the transposed matrix, whose rows are this matrix's columns.
+    /// ```
+    /// # use strctr::matrix::Matrix;
+    /// let m = Matrix::from_rows([[1, 2, 3], [4, 5, 6]]);
+    /// assert_eq!(m.transpose(), Matrix::from_rows([[1, 4], [2, 5], [3, 6]]));
+    /// ```
+    pub fn transpose(&self) -> Matrix<T, C, R> {
+        Matrix {
+            rows: std::array::from_fn(|c| std::array::from_fn(|r| self.rows[r][c])),
+        }
+    }
+}
+
+impl<T, const R: usize, const C: usize> Matrix<T, R, C> {
+    /// Constructs a new matrix out of its rows.
+    pub fn from_rows(rows: [[T; C]; R]) -> Self {
+        Self { rows }
+    }
+
+    /// Constructs a new matrix whose elements are computed from their `(row, col)` position.
+    /// ```
+    /// # use strctr::matrix::Matrix;
+    /// let identity: Matrix<u8, 3, 3> = Matrix::from_fn(|r, c| u8::from(r == c));
+    /// assert_eq!(identity.row(1), &[0, 1, 0]);
+    /// ```
+    pub fn from_fn(mut f: impl FnMut(usize, usize) -> T) -> Self {
+        Self {
+            rows: std::array::from_fn(|r| std::array::from_fn(|c| f(r, c))),
+        }
+    }
+
+    /// Returns the number of rows.
+    pub const fn row_count(&self) -> usize {
+        R
+    }
+
+    /// Returns the number of columns.
+    pub const fn col_count(&self) -> usize {
+        C
+    }
+
+    /// Returns the element at the position, or `None` if it is out of bounds.
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        self.rows.get(row)?.get(col)
+    }
+
+    /// Returns a mutable reference to the element at the position, or `None` if it is out of bounds.
+    pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
+        self.rows.get_mut(row)?.get_mut(col)
+    }
+
+    /// Returns the row.
+    ///
+    /// Panics if the row is out of bounds.
+    pub fn row(&self, row: usize) -> &[T; C] {
+        &self.rows[row]
+    }
+
+    /// Returns the row mutably.
+    ///
+    /// Panics if the row is out of bounds.
+    pub fn row_mut(&mut self, row: usize) -> &mut [T; C] {
+        &mut self.rows[row]
+    }
+
+    /// Returns an iterator over the elements of the column, top to bottom.
+    ///
+    /// Panics if the column is out of bounds.
+    /// ```
+    /// # use strctr::matrix::Matrix;
+    /// let m = Matrix::from_rows([[1, 2], [3, 4], [5, 6]]);
+    /// assert_eq!(m.col(1).sum::<i32>(), 12);
+    /// ```
+    pub fn col(&self, col: usize) -> impl Iterator<Item = &T> {
+        if col >= C {
+            panic!("OutOfBounds: Wanted column {}, but there are {}", col, C);
+        }
+        self.rows.iter().map(move |row| &row[col])
+    }
+
+    /// Returns an iterator over the rows, top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[T; C]> {
+        self.rows.iter()
+    }
+
+    /// Returns an iterator over the mutable rows, top to bottom.
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [T; C]> {
+        self.rows.iter_mut()
+    }
+
+    /// Returns an iterator over the columns, left to right. Each column is an iterator over its elements.
+    /// ```
+    /// # use strctr::matrix::Matrix;
+    /// let m = Matrix::from_rows([[1, 2], [3, 4]]);
+    /// let sums: Vec<i32> = m.cols().map(|c| c.sum()).collect();
+    /// assert_eq!(sums, vec![4, 6]);
+    /// ```
+    pub fn cols(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..C).map(move |c| self.col(c))
+    }
+
+    /// Returns an iterator over all elements along with their `(row, col)` position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.rows
+            .iter()
+            .enumerate()
+            .flat_map(|(r, row)| row.iter().enumerate().map(move |(c, v)| ((r, c), v)))
+    }
+
+    /// Returns the rows, consuming the matrix.
+    pub fn into_rows(self) -> [[T; C]; R] {
+        self.rows
+    }
+}
+
+impl<T, const R: usize, const C: usize> Index<(usize, usize)> for Matrix<T, R, C> {
+    type Output = T;
+
+    /// Returns the element at `(row, col)`.
+    ///
+    /// Panics if the position is out of bounds.
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        match self.get(row, col) {
+            Some(v) => v,
+            None => panic!(
+                "OutOfBounds: ({}, {}) is outside a {}x{} matrix",
+                row, col, R, C
+            ),
+        }
+    }
+}
+
+impl<T, const R: usize, const C: usize> IndexMut<(usize, usize)> for Matrix<T, R, C> {
+    /// Returns the element at `(row, col)` mutably.
+    ///
+    /// Panics if the position is out of bounds.
+    /// ```
+    /// # use strctr::matrix::Matrix;
+    /// let mut board: Matrix<bool, 8, 8> = Matrix::new();
+    /// board[(3, 4)] = true;
+    /// assert_eq!(board.iter().filter(|(_, v)| **v).count(), 1);
+    /// ```
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        match self.get_mut(row, col) {
+            Some(v) => v,
+            None => panic!(
+                "OutOfBounds: ({}, {}) is outside a {}x{} matrix",
+                row, col, R, C
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Sync, const R: usize, const C: usize> rayon::iter::IntoParallelIterator
+    for &'a Matrix<T, R, C>
+{
+    type Item = &'a T;
+    type Iter = rayon::slice::Iter<'a, T>;
+
+    /// Returns a parallel iterator over the elements, row by row. The element at index `i` is at position
+    /// `(i / C, i % C)`.
+    fn into_par_iter(self) -> Self::Iter {
+        self.rows.as_flattened().into_par_iter()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Send, const R: usize, const C: usize> rayon::iter::IntoParallelIterator
+    for &'a mut Matrix<T, R, C>
+{
+    type Item = &'a mut T;
+    type Iter = rayon::slice::IterMut<'a, T>;
+
+    /// Returns a parallel iterator over the elements, row by row. The element at index `i` is at position
+    /// `(i / C, i % C)`.
+    fn into_par_iter(self) -> Self::Iter {
+        self.rows.as_flattened_mut().into_par_iter()
+    }
+}
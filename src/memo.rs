@@ -0,0 +1,188 @@
+//! Memoization of recursive computations.
+//!
+//! A [`Memo`] stores the result of a computation per key, and runs the computation only for keys it has no result
+//! for. Computations may look up other keys of the same memo, like the subproblems of dynamic programming, and a key
+//! that ends up depending on itself is reported as a [cycle](`MemoError::Cycle`) instead of recursing forever. Memos
+//! are unbounded by default, or keep a bounded number of results evicted by one of the [`cache`](`crate::cache`)
+//! policies.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::cache::{Eviction, TtlCache};
+
+/// List of errors that could occur when computing a value of a [`Memo`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum MemoError<K> {
+    /// The key's value was needed while it was being computed.
+    Cycle(K),
+}
+
+enum Store<K, V> {
+    Unbounded(HashMap<K, V>),
+    Bounded(TtlCache<K, V>),
+}
+
+/// A memo of the values computed for keys.
+/// ```
+/// # use strctr::memo::{Memo, MemoError};
+/// fn fib(memo: &mut Memo<u64, u128>, n: u64) -> Result<u128, MemoError<u64>> {
+///     memo.get_or_compute(n, |memo| {
+///         if n < 2 {
+///             return Ok(n as u128);
+///         }
+///         Ok(fib(memo, n - 1)? + fib(memo, n - 2)?)
+///     })
+/// }
+///
+/// let mut memo = Memo::new();
+/// assert_eq!(fib(&mut memo, 150), Ok(9969216677189303386214405760200));
+/// assert_eq!(memo.len(), 151);
+/// ```
+pub struct Memo<K, V> {
+    store: Store<K, V>,
+    /// Keys whose values are being computed.
+    in_progress: HashSet<K>,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Memo<K, V> {
+    /// Constructs a new, empty memo that keeps every value.
+    pub fn new() -> Self {
+        Self {
+            store: Store::Unbounded(HashMap::new()),
+            in_progress: HashSet::new(),
+        }
+    }
+
+    /// Constructs a new, empty memo that keeps at most `capacity` values, evicting by the policy when full. Evicted
+    /// values are computed again when needed.
+    ///
+    /// Panics if the capacity is 0.
+    /// ```
+    /// # use strctr::cache::Eviction;
+    /// # use strctr::memo::Memo;
+    /// let mut memo = Memo::bounded(2, Eviction::Lru);
+    /// let mut runs = 0;
+    /// for key in [1, 2, 1, 3, 2] {
+    ///     memo.get_or_compute(key, |_| {
+    ///         runs += 1;
+    ///         Ok(key * 10)
+    ///     })
+    ///     .unwrap();
+    /// }
+    /// // 3 evicts 2, which has to be computed again.
+    /// assert_eq!(runs, 4);
+    /// ```
+    pub fn bounded(capacity: usize, eviction: Eviction) -> Self {
+        Self {
+            store: Store::Bounded(TtlCache::new(capacity, eviction)),
+            in_progress: HashSet::new(),
+        }
+    }
+
+    /// Returns the number of stored values.
+    pub fn len(&self) -> usize {
+        match &self.store {
+            Store::Unbounded(map) => map.len(),
+            Store::Bounded(cache) => cache.len(),
+        }
+    }
+
+    /// Returns whether the memo stores no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the stored value of the key. A bounded memo counts it as a use.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        match &mut self.store {
+            Store::Unbounded(map) => map.get(key),
+            Store::Bounded(cache) => cache.get(key),
+        }
+    }
+
+    /// Returns whether the memo stores a value for the key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        match &self.store {
+            Store::Unbounded(map) => map.contains_key(key),
+            Store::Bounded(cache) => cache.contains_key(key),
+        }
+    }
+
+    /// Returns whether the key's value is being computed.
+    pub fn is_in_progress(&self, key: &K) -> bool {
+        self.in_progress.contains(key)
+    }
+
+    /// Returns the stored value of the key, or computes, stores and returns it. The computation receives the memo, so
+    /// it can look up the values it depends on.
+    ///
+    /// Returns a [cycle](`MemoError::Cycle`) if the key's value is being computed already, which means that it depends
+    /// on itself. An error returned by the computation is passed on, and nothing is stored for the key.
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use strctr::memo::{Memo, MemoError};
+    /// type Deps = HashMap<&'static str, Vec<&'static str>>;
+    ///
+    /// fn depth(memo: &mut Memo<&'static str, usize>, deps: &Deps, name: &'static str) -> Result<usize, MemoError<&'static str>> {
+    ///     memo.get_or_compute(name, |memo| {
+    ///         let mut deepest = 0;
+    ///         for &dep in &deps[name] {
+    ///             deepest = deepest.max(depth(memo, deps, dep)? + 1);
+    ///         }
+    ///         Ok(deepest)
+    ///     })
+    /// }
+    ///
+    /// let deps = HashMap::from([("app", vec!["lib", "log"]), ("lib", vec!["log"]), ("log", vec!["app"])]);
+    /// let mut memo = Memo::new();
+    /// assert_eq!(depth(&mut memo, &deps, "app"), Err(MemoError::Cycle("app")));
+    /// assert!(memo.is_empty() && !memo.is_in_progress(&"app"));
+    /// ```
+    pub fn get_or_compute(
+        &mut self,
+        key: K,
+        compute: impl FnOnce(&mut Self) -> Result<V, MemoError<K>>,
+    ) -> Result<V, MemoError<K>> {
+        if let Some(value) = self.get(&key) {
+            return Ok(value.clone());
+        }
+        if !self.in_progress.insert(key.clone()) {
+            return Err(MemoError::Cycle(key));
+        }
+        let result = compute(self);
+        self.in_progress.remove(&key);
+        let value = result?;
+        match &mut self.store {
+            Store::Unbounded(map) => {
+                map.insert(key, value.clone());
+            }
+            Store::Bounded(cache) => {
+                cache.insert(key, value.clone());
+            }
+        }
+        Ok(value)
+    }
+
+    /// Removes the key's value, so that it is computed again when needed next. Returns the removed value.
+    pub fn invalidate(&mut self, key: &K) -> Option<V> {
+        match &mut self.store {
+            Store::Unbounded(map) => map.remove(key),
+            Store::Bounded(cache) => cache.remove(key),
+        }
+    }
+
+    /// Removes all values.
+    pub fn clear(&mut self) {
+        match &mut self.store {
+            Store::Unbounded(map) => map.clear(),
+            Store::Bounded(cache) => cache.clear(),
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Hash map from each key to a list of values.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::hash::HashState;
+
+/// A map that keeps every value inserted under a key, in insertion order.
+///
+/// A key is present exactly as long as it has at least one value: removing its last value removes the key.
+/// ```
+/// # use strctr::multimap::MultiMap;
+/// let mut routes = MultiMap::new();
+/// routes.insert("/users", "GET");
+/// routes.insert("/users", "POST");
+/// routes.insert("/health", "GET");
+/// assert_eq!(routes.get("/users"), &["GET", "POST"]);
+/// assert_eq!(routes.get("/admin"), &[] as &[&str]);
+/// assert_eq!(routes.len(), 3);
+/// assert_eq!(routes.key_count(), 2);
+///
+/// assert_eq!(routes.remove_one("/users", &"GET"), Some("GET"));
+/// assert_eq!(routes.remove_all("/health"), vec!["GET"]);
+/// assert_eq!(routes.iter().collect::<Vec<_>>(), vec![(&"/users", &"POST")]);
+/// ```
+#[derive(Clone)]
+pub struct MultiMap<K, V> {
+    map: HashMap<K, Vec<V>, HashState>,
+    /// Number of values over all keys.
+    len: usize,
+}
+
+impl<K, V> Default for MultiMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> MultiMap<K, V> {
+    /// Constructs a new, empty map.
+    pub fn new() -> Self {
+        Self::with_hasher(HashState::new())
+    }
+
+    /// Constructs a new, empty map that hashes keys with the state's keys.
+    pub fn with_hasher(state: HashState) -> Self {
+        Self {
+            map: HashMap::with_hasher(state),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of values, over all keys.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the map contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of distinct keys.
+    pub fn key_count(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Removes all keys and values.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.len = 0;
+    }
+
+    /// Returns an iterator over every key-value pair, visiting the values of a key in insertion order. The keys come in
+    /// no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map
+            .iter()
+            .flat_map(|(k, values)| values.iter().map(move |v| (k, v)))
+    }
+
+    /// Returns an iterator over the keys and their values, in no particular order.
+    pub fn iter_all(&self) -> impl Iterator<Item = (&K, &[V])> {
+        self.map.iter().map(|(k, values)| (k, values.as_slice()))
+    }
+
+    /// Returns an iterator over the distinct keys, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.map.keys()
+    }
+
+    /// Returns an iterator over all values, in the order of [iter()](`Self::iter()`).
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.map.values().flatten()
+    }
+}
+
+impl<K: Hash + Eq, V> MultiMap<K, V> {
+    /// Appends the value to the key's values.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.map.entry(key).or_default().push(value);
+        self.len += 1;
+    }
+
+    /// Returns the key's values in insertion order, or an empty slice if the key is missing.
+    pub fn get<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> &[V]
+    where
+        K: Borrow<Q>,
+    {
+        self.map.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the key's first value.
+    pub fn get_first<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        self.get(key).first()
+    }
+
+    /// Returns whether the key has any values.
+    pub fn contains_key<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Removes the key and returns all its values, in insertion order.
+    pub fn remove_all<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Vec<V>
+    where
+        K: Borrow<Q>,
+    {
+        let values = self.map.remove(key).unwrap_or_default();
+        self.len -= values.len();
+        values
+    }
+
+    /// Removes the first of the key's values that equals the given one and returns it.
+    pub fn remove_one<Q: Hash + Eq + ?Sized>(&mut self, key: &Q, value: &V) -> Option<V>
+    where
+        K: Borrow<Q>,
+        V: PartialEq,
+    {
+        self.remove_first_where(key, |v| v == value)
+    }
+
+    /// Removes the first of the key's values that satisfies the predicate and returns it.
+    pub fn remove_first_where<Q: Hash + Eq + ?Sized>(
+        &mut self,
+        key: &Q,
+        mut pred: impl FnMut(&V) -> bool,
+    ) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        let values = self.map.get_mut(key)?;
+        let i = values.iter().position(&mut pred)?;
+        let value = values.remove(i);
+        if values.is_empty() {
+            self.map.remove(key);
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Keeps only the pairs the predicate returns `true` for. Keys left without values are removed.
+    /// ```
+    /// # use strctr::multimap::MultiMap;
+    /// let mut m: MultiMap<_, _> = [("a", 1), ("a", 2), ("b", 3)].into_iter().collect();
+    /// m.retain(|_, v| v % 2 == 0);
+    /// assert_eq!(m.len(), 1);
+    /// assert!(!m.contains_key("b"));
+    /// ```
+    pub fn retain(&mut self, mut pred: impl FnMut(&K, &V) -> bool) {
+        let mut len = 0;
+        self.map.retain(|k, values| {
+            values.retain(|v| pred(k, v));
+            len += values.len();
+            !values.is_empty()
+        });
+        self.len = len;
+    }
+}
+
+impl<K: Hash + Eq, V> FromIterator<(K, V)> for MultiMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Hash + Eq, V> Extend<(K, V)> for MultiMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V: PartialEq> PartialEq for MultiMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<K: Hash + Eq, V: Eq> Eq for MultiMap<K, V> {}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for MultiMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.map.iter()).finish()
+    }
+}
@@ -0,0 +1,355 @@
+//! Operation language for driving the structures of this crate from fuzzers and differential tests.
+//!
+//! A test case is a list of [`Op`]s. [`decode()`] turns any byte string into one, so a fuzzer's raw input can be fed
+//! in directly, and [`encode()`] writes a list back for storing a corpus. Structures implement [`Interpreter`] to
+//! execute operations; [`differential()`] runs the same operations on a reference model and on a structure and reports
+//! the first step where their outcomes differ or the structure's invariants break. [`check_all()`] does so for every
+//! structure of the crate that can hold `u8` keys or `u32` elements:
+//! ```
+//! # use strctr::ops::{self, Op, Outcome};
+//! let ops = ops::decode(b"\x00\x07\x2a\x00\x00\x00\x02\x07\x01\x07\x06");
+//! assert_eq!(ops[..2], [Op::Insert { key: 7, value: 42 }, Op::Query { key: 7 }]);
+//! assert_eq!(ops::decode(&ops::encode(&ops)), ops);
+//!
+//! let mut map = strctr::rbtree::RBTreeMap::new();
+//! let outcomes = ops::run(&mut map, &ops);
+//! assert_eq!(outcomes[1..], [Outcome::Value(Some(42)), Outcome::Value(Some(42)), Outcome::Entries(vec![])]);
+//! assert_eq!(ops::check_all(&ops), Ok(()));
+//! ```
+//!
+//! With the `serde` feature enabled, [`Op`] and [`Outcome`] implement `Serialize` and `Deserialize`, for storing test
+//! cases in a readable format.
+
+use std::any::type_name;
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::bst::{Avl, BalancePolicy, BalancedTree, RedBlack, Scapegoat, Unbalanced};
+use crate::hybrid_vec::HybridVec;
+use crate::lru::LruCache;
+use crate::rbtree::RBTreeMap;
+use crate::skiplist::SkipListMap;
+use crate::sorted_vec::SortedMap;
+use crate::trie::Trie;
+use crate::versioned::VersionedStore;
+
+/// A single operation. Maps execute the keyed operations and sequences the positional ones; both iterate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Op {
+    /// Inserts or replaces the key's value.
+    Insert { key: u8, value: u32 },
+    /// Removes the key.
+    Remove { key: u8 },
+    /// Looks up the key.
+    Query { key: u8 },
+    /// Appends an element.
+    Push { value: u32 },
+    /// Removes the last element.
+    Pop,
+    /// Looks up the element at the index.
+    QueryAt { index: u8 },
+    /// Lists all entries or elements.
+    Iterate,
+}
+
+/// Number of [`Op`] variants, which the tag byte is reduced modulo.
+const TAGS: u8 = 7;
+
+/// The result of executing an [`Op`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Outcome {
+    /// The value replaced, removed, found or popped, if any.
+    Value(Option<u32>),
+    /// Nothing to report, after a push.
+    Done,
+    /// All entries of a map, in ascending key order.
+    Entries(Vec<(u8, u32)>),
+    /// All elements of a sequence, in order.
+    Elements(Vec<u32>),
+    /// The structure does not support the operation.
+    Skipped,
+}
+
+impl Outcome {
+    /// Collects map entries in ascending key order, whatever order the map yields them in.
+    fn entries<'a>(iter: impl Iterator<Item = (&'a u8, &'a u32)>) -> Self {
+        let mut entries: Vec<(u8, u32)> = iter.map(|(&k, &v)| (k, v)).collect();
+        entries.sort_unstable();
+        Outcome::Entries(entries)
+    }
+}
+
+/// Errors reported by [`differential()`] and [`check_all()`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum OpsError {
+    /// The structure returned a different outcome than the reference model.
+    Mismatch {
+        /// Type name of the structure.
+        target: &'static str,
+        /// Index of the operation.
+        step: usize,
+        expected: Outcome,
+        actual: Outcome,
+    },
+    /// The structure's invariants do not hold after the operation.
+    Invariant {
+        /// Type name of the structure.
+        target: &'static str,
+        /// Index of the operation.
+        step: usize,
+    },
+}
+
+/// A structure that can execute [`Op`]s.
+pub trait Interpreter {
+    /// Executes the operation, returning [`Outcome::Skipped`] for operations the structure does not support.
+    fn apply(&mut self, op: &Op) -> Outcome;
+
+    /// Returns whether the structure's internal invariants hold. Defaults to `true` for structures without checks.
+    fn is_valid(&self) -> bool {
+        true
+    }
+}
+
+/// Decodes a list of operations from arbitrary bytes.
+///
+/// Every operation starts with a tag byte, taken modulo the number of variants in the order [`Op`] declares them,
+/// followed by its fields: keys and indices take one byte, values four in little-endian order. An operation cut short
+/// by the end of the input is dropped.
+pub fn decode(mut bytes: &[u8]) -> Vec<Op> {
+    let mut ops = Vec::new();
+    while let Some((&tag, rest)) = bytes.split_first() {
+        let need = match tag % TAGS {
+            0 => 5,
+            1 | 2 | 5 => 1,
+            3 => 4,
+            _ => 0,
+        };
+        let Some(fields) = rest.get(..need) else {
+            break;
+        };
+        let value = |at: usize| u32::from_le_bytes(fields[at..at + 4].try_into().unwrap());
+        ops.push(match tag % TAGS {
+            0 => Op::Insert {
+                key: fields[0],
+                value: value(1),
+            },
+            1 => Op::Remove { key: fields[0] },
+            2 => Op::Query { key: fields[0] },
+            3 => Op::Push { value: value(0) },
+            4 => Op::Pop,
+            5 => Op::QueryAt { index: fields[0] },
+            _ => Op::Iterate,
+        });
+        bytes = &rest[need..];
+    }
+    ops
+}
+
+/// Encodes operations such that [`decode()`] returns them unchanged.
+pub fn encode(ops: &[Op]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for op in ops {
+        match *op {
+            Op::Insert { key, value } => {
+                bytes.extend([0, key]);
+                bytes.extend(value.to_le_bytes());
+            }
+            Op::Remove { key } => bytes.extend([1, key]),
+            Op::Query { key } => bytes.extend([2, key]),
+            Op::Push { value } => {
+                bytes.push(3);
+                bytes.extend(value.to_le_bytes());
+            }
+            Op::Pop => bytes.push(4),
+            Op::QueryAt { index } => bytes.extend([5, index]),
+            Op::Iterate => bytes.push(6),
+        }
+    }
+    bytes
+}
+
+/// Executes the operations in order and returns their outcomes.
+pub fn run<I: Interpreter + ?Sized>(target: &mut I, ops: &[Op]) -> Vec<Outcome> {
+    ops.iter().map(|op| target.apply(op)).collect()
+}
+
+/// Executes the operations on the reference model and the target side by side, checking the target's invariants after
+/// every step. Returns the first divergence.
+/// ```
+/// # use std::collections::BTreeMap;
+/// # use strctr::bst::{BalancedTree, Scapegoat};
+/// # use strctr::ops::{self, Op};
+/// let ops: Vec<Op> = (0..100).map(|key| Op::Insert { key, value: 0 }).chain([Op::Iterate]).collect();
+/// let mut tree = BalancedTree::new(Scapegoat::default());
+/// assert_eq!(ops::differential(&mut BTreeMap::new(), &mut tree, &ops), Ok(()));
+/// ```
+pub fn differential<R, T>(reference: &mut R, target: &mut T, ops: &[Op]) -> Result<(), OpsError>
+where
+    R: Interpreter + ?Sized,
+    T: Interpreter + ?Sized,
+{
+    for (step, op) in ops.iter().enumerate() {
+        let expected = reference.apply(op);
+        let actual = target.apply(op);
+        if expected != actual {
+            return Err(OpsError::Mismatch {
+                target: type_name::<T>(),
+                step,
+                expected,
+                actual,
+            });
+        }
+        if !target.is_valid() {
+            return Err(OpsError::Invariant {
+                target: type_name::<T>(),
+                step,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Runs [`differential()`] for every map of the crate against [`std::collections::BTreeMap`], and every sequence against
+/// [`Vec`]. Meant as the body of a fuzz target.
+pub fn check_all(ops: &[Op]) -> Result<(), OpsError> {
+    fn map<T: Interpreter>(mut target: T, ops: &[Op]) -> Result<(), OpsError> {
+        differential(&mut BTreeMap::new(), &mut target, ops)
+    }
+    fn sequence<T: Interpreter>(mut target: T, ops: &[Op]) -> Result<(), OpsError> {
+        differential(&mut Vec::new(), &mut target, ops)
+    }
+
+    map(RBTreeMap::new(), ops)?;
+    map(crate::btree::BTreeMap::<u8, u32, 2>::new(), ops)?;
+    map(crate::btree::BTreeMap::<u8, u32>::new(), ops)?;
+    map(SkipListMap::new(), ops)?;
+    map(BalancedTree::new(Avl), ops)?;
+    map(BalancedTree::new(RedBlack), ops)?;
+    map(BalancedTree::new(Scapegoat::default()), ops)?;
+    map(BalancedTree::new(Unbalanced), ops)?;
+    // A capacity covering every u8 key means nothing is ever evicted.
+    map(LruCache::new(256), ops)?;
+    map(SortedMap::new(), ops)?;
+    map(crate::im::HashMap::new(), ops)?;
+    map(Trie::new(), ops)?;
+    map(VersionedStore::new(), ops)?;
+    sequence(HybridVec::<u32, 4>::new(), ops)
+}
+
+/// Implements [`Interpreter`] for a map with the usual `insert`, `remove`, `get` and `iter` methods.
+macro_rules! map_interpreter {
+    ([$($generics:tt)*] $t:ty $(, |$map:ident| $valid:expr)?) => {
+        impl<$($generics)*> Interpreter for $t {
+            fn apply(&mut self, op: &Op) -> Outcome {
+                match *op {
+                    Op::Insert { key, value } => Outcome::Value(self.insert(key, value)),
+                    Op::Remove { key } => Outcome::Value(self.remove(&key)),
+                    Op::Query { key } => Outcome::Value(self.get(&key).copied()),
+                    Op::Iterate => Outcome::entries(self.iter()),
+                    _ => Outcome::Skipped,
+                }
+            }
+
+            $(
+                fn is_valid(&self) -> bool {
+                    let $map = self;
+                    $valid
+                }
+            )?
+        }
+    };
+}
+
+map_interpreter!([] BTreeMap<u8, u32>);
+map_interpreter!([] RBTreeMap<u8, u32>, |map| map.check_invariants().is_ok());
+map_interpreter!([const B: usize] crate::btree::BTreeMap<u8, u32, B>, |map| map.check_invariants().is_ok());
+map_interpreter!([] SkipListMap<u8, u32>);
+map_interpreter!([P: BalancePolicy] BalancedTree<u8, u32, P>, |map| map.check_invariants().is_ok());
+map_interpreter!([] LruCache<u8, u32>);
+map_interpreter!([] SortedMap<u8, u32>);
+
+impl Interpreter for crate::im::HashMap<u8, u32> {
+    fn apply(&mut self, op: &Op) -> Outcome {
+        match *op {
+            Op::Insert { key, value } => {
+                let old = self.get(&key).copied();
+                *self = self.insert(key, value);
+                Outcome::Value(old)
+            }
+            Op::Remove { key } => {
+                let old = self.get(&key).copied();
+                *self = self.remove(&key);
+                Outcome::Value(old)
+            }
+            Op::Query { key } => Outcome::Value(self.get(&key).copied()),
+            Op::Iterate => Outcome::entries(self.iter()),
+            _ => Outcome::Skipped,
+        }
+    }
+}
+
+impl Interpreter for Trie<u32> {
+    /// Keys are spelled in decimal, so some are prefixes of others.
+    fn apply(&mut self, op: &Op) -> Outcome {
+        match *op {
+            Op::Insert { key, value } => Outcome::Value(self.insert(&key.to_string(), value)),
+            Op::Remove { key } => Outcome::Value(self.remove(&key.to_string())),
+            Op::Query { key } => Outcome::Value(self.get(&key.to_string()).copied()),
+            Op::Iterate => {
+                let entries: Vec<(u8, u32)> =
+                    self.iter().map(|(k, &v)| (k.parse().unwrap(), v)).collect();
+                Outcome::entries(entries.iter().map(|(k, v)| (k, v)))
+            }
+            _ => Outcome::Skipped,
+        }
+    }
+}
+
+impl Interpreter for VersionedStore<u32> {
+    fn apply(&mut self, op: &Op) -> Outcome {
+        match *op {
+            Op::Insert { key, value } => Outcome::Value(self.insert([key], value)),
+            Op::Remove { key } => Outcome::Value(self.remove([key])),
+            Op::Query { key } => Outcome::Value(self.get([key]).copied()),
+            Op::Iterate => Outcome::Entries(self.iter().map(|(k, &v)| (k[0], v)).collect()),
+            _ => Outcome::Skipped,
+        }
+    }
+}
+
+impl Interpreter for Vec<u32> {
+    fn apply(&mut self, op: &Op) -> Outcome {
+        match *op {
+            Op::Push { value } => {
+                self.push(value);
+                Outcome::Done
+            }
+            Op::Pop => Outcome::Value(self.pop()),
+            Op::QueryAt { index } => Outcome::Value(self.get(usize::from(index)).copied()),
+            Op::Iterate => Outcome::Elements(self.clone()),
+            _ => Outcome::Skipped,
+        }
+    }
+}
+
+impl<const N: usize> Interpreter for HybridVec<u32, N> {
+    fn apply(&mut self, op: &Op) -> Outcome {
+        match *op {
+            Op::Push { value } => {
+                self.push(value);
+                Outcome::Done
+            }
+            Op::Pop => Outcome::Value(self.pop()),
+            Op::QueryAt { index } => {
+                Outcome::Value(self.as_slice().get(usize::from(index)).copied())
+            }
+            Op::Iterate => Outcome::Elements(self.as_slice().to_vec()),
+            _ => Outcome::Skipped,
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Fixed-size pool of reusable objects with checkout and return.
+//!
+//! The pool creates all its objects up front. [get()](`Pool::get()`) checks one out as a [`PoolGuard`], which returns
+//! it to the pool when dropped, so a hot loop reuses the same few objects instead of allocating and freeing one per
+//! iteration. Objects come back in whatever state the caller left them: a pool of buffers should clear them after
+//! checkout. Unlike a [`BufferPool`](`crate::buffer_pool::BufferPool`), the pool never creates objects on demand, so
+//! it also bounds how many are in use at once.
+
+use std::fmt;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::sync::PoisonError;
+
+use crate::sync::{Condvar, Mutex, MutexGuard};
+
+/// A thread-safe pool of a fixed number of objects.
+/// ```
+/// # use strctr::pool::Pool;
+/// let pool = Pool::new(2, || Vec::<u8>::with_capacity(4096));
+/// for line in ["GET /", "HEAD /index.html", "GET /favicon.ico"] {
+///     let mut buf = pool.get();
+///     buf.clear();
+///     buf.extend_from_slice(line.as_bytes());
+///     assert_eq!(buf.capacity(), 4096);
+/// }
+/// assert_eq!(pool.available(), 2);
+/// ```
+pub struct Pool<T> {
+    idle: Mutex<Vec<T>>,
+    returned: Condvar,
+    capacity: usize,
+}
+
+impl<T> Pool<T> {
+    /// Constructs a new pool of `size` objects, created by calling `init` once for each.
+    pub fn new(size: usize, init: impl FnMut() -> T) -> Self {
+        Self::from_objects(std::iter::repeat_with(init).take(size).collect())
+    }
+
+    /// Constructs a new pool of the given objects.
+    pub fn from_objects(objects: Vec<T>) -> Self {
+        Self {
+            capacity: objects.len(),
+            idle: Mutex::new(objects),
+            returned: Condvar::new(),
+        }
+    }
+
+    /// Returns the number of objects in the pool, checked out or not.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of objects that are not checked out.
+    pub fn available(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Checks out an object, or returns `None` if all are checked out.
+    /// ```
+    /// # use strctr::pool::Pool;
+    /// let pool = Pool::new(1, String::new);
+    /// let first = pool.try_get().unwrap();
+    /// assert!(pool.try_get().is_none());
+    /// drop(first);
+    /// assert!(pool.try_get().is_some());
+    /// ```
+    pub fn try_get(&self) -> Option<PoolGuard<'_, T>> {
+        let object = self.lock().pop()?;
+        Some(self.guard(object))
+    }
+
+    /// Checks out an object, blocking until one is returned if all are checked out.
+    ///
+    /// Blocks forever if the pool is empty, or if the calling thread holds all its objects.
+    /// ```
+    /// # use strctr::pool::Pool;
+    /// let connections = Pool::new(2, || 0u32);
+    /// std::thread::scope(|s| {
+    ///     for _ in 0..8 {
+    ///         s.spawn(|| *connections.get() += 1);
+    ///     }
+    /// });
+    /// let uses: u32 = connections.into_objects().iter().sum();
+    /// assert_eq!(uses, 8);
+    /// ```
+    pub fn get(&self) -> PoolGuard<'_, T> {
+        let mut idle = self.lock();
+        loop {
+            if let Some(object) = idle.pop() {
+                return self.guard(object);
+            }
+            idle = self
+                .returned
+                .wait(idle)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Consumes the pool and returns its objects.
+    ///
+    /// No guards can be alive at this point, so all objects are returned.
+    pub fn into_objects(self) -> Vec<T> {
+        self.idle
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn guard(&self, object: T) -> PoolGuard<'_, T> {
+        PoolGuard {
+            pool: self,
+            object: ManuallyDrop::new(object),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<T>> {
+        // The list of idle objects stays consistent even if a thread panicked while holding the lock.
+        self.idle.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T> fmt::Debug for Pool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pool")
+            .field("capacity", &self.capacity)
+            .field("available", &self.available())
+            .finish()
+    }
+}
+
+/// An object checked out of a [`Pool`], created by [get()](`Pool::get()`) and [try_get()](`Pool::try_get()`). It
+/// dereferences to the object and returns it to the pool when dropped.
+pub struct PoolGuard<'a, T> {
+    pool: &'a Pool<T>,
+    object: ManuallyDrop<T>,
+}
+
+impl<T> Deref for PoolGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.object
+    }
+}
+
+impl<T> DerefMut for PoolGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.object
+    }
+}
+
+impl<T> Drop for PoolGuard<'_, T> {
+    fn drop(&mut self) {
+        // The object is taken exactly once, here, and the guard is not used afterwards.
+        let object = unsafe { ManuallyDrop::take(&mut self.object) };
+        self.pool.lock().push(object);
+        self.pool.returned.notify_one();
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PoolGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.object, f)
+    }
+}
@@ -0,0 +1,164 @@
+//! Static priority search tree for three-sided range queries over 2D points: every point with `x` inside a range
+//! and `y` at or below a bound is reported in `O(log n + k)`.
+//!
+//! Each node stores the point with the smallest `y` of its subtree, like a heap, while the remaining points are
+//! split between the children by their median `x`, like a search tree.
+
+use std::ops::{Bound, RangeBounds};
+
+struct Node<X, Y, V> {
+    x: X,
+    y: Y,
+    value: V,
+    /// Every point in the left subtree has `x <= split`, every point in the right subtree has `x >= split`.
+    split: Option<X>,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+/// A priority search tree over points `(x, y)` carrying a value each. The tree is built once from all of its points.
+pub struct PrioritySearchTree<X, Y, V> {
+    nodes: Vec<Node<X, Y, V>>,
+    root: Option<usize>,
+}
+
+impl<X: Ord + Clone, Y: Ord, V> PrioritySearchTree<X, Y, V> {
+    /// Builds a tree out of the points in `O(n log n)`.
+    /// ```
+    /// # use strctr::priority_search_tree::PrioritySearchTree;
+    /// let t = PrioritySearchTree::new(vec![((1, 5), 'a'), ((3, 2), 'b')]);
+    /// assert_eq!(t.len(), 2);
+    /// ```
+    pub fn new(mut points: Vec<((X, Y), V)>) -> Self {
+        points.sort_by(|a, b| a.0 .0.cmp(&b.0 .0));
+        let mut tree = Self {
+            nodes: Vec::with_capacity(points.len()),
+            root: None,
+        };
+        tree.root = tree.build(points);
+        tree
+    }
+
+    fn build(&mut self, mut points: Vec<((X, Y), V)>) -> Option<usize> {
+        let min = (0..points.len()).min_by(|&a, &b| points[a].0 .1.cmp(&points[b].0 .1))?;
+        let ((x, y), value) = points.remove(min);
+
+        let right = points.split_off(points.len().div_ceil(2));
+        let split = points.last().map(|p| p.0 .0.clone());
+        let left = self.build(points);
+        let right = self.build(right);
+
+        self.nodes.push(Node {
+            x,
+            y,
+            value,
+            split,
+            left,
+            right,
+        });
+        Some(self.nodes.len() - 1)
+    }
+
+    /// Returns every point with `x` inside the range and `y <= y_max`, in no particular order.
+    /// ```
+    /// # use strctr::priority_search_tree::PrioritySearchTree;
+    /// let t = PrioritySearchTree::new(vec![
+    ///     ((1, 1), "a"),
+    ///     ((2, 9), "b"),
+    ///     ((4, 3), "c"),
+    ///     ((6, 2), "d"),
+    ///     ((9, 1), "e"),
+    /// ]);
+    /// let mut found: Vec<_> = t.query(2..=6, &3).into_iter().map(|(_, _, v)| *v).collect();
+    /// found.sort();
+    /// assert_eq!(found, vec!["c", "d"]);
+    /// ```
+    pub fn query<R: RangeBounds<X>>(&self, x_range: R, y_max: &Y) -> Vec<(&X, &Y, &V)> {
+        let mut out = Vec::new();
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(i) = stack.pop() {
+            let n = &self.nodes[i];
+            if n.y > *y_max {
+                continue;
+            }
+            if x_range.contains(&n.x) {
+                out.push((&n.x, &n.y, &n.value));
+            }
+            push_children(n, &x_range, &mut stack);
+        }
+        out
+    }
+
+    /// Returns the point with the smallest `y` among those with `x` inside the range.
+    /// ```
+    /// # use strctr::priority_search_tree::PrioritySearchTree;
+    /// let t = PrioritySearchTree::new(vec![((1, 1), ()), ((2, 9), ()), ((4, 3), ()), ((6, 2), ())]);
+    /// assert_eq!(t.min_y(2..5).map(|(x, y, _)| (*x, *y)), Some((4, 3)));
+    /// assert!(t.min_y(7..).is_none());
+    /// ```
+    pub fn min_y<R: RangeBounds<X>>(&self, x_range: R) -> Option<(&X, &Y, &V)> {
+        let mut best: Option<&Node<X, Y, V>> = None;
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(i) = stack.pop() {
+            let n = &self.nodes[i];
+            // Everything below this node has a larger y than the node itself.
+            if best.is_some_and(|b| b.y <= n.y) {
+                continue;
+            }
+            if x_range.contains(&n.x) {
+                best = Some(n);
+                continue;
+            }
+            push_children(n, &x_range, &mut stack);
+        }
+        best.map(|n| (&n.x, &n.y, &n.value))
+    }
+}
+
+/// Pushes the children of the node whose `x` span can overlap the range.
+fn push_children<X: Ord, Y, V, R: RangeBounds<X>>(
+    n: &Node<X, Y, V>,
+    x_range: &R,
+    stack: &mut Vec<usize>,
+) {
+    let Some(split) = &n.split else {
+        return;
+    };
+    let go_left = match x_range.start_bound() {
+        Bound::Included(a) | Bound::Excluded(a) => a <= split,
+        Bound::Unbounded => true,
+    };
+    let go_right = match x_range.end_bound() {
+        Bound::Included(b) | Bound::Excluded(b) => b >= split,
+        Bound::Unbounded => true,
+    };
+    if go_left {
+        stack.extend(n.left);
+    }
+    if go_right {
+        stack.extend(n.right);
+    }
+}
+
+impl<X, Y, V> PrioritySearchTree<X, Y, V> {
+    /// Returns the number of points in the tree.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns whether the tree has no points.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns an iterator over all points, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&X, &Y, &V)> {
+        self.nodes.iter().map(|n| (&n.x, &n.y, &n.value))
+    }
+}
+
+impl<X: Ord + Clone, Y: Ord, V> FromIterator<((X, Y), V)> for PrioritySearchTree<X, Y, V> {
+    fn from_iter<I: IntoIterator<Item = ((X, Y), V)>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
@@ -0,0 +1,302 @@
+//! Python bindings for a few of the crate's structures, built with the `python` feature.
+//!
+//! The classes here wrap [`SortedVec`], [`Trie`] and [`DisjointSet`] behind [PyO3](https://docs.rs/pyo3) exports, so
+//! scripts and notebooks can use them directly. They follow Python conventions: sizes come from `len()`, membership
+//! from `in`, missing keys raise `KeyError` and bad positions `IndexError`. [`strctr()`] is the module initializer; an
+//! extension built from a `cdylib` crate that depends on this one with the `python` feature, enables PyO3's
+//! `extension-module` feature and re-exports it (`pub use strctr::python::strctr;`) imports as `strctr`:
+//! ```python
+//! from strctr import SortedList, Trie, UnionFind
+//!
+//! scores = SortedList([70, 95, 82])
+//! scores.add(88)
+//! list(scores)  # [70, 82, 88, 95]
+//! scores.irange(80, 90)  # [82, 88]
+//!
+//! phone = Trie()
+//! phone["alice"] = "555-0100"
+//! phone["alan"] = "555-0199"
+//! phone.keys("al")  # ['alan', 'alice']
+//!
+//! friends = UnionFind(4)
+//! friends.union(0, 1)
+//! friends.connected(1, 0)  # True
+//! friends.sets()  # [[0, 1], [2], [3]]
+//! ```
+//! Elements of a `SortedList` are integers, so ordering them never calls back into Python.
+
+use pyo3::exceptions::{PyIndexError, PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyIterator, PyList};
+
+use crate::disjoint_set::DisjointSet;
+use crate::sorted_vec::SortedVec;
+use crate::trie::Trie;
+
+/// Registers the classes in the `strctr` Python module.
+#[pymodule]
+pub fn strctr(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySortedList>()?;
+    m.add_class::<PyTrie>()?;
+    m.add_class::<PyUnionFind>()?;
+    Ok(())
+}
+
+/// A list of integers kept in ascending order, wrapping [`SortedVec`].
+#[pyclass(name = "SortedList", module = "strctr")]
+#[derive(Default)]
+pub struct PySortedList {
+    values: SortedVec<i64>,
+}
+
+#[pymethods]
+impl PySortedList {
+    /// Constructs a new list of the given integers, or an empty one.
+    #[new]
+    #[pyo3(signature = (values = None))]
+    fn new(values: Option<Vec<i64>>) -> Self {
+        Self {
+            values: values.map(SortedVec::from).unwrap_or_default(),
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        self.values.len()
+    }
+
+    fn __contains__(&self, value: i64) -> bool {
+        self.values.contains(&value)
+    }
+
+    /// Returns the integer at the position, counting from the end if it is negative.
+    fn __getitem__(&self, index: isize) -> PyResult<i64> {
+        let position = index_from(index, self.values.len())?;
+        Ok(self.values.as_slice()[position])
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        PyList::new(py, self.values.as_slice())?.try_iter()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SortedList({:?})", self.values.as_slice())
+    }
+
+    /// Adds the integer after any equal ones.
+    fn add(&mut self, value: i64) {
+        self.values.insert(value);
+    }
+
+    /// Removes one occurrence of the integer. Raises `ValueError` if it is absent.
+    fn remove(&mut self, value: i64) -> PyResult<()> {
+        match self.values.remove(&value) {
+            Some(_) => Ok(()),
+            None => Err(PyValueError::new_err(format!("{} is not in list", value))),
+        }
+    }
+
+    /// Removes one occurrence of the integer, if present.
+    fn discard(&mut self, value: i64) {
+        self.values.remove(&value);
+    }
+
+    /// Returns the position of the first occurrence of the integer. Raises `ValueError` if it is absent.
+    fn index(&self, value: i64) -> PyResult<usize> {
+        self.values
+            .position(&value)
+            .ok_or_else(|| PyValueError::new_err(format!("{} is not in list", value)))
+    }
+
+    /// Returns the integers from `low` up to and including `high`.
+    fn irange(&self, low: i64, high: i64) -> Vec<i64> {
+        self.values.range(low..=high).to_vec()
+    }
+
+    /// Removes all integers.
+    fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+/// A map from strings to Python objects with prefix lookups, wrapping [`Trie`].
+#[pyclass(name = "Trie", module = "strctr")]
+#[derive(Default)]
+pub struct PyTrie {
+    entries: Trie<Py<PyAny>>,
+}
+
+#[pymethods]
+impl PyTrie {
+    /// Constructs a new, empty trie.
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn __len__(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn __contains__(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    fn __getitem__(&self, py: Python<'_>, key: &str) -> PyResult<Py<PyAny>> {
+        match self.entries.get(key) {
+            Some(value) => Ok(value.clone_ref(py)),
+            None => Err(PyKeyError::new_err(key.to_string())),
+        }
+    }
+
+    fn __setitem__(&mut self, key: &str, value: Py<PyAny>) {
+        self.entries.insert(key, value);
+    }
+
+    fn __delitem__(&mut self, key: &str) -> PyResult<()> {
+        match self.entries.remove(key) {
+            Some(_) => Ok(()),
+            None => Err(PyKeyError::new_err(key.to_string())),
+        }
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        let keys: Vec<String> = self.entries.iter().map(|(key, _)| key).collect();
+        PyList::new(py, keys)?.try_iter()
+    }
+
+    /// Returns the value of the key, or `default` if it is absent.
+    #[pyo3(signature = (key, default = None))]
+    fn get(&self, py: Python<'_>, key: &str, default: Option<Py<PyAny>>) -> Option<Py<PyAny>> {
+        match self.entries.get(key) {
+            Some(value) => Some(value.clone_ref(py)),
+            None => default,
+        }
+    }
+
+    /// Returns whether any key starts with the prefix.
+    fn has_prefix(&self, prefix: &str) -> bool {
+        self.entries.starts_with(prefix)
+    }
+
+    /// Returns the keys starting with the prefix, in alphabetical order.
+    #[pyo3(signature = (prefix = ""))]
+    fn keys(&self, prefix: &str) -> Vec<String> {
+        self.entries
+            .iter_prefix(prefix)
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// Returns the `(key, value)` pairs whose keys start with the prefix, in alphabetical order of the keys.
+    #[pyo3(signature = (prefix = ""))]
+    fn items(&self, py: Python<'_>, prefix: &str) -> Vec<(String, Py<PyAny>)> {
+        self.entries
+            .iter_prefix(prefix)
+            .map(|(key, value)| (key, value.clone_ref(py)))
+            .collect()
+    }
+
+    /// Removes all entries.
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Disjoint sets over the elements `0..len()`, wrapping [`DisjointSet`].
+#[pyclass(name = "UnionFind", module = "strctr")]
+pub struct PyUnionFind {
+    sets: DisjointSet,
+}
+
+#[pymethods]
+impl PyUnionFind {
+    /// Constructs `n` singleton sets, numbered `0..n`.
+    #[new]
+    #[pyo3(signature = (n = 0))]
+    fn new(n: usize) -> Self {
+        Self {
+            sets: DisjointSet::with_sets(n),
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        self.sets.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "UnionFind(len={}, count={})",
+            self.sets.len(),
+            self.sets.count()
+        )
+    }
+
+    /// The number of disjoint sets.
+    #[getter]
+    fn count(&self) -> usize {
+        self.sets.count()
+    }
+
+    /// Adds a new element in a set of its own and returns it.
+    fn add(&mut self) -> usize {
+        self.sets.make_set()
+    }
+
+    /// Returns the representative of the set containing the element.
+    fn find(&mut self, x: usize) -> PyResult<usize> {
+        self.check(x)?;
+        Ok(self.sets.find(x))
+    }
+
+    /// Merges the sets containing the two elements. Returns `False` if they already were in the same set.
+    fn union(&mut self, a: usize, b: usize) -> PyResult<bool> {
+        self.check(a)?;
+        self.check(b)?;
+        Ok(self.sets.union(a, b))
+    }
+
+    /// Returns whether the two elements are in the same set.
+    fn connected(&mut self, a: usize, b: usize) -> PyResult<bool> {
+        self.check(a)?;
+        self.check(b)?;
+        Ok(self.sets.same_set(a, b))
+    }
+
+    /// Returns the number of elements in the set containing the element.
+    fn set_size(&mut self, x: usize) -> PyResult<usize> {
+        self.check(x)?;
+        Ok(self.sets.set_size(x))
+    }
+
+    /// Returns every set as a list of its elements, ordered by their smallest element.
+    fn sets(&mut self) -> Vec<Vec<usize>> {
+        self.sets.sets()
+    }
+}
+
+impl PyUnionFind {
+    /// [`DisjointSet`] panics on elements out of bounds, which Python code should see as an `IndexError` instead.
+    fn check(&self, x: usize) -> PyResult<()> {
+        if x < self.sets.len() {
+            Ok(())
+        } else {
+            Err(PyIndexError::new_err(format!(
+                "Element {} is out of bounds for length {}",
+                x,
+                self.sets.len()
+            )))
+        }
+    }
+}
+
+/// Resolves a Python index, which counts from the end if it is negative.
+fn index_from(index: isize, len: usize) -> PyResult<usize> {
+    let position = if index < 0 {
+        len.checked_sub(index.unsigned_abs())
+    } else {
+        Some(index as usize)
+    };
+    position
+        .filter(|&p| p < len)
+        .ok_or_else(|| PyIndexError::new_err("SortedList index out of range"))
+}
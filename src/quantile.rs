@@ -0,0 +1,251 @@
+//! Approximate quantiles over a sliding window of a stream, like the p99 latency of the last minute.
+//!
+//! A [`WindowedQuantiles`] splits its window, a number of samples or a span of time, into panes. Every pane is a
+//! sketch of the samples recorded while it was current, and when a pane falls out of the window, its samples are
+//! forgotten all at once. Sketches bucket the samples logarithmically, as in DDSketch, so every quantile is estimated
+//! within a chosen relative error, whatever the distribution, in memory that grows with the logarithm of the range of
+//! samples rather than with their number.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::cache::{Clock, SystemClock};
+
+/// Bucket of a sample: its sign, then its logarithmic index, negated for negative samples so that keys sort like
+/// samples.
+type Bucket = (i8, i32);
+
+enum Window<C> {
+    Count {
+        pane_len: u64,
+        recorded: u64,
+    },
+    Time {
+        origin: Instant,
+        pane_span: Duration,
+        clock: C,
+    },
+}
+
+/// Approximate quantiles of the samples recorded over the last `n` samples or the last span of time.
+///
+/// The window moves a pane at a time: quantiles cover the current pane and the panes before it up to the number of
+/// panes, so between the whole window and the window less one pane. More panes make the window more precise, and take
+/// more memory and time per query.
+/// ```
+/// # use strctr::quantile::WindowedQuantiles;
+/// let mut latencies = WindowedQuantiles::by_count(100, 10, 0.01);
+/// for ms in 1..=1000 {
+///     latencies.record(ms as f64);
+/// }
+/// // Only the last 100 samples, 901 to 1000, are in the window.
+/// assert_eq!(latencies.count(), 100);
+/// let median = latencies.quantile(0.5).unwrap();
+/// assert!((median - 950.0).abs() <= 9.5);
+/// ```
+pub struct WindowedQuantiles<C = SystemClock> {
+    window: Window<C>,
+    /// Pane numbers and their samples per bucket, from oldest to newest.
+    panes: VecDeque<(u64, BTreeMap<Bucket, u64>)>,
+    pane_count: u64,
+    accuracy: f64,
+    /// Logarithm of the ratio between bucket bounds.
+    ln_gamma: f64,
+}
+
+impl WindowedQuantiles {
+    /// Constructs a new, empty window over the last `len` samples, split into `panes` panes, which estimates
+    /// quantiles within the relative accuracy, like `0.01` for 1%. Every pane holds `len / panes` samples, rounded
+    /// down, so the window holds at most `len` of them, and there are never more panes than samples.
+    ///
+    /// Panics if the length or the number of panes is 0, or the accuracy is not between 0 and 1.
+    pub fn by_count(len: usize, panes: usize, accuracy: f64) -> Self {
+        if len == 0 || panes == 0 {
+            panic!("InvalidCapacity: WindowedQuantiles needs at least one pane of one sample");
+        }
+        let panes = panes.min(len);
+        let window = Window::Count {
+            pane_len: (len / panes) as u64,
+            recorded: 0,
+        };
+        Self::with_window(window, panes, accuracy)
+    }
+
+    /// Constructs a new, empty window over the samples of the last span of time, split into `panes` panes of equal
+    /// spans, which estimates quantiles within the relative accuracy. It reads the [`SystemClock`].
+    ///
+    /// Panics if the span is too short to split into the panes, the number of panes is 0, or the accuracy is not
+    /// between 0 and 1.
+    pub fn by_time(window: Duration, panes: usize, accuracy: f64) -> Self {
+        Self::by_time_with_clock(window, panes, accuracy, SystemClock)
+    }
+}
+
+impl<C: Clock> WindowedQuantiles<C> {
+    /// Constructs a new, empty window over the samples of the last span of time like
+    /// [by_time()](`WindowedQuantiles::by_time()`), which reads the clock.
+    /// ```
+    /// # use std::time::Duration;
+    /// # use strctr::cache::ManualClock;
+    /// # use strctr::quantile::WindowedQuantiles;
+    /// let clock = ManualClock::new();
+    /// let mut p99 = WindowedQuantiles::by_time_with_clock(Duration::from_secs(60), 6, 0.01, clock.clone());
+    /// p99.record(2000.0);
+    /// clock.advance(Duration::from_secs(30));
+    /// p99.record(10.0);
+    /// assert!(p99.quantile(1.0).unwrap() > 1900.0);
+    ///
+    /// clock.advance(Duration::from_secs(40));
+    /// assert_eq!(p99.count(), 1);
+    /// assert!(p99.quantile(1.0).unwrap() < 11.0);
+    /// ```
+    pub fn by_time_with_clock(window: Duration, panes: usize, accuracy: f64, clock: C) -> Self {
+        let pane_span = match u32::try_from(panes) {
+            Ok(panes) if panes > 0 => window / panes,
+            _ => Duration::ZERO,
+        };
+        if pane_span.is_zero() {
+            panic!("InvalidCapacity: WindowedQuantiles needs at least one pane of a nanosecond");
+        }
+        let window = Window::Time {
+            origin: clock.now(),
+            pane_span,
+            clock,
+        };
+        Self::with_window(window, panes, accuracy)
+    }
+
+    fn with_window(window: Window<C>, panes: usize, accuracy: f64) -> Self {
+        if !(accuracy > 0.0 && accuracy < 1.0) {
+            panic!(
+                "InvalidAccuracy: Wanted a relative accuracy between 0 and 1, got {}",
+                accuracy
+            );
+        }
+        Self {
+            window,
+            panes: VecDeque::with_capacity(panes + 1),
+            pane_count: panes as u64,
+            accuracy,
+            ln_gamma: ((1.0 + accuracy) / (1.0 - accuracy)).ln(),
+        }
+    }
+
+    /// Returns the relative accuracy of the quantiles.
+    pub fn accuracy(&self) -> f64 {
+        self.accuracy
+    }
+
+    /// Records a sample.
+    ///
+    /// Panics if the sample is NaN.
+    pub fn record(&mut self, sample: f64) {
+        if sample.is_nan() {
+            panic!("InvalidValue: Cannot record NaN");
+        }
+        let pane = match &mut self.window {
+            Window::Count { pane_len, recorded } => {
+                *recorded += 1;
+                (*recorded - 1) / *pane_len
+            }
+            Window::Time { .. } => self.current_pane(),
+        };
+        while self
+            .panes
+            .front()
+            .is_some_and(|&(first, _)| first + self.pane_count <= pane)
+        {
+            self.panes.pop_front();
+        }
+        if self.panes.back().is_none_or(|&(last, _)| last != pane) {
+            self.panes.push_back((pane, BTreeMap::new()));
+        }
+        let bucket = self.bucket(sample);
+        let (_, counts) = self.panes.back_mut().expect("a pane was pushed");
+        *counts.entry(bucket).or_insert(0) += 1;
+    }
+
+    /// Returns the number of samples in the window.
+    pub fn count(&self) -> u64 {
+        self.live_panes().flat_map(|counts| counts.values()).sum()
+    }
+
+    /// Returns whether the window holds no samples.
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+
+    /// Returns the estimated sample at the quantile, between 0 and 1, of the samples in the window, or `None` if there
+    /// are none. The estimate is within the relative accuracy of a sample whose rank is the quantile's.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let mut merged = BTreeMap::new();
+        for counts in self.live_panes() {
+            for (&bucket, &n) in counts {
+                *merged.entry(bucket).or_insert(0) += n;
+            }
+        }
+        let count: u64 = merged.values().sum();
+        if count == 0 {
+            return None;
+        }
+        let rank = (q.clamp(0.0, 1.0) * (count - 1) as f64).floor() as u64;
+        let mut seen = 0;
+        for (&bucket, &n) in &merged {
+            seen += n;
+            if seen > rank {
+                return Some(self.estimate(bucket));
+            }
+        }
+        unreachable!("the rank is below the count")
+    }
+
+    /// Forgets all samples.
+    pub fn clear(&mut self) {
+        self.panes.clear();
+    }
+
+    /// Returns the number of the pane that is current now.
+    fn current_pane(&self) -> u64 {
+        match &self.window {
+            Window::Count { pane_len, recorded } => recorded.saturating_sub(1) / pane_len,
+            Window::Time {
+                origin,
+                pane_span,
+                clock,
+            } => (clock.now().duration_since(*origin).as_nanos() / pane_span.as_nanos()) as u64,
+        }
+    }
+
+    /// Returns the panes still in the window, which [record()](`Self::record()`) may not have dropped yet.
+    fn live_panes(&self) -> impl Iterator<Item = &BTreeMap<Bucket, u64>> {
+        let current = self.current_pane();
+        self.panes
+            .iter()
+            .filter(move |&&(pane, _)| pane + self.pane_count > current)
+            .map(|(_, counts)| counts)
+    }
+
+    fn bucket(&self, sample: f64) -> Bucket {
+        if sample.abs() < f64::MIN_POSITIVE {
+            return (0, 0);
+        }
+        let index = (sample.abs().ln() / self.ln_gamma).ceil() as i32;
+        if sample > 0.0 {
+            (1, index)
+        } else {
+            (-1, index.saturating_neg())
+        }
+    }
+
+    /// Returns the point of the bucket within the relative accuracy of all its samples.
+    fn estimate(&self, (sign, index): Bucket) -> f64 {
+        let index = if sign < 0 {
+            index.saturating_neg()
+        } else {
+            index
+        };
+        let gamma = self.ln_gamma.exp();
+        let upper = (index as f64 * self.ln_gamma).exp();
+        sign as f64 * 2.0 * upper / (gamma + 1.0)
+    }
+}
@@ -0,0 +1,724 @@
+//! Red-black tree implementation of an ordered map. Nodes are stored in an arena and linked by index.
+
+use std::cmp::Ordering;
+use std::io::{self, Read, Write};
+
+use crate::snapshot::{self, Codec, Snapshot, SnapshotError};
+use crate::viz::{self, Direction, Event};
+
+/// List of invariant violations that [check_invariants()](`RBTreeMap::check_invariants()`) can report.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RBTreeError {
+    /// The root of the tree is red.
+    RedRoot,
+    /// A red node has a red child.
+    RedRedEdge,
+    /// Two paths from the same node down to its leaves contain a different number of black nodes.
+    BlackHeightMismatch,
+    /// The keys are not in ascending in-order sequence.
+    Unordered,
+    /// A child does not point back to its parent.
+    BrokenParentLink,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Color {
+    Red,
+    Black,
+}
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    color: Color,
+    parent: Option<usize>,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+/// An ordered map backed by a red-black tree.
+pub struct RBTreeMap<K, V> {
+    nodes: Vec<Option<Node<K, V>>>,
+    free: Vec<usize>,
+    root: Option<usize>,
+    len: usize,
+}
+
+impl<K, V> Default for RBTreeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> RBTreeMap<K, V> {
+    /// Constructs a new, empty map.
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: None,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut m = RBTreeMap::new();
+    /// assert_eq!(m.len(), 0);
+    /// m.insert(1, "a");
+    /// assert_eq!(m.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the map contains no entries.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut m = RBTreeMap::new();
+    /// assert!(m.is_empty());
+    /// m.insert(1, "a");
+    /// assert!(!m.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes every entry from the map.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut m = RBTreeMap::new();
+    /// m.insert(1, "a");
+    /// m.clear();
+    /// assert!(m.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.root = None;
+        self.len = 0;
+    }
+
+    /// Returns the entry with the smallest key.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut m = RBTreeMap::new();
+    /// m.insert(2, "b");
+    /// m.insert(1, "a");
+    /// assert_eq!(m.first_key_value(), Some((&1, &"a")));
+    /// ```
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.root.map(|r| {
+            let n = self.node(self.minimum(r));
+            (&n.key, &n.value)
+        })
+    }
+
+    /// Returns the entry with the largest key.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut m = RBTreeMap::new();
+    /// m.insert(2, "b");
+    /// m.insert(1, "a");
+    /// assert_eq!(m.last_key_value(), Some((&2, &"b")));
+    /// ```
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.root.map(|r| {
+            let n = self.node(self.maximum(r));
+            (&n.key, &n.value)
+        })
+    }
+
+    /// Returns an iterator over the entries of the map, in ascending key order.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut m = RBTreeMap::new();
+    /// m.insert(3, "c");
+    /// m.insert(1, "a");
+    /// m.insert(2, "b");
+    /// let keys: Vec<_> = m.iter().map(|(k, _)| *k).collect();
+    /// assert_eq!(keys, vec![1, 2, 3]);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            map: self,
+            next: self.root.map(|r| self.minimum(r)),
+            remaining: self.len,
+        }
+    }
+
+    /// Returns an iterator over the keys of the map, in ascending order.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut m = RBTreeMap::new();
+    /// m.insert(2, "b");
+    /// m.insert(1, "a");
+    /// assert_eq!(m.keys().collect::<Vec<_>>(), vec![&1, &2]);
+    /// ```
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values of the map, in ascending key order.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut m = RBTreeMap::new();
+    /// m.insert(2, "b");
+    /// m.insert(1, "a");
+    /// assert_eq!(m.values().collect::<Vec<_>>(), vec![&"a", &"b"]);
+    /// ```
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    fn node(&self, i: usize) -> &Node<K, V> {
+        self.nodes[i].as_ref().expect("dangling node index")
+    }
+
+    fn node_mut(&mut self, i: usize) -> &mut Node<K, V> {
+        self.nodes[i].as_mut().expect("dangling node index")
+    }
+
+    fn color(&self, i: Option<usize>) -> Color {
+        i.map_or(Color::Black, |i| self.node(i).color)
+    }
+
+    fn set_color(&mut self, i: Option<usize>, color: Color) {
+        if let Some(i) = i {
+            self.node_mut(i).color = color;
+        }
+    }
+
+    fn parent(&self, i: usize) -> Option<usize> {
+        self.node(i).parent
+    }
+
+    fn left(&self, i: usize) -> Option<usize> {
+        self.node(i).left
+    }
+
+    fn right(&self, i: usize) -> Option<usize> {
+        self.node(i).right
+    }
+
+    fn set_parent(&mut self, i: Option<usize>, parent: Option<usize>) {
+        if let Some(i) = i {
+            self.node_mut(i).parent = parent;
+        }
+    }
+
+    fn minimum(&self, mut i: usize) -> usize {
+        while let Some(l) = self.left(i) {
+            i = l;
+        }
+        i
+    }
+
+    fn maximum(&self, mut i: usize) -> usize {
+        while let Some(r) = self.right(i) {
+            i = r;
+        }
+        i
+    }
+
+    fn successor(&self, i: usize) -> Option<usize> {
+        if let Some(r) = self.right(i) {
+            return Some(self.minimum(r));
+        }
+        let mut child = i;
+        let mut parent = self.parent(i);
+        while let Some(p) = parent {
+            if self.right(p) != Some(child) {
+                break;
+            }
+            child = p;
+            parent = self.parent(p);
+        }
+        parent
+    }
+
+    fn alloc(&mut self, node: Node<K, V>) -> usize {
+        match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = Some(node);
+                i
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    /// Replaces the parent's link to `u` with a link to `v`.
+    fn replace_child(&mut self, parent: Option<usize>, u: usize, v: Option<usize>) {
+        match parent {
+            None => self.root = v,
+            Some(p) if self.left(p) == Some(u) => self.node_mut(p).left = v,
+            Some(p) => self.node_mut(p).right = v,
+        }
+    }
+
+    fn rotate_left(&mut self, x: usize) {
+        let y = self.right(x).expect("rotate_left needs a right child");
+        viz::record(|| Event::Rotate {
+            structure: "RBTreeMap",
+            node: x,
+            child: y,
+            direction: Direction::Left,
+        });
+        let y_left = self.left(y);
+        self.node_mut(x).right = y_left;
+        self.set_parent(y_left, Some(x));
+        let x_parent = self.parent(x);
+        self.node_mut(y).parent = x_parent;
+        self.replace_child(x_parent, x, Some(y));
+        self.node_mut(y).left = Some(x);
+        self.node_mut(x).parent = Some(y);
+    }
+
+    fn rotate_right(&mut self, x: usize) {
+        let y = self.left(x).expect("rotate_right needs a left child");
+        viz::record(|| Event::Rotate {
+            structure: "RBTreeMap",
+            node: x,
+            child: y,
+            direction: Direction::Right,
+        });
+        let y_right = self.right(y);
+        self.node_mut(x).left = y_right;
+        self.set_parent(y_right, Some(x));
+        let x_parent = self.parent(x);
+        self.node_mut(y).parent = x_parent;
+        self.replace_child(x_parent, x, Some(y));
+        self.node_mut(y).right = Some(x);
+        self.node_mut(x).parent = Some(y);
+    }
+
+    fn insert_fixup(&mut self, mut z: usize) {
+        while let Some(p) = self.parent(z) {
+            if self.color(Some(p)) == Color::Black {
+                break;
+            }
+            // A red node is never the root, so the grandparent exists.
+            let g = self.parent(p).expect("red node without parent");
+            if self.left(g) == Some(p) {
+                let uncle = self.right(g);
+                if self.color(uncle) == Color::Red {
+                    self.set_color(Some(p), Color::Black);
+                    self.set_color(uncle, Color::Black);
+                    self.set_color(Some(g), Color::Red);
+                    z = g;
+                } else {
+                    if self.right(p) == Some(z) {
+                        z = p;
+                        self.rotate_left(z);
+                    }
+                    let p = self.parent(z).expect("rotated node without parent");
+                    let g = self.parent(p).expect("rotated node without grandparent");
+                    self.set_color(Some(p), Color::Black);
+                    self.set_color(Some(g), Color::Red);
+                    self.rotate_right(g);
+                }
+            } else {
+                let uncle = self.left(g);
+                if self.color(uncle) == Color::Red {
+                    self.set_color(Some(p), Color::Black);
+                    self.set_color(uncle, Color::Black);
+                    self.set_color(Some(g), Color::Red);
+                    z = g;
+                } else {
+                    if self.left(p) == Some(z) {
+                        z = p;
+                        self.rotate_right(z);
+                    }
+                    let p = self.parent(z).expect("rotated node without parent");
+                    let g = self.parent(p).expect("rotated node without grandparent");
+                    self.set_color(Some(p), Color::Black);
+                    self.set_color(Some(g), Color::Red);
+                    self.rotate_left(g);
+                }
+            }
+        }
+        self.set_color(self.root, Color::Black);
+    }
+
+    /// Unlinks node `z` from the tree, rebalancing as needed, and returns its slot to the free list.
+    fn remove_node(&mut self, z: usize) -> (K, V) {
+        let mut removed_color = self.color(Some(z));
+        let x;
+        let x_parent;
+
+        match (self.left(z), self.right(z)) {
+            (None, right) => {
+                x = right;
+                x_parent = self.parent(z);
+                self.replace_child(x_parent, z, right);
+                self.set_parent(right, x_parent);
+            }
+            (left, None) => {
+                x = left;
+                x_parent = self.parent(z);
+                self.replace_child(x_parent, z, left);
+                self.set_parent(left, x_parent);
+            }
+            (Some(z_left), Some(z_right)) => {
+                let y = self.minimum(z_right);
+                removed_color = self.color(Some(y));
+                x = self.right(y);
+                if self.parent(y) == Some(z) {
+                    x_parent = Some(y);
+                } else {
+                    x_parent = self.parent(y);
+                    self.replace_child(x_parent, y, x);
+                    self.set_parent(x, x_parent);
+                    self.node_mut(y).right = Some(z_right);
+                    self.node_mut(z_right).parent = Some(y);
+                }
+                let z_parent = self.parent(z);
+                self.replace_child(z_parent, z, Some(y));
+                self.node_mut(y).parent = z_parent;
+                self.node_mut(y).left = Some(z_left);
+                self.node_mut(z_left).parent = Some(y);
+                self.node_mut(y).color = self.node(z).color;
+            }
+        }
+
+        if removed_color == Color::Black {
+            self.remove_fixup(x, x_parent);
+        }
+
+        let node = self.nodes[z].take().expect("dangling node index");
+        self.free.push(z);
+        self.len -= 1;
+        (node.key, node.value)
+    }
+
+    fn remove_fixup(&mut self, mut x: Option<usize>, mut parent: Option<usize>) {
+        while x != self.root && self.color(x) == Color::Black {
+            // x is not the root, so it has a parent; its sibling exists because x's side is short a black node.
+            let p = parent.expect("non-root node without parent");
+            if self.left(p) == x {
+                let mut w = self.right(p).expect("missing sibling");
+                if self.color(Some(w)) == Color::Red {
+                    self.set_color(Some(w), Color::Black);
+                    self.set_color(Some(p), Color::Red);
+                    self.rotate_left(p);
+                    w = self.right(p).expect("missing sibling");
+                }
+                if self.color(self.left(w)) == Color::Black
+                    && self.color(self.right(w)) == Color::Black
+                {
+                    self.set_color(Some(w), Color::Red);
+                    x = Some(p);
+                    parent = self.parent(p);
+                } else {
+                    if self.color(self.right(w)) == Color::Black {
+                        self.set_color(self.left(w), Color::Black);
+                        self.set_color(Some(w), Color::Red);
+                        self.rotate_right(w);
+                        w = self.right(p).expect("missing sibling");
+                    }
+                    self.node_mut(w).color = self.node(p).color;
+                    self.set_color(Some(p), Color::Black);
+                    self.set_color(self.right(w), Color::Black);
+                    self.rotate_left(p);
+                    x = self.root;
+                    parent = None;
+                }
+            } else {
+                let mut w = self.left(p).expect("missing sibling");
+                if self.color(Some(w)) == Color::Red {
+                    self.set_color(Some(w), Color::Black);
+                    self.set_color(Some(p), Color::Red);
+                    self.rotate_right(p);
+                    w = self.left(p).expect("missing sibling");
+                }
+                if self.color(self.left(w)) == Color::Black
+                    && self.color(self.right(w)) == Color::Black
+                {
+                    self.set_color(Some(w), Color::Red);
+                    x = Some(p);
+                    parent = self.parent(p);
+                } else {
+                    if self.color(self.left(w)) == Color::Black {
+                        self.set_color(self.right(w), Color::Black);
+                        self.set_color(Some(w), Color::Red);
+                        self.rotate_left(w);
+                        w = self.left(p).expect("missing sibling");
+                    }
+                    self.node_mut(w).color = self.node(p).color;
+                    self.set_color(Some(p), Color::Black);
+                    self.set_color(self.left(w), Color::Black);
+                    self.rotate_right(p);
+                    x = self.root;
+                    parent = None;
+                }
+            }
+        }
+        self.set_color(x, Color::Black);
+    }
+}
+
+impl<K: Ord, V> RBTreeMap<K, V> {
+    fn find(&self, key: &K) -> Option<usize> {
+        let mut cur = self.root;
+        while let Some(i) = cur {
+            let n = self.node(i);
+            cur = match key.cmp(&n.key) {
+                Ordering::Less => n.left,
+                Ordering::Greater => n.right,
+                Ordering::Equal => return Some(i),
+            };
+        }
+        None
+    }
+
+    /// Inserts a key-value pair into the map. If the key was already present, its value is replaced and the old
+    /// value is returned.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut m = RBTreeMap::new();
+    /// assert_eq!(m.insert(1, "a"), None);
+    /// assert_eq!(m.insert(1, "b"), Some("a"));
+    /// assert_eq!(m.get(&1), Some(&"b"));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut parent = None;
+        let mut go_left = false;
+        let mut cur = self.root;
+        while let Some(i) = cur {
+            parent = Some(i);
+            let n = self.node(i);
+            match key.cmp(&n.key) {
+                Ordering::Less => {
+                    go_left = true;
+                    cur = n.left;
+                }
+                Ordering::Greater => {
+                    go_left = false;
+                    cur = n.right;
+                }
+                Ordering::Equal => {
+                    return Some(std::mem::replace(&mut self.node_mut(i).value, value));
+                }
+            }
+        }
+
+        let z = self.alloc(Node {
+            key,
+            value,
+            color: Color::Red,
+            parent,
+            left: None,
+            right: None,
+        });
+        match parent {
+            None => self.root = Some(z),
+            Some(p) if go_left => self.node_mut(p).left = Some(z),
+            Some(p) => self.node_mut(p).right = Some(z),
+        }
+        self.len += 1;
+        self.insert_fixup(z);
+        None
+    }
+
+    /// Returns a reference to the value stored under the key.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut m = RBTreeMap::new();
+    /// m.insert(1, "a");
+    /// assert_eq!(m.get(&1), Some(&"a"));
+    /// assert_eq!(m.get(&2), None);
+    /// ```
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.find(key).map(|i| &self.node(i).value)
+    }
+
+    /// Returns a mutable reference to the value stored under the key.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut m = RBTreeMap::new();
+    /// m.insert(1, 10);
+    /// *m.get_mut(&1).unwrap() += 5;
+    /// assert_eq!(m.get(&1), Some(&15));
+    /// ```
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.find(key).map(|i| &mut self.node_mut(i).value)
+    }
+
+    /// Returns whether the map contains the key.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut m = RBTreeMap::new();
+    /// m.insert(1, "a");
+    /// assert!(m.contains_key(&1));
+    /// assert!(!m.contains_key(&2));
+    /// ```
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Removes the key from the map, returning its value if it was present.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut m = RBTreeMap::new();
+    /// m.insert(1, "a");
+    /// assert_eq!(m.remove(&1), Some("a"));
+    /// assert_eq!(m.remove(&1), None);
+    /// ```
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.find(key).map(|i| self.remove_node(i).1)
+    }
+
+    /// Splits the map in two at the key. Returns a map with every entry whose key is greater than or equal to it,
+    /// leaving the smaller ones in `self`.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut m: RBTreeMap<_, _> = (0..10).map(|i| (i, i * 10)).collect();
+    /// let high = m.split_off(&6);
+    /// assert_eq!(m.keys().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5]);
+    /// assert_eq!(high.iter().collect::<Vec<_>>(), vec![(&6, &60), (&7, &70), (&8, &80), (&9, &90)]);
+    /// assert!(m.check_invariants().is_ok() && high.check_invariants().is_ok());
+    /// ```
+    pub fn split_off(&mut self, key: &K) -> Self {
+        let mut other = Self::new();
+        while let Some(root) = self.root {
+            let max = self.maximum(root);
+            if self.node(max).key < *key {
+                break;
+            }
+            let (k, v) = self.remove_node(max);
+            other.insert(k, v);
+        }
+        other
+    }
+
+    /// Moves every entry of `other` into `self`, leaving `other` empty. Values from `other` replace the ones already
+    /// stored under the same key.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut a: RBTreeMap<_, _> = [(1, "a"), (2, "b")].into_iter().collect();
+    /// let mut b: RBTreeMap<_, _> = [(2, "x"), (3, "c")].into_iter().collect();
+    /// a.append(&mut b);
+    /// assert!(b.is_empty());
+    /// assert_eq!(a.iter().collect::<Vec<_>>(), vec![(&1, &"a"), (&2, &"x"), (&3, &"c")]);
+    /// ```
+    pub fn append(&mut self, other: &mut Self) {
+        let nodes = std::mem::take(&mut other.nodes);
+        other.clear();
+        for node in nodes.into_iter().flatten() {
+            self.insert(node.key, node.value);
+        }
+    }
+
+    /// Verifies the red-black invariants: the root is black, no red node has a red child, and every path from a node
+    /// to its leaves has the same number of black nodes. It also checks key ordering and parent links. Returns the
+    /// black height of the tree.
+    ///
+    /// This is mostly useful in tests and benchmarks that want to make sure the tree stayed balanced.
+    /// ```
+    /// # use strctr::rbtree::RBTreeMap;
+    /// let mut m = RBTreeMap::new();
+    /// for i in 0..100 {
+    ///     m.insert(i, i);
+    /// }
+    /// for i in (0..100).step_by(3) {
+    ///     m.remove(&i);
+    /// }
+    /// assert!(m.check_invariants().is_ok());
+    /// ```
+    pub fn check_invariants(&self) -> Result<usize, RBTreeError> {
+        if self.color(self.root) == Color::Red {
+            return Err(RBTreeError::RedRoot);
+        }
+        let height = self.check_subtree(self.root, None)?;
+
+        let mut prev: Option<&K> = None;
+        for (k, _) in self.iter() {
+            if prev.is_some_and(|p| p >= k) {
+                return Err(RBTreeError::Unordered);
+            }
+            prev = Some(k);
+        }
+        Ok(height)
+    }
+
+    fn check_subtree(&self, i: Option<usize>, parent: Option<usize>) -> Result<usize, RBTreeError> {
+        let Some(i) = i else {
+            return Ok(1);
+        };
+        let n = self.node(i);
+        if n.parent != parent {
+            return Err(RBTreeError::BrokenParentLink);
+        }
+        if n.color == Color::Red
+            && (self.color(n.left) == Color::Red || self.color(n.right) == Color::Red)
+        {
+            return Err(RBTreeError::RedRedEdge);
+        }
+        let left = self.check_subtree(n.left, Some(i))?;
+        let right = self.check_subtree(n.right, Some(i))?;
+        if left != right {
+            return Err(RBTreeError::BlackHeightMismatch);
+        }
+        Ok(left + usize::from(n.color == Color::Black))
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for RBTreeMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (k, v) in iter {
+            map.insert(k, v);
+        }
+        map
+    }
+}
+
+impl<K: Ord + Codec, V: Codec> Snapshot for RBTreeMap<K, V> {
+    const TAG: [u8; 4] = *b"RBTM";
+
+    fn write_payload<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        snapshot::write_entries(w, self.len(), self.iter())
+    }
+
+    fn read_payload<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let mut map = Self::new();
+        snapshot::read_entries(r, |k, v| map.insert(k, v))?;
+        Ok(map)
+    }
+}
+
+/// In-order iterator over the entries of an [`RBTreeMap`].
+pub struct Iter<'a, K, V> {
+    map: &'a RBTreeMap<K, V>,
+    next: Option<usize>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let i = self.next?;
+        self.next = self.map.successor(i);
+        self.remaining -= 1;
+        let n = self.map.node(i);
+        Some((&n.key, &n.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a RBTreeMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
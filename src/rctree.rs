@@ -0,0 +1,406 @@
+//! Mutable tree built from reference-counted nodes. Parents own their children through [`Rc`], while children
+//! point back to their parent through [`Weak`], so dropping a subtree's last handle frees it.
+
+use std::cell::{Ref, RefCell, RefMut};
+use std::rc::{Rc, Weak};
+
+/// List of errors that could occur when restructuring a tree.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RcTreeError {
+    /// The operation would make a node its own ancestor.
+    Cycle,
+}
+
+type Link<T> = Rc<RefCell<NodeData<T>>>;
+type WeakLink<T> = Weak<RefCell<NodeData<T>>>;
+
+struct NodeData<T> {
+    value: T,
+    parent: Option<WeakLink<T>>,
+    children: Vec<Link<T>>,
+}
+
+impl<T> Drop for NodeData<T> {
+    /// Frees the subtree iteratively so that dropping very deep trees cannot overflow the stack. Children that are
+    /// still referenced elsewhere survive as roots of their own trees.
+    fn drop(&mut self) {
+        let mut stack = std::mem::take(&mut self.children);
+        while let Some(child) = stack.pop() {
+            if let Ok(cell) = Rc::try_unwrap(child) {
+                stack.append(&mut cell.into_inner().children);
+            }
+        }
+    }
+}
+
+/// A strong handle to a node of a tree. Cloning the handle does not clone the node.
+pub struct Node<T>(Link<T>);
+
+/// A weak handle to a node, which does not keep the node alive.
+pub struct WeakNode<T>(WeakLink<T>);
+
+impl<T> Clone for Node<T> {
+    fn clone(&self) -> Self {
+        Node(Rc::clone(&self.0))
+    }
+}
+
+impl<T> Clone for WeakNode<T> {
+    fn clone(&self) -> Self {
+        WeakNode(Weak::clone(&self.0))
+    }
+}
+
+impl<T> WeakNode<T> {
+    /// Returns a strong handle to the node, if it is still alive.
+    /// ```
+    /// # use strctr::rctree::Node;
+    /// let node = Node::new(1);
+    /// let weak = node.downgrade();
+    /// assert!(weak.upgrade().is_some());
+    /// drop(node);
+    /// assert!(weak.upgrade().is_none());
+    /// ```
+    pub fn upgrade(&self) -> Option<Node<T>> {
+        self.0.upgrade().map(Node)
+    }
+}
+
+impl<T> Node<T> {
+    /// Constructs a new node without parent or children.
+    pub fn new(value: T) -> Self {
+        Node(Rc::new(RefCell::new(NodeData {
+            value,
+            parent: None,
+            children: Vec::new(),
+        })))
+    }
+
+    /// Returns a weak handle to the node.
+    pub fn downgrade(&self) -> WeakNode<T> {
+        WeakNode(Rc::downgrade(&self.0))
+    }
+
+    /// Returns whether the two handles point to the same node.
+    /// ```
+    /// # use strctr::rctree::Node;
+    /// let a = Node::new(1);
+    /// let b = Node::new(1);
+    /// assert!(a.ptr_eq(&a.clone()));
+    /// assert!(!a.ptr_eq(&b));
+    /// ```
+    pub fn ptr_eq(&self, other: &Node<T>) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Borrows the value of the node.
+    ///
+    /// Panics if the value is currently mutably borrowed.
+    /// ```
+    /// # use strctr::rctree::Node;
+    /// let node = Node::new("root");
+    /// assert_eq!(*node.borrow(), "root");
+    /// ```
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref::map(self.0.borrow(), |d| &d.value)
+    }
+
+    /// Mutably borrows the value of the node.
+    ///
+    /// Panics if the value is currently borrowed.
+    /// ```
+    /// # use strctr::rctree::Node;
+    /// let node = Node::new(1);
+    /// *node.borrow_mut() += 1;
+    /// assert_eq!(*node.borrow(), 2);
+    /// ```
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        RefMut::map(self.0.borrow_mut(), |d| &mut d.value)
+    }
+
+    /// Returns the parent of the node, or `None` for roots and for nodes whose parent has been dropped.
+    /// ```
+    /// # use strctr::rctree::Node;
+    /// let root = Node::new(1);
+    /// let child = Node::new(2);
+    /// root.append(child.clone());
+    /// assert!(child.parent().unwrap().ptr_eq(&root));
+    /// assert!(root.parent().is_none());
+    /// ```
+    pub fn parent(&self) -> Option<Node<T>> {
+        self.0.borrow().parent.as_ref()?.upgrade().map(Node)
+    }
+
+    /// Returns the first child of the node.
+    pub fn first_child(&self) -> Option<Node<T>> {
+        self.0.borrow().children.first().cloned().map(Node)
+    }
+
+    /// Returns the last child of the node.
+    pub fn last_child(&self) -> Option<Node<T>> {
+        self.0.borrow().children.last().cloned().map(Node)
+    }
+
+    /// Returns the sibling that follows this node under the same parent.
+    /// ```
+    /// # use strctr::rctree::Node;
+    /// let root = Node::new(0);
+    /// let a = Node::new(1);
+    /// let b = Node::new(2);
+    /// root.append(a.clone());
+    /// root.append(b.clone());
+    /// assert!(a.next_sibling().unwrap().ptr_eq(&b));
+    /// assert!(b.next_sibling().is_none());
+    /// ```
+    pub fn next_sibling(&self) -> Option<Node<T>> {
+        let parent = self.parent()?;
+        let pos = parent.position_of(self)?;
+        let data = parent.0.borrow();
+        data.children.get(pos + 1).cloned().map(Node)
+    }
+
+    /// Returns the sibling that precedes this node under the same parent.
+    /// ```
+    /// # use strctr::rctree::Node;
+    /// let root = Node::new(0);
+    /// let a = Node::new(1);
+    /// let b = Node::new(2);
+    /// root.append(a.clone());
+    /// root.append(b.clone());
+    /// assert!(b.previous_sibling().unwrap().ptr_eq(&a));
+    /// assert!(a.previous_sibling().is_none());
+    /// ```
+    pub fn previous_sibling(&self) -> Option<Node<T>> {
+        let parent = self.parent()?;
+        let pos = parent.position_of(self)?.checked_sub(1)?;
+        let data = parent.0.borrow();
+        data.children.get(pos).cloned().map(Node)
+    }
+
+    /// Returns the number of direct children of the node.
+    pub fn child_count(&self) -> usize {
+        self.0.borrow().children.len()
+    }
+
+    /// Returns an iterator over the direct children of the node. The iterator does not hold a borrow of the node, so
+    /// the tree can be modified while iterating; children added or removed behind the cursor are not revisited.
+    /// ```
+    /// # use strctr::rctree::Node;
+    /// let root = Node::new(0);
+    /// root.append(Node::new(1));
+    /// root.append(Node::new(2));
+    /// let values: Vec<_> = root.children().map(|c| *c.borrow()).collect();
+    /// assert_eq!(values, vec![1, 2]);
+    /// ```
+    pub fn children(&self) -> Children<T> {
+        Children {
+            parent: self.clone(),
+            index: 0,
+        }
+    }
+
+    /// Returns an iterator over the ancestors of the node, starting with its parent.
+    /// ```
+    /// # use strctr::rctree::Node;
+    /// let root = Node::new(0);
+    /// let mid = Node::new(1);
+    /// let leaf = Node::new(2);
+    /// root.append(mid.clone());
+    /// mid.append(leaf.clone());
+    /// let values: Vec<_> = leaf.ancestors().map(|n| *n.borrow()).collect();
+    /// assert_eq!(values, vec![1, 0]);
+    /// ```
+    pub fn ancestors(&self) -> Ancestors<T> {
+        Ancestors {
+            next: self.parent(),
+        }
+    }
+
+    /// Returns a pre-order iterator over the node and all of its descendants.
+    /// ```
+    /// # use strctr::rctree::Node;
+    /// let root = Node::new(0);
+    /// let a = Node::new(1);
+    /// root.append(a.clone());
+    /// a.append(Node::new(2));
+    /// root.append(Node::new(3));
+    /// let values: Vec<_> = root.descendants().map(|n| *n.borrow()).collect();
+    /// assert_eq!(values, vec![0, 1, 2, 3]);
+    /// ```
+    pub fn descendants(&self) -> Descendants<T> {
+        Descendants {
+            stack: vec![self.clone()],
+        }
+    }
+
+    /// Appends `child` as the last child of this node, detaching it from its previous parent first.
+    ///
+    /// Returns an error if `child` is this node or one of its ancestors.
+    /// ```
+    /// # use strctr::rctree::{Node, RcTreeError};
+    /// let root = Node::new(0);
+    /// let child = Node::new(1);
+    /// assert!(root.try_append(child.clone()).is_ok());
+    /// assert_eq!(child.try_append(root.clone()), Err(RcTreeError::Cycle));
+    /// ```
+    pub fn try_append(&self, child: Node<T>) -> Result<(), RcTreeError> {
+        let index = self.child_count();
+        self.try_insert(index, child)
+    }
+
+    /// Appends `child` as the last child of this node, detaching it from its previous parent first.
+    ///
+    /// Panics if `child` is this node or one of its ancestors. For a non-panicing version, see
+    /// [try_append()](`Self::try_append()`)
+    /// ```should_panic
+    /// # use strctr::rctree::Node;
+    /// let root = Node::new(0);
+    /// root.append(root.clone());
+    /// ```
+    pub fn append(&self, child: Node<T>) {
+        if self.try_append(child).is_err() {
+            panic!("Cycle: A node cannot become a descendant of itself");
+        }
+    }
+
+    /// Inserts `child` at position `index` among this node's children, detaching it from its previous parent first.
+    /// An index past the end appends the child.
+    ///
+    /// Returns an error if `child` is this node or one of its ancestors.
+    /// ```
+    /// # use strctr::rctree::Node;
+    /// let root = Node::new(0);
+    /// root.append(Node::new(2));
+    /// root.try_insert(0, Node::new(1)).unwrap();
+    /// let values: Vec<_> = root.children().map(|c| *c.borrow()).collect();
+    /// assert_eq!(values, vec![1, 2]);
+    /// ```
+    pub fn try_insert(&self, index: usize, child: Node<T>) -> Result<(), RcTreeError> {
+        if child.ptr_eq(self) {
+            return Err(RcTreeError::Cycle);
+        }
+        // A leaf cannot be an ancestor, which saves walking up deep trees in the common case.
+        if child.child_count() > 0 && self.ancestors().any(|a| a.ptr_eq(&child)) {
+            return Err(RcTreeError::Cycle);
+        }
+
+        // Re-inserting under the same parent shifts the positions after the old slot.
+        let mut index = index;
+        if let Some(old_parent) = child.parent() {
+            if old_parent.ptr_eq(self) && self.position_of(&child).is_some_and(|p| p < index) {
+                index -= 1;
+            }
+        }
+        child.detach();
+
+        child.0.borrow_mut().parent = Some(Rc::downgrade(&self.0));
+        let mut data = self.0.borrow_mut();
+        let index = index.min(data.children.len());
+        data.children.insert(index, child.0);
+        Ok(())
+    }
+
+    /// Detaches the node from its parent, making it the root of its own tree. Its children stay attached to it.
+    /// ```
+    /// # use strctr::rctree::Node;
+    /// let root = Node::new(0);
+    /// let child = Node::new(1);
+    /// root.append(child.clone());
+    /// child.detach();
+    /// assert!(child.parent().is_none());
+    /// assert_eq!(root.child_count(), 0);
+    /// ```
+    pub fn detach(&self) {
+        if let Some(parent) = self.parent() {
+            if let Some(pos) = parent.position_of(self) {
+                parent.0.borrow_mut().children.remove(pos);
+            }
+        }
+        self.0.borrow_mut().parent = None;
+    }
+
+    /// Moves all children of `other` to the end of this node's children, keeping their order.
+    ///
+    /// Returns an error if this node lives inside `other`'s subtree.
+    /// ```
+    /// # use strctr::rctree::Node;
+    /// let a = Node::new(0);
+    /// let b = Node::new(1);
+    /// b.append(Node::new(2));
+    /// b.append(Node::new(3));
+    /// a.try_reparent_children(&b).unwrap();
+    /// assert_eq!(a.child_count(), 2);
+    /// assert_eq!(b.child_count(), 0);
+    /// ```
+    pub fn try_reparent_children(&self, other: &Node<T>) -> Result<(), RcTreeError> {
+        if self.ptr_eq(other) {
+            return Ok(());
+        }
+        if self.ancestors().any(|a| a.ptr_eq(other)) {
+            return Err(RcTreeError::Cycle);
+        }
+        let moved = std::mem::take(&mut other.0.borrow_mut().children);
+        for child in &moved {
+            child.borrow_mut().parent = Some(Rc::downgrade(&self.0));
+        }
+        self.0.borrow_mut().children.extend(moved);
+        Ok(())
+    }
+
+    fn position_of(&self, child: &Node<T>) -> Option<usize> {
+        self.0
+            .borrow()
+            .children
+            .iter()
+            .position(|c| Rc::ptr_eq(c, &child.0))
+    }
+}
+
+/// Iterator over the children of a [`Node`].
+pub struct Children<T> {
+    parent: Node<T>,
+    index: usize,
+}
+
+impl<T> Iterator for Children<T> {
+    type Item = Node<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let child = self.parent.0.borrow().children.get(self.index).cloned()?;
+        self.index += 1;
+        Some(Node(child))
+    }
+}
+
+/// Iterator over the ancestors of a [`Node`].
+pub struct Ancestors<T> {
+    next: Option<Node<T>>,
+}
+
+impl<T> Iterator for Ancestors<T> {
+    type Item = Node<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+        self.next = node.parent();
+        Some(node)
+    }
+}
+
+/// Pre-order iterator over a [`Node`] and its descendants.
+pub struct Descendants<T> {
+    stack: Vec<Node<T>>,
+}
+
+impl<T> Iterator for Descendants<T> {
+    type Item = Node<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        let data = node.0.borrow();
+        self.stack
+            .extend(data.children.iter().rev().cloned().map(Node));
+        drop(data);
+        Some(node)
+    }
+}
@@ -0,0 +1,428 @@
+//! Read-copy-update publishing of immutable data, for lookup tables that are read constantly and rebuilt rarely.
+//!
+//! An [`RcuCell`] holds the current version behind an [`Arc`]. Writers build a complete new version off to the side,
+//! typically on a background thread, and publish it in one step; readers that still hold the previous version keep
+//! using it undisturbed until they pick up the new one. A [`Reader`] caches the version it last saw and only touches
+//! the shared cell after a publish, so its lookups take no lock at all.
+//!
+//! [`FrozenMap`] is an immutable sorted map meant to be published this way, and [`FrozenIndex`] is the cell holding
+//! one. A [`FrozenMapBuilder`] builds one from a stream of entries too large to collect first.
+
+use std::borrow::Borrow;
+use std::io::{self, BufWriter, Write};
+use std::mem::size_of;
+use std::path::PathBuf;
+use std::sync::PoisonError;
+
+use crate::external_sort::{ExternalSorter, Sorted, SpillFile};
+use crate::mapped::{self, LayoutError, Pod};
+use crate::snapshot::Codec;
+use crate::sync::atomic::{AtomicU64, Ordering};
+use crate::sync::{Arc, Mutex, RwLock};
+
+/// An immutable map stored as a sorted array, answering lookups by binary search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrozenMap<K, V> {
+    entries: Box<[(K, V)]>,
+}
+
+impl<K: Ord, V> FrozenMap<K, V> {
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    pub fn get<Q: Ord + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        let i = self
+            .entries
+            .binary_search_by(|(k, _)| k.borrow().cmp(key))
+            .ok()?;
+        Some(&self.entries[i].1)
+    }
+
+    /// Returns whether the map contains the key.
+    pub fn contains_key<Q: Ord + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns an iterator over the entries, in ascending key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+
+    /// Returns a new map with the entries added to this map's. New entries replace existing ones with the same key.
+    /// ```
+    /// # use strctr::rcu::FrozenMap;
+    /// let v1: FrozenMap<_, _> = [("a", 1), ("c", 3)].into_iter().collect();
+    /// let v2 = v1.extended([("b", 2), ("c", 30)]);
+    /// assert_eq!(v2.iter().collect::<Vec<_>>(), vec![(&"a", &1), (&"b", &2), (&"c", &30)]);
+    /// assert_eq!(v1.get("c"), Some(&3));
+    /// ```
+    pub fn extended(&self, entries: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.entries.iter().cloned().chain(entries).collect()
+    }
+}
+
+impl<K: Ord, V> Default for FrozenMap<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: Box::new([]),
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for FrozenMap<K, V> {
+    /// Builds the map in `O(n log n)`. If a key appears more than once, the last value wins.
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut entries: Vec<(K, V)> = iter.into_iter().collect();
+        // Stable sort keeps duplicates in insertion order, so the last one of each run is the latest.
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut deduped: Vec<(K, V)> = Vec::with_capacity(entries.len());
+        for entry in entries {
+            match deduped.last_mut() {
+                Some(last) if last.0 == entry.0 => *last = entry,
+                _ => deduped.push(entry),
+            }
+        }
+        Self {
+            entries: deduped.into_boxed_slice(),
+        }
+    }
+}
+
+impl<K: Ord + Pod, V: Pod> FrozenMap<K, V> {
+    /// Writes the map in a [layout](`crate::mapped`) that [FrozenMapView::from_bytes()] can query in place. Keys and
+    /// values go into separate arrays, so lookups search the keys only.
+    /// ```
+    /// # use strctr::mapped::AlignedBytes;
+    /// # use strctr::rcu::{FrozenMap, FrozenMapView};
+    /// let prices: FrozenMap<u32, f64> = [(17, 9.99), (3, 0.5), (42, 120.0)].into_iter().collect();
+    /// let mut file = Vec::new();
+    /// prices.write_mapped(&mut file).unwrap();
+    ///
+    /// let bytes = AlignedBytes::from(file.as_slice());
+    /// let view = FrozenMapView::<u32, f64>::from_bytes(&bytes).unwrap();
+    /// assert_eq!(view.get(&42), Some(&120.0));
+    /// assert_eq!(view.get(&4), None);
+    /// assert_eq!(view.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec![3, 17, 42]);
+    /// ```
+    pub fn write_mapped<W: Write>(&self, mut w: W) -> io::Result<()> {
+        mapped::write_header::<_, K, V>(&mut w, MAPPED_TAG, self.len())?;
+        mapped::write_array(
+            &mut w,
+            &self.entries.iter().map(|e| e.0).collect::<Vec<K>>(),
+        )?;
+        mapped::write_array(
+            &mut w,
+            &self.entries.iter().map(|e| e.1).collect::<Vec<V>>(),
+        )
+    }
+}
+
+/// Tag of the [mapped layout](`crate::mapped`) of a [`FrozenMap`].
+const MAPPED_TAG: [u8; 4] = *b"FRZM";
+
+/// A [`FrozenMap`] of plain numbers, queried in place from a [mapped layout](`crate::mapped`).
+#[derive(Clone, Copy)]
+pub struct FrozenMapView<'a, K, V> {
+    keys: &'a [K],
+    values: &'a [V],
+}
+
+impl<'a, K: Ord + Pod, V: Pod> FrozenMapView<'a, K, V> {
+    /// Opens a map written by [FrozenMap::write_mapped()], in constant time.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, LayoutError> {
+        let (len, mut arrays) = mapped::read_header::<K, V>(bytes, MAPPED_TAG)?;
+        Ok(Self {
+            keys: arrays.next(len)?,
+            values: arrays.next(len)?,
+        })
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns whether the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    pub fn get(&self, key: &K) -> Option<&'a V> {
+        let i = self.keys.binary_search(key).ok()?;
+        Some(&self.values[i])
+    }
+
+    /// Returns whether the map contains the key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.keys.binary_search(key).is_ok()
+    }
+
+    /// Returns an iterator over the entries, in ascending key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a K, &'a V)> {
+        self.keys.iter().zip(self.values)
+    }
+}
+
+/// Builds a [`FrozenMap`] from entries pushed one at a time, sorting them with an [`ExternalSorter`]. Apart from the
+/// map itself, it holds at most `run_len` entries in memory; [write_mapped()](`Self::write_mapped()`) does not even
+/// hold the map, so maps larger than memory can be built for [`FrozenMapView`]. If a key is pushed more than once, the
+/// last value wins.
+/// ```
+/// # use strctr::rcu::{FrozenMapBuilder, FrozenMapView};
+/// # use strctr::mapped::AlignedBytes;
+/// let mut builder = FrozenMapBuilder::new(1000);
+/// for line in 0..5000u64 {
+///     let user = line * 7919 % 1000;
+///     builder.push(user, line).unwrap();
+/// }
+/// let mut file = Vec::new();
+/// builder.write_mapped(&mut file).unwrap();
+///
+/// let bytes = AlignedBytes::from(file.as_slice());
+/// let last_seen = FrozenMapView::<u64, u64>::from_bytes(&bytes).unwrap();
+/// assert_eq!(last_seen.len(), 1000);
+/// assert_eq!(last_seen.get(&0), Some(&4000));
+/// ```
+#[derive(Debug)]
+pub struct FrozenMapBuilder<K, V> {
+    sorter: ExternalSorter<K, V>,
+}
+
+impl<K: Ord + Codec, V: Codec> FrozenMapBuilder<K, V> {
+    /// Constructs a new builder that keeps up to `run_len` entries in memory and spills to the system's temporary
+    /// directory.
+    ///
+    /// Panics if `run_len` is 0.
+    pub fn new(run_len: usize) -> Self {
+        Self {
+            sorter: ExternalSorter::new(run_len),
+        }
+    }
+
+    /// Constructs a new builder that keeps up to `run_len` entries in memory and spills to the directory.
+    ///
+    /// Panics if `run_len` is 0.
+    pub fn in_dir(dir: impl Into<PathBuf>, run_len: usize) -> Self {
+        Self {
+            sorter: ExternalSorter::in_dir(dir, run_len),
+        }
+    }
+
+    /// Returns the number of entries pushed, counting repeated keys.
+    pub fn len(&self) -> usize {
+        self.sorter.len()
+    }
+
+    /// Returns whether no entries were pushed.
+    pub fn is_empty(&self) -> bool {
+        self.sorter.is_empty()
+    }
+
+    /// Adds the entry, replacing an earlier one with the same key.
+    pub fn push(&mut self, key: K, value: V) -> io::Result<()> {
+        self.sorter.push(key, value)
+    }
+
+    /// Builds the map.
+    /// ```
+    /// # use strctr::rcu::FrozenMapBuilder;
+    /// let mut builder = FrozenMapBuilder::new(2);
+    /// for (k, v) in [("b", 1), ("a", 2), ("b", 3), ("c", 4), ("a", 5)] {
+    ///     builder.push(k.to_string(), v).unwrap();
+    /// }
+    /// let map = builder.finish().unwrap();
+    /// assert_eq!(map.iter().map(|(_, v)| *v).collect::<Vec<_>>(), vec![5, 3, 4]);
+    /// ```
+    pub fn finish(self) -> io::Result<FrozenMap<K, V>> {
+        let mut entries = Vec::new();
+        for_each_distinct(self.sorter.finish()?, |k, v| {
+            entries.push((k, v));
+            Ok(())
+        })?;
+        Ok(FrozenMap {
+            entries: entries.into_boxed_slice(),
+        })
+    }
+}
+
+impl<K: Ord + Codec + Pod, V: Codec + Pod> FrozenMapBuilder<K, V> {
+    /// Writes the map in the [layout](`crate::mapped`) of [FrozenMap::write_mapped()], without building it in memory.
+    /// Keys and values are written to two more temporary files first, since the header needs the number of distinct
+    /// keys.
+    pub fn write_mapped<W: Write>(self, mut w: W) -> io::Result<()> {
+        let (keys_file, keys) = SpillFile::create(self.sorter.dir())?;
+        let (values_file, values) = SpillFile::create(self.sorter.dir())?;
+        let (mut keys, mut values) = (BufWriter::new(keys), BufWriter::new(values));
+        let mut len = 0;
+        for_each_distinct(self.sorter.finish()?, |k, v| {
+            len += 1;
+            mapped::write_elements(&mut keys, &[k])?;
+            mapped::write_elements(&mut values, &[v])
+        })?;
+        keys.flush()?;
+        values.flush()?;
+        mapped::write_header::<_, K, V>(&mut w, MAPPED_TAG, len)?;
+        io::copy(&mut keys_file.open()?, &mut w)?;
+        mapped::write_padding(&mut w, len * size_of::<K>())?;
+        io::copy(&mut values_file.open()?, &mut w)?;
+        mapped::write_padding(&mut w, len * size_of::<V>())
+    }
+}
+
+/// Calls the function with the last value of every key, in ascending key order.
+fn for_each_distinct<K: Ord + Codec, V: Codec>(
+    sorted: Sorted<K, V>,
+    mut f: impl FnMut(K, V) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut last: Option<(K, V)> = None;
+    for pair in sorted {
+        let (k, v) = pair?;
+        if let Some(entry) = last.as_mut().filter(|entry| entry.0 == k) {
+            entry.1 = v;
+            continue;
+        }
+        if let Some((k, v)) = last.replace((k, v)) {
+            f(k, v)?;
+        }
+    }
+    last.map_or(Ok(()), |(k, v)| f(k, v))
+}
+
+/// A shared cell publishing successive immutable versions of a value.
+pub struct RcuCell<T> {
+    current: RwLock<Arc<T>>,
+    /// Bumped after every publish, so readers can tell whether their cached version is stale.
+    generation: AtomicU64,
+    /// Serializes writers, so an [update()](`Self::update()`) never loses a concurrent publish.
+    writer: Mutex<()>,
+}
+
+/// An [`RcuCell`] holding a [`FrozenMap`].
+pub type FrozenIndex<K, V> = RcuCell<FrozenMap<K, V>>;
+
+impl<T: Default> Default for RcuCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> RcuCell<T> {
+    /// Constructs a new cell publishing the value.
+    pub fn new(value: T) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(value)),
+            generation: AtomicU64::new(0),
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Returns the current version. The lock guarding it is only held while cloning the [`Arc`].
+    pub fn load(&self) -> Arc<T> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Returns how many versions were published after the initial one.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Publishes the value as the new version and returns the previous one.
+    pub fn publish(&self, value: T) -> Arc<T> {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        self.swap(Arc::new(value))
+    }
+
+    /// Builds a new version out of the current one and publishes it, returning the previous version. Readers are not
+    /// blocked while `f` runs; other writers are.
+    /// ```
+    /// # use strctr::rcu::FrozenIndex;
+    /// let index: FrozenIndex<u32, &str> = FrozenIndex::default();
+    /// let mut reader = index.reader();
+    /// std::thread::scope(|s| {
+    ///     s.spawn(|| index.update(|old| old.extended([(1, "one"), (2, "two")])));
+    /// });
+    /// assert_eq!(reader.get().get(&2), Some(&"two"));
+    /// assert_eq!(index.generation(), 1);
+    /// ```
+    pub fn update(&self, f: impl FnOnce(&T) -> T) -> Arc<T> {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let next = f(&self.load());
+        self.swap(Arc::new(next))
+    }
+
+    /// Returns a reader that caches the current version between publishes.
+    pub fn reader(&self) -> Reader<'_, T> {
+        let generation = self.generation();
+        Reader {
+            cell: self,
+            cached: self.load(),
+            generation,
+        }
+    }
+
+    fn swap(&self, next: Arc<T>) -> Arc<T> {
+        let old = {
+            let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+            std::mem::replace(&mut *current, next)
+        };
+        self.generation.fetch_add(1, Ordering::Release);
+        old
+    }
+}
+
+/// A per-thread handle on an [`RcuCell`], created by [reader()](`RcuCell::reader()`). It keeps the version it last
+/// loaded alive and only goes back to the cell once a newer version was published.
+pub struct Reader<'a, T> {
+    cell: &'a RcuCell<T>,
+    cached: Arc<T>,
+    generation: u64,
+}
+
+impl<T> Reader<'_, T> {
+    /// Returns the latest published version. Unless a publish happened since the last call, this is a single atomic
+    /// load.
+    /// ```
+    /// # use strctr::rcu::RcuCell;
+    /// let cell = RcuCell::new(vec![1]);
+    /// let mut reader = cell.reader();
+    /// let old = reader.get().clone();
+    /// cell.publish(vec![1, 2]);
+    /// assert_eq!(old, vec![1]);
+    /// assert_eq!(*reader.get(), vec![1, 2]);
+    /// ```
+    pub fn get(&mut self) -> &T {
+        let generation = self.cell.generation();
+        if generation != self.generation {
+            self.cached = self.cell.load();
+            self.generation = generation;
+        }
+        &self.cached
+    }
+
+    /// Returns the version loaded last, without checking for a newer one.
+    pub fn cached(&self) -> &Arc<T> {
+        &self.cached
+    }
+}
@@ -0,0 +1,479 @@
+//! Rope for editing large texts.
+//!
+//! The text is split into chunks of at most [`MAX_CHUNK`] bytes, stored in the leaves of a height-balanced binary
+//! tree. Every inner node knows how many characters its subtree holds, so positions are found in `O(log n)`. Edits
+//! split the tree at the affected positions and join the pieces back together, rebalancing along the way, so inserting
+//! or removing anywhere costs `O(log n)` regardless of the text's size.
+//!
+//! Nodes are shared and never mutated once they are shared, so cloning a rope is `O(1)` and a clone is unaffected by
+//! edits to the original.
+//!
+//! [`ByteRope`] is the immutable counterpart for bytes: its chunks are shared buffers, so slicing and concatenating
+//! never copy, which suits assembling network messages out of many fragments.
+
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+use std::rc::Rc;
+
+mod bytes;
+
+pub use bytes::{ByteChunks, ByteRope};
+
+/// Largest chunk, in bytes, a leaf holds after building or inserting.
+pub const MAX_CHUNK: usize = 512;
+
+#[derive(Clone)]
+enum Node {
+    Leaf {
+        text: String,
+        chars: usize,
+    },
+    Branch {
+        left: Rc<Node>,
+        right: Rc<Node>,
+        chars: usize,
+        bytes: usize,
+        height: usize,
+    },
+}
+
+impl Node {
+    fn leaf(text: &str) -> Rc<Node> {
+        Rc::new(Node::Leaf {
+            text: text.to_string(),
+            chars: text.chars().count(),
+        })
+    }
+
+    fn branch(left: Rc<Node>, right: Rc<Node>) -> Rc<Node> {
+        Rc::new(Node::Branch {
+            chars: left.chars() + right.chars(),
+            bytes: left.bytes() + right.bytes(),
+            height: 1 + left.height().max(right.height()),
+            left,
+            right,
+        })
+    }
+
+    fn chars(&self) -> usize {
+        match self {
+            Node::Leaf { chars, .. } | Node::Branch { chars, .. } => *chars,
+        }
+    }
+
+    fn bytes(&self) -> usize {
+        match self {
+            Node::Leaf { text, .. } => text.len(),
+            Node::Branch { bytes, .. } => *bytes,
+        }
+    }
+
+    fn height(&self) -> usize {
+        match self {
+            Node::Leaf { .. } => 0,
+            Node::Branch { height, .. } => *height,
+        }
+    }
+
+    fn children(&self) -> (&Rc<Node>, &Rc<Node>) {
+        match self {
+            Node::Branch { left, right, .. } => (left, right),
+            Node::Leaf { .. } => unreachable!("leaves have no children"),
+        }
+    }
+}
+
+/// Builds a balanced tree over the text.
+fn build(text: &str) -> Rc<Node> {
+    if text.len() <= MAX_CHUNK {
+        return Node::leaf(text);
+    }
+    let mut mid = text.len() / 2;
+    while !text.is_char_boundary(mid) {
+        mid += 1;
+    }
+    Node::branch(build(&text[..mid]), build(&text[mid..]))
+}
+
+/// Combines two subtrees whose heights differ by at most 2 into a balanced one.
+fn balance(left: Rc<Node>, right: Rc<Node>) -> Rc<Node> {
+    if left.height() > right.height() + 1 {
+        let (ll, lr) = left.children();
+        if ll.height() >= lr.height() {
+            Node::branch(ll.clone(), Node::branch(lr.clone(), right))
+        } else {
+            let (lrl, lrr) = lr.children();
+            Node::branch(
+                Node::branch(ll.clone(), lrl.clone()),
+                Node::branch(lrr.clone(), right),
+            )
+        }
+    } else if right.height() > left.height() + 1 {
+        let (rl, rr) = right.children();
+        if rr.height() >= rl.height() {
+            Node::branch(Node::branch(left, rl.clone()), rr.clone())
+        } else {
+            let (rll, rlr) = rl.children();
+            Node::branch(
+                Node::branch(left, rll.clone()),
+                Node::branch(rlr.clone(), rr.clone()),
+            )
+        }
+    } else {
+        Node::branch(left, right)
+    }
+}
+
+/// Concatenates two trees in `O(|height difference|)`.
+fn join(left: Rc<Node>, right: Rc<Node>) -> Rc<Node> {
+    if left.chars() == 0 {
+        return right;
+    }
+    if right.chars() == 0 {
+        return left;
+    }
+    if left.height() > right.height() + 1 {
+        let (ll, lr) = left.children();
+        return balance(ll.clone(), join(lr.clone(), right));
+    }
+    if right.height() > left.height() + 1 {
+        let (rl, rr) = right.children();
+        return balance(join(left, rl.clone()), rr.clone());
+    }
+    if let (Node::Leaf { text: l, .. }, Node::Leaf { text: r, .. }) = (&*left, &*right) {
+        if l.len() + r.len() <= MAX_CHUNK {
+            return Node::leaf(&(l.to_string() + r));
+        }
+    }
+    Node::branch(left, right)
+}
+
+/// Splits a tree into the first `at` characters and the rest.
+fn split(node: &Rc<Node>, at: usize) -> (Rc<Node>, Rc<Node>) {
+    if at == 0 {
+        return (Node::leaf(""), node.clone());
+    }
+    if at == node.chars() {
+        return (node.clone(), Node::leaf(""));
+    }
+    match &**node {
+        Node::Leaf { text, .. } => {
+            let (head, tail) = text.split_at(byte_offset(text, at));
+            (Node::leaf(head), Node::leaf(tail))
+        }
+        Node::Branch { left, right, .. } => {
+            let left_chars = left.chars();
+            if at <= left_chars {
+                let (head, tail) = split(left, at);
+                (head, join(tail, right.clone()))
+            } else {
+                let (head, tail) = split(right, at - left_chars);
+                (join(left.clone(), head), tail)
+            }
+        }
+    }
+}
+
+/// Inserts the text into the leaf holding the position, copying the path to it where it is shared. The caller checks
+/// that the leaf has room.
+fn insert_into_leaf(node: &mut Rc<Node>, at: usize, text: &str, chars: usize) {
+    match Rc::make_mut(node) {
+        Node::Leaf {
+            text: leaf,
+            chars: leaf_chars,
+        } => {
+            leaf.insert_str(byte_offset(leaf, at), text);
+            *leaf_chars += chars;
+        }
+        Node::Branch {
+            left,
+            right,
+            chars: node_chars,
+            bytes,
+            ..
+        } => {
+            let left_chars = left.chars();
+            if at <= left_chars {
+                insert_into_leaf(left, at, text, chars);
+            } else {
+                insert_into_leaf(right, at - left_chars, text, chars);
+            }
+            *node_chars += chars;
+            *bytes += text.len();
+        }
+    }
+}
+
+/// Returns the length of the chunk [insert_into_leaf()] would insert into.
+fn leaf_len_at(mut node: &Node, mut at: usize) -> usize {
+    loop {
+        match node {
+            Node::Leaf { text, .. } => return text.len(),
+            Node::Branch { left, right, .. } => {
+                let left_chars = left.chars();
+                if at <= left_chars {
+                    node = left;
+                } else {
+                    at -= left_chars;
+                    node = right;
+                }
+            }
+        }
+    }
+}
+
+/// Returns the byte offset of the character at the position.
+fn byte_offset(text: &str, at: usize) -> usize {
+    text.char_indices().nth(at).map_or(text.len(), |(i, _)| i)
+}
+
+/// A text rope, supporting `O(log n)` edits and slicing at arbitrary character positions.
+///
+/// All positions count characters ([`char`]s), not bytes.
+#[derive(Clone)]
+pub struct Rope {
+    root: Rc<Node>,
+}
+
+impl Default for Rope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rope {
+    /// Constructs a new, empty rope.
+    pub fn new() -> Self {
+        Self {
+            root: Node::leaf(""),
+        }
+    }
+
+    /// Returns the number of characters.
+    pub fn len_chars(&self) -> usize {
+        self.root.chars()
+    }
+
+    /// Returns the length in bytes of the text's UTF-8 encoding.
+    pub fn len_bytes(&self) -> usize {
+        self.root.bytes()
+    }
+
+    /// Returns whether the rope contains no text.
+    pub fn is_empty(&self) -> bool {
+        self.len_chars() == 0
+    }
+
+    /// Returns the character at the position.
+    pub fn char(&self, at: usize) -> Option<char> {
+        let mut node = &*self.root;
+        let mut at = at;
+        loop {
+            match node {
+                Node::Leaf { text, .. } => return text.chars().nth(at),
+                Node::Branch { left, right, .. } => {
+                    let left_chars = left.chars();
+                    if at < left_chars {
+                        node = left;
+                    } else {
+                        at -= left_chars;
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Inserts the text before the character at the position.
+    ///
+    /// # Panics
+    /// If the position is past the end of the text.
+    /// ```
+    /// # use strctr::rope::Rope;
+    /// let mut rope = Rope::from("hello world");
+    /// rope.insert(5, ",");
+    /// rope.insert(12, "!");
+    /// rope.insert(0, "¡");
+    /// assert_eq!(rope.to_string(), "¡hello, world!");
+    /// ```
+    pub fn insert(&mut self, at: usize, text: &str) {
+        if at > self.len_chars() {
+            panic!(
+                "OutOfBounds: Wanted to insert at {}, but length is {}",
+                at,
+                self.len_chars()
+            );
+        }
+        if text.is_empty() {
+            return;
+        }
+        if leaf_len_at(&self.root, at) + text.len() <= MAX_CHUNK {
+            insert_into_leaf(&mut self.root, at, text, text.chars().count());
+            return;
+        }
+        let (head, tail) = split(&self.root, at);
+        self.root = join(join(head, build(text)), tail);
+    }
+
+    /// Removes the characters in the range.
+    ///
+    /// # Panics
+    /// If the range does not fit into the text.
+    /// ```
+    /// # use strctr::rope::Rope;
+    /// let mut rope = Rope::from("hello, cruel world");
+    /// rope.remove(5..12);
+    /// assert_eq!(rope.to_string(), "hello world");
+    /// ```
+    pub fn remove<R: RangeBounds<usize>>(&mut self, range: R) {
+        let (start, end) = self.bounds(&range);
+        let (rest, tail) = split(&self.root, end);
+        let (head, _) = split(&rest, start);
+        self.root = join(head, tail);
+    }
+
+    /// Returns the characters in the range as a new rope, sharing all untouched chunks with this one.
+    ///
+    /// # Panics
+    /// If the range does not fit into the text.
+    /// ```
+    /// # use strctr::rope::Rope;
+    /// let rope = Rope::from("hello world");
+    /// assert_eq!(rope.slice(6..).to_string(), "world");
+    /// assert_eq!(rope.slice(..=4).to_string(), "hello");
+    /// ```
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> Rope {
+        let (start, end) = self.bounds(&range);
+        let (rest, _) = split(&self.root, end);
+        let (_, slice) = split(&rest, start);
+        Rope { root: slice }
+    }
+
+    /// Appends the other rope's text.
+    pub fn append(&mut self, other: &Rope) {
+        self.root = join(self.root.clone(), other.root.clone());
+    }
+
+    /// Splits the rope at the position, leaving the characters before it and returning the rest.
+    ///
+    /// # Panics
+    /// If the position is past the end of the text.
+    /// ```
+    /// # use strctr::rope::Rope;
+    /// let mut rope = Rope::from("hello world");
+    /// let mut tail = rope.split_off(5);
+    /// assert_eq!(tail.to_string(), " world");
+    /// tail.append(&rope);
+    /// assert_eq!(tail.to_string(), " worldhello");
+    /// ```
+    pub fn split_off(&mut self, at: usize) -> Rope {
+        let (at, _) = self.bounds(&(at..));
+        let (head, tail) = split(&self.root, at);
+        self.root = head;
+        Rope { root: tail }
+    }
+
+    /// Returns an iterator over the chunks the text is stored in, in order. Chunks are never empty.
+    /// ```
+    /// # use strctr::rope::{Rope, MAX_CHUNK};
+    /// let text = "ab".repeat(1000);
+    /// let rope = Rope::from(text.as_str());
+    /// assert!(rope.chunks().all(|chunk| chunk.len() <= MAX_CHUNK));
+    /// assert_eq!(rope.chunks().collect::<String>(), text);
+    /// ```
+    pub fn chunks(&self) -> Chunks<'_> {
+        Chunks {
+            stack: vec![&self.root],
+        }
+    }
+
+    /// Returns an iterator over the characters.
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.chunks().flat_map(str::chars)
+    }
+
+    /// Resolves a range of positions into `start..end`, panicking if it does not fit into the text.
+    fn bounds<R: RangeBounds<usize>>(&self, range: &R) -> (usize, usize) {
+        resolve(range, self.len_chars())
+    }
+}
+
+/// Resolves a range of positions into `start..end`, panicking if it does not fit into the length.
+fn resolve<R: RangeBounds<usize>>(range: &R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&s) => s,
+        Bound::Excluded(&s) => s + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&e) => e + 1,
+        Bound::Excluded(&e) => e,
+        Bound::Unbounded => len,
+    };
+    if start > end || end > len {
+        panic!(
+            "OutOfBounds: Range {}..{} does not fit into length {}",
+            start, end, len
+        );
+    }
+    (start, end)
+}
+
+impl From<&str> for Rope {
+    fn from(text: &str) -> Self {
+        Self { root: build(text) }
+    }
+}
+
+impl From<String> for Rope {
+    fn from(text: String) -> Self {
+        Self::from(text.as_str())
+    }
+}
+
+impl fmt::Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chunks().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+impl fmt::Debug for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_string(), f)
+    }
+}
+
+impl PartialEq for Rope {
+    fn eq(&self, other: &Self) -> bool {
+        self.len_bytes() == other.len_bytes()
+            && self
+                .chunks()
+                .flat_map(str::bytes)
+                .eq(other.chunks().flat_map(str::bytes))
+    }
+}
+
+impl Eq for Rope {}
+
+/// Iterator over the chunks of a [`Rope`], created by [chunks()](`Rope::chunks()`).
+pub struct Chunks<'a> {
+    stack: Vec<&'a Rc<Node>>,
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            match &**node {
+                Node::Leaf { text, .. } if text.is_empty() => {}
+                Node::Leaf { text, .. } => return Some(text),
+                Node::Branch { left, right, .. } => {
+                    self.stack.push(right);
+                    self.stack.push(left);
+                }
+            }
+        }
+        None
+    }
+}
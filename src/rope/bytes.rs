@@ -0,0 +1,346 @@
+//! Immutable byte rope of shared chunks.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::RangeBounds;
+use std::sync::Arc;
+
+use super::resolve;
+
+enum Node {
+    /// A range of a shared buffer.
+    Leaf {
+        data: Arc<[u8]>,
+        start: usize,
+        len: usize,
+    },
+    Branch {
+        left: Arc<Node>,
+        right: Arc<Node>,
+        len: usize,
+        height: usize,
+    },
+}
+
+impl Node {
+    fn leaf(data: Arc<[u8]>, start: usize, len: usize) -> Arc<Node> {
+        Arc::new(Node::Leaf { data, start, len })
+    }
+
+    fn branch(left: Arc<Node>, right: Arc<Node>) -> Arc<Node> {
+        Arc::new(Node::Branch {
+            len: left.len() + right.len(),
+            height: 1 + left.height().max(right.height()),
+            left,
+            right,
+        })
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Node::Leaf { len, .. } | Node::Branch { len, .. } => *len,
+        }
+    }
+
+    fn height(&self) -> usize {
+        match self {
+            Node::Leaf { .. } => 0,
+            Node::Branch { height, .. } => *height,
+        }
+    }
+
+    fn children(&self) -> (&Arc<Node>, &Arc<Node>) {
+        match self {
+            Node::Branch { left, right, .. } => (left, right),
+            Node::Leaf { .. } => unreachable!("leaves have no children"),
+        }
+    }
+}
+
+/// Combines two subtrees whose heights differ by at most 2 into a balanced one.
+fn balance(left: Arc<Node>, right: Arc<Node>) -> Arc<Node> {
+    if left.height() > right.height() + 1 {
+        let (ll, lr) = left.children();
+        if ll.height() >= lr.height() {
+            Node::branch(ll.clone(), Node::branch(lr.clone(), right))
+        } else {
+            let (lrl, lrr) = lr.children();
+            Node::branch(
+                Node::branch(ll.clone(), lrl.clone()),
+                Node::branch(lrr.clone(), right),
+            )
+        }
+    } else if right.height() > left.height() + 1 {
+        let (rl, rr) = right.children();
+        if rr.height() >= rl.height() {
+            Node::branch(Node::branch(left, rl.clone()), rr.clone())
+        } else {
+            let (rll, rlr) = rl.children();
+            Node::branch(
+                Node::branch(left, rll.clone()),
+                Node::branch(rlr.clone(), rr.clone()),
+            )
+        }
+    } else {
+        Node::branch(left, right)
+    }
+}
+
+/// Concatenates two trees in `O(|height difference|)`.
+fn join(left: Arc<Node>, right: Arc<Node>) -> Arc<Node> {
+    if left.len() == 0 {
+        return right;
+    }
+    if right.len() == 0 {
+        return left;
+    }
+    if left.height() > right.height() + 1 {
+        let (ll, lr) = left.children();
+        return balance(ll.clone(), join(lr.clone(), right));
+    }
+    if right.height() > left.height() + 1 {
+        let (rl, rr) = right.children();
+        return balance(join(left, rl.clone()), rr.clone());
+    }
+    Node::branch(left, right)
+}
+
+/// Returns a tree of the bytes `start..end` of the tree, sharing all buffers with it.
+fn extract(node: &Arc<Node>, start: usize, end: usize) -> Arc<Node> {
+    if start == 0 && end == node.len() {
+        return node.clone();
+    }
+    match &**node {
+        Node::Leaf {
+            data, start: from, ..
+        } => Node::leaf(data.clone(), from + start, end - start),
+        Node::Branch { left, right, .. } => {
+            let left_len = left.len();
+            if end <= left_len {
+                extract(left, start, end)
+            } else if start >= left_len {
+                extract(right, start - left_len, end - left_len)
+            } else {
+                join(
+                    extract(left, start, left_len),
+                    extract(right, 0, end - left_len),
+                )
+            }
+        }
+    }
+}
+
+/// An immutable sequence of bytes made of shared chunks, for assembling messages out of many fragments without
+/// copying them.
+///
+/// The chunks are the leaves of a height-balanced tree, and a rope is a range of such a tree. Slicing only narrows the
+/// range, in `O(1)`, and concatenating cuts the trees down to their ranges and joins them in `O(log n)`. Neither
+/// copies any bytes: chunks are `Arc<[u8]>` buffers shared by every rope, slice and thread using them, so a rope is
+/// cheap to clone and send.
+/// ```
+/// # use strctr::rope::ByteRope;
+/// let header = ByteRope::from(b"HTTP/1.1 200 OK\r\n".as_slice());
+/// let body = ByteRope::from(b"hello, world".to_vec());
+/// let response: ByteRope = [header, ByteRope::from(b"\r\n".as_slice()), body.slice(..5)].into_iter().collect();
+/// assert_eq!(response.to_vec(), b"HTTP/1.1 200 OK\r\n\r\nhello");
+/// assert_eq!(response.chunks().count(), 3);
+/// ```
+#[derive(Clone)]
+pub struct ByteRope {
+    root: Arc<Node>,
+    start: usize,
+    len: usize,
+}
+
+impl Default for ByteRope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ByteRope {
+    /// Constructs a new, empty rope.
+    pub fn new() -> Self {
+        Self::from(Arc::<[u8]>::from([]))
+    }
+
+    /// Returns the number of bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the rope holds no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the byte at the position, or `None` if it is past the end.
+    pub fn get(&self, at: usize) -> Option<u8> {
+        if at >= self.len {
+            return None;
+        }
+        let mut node = &*self.root;
+        let mut at = self.start + at;
+        loop {
+            match node {
+                Node::Leaf { data, start, .. } => return Some(data[start + at]),
+                Node::Branch { left, right, .. } => {
+                    let left_len = left.len();
+                    if at < left_len {
+                        node = left;
+                    } else {
+                        at -= left_len;
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the bytes in the range as a new rope, in `O(1)`.
+    ///
+    /// # Panics
+    /// If the range does not fit into the rope.
+    /// ```
+    /// # use strctr::rope::ByteRope;
+    /// let rope = ByteRope::from(b"hello world".as_slice());
+    /// let world = rope.slice(6..);
+    /// assert_eq!(world.to_vec(), b"world");
+    /// assert_eq!(world.slice(1..=2).to_vec(), b"or");
+    /// ```
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> ByteRope {
+        let (start, end) = resolve(&range, self.len);
+        Self {
+            root: self.root.clone(),
+            start: self.start + start,
+            len: end - start,
+        }
+    }
+
+    /// Returns the bytes of this rope followed by the other's as a new rope, in `O(log n)`.
+    /// ```
+    /// # use strctr::rope::ByteRope;
+    /// let a = ByteRope::from(b"foo".as_slice());
+    /// let b = ByteRope::from(b"bar".as_slice());
+    /// assert_eq!(a.concat(&b).slice(2..4).to_vec(), b"ob");
+    /// ```
+    pub fn concat(&self, other: &ByteRope) -> ByteRope {
+        let root = join(self.tree(), other.tree());
+        Self {
+            len: root.len(),
+            root,
+            start: 0,
+        }
+    }
+
+    /// Returns an iterator over the chunks of the rope, in order. Chunks are never empty.
+    pub fn chunks(&self) -> ByteChunks<'_> {
+        ByteChunks {
+            stack: vec![(&*self.root, self.start, self.start + self.len)],
+        }
+    }
+
+    /// Returns an iterator over the bytes.
+    pub fn bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.chunks().flatten().copied()
+    }
+
+    /// Copies the bytes into a vector.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.len);
+        for chunk in self.chunks() {
+            bytes.extend_from_slice(chunk);
+        }
+        bytes
+    }
+
+    /// Writes all bytes to the writer, chunk by chunk.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.chunks().try_for_each(|chunk| w.write_all(chunk))
+    }
+
+    /// Returns the tree of the rope's range.
+    fn tree(&self) -> Arc<Node> {
+        extract(&self.root, self.start, self.start + self.len)
+    }
+}
+
+impl From<Arc<[u8]>> for ByteRope {
+    /// Wraps the buffer without copying it.
+    fn from(data: Arc<[u8]>) -> Self {
+        let len = data.len();
+        Self {
+            root: Node::leaf(data, 0, len),
+            start: 0,
+            len,
+        }
+    }
+}
+
+impl From<Vec<u8>> for ByteRope {
+    fn from(data: Vec<u8>) -> Self {
+        Self::from(Arc::<[u8]>::from(data))
+    }
+}
+
+impl From<&[u8]> for ByteRope {
+    fn from(data: &[u8]) -> Self {
+        Self::from(Arc::<[u8]>::from(data))
+    }
+}
+
+impl FromIterator<ByteRope> for ByteRope {
+    /// Concatenates the ropes.
+    fn from_iter<I: IntoIterator<Item = ByteRope>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(ByteRope::new(), |rope, next| rope.concat(&next))
+    }
+}
+
+impl fmt::Debug for ByteRope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.bytes()).finish()
+    }
+}
+
+impl PartialEq for ByteRope {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.bytes().eq(other.bytes())
+    }
+}
+
+impl Eq for ByteRope {}
+
+/// Iterator over the chunks of a [`ByteRope`], created by [chunks()](`ByteRope::chunks()`).
+pub struct ByteChunks<'a> {
+    /// Nodes to visit, with the range of their bytes in the rope.
+    stack: Vec<(&'a Node, usize, usize)>,
+}
+
+impl<'a> Iterator for ByteChunks<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, start, end)) = self.stack.pop() {
+            if start == end {
+                continue;
+            }
+            match node {
+                Node::Leaf {
+                    data, start: from, ..
+                } => return Some(&data[from + start..from + end]),
+                Node::Branch { left, right, .. } => {
+                    let left_len = left.len();
+                    if end > left_len {
+                        self.stack
+                            .push((right, start.saturating_sub(left_len), end - left_len));
+                    }
+                    if start < left_len {
+                        self.stack.push((left, start, end.min(left_len)));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
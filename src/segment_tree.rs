@@ -0,0 +1,433 @@
+//! Segment trees over a fixed-length sequence, answering queries over any index range in `O(log n)`.
+//!
+//! What a query computes is decided by a [`Monoid`]: an associative way to combine two values together with its
+//! identity. [`Sum`], [`Min`] and [`Max`] cover the common cases. [`SegmentTree`] supports point updates, and
+//! [`LazySegmentTree`] additionally applies updates to whole ranges, which needs an [`Action`] describing how an
+//! update changes the combined value of a range. [`ImplicitTreap`] answers the same queries over a sequence that also
+//! grows and shrinks at any index.
+
+use std::ops::{Add, Bound, RangeBounds};
+
+mod treap;
+
+pub use treap::ImplicitTreap;
+
+/// An associative operation with an identity element.
+pub trait Monoid<T> {
+    /// Returns the identity: combining it with any value yields that value.
+    fn identity(&self) -> T;
+
+    /// Combines two values. Has to be associative, but not necessarily commutative; `a` always comes from the left
+    /// of `b`.
+    fn combine(&self, a: &T, b: &T) -> T;
+}
+
+/// A [`Monoid`] whose values can be changed in bulk by updates of type `U`.
+pub trait Action<T, U>: Monoid<T> {
+    /// Returns the value of a range of `len` elements, whose combined value was `value`, after applying the update
+    /// to every element.
+    fn apply(&self, update: &U, value: &T, len: usize) -> T;
+
+    /// Returns the update that has the same effect as applying `older` and then `newer`.
+    fn compose(&self, newer: &U, older: &U) -> U;
+}
+
+/// Adds values up. As an [`Action`], adds the update to every element of the range.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sum;
+
+/// Keeps the smallest value. As an [`Action`], adds the update to every element of the range.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Min;
+
+/// Keeps the largest value. As an [`Action`], adds the update to every element of the range.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Max;
+
+impl<T: Add<Output = T> + Default + Clone> Monoid<T> for Sum {
+    fn identity(&self) -> T {
+        T::default()
+    }
+
+    fn combine(&self, a: &T, b: &T) -> T {
+        a.clone() + b.clone()
+    }
+}
+
+macro_rules! numeric_actions {
+    ($min:ident, $max:ident; $($t:ty)*) => {$(
+        impl Action<$t, $t> for Sum {
+            fn apply(&self, update: &$t, value: &$t, len: usize) -> $t {
+                value + update * len as $t
+            }
+
+            fn compose(&self, newer: &$t, older: &$t) -> $t {
+                newer + older
+            }
+        }
+
+        impl Monoid<$t> for Min {
+            fn identity(&self) -> $t {
+                <$t>::$max
+            }
+
+            fn combine(&self, a: &$t, b: &$t) -> $t {
+                if b < a { *b } else { *a }
+            }
+        }
+
+        impl Action<$t, $t> for Min {
+            fn apply(&self, update: &$t, value: &$t, _len: usize) -> $t {
+                // Empty ranges have to stay at the identity.
+                if *value == <$t>::$max { <$t>::$max } else { value + update }
+            }
+
+            fn compose(&self, newer: &$t, older: &$t) -> $t {
+                newer + older
+            }
+        }
+
+        impl Monoid<$t> for Max {
+            fn identity(&self) -> $t {
+                <$t>::$min
+            }
+
+            fn combine(&self, a: &$t, b: &$t) -> $t {
+                if b > a { *b } else { *a }
+            }
+        }
+
+        impl Action<$t, $t> for Max {
+            fn apply(&self, update: &$t, value: &$t, _len: usize) -> $t {
+                if *value == <$t>::$min { <$t>::$min } else { value + update }
+            }
+
+            fn compose(&self, newer: &$t, older: &$t) -> $t {
+                newer + older
+            }
+        }
+    )*};
+}
+
+numeric_actions!(MIN, MAX; i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
+numeric_actions!(NEG_INFINITY, INFINITY; f32 f64);
+
+/// Resolves a range of indices into `start..end`, panicking if it does not fit into `0..len`.
+fn bounds<R: RangeBounds<usize>>(range: &R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&s) => s,
+        Bound::Excluded(&s) => s + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&e) => e + 1,
+        Bound::Excluded(&e) => e,
+        Bound::Unbounded => len,
+    };
+    if start > end || end > len {
+        panic!(
+            "OutOfBounds: Range {}..{} does not fit into length {}",
+            start, end, len
+        );
+    }
+    (start, end)
+}
+
+/// A segment tree with point updates and range queries.
+pub struct SegmentTree<T, Op> {
+    /// Leaves live at `len..2 * len`, and every inner node `i` combines nodes `2i` and `2i + 1`.
+    tree: Vec<T>,
+    len: usize,
+    op: Op,
+}
+
+impl<T: Clone, Op: Monoid<T>> SegmentTree<T, Op> {
+    /// Builds a tree over the values in `O(n)`.
+    /// ```
+    /// # use strctr::segment_tree::{SegmentTree, Sum};
+    /// let t = SegmentTree::new(vec![5, 3, 8, 1], Sum);
+    /// assert_eq!(t.query(1..3), 11);
+    /// assert_eq!(t.query(..), 17);
+    /// ```
+    pub fn new(values: Vec<T>, op: Op) -> Self {
+        let len = values.len();
+        let mut tree = vec![op.identity(); len];
+        tree.extend(values);
+        for i in (1..len).rev() {
+            tree[i] = op.combine(&tree[2 * i], &tree[2 * i + 1]);
+        }
+        Self { tree, len, op }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the tree has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the element at the index.
+    pub fn get(&self, i: usize) -> Option<&T> {
+        (i < self.len).then(|| &self.tree[self.len + i])
+    }
+
+    /// Replaces the element at the index.
+    ///
+    /// Panics if the index is out of bounds.
+    /// ```
+    /// # use strctr::segment_tree::{SegmentTree, Min};
+    /// let mut t = SegmentTree::new(vec![4, 2, 7], Min);
+    /// assert_eq!(t.query(..), 2);
+    /// t.set(1, 9);
+    /// assert_eq!(t.query(..), 4);
+    /// ```
+    pub fn set(&mut self, i: usize, value: T) {
+        self.update(i, |v| *v = value);
+    }
+
+    /// Changes the element at the index in place.
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn update(&mut self, i: usize, f: impl FnOnce(&mut T)) {
+        if i >= self.len {
+            panic!(
+                "OutOfBounds: Wanted to update {}, but length is {}",
+                i, self.len
+            );
+        }
+        let mut node = self.len + i;
+        f(&mut self.tree[node]);
+        while node > 1 {
+            node /= 2;
+            self.tree[node] = self
+                .op
+                .combine(&self.tree[2 * node], &self.tree[2 * node + 1]);
+        }
+    }
+
+    /// Combines the elements in the range, left to right. Returns the identity for an empty range.
+    ///
+    /// Panics if the range is out of bounds.
+    /// ```
+    /// # use strctr::segment_tree::{SegmentTree, Monoid};
+    /// // Concatenation is associative but not commutative.
+    /// struct Concat;
+    /// impl Monoid<String> for Concat {
+    ///     fn identity(&self) -> String {
+    ///         String::new()
+    ///     }
+    ///     fn combine(&self, a: &String, b: &String) -> String {
+    ///         format!("{a}{b}")
+    ///     }
+    /// }
+    /// let words = ["a", "b", "c", "d", "e"].map(String::from).to_vec();
+    /// let t = SegmentTree::new(words, Concat);
+    /// assert_eq!(t.query(1..=3), "bcd");
+    /// ```
+    pub fn query<R: RangeBounds<usize>>(&self, range: R) -> T {
+        let (start, end) = bounds(&range, self.len);
+        let (mut l, mut r) = (start + self.len, end + self.len);
+        let mut left = self.op.identity();
+        let mut right = self.op.identity();
+        while l < r {
+            if l % 2 == 1 {
+                left = self.op.combine(&left, &self.tree[l]);
+                l += 1;
+            }
+            if r % 2 == 1 {
+                r -= 1;
+                right = self.op.combine(&self.tree[r], &right);
+            }
+            l /= 2;
+            r /= 2;
+        }
+        self.op.combine(&left, &right)
+    }
+}
+
+/// A segment tree with range updates and range queries. Updates are recorded on the topmost nodes covering their
+/// range and pushed down only when a query or update needs to look below them.
+pub struct LazySegmentTree<T, U, Op> {
+    /// Node `1` covers the whole sequence, and node `i` has children `2i` and `2i + 1`.
+    tree: Vec<T>,
+    /// Updates applied to a node's value but not yet to its children.
+    pending: Vec<Option<U>>,
+    len: usize,
+    op: Op,
+}
+
+impl<T: Clone, U: Clone, Op: Action<T, U>> LazySegmentTree<T, U, Op> {
+    /// Builds a tree over the values in `O(n)`.
+    pub fn new(values: Vec<T>, op: Op) -> Self {
+        let len = values.len();
+        let size = 2 * len.next_power_of_two();
+        let mut t = Self {
+            tree: vec![op.identity(); size],
+            pending: vec![None; size],
+            len,
+            op,
+        };
+        if len > 0 {
+            t.build(1, 0, len, &values);
+        }
+        t
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the tree has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Combines the elements in the range, left to right. Returns the identity for an empty range.
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn query<R: RangeBounds<usize>>(&mut self, range: R) -> T {
+        let (start, end) = bounds(&range, self.len);
+        if start == end {
+            return self.op.identity();
+        }
+        self.query_node(1, 0, self.len, start, end)
+    }
+
+    /// Applies the update to every element in the range.
+    ///
+    /// Panics if the range is out of bounds.
+    /// ```
+    /// # use strctr::segment_tree::{LazySegmentTree, Sum, Max};
+    /// let mut sums = LazySegmentTree::new(vec![0i64; 10], Sum);
+    /// sums.update(2..6, &5);
+    /// sums.update(4.., &1);
+    /// assert_eq!(sums.query(..), 4 * 5 + 6);
+    /// assert_eq!(sums.query(5..7), 6 + 1);
+    ///
+    /// let mut peaks = LazySegmentTree::new(vec![1, 5, 2, 4], Max);
+    /// peaks.update(2..4, &3);
+    /// assert_eq!(peaks.query(..), 7);
+    /// assert_eq!(peaks.query(..2), 5);
+    /// ```
+    pub fn update<R: RangeBounds<usize>>(&mut self, range: R, update: &U) {
+        let (start, end) = bounds(&range, self.len);
+        if start < end {
+            self.update_node(1, 0, self.len, start, end, update);
+        }
+    }
+
+    /// Replaces the element at the index.
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn set(&mut self, i: usize, value: T) {
+        if i >= self.len {
+            panic!(
+                "OutOfBounds: Wanted to set {}, but length is {}",
+                i, self.len
+            );
+        }
+        self.set_node(1, 0, self.len, i, value);
+    }
+
+    /// Returns the element at the index.
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn get(&mut self, i: usize) -> T {
+        self.query(i..=i)
+    }
+
+    fn build(&mut self, node: usize, lo: usize, hi: usize, values: &[T]) {
+        if hi - lo == 1 {
+            self.tree[node] = values[lo].clone();
+            return;
+        }
+        let mid = (lo + hi) / 2;
+        self.build(2 * node, lo, mid, values);
+        self.build(2 * node + 1, mid, hi, values);
+        self.pull(node);
+    }
+
+    fn pull(&mut self, node: usize) {
+        self.tree[node] = self
+            .op
+            .combine(&self.tree[2 * node], &self.tree[2 * node + 1]);
+    }
+
+    /// Applies the update to the node covering `len` elements, deferring it for its children.
+    fn apply_node(&mut self, node: usize, len: usize, update: &U) {
+        self.tree[node] = self.op.apply(update, &self.tree[node], len);
+        if len > 1 {
+            self.pending[node] = Some(match &self.pending[node] {
+                Some(older) => self.op.compose(update, older),
+                None => update.clone(),
+            });
+        }
+    }
+
+    fn push(&mut self, node: usize, lo: usize, hi: usize) {
+        if let Some(update) = self.pending[node].take() {
+            let mid = (lo + hi) / 2;
+            self.apply_node(2 * node, mid - lo, &update);
+            self.apply_node(2 * node + 1, hi - mid, &update);
+        }
+    }
+
+    fn query_node(&mut self, node: usize, lo: usize, hi: usize, start: usize, end: usize) -> T {
+        if start <= lo && hi <= end {
+            return self.tree[node].clone();
+        }
+        self.push(node, lo, hi);
+        let mid = (lo + hi) / 2;
+        if end <= mid {
+            return self.query_node(2 * node, lo, mid, start, end);
+        }
+        if start >= mid {
+            return self.query_node(2 * node + 1, mid, hi, start, end);
+        }
+        let left = self.query_node(2 * node, lo, mid, start, end);
+        let right = self.query_node(2 * node + 1, mid, hi, start, end);
+        self.op.combine(&left, &right)
+    }
+
+    fn update_node(
+        &mut self,
+        node: usize,
+        lo: usize,
+        hi: usize,
+        start: usize,
+        end: usize,
+        update: &U,
+    ) {
+        if end <= lo || hi <= start {
+            return;
+        }
+        if start <= lo && hi <= end {
+            self.apply_node(node, hi - lo, update);
+            return;
+        }
+        self.push(node, lo, hi);
+        let mid = (lo + hi) / 2;
+        self.update_node(2 * node, lo, mid, start, end, update);
+        self.update_node(2 * node + 1, mid, hi, start, end, update);
+        self.pull(node);
+    }
+
+    fn set_node(&mut self, node: usize, lo: usize, hi: usize, i: usize, value: T) {
+        if hi - lo == 1 {
+            self.tree[node] = value;
+            return;
+        }
+        self.push(node, lo, hi);
+        let mid = (lo + hi) / 2;
+        if i < mid {
+            self.set_node(2 * node, lo, mid, i, value);
+        } else {
+            self.set_node(2 * node + 1, mid, hi, i, value);
+        }
+        self.pull(node);
+    }
+}
@@ -0,0 +1,329 @@
+//! Implicit treap: an editable sequence with range queries.
+
+use std::hash::{BuildHasher, Hasher};
+use std::ops::RangeBounds;
+
+use super::{bounds, Monoid};
+use crate::hash::HashState;
+
+/// Link to no node.
+const NIL: usize = usize::MAX;
+
+struct Node<T> {
+    value: T,
+    /// Combined value of the subtree.
+    total: T,
+    size: usize,
+    priority: u64,
+    left: usize,
+    right: usize,
+}
+
+/// A sequence supporting insertion and removal at any index next to range queries, all in expected `O(log n)`.
+///
+/// Where a [`SegmentTree`](`super::SegmentTree`) has a fixed length, this is a treap ordered by position: a binary
+/// tree in in-order sequence, kept balanced by random heap priorities, in which every node caches its subtree's size
+/// and combined value. Indices are found through the sizes, and edits split the tree at their index and merge it back.
+/// ```
+/// # use strctr::segment_tree::{ImplicitTreap, Min};
+/// let mut t = ImplicitTreap::new(vec![5, 3, 8, 6], Min);
+/// assert_eq!(t.query(2..), 6);
+/// t.insert(3, 1);
+/// assert_eq!(t.query(2..), 1);
+/// assert_eq!(t.remove(1), 3);
+/// assert_eq!(t.query(..2), 5);
+/// assert_eq!(t.iter().copied().collect::<Vec<_>>(), [5, 8, 1, 6]);
+/// ```
+pub struct ImplicitTreap<T, Op> {
+    nodes: Vec<Option<Node<T>>>,
+    free: Vec<usize>,
+    root: usize,
+    op: Op,
+    rng: u64,
+}
+
+impl<T: Clone, Op: Monoid<T>> ImplicitTreap<T, Op> {
+    /// Builds a treap over the values in `O(n log n)`. Priorities are drawn from a randomly seeded generator, or one
+    /// with a fixed seed if the `deterministic` feature is enabled.
+    pub fn new(values: Vec<T>, op: Op) -> Self {
+        let seed = HashState::new().build_hasher().finish();
+        Self::with_seed(values, op, seed)
+    }
+
+    /// Builds a treap over the values whose priorities are drawn from a generator with the given seed, making its
+    /// shape reproducible.
+    pub fn with_seed(values: Vec<T>, op: Op, seed: u64) -> Self {
+        let mut t = Self {
+            nodes: Vec::with_capacity(values.len()),
+            free: Vec::new(),
+            root: NIL,
+            op,
+            // Xorshift gets stuck on 0.
+            rng: seed | 1,
+        };
+        for value in values {
+            t.push(value);
+        }
+        t
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.size(self.root)
+    }
+
+    /// Returns whether the sequence has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.root == NIL
+    }
+
+    /// Removes all elements, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.root = NIL;
+    }
+
+    /// Returns the element at the index.
+    pub fn get(&self, i: usize) -> Option<&T> {
+        let node = self.find(i)?;
+        Some(&self.node(node).value)
+    }
+
+    /// Replaces the element at the index.
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn set(&mut self, i: usize, value: T) {
+        self.update(i, |v| *v = value);
+    }
+
+    /// Changes the element at the index in place.
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn update(&mut self, i: usize, f: impl FnOnce(&mut T)) {
+        if i >= self.len() {
+            panic!(
+                "OutOfBounds: Wanted to update {}, but length is {}",
+                i,
+                self.len()
+            );
+        }
+        self.update_node(self.root, i, f);
+    }
+
+    /// Inserts the element at the index, shifting all elements after it.
+    ///
+    /// Panics if the index is greater than the length.
+    pub fn insert(&mut self, i: usize, value: T) {
+        if i > self.len() {
+            panic!(
+                "OutOfBounds: Wanted to insert at {}, but length is {}",
+                i,
+                self.len()
+            );
+        }
+        let node = self.alloc(value);
+        let (left, right) = self.split(self.root, i);
+        let left = self.merge(left, node);
+        self.root = self.merge(left, right);
+    }
+
+    /// Appends the element to the end.
+    pub fn push(&mut self, value: T) {
+        let node = self.alloc(value);
+        self.root = self.merge(self.root, node);
+    }
+
+    /// Removes and returns the element at the index, shifting all elements after it.
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn remove(&mut self, i: usize) -> T {
+        if i >= self.len() {
+            panic!(
+                "OutOfBounds: Wanted to remove {}, but length is {}",
+                i,
+                self.len()
+            );
+        }
+        let (left, rest) = self.split(self.root, i);
+        let (node, right) = self.split(rest, 1);
+        self.root = self.merge(left, right);
+        let removed = self.nodes[node].take().expect("live node");
+        self.free.push(node);
+        removed.value
+    }
+
+    /// Combines the elements in the range, left to right. Returns the identity for an empty range.
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn query<R: RangeBounds<usize>>(&self, range: R) -> T {
+        let (start, end) = bounds(&range, self.len());
+        self.query_node(self.root, start, end)
+    }
+
+    /// Returns an iterator over the elements, in order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let mut stack = Vec::new();
+        let mut cur = self.root;
+        std::iter::from_fn(move || {
+            while cur != NIL {
+                stack.push(cur);
+                cur = self.node(cur).left;
+            }
+            let node = self.node(stack.pop()?);
+            cur = node.right;
+            Some(&node.value)
+        })
+    }
+
+    fn node(&self, i: usize) -> &Node<T> {
+        self.nodes[i].as_ref().expect("live node")
+    }
+
+    fn node_mut(&mut self, i: usize) -> &mut Node<T> {
+        self.nodes[i].as_mut().expect("live node")
+    }
+
+    fn size(&self, i: usize) -> usize {
+        if i == NIL {
+            0
+        } else {
+            self.node(i).size
+        }
+    }
+
+    fn alloc(&mut self, value: T) -> usize {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng = x;
+        let node = Node {
+            total: value.clone(),
+            value,
+            size: 1,
+            priority: x,
+            left: NIL,
+            right: NIL,
+        };
+        match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = Some(node);
+                i
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    /// Recomputes the node's size and combined value from its children.
+    fn pull(&mut self, i: usize) {
+        let (left, right) = (self.node(i).left, self.node(i).right);
+        let mut total = self.node(i).value.clone();
+        let mut size = 1;
+        if left != NIL {
+            total = self.op.combine(&self.node(left).total, &total);
+            size += self.node(left).size;
+        }
+        if right != NIL {
+            total = self.op.combine(&total, &self.node(right).total);
+            size += self.node(right).size;
+        }
+        let node = self.node_mut(i);
+        node.total = total;
+        node.size = size;
+    }
+
+    /// Splits the subtree into its first `at` elements and the rest.
+    fn split(&mut self, i: usize, at: usize) -> (usize, usize) {
+        if i == NIL {
+            return (NIL, NIL);
+        }
+        let left_size = self.size(self.node(i).left);
+        if at <= left_size {
+            let (left, right) = self.split(self.node(i).left, at);
+            self.node_mut(i).left = right;
+            self.pull(i);
+            (left, i)
+        } else {
+            let (left, right) = self.split(self.node(i).right, at - left_size - 1);
+            self.node_mut(i).right = left;
+            self.pull(i);
+            (i, right)
+        }
+    }
+
+    /// Concatenates two subtrees, keeping the node of the higher priority on top.
+    fn merge(&mut self, a: usize, b: usize) -> usize {
+        if a == NIL {
+            return b;
+        }
+        if b == NIL {
+            return a;
+        }
+        if self.node(a).priority > self.node(b).priority {
+            let right = self.merge(self.node(a).right, b);
+            self.node_mut(a).right = right;
+            self.pull(a);
+            a
+        } else {
+            let left = self.merge(a, self.node(b).left);
+            self.node_mut(b).left = left;
+            self.pull(b);
+            b
+        }
+    }
+
+    fn find(&self, mut i: usize) -> Option<usize> {
+        let mut cur = self.root;
+        while cur != NIL {
+            let node = self.node(cur);
+            let left_size = self.size(node.left);
+            if i < left_size {
+                cur = node.left;
+            } else if i == left_size {
+                return Some(cur);
+            } else {
+                i -= left_size + 1;
+                cur = node.right;
+            }
+        }
+        None
+    }
+
+    fn update_node(&mut self, cur: usize, i: usize, f: impl FnOnce(&mut T)) {
+        let left_size = self.size(self.node(cur).left);
+        if i < left_size {
+            self.update_node(self.node(cur).left, i, f);
+        } else if i == left_size {
+            f(&mut self.node_mut(cur).value);
+        } else {
+            self.update_node(self.node(cur).right, i - left_size - 1, f);
+        }
+        self.pull(cur);
+    }
+
+    /// Combines the elements `start..end` of the subtree.
+    fn query_node(&self, cur: usize, start: usize, end: usize) -> T {
+        if cur == NIL || start >= end {
+            return self.op.identity();
+        }
+        let node = self.node(cur);
+        if start == 0 && end == node.size {
+            return node.total.clone();
+        }
+        let left_size = self.size(node.left);
+        let mut total = self.query_node(node.left, start, end.min(left_size));
+        if start <= left_size && left_size < end {
+            total = self.op.combine(&total, &node.value);
+        }
+        let right = self.query_node(
+            node.right,
+            start.saturating_sub(left_size + 1),
+            end.saturating_sub(left_size + 1),
+        );
+        self.op.combine(&total, &right)
+    }
+}
@@ -0,0 +1,191 @@
+//! Sequence lock for publishing small values between threads without blocking readers.
+//!
+//! A sequence counter sits next to the value. Writing makes it odd, stores the value and makes it even again. Readers
+//! copy the value optimistically and check that the counter was even and unchanged around the copy; otherwise the copy
+//! may be torn, and they retry. Writers never wait for readers, and readers only ever retry while a write is in
+//! progress, which suits small values that are read far more often than they change, like configuration snapshots or
+//! the latest sample of a sensor.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::{size_of, MaybeUninit};
+use std::ptr;
+
+use crate::mapped::Plain;
+use crate::sync::atomic::{fence, AtomicU64, AtomicUsize, Ordering};
+use crate::sync::hint::spin_loop;
+
+/// Number of bytes in a word of the stored value.
+const WORD: usize = size_of::<u64>();
+
+/// A cell holding a [`Plain`] value, readable by many threads while another one writes it.
+///
+/// Writes from several threads are serialized with each other, but the structure is meant for a single writer: that
+/// writer never waits.
+///
+/// The value is stored as a sequence of atomic words, so a reader copying it while a writer stores it sees a torn
+/// copy, which it discards, rather than a data race. That is why values have to be [`Plain`]: their bytes are all
+/// initialized, and any bytes make a valid value.
+/// ```
+/// # use strctr::seqlock::SeqLock;
+/// let latest = SeqLock::new([0u64, 0]);
+/// std::thread::scope(|s| {
+///     s.spawn(|| {
+///         for i in 1..=1000 {
+///             latest.write([i, 2 * i]);
+///         }
+///     });
+///     s.spawn(|| {
+///         for _ in 0..1000 {
+///             let [a, b] = latest.read();
+///             assert_eq!(b, 2 * a);
+///         }
+///     });
+/// });
+/// assert_eq!(latest.read(), [1000, 2000]);
+/// ```
+pub struct SeqLock<T> {
+    /// Odd while a write is in progress, and bumped twice by every write.
+    seq: AtomicUsize,
+    /// Bytes of the value in native order, the last word padded with zeros.
+    words: Box<[AtomicU64]>,
+    _value: PhantomData<T>,
+}
+
+impl<T: Plain + Default> Default for SeqLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Plain> SeqLock<T> {
+    /// Constructs a new cell holding the value.
+    pub fn new(value: T) -> Self {
+        let words = (0..size_of::<T>().div_ceil(WORD))
+            .map(|_| AtomicU64::new(0))
+            .collect();
+        let lock = Self {
+            seq: AtomicUsize::new(0),
+            words,
+            _value: PhantomData,
+        };
+        lock.store(value);
+        lock
+    }
+
+    /// Returns a copy of the value, retrying while a write is in progress.
+    pub fn read(&self) -> T {
+        loop {
+            if let Some(value) = self.try_read() {
+                return value;
+            }
+            spin_loop();
+        }
+    }
+
+    /// Returns a copy of the value, or `None` if a write was in progress.
+    pub fn try_read(&self) -> Option<T> {
+        let before = self.seq.load(Ordering::Acquire);
+        if before & 1 == 1 {
+            return None;
+        }
+        let value = self.load();
+        fence(Ordering::Acquire);
+        if self.seq.load(Ordering::Relaxed) != before {
+            return None;
+        }
+        Some(value)
+    }
+
+    /// Returns how many writes happened so far. A reader can compare it with an earlier call to tell whether the value
+    /// changed in between.
+    pub fn version(&self) -> usize {
+        self.seq.load(Ordering::Acquire) / 2
+    }
+
+    /// Replaces the value. Waits only for writes from other threads.
+    pub fn write(&self, value: T) {
+        let seq = self.begin_write();
+        self.store(value);
+        self.seq.store(seq.wrapping_add(2), Ordering::Release);
+    }
+
+    /// Replaces the value with the result of the function, applied to the current value. No other write can happen in
+    /// between.
+    /// ```
+    /// # use strctr::seqlock::SeqLock;
+    /// let lock = SeqLock::new(1);
+    /// lock.update(|n| n * 10);
+    /// assert_eq!(lock.read(), 10);
+    /// assert_eq!(lock.version(), 1);
+    /// ```
+    pub fn update(&self, f: impl FnOnce(T) -> T) {
+        let seq = self.begin_write();
+        // Writers are serialized, so the value is not torn.
+        let current = self.load();
+        self.store(f(current));
+        self.seq.store(seq.wrapping_add(2), Ordering::Release);
+    }
+
+    /// Consumes the cell, returning the value.
+    pub fn into_inner(self) -> T {
+        self.load()
+    }
+
+    /// Copies the value out of the words. The copy is torn if a write is in progress.
+    fn load(&self) -> T {
+        let mut value = MaybeUninit::<T>::uninit();
+        let bytes = value.as_mut_ptr().cast::<u8>();
+        for (i, word) in self.words.iter().enumerate() {
+            let word = word.load(Ordering::Relaxed).to_ne_bytes();
+            let len = WORD.min(size_of::<T>() - i * WORD);
+            // The words hold exactly the value's bytes.
+            unsafe { ptr::copy_nonoverlapping(word.as_ptr(), bytes.add(i * WORD), len) };
+        }
+        // All bytes are initialized, and any bytes make a valid plain value, torn or not.
+        unsafe { value.assume_init() }
+    }
+
+    /// Copies the value into the words.
+    fn store(&self, value: T) {
+        // Plain values have no padding, so all their bytes are initialized.
+        let bytes = unsafe {
+            std::slice::from_raw_parts(ptr::from_ref(&value).cast::<u8>(), size_of::<T>())
+        };
+        for (word, chunk) in self.words.iter().zip(bytes.chunks(WORD)) {
+            let mut padded = [0; WORD];
+            padded[..chunk.len()].copy_from_slice(chunk);
+            word.store(u64::from_ne_bytes(padded), Ordering::Relaxed);
+        }
+    }
+
+    /// Makes the sequence odd, waiting for any other writer to finish first. Returns the even sequence it started from.
+    fn begin_write(&self) -> usize {
+        let mut seq = self.seq.load(Ordering::Relaxed);
+        loop {
+            if seq & 1 == 1 {
+                spin_loop();
+                seq = self.seq.load(Ordering::Relaxed);
+                continue;
+            }
+            match self.seq.compare_exchange_weak(
+                seq,
+                seq.wrapping_add(1),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(current) => seq = current,
+            }
+        }
+        // Keeps the value's stores from being reordered before the sequence turns odd.
+        fence(Ordering::Release);
+        seq
+    }
+}
+
+impl<T: Plain + fmt::Debug> fmt::Debug for SeqLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SeqLock").field(&self.read()).finish()
+    }
+}
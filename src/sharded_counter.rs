@@ -0,0 +1,267 @@
+//! Counters and histograms for hot paths shared by many threads.
+//!
+//! A single atomic counter bumped from many cores makes its cache line bounce between them, and throughput collapses
+//! as threads are added. These structures give every thread its own slot instead, padded to a cache line of its own,
+//! and only add the slots up when the total is read. Increments stay cheap and uncontended; reads cost one load per
+//! slot and are not a consistent snapshot while increments are in flight.
+
+use std::fmt;
+
+use crate::align::{CachePadded, CACHE_LINE_SIZE};
+use crate::sync::atomic::{AtomicU64, Ordering};
+use crate::sync::{default_shards, thread_slot};
+
+/// Number of counters fitting into one padded cache line.
+const LINE_WORDS: usize = CACHE_LINE_SIZE / 8;
+
+/// A counter spreading its increments over per-thread slots.
+/// ```
+/// # use strctr::sharded_counter::ShardedCounter;
+/// let requests = ShardedCounter::new();
+/// std::thread::scope(|s| {
+///     for _ in 0..4 {
+///         s.spawn(|| (0..1000).for_each(|_| requests.increment()));
+///     }
+/// });
+/// assert_eq!(requests.sum(), 4000);
+/// ```
+pub struct ShardedCounter {
+    slots: Box<[CachePadded<AtomicU64>]>,
+}
+
+impl Default for ShardedCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShardedCounter {
+    /// Constructs a new counter at 0, with one slot per available CPU.
+    pub fn new() -> Self {
+        Self::with_shards(default_shards())
+    }
+
+    /// Constructs a new counter at 0, with the number of slots. Threads share slots if there are more of them.
+    ///
+    /// Panics if the number of slots is 0.
+    pub fn with_shards(shards: usize) -> Self {
+        if shards == 0 {
+            panic!("InvalidCapacity: ShardedCounter needs at least one slot");
+        }
+        Self {
+            slots: (0..shards).map(|_| CachePadded::default()).collect(),
+        }
+    }
+
+    /// Returns the number of slots.
+    pub fn shard_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Adds to the counter, wrapping around on overflow.
+    pub fn add(&self, n: u64) {
+        self.slots[thread_slot(self.slots.len())].fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Adds 1 to the counter.
+    pub fn increment(&self) {
+        self.add(1);
+    }
+
+    /// Returns the total of all slots.
+    pub fn sum(&self) -> u64 {
+        self.slots.iter().fold(0, |sum, slot| {
+            sum.wrapping_add(slot.load(Ordering::Relaxed))
+        })
+    }
+
+    /// Resets the counter to 0 and returns the total it held. Every increment is counted exactly once, either in the
+    /// returned total or in the counter afterwards.
+    /// ```
+    /// # use strctr::sharded_counter::ShardedCounter;
+    /// let counter = ShardedCounter::with_shards(2);
+    /// counter.add(5);
+    /// assert_eq!(counter.reset(), 5);
+    /// assert_eq!(counter.sum(), 0);
+    /// ```
+    pub fn reset(&self) -> u64 {
+        self.slots.iter().fold(0, |sum, slot| {
+            sum.wrapping_add(slot.swap(0, Ordering::Relaxed))
+        })
+    }
+}
+
+impl fmt::Debug for ShardedCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ShardedCounter").field(&self.sum()).finish()
+    }
+}
+
+/// A histogram of `u64` samples, spreading its recordings over per-thread slots.
+///
+/// Buckets are given by their inclusive upper bounds. A sample lands in the first bucket whose bound is at least the
+/// sample, and samples above the last bound land in an extra overflow bucket.
+/// ```
+/// # use strctr::sharded_counter::ShardedHistogram;
+/// let latencies = ShardedHistogram::new(vec![10, 100, 1000]);
+/// std::thread::scope(|s| {
+///     s.spawn(|| [5, 50, 500].into_iter().for_each(|ms| latencies.record(ms)));
+///     s.spawn(|| [7, 5000].into_iter().for_each(|ms| latencies.record(ms)));
+/// });
+/// let snapshot = latencies.snapshot();
+/// assert_eq!(snapshot.counts(), &[2, 1, 1, 1]);
+/// assert_eq!(snapshot.count(), 5);
+/// assert_eq!(snapshot.sum(), 5562);
+/// assert_eq!(snapshot.quantile(0.5), Some(100));
+/// assert_eq!(snapshot.quantile(1.0), None);
+/// ```
+pub struct ShardedHistogram {
+    bounds: Box<[u64]>,
+    /// Every slot holds one counter per bucket followed by the sum of its samples, spread over `stride` lines.
+    lines: Box<[CachePadded<[AtomicU64; LINE_WORDS]>]>,
+    stride: usize,
+}
+
+impl ShardedHistogram {
+    /// Constructs a new, empty histogram with the bucket bounds, with one slot per available CPU.
+    ///
+    /// Panics if the bounds are not strictly ascending.
+    pub fn new(bounds: Vec<u64>) -> Self {
+        Self::with_shards(bounds, default_shards())
+    }
+
+    /// Constructs a new, empty histogram with the bucket bounds and the number of slots.
+    ///
+    /// Panics if the bounds are not strictly ascending or the number of slots is 0.
+    pub fn with_shards(bounds: Vec<u64>, shards: usize) -> Self {
+        if shards == 0 {
+            panic!("InvalidCapacity: ShardedHistogram needs at least one slot");
+        }
+        if bounds.windows(2).any(|w| w[0] >= w[1]) {
+            panic!("InvalidBounds: Bucket bounds must be strictly ascending");
+        }
+        // Buckets, the overflow bucket and the sum.
+        let stride = (bounds.len() + 2).div_ceil(LINE_WORDS);
+        Self {
+            bounds: bounds.into_boxed_slice(),
+            lines: (0..shards * stride)
+                .map(|_| CachePadded::default())
+                .collect(),
+            stride,
+        }
+    }
+
+    /// Returns the upper bounds of the buckets, without the overflow bucket.
+    pub fn bounds(&self) -> &[u64] {
+        &self.bounds
+    }
+
+    /// Returns the number of slots.
+    pub fn shard_count(&self) -> usize {
+        self.lines.len() / self.stride
+    }
+
+    /// Records a sample.
+    pub fn record(&self, value: u64) {
+        let base = thread_slot(self.shard_count()) * self.stride * LINE_WORDS;
+        let bucket = self.bounds.partition_point(|&bound| bound < value);
+        self.word(base + bucket).fetch_add(1, Ordering::Relaxed);
+        self.word(base + self.bounds.len() + 1)
+            .fetch_add(value, Ordering::Relaxed);
+    }
+
+    /// Returns the bucket counts and sum, added up over all slots.
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let words = self.bounds.len() + 2;
+        let mut totals = vec![0u64; words];
+        for slot in 0..self.shard_count() {
+            let base = slot * self.stride * LINE_WORDS;
+            for (i, total) in totals.iter_mut().enumerate() {
+                *total = total.wrapping_add(self.word(base + i).load(Ordering::Relaxed));
+            }
+        }
+        let sum = totals.pop().unwrap_or(0);
+        HistogramSnapshot {
+            bounds: self.bounds.clone(),
+            counts: totals.into_boxed_slice(),
+            sum,
+        }
+    }
+
+    /// Resets all buckets to 0.
+    pub fn reset(&self) {
+        for line in self.lines.iter() {
+            for word in line.iter() {
+                word.store(0, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn word(&self, i: usize) -> &AtomicU64 {
+        &self.lines[i / LINE_WORDS][i % LINE_WORDS]
+    }
+}
+
+impl fmt::Debug for ShardedHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ShardedHistogram")
+            .field(&self.snapshot())
+            .finish()
+    }
+}
+
+/// The totals of a [`ShardedHistogram`] at one point, created by [snapshot()](`ShardedHistogram::snapshot()`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistogramSnapshot {
+    bounds: Box<[u64]>,
+    counts: Box<[u64]>,
+    sum: u64,
+}
+
+impl HistogramSnapshot {
+    /// Returns the upper bounds of the buckets, without the overflow bucket.
+    pub fn bounds(&self) -> &[u64] {
+        &self.bounds
+    }
+
+    /// Returns the number of samples in every bucket, followed by the overflow bucket.
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    /// Returns the number of samples.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the sum of all samples, wrapping around on overflow.
+    pub fn sum(&self) -> u64 {
+        self.sum
+    }
+
+    /// Returns the mean of all samples, or `None` if there are none.
+    pub fn mean(&self) -> Option<f64> {
+        match self.count() {
+            0 => None,
+            count => Some(self.sum as f64 / count as f64),
+        }
+    }
+
+    /// Returns the upper bound of the bucket holding the sample at the quantile, between 0 and 1. Returns `None` if
+    /// there are no samples or the sample is in the overflow bucket.
+    pub fn quantile(&self, q: f64) -> Option<u64> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, &n) in self.counts.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return self.bounds.get(i).copied();
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,251 @@
+//! Vectorized kernels for the crate's hot loops, with scalar fallbacks.
+//!
+//! Without the `simd` feature, every function here is its [`scalar`] counterpart. With it, they use explicit vector
+//! instructions where the target has them:
+//!
+//! - On x86-64, word-wise bit operations and population counts dispatch at runtime to AVX2 and POPCNT versions when
+//!   the CPU supports them. Byte searches use SSE2 and [prefetch()] a prefetch instruction, which every x86-64 CPU
+//!   has.
+//! - On aarch64, byte searches use NEON. Word-wise operations stay scalar, since the compiler already vectorizes them
+//!   with the baseline NEON instructions.
+//!
+//! Other targets always use the scalar versions. [`BitSet`](`crate::bitset::BitSet`) and
+//! [`FixedBitSet`](`crate::bitset::FixedBitSet`) run their set operations and counts through these kernels. `cargo
+//! bench --features simd` compares both versions.
+
+/// Width of a control group for [match_group()], as in SwissTable-style hash maps that probe 16 slots at a time.
+pub const GROUP_WIDTH: usize = 16;
+
+/// The portable versions of the kernels.
+pub mod scalar {
+    use super::GROUP_WIDTH;
+
+    /// Sets every word of `a` to `a & b`, over the length of the shorter slice.
+    #[inline(always)]
+    pub fn and_assign(a: &mut [u64], b: &[u64]) {
+        a.iter_mut().zip(b).for_each(|(a, b)| *a &= b);
+    }
+
+    /// Sets every word of `a` to `a | b`, over the length of the shorter slice.
+    #[inline(always)]
+    pub fn or_assign(a: &mut [u64], b: &[u64]) {
+        a.iter_mut().zip(b).for_each(|(a, b)| *a |= b);
+    }
+
+    /// Sets every word of `a` to `a ^ b`, over the length of the shorter slice.
+    #[inline(always)]
+    pub fn xor_assign(a: &mut [u64], b: &[u64]) {
+        a.iter_mut().zip(b).for_each(|(a, b)| *a ^= b);
+    }
+
+    /// Sets every word of `a` to `a & !b`, over the length of the shorter slice.
+    #[inline(always)]
+    pub fn and_not_assign(a: &mut [u64], b: &[u64]) {
+        a.iter_mut().zip(b).for_each(|(a, b)| *a &= !b);
+    }
+
+    /// Returns the number of set bits in all words.
+    #[inline(always)]
+    pub fn count_ones(words: &[u64]) -> usize {
+        words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Returns the index of the first occurrence of the byte.
+    #[inline(always)]
+    pub fn find_byte(haystack: &[u8], needle: u8) -> Option<usize> {
+        haystack.iter().position(|&b| b == needle)
+    }
+
+    /// Returns a mask with bit `i` set for every byte `i` of the group equal to the tag.
+    #[inline(always)]
+    pub fn match_group(group: &[u8; GROUP_WIDTH], tag: u8) -> u16 {
+        group
+            .iter()
+            .enumerate()
+            .fold(0, |mask, (i, &b)| mask | (u16::from(b == tag) << i))
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    use super::{scalar, GROUP_WIDTH};
+
+    // Compiling the scalar loops with AVX2 enabled lets the compiler use 256-bit registers for them.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn and_assign(a: &mut [u64], b: &[u64]) {
+        scalar::and_assign(a, b)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn or_assign(a: &mut [u64], b: &[u64]) {
+        scalar::or_assign(a, b)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn xor_assign(a: &mut [u64], b: &[u64]) {
+        scalar::xor_assign(a, b)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn and_not_assign(a: &mut [u64], b: &[u64]) {
+        scalar::and_not_assign(a, b)
+    }
+
+    #[target_feature(enable = "popcnt")]
+    pub unsafe fn count_ones(words: &[u64]) -> usize {
+        scalar::count_ones(words)
+    }
+
+    pub fn find_byte(haystack: &[u8], needle: u8) -> Option<usize> {
+        let mut i = 0;
+        // SSE2 is part of the x86-64 baseline.
+        unsafe {
+            let needle = _mm_set1_epi8(needle as i8);
+            while i + 16 <= haystack.len() {
+                let chunk = _mm_loadu_si128(haystack.as_ptr().add(i) as *const __m128i);
+                let mask = _mm_movemask_epi8(_mm_cmpeq_epi8(chunk, needle));
+                if mask != 0 {
+                    return Some(i + mask.trailing_zeros() as usize);
+                }
+                i += 16;
+            }
+        }
+        scalar::find_byte(&haystack[i..], needle).map(|j| i + j)
+    }
+
+    pub fn match_group(group: &[u8; GROUP_WIDTH], tag: u8) -> u16 {
+        unsafe {
+            let group = _mm_loadu_si128(group.as_ptr() as *const __m128i);
+            _mm_movemask_epi8(_mm_cmpeq_epi8(group, _mm_set1_epi8(tag as i8))) as u16
+        }
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+mod neon {
+    use std::arch::aarch64::*;
+
+    use super::{scalar, GROUP_WIDTH};
+
+    pub fn find_byte(haystack: &[u8], needle: u8) -> Option<usize> {
+        let mut i = 0;
+        // NEON is part of the aarch64 baseline.
+        unsafe {
+            let needle_vec = vdupq_n_u8(needle);
+            while i + 16 <= haystack.len() {
+                let chunk = vld1q_u8(haystack.as_ptr().add(i));
+                if vmaxvq_u8(vceqq_u8(chunk, needle_vec)) != 0 {
+                    break;
+                }
+                i += 16;
+            }
+        }
+        scalar::find_byte(&haystack[i..], needle).map(|j| i + j)
+    }
+
+    pub fn match_group(group: &[u8; GROUP_WIDTH], tag: u8) -> u16 {
+        // NEON has no movemask, so every matching byte keeps one bit of its position and each half is summed up.
+        const BITS: [u8; 16] = [1, 2, 4, 8, 16, 32, 64, 128, 1, 2, 4, 8, 16, 32, 64, 128];
+        unsafe {
+            let eq = vceqq_u8(vld1q_u8(group.as_ptr()), vdupq_n_u8(tag));
+            let bits = vandq_u8(eq, vld1q_u8(BITS.as_ptr()));
+            u16::from(vaddv_u8(vget_low_u8(bits))) | u16::from(vaddv_u8(vget_high_u8(bits))) << 8
+        }
+    }
+}
+
+/// Calls the AVX2 version of a word-wise kernel if the CPU supports it, and the scalar one otherwise.
+macro_rules! dispatch_avx2 {
+    ($name:ident($($arg:expr),*)) => {{
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        if std::arch::is_x86_feature_detected!("avx2") {
+            // Just checked that the CPU supports AVX2.
+            return unsafe { x86::$name($($arg),*) };
+        }
+        scalar::$name($($arg),*)
+    }};
+}
+
+/// Sets every word of `a` to `a & b`, over the length of the shorter slice.
+pub fn and_assign(a: &mut [u64], b: &[u64]) {
+    dispatch_avx2!(and_assign(a, b))
+}
+
+/// Sets every word of `a` to `a | b`, over the length of the shorter slice.
+pub fn or_assign(a: &mut [u64], b: &[u64]) {
+    dispatch_avx2!(or_assign(a, b))
+}
+
+/// Sets every word of `a` to `a ^ b`, over the length of the shorter slice.
+pub fn xor_assign(a: &mut [u64], b: &[u64]) {
+    dispatch_avx2!(xor_assign(a, b))
+}
+
+/// Sets every word of `a` to `a & !b`, over the length of the shorter slice.
+pub fn and_not_assign(a: &mut [u64], b: &[u64]) {
+    dispatch_avx2!(and_not_assign(a, b))
+}
+
+/// Returns the number of set bits in all words.
+/// ```
+/// # use strctr::simd::count_ones;
+/// assert_eq!(count_ones(&[0b1011, u64::MAX]), 67);
+/// ```
+pub fn count_ones(words: &[u64]) -> usize {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if std::arch::is_x86_feature_detected!("popcnt") {
+        // Just checked that the CPU supports POPCNT.
+        return unsafe { x86::count_ones(words) };
+    }
+    scalar::count_ones(words)
+}
+
+/// Returns the index of the first occurrence of the byte.
+/// ```
+/// # use strctr::simd::find_byte;
+/// let buf = b"GET /index.html HTTP/1.1\r\n";
+/// assert_eq!(find_byte(buf, b'\r'), Some(24));
+/// assert_eq!(find_byte(buf, b'#'), None);
+/// ```
+pub fn find_byte(haystack: &[u8], needle: u8) -> Option<usize> {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    return x86::find_byte(haystack, needle);
+    #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+    return neon::find_byte(haystack, needle);
+    #[allow(unreachable_code)]
+    scalar::find_byte(haystack, needle)
+}
+
+/// Returns a mask with bit `i` set for every byte `i` of the group equal to the tag. Open-addressing hash maps use this
+/// to compare a key's tag against a whole group of slots at once while probing.
+/// ```
+/// # use strctr::simd::match_group;
+/// let mut group = [0u8; 16];
+/// group[3] = 0x2a;
+/// group[12] = 0x2a;
+/// assert_eq!(match_group(&group, 0x2a), 1 << 3 | 1 << 12);
+/// ```
+pub fn match_group(group: &[u8; GROUP_WIDTH], tag: u8) -> u16 {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    return x86::match_group(group, tag);
+    #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+    return neon::match_group(group, tag);
+    #[allow(unreachable_code)]
+    scalar::match_group(group, tag)
+}
+
+/// Hints the CPU to start loading the cache line at the address, for a read that follows soon. The pointer does not
+/// have to be valid; nothing is read from it.
+#[inline(always)]
+pub fn prefetch<T>(ptr: *const T) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    // Prefetching is an SSE instruction, part of the x86-64 baseline, and never faults.
+    unsafe {
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch::<_MM_HINT_T0>(ptr as *const i8);
+    }
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    let _ = ptr;
+}
@@ -0,0 +1,425 @@
+//! Ordered map backed by a skip list.
+//!
+//! Entries form a sorted linked list, and every entry is additionally linked on a random number of express levels,
+//! each level skipping about half of the entries of the level below. Searches start on the highest level and drop
+//! down whenever the next entry would overshoot, which takes `O(log n)` expected steps. Nodes live in an arena and
+//! link to each other by index.
+
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read, Write};
+use std::ops::{Bound, RangeBounds};
+
+use crate::hash::HashState;
+use crate::snapshot::{self, Codec, Snapshot, SnapshotError};
+
+/// Highest level an entry can be linked on. Plenty for any map that fits into memory.
+const MAX_LEVEL: usize = 32;
+
+/// End-of-list marker for links.
+const NIL: usize = usize::MAX;
+
+#[derive(Clone)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+    /// Successor on each level the node is linked on. The node's height is `next.len()`.
+    next: Vec<usize>,
+}
+
+/// An ordered map implemented as a skip list.
+#[derive(Clone)]
+pub struct SkipListMap<K, V> {
+    nodes: Vec<Option<Node<K, V>>>,
+    free: Vec<usize>,
+    /// First node on each level. Only has as many levels as the tallest node.
+    head: Vec<usize>,
+    len: usize,
+    rng: u64,
+}
+
+impl<K, V> Default for SkipListMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> SkipListMap<K, V> {
+    /// Constructs a new, empty map. Node heights are drawn from a randomly seeded generator, or one with a fixed seed
+    /// if the `deterministic` feature is enabled.
+    pub fn new() -> Self {
+        let seed = HashState::new().build_hasher().finish();
+        Self::with_seed(seed)
+    }
+
+    /// Constructs a new, empty map whose node heights are drawn from a generator with the given seed, making the
+    /// shape of the list reproducible.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            head: Vec::new(),
+            len: 0,
+            // Xorshift gets stuck on 0.
+            rng: seed | 1,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all entries from the map.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.head.clear();
+        self.len = 0;
+    }
+
+    /// Returns the number of levels currently in use, which is the height of the tallest node.
+    pub fn levels(&self) -> usize {
+        self.head.len()
+    }
+
+    /// Returns the entry with the smallest key.
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.entry(*self.head.first()?)
+    }
+
+    /// Returns the entry with the largest key.
+    /// ```
+    /// # use strctr::skiplist::SkipListMap;
+    /// let m: SkipListMap<_, _> = [(3, 'c'), (1, 'a'), (2, 'b')].into_iter().collect();
+    /// assert_eq!(m.first_key_value(), Some((&1, &'a')));
+    /// assert_eq!(m.last_key_value(), Some((&3, &'c')));
+    /// ```
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let mut cur = None;
+        for level in (0..self.head.len()).rev() {
+            while self.link(cur, level) != NIL {
+                cur = Some(self.link(cur, level));
+            }
+        }
+        self.entry(cur?)
+    }
+
+    /// Returns an iterator over the entries, in ascending key order.
+    /// ```
+    /// # use strctr::skiplist::SkipListMap;
+    /// let mut m = SkipListMap::new();
+    /// assert_eq!(m.iter().next(), None);
+    /// m.insert(1, "a");
+    /// assert_eq!(m.iter().collect::<Vec<_>>(), vec![(&1, &"a")]);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            map: self,
+            cur: self.head.first().copied().unwrap_or(NIL),
+            remaining: self.len,
+        }
+    }
+
+    /// Returns an iterator over the keys, in ascending order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values, in ascending key order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    fn node(&self, i: usize) -> &Node<K, V> {
+        self.nodes[i].as_ref().expect("live node")
+    }
+
+    fn node_mut(&mut self, i: usize) -> &mut Node<K, V> {
+        self.nodes[i].as_mut().expect("live node")
+    }
+
+    fn entry(&self, i: usize) -> Option<(&K, &V)> {
+        if i == NIL {
+            return None;
+        }
+        let node = self.node(i);
+        Some((&node.key, &node.value))
+    }
+
+    /// Returns the successor of `from` on the level, where `None` stands for the head.
+    fn link(&self, from: Option<usize>, level: usize) -> usize {
+        match from {
+            None => self.head[level],
+            Some(i) => self.node(i).next[level],
+        }
+    }
+
+    fn set_link(&mut self, from: Option<usize>, level: usize, to: usize) {
+        match from {
+            None => self.head[level] = to,
+            Some(i) => self.node_mut(i).next[level] = to,
+        }
+    }
+
+    /// Returns the last node on each level for which `before` holds, or `None` if that is the head. `before` has to
+    /// hold for a prefix of the list.
+    fn predecessors(&self, before: impl Fn(&K) -> bool) -> Vec<Option<usize>> {
+        let mut preds = vec![None; self.head.len()];
+        let mut cur = None;
+        for level in (0..self.head.len()).rev() {
+            loop {
+                let next = self.link(cur, level);
+                if next == NIL || !before(&self.node(next).key) {
+                    break;
+                }
+                cur = Some(next);
+            }
+            preds[level] = cur;
+        }
+        preds
+    }
+
+    /// Returns the first node for which `before` does not hold, or `NIL`.
+    fn first_after(&self, before: impl Fn(&K) -> bool) -> usize {
+        let mut cur = None;
+        for level in (0..self.head.len()).rev() {
+            loop {
+                let next = self.link(cur, level);
+                if next == NIL || !before(&self.node(next).key) {
+                    break;
+                }
+                cur = Some(next);
+            }
+        }
+        if self.head.is_empty() {
+            NIL
+        } else {
+            self.link(cur, 0)
+        }
+    }
+
+    /// Draws a height with `P(height > h) = 2^-h`.
+    fn random_level(&mut self) -> usize {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng = x;
+        (x.trailing_ones() as usize + 1).min(MAX_LEVEL)
+    }
+}
+
+impl<K: Ord, V> SkipListMap<K, V> {
+    /// Inserts a key-value pair into the map, returning the previous value if the key was already present.
+    /// ```
+    /// # use strctr::skiplist::SkipListMap;
+    /// let mut m = SkipListMap::new();
+    /// assert_eq!(m.insert("b", 1), None);
+    /// assert_eq!(m.insert("a", 2), None);
+    /// assert_eq!(m.insert("b", 3), Some(1));
+    /// assert_eq!(m.keys().collect::<Vec<_>>(), vec![&"a", &"b"]);
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut preds = self.predecessors(|k| *k < key);
+        if !self.head.is_empty() {
+            let found = self.link(preds[0], 0);
+            if found != NIL && self.node(found).key == key {
+                return Some(std::mem::replace(&mut self.node_mut(found).value, value));
+            }
+        }
+
+        let height = self.random_level();
+        while self.head.len() < height {
+            self.head.push(NIL);
+            preds.push(None);
+        }
+        let node = Node {
+            key,
+            value,
+            next: vec![NIL; height],
+        };
+        let i = match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = Some(node);
+                i
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        for (level, &pred) in preds.iter().enumerate().take(height) {
+            let next = self.link(pred, level);
+            self.node_mut(i).next[level] = next;
+            self.set_link(pred, level, i);
+        }
+        self.len += 1;
+        None
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let i = self.find(key)?;
+        Some(&self.node(i).value)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let i = self.find(key)?;
+        Some(&mut self.node_mut(i).value)
+    }
+
+    /// Returns whether the map contains the key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Removes the key from the map, returning its value if it was present.
+    /// ```
+    /// # use strctr::skiplist::SkipListMap;
+    /// let mut m: SkipListMap<_, _> = (0..10).map(|i| (i, i * i)).collect();
+    /// assert_eq!(m.remove(&3), Some(9));
+    /// assert_eq!(m.remove(&3), None);
+    /// assert_eq!(m.len(), 9);
+    /// ```
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let preds = self.predecessors(|k| k < key);
+        if self.head.is_empty() {
+            return None;
+        }
+        let found = self.link(preds[0], 0);
+        if found == NIL || self.node(found).key != *key {
+            return None;
+        }
+        let node = self.nodes[found].take().expect("live node");
+        for (level, &next) in node.next.iter().enumerate() {
+            self.set_link(preds[level], level, next);
+        }
+        while self.head.last() == Some(&NIL) {
+            self.head.pop();
+        }
+        self.free.push(found);
+        self.len -= 1;
+        Some(node.value)
+    }
+
+    /// Returns an iterator over the entries whose keys fall within the range, in ascending key order.
+    /// ```
+    /// # use strctr::skiplist::SkipListMap;
+    /// let m: SkipListMap<_, _> = (0..20).map(|i| (i, i * 10)).collect();
+    /// let keys: Vec<_> = m.range(5..9).map(|(k, _)| *k).collect();
+    /// assert_eq!(keys, vec![5, 6, 7, 8]);
+    /// assert_eq!(m.range(17..).count(), 3);
+    /// assert_eq!(m.range(..=1).count(), 2);
+    /// ```
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V, R> {
+        let cur = match range.start_bound() {
+            Bound::Included(start) => self.first_after(|k| k < start),
+            Bound::Excluded(start) => self.first_after(|k| k <= start),
+            Bound::Unbounded => self.head.first().copied().unwrap_or(NIL),
+        };
+        Range {
+            map: self,
+            cur,
+            range,
+        }
+    }
+
+    fn find(&self, key: &K) -> Option<usize> {
+        let i = self.first_after(|k| k < key);
+        (i != NIL && self.node(i).key == *key).then_some(i)
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SkipListMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (k, v) in iter {
+            map.insert(k, v);
+        }
+        map
+    }
+}
+
+impl<K: Ord + Codec, V: Codec> Snapshot for SkipListMap<K, V> {
+    const TAG: [u8; 4] = *b"SKPL";
+
+    fn write_payload<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        snapshot::write_entries(w, self.len(), self.iter())
+    }
+
+    fn read_payload<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let mut map = Self::new();
+        snapshot::read_entries(r, |k, v| map.insert(k, v))?;
+        Ok(map)
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for SkipListMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// In-order iterator over the entries of a [`SkipListMap`].
+pub struct Iter<'a, K, V> {
+    map: &'a SkipListMap<K, V>,
+    cur: usize,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.map.entry(self.cur)?;
+        self.cur = self.map.node(self.cur).next[0];
+        self.remaining -= 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SkipListMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over a key range of a [`SkipListMap`], created by [range()](`SkipListMap::range()`).
+pub struct Range<'a, K, V, R> {
+    map: &'a SkipListMap<K, V>,
+    cur: usize,
+    range: R,
+}
+
+impl<'a, K: Ord, V, R: RangeBounds<K>> Iterator for Range<'a, K, V, R> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (k, v) = self.map.entry(self.cur)?;
+        let in_range = match self.range.end_bound() {
+            Bound::Included(end) => k <= end,
+            Bound::Excluded(end) => k < end,
+            Bound::Unbounded => true,
+        };
+        if !in_range {
+            self.cur = NIL;
+            return None;
+        }
+        self.cur = self.map.node(self.cur).next[0];
+        Some((k, v))
+    }
+}
@@ -0,0 +1,271 @@
+//! Fixed-capacity circular buffer that keeps the last `N` elements pushed.
+//!
+//! Where a [`RingBuffer`](`crate::spsc::RingBuffer`) refuses new elements when full, a [`SlidingBuffer`] overwrites
+//! its oldest one. That makes it the window of rolling logs, telemetry samples and moving averages. The elements live
+//! inline, and pushing never allocates.
+
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::ops::Index;
+
+/// A circular buffer holding the `N` most recently pushed elements, iterated from oldest to newest.
+/// ```
+/// # use strctr::sliding::SlidingBuffer;
+/// let mut log: SlidingBuffer<&str, 3> = SlidingBuffer::new();
+/// for line in ["boot", "mount /", "start sshd", "login root"] {
+///     log.push(line);
+/// }
+/// assert_eq!(log.oldest(), Some(&"mount /"));
+/// assert_eq!(log.latest(), Some(&"login root"));
+/// assert_eq!(log.iter().copied().collect::<Vec<_>>(), vec!["mount /", "start sshd", "login root"]);
+/// ```
+pub struct SlidingBuffer<T, const N: usize> {
+    /// Slot `(start + i) % N` is initialized for every `i < len`.
+    slots: [MaybeUninit<T>; N],
+    /// Slot of the oldest element.
+    start: usize,
+    len: usize,
+}
+
+impl<T, const N: usize> Default for SlidingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> SlidingBuffer<T, N> {
+    /// Constructs a new, empty buffer with room for `N` elements.
+    ///
+    /// Panics if `N` is 0.
+    pub fn new() -> Self {
+        if N == 0 {
+            panic!("InvalidCapacity: SlidingBuffer needs room for at least one element");
+        }
+        Self {
+            slots: std::array::from_fn(|_| MaybeUninit::uninit()),
+            start: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of elements the buffer keeps.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of elements in the buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns whether the buffer is full, so that the next push overwrites the oldest element.
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Appends the element. If the buffer is full, the oldest element is removed to make room and returned.
+    /// ```
+    /// # use strctr::sliding::SlidingBuffer;
+    /// // Moving average over the last 4 samples.
+    /// let mut window: SlidingBuffer<f64, 4> = SlidingBuffer::new();
+    /// let mut sum = 0.0;
+    /// for sample in [2.0, 4.0, 6.0, 8.0, 10.0, 12.0] {
+    ///     sum += sample;
+    ///     if let Some(old) = window.push(sample) {
+    ///         sum -= old;
+    ///     }
+    /// }
+    /// assert_eq!(sum / window.len() as f64, 9.0);
+    /// ```
+    pub fn push(&mut self, value: T) -> Option<T> {
+        if self.len < N {
+            self.slots[(self.start + self.len) % N].write(value);
+            self.len += 1;
+            return None;
+        }
+        // The buffer is full, so the oldest slot is initialized; it becomes the newest.
+        let old = std::mem::replace(&mut self.slots[self.start], MaybeUninit::new(value));
+        self.start = (self.start + 1) % N;
+        Some(unsafe { old.assume_init() })
+    }
+
+    /// Removes the oldest element and returns it, or `None` if the buffer is empty.
+    pub fn pop_oldest(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        // Slot start holds the oldest element, and moving start past it marks it uninitialized.
+        let value = unsafe { self.slots[self.start].assume_init_read() };
+        self.start = (self.start + 1) % N;
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Removes the newest element and returns it, or `None` if the buffer is empty.
+    pub fn pop_latest(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        // The slot held the newest element, and shortening len marks it uninitialized.
+        Some(unsafe { self.slots[(self.start + self.len) % N].assume_init_read() })
+    }
+
+    /// Returns the oldest element.
+    pub fn oldest(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    /// Returns the most recently pushed element.
+    pub fn latest(&self) -> Option<&T> {
+        self.get(self.len.checked_sub(1)?)
+    }
+
+    /// Returns the element at the position, counting from the oldest one.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        // Positions below len are initialized.
+        Some(unsafe { self.slots[(self.start + index) % N].assume_init_ref() })
+    }
+
+    /// Returns the elements as two slices, which hold them from oldest to newest when joined.
+    /// ```
+    /// # use strctr::sliding::SlidingBuffer;
+    /// let b: SlidingBuffer<u8, 4> = (1..=6).collect();
+    /// assert_eq!(b.as_slices(), (&[3, 4][..], &[5, 6][..]));
+    /// ```
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let first = self.len.min(N - self.start);
+        let (tail, head) = self.slots.split_at(self.start);
+        // The first slices of both halves hold the len initialized slots, and MaybeUninit<T> has T's layout.
+        unsafe {
+            (
+                std::slice::from_raw_parts(head.as_ptr().cast::<T>(), first),
+                std::slice::from_raw_parts(tail.as_ptr().cast::<T>(), self.len - first),
+            )
+        }
+    }
+
+    /// Returns an iterator over the elements, from oldest to newest.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (first, second) = self.as_slices();
+        first.iter().chain(second)
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        while self.pop_oldest().is_some() {}
+        self.start = 0;
+    }
+}
+
+/// Iterator over the elements of a [`SlidingBuffer`], from oldest to newest.
+pub type Iter<'a, T> = std::iter::Chain<std::slice::Iter<'a, T>, std::slice::Iter<'a, T>>;
+
+impl<T, const N: usize> Drop for SlidingBuffer<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for SlidingBuffer<T, N> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T, const N: usize> Index<usize> for SlidingBuffer<T, N> {
+    type Output = T;
+
+    /// Returns the element at the position, counting from the oldest one.
+    ///
+    /// Panics if the position is out of bounds.
+    fn index(&self, index: usize) -> &T {
+        match self.get(index) {
+            Some(value) => value,
+            None => panic!(
+                "OutOfBounds: Index {} is out of bounds for length {}",
+                index, self.len
+            ),
+        }
+    }
+}
+
+impl<T, const N: usize> Extend<T> for SlidingBuffer<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for SlidingBuffer<T, N> {
+    /// Collects the last `N` elements of the iterator.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut buffer = Self::new();
+        buffer.extend(iter);
+        buffer
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a SlidingBuffer<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for SlidingBuffer<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for SlidingBuffer<T, N> {}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for SlidingBuffer<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Sync, const N: usize> rayon::iter::IntoParallelIterator for &'a SlidingBuffer<T, N> {
+    type Item = &'a T;
+    type Iter = rayon::iter::Chain<rayon::slice::Iter<'a, T>, rayon::slice::Iter<'a, T>>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        use rayon::iter::ParallelIterator;
+
+        let (first, second) = self.as_slices();
+        first.into_par_iter().chain(second)
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl<T: quickcheck::Arbitrary, const N: usize> quickcheck::Arbitrary for SlidingBuffer<T, N> {
+    /// Generates a buffer of up to `N` arbitrary elements. Up to `2 * N` elements are pushed, so the elements wrap
+    /// around the end of the slots as often as not.
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let pushes = usize::arbitrary(g) % (2 * N + 1);
+        (0..pushes).map(|_| T::arbitrary(g)).collect()
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let elements: Vec<T> = self.iter().cloned().collect();
+        Box::new(
+            elements
+                .shrink()
+                .map(|elements| elements.into_iter().collect()),
+        )
+    }
+}
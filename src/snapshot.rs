@@ -0,0 +1,301 @@
+//! Compact binary snapshots of the crate's structures, for checkpointing without serde.
+//!
+//! A snapshot is a header followed by the structure's payload. The header holds the magic bytes `STRC`, the format
+//! version as a little-endian `u16`, and a four-byte tag naming the structure, so loading a file into the wrong type or
+//! a newer format fails cleanly instead of producing garbage. Structures implementing [`Snapshot`] write themselves
+//! with [save_to()](`Snapshot::save_to()`) and are rebuilt with [load_from()](`Snapshot::load_from()`); their elements
+//! are written with [`Codec`]. Integers are stored little-endian at their full width, while lengths and `usize` values
+//! use a variable-length encoding of 1 to 10 bytes.
+//!
+//! Payloads are written straight to the writer, in many small writes: wrap files in a [`BufWriter`](`std::io::BufWriter`)
+//! or [`BufReader`](`std::io::BufReader`).
+//! ```
+//! # use strctr::rbtree::RBTreeMap;
+//! # use strctr::snapshot::{Snapshot, SnapshotError};
+//! # use strctr::trie::Trie;
+//! let index: RBTreeMap<u32, String> = (0..100).map(|i| (i, format!("doc{i}"))).collect();
+//! let mut file = Vec::new();
+//! index.save_to(&mut file).unwrap();
+//!
+//! let loaded = RBTreeMap::<u32, String>::load_from(file.as_slice()).unwrap();
+//! assert!(loaded.iter().eq(index.iter()));
+//! assert!(matches!(Trie::<u32>::load_from(file.as_slice()), Err(SnapshotError::WrongStructure { .. })));
+//! ```
+
+use std::io::{self, Read, Write};
+
+/// Magic bytes starting every snapshot.
+pub const MAGIC: [u8; 4] = *b"STRC";
+
+/// Version of the format written by this release. Snapshots of later versions are rejected.
+pub const VERSION: u16 = 1;
+
+/// List of errors that could occur when loading a snapshot.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// Reading failed, or the input ended early.
+    Io(io::Error),
+    /// The input does not start with [`MAGIC`].
+    BadMagic,
+    /// The snapshot was written by a newer format version.
+    UnsupportedVersion(u16),
+    /// The snapshot holds a different structure.
+    WrongStructure { expected: [u8; 4], found: [u8; 4] },
+    /// The payload is malformed, like a duplicate key or an invalid UTF-8 string.
+    Corrupt,
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+/// A structure that can be saved to and loaded from a snapshot.
+pub trait Snapshot: Sized {
+    /// Tag identifying the structure in the header.
+    const TAG: [u8; 4];
+
+    /// Writes the payload, without the header.
+    fn write_payload<W: Write>(&self, w: &mut W) -> io::Result<()>;
+
+    /// Reads a payload written by [write_payload()](`Self::write_payload()`).
+    fn read_payload<R: Read>(r: &mut R) -> Result<Self, SnapshotError>;
+
+    /// Writes the header and the payload.
+    fn save_to<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(&MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        w.write_all(&Self::TAG)?;
+        self.write_payload(&mut w)
+    }
+
+    /// Reads a snapshot, checking its header.
+    fn load_from<R: Read>(mut r: R) -> Result<Self, SnapshotError> {
+        if <[u8; 4]>::decode(&mut r)? != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let version = u16::decode(&mut r)?;
+        if version > VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let found = <[u8; 4]>::decode(&mut r)?;
+        if found != Self::TAG {
+            return Err(SnapshotError::WrongStructure {
+                expected: Self::TAG,
+                found,
+            });
+        }
+        Self::read_payload(&mut r)
+    }
+}
+
+/// A value that can be stored in a snapshot.
+pub trait Codec: Sized {
+    /// Writes the value.
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()>;
+
+    /// Reads a value written by [encode()](`Self::encode()`).
+    fn decode<R: Read>(r: &mut R) -> Result<Self, SnapshotError>;
+}
+
+macro_rules! fixed_width {
+    ($($t:ty),*) => {
+        $(
+            impl Codec for $t {
+                fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+                    w.write_all(&self.to_le_bytes())
+                }
+
+                fn decode<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+                    let mut bytes = [0; std::mem::size_of::<$t>()];
+                    r.read_exact(&mut bytes)?;
+                    Ok(<$t>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+fixed_width!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+impl Codec for usize {
+    /// Writes seven bits per byte, lowest first, with the high bit set on all but the last byte.
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut n = *self as u64;
+        while n >= 0x80 {
+            w.write_all(&[n as u8 | 0x80])?;
+            n >>= 7;
+        }
+        w.write_all(&[n as u8])
+    }
+
+    fn decode<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = u8::decode(r)?;
+            if shift == 63 && byte > 1 {
+                return Err(SnapshotError::Corrupt);
+            }
+            n |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return usize::try_from(n).map_err(|_| SnapshotError::Corrupt);
+            }
+        }
+        Err(SnapshotError::Corrupt)
+    }
+}
+
+impl Codec for isize {
+    /// Zigzag-maps the value onto a `usize`, so small magnitudes stay short.
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (((*self << 1) ^ (*self >> (isize::BITS - 1))) as usize).encode(w)
+    }
+
+    fn decode<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let n = usize::decode(r)?;
+        Ok((n >> 1) as isize ^ -((n & 1) as isize))
+    }
+}
+
+impl Codec for bool {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        u8::from(*self).encode(w)
+    }
+
+    fn decode<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        match u8::decode(r)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(SnapshotError::Corrupt),
+        }
+    }
+}
+
+impl Codec for char {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        u32::from(*self).encode(w)
+    }
+
+    fn decode<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        char::from_u32(u32::decode(r)?).ok_or(SnapshotError::Corrupt)
+    }
+}
+
+impl Codec for () {
+    fn encode<W: Write>(&self, _: &mut W) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn decode<R: Read>(_: &mut R) -> Result<Self, SnapshotError> {
+        Ok(())
+    }
+}
+
+impl<const N: usize> Codec for [u8; N] {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(self)
+    }
+
+    fn decode<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let mut bytes = [0; N];
+        r.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl Codec for String {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.len().encode(w)?;
+        w.write_all(self.as_bytes())
+    }
+
+    fn decode<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let len = usize::decode(r)?;
+        let mut bytes = Vec::new();
+        // take() keeps a corrupt length from allocating more than the input holds.
+        r.take(len as u64).read_to_end(&mut bytes)?;
+        if bytes.len() != len {
+            return Err(SnapshotError::Io(io::ErrorKind::UnexpectedEof.into()));
+        }
+        String::from_utf8(bytes).map_err(|_| SnapshotError::Corrupt)
+    }
+}
+
+impl<T: Codec> Codec for Vec<T> {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.len().encode(w)?;
+        self.iter().try_for_each(|x| x.encode(w))
+    }
+
+    fn decode<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let len = usize::decode(r)?;
+        (0..len).map(|_| T::decode(r)).collect()
+    }
+}
+
+impl<T: Codec> Codec for Option<T> {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.is_some().encode(w)?;
+        self.as_ref().map_or(Ok(()), |x| x.encode(w))
+    }
+
+    fn decode<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        Ok(if bool::decode(r)? {
+            Some(T::decode(r)?)
+        } else {
+            None
+        })
+    }
+}
+
+impl<A: Codec, B: Codec> Codec for (A, B) {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.0.encode(w)?;
+        self.1.encode(w)
+    }
+
+    fn decode<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        Ok((A::decode(r)?, B::decode(r)?))
+    }
+}
+
+/// Writes a map's length and entries.
+pub(crate) fn write_entries<'a, W, K, V>(
+    w: &mut W,
+    len: usize,
+    entries: impl Iterator<Item = (&'a K, &'a V)>,
+) -> io::Result<()>
+where
+    W: Write,
+    K: Codec + 'a,
+    V: Codec + 'a,
+{
+    len.encode(w)?;
+    for (k, v) in entries {
+        k.encode(w)?;
+        v.encode(w)?;
+    }
+    Ok(())
+}
+
+/// Reads entries written by [`write_entries()`] and inserts them. `insert` returns the replaced value, since a
+/// duplicate key means the payload is corrupt.
+pub(crate) fn read_entries<R, K, V, Old>(
+    r: &mut R,
+    mut insert: impl FnMut(K, V) -> Option<Old>,
+) -> Result<(), SnapshotError>
+where
+    R: Read,
+    K: Codec,
+    V: Codec,
+{
+    let len = usize::decode(r)?;
+    for _ in 0..len {
+        let (k, v) = <(K, V)>::decode(r)?;
+        if insert(k, v).is_some() {
+            return Err(SnapshotError::Corrupt);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,620 @@
+//! Vector and map that keep their elements sorted, for small to medium collections.
+//!
+//! Both store their elements in one contiguous `Vec` and find positions by binary search. Lookups touch a few cache
+//! lines instead of chasing node pointers, and iteration is a slice walk, so up to some thousands of elements they beat
+//! tree maps on everything but insertion and removal, which shift the elements behind the position in linear time.
+//! Building from an iterator sorts once instead of inserting one by one.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::ops::{Bound, Index, RangeBounds};
+
+use crate::snapshot::{self, Codec, Snapshot, SnapshotError};
+
+/// Returns the positions of the items whose keys fall within the range.
+fn bounds<T, K: Ord, R: RangeBounds<K>>(
+    items: &[T],
+    key: impl Fn(&T) -> &K,
+    range: &R,
+) -> std::ops::Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(start) => items.partition_point(|x| key(x) < start),
+        Bound::Excluded(start) => items.partition_point(|x| key(x) <= start),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(end) => items.partition_point(|x| key(x) <= end),
+        Bound::Excluded(end) => items.partition_point(|x| key(x) < end),
+        Bound::Unbounded => items.len(),
+    };
+    start..end.max(start)
+}
+
+/// A vector whose elements are always in ascending order. It may hold equal elements; the `_unique` and `replace`
+/// variants of insertion keep it free of them.
+/// ```
+/// # use strctr::sorted_vec::SortedVec;
+/// let mut v = SortedVec::new();
+/// v.insert(30);
+/// v.insert(10);
+/// v.insert(20);
+/// v.insert(10);
+/// assert_eq!(v.as_slice(), &[10, 10, 20, 30]);
+/// assert!(v.contains(&20));
+/// assert_eq!(v.range(15..), &[20, 30]);
+/// assert!(!v.insert_unique(30));
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SortedVec<T> {
+    items: Vec<T>,
+}
+
+impl<T> Default for SortedVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SortedVec<T> {
+    /// Constructs a new, empty vector.
+    pub fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    /// Constructs a new, empty vector with room for `capacity` elements.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            items: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns whether the vector is empty.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the elements in ascending order.
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Returns an iterator over the elements in ascending order.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// Returns the smallest element.
+    pub fn first(&self) -> Option<&T> {
+        self.items.first()
+    }
+
+    /// Returns the largest element.
+    pub fn last(&self) -> Option<&T> {
+        self.items.last()
+    }
+
+    /// Removes the element at the index and returns it.
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn remove_index(&mut self, index: usize) -> T {
+        self.items.remove(index)
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// Keeps only the elements the predicate returns `true` for.
+    pub fn retain(&mut self, pred: impl FnMut(&T) -> bool) {
+        self.items.retain(pred);
+    }
+
+    /// Returns the elements as a `Vec`, in ascending order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+}
+
+impl<T: Ord> SortedVec<T> {
+    /// Searches for the element like [`slice::binary_search()`]: returns `Ok` with the position of an equal element,
+    /// or `Err` with the position where it would be inserted.
+    pub fn binary_search(&self, value: &T) -> Result<usize, usize> {
+        self.items.binary_search(value)
+    }
+
+    /// Returns whether an equal element is present.
+    pub fn contains(&self, value: &T) -> bool {
+        self.binary_search(value).is_ok()
+    }
+
+    /// Returns the position of the first element equal to the value, if any.
+    pub fn position(&self, value: &T) -> Option<usize> {
+        let i = self.items.partition_point(|x| x < value);
+        (self.items.get(i) == Some(value)).then_some(i)
+    }
+
+    /// Inserts the element after all equal ones and returns its position.
+    pub fn insert(&mut self, value: T) -> usize {
+        let i = self.items.partition_point(|x| x <= &value);
+        self.items.insert(i, value);
+        i
+    }
+
+    /// Inserts the element unless an equal one is present. Returns whether it was inserted.
+    pub fn insert_unique(&mut self, value: T) -> bool {
+        match self.binary_search(&value) {
+            Ok(_) => false,
+            Err(i) => {
+                self.items.insert(i, value);
+                true
+            }
+        }
+    }
+
+    /// Inserts the element, replacing an equal one if present, and returns the replaced element.
+    /// ```
+    /// # use strctr::sorted_vec::SortedVec;
+    /// # use std::cmp::Ordering;
+    /// #[derive(Debug, PartialEq, Eq)]
+    /// struct Version(u32, &'static str);
+    /// # impl PartialOrd for Version {
+    /// #     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    /// #         Some(self.cmp(other))
+    /// #     }
+    /// # }
+    /// // Ordered by number only.
+    /// impl Ord for Version {
+    ///     fn cmp(&self, other: &Self) -> Ordering {
+    ///         self.0.cmp(&other.0)
+    ///     }
+    /// }
+    ///
+    /// let mut v = SortedVec::new();
+    /// v.replace(Version(2, "draft"));
+    /// assert_eq!(v.replace(Version(2, "final")), Some(Version(2, "draft")));
+    /// assert_eq!(v.as_slice(), &[Version(2, "final")]);
+    /// ```
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        match self.binary_search(&value) {
+            Ok(i) => Some(std::mem::replace(&mut self.items[i], value)),
+            Err(i) => {
+                self.items.insert(i, value);
+                None
+            }
+        }
+    }
+
+    /// Removes one element equal to the value and returns it.
+    pub fn remove(&mut self, value: &T) -> Option<T> {
+        let i = self.binary_search(value).ok()?;
+        Some(self.items.remove(i))
+    }
+
+    /// Returns the elements that fall within the range.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> &[T] {
+        &self.items[bounds(&self.items, |x| x, &range)]
+    }
+
+    /// Removes consecutive equal elements, keeping the first of each run, so no two elements are equal.
+    pub fn dedup(&mut self) {
+        self.items.dedup();
+    }
+}
+
+impl<T: Ord> From<Vec<T>> for SortedVec<T> {
+    /// Sorts the elements, keeping equal ones in their order.
+    fn from(mut items: Vec<T>) -> Self {
+        items.sort();
+        Self { items }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SortedVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Vec::from_iter(iter).into()
+    }
+}
+
+impl<T: Ord> Extend<T> for SortedVec<T> {
+    /// Appends the elements and sorts once, which beats inserting them one by one.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.items.extend(iter);
+        self.items.sort();
+    }
+}
+
+impl<T> Index<usize> for SortedVec<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.items[index]
+    }
+}
+
+impl<T> IntoIterator for SortedVec<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SortedVec<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl<T: Ord + Codec> Snapshot for SortedVec<T> {
+    const TAG: [u8; 4] = *b"SRTV";
+
+    fn write_payload<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.items.encode(w)
+    }
+
+    fn read_payload<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let items = Vec::<T>::decode(r)?;
+        if items.windows(2).any(|w| w[0] > w[1]) {
+            return Err(SnapshotError::Corrupt);
+        }
+        Ok(Self { items })
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SortedVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// A map backed by a vector of entries sorted by key.
+/// ```
+/// # use strctr::sorted_vec::SortedMap;
+/// let mut ports = SortedMap::new();
+/// ports.insert(443, "https");
+/// ports.insert(22, "ssh");
+/// ports.insert(80, "http");
+/// assert_eq!(ports.get(&80), Some(&"http"));
+/// assert_eq!(ports.insert(80, "www"), Some("http"));
+/// let well_known: Vec<_> = ports.range(..100).map(|(port, _)| *port).collect();
+/// assert_eq!(well_known, vec![22, 80]);
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SortedMap<K, V> {
+    entries: Vec<(K, V)>,
+}
+
+impl<K, V> Default for SortedMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> SortedMap<K, V> {
+    /// Constructs a new, empty map.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Constructs a new, empty map with room for `capacity` entries.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entries in ascending key order.
+    pub fn as_slice(&self) -> &[(K, V)] {
+        &self.entries
+    }
+
+    /// Returns an iterator over the entries in ascending key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(self.entries.iter())
+    }
+
+    /// Returns an iterator over the keys in ascending order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values in ascending key order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    /// Returns an iterator over mutable references to the values in ascending key order.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.entries.iter_mut().map(|(_, v)| v)
+    }
+
+    /// Returns the entry with the smallest key.
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.entries.first().map(|(k, v)| (k, v))
+    }
+
+    /// Returns the entry with the largest key.
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.entries.last().map(|(k, v)| (k, v))
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Keeps only the entries the predicate returns `true` for.
+    pub fn retain(&mut self, mut pred: impl FnMut(&K, &mut V) -> bool) {
+        self.entries.retain_mut(|(k, v)| pred(k, v));
+    }
+
+    /// Returns the entries as a `Vec`, in ascending key order.
+    pub fn into_vec(self) -> Vec<(K, V)> {
+        self.entries
+    }
+}
+
+impl<K: Ord, V> SortedMap<K, V> {
+    fn search(&self, key: &K) -> Result<usize, usize> {
+        self.entries.binary_search_by(|(k, _)| k.cmp(key))
+    }
+
+    /// Inserts the entry and returns the previous value of the key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.search(&key) {
+            Ok(i) => Some(std::mem::replace(&mut self.entries[i].1, value)),
+            Err(i) => {
+                self.entries.insert(i, (key, value));
+                None
+            }
+        }
+    }
+
+    /// Returns the value of the key.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let i = self.search(key).ok()?;
+        Some(&self.entries[i].1)
+    }
+
+    /// Returns a mutable reference to the value of the key.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let i = self.search(key).ok()?;
+        Some(&mut self.entries[i].1)
+    }
+
+    /// Returns the value of the key, inserting the function's result first if the key is absent.
+    /// ```
+    /// # use strctr::sorted_vec::SortedMap;
+    /// let mut counts = SortedMap::new();
+    /// for word in "a rose is a rose".split(' ') {
+    ///     *counts.get_or_insert_with(word, || 0) += 1;
+    /// }
+    /// assert_eq!(counts.as_slice(), &[("a", 2), ("is", 1), ("rose", 2)]);
+    /// ```
+    pub fn get_or_insert_with(&mut self, key: K, f: impl FnOnce() -> V) -> &mut V {
+        let i = match self.search(&key) {
+            Ok(i) => i,
+            Err(i) => {
+                self.entries.insert(i, (key, f()));
+                i
+            }
+        };
+        &mut self.entries[i].1
+    }
+
+    /// Returns whether the map contains the key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.search(key).is_ok()
+    }
+
+    /// Removes the key and returns its value.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let i = self.search(key).ok()?;
+        Some(self.entries.remove(i).1)
+    }
+
+    /// Returns an iterator over the entries whose keys fall within the range, in ascending key order.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Iter<'_, K, V> {
+        Iter(self.entries[bounds(&self.entries, |(k, _)| k, &range)].iter())
+    }
+}
+
+impl<K: Ord, V> From<Vec<(K, V)>> for SortedMap<K, V> {
+    /// Sorts the entries by key. Of entries with equal keys, the last one wins, like repeated insertion.
+    fn from(mut entries: Vec<(K, V)>) -> Self {
+        entries.reverse();
+        // The stable sort keeps the last entry of every key first, which dedup keeps.
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries.dedup_by(|later, earlier| later.0 == earlier.0);
+        Self { entries }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SortedMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Vec::from_iter(iter).into()
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for SortedMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Ord, V> Index<&K> for SortedMap<K, V> {
+    type Output = V;
+
+    /// Returns the value of the key.
+    ///
+    /// Panics if the key is absent.
+    fn index(&self, key: &K) -> &V {
+        self.get(key)
+            .expect("InvalidKey: Key is not present in the map")
+    }
+}
+
+impl<K, V> IntoIterator for SortedMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = std::vec::IntoIter<(K, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SortedMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K: Ord + Codec, V: Codec> Snapshot for SortedMap<K, V> {
+    const TAG: [u8; 4] = *b"SRTM";
+
+    fn write_payload<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        snapshot::write_entries(w, self.len(), self.iter())
+    }
+
+    fn read_payload<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let mut map = Self::new();
+        snapshot::read_entries(r, |k, v| map.insert(k, v))?;
+        Ok(map)
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for SortedMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over the entries of a [`SortedMap`], created by [iter()](`SortedMap::iter()`) and
+/// [range()](`SortedMap::range()`).
+#[derive(Clone)]
+pub struct Iter<'a, K, V>(std::slice::Iter<'a, (K, V)>);
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+#[cfg(feature = "rayon")]
+impl<T: Send> rayon::iter::IntoParallelIterator for SortedVec<T> {
+    type Item = T;
+    type Iter = rayon::vec::IntoIter<T>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.items.into_par_iter()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Sync> rayon::iter::IntoParallelIterator for &'a SortedVec<T> {
+    type Item = &'a T;
+    type Iter = rayon::slice::Iter<'a, T>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.items.as_slice().into_par_iter()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<K: Send, V: Send> rayon::iter::IntoParallelIterator for SortedMap<K, V> {
+    type Item = (K, V);
+    type Iter = rayon::vec::IntoIter<(K, V)>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.entries.into_par_iter()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, K: Sync, V: Sync> rayon::iter::IntoParallelIterator for &'a SortedMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type Iter = rayon::iter::Map<rayon::slice::Iter<'a, (K, V)>, fn(&'a (K, V)) -> (&'a K, &'a V)>;
+
+    /// Returns a parallel iterator over the entries. Order-preserving adapters like `collect()` into a [`Vec`] keep
+    /// them in ascending key order.
+    /// ```
+    /// # use strctr::sorted_vec::SortedMap;
+    /// use rayon::prelude::*;
+    ///
+    /// let prices: SortedMap<&str, u32> = SortedMap::from(vec![("pear", 3), ("apple", 2), ("fig", 7)]);
+    /// let expensive: Vec<&str> = prices.par_iter().filter(|(_, &p)| p > 2).map(|(&k, _)| k).collect();
+    /// assert_eq!(expensive, vec!["fig", "pear"]);
+    /// ```
+    fn into_par_iter(self) -> Self::Iter {
+        use rayon::iter::ParallelIterator;
+
+        self.entries.as_slice().into_par_iter().map(|(k, v)| (k, v))
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl<T: quickcheck::Arbitrary + Ord> quickcheck::Arbitrary for SortedVec<T> {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        Self::from(Vec::<T>::arbitrary(g))
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.items.shrink().map(Self::from))
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl<K, V> quickcheck::Arbitrary for SortedMap<K, V>
+where
+    K: quickcheck::Arbitrary + Ord,
+    V: quickcheck::Arbitrary,
+{
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        Self::from(Vec::<(K, V)>::arbitrary(g))
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.entries.shrink().map(Self::from))
+    }
+}
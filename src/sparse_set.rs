@@ -0,0 +1,197 @@
+//! Sparse set of small integer IDs, the membership structure of entity-component systems.
+//!
+//! The set keeps its members packed in a dense array and maps every possible ID to a member's position in a sparse
+//! array. Insertion, removal and lookup take constant time, and iteration runs over the dense array only, no matter how
+//! large the IDs are. The sparse array grows to the largest ID inserted, so IDs should be small: indices, not hashes.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::snapshot::{Codec, Snapshot, SnapshotError};
+
+/// Bound on the IDs a snapshot may hold. The sparse array of a set grows to its largest ID, so a corrupt snapshot
+/// could otherwise make loading allocate any amount of memory.
+pub const MAX_SNAPSHOT_ID: usize = u32::MAX as usize;
+
+/// A set of `usize` IDs. Iteration order is insertion order until an ID is removed, which moves the last member into
+/// its place.
+/// ```
+/// # use strctr::sparse_set::SparseSet;
+/// let mut alive = SparseSet::new();
+/// alive.insert(3);
+/// alive.insert(10);
+/// alive.insert(7);
+/// assert!(alive.contains(10));
+/// assert!(alive.remove(3));
+/// assert!(!alive.contains(3));
+/// assert_eq!(alive.as_slice(), &[7, 10]);
+/// ```
+#[derive(Clone)]
+pub struct SparseSet {
+    /// The members, packed.
+    dense: Vec<usize>,
+    /// For every ID below its length, the ID's position in `dense`. Entries of IDs that are not members hold stale
+    /// positions, which [contains()](`Self::contains()`) rejects by checking `dense`.
+    sparse: Vec<usize>,
+}
+
+impl Default for SparseSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SparseSet {
+    /// Constructs a new, empty set.
+    pub fn new() -> Self {
+        Self {
+            dense: Vec::new(),
+            sparse: Vec::new(),
+        }
+    }
+
+    /// Constructs a new, empty set that holds the IDs `0..universe` without reallocating.
+    pub fn with_capacity(universe: usize) -> Self {
+        Self {
+            dense: Vec::with_capacity(universe),
+            sparse: vec![0; universe],
+        }
+    }
+
+    /// Returns the number of members.
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    /// Returns whether the set has no members.
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    /// Returns whether the ID is a member.
+    pub fn contains(&self, id: usize) -> bool {
+        self.index_of(id).is_some()
+    }
+
+    /// Returns the ID's position in [as_slice()](`Self::as_slice()`), if it is a member. ECS frameworks store the
+    /// components of an entity at this position in parallel arrays.
+    pub fn index_of(&self, id: usize) -> Option<usize> {
+        let i = *self.sparse.get(id)?;
+        (self.dense.get(i) == Some(&id)).then_some(i)
+    }
+
+    /// Adds the ID. Returns `false` if it was a member already.
+    pub fn insert(&mut self, id: usize) -> bool {
+        if self.contains(id) {
+            return false;
+        }
+        if id >= self.sparse.len() {
+            self.sparse.resize(id + 1, 0);
+        }
+        self.sparse[id] = self.dense.len();
+        self.dense.push(id);
+        true
+    }
+
+    /// Removes the ID, moving the last member into its position. Returns `false` if it was not a member.
+    /// ```
+    /// # use strctr::sparse_set::SparseSet;
+    /// let mut s: SparseSet = [1, 2, 3].into_iter().collect();
+    /// assert!(s.remove(1));
+    /// assert!(!s.remove(1));
+    /// assert_eq!(s.as_slice(), &[3, 2]);
+    /// assert_eq!(s.index_of(3), Some(0));
+    /// ```
+    pub fn remove(&mut self, id: usize) -> bool {
+        let Some(i) = self.index_of(id) else {
+            return false;
+        };
+        self.dense.swap_remove(i);
+        if let Some(&moved) = self.dense.get(i) {
+            self.sparse[moved] = i;
+        }
+        true
+    }
+
+    /// Removes all members in constant time, keeping the allocations.
+    pub fn clear(&mut self) {
+        self.dense.clear();
+    }
+
+    /// Returns the members in iteration order.
+    pub fn as_slice(&self) -> &[usize] {
+        &self.dense
+    }
+
+    /// Returns an iterator over the members.
+    pub fn iter(&self) -> std::iter::Copied<std::slice::Iter<'_, usize>> {
+        self.dense.iter().copied()
+    }
+}
+
+impl Snapshot for SparseSet {
+    const TAG: [u8; 4] = *b"SPRS";
+
+    /// Writes the members in iteration order, which loading restores.
+    fn write_payload<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.dense.encode(w)
+    }
+
+    /// Rejects IDs from [`MAX_SNAPSHOT_ID`] up as corrupt, rather than allocating a sparse array for them.
+    /// ```
+    /// # use strctr::snapshot::{Codec, Snapshot, SnapshotError, MAGIC, VERSION};
+    /// # use strctr::sparse_set::SparseSet;
+    /// let mut file = [MAGIC.as_slice(), &VERSION.to_le_bytes(), &SparseSet::TAG].concat();
+    /// vec![3, usize::MAX].encode(&mut file).unwrap();
+    /// assert!(matches!(SparseSet::load_from(file.as_slice()), Err(SnapshotError::Corrupt)));
+    /// ```
+    fn read_payload<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let mut set = Self::new();
+        for id in Vec::<usize>::decode(r)? {
+            if id >= MAX_SNAPSHOT_ID || !set.insert(id) {
+                return Err(SnapshotError::Corrupt);
+            }
+        }
+        Ok(set)
+    }
+}
+
+impl FromIterator<usize> for SparseSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for SparseSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for id in iter {
+            self.insert(id);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a SparseSet {
+    type Item = usize;
+    type IntoIter = std::iter::Copied<std::slice::Iter<'a, usize>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl PartialEq for SparseSet {
+    /// Compares the members, regardless of their order.
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|id| other.contains(id))
+    }
+}
+
+impl Eq for SparseSet {}
+
+impl fmt::Debug for SparseSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
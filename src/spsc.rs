@@ -0,0 +1,231 @@
+//! Bounded single-producer single-consumer queue.
+//!
+//! [`RingBuffer`] stores up to `N` elements inline and hands out one [`Producer`] and one [`Consumer`], which can live
+//! on different threads. Each side only ever writes its own index and reads the other's, so neither pushing nor
+//! popping takes a lock, and nothing is allocated after the buffer itself.
+
+use std::cell::{Cell, UnsafeCell};
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+
+use crate::align::CachePadded;
+use crate::sync::atomic::{AtomicUsize, Ordering};
+
+/// A fixed-capacity ring buffer for passing elements from one thread to another.
+/// ```
+/// # use strctr::spsc::RingBuffer;
+/// let mut ring: RingBuffer<u32, 16> = RingBuffer::new();
+/// let (mut producer, mut consumer) = ring.split();
+/// std::thread::scope(|s| {
+///     s.spawn(move || {
+///         for sample in 0..1000 {
+///             while producer.try_push(sample).is_err() {
+///                 std::hint::spin_loop();
+///             }
+///         }
+///     });
+///     let mut expected = 0;
+///     while expected < 1000 {
+///         if let Some(sample) = consumer.pop() {
+///             assert_eq!(sample, expected);
+///             expected += 1;
+///         }
+///     }
+/// });
+/// ```
+pub struct RingBuffer<T, const N: usize> {
+    /// Number of elements ever popped. Only the consumer writes it.
+    head: CachePadded<AtomicUsize>,
+    /// Number of elements ever pushed. Only the producer writes it.
+    tail: CachePadded<AtomicUsize>,
+    /// Slot `i % N` is initialized for every `head <= i < tail`.
+    slots: [UnsafeCell<MaybeUninit<T>>; N],
+}
+
+// The producer moves elements in and the consumer moves them out, possibly on another thread, so they have to be Send.
+// Slots are only touched by the side that currently owns them according to the indices.
+unsafe impl<T: Send, const N: usize> Sync for RingBuffer<T, N> {}
+
+impl<T, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> RingBuffer<T, N> {
+    /// Constructs a new, empty ring buffer with room for `N` elements.
+    ///
+    /// Panics if `N` is 0.
+    pub fn new() -> Self {
+        if N == 0 {
+            panic!("InvalidCapacity: RingBuffer needs room for at least one element");
+        }
+        Self {
+            head: CachePadded::new(AtomicUsize::new(0)),
+            tail: CachePadded::new(AtomicUsize::new(0)),
+            slots: std::array::from_fn(|_| UnsafeCell::new(MaybeUninit::uninit())),
+        }
+    }
+
+    /// Returns the number of elements the buffer can hold.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of elements in the buffer. While the halves are in use, this may be outdated as soon as it
+    /// returns.
+    pub fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        tail.wrapping_sub(head)
+    }
+
+    /// Returns whether the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Splits the buffer into its producing and consuming halves. Elements left in the buffer when the halves are
+    /// dropped stay there for the next split.
+    pub fn split(&mut self) -> (Producer<'_, T, N>, Consumer<'_, T, N>) {
+        (
+            Producer {
+                ring: self,
+                _not_sync: PhantomData,
+            },
+            Consumer {
+                ring: self,
+                _not_sync: PhantomData,
+            },
+        )
+    }
+}
+
+impl<T, const N: usize> Drop for RingBuffer<T, N> {
+    fn drop(&mut self) {
+        let mut consumer = Consumer {
+            ring: self,
+            _not_sync: PhantomData,
+        };
+        while consumer.pop().is_some() {}
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for RingBuffer<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RingBuffer")
+            .field("len", &self.len())
+            .field("capacity", &N)
+            .finish()
+    }
+}
+
+/// The pushing half of a [`RingBuffer`], created by [split()](`RingBuffer::split()`). It can be sent to another
+/// thread, but not shared between threads.
+pub struct Producer<'a, T, const N: usize> {
+    ring: &'a RingBuffer<T, N>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<T, const N: usize> Producer<'_, T, N> {
+    /// Appends the element, or hands it back if the buffer is full.
+    /// ```
+    /// # use strctr::spsc::RingBuffer;
+    /// let mut ring: RingBuffer<&str, 1> = RingBuffer::new();
+    /// let (mut producer, mut consumer) = ring.split();
+    /// assert_eq!(producer.try_push("a"), Ok(()));
+    /// assert_eq!(producer.try_push("b"), Err("b"));
+    /// assert_eq!(consumer.pop(), Some("a"));
+    /// assert_eq!(producer.try_push("b"), Ok(()));
+    /// ```
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        let tail = self.ring.tail.load(Ordering::Relaxed);
+        let head = self.ring.head.load(Ordering::Acquire);
+        if tail.wrapping_sub(head) == N {
+            return Err(value);
+        }
+        // The slot is outside head..tail, so the consumer does not touch it until the store below publishes it.
+        unsafe { (*self.ring.slots[tail % N].get()).write(value) };
+        self.ring
+            .tail
+            .store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Returns whether the buffer is full. The consumer may make room at any moment.
+    pub fn is_full(&self) -> bool {
+        self.ring.len() == N
+    }
+
+    /// Returns the number of free slots. It can only grow until the next push.
+    pub fn free(&self) -> usize {
+        N - self.ring.len()
+    }
+}
+
+/// The popping half of a [`RingBuffer`], created by [split()](`RingBuffer::split()`). It can be sent to another
+/// thread, but not shared between threads: [peek()](`Consumer::peek()`) hands out a reference to an element, which
+/// is only safe to use on one thread unless the element is [`Sync`].
+/// ```compile_fail
+/// # use std::cell::Cell;
+/// # use strctr::spsc::RingBuffer;
+/// let mut ring: RingBuffer<Cell<u64>, 4> = RingBuffer::new();
+/// let (_, consumer) = ring.split();
+/// let consumer = &consumer;
+/// std::thread::scope(|s| {
+///     s.spawn(move || consumer.peek().map(|c| c.set(1)));
+///     s.spawn(move || consumer.peek().map(|c| c.set(2)));
+/// });
+/// ```
+pub struct Consumer<'a, T, const N: usize> {
+    ring: &'a RingBuffer<T, N>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<T, const N: usize> Consumer<'_, T, N> {
+    /// Removes the oldest element and returns it, or `None` if the buffer is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        let head = self.ring.head.load(Ordering::Relaxed);
+        let tail = self.ring.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        // The slot is inside head..tail, so the producer initialized it and does not touch it until head moves on.
+        let value = unsafe { (*self.ring.slots[head % N].get()).assume_init_read() };
+        self.ring
+            .head
+            .store(head.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+
+    /// Returns a reference to the oldest element, or `None` if the buffer is empty.
+    pub fn peek(&self) -> Option<&T> {
+        let head = self.ring.head.load(Ordering::Relaxed);
+        let tail = self.ring.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        // Only this consumer moves head, and it cannot pop while the reference borrows it.
+        Some(unsafe { (*self.ring.slots[head % N].get()).assume_init_ref() })
+    }
+
+    /// Returns the number of elements waiting. It can only grow until the next pop.
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    /// Returns whether no elements are waiting.
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+}
+
+impl<T, const N: usize> Iterator for Consumer<'_, T, N> {
+    type Item = T;
+
+    /// Pops elements until the buffer is empty.
+    fn next(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
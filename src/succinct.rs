@@ -0,0 +1,11 @@
+//! Succinct structures, which take space close to the information-theoretic minimum and answer queries in place.
+//!
+//! [`RankSelect`] is a bit vector that counts and finds ones and zeros quickly, the building block of the others.
+//! [`LoudsTrie`] stores a large static set of byte strings in a few bits per trie node, mapping each to a dense id and
+//! back, which suits dictionaries of millions of entries embedded into a binary.
+
+mod louds;
+mod rank_select;
+
+pub use louds::{LoudsTrie, LoudsTrieError};
+pub use rank_select::RankSelect;
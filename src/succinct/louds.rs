@@ -0,0 +1,204 @@
+//! Static trie encoded with LOUDS.
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+
+use super::RankSelect;
+use crate::snapshot::{Snapshot, SnapshotError};
+
+/// List of errors that could occur when building a [`LoudsTrie`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum LoudsTrieError {
+    /// A key is smaller than the key before it.
+    Unsorted,
+    /// The same key appears more than once.
+    DuplicateKey,
+}
+
+/// An immutable set of byte strings stored as a trie in about 12 bits per node, which maps every key to a dense id
+/// and back.
+///
+/// The trie's shape is encoded level by level in LOUDS (level-order unary degree sequence): every node, in
+/// breadth-first order, writes a one per child followed by a zero, so the whole shape takes two bits per node. The
+/// children of a node and the parent of a node are found by rank and select queries on those bits. Next to it, the
+/// trie stores a byte labelling every node and a bit marking the nodes that end a key. Lookups take O(log n) per byte
+/// of the key.
+///
+/// Ids run from 0 to [len()](`Self::len()`), in the breadth-first order of the keys' last nodes: shorter keys get
+/// smaller ids, and keys of the same length are in sorted order. A trie built once can be saved as a
+/// [`Snapshot`] and loaded again, like from bytes embedded with [`include_bytes!`].
+/// ```
+/// # use strctr::succinct::LoudsTrie;
+/// let words = LoudsTrie::from_sorted(&["car", "card", "care", "cat", "dog"]).unwrap();
+/// assert_eq!(words.len(), 5);
+/// let id = words.id("care").unwrap();
+/// assert_eq!(words.key(id).unwrap(), b"care");
+/// assert_eq!(words.id("ca"), None);
+/// assert!(!words.contains("cards"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct LoudsTrie {
+    louds: RankSelect,
+    labels: Vec<u8>,
+    terminal: RankSelect,
+}
+
+impl LoudsTrie {
+    /// Builds a trie out of the keys, which must be sorted and distinct. Unsorted keys can be collected into a trie
+    /// instead.
+    /// ```
+    /// # use strctr::succinct::{LoudsTrie, LoudsTrieError};
+    /// assert_eq!(LoudsTrie::from_sorted(&["b", "a"]).err(), Some(LoudsTrieError::Unsorted));
+    /// assert_eq!(LoudsTrie::from_sorted(&["a", "a"]).err(), Some(LoudsTrieError::DuplicateKey));
+    ///
+    /// let trie: LoudsTrie = ["b", "a", "b"].into_iter().collect();
+    /// assert_eq!(trie.len(), 2);
+    /// ```
+    pub fn from_sorted<K: AsRef<[u8]>>(keys: &[K]) -> Result<Self, LoudsTrieError> {
+        for pair in keys.windows(2) {
+            match pair[0].as_ref().cmp(pair[1].as_ref()) {
+                std::cmp::Ordering::Less => {}
+                std::cmp::Ordering::Equal => return Err(LoudsTrieError::DuplicateKey),
+                std::cmp::Ordering::Greater => return Err(LoudsTrieError::Unsorted),
+            }
+        }
+        // The super-root's list holds the root alone.
+        let mut louds = vec![true, false];
+        let mut labels = Vec::new();
+        let mut terminal = Vec::new();
+        // Every node is the range of keys starting with its prefix, whose length is the depth.
+        let mut queue = VecDeque::from([(0, 0, keys.len(), 0)]);
+        while let Some((label, mut start, end, depth)) = queue.pop_front() {
+            labels.push(label);
+            // Keys are sorted, so the one ending here, if any, is first.
+            let ends_here = start < end && keys[start].as_ref().len() == depth;
+            terminal.push(ends_here);
+            if ends_here {
+                start += 1;
+            }
+            while start < end {
+                let byte = keys[start].as_ref()[depth];
+                let mut next = start + 1;
+                while next < end && keys[next].as_ref()[depth] == byte {
+                    next += 1;
+                }
+                louds.push(true);
+                queue.push_back((byte, start, next, depth + 1));
+                start = next;
+            }
+            louds.push(false);
+        }
+        Ok(Self {
+            louds: louds.into_iter().collect(),
+            labels,
+            terminal: terminal.into_iter().collect(),
+        })
+    }
+
+    /// Returns the number of keys.
+    pub fn len(&self) -> usize {
+        self.terminal.count_ones()
+    }
+
+    /// Returns whether the trie holds no keys.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns whether the key is in the trie.
+    pub fn contains(&self, key: impl AsRef<[u8]>) -> bool {
+        self.id(key).is_some()
+    }
+
+    /// Returns the id of the key, or `None` if it is not in the trie.
+    pub fn id(&self, key: impl AsRef<[u8]>) -> Option<usize> {
+        let mut node = 0;
+        for &byte in key.as_ref() {
+            let children = self.children(node);
+            let offset = self.labels[children.clone()].binary_search(&byte).ok()?;
+            node = children.start + offset;
+        }
+        self.terminal.get(node).then(|| self.terminal.rank1(node))
+    }
+
+    /// Returns the key with the id, or `None` if there is no such id.
+    pub fn key(&self, id: usize) -> Option<Vec<u8>> {
+        let mut node = self.terminal.select1(id)?;
+        let mut key = Vec::new();
+        while node != 0 {
+            key.push(self.labels[node]);
+            node = self.parent(node);
+        }
+        key.reverse();
+        Some(key)
+    }
+
+    /// Returns the number of bytes taken by the trie's bits, labels and indexes.
+    pub fn size_in_bytes(&self) -> usize {
+        self.louds.size_in_bytes() + self.terminal.size_in_bytes() + self.labels.len()
+    }
+
+    /// Returns the nodes that are children of the node. Their ones lie between the zeros ending the lists of the nodes
+    /// before it and of the node itself, and their numbers are the ones before them.
+    fn children(&self, node: usize) -> std::ops::Range<usize> {
+        let start = self.louds.select0(node).expect("a live node") + 1;
+        let end = self.louds.select0(node + 1).expect("a live node");
+        let first = self.louds.rank1(start);
+        first..first + (end - start)
+    }
+
+    /// Returns the parent of a node other than the root, from the number of lists ended before its one.
+    fn parent(&self, node: usize) -> usize {
+        let one = self.louds.select1(node).expect("a live node");
+        self.louds.rank0(one) - 1
+    }
+}
+
+impl<K: AsRef<[u8]>> FromIterator<K> for LoudsTrie {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut keys: Vec<K> = iter.into_iter().collect();
+        keys.sort_unstable_by(|a, b| a.as_ref().cmp(b.as_ref()));
+        keys.dedup_by(|a, b| a.as_ref() == b.as_ref());
+        Self::from_sorted(&keys).expect("the keys are sorted and distinct")
+    }
+}
+
+impl Snapshot for LoudsTrie {
+    const TAG: [u8; 4] = *b"LOUD";
+
+    fn write_payload<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.louds.write_payload(w)?;
+        self.terminal.write_payload(w)?;
+        w.write_all(&self.labels)
+    }
+
+    /// Checks that the bits encode a tree, so lookups on the loaded trie cannot fail.
+    fn read_payload<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let louds = RankSelect::read_payload(r)?;
+        let terminal = RankSelect::read_payload(r)?;
+        let nodes = terminal.len();
+        if nodes == 0 || louds.len() != 2 * nodes + 1 || louds.count_ones() != nodes {
+            return Err(SnapshotError::Corrupt);
+        }
+        // Every node but the root must be in the list of a node before it.
+        let mut lists = 0;
+        let mut ones = 0;
+        for i in 0..louds.len() {
+            if louds.get(i) {
+                if (ones > 0 && lists == 0) || lists > ones {
+                    return Err(SnapshotError::Corrupt);
+                }
+                ones += 1;
+            } else {
+                lists += 1;
+            }
+        }
+        let mut labels = vec![0; nodes];
+        r.read_exact(&mut labels)?;
+        Ok(Self {
+            louds,
+            labels,
+            terminal,
+        })
+    }
+}
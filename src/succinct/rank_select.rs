@@ -0,0 +1,192 @@
+//! Bit vector answering rank and select queries.
+
+use std::io::{self, Read, Write};
+
+use crate::snapshot::{Codec, Snapshot, SnapshotError};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Words per block of the rank index. Each block stores the ones before it, so the index takes an eighth of the bits.
+const BLOCK_WORDS: usize = 8;
+
+/// An immutable bit vector that counts the ones before any position ([rank1()](`Self::rank1()`)) in O(1), and finds
+/// the position of the k-th one ([select1()](`Self::select1()`)) in O(log n). Zeros work the same way.
+///
+/// Besides the bits, it stores the number of ones before every 512 bits, an eighth of their size.
+/// ```
+/// # use strctr::succinct::RankSelect;
+/// let bits: RankSelect = [true, false, true, true, false].into_iter().collect();
+/// assert_eq!(bits.rank1(3), 2);
+/// assert_eq!(bits.select1(2), Some(3));
+/// assert_eq!(bits.select0(1), Some(4));
+/// assert_eq!(bits.select1(3), None);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RankSelect {
+    words: Vec<u64>,
+    len: usize,
+    // The ones before every block, then the ones in total.
+    blocks: Vec<usize>,
+}
+
+impl RankSelect {
+    fn from_words(words: Vec<u64>, len: usize) -> Self {
+        let mut blocks = Vec::with_capacity(words.len() / BLOCK_WORDS + 2);
+        let mut ones = 0;
+        for block in words.chunks(BLOCK_WORDS) {
+            blocks.push(ones);
+            ones += block.iter().map(|w| w.count_ones() as usize).sum::<usize>();
+        }
+        blocks.push(ones);
+        Self { words, len, blocks }
+    }
+
+    /// Returns the number of bits.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether there are no bits.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of ones.
+    pub fn count_ones(&self) -> usize {
+        *self.blocks.last().unwrap_or(&0)
+    }
+
+    /// Returns the number of bytes taken by the bits and the rank index.
+    pub fn size_in_bytes(&self) -> usize {
+        self.words.len() * std::mem::size_of::<u64>()
+            + self.blocks.len() * std::mem::size_of::<usize>()
+    }
+
+    /// Returns the bit at the position.
+    ///
+    /// Panics if `i` >= [len()](`Self::len()`).
+    pub fn get(&self, i: usize) -> bool {
+        if i >= self.len {
+            panic!("OutOfBounds: Wanted bit {}, but length is {}", i, self.len);
+        }
+        self.words[i / WORD_BITS] >> (i % WORD_BITS) & 1 == 1
+    }
+
+    /// Returns the number of ones before the position.
+    ///
+    /// Panics if `i` > [len()](`Self::len()`).
+    pub fn rank1(&self, i: usize) -> usize {
+        if i > self.len {
+            panic!(
+                "OutOfBounds: Wanted rank at {}, but length is {}",
+                i, self.len
+            );
+        }
+        let word = i / WORD_BITS;
+        let block = word / BLOCK_WORDS;
+        let mut rank = self.blocks[block];
+        for w in &self.words[block * BLOCK_WORDS..word] {
+            rank += w.count_ones() as usize;
+        }
+        if !i.is_multiple_of(WORD_BITS) {
+            rank += (self.words[word] << (WORD_BITS - i % WORD_BITS)).count_ones() as usize;
+        }
+        rank
+    }
+
+    /// Returns the number of zeros before the position.
+    ///
+    /// Panics if `i` > [len()](`Self::len()`).
+    pub fn rank0(&self, i: usize) -> usize {
+        i - self.rank1(i)
+    }
+
+    /// Returns the position of the one with `k` ones before it, or `None` if there are not that many.
+    pub fn select1(&self, k: usize) -> Option<usize> {
+        if k >= self.count_ones() {
+            return None;
+        }
+        Some(self.select(k, |block| self.blocks[block], |w| w))
+    }
+
+    /// Returns the position of the zero with `k` zeros before it, or `None` if there are not that many.
+    pub fn select0(&self, k: usize) -> Option<usize> {
+        if k >= self.len - self.count_ones() {
+            return None;
+        }
+        let block_bits = BLOCK_WORDS * WORD_BITS;
+        Some(self.select(k, |block| block * block_bits - self.blocks[block], |w| !w))
+    }
+
+    /// Finds the `k`-th set bit of the words as transformed by `word`, given the number before each block. Bits past
+    /// the end are zeros, so only counts of ones and real zeros may be passed.
+    fn select(
+        &self,
+        k: usize,
+        before: impl Fn(usize) -> usize,
+        word: impl Fn(u64) -> u64,
+    ) -> usize {
+        // Binary search for the last block starting at or before the bit.
+        let (mut block, mut end) = (0, self.blocks.len() - 1);
+        while end - block > 1 {
+            let mid = (block + end) / 2;
+            if before(mid) <= k {
+                block = mid;
+            } else {
+                end = mid;
+            }
+        }
+        let mut remaining = k - before(block);
+        for (i, &w) in self.words.iter().enumerate().skip(block * BLOCK_WORDS) {
+            let mut w = word(w);
+            let ones = w.count_ones() as usize;
+            if remaining < ones {
+                for _ in 0..remaining {
+                    w &= w - 1;
+                }
+                return i * WORD_BITS + w.trailing_zeros() as usize;
+            }
+            remaining -= ones;
+        }
+        unreachable!("the bit was counted")
+    }
+}
+
+impl FromIterator<bool> for RankSelect {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut words = Vec::new();
+        let mut len = 0;
+        for bit in iter {
+            if len % WORD_BITS == 0 {
+                words.push(0);
+            }
+            if bit {
+                *words.last_mut().expect("a word was pushed") |= 1 << (len % WORD_BITS);
+            }
+            len += 1;
+        }
+        Self::from_words(words, len)
+    }
+}
+
+impl Snapshot for RankSelect {
+    const TAG: [u8; 4] = *b"RSEL";
+
+    /// Writes the bits; the rank index is rebuilt on loading.
+    fn write_payload<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.len.encode(w)?;
+        self.words.encode(w)
+    }
+
+    fn read_payload<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let len = usize::decode(r)?;
+        let words: Vec<u64> = Vec::decode(r)?;
+        let tail = len % WORD_BITS;
+        if words.len() != len.div_ceil(WORD_BITS)
+            || (tail != 0 && words.last().is_some_and(|w| w >> tail != 0))
+        {
+            return Err(SnapshotError::Corrupt);
+        }
+        Ok(Self::from_words(words, len))
+    }
+}
@@ -0,0 +1,86 @@
+//! Synchronization primitives used by the concurrent structures, switchable to [loom](https://docs.rs/loom)'s, and the
+//! [`BoundedQueue`] and [`Stack`] built on them.
+//!
+//! Normally these are the types from [`std::sync`] and [`std::thread`]. When compiled with `--cfg loom`, they are
+//! loom's instrumented versions instead, and [`model()`] explores every interleaving of the threads it spawns, so the
+//! atomics and locks of the crate's concurrent structures, like [`RcuCell`](`crate::rcu::RcuCell`) or
+//! [`RingBuffer`](`crate::spsc::RingBuffer`), get model-checked.
+//!
+//! Tests written against this module run once as ordinary tests and exhaustively under loom:
+//! ```
+//! # use strctr::sharded_counter::ShardedCounter;
+//! # use strctr::sync::{model, thread, Arc};
+//! model(|| {
+//!     let counter = Arc::new(ShardedCounter::with_shards(2));
+//!     let other = counter.clone();
+//!     let handle = thread::spawn(move || other.add(2));
+//!     counter.increment();
+//!     handle.join().unwrap();
+//!     assert_eq!(counter.sum(), 3);
+//! });
+//! ```
+//! The crate's own model checks live in `tests/loom.rs` and run with
+//! `RUSTFLAGS="--cfg loom" cargo test --release --test loom`. Under loom, objects from this module may only be created
+//! inside [`model()`], so other tests do not work with that configuration.
+
+mod bounded_queue;
+mod stack;
+
+pub use bounded_queue::{BoundedQueue, PopError, PushError};
+pub use stack::Stack;
+
+#[cfg(loom)]
+pub use loom::sync::{Arc, Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+#[cfg(not(loom))]
+pub use std::sync::{Arc, Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Atomic types.
+pub mod atomic {
+    #[cfg(loom)]
+    pub use loom::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU64, AtomicUsize};
+    pub use std::sync::atomic::Ordering;
+    #[cfg(not(loom))]
+    pub use std::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU64, AtomicUsize};
+}
+
+/// Hints for busy-waiting. Under loom, spinning yields to the other threads of the model.
+pub mod hint {
+    #[cfg(loom)]
+    pub use loom::hint::spin_loop;
+    #[cfg(not(loom))]
+    pub use std::hint::spin_loop;
+}
+
+/// Thread spawning.
+pub mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::{spawn, yield_now, JoinHandle};
+    #[cfg(not(loom))]
+    pub use std::thread::{spawn, yield_now, JoinHandle};
+}
+
+/// Runs the test. Under loom, runs it once for every possible interleaving of the threads it spawns.
+pub fn model<F: Fn() + Sync + Send + 'static>(f: F) {
+    #[cfg(loom)]
+    loom::model(f);
+    #[cfg(not(loom))]
+    f();
+}
+
+/// Deliberately the `std` atomic even under loom: slot assignment only spreads load and is not part of the model.
+static NEXT_THREAD: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+std::thread_local! {
+    /// Handed out round-robin on the thread's first access, so threads spread evenly over the slots.
+    static THREAD_SLOT: usize = NEXT_THREAD.fetch_add(1, atomic::Ordering::Relaxed);
+}
+
+/// Returns the slot of the current thread among `shards` per-thread slots.
+pub(crate) fn thread_slot(shards: usize) -> usize {
+    THREAD_SLOT.with(|slot| slot % shards)
+}
+
+/// Returns the default number of per-thread slots: one per available CPU.
+pub(crate) fn default_shards() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
@@ -0,0 +1,306 @@
+//! Bounded multi-producer multi-consumer queue.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::PoisonError;
+use std::time::{Duration, Instant};
+
+use super::{Condvar, Mutex, MutexGuard};
+
+/// List of errors that could occur when pushing onto a [`BoundedQueue`]. Both hand the element back.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PushError<T> {
+    /// The queue was full.
+    Full(T),
+    /// The queue was closed.
+    Closed(T),
+}
+
+impl<T> PushError<T> {
+    /// Returns the element that could not be pushed.
+    pub fn into_inner(self) -> T {
+        match self {
+            PushError::Full(value) | PushError::Closed(value) => value,
+        }
+    }
+}
+
+/// List of errors that could occur when popping from a [`BoundedQueue`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum PopError {
+    /// The queue was empty.
+    Empty,
+    /// The queue was closed and all its elements were popped.
+    Closed,
+}
+
+struct State<T> {
+    items: VecDeque<T>,
+    closed: bool,
+}
+
+/// A queue with a fixed capacity, shared by any number of producing and consuming threads.
+///
+/// Pushing onto a full queue and popping from an empty one either fail right away (the `try_` variants), block until
+/// the queue has room or an element, or block up to a timeout. [close()](`Self::close()`) shuts the queue down: pushes
+/// fail from then on, and pops drain the remaining elements before failing too, which lets consumers finish cleanly.
+/// ```
+/// # use strctr::sync::BoundedQueue;
+/// let jobs = BoundedQueue::new(4);
+/// let done = std::thread::scope(|s| {
+///     let workers: Vec<_> = (0..3)
+///         .map(|_| {
+///             s.spawn(|| {
+///                 let mut sum = 0;
+///                 while let Some(job) = jobs.pop() {
+///                     sum += job;
+///                 }
+///                 sum
+///             })
+///         })
+///         .collect();
+///     for job in 1..=100 {
+///         jobs.push(job).unwrap();
+///     }
+///     jobs.close();
+///     workers.into_iter().map(|w| w.join().unwrap()).sum::<u32>()
+/// });
+/// assert_eq!(done, 5050);
+/// ```
+pub struct BoundedQueue<T> {
+    state: Mutex<State<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+}
+
+impl<T> BoundedQueue<T> {
+    /// Constructs a new, empty queue holding at most `capacity` elements.
+    ///
+    /// Panics if the capacity is 0.
+    pub fn new(capacity: usize) -> Self {
+        if capacity == 0 {
+            panic!("InvalidCapacity: BoundedQueue needs room for at least one element");
+        }
+        Self {
+            state: Mutex::new(State {
+                items: VecDeque::with_capacity(capacity),
+                closed: false,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity,
+        }
+    }
+
+    /// Returns the maximum number of elements.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of elements in the queue.
+    pub fn len(&self) -> usize {
+        self.lock().items.len()
+    }
+
+    /// Returns whether the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns whether the queue is full.
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity
+    }
+
+    /// Returns whether the queue was closed.
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    /// Closes the queue and wakes all waiting threads. Pushes fail from now on, while pops still return the remaining
+    /// elements.
+    pub fn close(&self) {
+        self.lock().closed = true;
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+
+    /// Appends the element if there is room, without blocking.
+    /// ```
+    /// # use strctr::sync::{BoundedQueue, PushError};
+    /// let queue = BoundedQueue::new(1);
+    /// assert_eq!(queue.try_push(1), Ok(()));
+    /// assert_eq!(queue.try_push(2), Err(PushError::Full(2)));
+    /// queue.close();
+    /// assert_eq!(queue.try_push(3), Err(PushError::Closed(3)));
+    /// ```
+    pub fn try_push(&self, value: T) -> Result<(), PushError<T>> {
+        let state = self.lock();
+        if state.closed {
+            return Err(PushError::Closed(value));
+        }
+        if state.items.len() == self.capacity {
+            return Err(PushError::Full(value));
+        }
+        self.push_locked(state, value);
+        Ok(())
+    }
+
+    /// Appends the element, blocking while the queue is full. Fails, handing the element back, if the queue is closed.
+    pub fn push(&self, value: T) -> Result<(), T> {
+        let mut state = self.lock();
+        loop {
+            if state.closed {
+                return Err(value);
+            }
+            if state.items.len() < self.capacity {
+                self.push_locked(state, value);
+                return Ok(());
+            }
+            state = self
+                .not_full
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Appends the element, blocking while the queue is full, but for no longer than the timeout. A timeout too long
+    /// to represent as an [`Instant`] blocks like [push()](`Self::push()`).
+    /// ```
+    /// # use std::time::Duration;
+    /// # use strctr::sync::{BoundedQueue, PushError};
+    /// let queue = BoundedQueue::new(1);
+    /// assert_eq!(queue.push_timeout(1, Duration::MAX), Ok(()));
+    /// assert_eq!(queue.push_timeout(2, Duration::from_millis(1)), Err(PushError::Full(2)));
+    /// ```
+    pub fn push_timeout(&self, value: T, timeout: Duration) -> Result<(), PushError<T>> {
+        let Some(deadline) = Instant::now().checked_add(timeout) else {
+            return self.push(value).map_err(PushError::Closed);
+        };
+        let mut state = self.lock();
+        loop {
+            if state.closed {
+                return Err(PushError::Closed(value));
+            }
+            if state.items.len() < self.capacity {
+                self.push_locked(state, value);
+                return Ok(());
+            }
+            let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+                return Err(PushError::Full(value));
+            };
+            state = self
+                .not_full
+                .wait_timeout(state, remaining)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /// Removes the oldest element and returns it, without blocking.
+    /// ```
+    /// # use strctr::sync::{BoundedQueue, PopError};
+    /// let queue = BoundedQueue::new(2);
+    /// assert_eq!(queue.try_pop(), Err(PopError::Empty));
+    /// queue.push("a").unwrap();
+    /// queue.close();
+    /// assert_eq!(queue.try_pop(), Ok("a"));
+    /// assert_eq!(queue.try_pop(), Err(PopError::Closed));
+    /// ```
+    pub fn try_pop(&self) -> Result<T, PopError> {
+        let state = self.lock();
+        if state.items.is_empty() {
+            return Err(if state.closed {
+                PopError::Closed
+            } else {
+                PopError::Empty
+            });
+        }
+        Ok(self.pop_locked(state))
+    }
+
+    /// Removes the oldest element and returns it, blocking while the queue is empty. Returns `None` once the queue is
+    /// closed and empty.
+    pub fn pop(&self) -> Option<T> {
+        let mut state = self.lock();
+        loop {
+            if !state.items.is_empty() {
+                return Some(self.pop_locked(state));
+            }
+            if state.closed {
+                return None;
+            }
+            state = self
+                .not_empty
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Removes the oldest element and returns it, blocking while the queue is empty, but for no longer than the
+    /// timeout. A timeout too long to represent as an [`Instant`] blocks like [pop()](`Self::pop()`).
+    /// ```
+    /// # use std::time::Duration;
+    /// # use strctr::sync::{BoundedQueue, PopError};
+    /// let queue = BoundedQueue::new(1);
+    /// assert_eq!(queue.pop_timeout(Duration::from_millis(1)), Err(PopError::Empty));
+    /// queue.push("a").unwrap();
+    /// queue.close();
+    /// assert_eq!(queue.pop_timeout(Duration::MAX), Ok("a"));
+    /// assert_eq!(queue.pop_timeout(Duration::MAX), Err(PopError::Closed));
+    /// ```
+    pub fn pop_timeout(&self, timeout: Duration) -> Result<T, PopError> {
+        let Some(deadline) = Instant::now().checked_add(timeout) else {
+            return self.pop().ok_or(PopError::Closed);
+        };
+        let mut state = self.lock();
+        loop {
+            if !state.items.is_empty() {
+                return Ok(self.pop_locked(state));
+            }
+            if state.closed {
+                return Err(PopError::Closed);
+            }
+            let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+                return Err(PopError::Empty);
+            };
+            state = self
+                .not_empty
+                .wait_timeout(state, remaining)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    fn push_locked(&self, mut state: MutexGuard<'_, State<T>>, value: T) {
+        state.items.push_back(value);
+        drop(state);
+        self.not_empty.notify_one();
+    }
+
+    fn pop_locked(&self, mut state: MutexGuard<'_, State<T>>) -> T {
+        let value = state
+            .items
+            .pop_front()
+            .expect("callers check that the queue is not empty");
+        drop(state);
+        self.not_full.notify_one();
+        value
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T> fmt::Debug for BoundedQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.lock();
+        f.debug_struct("BoundedQueue")
+            .field("len", &state.items.len())
+            .field("capacity", &self.capacity)
+            .field("closed", &state.closed)
+            .finish()
+    }
+}
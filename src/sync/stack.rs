@@ -0,0 +1,193 @@
+//! Lock-free stack.
+
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::ptr;
+
+use super::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+struct Node<T> {
+    /// Moved out by the thread whose pop unlinks the node.
+    value: MaybeUninit<T>,
+    /// Never changes once the node is pushed, so poppers that lose the race may still read it.
+    next: *mut Node<T>,
+    /// Link in the list of unlinked nodes waiting to be freed. Only written by the thread retiring the node.
+    retired_next: *mut Node<T>,
+}
+
+/// A stack shared by many threads without locks, following Treiber's algorithm: the head is swapped with a
+/// compare-and-swap, and whoever wins the race owns the element.
+///
+/// Popping threads still read the next link of the node they are trying to unlink after another thread already won it,
+/// so unlinked nodes cannot be freed right away. The stack counts the threads inside [pop()](`Self::pop()`): a popper
+/// that finds itself alone frees its node at once, together with all nodes retired earlier, and otherwise retires the
+/// node into a list. Under constant concurrent popping, that list only shrinks once popping threads stop overlapping.
+/// ```
+/// # use strctr::sync::Stack;
+/// let stack = Stack::new();
+/// std::thread::scope(|s| {
+///     for t in 0..4 {
+///         let stack = &stack;
+///         s.spawn(move || (0..100).for_each(|i| stack.push(t * 100 + i)));
+///     }
+/// });
+/// let mut popped: Vec<_> = std::iter::from_fn(|| stack.pop()).collect();
+/// popped.sort();
+/// assert_eq!(popped, (0..400).collect::<Vec<_>>());
+/// ```
+pub struct Stack<T> {
+    head: AtomicPtr<Node<T>>,
+    threads_in_pop: AtomicUsize,
+    /// Unlinked nodes that could not be freed yet, chained through `retired_next`.
+    retired: AtomicPtr<Node<T>>,
+}
+
+// Elements are moved in by one thread and out by another.
+unsafe impl<T: Send> Send for Stack<T> {}
+unsafe impl<T: Send> Sync for Stack<T> {}
+
+impl<T> Default for Stack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Stack<T> {
+    /// Constructs a new, empty stack.
+    pub fn new() -> Self {
+        Self {
+            head: AtomicPtr::new(ptr::null_mut()),
+            threads_in_pop: AtomicUsize::new(0),
+            retired: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// Returns whether the stack is empty. Other threads may change that at any moment.
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+
+    /// Pushes the element on top of the stack.
+    pub fn push(&self, value: T) {
+        let node = Box::into_raw(Box::new(Node {
+            value: MaybeUninit::new(value),
+            next: ptr::null_mut(),
+            retired_next: ptr::null_mut(),
+        }));
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // The node is not shared until the exchange succeeds.
+            unsafe { (*node).next = head };
+            match self
+                .head
+                .compare_exchange_weak(head, node, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// Removes the top element and returns it, or `None` if the stack is empty.
+    /// ```
+    /// # use strctr::sync::Stack;
+    /// let stack = Stack::new();
+    /// stack.push(1);
+    /// stack.push(2);
+    /// assert_eq!(stack.pop(), Some(2));
+    /// assert_eq!(stack.pop(), Some(1));
+    /// assert_eq!(stack.pop(), None);
+    /// ```
+    pub fn pop(&self) -> Option<T> {
+        self.threads_in_pop.fetch_add(1, Ordering::SeqCst);
+        let mut head = self.head.load(Ordering::Acquire);
+        loop {
+            if head.is_null() {
+                self.threads_in_pop.fetch_sub(1, Ordering::SeqCst);
+                return None;
+            }
+            // The node cannot be freed while this thread is counted in threads_in_pop.
+            let next = unsafe { (*head).next };
+            match self
+                .head
+                .compare_exchange_weak(head, next, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+        // Winning the exchange makes this thread the only one to take the value.
+        let value = unsafe { (*head).value.assume_init_read() };
+        self.reclaim(head);
+        Some(value)
+    }
+
+    /// Frees the unlinked node, and the retired ones with it, if no other thread is popping; otherwise retires it.
+    /// Leaves pop.
+    fn reclaim(&self, node: *mut Node<T>) {
+        if self.threads_in_pop.load(Ordering::SeqCst) != 1 {
+            self.retire(node, node);
+            self.threads_in_pop.fetch_sub(1, Ordering::SeqCst);
+            return;
+        }
+        let retired = self.retired.swap(ptr::null_mut(), Ordering::SeqCst);
+        if self.threads_in_pop.fetch_sub(1, Ordering::SeqCst) == 1 {
+            // Nobody else entered pop since the retired nodes were taken, so nobody can still see them.
+            unsafe { free_chain(retired) };
+        } else if !retired.is_null() {
+            let mut last = retired;
+            // The taken list belongs to this thread alone.
+            unsafe {
+                while !(*last).retired_next.is_null() {
+                    last = (*last).retired_next;
+                }
+            }
+            self.retire(retired, last);
+        }
+        // This thread was the only one in pop when it unlinked the node, so no other thread loaded it.
+        unsafe { drop(Box::from_raw(node)) };
+    }
+
+    /// Prepends the chain of nodes from `first` to `last` to the retired list.
+    fn retire(&self, first: *mut Node<T>, last: *mut Node<T>) {
+        let mut retired = self.retired.load(Ordering::SeqCst);
+        loop {
+            // The chain is unlinked and not yet published, so only this thread touches it.
+            unsafe { (*last).retired_next = retired };
+            match self.retired.compare_exchange_weak(
+                retired,
+                first,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => return,
+                Err(current) => retired = current,
+            }
+        }
+    }
+}
+
+/// Frees a chain of retired nodes, whose values were already moved out.
+unsafe fn free_chain<T>(mut node: *mut Node<T>) {
+    while !node.is_null() {
+        let next = (*node).retired_next;
+        drop(Box::from_raw(node));
+        node = next;
+    }
+}
+
+impl<T> Drop for Stack<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+        // No other thread can access the stack anymore.
+        unsafe { free_chain(self.retired.load(Ordering::Relaxed)) };
+    }
+}
+
+impl<T> fmt::Debug for Stack<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stack")
+            .field("is_empty", &self.is_empty())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,211 @@
+//! Two-tier map for skewed workloads. A small hot tier of at most `N` entries is searched linearly and fronts a
+//! [`HashMap`] holding everything else. Cold entries that are looked up often enough are promoted into the hot tier,
+//! displacing its least used entry.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Hit and promotion counters of a [`TieredMap`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TierStats {
+    /// Lookups answered by the hot tier.
+    pub hot_hits: u64,
+    /// Lookups answered by the cold tier.
+    pub cold_hits: u64,
+    /// Lookups of keys that were not in the map.
+    pub misses: u64,
+    /// Entries moved from the cold tier into the hot tier.
+    pub promotions: u64,
+    /// Entries moved from the hot tier back into the cold tier.
+    pub demotions: u64,
+}
+
+impl TierStats {
+    /// Returns the share of successful lookups that were answered by the hot tier, between 0 and 1.
+    pub fn hot_ratio(&self) -> f64 {
+        let hits = self.hot_hits + self.cold_hits;
+        if hits == 0 {
+            return 0.0;
+        }
+        self.hot_hits as f64 / hits as f64
+    }
+}
+
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    hits: u32,
+}
+
+/// A map with a hot tier of at most `N` entries in front of a hash map.
+pub struct TieredMap<K, V, const N: usize> {
+    hot: Vec<Entry<K, V>>,
+    cold: HashMap<K, (V, u32)>,
+    promote_after: u32,
+    stats: TierStats,
+}
+
+impl<K: Hash + Eq, V, const N: usize> Default for TieredMap<K, V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq, V, const N: usize> TieredMap<K, V, N> {
+    /// Constructs a new, empty map that promotes a cold entry on its fourth hit.
+    pub fn new() -> Self {
+        Self::with_promotion_threshold(4)
+    }
+
+    /// Constructs a new, empty map that promotes a cold entry once it was hit `hits` times while cold.
+    pub fn with_promotion_threshold(hits: u32) -> Self {
+        Self {
+            hot: Vec::with_capacity(N),
+            cold: HashMap::new(),
+            promote_after: hits.max(1),
+            stats: TierStats::default(),
+        }
+    }
+
+    /// Returns the number of entries in both tiers.
+    pub fn len(&self) -> usize {
+        self.hot.len() + self.cold.len()
+    }
+
+    /// Returns whether the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of entries in the hot tier.
+    pub fn hot_len(&self) -> usize {
+        self.hot.len()
+    }
+
+    /// Returns the hit and promotion counters.
+    pub fn stats(&self) -> TierStats {
+        self.stats
+    }
+
+    /// Resets the hit and promotion counters.
+    pub fn reset_stats(&mut self) {
+        self.stats = TierStats::default();
+    }
+
+    /// Returns whether the key is currently in the hot tier.
+    pub fn is_hot(&self, key: &K) -> bool {
+        self.hot_position(key).is_some()
+    }
+
+    /// Inserts a key-value pair. New keys start out in the cold tier. If the key was already present, its value is
+    /// replaced in whichever tier it lives and the old value is returned.
+    /// ```
+    /// # use strctr::tiered::TieredMap;
+    /// let mut m: TieredMap<_, _, 4> = TieredMap::new();
+    /// assert_eq!(m.insert("a", 1), None);
+    /// assert_eq!(m.insert("a", 2), Some(1));
+    /// assert!(!m.is_hot(&"a"));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(i) = self.hot_position(&key) {
+            return Some(std::mem::replace(&mut self.hot[i].value, value));
+        }
+        match self.cold.get_mut(&key) {
+            Some((v, _)) => Some(std::mem::replace(v, value)),
+            None => {
+                self.cold.insert(key, (value, 0));
+                None
+            }
+        }
+    }
+
+    /// Looks up the key, counting the hit and promoting the entry into the hot tier once it is hit often enough.
+    /// ```
+    /// # use strctr::tiered::TieredMap;
+    /// let mut m: TieredMap<_, _, 1> = TieredMap::with_promotion_threshold(2);
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.get(&"a");
+    /// assert!(!m.is_hot(&"a"));
+    /// m.get(&"a");
+    /// assert!(m.is_hot(&"a"));
+    /// assert_eq!(m.get(&"a"), Some(&1));
+    /// assert_eq!(m.stats().hot_hits, 1);
+    /// assert_eq!(m.stats().cold_hits, 2);
+    /// ```
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    /// Looks up the key like [get()](`Self::get()`), returning a mutable reference.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        if let Some(i) = self.hot_position(key) {
+            self.stats.hot_hits += 1;
+            let entry = &mut self.hot[i];
+            entry.hits = entry.hits.saturating_add(1);
+            return Some(&mut self.hot[i].value);
+        }
+        let Some((_, hits)) = self.cold.get_mut(key) else {
+            self.stats.misses += 1;
+            return None;
+        };
+        self.stats.cold_hits += 1;
+        *hits = hits.saturating_add(1);
+        let hits = *hits;
+        if hits >= self.promote_after {
+            if let Some(i) = self.promote(key, hits) {
+                return Some(&mut self.hot[i].value);
+            }
+        }
+        self.cold.get_mut(key).map(|(v, _)| v)
+    }
+
+    /// Looks up the key without counting a hit or moving entries between tiers.
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        match self.hot_position(key) {
+            Some(i) => Some(&self.hot[i].value),
+            None => self.cold.get(key).map(|(v, _)| v),
+        }
+    }
+
+    /// Returns whether the key is in either tier.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.peek(key).is_some()
+    }
+
+    /// Removes the key from whichever tier it lives in, returning its value.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        if let Some(i) = self.hot_position(key) {
+            return Some(self.hot.swap_remove(i).value);
+        }
+        self.cold.remove(key).map(|(v, _)| v)
+    }
+
+    fn hot_position(&self, key: &K) -> Option<usize> {
+        self.hot.iter().position(|e| e.key == *key)
+    }
+
+    /// Moves a cold entry into the hot tier, demoting the least hit hot entry if the tier is full and that entry has
+    /// fewer hits. Returns the entry's new position in the hot tier, or `None` if it stayed cold.
+    fn promote(&mut self, key: &K, hits: u32) -> Option<usize> {
+        if N == 0 {
+            return None;
+        }
+        if self.hot.len() == N {
+            let (coldest, _) = self
+                .hot
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, e)| e.hits)
+                .filter(|(_, e)| e.hits < hits)?;
+            let demoted = self.hot.swap_remove(coldest);
+            // Demoted entries restart their count, so they have to earn promotion again.
+            self.cold.insert(demoted.key, (demoted.value, 0));
+            self.stats.demotions += 1;
+        }
+        let (key, (value, hits)) = self.cold.remove_entry(key).expect("cold entry");
+        self.hot.push(Entry { key, value, hits });
+        self.stats.promotions += 1;
+        Some(self.hot.len() - 1)
+    }
+}
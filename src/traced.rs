@@ -0,0 +1,209 @@
+//! Debug wrapper logging every operation on a collection, built with the `log` feature.
+//!
+//! A [`Traced`] implements the [collection traits](`crate::collection`) of the collection it wraps by delegating to it,
+//! and logs each call with its arguments, its outcome and how long it took under the `strctr::traced` target.
+//! Operations that change the collection log at the debug level, and lookups at the trace level, so a logger filter
+//! picks how much to see. Code written against the traits takes a traced collection unchanged, which lets an incident
+//! be debugged by swapping the wrapper in and enabling the target. Applications using `tracing` receive the records
+//! through its `log` compatibility layer.
+//! ```
+//! # use std::sync::Mutex;
+//! # use strctr::collection::Map;
+//! # use strctr::rbtree::RBTreeMap;
+//! # use strctr::traced::Traced;
+//! struct Recorder(Mutex<Vec<String>>);
+//!
+//! impl log::Log for Recorder {
+//!     fn enabled(&self, metadata: &log::Metadata) -> bool {
+//!         metadata.target() == "strctr::traced"
+//!     }
+//!
+//!     fn log(&self, record: &log::Record) {
+//!         // Drop the timing, which differs from run to run.
+//!         let line = record.args().to_string();
+//!         let line = line.split(" in ").next().unwrap().to_string();
+//!         self.0.lock().unwrap().push(format!("{} {}", record.level(), line));
+//!     }
+//!
+//!     fn flush(&self) {}
+//! }
+//!
+//! static RECORDER: Recorder = Recorder(Mutex::new(Vec::new()));
+//! log::set_logger(&RECORDER).unwrap();
+//! log::set_max_level(log::LevelFilter::Trace);
+//!
+//! let mut sessions = Traced::new("sessions", RBTreeMap::new());
+//! sessions.insert(17, "alice");
+//! sessions.get(&4);
+//! sessions.remove(&17);
+//! assert_eq!(
+//!     *RECORDER.0.lock().unwrap(),
+//!     vec![
+//!         "DEBUG sessions: insert(17) -> added, len 1",
+//!         "TRACE sessions: get(4) -> miss",
+//!         "DEBUG sessions: remove(17) -> removed, len 0",
+//!     ]
+//! );
+//! ```
+
+use std::fmt::Debug;
+use std::ops::Deref;
+use std::time::Instant;
+
+use crate::collection::{Collection, Map, Sequence};
+
+/// The log target of all records.
+const TARGET: &str = "strctr::traced";
+
+/// A collection whose operations through the collection traits are logged.
+///
+/// Dereferencing gives read access to the collection itself, whose calls are not logged.
+pub struct Traced<C> {
+    name: &'static str,
+    inner: C,
+}
+
+impl<C> Traced<C> {
+    /// Wraps the collection, naming it in the log records.
+    pub fn new(name: &'static str, collection: C) -> Self {
+        Self {
+            name,
+            inner: collection,
+        }
+    }
+
+    /// Returns the name of the collection in the log records.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Consumes the wrapper and returns the collection.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+impl<C> Deref for Traced<C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.inner
+    }
+}
+
+/// Formats a call to the operation with the argument, if debug records are logged at all. The argument is moved
+/// into the call, so this has to happen before it runs.
+fn describe_arg(op: &str, arg: &impl Debug) -> String {
+    if log::log_enabled!(target: TARGET, log::Level::Debug) {
+        format!("{}({:?})", op, arg)
+    } else {
+        String::new()
+    }
+}
+
+/// Runs the operation and logs it at the level, with the outcome described from a reference to its result.
+macro_rules! traced {
+    ($self:ident, $level:ident, $call:expr, $op:expr, |$result:ident| $describe:expr) => {{
+        let start = Instant::now();
+        let result = $call;
+        if log::log_enabled!(target: TARGET, log::Level::$level) {
+            let elapsed = start.elapsed();
+            let $result = &result;
+            log::log!(
+                target: TARGET,
+                log::Level::$level,
+                "{}: {} -> {} in {:?}",
+                $self.name,
+                $op,
+                $describe,
+                elapsed
+            );
+        }
+        result
+    }};
+}
+
+impl<C: Collection> Collection for Traced<C> {
+    fn len(&self) -> usize {
+        traced!(self, Trace, self.inner.len(), "len()", |len| len)
+    }
+
+    fn clear(&mut self) {
+        traced!(self, Debug, self.inner.clear(), "clear()", |_r| "cleared")
+    }
+}
+
+impl<C> Sequence for Traced<C>
+where
+    C: Sequence,
+    C::Element: Debug,
+{
+    type Element = C::Element;
+
+    fn get(&self, index: usize) -> Option<&C::Element> {
+        let op = format_args!("get({})", index);
+        traced!(self, Trace, self.inner.get(index), op, |found| {
+            format!("{:?}", found)
+        })
+    }
+
+    fn push(&mut self, element: C::Element) {
+        let op = describe_arg("push", &element);
+        traced!(self, Debug, self.inner.push(element), op, |_r| {
+            format!("len {}", self.inner.len())
+        })
+    }
+
+    fn pop(&mut self) -> Option<C::Element> {
+        traced!(self, Debug, self.inner.pop(), "pop()", |popped| {
+            format!("{:?}, len {}", popped, self.inner.len())
+        })
+    }
+}
+
+impl<C> Map for Traced<C>
+where
+    C: Map,
+    C::Key: Debug,
+{
+    type Key = C::Key;
+    type Value = C::Value;
+
+    fn get(&self, key: &C::Key) -> Option<&C::Value> {
+        let op = format_args!("get({:?})", key);
+        traced!(self, Trace, self.inner.get(key), op, |found| {
+            outcome(found, "hit", "miss")
+        })
+    }
+
+    fn insert(&mut self, key: C::Key, value: C::Value) -> Option<C::Value> {
+        let op = describe_arg("insert", &key);
+        traced!(self, Debug, self.inner.insert(key, value), op, |old| {
+            format!(
+                "{}, len {}",
+                outcome(old, "replaced", "added"),
+                self.inner.len()
+            )
+        })
+    }
+
+    fn remove(&mut self, key: &C::Key) -> Option<C::Value> {
+        let op = format_args!("remove({:?})", key);
+        traced!(self, Debug, self.inner.remove(key), op, |removed| {
+            format!(
+                "{}, len {}",
+                outcome(removed, "removed", "absent"),
+                self.inner.len()
+            )
+        })
+    }
+}
+
+/// Names the outcome of a lookup by whether it found something.
+fn outcome<T>(found: &Option<T>, some: &'static str, none: &'static str) -> &'static str {
+    if found.is_some() {
+        some
+    } else {
+        none
+    }
+}
@@ -0,0 +1,312 @@
+//! Prefix tree keyed by strings. Every edge is labelled with a single `char`, so all keys sharing a prefix live
+//! under the same node, which makes prefix queries and autocomplete-style listings cheap.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+
+use crate::snapshot::{self, Codec, Snapshot, SnapshotError};
+
+struct Node<V> {
+    value: Option<V>,
+    children: BTreeMap<char, Node<V>>,
+}
+
+impl<V> Node<V> {
+    fn new() -> Self {
+        Self {
+            value: None,
+            children: BTreeMap::new(),
+        }
+    }
+}
+
+impl<V: Clone> Clone for Node<V> {
+    /// Copies the subtree iteratively, so cloning the nodes of a long key cannot overflow the stack.
+    fn clone(&self) -> Self {
+        // Each frame holds the character leading to a copied node, the originals of its children still to copy, and
+        // the copy itself. A copy is attached to its parent once all of its children are.
+        let mut stack = vec![(
+            None,
+            self.children.iter(),
+            Node {
+                value: self.value.clone(),
+                children: BTreeMap::new(),
+            },
+        )];
+        loop {
+            let (_, children, _) = stack.last_mut().expect("the root frame is popped last");
+            if let Some((&c, child)) = children.next() {
+                let copy = Node {
+                    value: child.value.clone(),
+                    children: BTreeMap::new(),
+                };
+                stack.push((Some(c), child.children.iter(), copy));
+                continue;
+            }
+            let (c, _, copy) = stack.pop().expect("the root frame is popped last");
+            match (stack.last_mut(), c) {
+                (Some((_, _, parent)), Some(c)) => {
+                    parent.children.insert(c, copy);
+                }
+                _ => return copy,
+            }
+        }
+    }
+}
+
+impl<V> Drop for Node<V> {
+    /// Frees the subtree iteratively, so dropping the nodes of a long key cannot overflow the stack.
+    fn drop(&mut self) {
+        let mut stack: Vec<Node<V>> = std::mem::take(&mut self.children).into_values().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(std::mem::take(&mut node.children).into_values());
+        }
+    }
+}
+
+/// A map from strings to values, stored as a prefix tree.
+#[derive(Clone)]
+pub struct Trie<V> {
+    root: Node<V>,
+    len: usize,
+}
+
+impl<V> Default for Trie<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> Trie<V> {
+    /// Constructs a new, empty trie.
+    pub fn new() -> Self {
+        Self {
+            root: Node::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of keys in the trie.
+    /// ```
+    /// # use strctr::trie::Trie;
+    /// let mut t = Trie::new();
+    /// assert_eq!(t.len(), 0);
+    /// t.insert("tea", 1);
+    /// assert_eq!(t.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the trie contains no keys.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes every key from the trie.
+    pub fn clear(&mut self) {
+        self.root = Node::new();
+        self.len = 0;
+    }
+
+    /// Inserts a key-value pair into the trie. If the key was already present, its value is replaced and the old
+    /// value is returned.
+    /// ```
+    /// # use strctr::trie::Trie;
+    /// let mut t = Trie::new();
+    /// assert_eq!(t.insert("tea", 1), None);
+    /// assert_eq!(t.insert("tea", 2), Some(1));
+    /// ```
+    pub fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        let mut node = &mut self.root;
+        for c in key.chars() {
+            node = node.children.entry(c).or_insert_with(Node::new);
+        }
+        let old = node.value.replace(value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Returns a reference to the value stored under the key.
+    /// ```
+    /// # use strctr::trie::Trie;
+    /// let mut t = Trie::new();
+    /// t.insert("tea", 1);
+    /// assert_eq!(t.get("tea"), Some(&1));
+    /// assert_eq!(t.get("te"), None);
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.find(key)?.value.as_ref()
+    }
+
+    /// Returns a mutable reference to the value stored under the key.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        let mut node = &mut self.root;
+        for c in key.chars() {
+            node = node.children.get_mut(&c)?;
+        }
+        node.value.as_mut()
+    }
+
+    /// Returns whether the key is in the trie.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns whether any key in the trie starts with the prefix. Every trie starts with the empty prefix, even an
+    /// empty one.
+    /// ```
+    /// # use strctr::trie::Trie;
+    /// let mut t = Trie::new();
+    /// t.insert("tea", 1);
+    /// assert!(t.starts_with("te"));
+    /// assert!(!t.starts_with("to"));
+    /// ```
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        self.find(prefix).is_some()
+    }
+
+    /// Removes the key from the trie, returning its value if it was present. Branches left without any keys are
+    /// pruned.
+    /// ```
+    /// # use strctr::trie::Trie;
+    /// let mut t = Trie::new();
+    /// t.insert("tea", 1);
+    /// t.insert("ten", 2);
+    /// assert_eq!(t.remove("tea"), Some(1));
+    /// assert_eq!(t.remove("tea"), None);
+    /// assert!(!t.starts_with("tea"));
+    /// assert!(t.starts_with("te"));
+    ///
+    /// let long = "a".repeat(1_000_000);
+    /// t.insert(&long, 3);
+    /// assert_eq!(t.remove(&long), Some(3));
+    /// assert!(!t.starts_with("a"));
+    /// t.insert(&long, 4);
+    /// let copy = t.clone();
+    /// drop(t);
+    /// assert_eq!(copy.get(&long), Some(&4));
+    /// drop(copy);
+    /// ```
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        // The branch to prune hangs off the deepest node on the path that keeps a value or another child.
+        let mut cut = 0;
+        let mut node = &mut self.root;
+        for (depth, c) in key.chars().enumerate() {
+            if node.value.is_some() || node.children.len() > 1 {
+                cut = depth;
+            }
+            node = node.children.get_mut(&c)?;
+        }
+        let removed = node.value.take()?;
+        self.len -= 1;
+        if node.children.is_empty() {
+            let mut chars = key.chars();
+            let mut node = &mut self.root;
+            for c in chars.by_ref().take(cut) {
+                node = node.children.get_mut(&c).expect("a node on the path");
+            }
+            if let Some(c) = chars.next() {
+                node.children.remove(&c);
+            }
+        }
+        Some(removed)
+    }
+
+    /// Returns an iterator over every entry, in lexicographic key order.
+    /// ```
+    /// # use strctr::trie::Trie;
+    /// let mut t = Trie::new();
+    /// t.insert("b", 2);
+    /// t.insert("a", 1);
+    /// t.insert("ab", 3);
+    /// let keys: Vec<_> = t.iter().map(|(k, _)| k).collect();
+    /// assert_eq!(keys, vec!["a", "ab", "b"]);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, V> {
+        self.iter_prefix("")
+    }
+
+    /// Returns an iterator over every entry whose key starts with the prefix, in lexicographic key order.
+    /// ```
+    /// # use strctr::trie::Trie;
+    /// let mut t = Trie::new();
+    /// for (i, word) in ["tea", "ted", "ten", "to", "inn"].iter().enumerate() {
+    ///     t.insert(word, i);
+    /// }
+    /// let completions: Vec<_> = t.iter_prefix("te").map(|(k, _)| k).collect();
+    /// assert_eq!(completions, vec!["tea", "ted", "ten"]);
+    /// assert_eq!(t.iter_prefix("x").count(), 0);
+    /// ```
+    pub fn iter_prefix(&self, prefix: &str) -> Iter<'_, V> {
+        Iter {
+            stack: self
+                .find(prefix)
+                .map(|node| (prefix.to_string(), node))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    fn find(&self, key: &str) -> Option<&Node<V>> {
+        let mut node = &self.root;
+        for c in key.chars() {
+            node = node.children.get(&c)?;
+        }
+        Some(node)
+    }
+}
+
+impl<'a, V> FromIterator<(&'a str, V)> for Trie<V> {
+    fn from_iter<I: IntoIterator<Item = (&'a str, V)>>(iter: I) -> Self {
+        let mut trie = Self::new();
+        for (k, v) in iter {
+            trie.insert(k, v);
+        }
+        trie
+    }
+}
+
+impl<V: Codec> Snapshot for Trie<V> {
+    const TAG: [u8; 4] = *b"TRIE";
+
+    fn write_payload<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.len().encode(w)?;
+        for (k, v) in self.iter() {
+            k.encode(w)?;
+            v.encode(w)?;
+        }
+        Ok(())
+    }
+
+    fn read_payload<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let mut trie = Self::new();
+        snapshot::read_entries(r, |k: String, v| trie.insert(&k, v))?;
+        Ok(trie)
+    }
+}
+
+/// Iterator over the entries of a [`Trie`], in lexicographic key order.
+pub struct Iter<'a, V> {
+    stack: Vec<(String, &'a Node<V>)>,
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (String, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((key, node)) = self.stack.pop() {
+            for (c, child) in node.children.iter().rev() {
+                let mut child_key = key.clone();
+                child_key.push(*c);
+                self.stack.push((child_key, child));
+            }
+            if let Some(value) = &node.value {
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,344 @@
+//! In-memory multi-version key-value store built on a persistent radix trie.
+//!
+//! Writes go to a working version. [commit()](`VersionedStore::commit()`) freezes it as an immutable snapshot and
+//! returns its [`SnapshotId`]; any snapshot can be read later. Versions share every trie node they have in common:
+//! a write copies only the nodes on the path to its key, which is what an MVCC storage engine does on disk.
+
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// Identifier of a committed snapshot. Later commits get larger ids.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SnapshotId(u64);
+
+/// Keys are split into 4-bit nibbles, so every node has up to 16 children.
+const FANOUT: usize = 16;
+
+struct Node<V> {
+    value: Option<V>,
+    children: [Option<Rc<Node<V>>>; FANOUT],
+}
+
+impl<V> Node<V> {
+    fn new() -> Self {
+        Self {
+            value: None,
+            children: Default::default(),
+        }
+    }
+}
+
+impl<V: Clone> Clone for Node<V> {
+    /// Copies the node alone: the children are shared with the original, so cloning never walks the subtree.
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            children: self.children.clone(),
+        }
+    }
+}
+
+impl<V> Drop for Node<V> {
+    /// Frees the nodes no other version shares iteratively, so dropping the nodes of a long key cannot overflow the
+    /// stack.
+    fn drop(&mut self) {
+        let mut stack: Vec<Rc<Node<V>>> =
+            self.children.iter_mut().filter_map(Option::take).collect();
+        while let Some(child) = stack.pop() {
+            if let Ok(mut node) = Rc::try_unwrap(child) {
+                stack.extend(node.children.iter_mut().filter_map(Option::take));
+            }
+        }
+    }
+}
+
+struct Version<V> {
+    root: Option<Rc<Node<V>>>,
+    len: usize,
+}
+
+impl<V> Clone for Version<V> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+/// A key-value store with a mutable working version and any number of immutable snapshots.
+pub struct VersionedStore<V> {
+    working: Version<V>,
+    snapshots: BTreeMap<SnapshotId, Version<V>>,
+    next_id: u64,
+}
+
+impl<V: Clone> Default for VersionedStore<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: Clone> VersionedStore<V> {
+    /// Constructs a new store with an empty working version and no snapshots.
+    pub fn new() -> Self {
+        Self {
+            working: Version { root: None, len: 0 },
+            snapshots: BTreeMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Returns the number of keys in the working version.
+    pub fn len(&self) -> usize {
+        self.working.len
+    }
+
+    /// Returns whether the working version has no keys.
+    pub fn is_empty(&self) -> bool {
+        self.working.len == 0
+    }
+
+    /// Sets the key in the working version, returning the previous value.
+    /// ```
+    /// # use strctr::versioned::VersionedStore;
+    /// let mut s = VersionedStore::new();
+    /// assert_eq!(s.insert("a", 1), None);
+    /// assert_eq!(s.insert("a", 2), Some(1));
+    /// ```
+    pub fn insert(&mut self, key: impl AsRef<[u8]>, value: V) -> Option<V> {
+        let nibbles = nibbles(key.as_ref());
+        let old = insert(&mut self.working.root, &nibbles, value);
+        if old.is_none() {
+            self.working.len += 1;
+        }
+        old
+    }
+
+    /// Removes the key from the working version, returning its value. Snapshots are not affected.
+    /// ```
+    /// # use strctr::versioned::VersionedStore;
+    /// let mut s = VersionedStore::new();
+    /// let long = vec![b'a'; 500_000];
+    /// s.insert(&long, 1);
+    /// let v = s.commit();
+    /// assert_eq!(s.remove(&long), Some(1));
+    /// assert_eq!(s.remove(&long), None);
+    /// assert_eq!(s.get_at(v, &long), Some(&1));
+    /// s.insert(&long[..1000], 2);
+    /// drop(s);
+    /// ```
+    pub fn remove(&mut self, key: impl AsRef<[u8]>) -> Option<V> {
+        let nibbles = nibbles(key.as_ref());
+        find(&self.working.root, &nibbles)?;
+        let old = remove(&mut self.working.root, &nibbles);
+        if old.is_some() {
+            self.working.len -= 1;
+        }
+        old
+    }
+
+    /// Returns the value of the key in the working version.
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&V> {
+        find(&self.working.root, &nibbles(key.as_ref()))
+    }
+
+    /// Freezes the working version as a snapshot and returns its id. The working version stays as it is, and keeps
+    /// sharing all of its nodes with the snapshot until it is written to.
+    /// ```
+    /// # use strctr::versioned::VersionedStore;
+    /// let mut s = VersionedStore::new();
+    /// s.insert("k", "v1");
+    /// let v1 = s.commit();
+    /// s.insert("k", "v2");
+    /// let v2 = s.commit();
+    /// s.remove("k");
+    /// assert_eq!(s.get_at(v1, "k"), Some(&"v1"));
+    /// assert_eq!(s.get_at(v2, "k"), Some(&"v2"));
+    /// assert_eq!(s.get("k"), None);
+    /// ```
+    pub fn commit(&mut self) -> SnapshotId {
+        let id = SnapshotId(self.next_id);
+        self.next_id += 1;
+        self.snapshots.insert(id, self.working.clone());
+        id
+    }
+
+    /// Returns the value of the key in the snapshot. Returns `None` if the key is not in the snapshot or the
+    /// snapshot does not exist.
+    pub fn get_at(&self, id: SnapshotId, key: impl AsRef<[u8]>) -> Option<&V> {
+        find(&self.snapshots.get(&id)?.root, &nibbles(key.as_ref()))
+    }
+
+    /// Returns a read-only view of the snapshot.
+    pub fn snapshot(&self, id: SnapshotId) -> Option<Snapshot<'_, V>> {
+        self.snapshots.get(&id).map(|version| Snapshot { version })
+    }
+
+    /// Returns the ids of every snapshot still kept, oldest first.
+    pub fn snapshot_ids(&self) -> impl Iterator<Item = SnapshotId> + '_ {
+        self.snapshots.keys().copied()
+    }
+
+    /// Drops the snapshot, freeing the nodes no other version shares. Returns whether the snapshot existed.
+    pub fn discard(&mut self, id: SnapshotId) -> bool {
+        self.snapshots.remove(&id).is_some()
+    }
+
+    /// Replaces the working version with the contents of the snapshot, throwing away uncommitted writes. Returns
+    /// whether the snapshot existed.
+    /// ```
+    /// # use strctr::versioned::VersionedStore;
+    /// let mut s = VersionedStore::new();
+    /// s.insert("a", 1);
+    /// let v = s.commit();
+    /// s.insert("b", 2);
+    /// assert!(s.rollback(v));
+    /// assert_eq!(s.get("b"), None);
+    /// assert_eq!(s.len(), 1);
+    /// ```
+    pub fn rollback(&mut self, id: SnapshotId) -> bool {
+        match self.snapshots.get(&id) {
+            Some(version) => {
+                self.working = version.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns an iterator over the entries of the working version, in lexicographic key order.
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter::new(&self.working.root)
+    }
+}
+
+/// Read-only view of a committed snapshot, created by [snapshot()](`VersionedStore::snapshot()`).
+pub struct Snapshot<'a, V> {
+    version: &'a Version<V>,
+}
+
+impl<'a, V> Snapshot<'a, V> {
+    /// Returns the number of keys in the snapshot.
+    pub fn len(&self) -> usize {
+        self.version.len
+    }
+
+    /// Returns whether the snapshot has no keys.
+    pub fn is_empty(&self) -> bool {
+        self.version.len == 0
+    }
+
+    /// Returns the value of the key in the snapshot.
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&'a V> {
+        find(&self.version.root, &nibbles(key.as_ref()))
+    }
+
+    /// Returns an iterator over the entries of the snapshot, in lexicographic key order.
+    /// ```
+    /// # use strctr::versioned::VersionedStore;
+    /// let mut s = VersionedStore::new();
+    /// s.insert("b", 2);
+    /// s.insert("a", 1);
+    /// let v = s.commit();
+    /// s.insert("c", 3);
+    /// let entries: Vec<_> = s.snapshot(v).unwrap().iter().collect();
+    /// assert_eq!(entries, vec![(b"a".to_vec(), &1), (b"b".to_vec(), &2)]);
+    /// ```
+    pub fn iter(&self) -> Iter<'a, V> {
+        Iter::new(&self.version.root)
+    }
+}
+
+fn nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect()
+}
+
+fn find<'a, V>(mut link: &'a Option<Rc<Node<V>>>, nibbles: &[u8]) -> Option<&'a V> {
+    for &n in nibbles {
+        link = &link.as_ref()?.children[n as usize];
+    }
+    link.as_ref()?.value.as_ref()
+}
+
+/// Writes the value below `link`, copying every node on the path that is shared with another version.
+fn insert<V: Clone>(mut link: &mut Option<Rc<Node<V>>>, nibbles: &[u8], value: V) -> Option<V> {
+    for &n in nibbles {
+        let node = Rc::make_mut(link.get_or_insert_with(|| Rc::new(Node::new())));
+        link = &mut node.children[n as usize];
+    }
+    let node = Rc::make_mut(link.get_or_insert_with(|| Rc::new(Node::new())));
+    node.value.replace(value)
+}
+
+/// Removes the value below `root`, copying shared nodes on the path and pruning nodes left empty.
+fn remove<V: Clone>(root: &mut Option<Rc<Node<V>>>, nibbles: &[u8]) -> Option<V> {
+    let mut link = &mut *root;
+    for &n in nibbles {
+        link = &mut Rc::make_mut(link.as_mut()?).children[n as usize];
+    }
+    let old = Rc::make_mut(link.as_mut()?).value.take();
+
+    // The shallowest depth from which every node on the path holds nothing but the next node of the path.
+    let mut prune = None;
+    let mut node = root.as_deref();
+    for depth in 0..=nibbles.len() {
+        let Some(current) = node else { break };
+        let next = nibbles.get(depth).map(|&n| n as usize);
+        let holds_more = current.value.is_some()
+            || current
+                .children
+                .iter()
+                .enumerate()
+                .any(|(i, child)| child.is_some() && Some(i) != next);
+        if holds_more {
+            prune = None;
+        } else if prune.is_none() {
+            prune = Some(depth);
+        }
+        node = next.and_then(|n| current.children[n].as_deref());
+    }
+    if let Some(depth) = prune {
+        let mut link = root;
+        for &n in &nibbles[..depth] {
+            link = &mut Rc::make_mut(link.as_mut().expect("node on the path")).children[n as usize];
+        }
+        *link = None;
+    }
+    old
+}
+
+/// Iterator over the entries of one version of a [`VersionedStore`], in lexicographic key order.
+pub struct Iter<'a, V> {
+    /// Pending nodes along with the nibbles leading to them.
+    stack: Vec<(Vec<u8>, &'a Node<V>)>,
+}
+
+impl<'a, V> Iter<'a, V> {
+    fn new(root: &'a Option<Rc<Node<V>>>) -> Self {
+        Self {
+            stack: root.iter().map(|r| (Vec::new(), r.as_ref())).collect(),
+        }
+    }
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (Vec<u8>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((path, node)) = self.stack.pop() {
+            for (n, child) in node.children.iter().enumerate().rev() {
+                if let Some(child) = child {
+                    let mut child_path = path.clone();
+                    child_path.push(n as u8);
+                    self.stack.push((child_path, child));
+                }
+            }
+            if let Some(value) = &node.value {
+                let key = path.chunks(2).map(|c| (c[0] << 4) | c[1]).collect();
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,218 @@
+//! Traces of the structural changes that operations make, for animating how structures evolve.
+//!
+//! [`trace()`] runs a closure and records the [`Event`]s that the structures it touches report on the current thread:
+//! rotations and subtree rebuilds of [`BalancedTree`](`crate::bst::BalancedTree`) and
+//! [`RBTreeMap`](`crate::rbtree::RBTreeMap`), and node splits and merges of [`BTreeMap`](`crate::btree::BTreeMap`).
+//! [mark()](`mark()`) adds events of its own, like the operation about to run, so a viewer can group the changes
+//! per step. [to_json()](`Trace::to_json()`) and [write_json_lines()](`Trace::write_json_lines()`) turn the events
+//! into JSON for an external viewer. Outside of a trace, structures skip reporting after a thread-local check.
+//! ```
+//! # use strctr::bst::{Avl, BalancedTree};
+//! # use strctr::viz::{self, Direction, Event};
+//! let mut tree = BalancedTree::new(Avl);
+//! let ((), trace) = viz::trace(|| {
+//!     for key in 1..=3 {
+//!         viz::mark(format!("insert {}", key));
+//!         tree.insert(key, ());
+//!     }
+//! });
+//! let rotation = Event::Rotate {
+//!     structure: "BalancedTree",
+//!     node: 0,
+//!     child: 1,
+//!     direction: Direction::Left,
+//! };
+//! assert_eq!(trace.events().last(), Some(&rotation));
+//! assert_eq!(
+//!     trace.events()[3].to_json(),
+//!     r#"{"child":1,"direction":"left","event":"rotate","node":0,"structure":"BalancedTree"}"#
+//! );
+//! ```
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+
+use crate::document::{Object, Value};
+
+std::thread_local! {
+    /// The events of the innermost running trace, or `None` outside of traces.
+    static EVENTS: RefCell<Option<Vec<Event>>> = const { RefCell::new(None) };
+}
+
+/// The direction of a rotation: in a left rotation, the node's right child moves up into its place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// The right child moved up.
+    Left,
+    /// The left child moved up.
+    Right,
+}
+
+/// A structural change, or a marker placed by [mark()](`mark()`).
+///
+/// Binary tree nodes are identified by their slot in the tree's node storage, which stays the same while the node is
+/// in the tree. B-tree nodes have no identity of their own, so their events name the level of the node instead: leaves
+/// are at level 0, and their parents at level 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A marker with the label.
+    Mark(String),
+    /// The child moved up into the node's place, and the node became its child.
+    Rotate {
+        structure: &'static str,
+        node: usize,
+        child: usize,
+        direction: Direction,
+    },
+    /// The subtree of `nodes` nodes was relinked into a perfectly balanced one, now rooted at `root`.
+    Rebuild {
+        structure: &'static str,
+        root: usize,
+        nodes: usize,
+    },
+    /// A full node at the level was split around its median key, which moved up into the parent. Splitting the root
+    /// adds a level to the tree.
+    Split {
+        structure: &'static str,
+        level: usize,
+    },
+    /// Two sibling nodes at the level were merged, together with the parent's key between them.
+    Merge {
+        structure: &'static str,
+        level: usize,
+    },
+}
+
+impl Event {
+    /// Returns the event as a JSON-like object. The kind of event is in the `"event"` member, which is `"mark"`,
+    /// `"rotate"`, `"rebuild"`, `"split"` or `"merge"`; the fields follow under their names.
+    pub fn to_value(&self) -> Value {
+        let mut object = Object::new();
+        let mut set = |name: &str, value: Value| {
+            object.insert(name.to_string(), value);
+        };
+        let number = |n: usize| Value::Number(n as f64);
+        match self {
+            Event::Mark(label) => {
+                set("event", "mark".into());
+                set("label", label.as_str().into());
+            }
+            Event::Rotate {
+                structure,
+                node,
+                child,
+                direction,
+            } => {
+                set("event", "rotate".into());
+                set("structure", (*structure).into());
+                set("node", number(*node));
+                set("child", number(*child));
+                let direction = match direction {
+                    Direction::Left => "left",
+                    Direction::Right => "right",
+                };
+                set("direction", direction.into());
+            }
+            Event::Rebuild {
+                structure,
+                root,
+                nodes,
+            } => {
+                set("event", "rebuild".into());
+                set("structure", (*structure).into());
+                set("root", number(*root));
+                set("nodes", number(*nodes));
+            }
+            Event::Split { structure, level } => {
+                set("event", "split".into());
+                set("structure", (*structure).into());
+                set("level", number(*level));
+            }
+            Event::Merge { structure, level } => {
+                set("event", "merge".into());
+                set("structure", (*structure).into());
+                set("level", number(*level));
+            }
+        }
+        Value::Object(object)
+    }
+
+    /// Returns the event as a JSON object, as described at [to_value()](`Self::to_value()`).
+    pub fn to_json(&self) -> String {
+        self.to_value().to_string()
+    }
+}
+
+/// The events recorded by [trace()](`trace()`), in the order they happened.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Trace {
+    events: Vec<Event>,
+}
+
+impl Trace {
+    /// Returns the events.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Consumes the trace and returns its events.
+    pub fn into_events(self) -> Vec<Event> {
+        self.events
+    }
+
+    /// Returns the events as a JSON array.
+    pub fn to_json(&self) -> String {
+        self.events
+            .iter()
+            .map(Event::to_value)
+            .collect::<Value>()
+            .to_string()
+    }
+
+    /// Writes the events as JSON Lines: one JSON object per line, for viewers that read the stream as it arrives.
+    pub fn write_json_lines(&self, w: &mut impl Write) -> io::Result<()> {
+        for event in &self.events {
+            writeln!(w, "{}", event.to_value())?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the closure and returns its result, together with the events reported on the current thread meanwhile.
+///
+/// A trace started inside the closure takes the events of its own closure, which this trace does not see.
+pub fn trace<R>(f: impl FnOnce() -> R) -> (R, Trace) {
+    let scope = Scope {
+        outer: EVENTS.with(|events| events.replace(Some(Vec::new()))),
+    };
+    let result = f();
+    let events = EVENTS.with(|events| events.borrow_mut().take().unwrap_or_default());
+    drop(scope);
+    (result, Trace { events })
+}
+
+/// Adds a marker with the label to the running trace. Does nothing outside of traces.
+pub fn mark(label: impl Into<String>) {
+    record(|| Event::Mark(label.into()));
+}
+
+/// Adds the event to the running trace, if there is one. The event is only built when it is recorded.
+pub(crate) fn record(event: impl FnOnce() -> Event) {
+    // Structures dropped while the thread shuts down find the thread-local gone; they have nothing to report anyway.
+    let _ = EVENTS.try_with(|events| {
+        if let Some(events) = events.borrow_mut().as_mut() {
+            events.push(event());
+        }
+    });
+}
+
+/// Puts the enclosing trace's events back when a trace ends, even if its closure panicked.
+struct Scope {
+    outer: Option<Vec<Event>>,
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let _ = EVENTS.try_with(|events| *events.borrow_mut() = self.outer.take());
+    }
+}
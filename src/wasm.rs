@@ -0,0 +1,293 @@
+//! JavaScript bindings for a few of the crate's structures, built with the `wasm` feature.
+//!
+//! The classes here wrap [`Trie`], [`LruCache`] and [`Graph`] behind [wasm-bindgen](https://docs.rs/wasm-bindgen)
+//! exports, so a web page can use them directly. Keys are strings and cached values or node labels are arbitrary
+//! JavaScript values; method names follow JavaScript conventions. The classes are exported from any `cdylib` crate that
+//! depends on this one with the `wasm` feature and re-exports the module (`pub use strctr::wasm::*;`), built with
+//! `wasm-pack`:
+//! ```js
+//! import { Autocomplete, LruCache, Graph } from "./pkg/demo.js";
+//!
+//! const words = new Autocomplete();
+//! ["tea", "ted", "tea", "ten"].forEach((w) => words.insert(w));
+//! words.complete("te", 2); // ["tea", "ted"]
+//!
+//! const cache = new LruCache(100);
+//! cache.set("user:1", { name: "Ada" });
+//! cache.get("user:1").name; // "Ada"
+//!
+//! const map = new Graph(false);
+//! const [a, b, c] = ["A", "B", "C"].map((city) => map.addNode(city));
+//! map.addEdge(a, b, 4);
+//! map.addEdge(b, c, 3);
+//! map.addEdge(a, c, 9);
+//! map.shortestPath(a, c); // Uint32Array [0, 1, 2]
+//! map.distance(a, c); // 7
+//! ```
+//! The feature builds on every target, but only WebAssembly has JavaScript values to pass in.
+
+use std::cmp::{Ordering, Reverse};
+use std::ops::Add;
+
+use wasm_bindgen::prelude::*;
+
+use crate::graph::{Graph, NodeId};
+use crate::lru::LruCache;
+use crate::trie::Trie;
+
+/// Word completion over a [`Trie`] that counts how often each word was inserted.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Autocomplete {
+    words: Trie<u32>,
+}
+
+#[wasm_bindgen]
+impl Autocomplete {
+    /// Constructs a new, empty word list.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of distinct words.
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Adds the word, or counts it once more if it is present.
+    pub fn insert(&mut self, word: &str) {
+        match self.words.get_mut(word) {
+            Some(count) => *count += 1,
+            None => {
+                self.words.insert(word, 1);
+            }
+        }
+    }
+
+    /// Removes the word. Returns whether it was present.
+    pub fn remove(&mut self, word: &str) -> bool {
+        self.words.remove(word).is_some()
+    }
+
+    /// Returns whether the word is present.
+    pub fn has(&self, word: &str) -> bool {
+        self.words.contains_key(word)
+    }
+
+    /// Returns up to `limit` words starting with the prefix, most often inserted first and alphabetically among equally
+    /// frequent ones.
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<String> {
+        let mut matches: Vec<(String, u32)> = self
+            .words
+            .iter_prefix(prefix)
+            .map(|(word, &count)| (word, count))
+            .collect();
+        // iter_prefix() yields alphabetically, and the stable sort keeps that order among equal counts.
+        matches.sort_by_key(|&(_, count)| Reverse(count));
+        matches.truncate(limit);
+        matches.into_iter().map(|(word, _)| word).collect()
+    }
+}
+
+/// A least-recently-used cache from strings to JavaScript values, wrapping [`LruCache`].
+#[wasm_bindgen(js_name = LruCache)]
+pub struct JsLruCache {
+    cache: LruCache<String, JsValue>,
+}
+
+#[wasm_bindgen(js_class = LruCache)]
+impl JsLruCache {
+    /// Constructs a new, empty cache holding at most `capacity` entries. Throws if the capacity is 0.
+    #[wasm_bindgen(constructor)]
+    pub fn new(capacity: usize) -> Result<JsLruCache, JsError> {
+        if capacity == 0 {
+            return Err(JsError::new("InvalidCapacity: Capacity must be positive"));
+        }
+        Ok(Self {
+            cache: LruCache::new(capacity),
+        })
+    }
+
+    /// Returns the number of entries.
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Returns the maximum number of entries.
+    #[wasm_bindgen(getter)]
+    pub fn capacity(&self) -> usize {
+        self.cache.capacity()
+    }
+
+    /// Returns the value of the key and marks it as recently used, or `undefined` if it is absent.
+    pub fn get(&mut self, key: &str) -> JsValue {
+        self.cache
+            .get(&key.to_string())
+            .cloned()
+            .unwrap_or(JsValue::UNDEFINED)
+    }
+
+    /// Returns the value of the key without marking it as recently used, or `undefined` if it is absent.
+    pub fn peek(&self, key: &str) -> JsValue {
+        self.cache
+            .peek(&key.to_string())
+            .cloned()
+            .unwrap_or(JsValue::UNDEFINED)
+    }
+
+    /// Sets the value of the key, evicting the least recently used entry if the cache is full. Returns the previous
+    /// value, or `undefined` if the key was absent.
+    pub fn set(&mut self, key: String, value: JsValue) -> JsValue {
+        self.cache.insert(key, value).unwrap_or(JsValue::UNDEFINED)
+    }
+
+    /// Returns whether the key is present.
+    pub fn has(&self, key: &str) -> bool {
+        self.cache.contains_key(&key.to_string())
+    }
+
+    /// Removes the key. Returns whether it was present.
+    pub fn delete(&mut self, key: &str) -> bool {
+        self.cache.remove(&key.to_string()).is_some()
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Returns the keys, from most to least recently used.
+    pub fn keys(&self) -> Vec<String> {
+        self.cache.iter().map(|(k, _)| k.clone()).collect()
+    }
+}
+
+/// Edge length for [dijkstra()](`Graph::dijkstra()`), which needs a total order. Lengths are finite and not negative.
+#[derive(Clone, Copy, Default, PartialEq)]
+struct Length(f64);
+
+impl Eq for Length {}
+
+impl PartialOrd for Length {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Length {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Add for Length {
+    type Output = Length;
+
+    fn add(self, other: Length) -> Length {
+        Length(self.0 + other.0)
+    }
+}
+
+/// A graph with JavaScript node labels and numeric edge lengths, wrapping [`Graph`]. Nodes are numbered in the order
+/// they are added, starting at 0.
+#[wasm_bindgen(js_name = Graph)]
+pub struct JsGraph {
+    graph: Graph<JsValue, f64>,
+    nodes: Vec<NodeId>,
+}
+
+#[wasm_bindgen(js_class = Graph)]
+impl JsGraph {
+    /// Constructs a new, empty graph, directed or undirected.
+    #[wasm_bindgen(constructor)]
+    pub fn new(directed: bool) -> JsGraph {
+        Self {
+            graph: if directed {
+                Graph::new_directed()
+            } else {
+                Graph::new_undirected()
+            },
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Returns the number of nodes.
+    #[wasm_bindgen(getter, js_name = nodeCount)]
+    pub fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    /// Returns the number of edges.
+    #[wasm_bindgen(getter, js_name = edgeCount)]
+    pub fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    /// Adds a node with the label and returns its number.
+    #[wasm_bindgen(js_name = addNode)]
+    pub fn add_node(&mut self, label: JsValue) -> u32 {
+        self.nodes.push(self.graph.add_node(label));
+        (self.nodes.len() - 1) as u32
+    }
+
+    /// Returns the label of the node, or `undefined` if there is no such node.
+    pub fn label(&self, node: u32) -> JsValue {
+        self.node(node)
+            .and_then(|id| self.graph.node_weight(id))
+            .cloned()
+            .unwrap_or(JsValue::UNDEFINED)
+    }
+
+    /// Adds an edge of the given length. Throws if a node does not exist or the length is negative or not finite.
+    #[wasm_bindgen(js_name = addEdge)]
+    pub fn add_edge(&mut self, from: u32, to: u32, length: f64) -> Result<(), JsError> {
+        if !(length.is_finite() && length >= 0.0) {
+            return Err(JsError::new(&format!(
+                "InvalidLength: Edge length must be finite and not negative, got {}",
+                length
+            )));
+        }
+        let (Some(source), Some(target)) = (self.node(from), self.node(to)) else {
+            return Err(JsError::new("InvalidNode: Node does not exist"));
+        };
+        self.graph.add_edge(source, target, length);
+        Ok(())
+    }
+
+    /// Returns the numbers of the nodes adjacent to the node, following edge direction.
+    pub fn neighbors(&self, node: u32) -> Vec<u32> {
+        let Some(id) = self.node(node) else {
+            return Vec::new();
+        };
+        self.graph.neighbors(id).map(|n| self.number(n)).collect()
+    }
+
+    /// Returns the nodes of a shortest path from `from` to `to`, both included, or `undefined` if `to` is unreachable.
+    #[wasm_bindgen(js_name = shortestPath)]
+    pub fn shortest_path(&self, from: u32, to: u32) -> Option<Vec<u32>> {
+        let (from, to) = (self.node(from)?, self.node(to)?);
+        let path = self.graph.dijkstra(from, |&l| Length(l)).path_to(to)?;
+        Some(path.into_iter().map(|n| self.number(n)).collect())
+    }
+
+    /// Returns the length of a shortest path from `from` to `to`, or `undefined` if `to` is unreachable.
+    pub fn distance(&self, from: u32, to: u32) -> Option<f64> {
+        let (from, to) = (self.node(from)?, self.node(to)?);
+        let paths = self.graph.dijkstra(from, |&l| Length(l));
+        paths.distance(to).map(|l| l.0)
+    }
+}
+
+impl JsGraph {
+    fn node(&self, number: u32) -> Option<NodeId> {
+        self.nodes.get(number as usize).copied()
+    }
+
+    /// Nodes are never removed, so node `k` is in slot `k` of the graph.
+    fn number(&self, id: NodeId) -> u32 {
+        id.index() as u32
+    }
+}
@@ -0,0 +1,206 @@
+//! Model checks of the concurrent structures. Run with `RUSTFLAGS="--cfg loom" cargo test --release --test loom`.
+#![cfg(loom)]
+
+use strctr::buffer_pool::BufferPool;
+use strctr::lru::ConcurrentLruCache;
+use strctr::pool::Pool;
+use strctr::rcu::RcuCell;
+use strctr::seqlock::SeqLock;
+use strctr::sharded_counter::{ShardedCounter, ShardedHistogram};
+use strctr::spsc::RingBuffer;
+use strctr::sync::{model, thread, Arc, BoundedQueue, Stack};
+
+#[test]
+fn sharded_counter_counts_every_add() {
+    model(|| {
+        let counter = Arc::new(ShardedCounter::with_shards(1));
+        let other = counter.clone();
+        let handle = thread::spawn(move || {
+            other.add(2);
+            other.reset()
+        });
+        counter.increment();
+        let reset = handle.join().unwrap();
+        assert_eq!(reset + counter.sum(), 3);
+    });
+}
+
+#[test]
+fn sharded_histogram_counts_every_sample() {
+    model(|| {
+        let histogram = Arc::new(ShardedHistogram::with_shards(vec![10], 1));
+        let other = histogram.clone();
+        let handle = thread::spawn(move || other.record(20));
+        histogram.record(5);
+        handle.join().unwrap();
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.counts(), &[1, 1]);
+        assert_eq!(snapshot.sum(), 25);
+    });
+}
+
+#[test]
+fn rcu_readers_never_go_back() {
+    model(|| {
+        let cell = Arc::new(RcuCell::new(0));
+        let writer = cell.clone();
+        let handle = thread::spawn(move || {
+            writer.publish(1);
+            writer.update(|n| n + 1);
+        });
+        let mut reader = cell.reader();
+        let first = *reader.get();
+        let second = *reader.get();
+        assert!(first <= second);
+        handle.join().unwrap();
+        assert_eq!(*reader.get(), 2);
+    });
+}
+
+#[test]
+fn rcu_updates_are_not_lost() {
+    model(|| {
+        let cell = Arc::new(RcuCell::new(0));
+        let other = cell.clone();
+        let handle = thread::spawn(move || {
+            other.update(|n| n + 1);
+        });
+        cell.update(|n| n + 1);
+        handle.join().unwrap();
+        assert_eq!(*cell.load(), 2);
+        assert_eq!(cell.generation(), 2);
+    });
+}
+
+#[test]
+fn concurrent_lru_keeps_capacity() {
+    model(|| {
+        let cache = Arc::new(ConcurrentLruCache::with_shards(1, 1));
+        let other = cache.clone();
+        let handle = thread::spawn(move || {
+            other.insert(1, "one");
+        });
+        cache.insert(2, "two");
+        handle.join().unwrap();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(&1).is_some() || cache.get(&2).is_some());
+    });
+}
+
+#[test]
+fn seqlock_reads_are_never_torn() {
+    model(|| {
+        let lock = Arc::new(SeqLock::new([0u64, 0]));
+        let writer = lock.clone();
+        let handle = thread::spawn(move || {
+            writer.write([1, 1]);
+            writer.update(|[a, b]| [a + 1, b + 1]);
+        });
+        let [a, b] = lock.read();
+        assert_eq!(a, b);
+        handle.join().unwrap();
+        assert_eq!(lock.read(), [2, 2]);
+        assert_eq!(lock.version(), 2);
+    });
+}
+
+#[test]
+fn buffer_pool_hands_out_each_buffer_once() {
+    model(|| {
+        let pool = Arc::new(BufferPool::with_limits(16, 16, 1, 1));
+        pool.recycle(Vec::with_capacity(16));
+        let other = pool.clone();
+        let handle = thread::spawn(move || other.take(16));
+        let mine = pool.take(16);
+        let theirs = handle.join().unwrap();
+        assert_ne!(mine.as_ptr(), theirs.as_ptr());
+        let stats = pool.stats();
+        assert_eq!(stats.local_hits + stats.shared_hits + stats.allocations, 2);
+    });
+}
+
+#[test]
+fn pool_blocks_until_object_returned() {
+    model(|| {
+        let pool = Arc::new(Pool::new(1, || 0));
+        let other = pool.clone();
+        let handle = thread::spawn(move || *other.get() += 1);
+        *pool.get() += 1;
+        handle.join().unwrap();
+        assert_eq!(*pool.get(), 2);
+    });
+}
+
+#[test]
+fn spsc_ring_buffer_delivers_in_order() {
+    model(|| {
+        let ring: &'static mut RingBuffer<u32, 2> = Box::leak(Box::new(RingBuffer::new()));
+        let (mut producer, mut consumer) = ring.split();
+        let handle = thread::spawn(move || {
+            for i in 0..3 {
+                while producer.try_push(i).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+        let mut expected = 0;
+        while expected < 3 {
+            match consumer.pop() {
+                Some(value) => {
+                    assert_eq!(value, expected);
+                    expected += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+        handle.join().unwrap();
+    });
+}
+
+#[test]
+fn bounded_queue_blocks_until_room() {
+    model(|| {
+        let queue = Arc::new(BoundedQueue::new(1));
+        let producer = queue.clone();
+        let handle = thread::spawn(move || {
+            producer.push(1).unwrap();
+            producer.push(2).unwrap();
+            producer.close();
+        });
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.pop(), None);
+        handle.join().unwrap();
+    });
+}
+
+#[test]
+fn stack_pops_every_element_once() {
+    model(|| {
+        let stack = Arc::new(Stack::new());
+        stack.push(1);
+        stack.push(2);
+        let other = stack.clone();
+        let handle = thread::spawn(move || other.pop());
+        let mine = stack.pop();
+        let theirs = handle.join().unwrap();
+        let mut popped = vec![mine.unwrap(), theirs.unwrap()];
+        popped.sort();
+        assert_eq!(popped, vec![1, 2]);
+        assert!(stack.is_empty());
+    });
+}
+
+#[test]
+fn stack_push_races_pop() {
+    model(|| {
+        let stack = Arc::new(Stack::new());
+        stack.push(1);
+        let other = stack.clone();
+        let handle = thread::spawn(move || other.push(2));
+        let first = stack.pop();
+        handle.join().unwrap();
+        let second = stack.pop();
+        assert_eq!(first.unwrap() + second.unwrap(), 3);
+    });
+}